@@ -304,6 +304,7 @@ pub(crate) fn create_doc_export_prefs_from_args(
         with_pattern: !no_pattern,
         optimize_printing,
         page_order,
+        ..Default::default()
     };
 
     Ok(prefs)
@@ -337,6 +338,7 @@ pub(crate) fn create_doc_pages_export_prefs_from_args(
         page_order,
         bitmap_scalefactor,
         jpeg_quality,
+        ..Default::default()
     })
 }
 
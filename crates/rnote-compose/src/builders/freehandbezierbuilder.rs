@@ -0,0 +1,252 @@
+// Imports
+use super::buildable::{Buildable, BuilderCreator, BuilderProgress};
+use crate::eventresult::EventPropagation;
+use crate::penevent::{PenEvent, PenState};
+use crate::penpath::{CurveFit, Element, Segment};
+use crate::shapes::{BezierPath, CubicBezier, Polyline};
+use crate::style::{indicators, Composer};
+use crate::{Constraints, EventResult};
+use crate::{Shape, Style};
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use piet::RenderContext;
+use std::time::Instant;
+
+/// Freehand bezier builder.
+///
+/// The path is drawn freehand and fitted to a single path of cubic bezier curves when it is finished.
+#[derive(Debug, Clone)]
+pub struct FreehandBezierBuilder {
+    /// The drawn elements.
+    elements: Vec<Element>,
+    /// Pen state.
+    pen_state: PenState,
+    /// The maximum distance of the drawn elements to the fitted curves.
+    fit_tolerance: f64,
+}
+
+impl BuilderCreator for FreehandBezierBuilder {
+    fn start(element: Element, _now: Instant) -> Self {
+        Self {
+            elements: vec![element],
+            pen_state: PenState::Down,
+            fit_tolerance: Self::FIT_TOLERANCE_DEFAULT,
+        }
+    }
+}
+
+impl Buildable for FreehandBezierBuilder {
+    type Emit = Shape;
+
+    fn handle_event(
+        &mut self,
+        event: PenEvent,
+        _now: Instant,
+        _constraints: Constraints,
+    ) -> EventResult<BuilderProgress<Self::Emit>> {
+        let progress = match event {
+            PenEvent::Down { element, .. } => {
+                self.pen_state = PenState::Down;
+                self.elements.push(element);
+                BuilderProgress::InProgress
+            }
+            PenEvent::Up { element, .. } => {
+                self.pen_state = PenState::Up;
+                self.elements.push(element);
+                let curves = self.fitted_curves();
+                if curves.is_empty() {
+                    BuilderProgress::Finished(vec![])
+                } else {
+                    BuilderProgress::Finished(vec![Shape::BezierPath(BezierPath::new(curves))])
+                }
+            }
+            PenEvent::Proximity { .. } | PenEvent::KeyPressed { .. } | PenEvent::Text { .. } => {
+                BuilderProgress::InProgress
+            }
+            PenEvent::Cancel => {
+                self.pen_state = PenState::Up;
+                BuilderProgress::Finished(vec![])
+            }
+        };
+
+        EventResult {
+            handled: true,
+            propagate: EventPropagation::Stop,
+            progress,
+        }
+    }
+
+    fn bounds(&self, style: &Style, zoom: f64) -> Option<Aabb> {
+        Some(
+            self.state_as_polyline()
+                .composed_bounds(style)
+                .loosened(indicators::POS_INDICATOR_RADIUS / zoom),
+        )
+    }
+
+    fn draw_styled(&self, cx: &mut piet_cairo::CairoRenderContext, style: &Style, zoom: f64) {
+        cx.save().unwrap();
+        self.state_as_polyline().draw_composed(cx, style);
+        indicators::draw_pos_indicator(cx, PenState::Up, self.elements[0].pos, zoom);
+        if let Some(last) = self.elements.last() {
+            indicators::draw_pos_indicator(cx, self.pen_state, last.pos, zoom);
+        }
+        cx.restore().unwrap();
+    }
+}
+
+impl FreehandBezierBuilder {
+    /// The default maximum distance of the drawn elements to the fitted curves.
+    pub const FIT_TOLERANCE_DEFAULT: f64 = 2.0;
+
+    /// Use the given maximum distance of the drawn elements to the fitted curves.
+    pub fn with_fit_tolerance(mut self, fit_tolerance: f64) -> Self {
+        self.fit_tolerance = fit_tolerance;
+        self
+    }
+
+    /// The drawn elements as polyline.
+    pub fn state_as_polyline(&self) -> Polyline {
+        Polyline {
+            start: self.elements[0].pos,
+            path: self.elements[1..].iter().map(|el| el.pos).collect(),
+        }
+    }
+
+    /// Fit the drawn elements to as few connected cubic bezier curves as possible within the fit tolerance.
+    pub fn fitted_curves(&self) -> Vec<CubicBezier> {
+        let mut start = self.elements[0].pos;
+        CurveFit::Bezier
+            .fit(&self.elements, self.fit_tolerance)
+            .into_iter()
+            .filter_map(|segment| {
+                let Segment::CubBezTo { cp1, cp2, end } = segment else {
+                    return None;
+                };
+                let cubbez = CubicBezier {
+                    start,
+                    cp1,
+                    cp2,
+                    end: end.pos,
+                };
+                start = end.pos;
+                Some(cubbez)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::Shapeable;
+
+    fn sine_elements() -> Vec<Element> {
+        (0..200)
+            .map(|i| {
+                let x = i as f64;
+                Element::new(na::vector![x, 30.0 * (x / 20.0).sin()], 0.5)
+            })
+            .collect()
+    }
+
+    /// Draws the elements with the builder and returns the curves of the emitted path.
+    fn build_curves(elements: &[Element], fit_tolerance: f64) -> Vec<CubicBezier> {
+        let now = Instant::now();
+        let mut builder =
+            FreehandBezierBuilder::start(elements[0], now).with_fit_tolerance(fit_tolerance);
+        for &element in &elements[1..elements.len() - 1] {
+            builder.handle_event(
+                PenEvent::Down {
+                    element,
+                    modifier_keys: Default::default(),
+                },
+                now,
+                Constraints::default(),
+            );
+        }
+        let progress = builder
+            .handle_event(
+                PenEvent::Up {
+                    element: elements[elements.len() - 1],
+                    modifier_keys: Default::default(),
+                },
+                now,
+                Constraints::default(),
+            )
+            .progress;
+        let BuilderProgress::Finished(shapes) = progress else {
+            panic!("the builder did not finish");
+        };
+        let [Shape::BezierPath(bezier_path)] = shapes.as_slice() else {
+            panic!("the builder did not emit a single bezier path");
+        };
+        // one continuous path, so it is drawn with only one cap at each end
+        assert_eq!(
+            bezier_path
+                .outline_path()
+                .elements()
+                .iter()
+                .filter(|el| matches!(el, kurbo::PathEl::MoveTo(_)))
+                .count(),
+            1
+        );
+        bezier_path.curves.clone()
+    }
+
+    /// The largest distance of the elements to the curves.
+    fn max_deviation(elements: &[Element], curves: &[CubicBezier]) -> f64 {
+        let mut points = Vec::new();
+        for curve in curves {
+            kurbo::flatten(curve.outline_path(), 0.01, |el| match el {
+                kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) => {
+                    points.push(na::vector![p.x, p.y])
+                }
+                _ => {}
+            });
+        }
+        elements
+            .iter()
+            .map(|element| {
+                points
+                    .windows(2)
+                    .map(|w| {
+                        let ab = w[1] - w[0];
+                        let t = if ab.norm_squared() > 0.0 {
+                            ((element.pos - w[0]).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0)
+                        } else {
+                            0.0
+                        };
+                        (w[0] + ab * t - element.pos).norm()
+                    })
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn fitted_curves_stay_within_tolerance() {
+        let elements = sine_elements();
+        let curves = build_curves(&elements, FreehandBezierBuilder::FIT_TOLERANCE_DEFAULT);
+        // far fewer curves than input elements, connected end to end
+        assert!(!curves.is_empty() && curves.len() < elements.len() / 10);
+        for w in curves.windows(2) {
+            assert_eq!(w[0].end, w[1].start);
+        }
+        assert!(
+            max_deviation(&elements, &curves)
+                <= FreehandBezierBuilder::FIT_TOLERANCE_DEFAULT + 0.05
+        );
+    }
+
+    #[test]
+    fn smaller_fit_tolerance_fits_closer_with_more_curves() {
+        let elements = sine_elements();
+        let coarse = build_curves(&elements, 4.0);
+        let fine = build_curves(&elements, 0.1);
+        assert!(fine.len() > coarse.len());
+        let coarse_deviation = max_deviation(&elements, &coarse);
+        let fine_deviation = max_deviation(&elements, &fine);
+        assert!(fine_deviation < coarse_deviation);
+        assert!(fine_deviation <= 0.1 + 0.05);
+    }
+}
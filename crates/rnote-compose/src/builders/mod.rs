@@ -7,6 +7,7 @@ mod coordsystem3dbuilder;
 mod cubbezbuilder;
 mod ellipsebuilder;
 mod fociellipsebuilder;
+mod freehandbezierbuilder;
 mod gridbuilder;
 mod linebuilder;
 mod penpathcurvedbuilder;
@@ -25,6 +26,7 @@ pub use coordsystem3dbuilder::CoordSystem3DBuilder;
 pub use cubbezbuilder::CubBezBuilder;
 pub use ellipsebuilder::EllipseBuilder;
 pub use fociellipsebuilder::FociEllipseBuilder;
+pub use freehandbezierbuilder::FreehandBezierBuilder;
 pub use gridbuilder::GridBuilder;
 pub use linebuilder::LineBuilder;
 pub use penpathcurvedbuilder::PenPathCurvedBuilder;
@@ -85,6 +87,9 @@ pub enum ShapeBuilderType {
     /// A polygon builder
     #[serde(rename = "polygon")]
     Polygon,
+    /// A freehand path that is fitted to bezier curves
+    #[serde(rename = "freehand_bezier")]
+    FreehandBezier,
 }
 
 impl ShapeBuilderType {
@@ -104,6 +109,7 @@ impl ShapeBuilderType {
             "shapebuilder-cubbez-symbolic" => Some(Self::CubBez),
             "shapebuilder-polyline-symbolic" => Some(Self::Polyline),
             "shapebuilder-polygon-symbolic" => Some(Self::Polygon),
+            "shapebuilder-freehandbezier-symbolic" => Some(Self::FreehandBezier),
             _ => None,
         }
     }
@@ -126,6 +132,7 @@ impl ShapeBuilderType {
             Self::CubBez => String::from("shapebuilder-cubbez-symbolic"),
             Self::Polyline => String::from("shapebuilder-polyline-symbolic"),
             Self::Polygon => String::from("shapebuilder-polygon-symbolic"),
+            Self::FreehandBezier => String::from("shapebuilder-freehandbezier-symbolic"),
         }
     }
 }
@@ -84,6 +84,10 @@ impl RectangleBuilder {
         let half_extents = (self.current - self.start) * 0.5;
         let cuboid = Cuboid::new(half_extents);
 
-        Rectangle { cuboid, transform }
+        Rectangle {
+            cuboid,
+            transform,
+            corner_radius: 0.0,
+        }
     }
 }
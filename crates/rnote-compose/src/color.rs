@@ -8,6 +8,15 @@ use serde::{Deserialize, Serialize};
 /// The threshold of the luminance of a color, deciding if a light or dark fg color is used. Between 0.0 and 1.0.
 pub const FG_LUMINANCE_THRESHOLD: f64 = 0.7;
 
+/// The ink color that stays legible on the background color, white on dark and black on light backgrounds.
+pub fn contrasting_ink(background: Color) -> Color {
+    if background.luma() < FG_LUMINANCE_THRESHOLD {
+        Color::WHITE
+    } else {
+        Color::BLACK
+    }
+}
+
 /// A rgba color
 #[derive(
     Debug,
@@ -107,6 +116,17 @@ impl Color {
         }
     }
 
+    /// Linearly interpolate between the color and the other color, with `t` ranging [0.0, 1.0].
+    pub fn mix(self, other: Self, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Self {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+
     /// Approximate equality.
     pub fn approx_eq(self, other: Self) -> bool {
         approx::relative_eq!(self.r, other.r)
@@ -344,3 +364,22 @@ pub const GNOME_DARKS: [piet::Color; 5] = [
     piet::Color::rgb8(0x24, 0x1f, 0x31),
     piet::Color::rgb8(0x00, 0x00, 0x00),
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::{contrasting_ink, Color};
+
+    #[test]
+    fn contrasting_ink_is_legible_on_background() {
+        for dark in [Color::BLACK, Color::new(0.12, 0.12, 0.14, 1.0), Color::BLUE] {
+            assert_eq!(contrasting_ink(dark), Color::WHITE);
+        }
+        for light in [
+            Color::WHITE,
+            Color::new(0.96, 0.94, 0.88, 1.0),
+            Color::new(0.9, 0.95, 1.0, 1.0),
+        ] {
+            assert_eq!(contrasting_ink(light), Color::BLACK);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+// Imports
+use super::{Element, Segment};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// The algorithm that fits the path segments to the input elements.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "curve_fit")]
+pub enum CurveFit {
+    /// Straight lines between all input elements.
+    #[serde(rename = "polyline")]
+    Polyline = 0,
+    /// A Catmull-Rom spline passing through all input elements.
+    #[serde(rename = "catmull_rom")]
+    CatmullRom,
+    /// Cubic bezier curves that approximate the input elements within a tolerance, with as few segments as possible.
+    #[serde(rename = "bezier")]
+    Bezier,
+}
+
+impl Default for CurveFit {
+    fn default() -> Self {
+        Self::Polyline
+    }
+}
+
+impl TryFrom<u32> for CurveFit {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value)
+            .with_context(|| format!("CurveFit try_from::<u32>() for value {value} failed"))
+    }
+}
+
+impl CurveFit {
+    /// Fit segments to the elements, where the first element is the start of the path.
+    ///
+    /// The tolerance is the maximum distance of the input elements to the path and is only used for [CurveFit::Bezier].
+    pub fn fit(&self, elements: &[Element], tolerance: f64) -> Vec<Segment> {
+        match self {
+            Self::Polyline => elements
+                .iter()
+                .skip(1)
+                .map(|&end| Segment::LineTo { end })
+                .collect(),
+            Self::CatmullRom => fit_catmull_rom(elements),
+            Self::Bezier => fit_bezier(elements, tolerance),
+        }
+    }
+}
+
+fn fit_catmull_rom(elements: &[Element]) -> Vec<Segment> {
+    (1..elements.len())
+        .map(|i| {
+            let p0 = elements[i.saturating_sub(2)].pos;
+            let p1 = elements[i - 1].pos;
+            let p2 = elements[i].pos;
+            let p3 = elements[(i + 1).min(elements.len() - 1)].pos;
+
+            Segment::CubBezTo {
+                cp1: p1 + (p2 - p0) / 6.0,
+                cp2: p2 - (p3 - p1) / 6.0,
+                end: elements[i],
+            }
+        })
+        .collect()
+}
+
+/// Fits cubic beziers with the algorithm from "An Algorithm for Automatically Fitting Digitized Curves"
+/// by Philip J. Schneider, recursively splitting at the element with the largest error.
+fn fit_bezier(elements: &[Element], tolerance: f64) -> Vec<Segment> {
+    let mut elements = elements.to_vec();
+    // Duplicate positions have no tangent
+    elements.dedup_by(|second, first| (second.pos - first.pos).norm_squared() < f64::EPSILON);
+    let mut segments = Vec::new();
+    if elements.len() < 2 {
+        return segments;
+    }
+
+    let n = elements.len();
+    let tan_start = unit_tangent(elements[1].pos - elements[0].pos);
+    let tan_end = unit_tangent(elements[n - 2].pos - elements[n - 1].pos);
+    fit_bezier_int(
+        &elements,
+        tan_start,
+        tan_end,
+        tolerance.max(f64::EPSILON),
+        &mut segments,
+    );
+    segments
+}
+
+fn fit_bezier_int(
+    elements: &[Element],
+    tan_start: na::Vector2<f64>,
+    tan_end: na::Vector2<f64>,
+    tolerance: f64,
+    segments: &mut Vec<Segment>,
+) {
+    const MAX_REPARAMETERIZATIONS: usize = 4;
+    let n = elements.len();
+    let first = elements[0].pos;
+    let last = elements[n - 1].pos;
+
+    if n == 2 {
+        let dist = (last - first).norm() / 3.0;
+        segments.push(Segment::CubBezTo {
+            cp1: first + tan_start * dist,
+            cp2: last + tan_end * dist,
+            end: elements[1],
+        });
+        return;
+    }
+
+    let mut params = chord_length_params(elements);
+    let mut bezier = generate_bezier(elements, &params, tan_start, tan_end);
+    let (mut max_error, mut split) = max_error(elements, &params, &bezier);
+
+    // If the error is not too large, try to improve the fit by reparameterization
+    if max_error > tolerance && max_error < (tolerance * 4.0) {
+        for _ in 0..MAX_REPARAMETERIZATIONS {
+            params = reparameterize(elements, &params, &bezier);
+            bezier = generate_bezier(elements, &params, tan_start, tan_end);
+            (max_error, split) = max_error(elements, &params, &bezier);
+            if max_error <= tolerance {
+                break;
+            }
+        }
+    }
+
+    if max_error <= tolerance {
+        segments.push(Segment::CubBezTo {
+            cp1: bezier[1],
+            cp2: bezier[2],
+            end: elements[n - 1],
+        });
+        return;
+    }
+
+    let tan_center = unit_tangent(elements[split - 1].pos - elements[split + 1].pos);
+    fit_bezier_int(
+        &elements[..=split],
+        tan_start,
+        tan_center,
+        tolerance,
+        segments,
+    );
+    fit_bezier_int(
+        &elements[split..],
+        -tan_center,
+        tan_end,
+        tolerance,
+        segments,
+    );
+}
+
+fn unit_tangent(v: na::Vector2<f64>) -> na::Vector2<f64> {
+    v.try_normalize(f64::EPSILON)
+        .unwrap_or_else(na::Vector2::zeros)
+}
+
+fn chord_length_params(elements: &[Element]) -> Vec<f64> {
+    let mut params = Vec::with_capacity(elements.len());
+    let mut len = 0.0;
+    params.push(0.0);
+    for w in elements.windows(2) {
+        len += (w[1].pos - w[0].pos).norm();
+        params.push(len);
+    }
+    if len > 0.0 {
+        params.iter_mut().for_each(|u| *u /= len);
+    }
+    params
+}
+
+fn bernstein(u: f64) -> [f64; 4] {
+    let v = 1.0 - u;
+    [v * v * v, 3.0 * u * v * v, 3.0 * u * u * v, u * u * u]
+}
+
+fn eval_bezier(bezier: &[na::Vector2<f64>; 4], u: f64) -> na::Vector2<f64> {
+    let b = bernstein(u);
+    bezier[0] * b[0] + bezier[1] * b[1] + bezier[2] * b[2] + bezier[3] * b[3]
+}
+
+/// Least squares fit of the control points with the given end tangents.
+fn generate_bezier(
+    elements: &[Element],
+    params: &[f64],
+    tan_start: na::Vector2<f64>,
+    tan_end: na::Vector2<f64>,
+) -> [na::Vector2<f64>; 4] {
+    let first = elements[0].pos;
+    let last = elements[elements.len() - 1].pos;
+    let mut c = [[0.0; 2]; 2];
+    let mut x = [0.0; 2];
+
+    for (element, &u) in elements.iter().zip(params) {
+        let b = bernstein(u);
+        let a0 = tan_start * b[1];
+        let a1 = tan_end * b[2];
+        c[0][0] += a0.dot(&a0);
+        c[0][1] += a0.dot(&a1);
+        c[1][1] += a1.dot(&a1);
+        let tmp = element.pos - (first * (b[0] + b[1]) + last * (b[2] + b[3]));
+        x[0] += a0.dot(&tmp);
+        x[1] += a1.dot(&tmp);
+    }
+    c[1][0] = c[0][1];
+
+    let det_c0_c1 = c[0][0] * c[1][1] - c[1][0] * c[0][1];
+    let det_c0_x = c[0][0] * x[1] - c[1][0] * x[0];
+    let det_x_c1 = x[0] * c[1][1] - x[1] * c[0][1];
+    let (alpha_start, alpha_end) = if det_c0_c1.abs() > f64::EPSILON {
+        (det_x_c1 / det_c0_c1, det_c0_x / det_c0_c1)
+    } else {
+        (0.0, 0.0)
+    };
+
+    // Fall back to a heuristic if the least squares solution is degenerate
+    let seg_len = (last - first).norm();
+    let epsilon = 1e-6 * seg_len;
+    let (alpha_start, alpha_end) = if alpha_start < epsilon || alpha_end < epsilon {
+        (seg_len / 3.0, seg_len / 3.0)
+    } else {
+        (alpha_start, alpha_end)
+    };
+
+    [
+        first,
+        first + tan_start * alpha_start,
+        last + tan_end * alpha_end,
+        last,
+    ]
+}
+
+/// The maximum distance of the elements to their parameterized position on the curve, and the index of that element.
+fn max_error(elements: &[Element], params: &[f64], bezier: &[na::Vector2<f64>; 4]) -> (f64, usize) {
+    let mut max_error = 0.0;
+    let mut split = elements.len() / 2;
+
+    for i in 1..elements.len() - 1 {
+        let error = (eval_bezier(bezier, params[i]) - elements[i].pos).norm();
+        if error >= max_error {
+            max_error = error;
+            split = i;
+        }
+    }
+
+    (max_error, split)
+}
+
+/// Improve the parameters with a Newton-Raphson iteration.
+fn reparameterize(
+    elements: &[Element],
+    params: &[f64],
+    bezier: &[na::Vector2<f64>; 4],
+) -> Vec<f64> {
+    let d1 = [
+        (bezier[1] - bezier[0]) * 3.0,
+        (bezier[2] - bezier[1]) * 3.0,
+        (bezier[3] - bezier[2]) * 3.0,
+    ];
+    let d2 = [(d1[1] - d1[0]) * 2.0, (d1[2] - d1[1]) * 2.0];
+
+    elements
+        .iter()
+        .zip(params)
+        .map(|(element, &u)| {
+            let v = 1.0 - u;
+            let q = eval_bezier(bezier, u);
+            let q1 = d1[0] * (v * v) + d1[1] * (2.0 * u * v) + d1[2] * (u * u);
+            let q2 = d2[0] * v + d2[1] * u;
+            let diff = q - element.pos;
+            let numerator = diff.dot(&q1);
+            let denominator = q1.dot(&q1) + diff.dot(&q2);
+            if denominator.abs() < f64::EPSILON {
+                u
+            } else {
+                (u - numerator / denominator).clamp(0.0, 1.0)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::penpath::PenPath;
+
+    fn sine_elements() -> Vec<Element> {
+        (0..200)
+            .map(|i| {
+                let x = i as f64;
+                Element::new(na::vector![x, 20.0 * (x / 15.0).sin()], 0.5)
+            })
+            .collect()
+    }
+
+    /// The maximum distance of the elements to the flattened path.
+    fn max_deviation(elements: &[Element], segments: Vec<Segment>) -> f64 {
+        let path = PenPath::new_w_segments(elements[0], segments);
+        let mut points = Vec::new();
+        for el in path.to_kurbo_flattened(0.001).elements() {
+            match el {
+                kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) => {
+                    points.push(na::vector![p.x, p.y])
+                }
+                _ => {}
+            }
+        }
+        let dist_to_line = |p: na::Vector2<f64>, a: na::Vector2<f64>, b: na::Vector2<f64>| {
+            let ab = b - a;
+            let t = if ab.norm_squared() > 0.0 {
+                ((p - a).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (a + ab * t - p).norm()
+        };
+
+        elements
+            .iter()
+            .map(|element| {
+                points
+                    .windows(2)
+                    .map(|w| dist_to_line(element.pos, w[0], w[1]))
+                    .fold(f64::MAX, f64::min)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn curve_fits_point_counts_and_deviation() {
+        let elements = sine_elements();
+        let tolerance = 0.5;
+
+        let polyline = CurveFit::Polyline.fit(&elements, tolerance);
+        assert_eq!(polyline.len(), elements.len() - 1);
+        assert!(max_deviation(&elements, polyline) < 1e-6);
+
+        // Catmull-Rom passes through all elements
+        let catmull_rom = CurveFit::CatmullRom.fit(&elements, tolerance);
+        assert_eq!(catmull_rom.len(), elements.len() - 1);
+        assert!(max_deviation(&elements, catmull_rom) < 1e-6);
+
+        // Bezier fitting reduces the segments, but stays within the tolerance
+        let bezier = CurveFit::Bezier.fit(&elements, tolerance);
+        assert!(bezier.len() < elements.len() / 10);
+        assert!(max_deviation(&elements, bezier.clone()) <= tolerance + 0.01);
+        assert_eq!(bezier.last().map(|seg| seg.end()), elements.last().copied());
+
+        // A tighter tolerance needs more segments
+        let bezier_tight = CurveFit::Bezier.fit(&elements, 0.05);
+        assert!(bezier_tight.len() > bezier.len());
+        assert!(max_deviation(&elements, bezier_tight) <= 0.05 + 0.01);
+    }
+}
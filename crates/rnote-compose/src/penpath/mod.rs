@@ -1,8 +1,10 @@
 // Modules
+mod curvefit;
 mod element;
 mod segment;
 
 // Re-exports
+pub use curvefit::CurveFit;
 pub use element::Element;
 pub use segment::Segment;
 
@@ -142,6 +144,16 @@ impl PenPath {
         Some(Self { start, segments })
     }
 
+    /// A new pen path from the input elements of this path, with segments fitted by the given algorithm.
+    ///
+    /// The tolerance is the maximum distance of the input elements to the fitted path, used for [CurveFit::Bezier].
+    pub fn fitted(&self, curve_fit: CurveFit, tolerance: f64) -> Self {
+        let elements = self.clone().into_elements();
+        let segments = curve_fit.fit(&elements, tolerance);
+
+        Self::new_w_segments(self.start, segments)
+    }
+
     /// Checks whether bounds collide with the path. If it does, it returns the indices of the colliding segments
     ///
     /// `loosened` loosens the segments hitboxes by the value
@@ -0,0 +1,88 @@
+// Imports
+use super::CubicBezier;
+use crate::ext::Vector2Ext;
+use crate::shapes::Shapeable;
+use crate::transform::Transformable;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "bezier_path")]
+/// A path of connected cubic bezier curves.
+///
+/// Every curve starts at the end of the previous one.
+pub struct BezierPath {
+    /// The curves
+    #[serde(rename = "curves")]
+    pub curves: Vec<CubicBezier>,
+}
+
+impl Transformable for BezierPath {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        for curve in &mut self.curves {
+            curve.translate(offset);
+        }
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        for curve in &mut self.curves {
+            curve.rotate(angle, center);
+        }
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        for curve in &mut self.curves {
+            curve.scale(scale);
+        }
+    }
+}
+
+impl Shapeable for BezierPath {
+    fn bounds(&self) -> Aabb {
+        self.curves
+            .iter()
+            .map(|curve| curve.bounds())
+            .reduce(|acc, bounds| acc.merged(&bounds))
+            .unwrap_or_else(|| Aabb::new(na::point![0.0, 0.0], na::point![0.0, 0.0]))
+    }
+
+    fn hitboxes(&self) -> Vec<Aabb> {
+        self.curves
+            .iter()
+            .flat_map(|curve| curve.hitboxes())
+            .collect()
+    }
+
+    fn outline_path(&self) -> kurbo::BezPath {
+        let Some(first) = self.curves.first() else {
+            return kurbo::BezPath::new();
+        };
+        let iter = std::iter::once(kurbo::PathEl::MoveTo(first.start.to_kurbo_point())).chain(
+            self.curves.iter().map(|curve| {
+                kurbo::PathEl::CurveTo(
+                    curve.cp1.to_kurbo_point(),
+                    curve.cp2.to_kurbo_point(),
+                    curve.end.to_kurbo_point(),
+                )
+            }),
+        );
+        kurbo::BezPath::from_iter(iter)
+    }
+}
+
+impl BezierPath {
+    /// A new bezier path from connected curves.
+    pub fn new(curves: Vec<CubicBezier>) -> Self {
+        Self { curves }
+    }
+
+    /// The start of the path.
+    pub fn start(&self) -> Option<na::Vector2<f64>> {
+        self.curves.first().map(|curve| curve.start)
+    }
+
+    /// The end of the path.
+    pub fn end(&self) -> Option<na::Vector2<f64>> {
+        self.curves.last().map(|curve| curve.end)
+    }
+}
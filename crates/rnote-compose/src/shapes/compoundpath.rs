@@ -0,0 +1,225 @@
+// Imports
+use super::{Shape, Shapeable};
+use crate::transform::Transformable;
+use geo::orient::{Direction, Orient};
+use geo::BooleanOps;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "compound_path")]
+/// A compound path, made up of multiple shapes as its subpaths.
+///
+/// It is drawn as one geometry, so the fill of overlapping subpaths cuts holes into each other.
+pub struct CompoundPath {
+    /// The subpaths. Never contains compound paths itself.
+    #[serde(rename = "subpaths")]
+    pub subpaths: Vec<Shape>,
+}
+
+impl Transformable for CompoundPath {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        for subpath in &mut self.subpaths {
+            subpath.translate(offset);
+        }
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        for subpath in &mut self.subpaths {
+            subpath.rotate(angle, center);
+        }
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        for subpath in &mut self.subpaths {
+            subpath.scale(scale);
+        }
+    }
+}
+
+impl Shapeable for CompoundPath {
+    fn bounds(&self) -> Aabb {
+        self.subpaths
+            .iter()
+            .map(|subpath| subpath.bounds())
+            .reduce(|acc, bounds| acc.merged(&bounds))
+            .unwrap_or_else(|| Aabb::new(na::point![0.0, 0.0], na::point![0.0, 0.0]))
+    }
+
+    fn hitboxes(&self) -> Vec<Aabb> {
+        self.subpaths
+            .iter()
+            .flat_map(|subpath| subpath.hitboxes())
+            .collect()
+    }
+
+    fn outline_path(&self) -> kurbo::BezPath {
+        let mut path = kurbo::BezPath::new();
+        for subpath in &self.subpaths {
+            path.extend(subpath.outline_path());
+        }
+        path
+    }
+}
+
+impl CompoundPath {
+    /// A new compound path from the shapes. Compound paths among them are flattened into their subpaths.
+    pub fn new(shapes: impl IntoIterator<Item = Shape>) -> Self {
+        let mut subpaths = Vec::new();
+        for shape in shapes {
+            match shape {
+                Shape::CompoundPath(compound) => subpaths.extend(compound.subpaths),
+                shape => subpaths.push(shape),
+            }
+        }
+        Self { subpaths }
+    }
+
+    /// The region that is filled with the even-odd rule, where overlapping subpaths cut holes into each other. Curves
+    /// are flattened with the given tolerance.
+    pub fn even_odd_region(&self, tolerance: f64) -> geo::MultiPolygon<f64> {
+        let mut rings: Vec<Vec<geo::Coord<f64>>> = vec![];
+        for subpath in &self.subpaths {
+            kurbo::flatten(subpath.outline_path(), tolerance, |el| match el {
+                kurbo::PathEl::MoveTo(p) => rings.push(vec![geo::Coord { x: p.x, y: p.y }]),
+                kurbo::PathEl::LineTo(p) => {
+                    if let Some(ring) = rings.last_mut() {
+                        ring.push(geo::Coord { x: p.x, y: p.y });
+                    }
+                }
+                _ => {}
+            });
+        }
+
+        rings
+            .into_iter()
+            .filter(|ring| ring.len() >= 3)
+            .map(|ring| {
+                geo::MultiPolygon::new(vec![geo::Polygon::new(geo::LineString::new(ring), vec![])])
+            })
+            .reduce(|region, polygon| region.xor(&polygon))
+            .unwrap_or_else(|| geo::MultiPolygon::new(vec![]))
+    }
+
+    /// The even-odd region as path. Its subpaths are oriented so that it is filled the same with the non-zero rule,
+    /// for example when clipping to it.
+    pub fn even_odd_region_path(&self, tolerance: f64) -> kurbo::BezPath {
+        let mut path = kurbo::BezPath::new();
+        for polygon in self.even_odd_region(tolerance).orient(Direction::Default) {
+            for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+                let mut coords = ring.coords();
+                let Some(first) = coords.next() else {
+                    continue;
+                };
+                path.move_to((first.x, first.y));
+                for coord in coords {
+                    path.line_to((coord.x, coord.y));
+                }
+                path.close_path();
+            }
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::{Line, Rectangle};
+    use crate::style::smooth::SmoothOptions;
+    use crate::style::textured::TexturedOptions;
+    use crate::style::Composer;
+    use crate::Style;
+    use geo::Area;
+
+    #[test]
+    fn nested_compound_paths_are_flattened() {
+        let rect = Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+            na::point![0.0, 0.0],
+            na::point![10.0, 10.0],
+        )));
+        let inner = CompoundPath::new([
+            Shape::Line(Line::new(na::vector![20.0, 0.0], na::vector![30.0, 5.0])),
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![22.0, 2.0],
+                na::point![26.0, 6.0],
+            ))),
+        ]);
+        let compound = CompoundPath::new([rect, Shape::CompoundPath(inner)]);
+
+        assert_eq!(compound.subpaths.len(), 3);
+        assert!(compound
+            .subpaths
+            .iter()
+            .all(|subpath| !matches!(subpath, Shape::CompoundPath(_))));
+        let bounds = compound.bounds();
+        assert_eq!(bounds.maxs, na::point![30.0, 10.0]);
+        // every subpath starts a new subpath in the outline
+        assert_eq!(
+            compound
+                .outline_path()
+                .elements()
+                .iter()
+                .filter(|el| matches!(el, kurbo::PathEl::MoveTo(_)))
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn overlapping_subpaths_cut_holes_into_region() {
+        let compound = CompoundPath::new([
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![0.0, 0.0],
+                na::point![100.0, 100.0],
+            ))),
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![20.0, 20.0],
+                na::point![60.0, 60.0],
+            ))),
+            // lines don't enclose a region
+            Shape::Line(Line::new(na::vector![0.0, 0.0], na::vector![100.0, 100.0])),
+        ]);
+
+        let region = compound.even_odd_region(0.1);
+        assert_eq!(region.0.len(), 1);
+        assert_eq!(region.0[0].interiors().len(), 1);
+        approx::assert_relative_eq!(region.unsigned_area(), 8400.0, epsilon = 1e-6);
+        // the exterior and the hole are separate subpaths
+        assert_eq!(
+            compound
+                .even_odd_region_path(0.1)
+                .elements()
+                .iter()
+                .filter(|el| matches!(el, kurbo::PathEl::MoveTo(_)))
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn textured_compound_path_falls_back_to_smooth() {
+        let compound = CompoundPath::new([
+            Shape::Line(Line::new(na::vector![0.0, 0.0], na::vector![10.0, 5.0])),
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![20.0, 0.0],
+                na::point![30.0, 10.0],
+            ))),
+        ]);
+        let textured = TexturedOptions {
+            stroke_width: 4.0,
+            ..Default::default()
+        };
+        let smooth = SmoothOptions {
+            stroke_width: 4.0,
+            stroke_color: textured.stroke_color,
+            pressure_curve: textured.pressure_curve,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            compound.composed_bounds(&Style::Textured(textured)),
+            compound.composed_bounds(&smooth)
+        );
+    }
+}
@@ -0,0 +1,100 @@
+// Imports
+use geo::{Area, ConvexHull};
+use std::cmp::Ordering;
+
+/// The convex hull of the points.
+///
+/// The hull vertices are ordered counter-clockwise in a coordinate system where the y-axis points upwards, starting
+/// at the point with the smallest x and y coordinates. Collinear points on the edges of the hull are omitted. Fewer
+/// than three distinct points are returned as they are, deduplicated.
+pub fn convex_hull(points: &[na::Point2<f64>]) -> Vec<na::Point2<f64>> {
+    let cmp = |a: &na::Point2<f64>, b: &na::Point2<f64>| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+    };
+    let mut points = points
+        .iter()
+        .filter(|p| p.x.is_finite() && p.y.is_finite())
+        .copied()
+        .collect::<Vec<na::Point2<f64>>>();
+    points.sort_unstable_by(cmp);
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let hull = points
+        .iter()
+        .map(|p| geo::Point::new(p.x, p.y))
+        .collect::<geo::MultiPoint<f64>>()
+        .convex_hull();
+    // collinear points only keep the ends
+    if hull.unsigned_area() == 0.0 {
+        return vec![points[0], points[points.len() - 1]];
+    }
+    let mut hull = hull
+        .exterior()
+        .coords()
+        .map(|coord| na::point![coord.x, coord.y])
+        .collect::<Vec<na::Point2<f64>>>();
+    // the ring is closed, so the last point is the first one again
+    hull.pop();
+    if let Some(start) = hull
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| cmp(a, b))
+        .map(|(i, _)| i)
+    {
+        hull.rotate_left(start);
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::convex_hull;
+
+    #[test]
+    fn hull_of_square_with_inner_points() {
+        let points = [
+            na::point![2.0, 2.0],
+            na::point![0.0, 0.0],
+            na::point![4.0, 4.0],
+            na::point![1.0, 3.0],
+            na::point![4.0, 0.0],
+            // collinear on an edge
+            na::point![2.0, 0.0],
+            na::point![0.0, 4.0],
+            na::point![3.0, 1.0],
+            na::point![0.0, 0.0],
+        ];
+        assert_eq!(
+            convex_hull(&points),
+            vec![
+                na::point![0.0, 0.0],
+                na::point![4.0, 0.0],
+                na::point![4.0, 4.0],
+                na::point![0.0, 4.0],
+            ]
+        );
+    }
+
+    #[test]
+    fn hull_of_degenerate_points() {
+        assert!(convex_hull(&[]).is_empty());
+        assert_eq!(
+            convex_hull(&[na::point![1.0, 1.0], na::point![1.0, 1.0]]),
+            vec![na::point![1.0, 1.0]]
+        );
+        // collinear points only keep the ends
+        assert_eq!(
+            convex_hull(&[
+                na::point![0.0, 0.0],
+                na::point![1.0, 1.0],
+                na::point![2.0, 2.0]
+            ]),
+            vec![na::point![0.0, 0.0], na::point![2.0, 2.0]]
+        );
+    }
+}
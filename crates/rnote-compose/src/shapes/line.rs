@@ -74,6 +74,7 @@ impl Line {
         Rectangle {
             cuboid: p2d::shape::Cuboid::new(na::vector![magn * 0.5, width * 0.5]),
             transform: Transform::new_w_isometry(na::Isometry2::new(self.start + vec * 0.5, angle)),
+            corner_radius: 0.0,
         }
     }
 
@@ -1,10 +1,16 @@
 // Modules
 /// Arrow
 pub mod arrow;
+/// Bezier path
+pub mod bezierpath;
+/// Compound path
+pub mod compoundpath;
 /// Cubic-bezier curve
 pub mod cubbez;
 /// Ellipse
 pub mod ellipse;
+/// Convex hull
+pub mod hull;
 /// Line
 pub mod line;
 /// Polygon
@@ -13,6 +19,8 @@ pub mod polygon;
 pub mod polyline;
 /// Polyline
 pub mod quadbez;
+/// Shape recognition
+pub mod recognize;
 /// Rectangle
 pub mod rectangle;
 /// Shape
@@ -22,12 +30,16 @@ pub mod shapeable;
 
 // Re-exports
 pub use arrow::Arrow;
+pub use bezierpath::BezierPath;
+pub use compoundpath::CompoundPath;
 pub use cubbez::CubicBezier;
 pub use ellipse::Ellipse;
+pub use hull::convex_hull;
 pub use line::Line;
 pub use polygon::Polygon;
 pub use polyline::Polyline;
 pub use quadbez::QuadraticBezier;
+pub use recognize::recognize_shape;
 pub use rectangle::Rectangle;
 pub use shape::Shape;
 pub use shapeable::Shapeable;
@@ -38,3 +50,73 @@ fn hitbox_elems_for_shape_len(len: f64) -> i32 {
 
     ((len / MAX_HITBOX_DIAGONAL).ceil() as i32).max(1)
 }
+
+/// The closed outline through the corner points, with the corners rounded by arcs of the given radius.
+///
+/// The radius is reduced at corners where the adjacent edges are too short for it. The arcs are approximated with
+/// cubic beziers.
+fn rounded_corners_path(points: &[na::Vector2<f64>], radius: f64) -> kurbo::BezPath {
+    use crate::ext::Vector2Ext;
+
+    let mut points = points.to_vec();
+    points.dedup();
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    let n_points = points.len();
+    let mut path = kurbo::BezPath::new();
+
+    for i in 0..n_points {
+        let (prev, corner, next) = (
+            points[(i + n_points - 1) % n_points],
+            points[i],
+            points[(i + 1) % n_points],
+        );
+        let (to_prev, to_next) = (prev - corner, next - corner);
+        let (len_prev, len_next) = (to_prev.magnitude(), to_next.magnitude());
+        let angle = if len_prev > 0.0 && len_next > 0.0 {
+            to_prev.angle(&to_next)
+        } else {
+            0.0
+        };
+
+        // Degenerate and straight corners are not rounded
+        if radius <= 0.0
+            || n_points < 3
+            || angle <= f64::EPSILON
+            || std::f64::consts::PI - angle <= f64::EPSILON
+        {
+            if i == 0 {
+                path.move_to(corner.to_kurbo_point());
+            } else {
+                path.line_to(corner.to_kurbo_point());
+            }
+            continue;
+        }
+
+        let half_angle_tan = (angle * 0.5).tan();
+        // The distance from the corner to where the arc touches the edges
+        let tangent_dist = (radius / half_angle_tan)
+            .min(len_prev * 0.5)
+            .min(len_next * 0.5);
+        let arc_radius = tangent_dist * half_angle_tan;
+        let (dir_prev, dir_next) = (to_prev / len_prev, to_next / len_next);
+        let arc_start = corner + dir_prev * tangent_dist;
+        let arc_end = corner + dir_next * tangent_dist;
+        let handle_len = arc_radius * 4.0 / 3.0 * ((std::f64::consts::PI - angle) * 0.25).tan();
+
+        if i == 0 {
+            path.move_to(arc_start.to_kurbo_point());
+        } else {
+            path.line_to(arc_start.to_kurbo_point());
+        }
+        path.curve_to(
+            (arc_start - dir_prev * handle_len).to_kurbo_point(),
+            (arc_end - dir_next * handle_len).to_kurbo_point(),
+            arc_end.to_kurbo_point(),
+        );
+    }
+    path.close_path();
+
+    path
+}
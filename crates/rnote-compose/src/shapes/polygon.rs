@@ -15,6 +15,9 @@ pub struct Polygon {
     /// The polygon path
     #[serde(rename = "path")]
     pub path: Vec<na::Vector2<f64>>,
+    /// The radius of the rounded corners. Zero for sharp corners.
+    #[serde(rename = "corner_radius", with = "crate::serialize::f64_dp3")]
+    pub corner_radius: f64,
 }
 
 impl Transformable for Polygon {
@@ -71,6 +74,13 @@ impl Shapeable for Polygon {
     }
 
     fn outline_path(&self) -> kurbo::BezPath {
+        if self.corner_radius > 0.0 {
+            let points = std::iter::once(self.start)
+                .chain(self.path.iter().copied())
+                .collect::<Vec<na::Vector2<f64>>>();
+            return super::rounded_corners_path(&points, self.corner_radius);
+        }
+
         let iter = std::iter::once(kurbo::PathEl::MoveTo(self.start.to_kurbo_point())).chain(
             self.path
                 .iter()
@@ -88,6 +98,7 @@ impl Polygon {
         Self {
             start,
             path: Vec::new(),
+            corner_radius: 0.0,
         }
     }
 }
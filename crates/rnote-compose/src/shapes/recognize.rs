@@ -0,0 +1,165 @@
+// Imports
+use super::{Ellipse, Line, Rectangle, Shape};
+use crate::Transform;
+use p2d::bounding_volume::Aabb;
+
+/// The maximum gap between the first and last point relative to the size of the bounds,
+/// for the points to be considered a closed shape.
+const CLOSED_GAP_RATIO: f64 = 0.2;
+
+/// Recognizes the clean shape that the freehand drawn points resemble.
+///
+/// Lines are recognized from open strokes, rectangles and ellipses aligned to the coordinate axes from closed ones.
+/// `tolerance` is the maximum mean deviation of the points from the shape, relative to the size of their bounds.
+pub fn recognize_shape(points: &[na::Vector2<f64>], tolerance: f64) -> Option<Shape> {
+    if points.len() < 2 {
+        return None;
+    }
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let bounds = points.iter().fold(
+        Aabb::new(first.into(), first.into()),
+        |mut bounds, point| {
+            bounds.take_point((*point).into());
+            bounds
+        },
+    );
+    let size = bounds.extents().magnitude();
+    if size <= 0.0 {
+        return None;
+    }
+
+    if (last - first).magnitude() > CLOSED_GAP_RATIO * size {
+        let line = Line::new(first, last);
+        let deviation =
+            mean(points.iter().map(|&p| distance_to_line(&line, p))) / (last - first).magnitude();
+        return (deviation <= tolerance).then_some(Shape::Line(line));
+    }
+
+    let center = bounds.center().coords;
+    let half_extents = bounds.half_extents();
+    if half_extents[0] <= 0.0 || half_extents[1] <= 0.0 {
+        return None;
+    }
+    let rect_deviation = mean(
+        points
+            .iter()
+            .map(|&p| distance_to_rect(center, half_extents, p)),
+    ) / size;
+    let ellipse_deviation = mean(
+        points
+            .iter()
+            .map(|&p| distance_to_ellipse(center, half_extents, p)),
+    ) / size;
+
+    if rect_deviation.min(ellipse_deviation) > tolerance {
+        None
+    } else if rect_deviation < ellipse_deviation {
+        Some(Shape::Rectangle(Rectangle::from_p2d_aabb(bounds)))
+    } else {
+        Some(Shape::Ellipse(Ellipse {
+            radii: half_extents,
+            transform: Transform::new_w_isometry(na::Isometry2::new(center, 0.0)),
+        }))
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, n) = values.fold((0.0, 0), |(sum, n), value| (sum + value, n + 1));
+    if n == 0 {
+        0.0
+    } else {
+        sum / n as f64
+    }
+}
+
+fn distance_to_line(line: &Line, point: na::Vector2<f64>) -> f64 {
+    let v = line.end - line.start;
+    let t = ((point - line.start).dot(&v) / v.magnitude_squared()).clamp(0.0, 1.0);
+    (line.start + v * t - point).magnitude()
+}
+
+fn distance_to_rect(
+    center: na::Vector2<f64>,
+    half_extents: na::Vector2<f64>,
+    point: na::Vector2<f64>,
+) -> f64 {
+    let q = (point - center).abs() - half_extents;
+    let outside = q.sup(&na::Vector2::zeros()).magnitude();
+    let inside = q[0].max(q[1]).min(0.0);
+    (outside + inside).abs()
+}
+
+fn distance_to_ellipse(
+    center: na::Vector2<f64>,
+    radii: na::Vector2<f64>,
+    point: na::Vector2<f64>,
+) -> f64 {
+    let offset = point - center;
+    let dist = offset.magnitude();
+    if dist <= 0.0 {
+        return radii.min();
+    }
+    // the radius of the ellipse in the direction of the point
+    let (cos, sin) = (offset[0] / dist, offset[1] / dist);
+    let radius = radii[0] * radii[1] / ((radii[1] * cos).powi(2) + (radii[0] * sin).powi(2)).sqrt();
+    (dist - radius).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn recognize_lines_rectangles_and_ellipses() {
+        let jitter = |i: usize| if i % 2 == 0 { 1.0 } else { -1.0 };
+        let tolerance = 0.05;
+
+        let line = (0..=20)
+            .map(|i| na::vector![i as f64 * 10.0, 50.0 + jitter(i)])
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            recognize_shape(&line, tolerance),
+            Some(Shape::Line(_))
+        ));
+
+        let mut rect = vec![];
+        for i in 0..10 {
+            let t = i as f64 * 10.0;
+            rect.push(na::vector![t, jitter(i)]);
+        }
+        for i in 0..10 {
+            let t = i as f64 * 10.0;
+            rect.push(na::vector![100.0 + jitter(i), t]);
+        }
+        for i in 0..10 {
+            let t = i as f64 * 10.0;
+            rect.push(na::vector![100.0 - t, 100.0 + jitter(i)]);
+        }
+        for i in 0..=10 {
+            let t = i as f64 * 10.0;
+            rect.push(na::vector![jitter(i), 100.0 - t]);
+        }
+        let Some(Shape::Rectangle(rectangle)) = recognize_shape(&rect, tolerance) else {
+            panic!("the points were not recognized as rectangle");
+        };
+        approx::assert_relative_eq!(rectangle.cuboid.half_extents, na::vector![51.0, 51.0]);
+
+        let ellipse = (0..=40)
+            .map(|i| {
+                let angle = i as f64 / 40.0 * 2.0 * PI;
+                na::vector![100.0 + 80.0 * angle.cos(), 50.0 + 40.0 * angle.sin()]
+            })
+            .collect::<Vec<_>>();
+        let Some(Shape::Ellipse(ellipse)) = recognize_shape(&ellipse, tolerance) else {
+            panic!("the points were not recognized as ellipse");
+        };
+        approx::assert_relative_eq!(ellipse.radii, na::vector![80.0, 40.0], epsilon = 1e-6);
+
+        // a zigzag is not a clean shape
+        let zigzag = (0..=10)
+            .map(|i| na::vector![i as f64 * 10.0, if i % 2 == 0 { 0.0 } else { 40.0 }])
+            .collect::<Vec<_>>();
+        assert!(recognize_shape(&zigzag, tolerance).is_none());
+    }
+}
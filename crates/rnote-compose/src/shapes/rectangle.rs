@@ -17,6 +17,9 @@ pub struct Rectangle {
     #[serde(rename = "transform")]
     /// The transform of the center of the cuboid.
     pub transform: Transform,
+    #[serde(rename = "corner_radius", with = "crate::serialize::f64_dp3")]
+    /// The radius of the rounded corners. Zero for sharp corners.
+    pub corner_radius: f64,
 }
 
 impl Default for Rectangle {
@@ -24,6 +27,7 @@ impl Default for Rectangle {
         Self {
             cuboid: p2d::shape::Cuboid::new(na::Vector2::zeros()),
             transform: Transform::default(),
+            corner_radius: 0.0,
         }
     }
 }
@@ -59,6 +63,13 @@ impl Shapeable for Rectangle {
         let br = self.transform.affine
             * na::point![self.cuboid.half_extents[0], self.cuboid.half_extents[1]];
 
+        if self.corner_radius > 0.0 {
+            return super::rounded_corners_path(
+                &[tl.coords, tr.coords, br.coords, bl.coords],
+                self.corner_radius,
+            );
+        }
+
         kurbo::BezPath::from_vec(vec![
             kurbo::PathEl::MoveTo(tl.coords.to_kurbo_point()),
             kurbo::PathEl::LineTo(tr.coords.to_kurbo_point()),
@@ -89,7 +100,11 @@ impl Rectangle {
         let cuboid = p2d::shape::Cuboid::new(half_extents);
         let transform = Transform::new_w_isometry(na::Isometry2::new(center, 0.0));
 
-        Self { cuboid, transform }
+        Self {
+            cuboid,
+            transform,
+            corner_radius: 0.0,
+        }
     }
 
     /// Construct from corners across from each other.
@@ -100,7 +115,11 @@ impl Rectangle {
         let cuboid = p2d::shape::Cuboid::new(half_extents);
         let transform = Transform::new_w_isometry(na::Isometry2::new(center, 0.0));
 
-        Self { cuboid, transform }
+        Self {
+            cuboid,
+            transform,
+            corner_radius: 0.0,
+        }
     }
 
     /// Construct from bounds.
@@ -109,7 +128,11 @@ impl Rectangle {
         let cuboid = p2d::shape::Cuboid::new(bounds.half_extents());
         let transform = Transform::new_w_isometry(na::Isometry2::new(bounds.center().coords, 0.0));
 
-        Self { cuboid, transform }
+        Self {
+            cuboid,
+            transform,
+            corner_radius: 0.0,
+        }
     }
 
     /// The outlines of the rect.
@@ -151,3 +174,56 @@ impl Rectangle {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Rectangle;
+    use crate::shapes::Shapeable;
+    use approx::assert_relative_eq;
+    use kurbo::Shape;
+
+    #[test]
+    fn rounded_rectangle_corners_are_arcs() {
+        let radius = 10.0;
+        let mut rectangle =
+            Rectangle::from_corners(na::vector![0.0, 0.0], na::vector![100.0, 60.0]);
+        rectangle.corner_radius = radius;
+
+        let mut points = vec![];
+        kurbo::flatten(rectangle.outline_path(), 0.01, |el| match el {
+            kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) => points.push(p),
+            _ => {}
+        });
+        // the centers of the corner arcs
+        let centers = [
+            na::vector![radius, radius],
+            na::vector![100.0 - radius, radius],
+            na::vector![100.0 - radius, 60.0 - radius],
+            na::vector![radius, 60.0 - radius],
+        ];
+        let mut n_corner_points = 0;
+        for p in points {
+            let p = na::vector![p.x, p.y];
+            // points beyond the straight edges lie on a corner arc
+            if let Some(center) = centers.iter().find(|center| {
+                (p.x - center.x).abs() > 1e-6
+                    && (p.y - center.y).abs() > 1e-6
+                    && (p.x < radius || p.x > 100.0 - radius)
+                    && (p.y < radius || p.y > 60.0 - radius)
+                    && (p - *center).magnitude() < radius * 1.5
+            }) {
+                assert_relative_eq!((p - center).magnitude(), radius, epsilon = 0.05);
+                n_corner_points += 1;
+            }
+        }
+        assert!(n_corner_points >= 8);
+
+        // a radius of zero keeps the sharp corners
+        rectangle.corner_radius = 0.0;
+        assert_relative_eq!(
+            rectangle.outline_path().area().abs(),
+            6000.0,
+            epsilon = 1e-9
+        );
+    }
+}
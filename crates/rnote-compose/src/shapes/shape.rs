@@ -1,6 +1,7 @@
 // Imports
 use super::{
-    Arrow, CubicBezier, Ellipse, Line, Polygon, Polyline, QuadraticBezier, Rectangle, Shapeable,
+    Arrow, BezierPath, CompoundPath, CubicBezier, Ellipse, Line, Polygon, Polyline,
+    QuadraticBezier, Rectangle, Shapeable,
 };
 use crate::transform::Transformable;
 use p2d::bounding_volume::Aabb;
@@ -28,12 +29,18 @@ pub enum Shape {
     /// A cubic bezier curve shape.
     #[serde(rename = "cubbez")]
     CubicBezier(CubicBezier),
+    /// A path of connected cubic bezier curves.
+    #[serde(rename = "bezier_path")]
+    BezierPath(BezierPath),
     /// A polyline shape.
     #[serde(rename = "polyline")]
     Polyline(Polyline),
     /// A polygon shape.
     #[serde(rename = "polygon")]
     Polygon(Polygon),
+    /// A compound path shape.
+    #[serde(rename = "compound_path")]
+    CompoundPath(CompoundPath),
 }
 
 impl Default for Shape {
@@ -63,12 +70,18 @@ impl Transformable for Shape {
             Self::CubicBezier(cubbez) => {
                 cubbez.translate(offset);
             }
+            Self::BezierPath(bezier_path) => {
+                bezier_path.translate(offset);
+            }
             Self::Polyline(polyline) => {
                 polyline.translate(offset);
             }
             Self::Polygon(polygon) => {
                 polygon.translate(offset);
             }
+            Self::CompoundPath(compound) => {
+                compound.translate(offset);
+            }
         }
     }
 
@@ -92,12 +105,18 @@ impl Transformable for Shape {
             Self::CubicBezier(cubbez) => {
                 cubbez.rotate(angle, center);
             }
+            Self::BezierPath(bezier_path) => {
+                bezier_path.rotate(angle, center);
+            }
             Self::Polyline(polyline) => {
                 polyline.rotate(angle, center);
             }
             Self::Polygon(polygon) => {
                 polygon.rotate(angle, center);
             }
+            Self::CompoundPath(compound) => {
+                compound.rotate(angle, center);
+            }
         }
     }
 
@@ -121,12 +140,18 @@ impl Transformable for Shape {
             Self::CubicBezier(cubbez) => {
                 cubbez.scale(scale);
             }
+            Self::BezierPath(bezier_path) => {
+                bezier_path.scale(scale);
+            }
             Self::Polyline(polyline) => {
                 polyline.scale(scale);
             }
             Self::Polygon(polygon) => {
                 polygon.scale(scale);
             }
+            Self::CompoundPath(compound) => {
+                compound.scale(scale);
+            }
         }
     }
 }
@@ -140,8 +165,10 @@ impl Shapeable for Shape {
             Self::Ellipse(ellipse) => ellipse.bounds(),
             Self::QuadraticBezier(quadbez) => quadbez.bounds(),
             Self::CubicBezier(cubbez) => cubbez.bounds(),
+            Self::BezierPath(bezier_path) => bezier_path.bounds(),
             Self::Polyline(polyline) => polyline.bounds(),
             Self::Polygon(polygon) => polygon.bounds(),
+            Self::CompoundPath(compound) => compound.bounds(),
         }
     }
 
@@ -153,8 +180,10 @@ impl Shapeable for Shape {
             Self::Ellipse(ellipse) => ellipse.hitboxes(),
             Self::QuadraticBezier(quadbez) => quadbez.hitboxes(),
             Self::CubicBezier(cubbez) => cubbez.hitboxes(),
+            Self::BezierPath(bezier_path) => bezier_path.hitboxes(),
             Self::Polyline(polyline) => polyline.hitboxes(),
             Self::Polygon(polygon) => polygon.hitboxes(),
+            Self::CompoundPath(compound) => compound.hitboxes(),
         }
     }
 
@@ -166,8 +195,10 @@ impl Shapeable for Shape {
             Self::Ellipse(ellipse) => ellipse.outline_path(),
             Self::QuadraticBezier(quadbez) => quadbez.outline_path(),
             Self::CubicBezier(cubbez) => cubbez.outline_path(),
+            Self::BezierPath(bezier_path) => bezier_path.outline_path(),
             Self::Polyline(polyline) => polyline.outline_path(),
             Self::Polygon(polygon) => polygon.outline_path(),
+            Self::CompoundPath(compound) => compound.outline_path(),
         }
     }
 }
@@ -17,7 +17,8 @@ use self::textured::TexturedOptions;
 
 // Imports
 use crate::shapes::{
-    Arrow, CubicBezier, Ellipse, Line, Polygon, Polyline, QuadraticBezier, Rectangle,
+    Arrow, BezierPath, CompoundPath, CubicBezier, Ellipse, Line, Polygon, Polyline,
+    QuadraticBezier, Rectangle,
 };
 use crate::{Color, PenPath, Shape};
 use anyhow::Context;
@@ -109,6 +110,14 @@ impl Style {
         };
     }
 
+    /// The style that every shape can be composed with, converting textured options to smooth ones.
+    pub fn to_shape_style(&self) -> Style {
+        match self {
+            Style::Textured(options) => Style::Smooth(smooth_options_for_textured(options)),
+            style => style.clone(),
+        }
+    }
+
     /// Set the fill color of the style.
     pub fn set_fill_color(&mut self, color: Color) {
         match self {
@@ -227,6 +236,24 @@ impl Composer<Style> for CubicBezier {
     }
 }
 
+impl Composer<Style> for BezierPath {
+    fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
+        match options {
+            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Rough(options) => self.composed_bounds(options),
+            Style::Textured(_options) => unimplemented!(),
+        }
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
+        match options {
+            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Rough(options) => self.draw_composed(cx, options),
+            Style::Textured(_options) => unimplemented!(),
+        }
+    }
+}
+
 impl Composer<Style> for Polyline {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
@@ -263,6 +290,38 @@ impl Composer<Style> for Polygon {
     }
 }
 
+impl Composer<Style> for CompoundPath {
+    fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
+        match options {
+            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Rough(options) => self.composed_bounds(options),
+            Style::Textured(options) => self.composed_bounds(&smooth_options_for_textured(options)),
+        }
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
+        match options {
+            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Rough(options) => self.draw_composed(cx, options),
+            Style::Textured(options) => {
+                self.draw_composed(cx, &smooth_options_for_textured(options))
+            }
+        }
+    }
+}
+
+/// Smooth options with the width, color and pressure curve of the textured options.
+///
+/// Shapes that can't be composed textured fall back to the smooth composer.
+fn smooth_options_for_textured(options: &TexturedOptions) -> SmoothOptions {
+    SmoothOptions {
+        stroke_width: options.stroke_width,
+        stroke_color: options.stroke_color,
+        pressure_curve: options.pressure_curve,
+        ..Default::default()
+    }
+}
+
 impl Composer<Style> for PenPath {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
@@ -290,8 +349,10 @@ impl Composer<Style> for Shape {
             Shape::Ellipse(ellipse) => ellipse.composed_bounds(options),
             Shape::QuadraticBezier(quadratic_bezier) => quadratic_bezier.composed_bounds(options),
             Shape::CubicBezier(cubic_bezier) => cubic_bezier.composed_bounds(options),
+            Shape::BezierPath(bezier_path) => bezier_path.composed_bounds(options),
             Shape::Polyline(polyline) => polyline.composed_bounds(options),
             Shape::Polygon(polygon) => polygon.composed_bounds(options),
+            Shape::CompoundPath(compound) => compound.composed_bounds(options),
         }
     }
 
@@ -303,8 +364,10 @@ impl Composer<Style> for Shape {
             Shape::Ellipse(ellipse) => ellipse.draw_composed(cx, options),
             Shape::QuadraticBezier(quadratic_bezier) => quadratic_bezier.draw_composed(cx, options),
             Shape::CubicBezier(cubic_bezier) => cubic_bezier.draw_composed(cx, options),
+            Shape::BezierPath(bezier_path) => bezier_path.draw_composed(cx, options),
             Shape::Polyline(polyline) => polyline.draw_composed(cx, options),
             Shape::Polygon(polygon) => polygon.draw_composed(cx, options),
+            Shape::CompoundPath(compound) => compound.draw_composed(cx, options),
         }
     }
 }
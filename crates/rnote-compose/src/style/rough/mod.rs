@@ -9,12 +9,16 @@ pub use roughoptions::RoughOptions;
 use super::Composer;
 use crate::ext::Vector2Ext;
 use crate::shapes::{
-    Arrow, CubicBezier, Ellipse, Line, Polygon, Polyline, QuadraticBezier, Rectangle, Shapeable,
+    Arrow, BezierPath, CompoundPath, CubicBezier, Ellipse, Line, Polygon, Polyline,
+    QuadraticBezier, Rectangle, Shapeable,
 };
 use crate::Color;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use roughr::Point2D;
 
+/// The tolerance when flattening compound paths for clipping their fill.
+const COMPOUND_FILL_CLIP_TOLERANCE: f64 = 0.1;
+
 fn generate_roughr_options(options: &RoughOptions) -> roughr::core::Options {
     let mut roughr_options = roughr::core::OptionsBuilder::default();
 
@@ -201,6 +205,23 @@ impl Composer<RoughOptions> for CubicBezier {
     }
 }
 
+impl Composer<RoughOptions> for BezierPath {
+    fn composed_bounds(&self, options: &RoughOptions) -> Aabb {
+        self.bounds()
+            .loosened(options.stroke_width * 0.5 + RoughOptions::ROUGH_BOUNDS_MARGIN)
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &RoughOptions) {
+        cx.save().unwrap();
+
+        for curve in &self.curves {
+            curve.draw_composed(cx, options);
+        }
+
+        cx.restore().unwrap();
+    }
+}
+
 impl Composer<RoughOptions> for Polyline {
     fn composed_bounds(&self, options: &RoughOptions) -> Aabb {
         self.bounds()
@@ -251,6 +272,44 @@ impl Composer<RoughOptions> for Polygon {
     }
 }
 
+impl Composer<RoughOptions> for CompoundPath {
+    fn composed_bounds(&self, options: &RoughOptions) -> Aabb {
+        self.subpaths
+            .iter()
+            .map(|subpath| subpath.composed_bounds(options))
+            .reduce(|acc, bounds| acc.merged(&bounds))
+            .unwrap_or_else(|| self.bounds())
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &RoughOptions) {
+        cx.save().unwrap();
+
+        if options.fill_color.is_some() {
+            // Overlapping subpaths cut holes into the fill, like for the smooth style
+            let fill_options = RoughOptions {
+                stroke_color: None,
+                ..options.clone()
+            };
+            cx.save().unwrap();
+            cx.clip(self.even_odd_region_path(COMPOUND_FILL_CLIP_TOLERANCE));
+            for subpath in &self.subpaths {
+                subpath.draw_composed(cx, &fill_options);
+            }
+            cx.restore().unwrap();
+        }
+
+        let stroke_options = RoughOptions {
+            fill_color: None,
+            ..options.clone()
+        };
+        for subpath in &self.subpaths {
+            subpath.draw_composed(cx, &stroke_options);
+        }
+
+        cx.restore().unwrap();
+    }
+}
+
 impl Composer<RoughOptions> for crate::Shape {
     fn composed_bounds(&self, options: &RoughOptions) -> Aabb {
         match self {
@@ -260,8 +319,10 @@ impl Composer<RoughOptions> for crate::Shape {
             crate::Shape::Ellipse(ellipse) => ellipse.composed_bounds(options),
             crate::Shape::QuadraticBezier(quadbez) => quadbez.composed_bounds(options),
             crate::Shape::CubicBezier(cubbez) => cubbez.composed_bounds(options),
+            crate::Shape::BezierPath(bezier_path) => bezier_path.composed_bounds(options),
             crate::Shape::Polyline(polyline) => polyline.composed_bounds(options),
             crate::Shape::Polygon(polygon) => polygon.composed_bounds(options),
+            crate::Shape::CompoundPath(compound) => compound.composed_bounds(options),
         }
     }
 
@@ -273,8 +334,10 @@ impl Composer<RoughOptions> for crate::Shape {
             crate::Shape::Ellipse(ellipse) => ellipse.draw_composed(cx, options),
             crate::Shape::QuadraticBezier(quadbez) => quadbez.draw_composed(cx, options),
             crate::Shape::CubicBezier(cubbez) => cubbez.draw_composed(cx, options),
+            crate::Shape::BezierPath(bezier_path) => bezier_path.draw_composed(cx, options),
             crate::Shape::Polyline(polyline) => polyline.draw_composed(cx, options),
             crate::Shape::Polygon(polygon) => polygon.draw_composed(cx, options),
+            crate::Shape::CompoundPath(compound) => compound.draw_composed(cx, options),
         }
     }
 }
@@ -2,14 +2,15 @@
 mod smoothoptions;
 
 // Re-exports
-pub use smoothoptions::SmoothOptions;
+pub use smoothoptions::{SmoothOptions, TipShape};
 
 // Imports
 use super::Composer;
 use crate::ext::Vector2Ext;
 use crate::penpath::{self, Segment};
 use crate::shapes::{
-    Arrow, CubicBezier, Ellipse, Line, Polygon, Polyline, QuadraticBezier, Rectangle, Shapeable,
+    Arrow, BezierPath, CompoundPath, CubicBezier, Ellipse, Line, Polygon, Polyline,
+    QuadraticBezier, Rectangle, Shapeable,
 };
 use crate::PenPath;
 use kurbo::Shape;
@@ -142,6 +143,28 @@ impl Composer<SmoothOptions> for CubicBezier {
     }
 }
 
+impl Composer<SmoothOptions> for BezierPath {
+    fn composed_bounds(&self, options: &SmoothOptions) -> Aabb {
+        self.bounds().loosened(options.stroke_width * 0.5)
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &SmoothOptions) {
+        cx.save().unwrap();
+        let path = self.outline_path();
+
+        if let Some(fill_color) = options.fill_color {
+            let fill_brush = cx.solid_brush(fill_color.into());
+            cx.fill(&path, &fill_brush);
+        }
+
+        if let Some(stroke_color) = options.stroke_color {
+            let stroke_brush = cx.solid_brush(stroke_color.into());
+            cx.stroke(path, &stroke_brush, options.stroke_width);
+        }
+        cx.restore().unwrap();
+    }
+}
+
 impl Composer<SmoothOptions> for Polyline {
     fn composed_bounds(&self, options: &SmoothOptions) -> Aabb {
         self.bounds().loosened(options.stroke_width * 0.5)
@@ -209,127 +232,186 @@ impl Composer<SmoothOptions> for Polygon {
     }
 }
 
+impl Composer<SmoothOptions> for CompoundPath {
+    fn composed_bounds(&self, options: &SmoothOptions) -> Aabb {
+        self.subpaths
+            .iter()
+            .map(|subpath| subpath.composed_bounds(options))
+            .reduce(|acc, bounds| acc.merged(&bounds))
+            .unwrap_or_else(|| self.bounds())
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &SmoothOptions) {
+        cx.save().unwrap();
+        let path = self.outline_path();
+
+        if let Some(fill_color) = options.fill_color {
+            let fill_brush = cx.solid_brush(fill_color.into());
+            // Overlapping subpaths cut holes into the fill
+            cx.fill_even_odd(&path, &fill_brush);
+        }
+
+        if let Some(stroke_color) = options.stroke_color {
+            let stroke_brush = cx.solid_brush(stroke_color.into());
+            cx.stroke(path, &stroke_brush, options.stroke_width);
+        }
+        cx.restore().unwrap();
+    }
+}
+
 impl Composer<SmoothOptions> for PenPath {
     fn composed_bounds(&self, options: &SmoothOptions) -> Aabb {
-        self.bounds().loosened(options.stroke_width * 0.5)
+        // The corners of the non-round footprints extend further than half of the stroke width
+        let extent_factor = match options.tip_shape {
+            TipShape::Round => 0.5,
+            TipShape::Flat => (1.0 + SmoothOptions::FLAT_TIP_THICKNESS_FACTOR.powi(2)).sqrt() * 0.5,
+            TipShape::Square => std::f64::consts::FRAC_1_SQRT_2,
+        };
+        self.bounds().loosened(options.stroke_width * extent_factor)
     }
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &SmoothOptions) {
         let Some(color) = options.stroke_color else {
             return;
         };
-        let mut single_pos = true;
-        let mut prev = self.start;
 
         cx.save().unwrap();
 
-        for seg in self.segments.iter() {
-            if seg.end().pos == self.start.pos {
-                continue;
-            } else {
-                single_pos = false;
+        if options.smooth_edges {
+            // Narrower outlines are layered on top of each other, so that the opacity falls off towards the edges.
+            // The layer opacities are chosen so that the accumulated opacity increases linearly towards the core.
+            let n_layers = SmoothOptions::SMOOTH_EDGES_LAYERS;
+            let mut prev_alpha = 0.0;
+            for i in 0..n_layers {
+                let t = i as f64 / (n_layers - 1) as f64;
+                let layer_options = SmoothOptions {
+                    stroke_width: options.stroke_width
+                        * (1.0 - t * (1.0 - SmoothOptions::SMOOTH_EDGES_CORE_FACTOR)),
+                    ..options.clone()
+                };
+                let alpha = color.a * (i + 1) as f64 / n_layers as f64;
+                let mut layer_color = color;
+                layer_color.a = (alpha - prev_alpha) / (1.0 - prev_alpha);
+                prev_alpha = alpha;
+
+                // Each layer is filled at once, else the opacity would stack where the segments overlap
+                draw_penpath_outline(self, cx, &layer_options, layer_color, true);
             }
+        } else {
+            draw_penpath_outline(self, cx, options, color, false);
+        }
 
-            let bez_path = {
-                match seg {
-                    Segment::LineTo { end } => {
-                        let (width_start, width_end) = (
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, prev.pressure),
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, end.pressure),
-                        );
-
-                        let bez_path = compose_lines_variable_width(
-                            &[Line {
-                                start: prev.pos,
-                                end: end.pos,
-                            }],
-                            width_start,
-                            width_end,
-                            options,
-                        );
-
-                        prev = *end;
-                        bez_path
-                    }
-                    Segment::QuadBezTo { cp, end } => {
-                        let (width_start, width_end) = (
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, prev.pressure),
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, end.pressure),
-                        );
-
-                        let quadbez = QuadraticBezier {
-                            start: prev.pos,
-                            cp: *cp,
-                            end: end.pos,
-                        };
-                        let n_splits = penpath::no_subsegments_for_segment_len(
-                            quadbez.outline_path().perimeter(0.25),
-                        )
-                        .max(2);
-                        let lines = quadbez.approx_with_lines(n_splits);
-                        let bez_path =
-                            compose_lines_variable_width(&lines, width_start, width_end, options);
+        cx.restore().unwrap();
+    }
+}
 
-                        prev = *end;
-                        bez_path
-                    }
-                    Segment::CubBezTo { cp1, cp2, end } => {
-                        let (width_start, width_end) = (
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, prev.pressure),
-                            options
-                                .pressure_curve
-                                .apply(options.stroke_width, end.pressure),
-                        );
-
-                        let cubbez = CubicBezier {
-                            start: prev.pos,
-                            cp1: *cp1,
-                            cp2: *cp2,
-                            end: end.pos,
-                        };
-                        let n_splits = penpath::no_subsegments_for_segment_len(
-                            cubbez.outline_path().perimeter(0.25),
-                        )
-                        .max(2);
-                        let lines = cubbez.approx_with_lines(n_splits);
-                        let bez_path =
-                            compose_lines_variable_width(&lines, width_start, width_end, options);
+/// Fills the outline of the pen path, composed with the given options and color.
+///
+/// When `fill_once` is set, the outlines of all segments are filled together with the color for the mean pressure,
+/// so that overlapping segments don't stack their opacity. When the pressure is mapped to the opacity, the segments are
+/// filled together per opacity level, so that their opacity doesn't stack at the joins.
+fn draw_penpath_outline(
+    penpath: &PenPath,
+    cx: &mut impl piet::RenderContext,
+    options: &SmoothOptions,
+    color: crate::Color,
+    fill_once: bool,
+) {
+    let mut single_pos = true;
+    let mut prev = penpath.start;
+    let mut outline = kurbo::BezPath::new();
+    let mut pressure_sum = 0.0;
+    let mut n_segments = 0;
+    let mut opacity_levels = std::collections::BTreeMap::<u32, kurbo::BezPath>::new();
+
+    for seg in penpath.segments.iter() {
+        if seg.end().pos == penpath.start.pos {
+            continue;
+        } else {
+            single_pos = false;
+        }
+        let seg_pressure = (prev.pressure + seg.end().pressure) * 0.5;
+
+        let bez_path = compose_segment_outline(prev, seg, options);
+        prev = seg.end();
+
+        // Outlines for debugging
+        //let stroke_brush = cx.solid_brush(piet::Color::RED);
+        //cx.stroke(bez_path.clone(), &stroke_brush, 0.2);
+
+        if fill_once {
+            // The segment outlines share their winding direction, so the nonzero fill results in their union
+            outline.extend(bez_path);
+            pressure_sum += seg_pressure;
+            n_segments += 1;
+        } else if options.pressure_opacity {
+            let level = (seg_pressure.clamp(0.0, 1.0)
+                * SmoothOptions::PRESSURE_OPACITY_LEVELS as f64)
+                .round() as u32;
+            opacity_levels.entry(level).or_default().extend(bez_path);
+        } else {
+            let seg_color = options.color_for_pressure(color, seg_pressure);
+            cx.fill(bez_path, &Into::<piet::Color>::into(seg_color));
+        }
+    }
+
+    if n_segments > 0 {
+        let outline_color = options.color_for_pressure(color, pressure_sum / n_segments as f64);
+        cx.fill(outline, &Into::<piet::Color>::into(outline_color));
+    }
+    for (level, level_outline) in opacity_levels {
+        let level_color = options.color_for_pressure(
+            color,
+            level as f64 / SmoothOptions::PRESSURE_OPACITY_LEVELS as f64,
+        );
+        cx.fill(level_outline, &Into::<piet::Color>::into(level_color));
+    }
 
-                        prev = *end;
-                        bez_path
+    // Single element/position strokes need special treatment to be rendered
+    if single_pos {
+        let start_width = options.width_for_pressure(penpath.start.pressure);
+        let color =
+            Into::<piet::Color>::into(options.color_for_pressure(color, penpath.start.pressure));
+        match options.tip_shape {
+            TipShape::Round => cx.fill(
+                kurbo::Circle::new(penpath.start.pos.to_kurbo_point(), start_width * 0.5),
+                &color,
+            ),
+            TipShape::Flat | TipShape::Square => {
+                // The corners of the tip footprint
+                let (axis_0, axis_1) = match options.tip_shape {
+                    TipShape::Flat => {
+                        let nib_dir =
+                            na::Vector2::new(options.nib_angle.cos(), options.nib_angle.sin());
+                        (
+                            nib_dir * start_width * 0.5,
+                            nib_dir.orth_unit()
+                                * start_width
+                                * SmoothOptions::FLAT_TIP_THICKNESS_FACTOR
+                                * 0.5,
+                        )
                     }
+                    _ => (
+                        na::Vector2::x() * start_width * 0.5,
+                        na::Vector2::y() * start_width * 0.5,
+                    ),
+                };
+                let corners = [
+                    axis_0 + axis_1,
+                    -axis_0 + axis_1,
+                    -axis_0 - axis_1,
+                    axis_0 - axis_1,
+                ]
+                .map(|offset| (penpath.start.pos + offset).to_kurbo_point());
+                let mut footprint = kurbo::BezPath::new();
+                footprint.move_to(corners[0]);
+                for corner in &corners[1..] {
+                    footprint.line_to(*corner);
                 }
-            };
-
-            // Outlines for debugging
-            //let stroke_brush = cx.solid_brush(piet::Color::RED);
-            //cx.stroke(bez_path.clone(), &stroke_brush, 0.2);
-
-            cx.fill(bez_path, &Into::<piet::Color>::into(color));
-        }
-
-        // Single element/position strokes need special treatment to be rendered
-        if single_pos {
-            let start_width = options
-                .pressure_curve
-                .apply(options.stroke_width, self.start.pressure);
-            cx.fill(
-                kurbo::Circle::new(self.start.pos.to_kurbo_point(), start_width * 0.5),
-                &Into::<piet::Color>::into(color),
-            );
+                footprint.close_path();
+                cx.fill(footprint, &color);
+            }
         }
-
-        cx.restore().unwrap();
     }
 }
 
@@ -342,8 +424,10 @@ impl Composer<SmoothOptions> for crate::Shape {
             crate::Shape::Ellipse(ellipse) => ellipse.composed_bounds(options),
             crate::Shape::QuadraticBezier(quadbez) => quadbez.composed_bounds(options),
             crate::Shape::CubicBezier(cubbez) => cubbez.composed_bounds(options),
+            crate::Shape::BezierPath(bezier_path) => bezier_path.composed_bounds(options),
             crate::Shape::Polyline(polyline) => polyline.composed_bounds(options),
             crate::Shape::Polygon(polygon) => polygon.composed_bounds(options),
+            crate::Shape::CompoundPath(compound) => compound.composed_bounds(options),
         }
     }
 
@@ -355,8 +439,95 @@ impl Composer<SmoothOptions> for crate::Shape {
             crate::Shape::Ellipse(ellipse) => ellipse.draw_composed(cx, options),
             crate::Shape::QuadraticBezier(quadbez) => quadbez.draw_composed(cx, options),
             crate::Shape::CubicBezier(cubbez) => cubbez.draw_composed(cx, options),
+            crate::Shape::BezierPath(bezier_path) => bezier_path.draw_composed(cx, options),
             crate::Shape::Polyline(polyline) => polyline.draw_composed(cx, options),
             crate::Shape::Polygon(polygon) => polygon.draw_composed(cx, options),
+            crate::Shape::CompoundPath(compound) => compound.draw_composed(cx, options),
+        }
+    }
+}
+
+/// The offset from the path to the outline of the tip footprint on the side of the given direction.
+///
+/// The footprints are symmetric, the offset to the other side is the negated offset.
+fn tip_offset(
+    dir_orth_unit: na::Vector2<f64>,
+    width: f64,
+    options: &SmoothOptions,
+) -> na::Vector2<f64> {
+    match options.tip_shape {
+        TipShape::Round | TipShape::Square => dir_orth_unit * width * 0.5,
+        TipShape::Flat => {
+            // The support point of the nib rectangle in the direction
+            let nib_dir = na::Vector2::new(options.nib_angle.cos(), options.nib_angle.sin());
+            let nib_orth = nib_dir.orth_unit();
+            nib_dir * dir_orth_unit.dot(&nib_dir).signum() * width * 0.5
+                + nib_orth
+                    * dir_orth_unit.dot(&nib_orth).signum()
+                    * width
+                    * SmoothOptions::FLAT_TIP_THICKNESS_FACTOR
+                    * 0.5
+        }
+    }
+}
+
+/// Composes the outline of a single pen path segment, which starts at the given element. Must be drawn with only a fill.
+fn compose_segment_outline(
+    start: penpath::Element,
+    seg: &Segment,
+    options: &SmoothOptions,
+) -> kurbo::BezPath {
+    match seg {
+        Segment::LineTo { end } => {
+            let (width_start, width_end) = (
+                options.width_for_pressure(start.pressure),
+                options.width_for_pressure(end.pressure),
+            );
+
+            compose_lines_variable_width(
+                &[Line {
+                    start: start.pos,
+                    end: end.pos,
+                }],
+                width_start,
+                width_end,
+                options,
+            )
+        }
+        Segment::QuadBezTo { cp, end } => {
+            let (width_start, width_end) = (
+                options.width_for_pressure(start.pressure),
+                options.width_for_pressure(end.pressure),
+            );
+
+            let quadbez = QuadraticBezier {
+                start: start.pos,
+                cp: *cp,
+                end: end.pos,
+            };
+            let n_splits =
+                penpath::no_subsegments_for_segment_len(quadbez.outline_path().perimeter(0.25))
+                    .max(2);
+            let lines = quadbez.approx_with_lines(n_splits);
+            compose_lines_variable_width(&lines, width_start, width_end, options)
+        }
+        Segment::CubBezTo { cp1, cp2, end } => {
+            let (width_start, width_end) = (
+                options.width_for_pressure(start.pressure),
+                options.width_for_pressure(end.pressure),
+            );
+
+            let cubbez = CubicBezier {
+                start: start.pos,
+                cp1: *cp1,
+                cp2: *cp2,
+                end: end.pos,
+            };
+            let n_splits =
+                penpath::no_subsegments_for_segment_len(cubbez.outline_path().perimeter(0.25))
+                    .max(2);
+            let lines = cubbez.approx_with_lines(n_splits);
+            compose_lines_variable_width(&lines, width_start, width_end, options)
         }
     }
 }
@@ -366,38 +537,47 @@ fn compose_lines_variable_width(
     lines: &[Line],
     start_width: f64,
     end_width: f64,
-    _options: &SmoothOptions,
+    options: &SmoothOptions,
 ) -> kurbo::BezPath {
     // The the lines variable is ghosted here, to make sure we can only use the filtered
     let lines = lines
         .iter()
         .filter(|line| (line.end - line.start).magnitude() > 0.0)
         .collect::<Vec<&Line>>();
-    let n_lines = lines.len();
-    if n_lines == 0 {
+    if lines.is_empty() {
         return kurbo::BezPath::new();
     }
+    let lines = if options.width_smoothing {
+        subdivide_lines_for_width_smoothing(&lines, start_width, end_width)
+    } else {
+        lines.into_iter().copied().collect::<Vec<Line>>()
+    };
+    let n_lines = lines.len();
+    let width_at = |i: usize| {
+        let t = i as f64 / n_lines as f64;
+        let t = if options.width_smoothing {
+            // smoothstep, so that the width changes continuously across the elements of the path
+            t * t * (3.0 - 2.0 * t)
+        } else {
+            t
+        };
+        start_width + (end_width - start_width) * t
+    };
 
     let (pos_offset_coords, neg_offset_coords): (Vec<_>, Vec<_>) = lines
         .iter()
         .enumerate()
         .flat_map(|(i, line)| {
-            let line_start_width = start_width
-                + (end_width - start_width) * (f64::from(i as i32) / f64::from(n_lines as u32));
-            let line_end_width = start_width
-                + (end_width - start_width) * (f64::from(i as i32 + 1) / f64::from(n_lines as u32));
+            let line_start_width = width_at(i);
+            let line_end_width = width_at(i + 1);
 
             let dir_orth_unit = (line.end - line.start).orth_unit();
+            let start_offset = tip_offset(dir_orth_unit, line_start_width, options);
+            let end_offset = tip_offset(dir_orth_unit, line_end_width, options);
 
             [
-                (
-                    line.start + dir_orth_unit * line_start_width * 0.5,
-                    line.start - dir_orth_unit * line_start_width * 0.5,
-                ),
-                (
-                    line.end + dir_orth_unit * line_end_width * 0.5,
-                    line.end - dir_orth_unit * line_end_width * 0.5,
-                ),
+                (line.start + start_offset, line.start - start_offset),
+                (line.end + end_offset, line.end - end_offset),
             ]
         })
         .unzip();
@@ -416,11 +596,25 @@ fn compose_lines_variable_width(
     // Start cap
     if start_width > 0.0 && start_pos_offset_coord != start_neg_offset_coord {
         bez_path.move_to(start_neg_offset_coord.to_kurbo_point());
-        bez_path.curve_to(
-            (start_neg_offset_coord - start_dir_unit * start_width * (2.0 / 3.0)).to_kurbo_point(),
-            (start_pos_offset_coord - start_dir_unit * start_width * (2.0 / 3.0)).to_kurbo_point(),
-            start_pos_offset_coord.to_kurbo_point(),
-        );
+        match options.tip_shape {
+            TipShape::Round => bez_path.curve_to(
+                (start_neg_offset_coord - start_dir_unit * start_width * (2.0 / 3.0))
+                    .to_kurbo_point(),
+                (start_pos_offset_coord - start_dir_unit * start_width * (2.0 / 3.0))
+                    .to_kurbo_point(),
+                start_pos_offset_coord.to_kurbo_point(),
+            ),
+            TipShape::Square => {
+                bez_path.line_to(
+                    (start_neg_offset_coord - start_dir_unit * start_width * 0.5).to_kurbo_point(),
+                );
+                bez_path.line_to(
+                    (start_pos_offset_coord - start_dir_unit * start_width * 0.5).to_kurbo_point(),
+                );
+                bez_path.line_to(start_pos_offset_coord.to_kurbo_point());
+            }
+            TipShape::Flat => bez_path.line_to(start_pos_offset_coord.to_kurbo_point()),
+        }
     } else {
         bez_path.move_to(start_pos_offset_coord.to_kurbo_point());
     }
@@ -434,11 +628,23 @@ fn compose_lines_variable_width(
 
     // End cap
     if end_width > 0.0 && end_pos_offset_coord != end_neg_offset_coord {
-        bez_path.curve_to(
-            (end_pos_offset_coord + end_dir_unit * end_width * (2.0 / 3.0)).to_kurbo_point(),
-            (end_neg_offset_coord + end_dir_unit * end_width * (2.0 / 3.0)).to_kurbo_point(),
-            end_neg_offset_coord.to_kurbo_point(),
-        );
+        match options.tip_shape {
+            TipShape::Round => bez_path.curve_to(
+                (end_pos_offset_coord + end_dir_unit * end_width * (2.0 / 3.0)).to_kurbo_point(),
+                (end_neg_offset_coord + end_dir_unit * end_width * (2.0 / 3.0)).to_kurbo_point(),
+                end_neg_offset_coord.to_kurbo_point(),
+            ),
+            TipShape::Square => {
+                bez_path.line_to(
+                    (end_pos_offset_coord + end_dir_unit * end_width * 0.5).to_kurbo_point(),
+                );
+                bez_path.line_to(
+                    (end_neg_offset_coord + end_dir_unit * end_width * 0.5).to_kurbo_point(),
+                );
+                bez_path.line_to(end_neg_offset_coord.to_kurbo_point());
+            }
+            TipShape::Flat => bez_path.line_to(end_neg_offset_coord.to_kurbo_point()),
+        }
     } else {
         bez_path.line_to(end_neg_offset_coord.to_kurbo_point());
     }
@@ -454,3 +660,120 @@ fn compose_lines_variable_width(
 
     bez_path
 }
+
+/// Subdivides the lines evenly, so that the eased width changes by at most
+/// [SmoothOptions::WIDTH_SMOOTHING_MAX_STEP] between adjacent outline points.
+fn subdivide_lines_for_width_smoothing(
+    lines: &[&Line],
+    start_width: f64,
+    end_width: f64,
+) -> Vec<Line> {
+    // The steepest slope of smoothstep is 1.5
+    let n_needed = (1.5 * (end_width - start_width).abs() / SmoothOptions::WIDTH_SMOOTHING_MAX_STEP)
+        .ceil() as usize;
+    let n_splits = n_needed.div_ceil(lines.len()).max(1);
+    lines
+        .iter()
+        .flat_map(|line| {
+            (0..n_splits).map(move |i| Line {
+                start: line.start + (line.end - line.start) * (i as f64 / n_splits as f64),
+                end: line.start + (line.end - line.start) * ((i + 1) as f64 / n_splits as f64),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn tip_shapes_on_diagonal_stroke() {
+        let lines = [Line {
+            start: na::vector![0.0, 0.0],
+            end: na::vector![100.0, 100.0],
+        }];
+        let length = 100.0 * std::f64::consts::SQRT_2;
+        let width = 10.0;
+        let area = |tip_shape: TipShape, nib_angle: f64| {
+            let options = SmoothOptions {
+                tip_shape,
+                nib_angle,
+                ..Default::default()
+            };
+            compose_lines_variable_width(&lines, width, width, &options)
+                .area()
+                .abs()
+        };
+
+        let round = area(TipShape::Round, 0.0);
+        let square = area(TipShape::Square, 0.0);
+        // The round caps are approximated with cubic beziers
+        assert_relative_eq!(
+            round,
+            length * width + (width * 0.5).powi(2) * std::f64::consts::PI,
+            epsilon = 2.0
+        );
+        assert_relative_eq!(square, (length + width) * width, epsilon = 1e-6);
+
+        // A flat tip held across the stroke is as wide as the stroke width, along the stroke it only leaves a hairline
+        let flat_across = area(TipShape::Flat, -std::f64::consts::FRAC_PI_4);
+        let flat_along = area(TipShape::Flat, std::f64::consts::FRAC_PI_4);
+        assert_relative_eq!(flat_across, length * width, epsilon = 1e-6);
+        assert_relative_eq!(
+            flat_along,
+            length * width * SmoothOptions::FLAT_TIP_THICKNESS_FACTOR,
+            epsilon = 1e-6
+        );
+        assert!(flat_along < round && round < square);
+    }
+
+    #[test]
+    fn width_smoothing_avoids_width_jumps() {
+        // the pressure jumps between low and high at every join
+        let penpath = PenPath::new_w_segments(
+            penpath::Element::new(na::vector![0.0, 0.0], 0.1),
+            [1.0, 0.1, 1.0, 0.1]
+                .into_iter()
+                .enumerate()
+                .map(|(i, pressure)| Segment::LineTo {
+                    end: penpath::Element::new(na::vector![(i + 1) as f64 * 25.0, 0.0], pressure),
+                }),
+        );
+        // the largest change of the outline offset between adjacent outline points
+        let max_offset_step = |width_smoothing: bool| {
+            let options = SmoothOptions {
+                stroke_width: 20.0,
+                tip_shape: TipShape::Round,
+                width_smoothing,
+                ..Default::default()
+            };
+            let mut offsets = Vec::new();
+            let mut prev = penpath.start;
+            for seg in penpath.segments.iter() {
+                // the caps are curves, so only the offset coordinates of the outline are collected
+                offsets.extend(
+                    compose_segment_outline(prev, seg, &options)
+                        .elements()
+                        .iter()
+                        .filter_map(|el| match el {
+                            kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) if p.y > 0.0 => {
+                                Some((p.x, p.y))
+                            }
+                            _ => None,
+                        }),
+                );
+                prev = seg.end();
+            }
+            offsets.sort_by(|a, b| a.0.total_cmp(&b.0));
+            offsets
+                .windows(2)
+                .map(|w| (w[1].1 - w[0].1).abs())
+                .fold(0.0, f64::max)
+        };
+
+        assert!(max_offset_step(false) > 4.0);
+        assert!(max_offset_step(true) <= SmoothOptions::WIDTH_SMOOTHING_MAX_STEP * 0.5 + 1e-9);
+    }
+}
@@ -1,8 +1,49 @@
 // Imports
 use crate::style::PressureCurve;
 use crate::Color;
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
 
+/// The shape of the tip, the footprint of the pen along the path.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "tip_shape")]
+pub enum TipShape {
+    /// A round tip.
+    #[serde(rename = "round")]
+    Round = 0,
+    /// A flat tip like a calligraphy nib, held at the nib angle.
+    #[serde(rename = "flat")]
+    Flat,
+    /// A square tip like a marker, aligned with the path.
+    #[serde(rename = "square")]
+    Square,
+}
+
+impl Default for TipShape {
+    fn default() -> Self {
+        Self::Round
+    }
+}
+
+impl TryFrom<u32> for TipShape {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value)
+            .with_context(|| format!("TipShape try_from::<u32>() for value {value} failed"))
+    }
+}
+
 /// Options for shapes that can be drawn in a smooth style.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "smooth_options")]
@@ -19,6 +60,33 @@ pub struct SmoothOptions {
     /// Pressure curve.
     #[serde(rename = "pressure_curve")]
     pub pressure_curve: PressureCurve,
+    /// The tip shape when composing pen paths.
+    #[serde(rename = "tip_shape")]
+    pub tip_shape: TipShape,
+    /// The angle of the flat tip in radians, relative to the x-axis.
+    #[serde(rename = "nib_angle", with = "crate::serialize::f64_dp3")]
+    pub nib_angle: f64,
+    /// Map the pen pressure to the opacity of the path segments, in addition to the stroke color opacity.
+    #[serde(rename = "pressure_opacity")]
+    pub pressure_opacity: bool,
+    /// The color at full pressure. When set, the colors of the path segments are mixed from the stroke color at no
+    /// pressure to this color at full pressure, keeping the opacity of the stroke color.
+    #[serde(rename = "pressure_color")]
+    pub pressure_color: Option<Color>,
+    /// Feather the outline of pen paths with a soft falloff towards the edges, like an airbrush.
+    ///
+    /// Independent of the anti-aliasing of the renderer.
+    #[serde(rename = "smooth_edges")]
+    pub smooth_edges: bool,
+    /// Ease the width transitions between the elements of pen paths, instead of changing the width linearly.
+    ///
+    /// Avoids visible facets where the width changes abruptly between sampled elements.
+    #[serde(rename = "width_smoothing")]
+    pub width_smoothing: bool,
+    /// The minimum width of pen paths, so that tapering to low pressures never lets them vanish. Capped at the stroke
+    /// width.
+    #[serde(rename = "min_width", with = "crate::serialize::f64_dp3")]
+    pub min_width: f64,
 }
 
 impl Default for SmoothOptions {
@@ -28,6 +96,53 @@ impl Default for SmoothOptions {
             stroke_color: Some(Color::BLACK),
             fill_color: None,
             pressure_curve: PressureCurve::default(),
+            tip_shape: TipShape::default(),
+            nib_angle: Self::NIB_ANGLE_DEFAULT,
+            pressure_opacity: false,
+            pressure_color: None,
+            smooth_edges: false,
+            width_smoothing: false,
+            min_width: 0.0,
+        }
+    }
+}
+
+impl SmoothOptions {
+    /// The default angle of the flat tip.
+    pub const NIB_ANGLE_DEFAULT: f64 = std::f64::consts::FRAC_PI_4;
+    /// The thickness of the flat tip in relation to the stroke width.
+    pub const FLAT_TIP_THICKNESS_FACTOR: f64 = 0.15;
+    /// The opacity factor at zero pressure when the pressure is mapped to the opacity.
+    pub const PRESSURE_OPACITY_MIN: f64 = 0.1;
+    /// The number of distinct opacity levels of the segments when the pressure is mapped to the opacity.
+    pub const PRESSURE_OPACITY_LEVELS: u32 = 32;
+    /// The number of layered outlines that make up the falloff of smooth edges.
+    pub const SMOOTH_EDGES_LAYERS: usize = 6;
+    /// The width of the fully opaque core of smooth edged paths in relation to the stroke width.
+    pub const SMOOTH_EDGES_CORE_FACTOR: f64 = 0.4;
+    /// The maximum width change between adjacent outline points when the width smoothing is enabled.
+    pub const WIDTH_SMOOTHING_MAX_STEP: f64 = 0.5;
+
+    /// The width drawn at the given pressure, which is never below the minimum width.
+    pub fn width_for_pressure(&self, pressure: f64) -> f64 {
+        self.pressure_curve
+            .apply(self.stroke_width, pressure)
+            .max(self.min_width.min(self.stroke_width))
+    }
+
+    /// The color drawn at the given pressure. Only differs from the given color when the pressure opacity is enabled
+    /// or a pressure color is set.
+    pub fn color_for_pressure(&self, mut color: Color, pressure: f64) -> Color {
+        if let Some(pressure_color) = self.pressure_color {
+            color = Color {
+                a: color.a,
+                ..color.mix(pressure_color, pressure)
+            };
+        }
+        if self.pressure_opacity {
+            color.a *= Self::PRESSURE_OPACITY_MIN
+                + (1.0 - Self::PRESSURE_OPACITY_MIN) * pressure.clamp(0.0, 1.0);
         }
+        color
     }
 }
@@ -18,91 +18,160 @@ use rand_distr::{Distribution, Uniform};
 
 impl Composer<TexturedOptions> for Line {
     fn composed_bounds(&self, options: &TexturedOptions) -> Aabb {
-        self.bounds().loosened(options.stroke_width * 0.5)
+        self.bounds()
+            .loosened(options.stroke_width * options.extent_factor())
     }
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &TexturedOptions) {
-        cx.save().unwrap();
-        let bez_path = {
-            // Return early if line has no length, else Uniform::new() will panic for range with low >= high
-            if (self.end - self.start).magnitude() <= 0.0 {
-                return;
-            };
-
-            let mut rng = crate::utils::new_rng_default_pcg64(options.seed);
-
-            let line_vec = self.end - self.start;
-            let line_rect = self.line_w_width_to_rect(options.stroke_width);
-
-            let area = 4.0 * line_rect.cuboid.half_extents[0] * line_rect.cuboid.half_extents[1];
+        draw_textured_line(self, cx, options, 0.0);
+    }
+}
 
-            // Radii scale with the stroke width, with a weight.
-            let dots_radii = TexturedOptions::DOTS_RADII_DEFAULT
-                * (1.0 + options.stroke_width * TexturedOptions::STROKE_WIDTH_RADII_WEIGHT);
+/// Draws a textured line.
+///
+/// When the options have a dab spacing, `dab_offset` is the distance from the line start to the first dab.
+/// Returns the dab offset for a following connected line.
+fn draw_textured_line(
+    line: &Line,
+    cx: &mut impl piet::RenderContext,
+    options: &TexturedOptions,
+    dab_offset: f64,
+) -> f64 {
+    let line_vec = line.end - line.start;
+    // Return early if line has no length, else Uniform::new() will panic for range with low >= high
+    if line_vec.magnitude() <= 0.0 || options.stroke_width <= 0.0 {
+        return dab_offset;
+    };
+
+    cx.save().unwrap();
+    let mut rng = crate::utils::new_rng_default_pcg64(options.seed);
+    let line_angle = na::Rotation2::rotation_between(&na::Vector2::x(), &line_vec).angle();
+
+    // Radii scale with the stroke width, with a weight.
+    let dots_radii = TexturedOptions::DOTS_RADII_DEFAULT
+        * (1.0 + options.stroke_width * TexturedOptions::STROKE_WIDTH_RADII_WEIGHT);
+
+    // Ranges for randomization
+    let range_dots_rot = -std::f64::consts::FRAC_PI_8..std::f64::consts::FRAC_PI_8;
+    let range_dots_rx = dots_radii[0] * 0.8..dots_radii[0] * 1.25;
+    let range_dots_ry = dots_radii[1] * 0.8..dots_radii[1] * 1.25;
+
+    let distr_dots_rot = Uniform::from(range_dots_rot);
+    let distr_dots_rx = Uniform::from(range_dots_rx);
+    let distr_dots_ry = Uniform::from(range_dots_ry);
+
+    let mut bez_path = kurbo::BezPath::new();
+    let mut add_dot = |rng: &mut rand_pcg::Pcg64, pos: na::Point2<f64>| {
+        let rotation_angle = line_angle + distr_dots_rot.sample(rng);
+        let radii = na::vector![distr_dots_rx.sample(rng), distr_dots_ry.sample(rng)];
+
+        let ellipse = kurbo::Ellipse::new(
+            kurbo::Point {
+                x: pos[0],
+                y: pos[1],
+            },
+            radii.to_kurbo_vec(),
+            rotation_angle,
+        );
+
+        bez_path.extend(ellipse.to_path(0.1));
+    };
+
+    let dab_distance = options.spacing * options.stroke_width;
+    let next_dab_offset = if dab_distance > 0.0 {
+        // Stamp the dots in dabs along the line
+        let (dab_positions, next_dab_offset) = dab_positions(line, dab_distance, dab_offset);
+        let dab_rotation = na::Rotation2::new(line_angle);
+
+        let n_dots_per_dab = (options.stroke_width.powi(2) * 0.1 * options.density).round() as i32;
+
+        for dab_pos in dab_positions {
+            let (dab_pos, dab_width) = scatter_dab(&mut rng, dab_pos, options);
+            let half_width = dab_width * 0.5;
+            let range = -half_width..half_width;
+            let distr_x = Uniform::from(range.clone());
+
+            for _ in 0..n_dots_per_dab {
+                let x_pos = distr_x.sample(&mut rng);
+                let y_pos = options
+                    .distribution
+                    .sample_for_range_symmetrical_clipped(&mut rng, range.clone());
 
-            // Ranges for randomization
-            let range_x = -line_rect.cuboid.half_extents[0]..line_rect.cuboid.half_extents[0];
-            let range_y = -line_rect.cuboid.half_extents[1]..line_rect.cuboid.half_extents[1];
-            let range_dots_rot = -std::f64::consts::FRAC_PI_8..std::f64::consts::FRAC_PI_8;
-            let range_dots_rx = dots_radii[0] * 0.8..dots_radii[0] * 1.25;
-            let range_dots_ry = dots_radii[1] * 0.8..dots_radii[1] * 1.25;
+                add_dot(
+                    &mut rng,
+                    (dab_pos + dab_rotation * na::vector![x_pos, y_pos]).into(),
+                );
+            }
+        }
 
-            let distr_x = Uniform::from(range_x);
-            let distr_dots_rot = Uniform::from(range_dots_rot);
-            let distr_dots_rx = Uniform::from(range_dots_rx);
-            let distr_dots_ry = Uniform::from(range_dots_ry);
+        next_dab_offset
+    } else {
+        // Scatter the dots continuously along the line
+        let line_rect = line.line_w_width_to_rect(options.stroke_width);
+        let area = 4.0 * line_rect.cuboid.half_extents[0] * line_rect.cuboid.half_extents[1];
+        let range_x = -line_rect.cuboid.half_extents[0]..line_rect.cuboid.half_extents[0];
+        let range_y = -line_rect.cuboid.half_extents[1]..line_rect.cuboid.half_extents[1];
+        let distr_x = Uniform::from(range_x);
+
+        let n_dots = (area * 0.1 * options.density).round() as i32;
+
+        for _ in 0..n_dots {
+            let x_pos = distr_x.sample(&mut rng);
+            let y_pos = options
+                .distribution
+                .sample_for_range_symmetrical_clipped(&mut rng, range_y.clone());
+
+            add_dot(
+                &mut rng,
+                line_rect.transform.affine * na::point![x_pos, y_pos],
+            );
+        }
 
-            let n_dots = (area * 0.1 * options.density).round() as i32;
+        dab_offset
+    };
 
-            let mut bez_path = kurbo::BezPath::new();
+    if let Some(fill_color) = options.stroke_color {
+        let fill_brush = cx.solid_brush(fill_color.into());
+        cx.fill(bez_path, &fill_brush);
+    }
+    cx.restore().unwrap();
 
-            for _ in 0..n_dots {
-                let x_pos = distr_x.sample(&mut rng);
-                let y_pos = options
-                    .distribution
-                    .sample_for_range_symmetrical_clipped(&mut rng, range_y.clone());
-
-                let pos = line_rect.transform.affine * na::point![x_pos, y_pos];
-
-                let rotation_angle = na::Rotation2::rotation_between(&na::Vector2::x(), &line_vec)
-                    .angle()
-                    + distr_dots_rot.sample(&mut rng);
-                let radii = na::vector![
-                    distr_dots_rx.sample(&mut rng),
-                    distr_dots_ry.sample(&mut rng)
-                ];
-
-                let ellipse = kurbo::Ellipse::new(
-                    kurbo::Point {
-                        x: pos[0],
-                        y: pos[1],
-                    },
-                    radii.to_kurbo_vec(),
-                    rotation_angle,
-                );
+    next_dab_offset
+}
 
-                bez_path.extend(ellipse.to_path(0.1));
-            }
+/// The positions of the dabs along the line, spaced by `dab_distance`.
+///
+/// `offset` is the distance from the line start to the first dab.
+/// Returns the positions and the offset for a following connected line.
+fn dab_positions(line: &Line, dab_distance: f64, offset: f64) -> (Vec<na::Vector2<f64>>, f64) {
+    let line_vec = line.end - line.start;
+    let length = line_vec.magnitude();
+    if length <= 0.0 || dab_distance <= 0.0 {
+        return (vec![], offset);
+    }
 
-            bez_path
-        };
+    let direction = line_vec / length;
+    let mut positions = vec![];
+    let mut dist = offset.max(0.0);
 
-        if let Some(fill_color) = options.stroke_color {
-            let fill_brush = cx.solid_brush(fill_color.into());
-            cx.fill(bez_path, &fill_brush);
-        }
-        cx.restore().unwrap();
+    while dist < length {
+        positions.push(line.start + direction * dist);
+        dist += dab_distance;
     }
+
+    (positions, dist - length)
 }
 
 impl Composer<TexturedOptions> for PenPath {
     fn composed_bounds(&self, options: &TexturedOptions) -> Aabb {
-        self.bounds().loosened(options.stroke_width)
+        self.bounds()
+            .loosened(options.stroke_width * options.extent_factor() * 2.0)
     }
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &TexturedOptions) {
         let mut options = options.clone();
         let mut prev = self.start;
+        let mut dab_offset = 0.0;
 
         cx.save().unwrap();
 
@@ -125,7 +194,7 @@ impl Composer<TexturedOptions> for PenPath {
                         .pressure_curve
                         .apply(options.stroke_width, (prev.pressure + end.pressure) * 0.5);
 
-                    line.draw_composed(cx, &options);
+                    dab_offset = draw_textured_line(&line, cx, &options, dab_offset);
                     prev = *end;
                 }
                 Segment::QuadBezTo { end, .. } => {
@@ -140,7 +209,7 @@ impl Composer<TexturedOptions> for PenPath {
                         .pressure_curve
                         .apply(options.stroke_width, (prev.pressure + end.pressure) * 0.5);
 
-                    line.draw_composed(cx, &options);
+                    dab_offset = draw_textured_line(&line, cx, &options, dab_offset);
                     prev = *end;
                 }
                 Segment::CubBezTo { end, .. } => {
@@ -155,7 +224,7 @@ impl Composer<TexturedOptions> for PenPath {
                         .pressure_curve
                         .apply(options.stroke_width, (prev.pressure + end.pressure) * 0.5);
 
-                    line.draw_composed(cx, &options);
+                    dab_offset = draw_textured_line(&line, cx, &options, dab_offset);
                     prev = *end;
                 }
             }
@@ -166,3 +235,104 @@ impl Composer<TexturedOptions> for PenPath {
         cx.restore().unwrap();
     }
 }
+
+/// Randomly offsets the dab position and varies its width, according to the scatter and jitter of the options.
+///
+/// Returns the new position and width.
+fn scatter_dab(
+    rng: &mut rand_pcg::Pcg64,
+    pos: na::Vector2<f64>,
+    options: &TexturedOptions,
+) -> (na::Vector2<f64>, f64) {
+    let scatter = options
+        .scatter
+        .clamp(TexturedOptions::SCATTER_MIN, TexturedOptions::SCATTER_MAX);
+    let jitter = options
+        .jitter
+        .clamp(TexturedOptions::JITTER_MIN, TexturedOptions::JITTER_MAX);
+
+    // Only sample when needed, so that strokes without scatter and jitter are not altered.
+    let pos = if scatter > 0.0 {
+        let distr = Uniform::from(-1.0..1.0);
+        pos + na::vector![distr.sample(rng), distr.sample(rng)] * scatter * options.stroke_width
+    } else {
+        pos
+    };
+    let width = if jitter > 0.0 {
+        options.stroke_width * (1.0 + Uniform::from(-jitter..jitter).sample(rng))
+    } else {
+        options.stroke_width
+    };
+
+    (pos, width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dab_positions, scatter_dab, TexturedOptions};
+    use crate::shapes::Line;
+
+    #[test]
+    fn dab_count_scales_inversely_with_spacing() {
+        let line = Line {
+            start: na::vector![0.0, 0.0],
+            end: na::vector![100.0, 0.0],
+        };
+        let stroke_width = 10.0;
+
+        let (dabs_dense, _) = dab_positions(&line, 0.5 * stroke_width, 0.0);
+        let (dabs_sparse, _) = dab_positions(&line, 1.0 * stroke_width, 0.0);
+        let (dabs_sparser, offset) = dab_positions(&line, 2.0 * stroke_width, 0.0);
+
+        assert_eq!(dabs_dense.len(), 20);
+        assert_eq!(dabs_sparse.len(), 10);
+        assert_eq!(dabs_sparser.len(), 5);
+        assert_eq!(offset, 0.0);
+
+        // Spacing is maintained across connected lines
+        let (dabs_split_first, offset) = dab_positions(
+            &Line {
+                start: na::vector![0.0, 0.0],
+                end: na::vector![55.0, 0.0],
+            },
+            stroke_width,
+            0.0,
+        );
+        let (dabs_split_second, _) = dab_positions(
+            &Line {
+                start: na::vector![55.0, 0.0],
+                end: na::vector![100.0, 0.0],
+            },
+            stroke_width,
+            offset,
+        );
+        assert_eq!(dabs_split_first.len() + dabs_split_second.len(), 10);
+    }
+
+    #[test]
+    fn scatter_is_reproducible_by_seed() {
+        let options = TexturedOptions {
+            spacing: 0.5,
+            scatter: 1.0,
+            jitter: 0.5,
+            ..Default::default()
+        };
+        let scattered = |seed: u64| {
+            let mut rng = crate::utils::new_rng_default_pcg64(Some(seed));
+            (0..10)
+                .map(|i| scatter_dab(&mut rng, na::vector![i as f64 * 3.0, 0.0], &options))
+                .collect::<Vec<(na::Vector2<f64>, f64)>>()
+        };
+
+        assert_eq!(scattered(1), scattered(1));
+        assert_ne!(scattered(1), scattered(2));
+
+        // Without scatter and jitter the dabs are not altered
+        let options = TexturedOptions::default();
+        let mut rng = crate::utils::new_rng_default_pcg64(Some(1));
+        assert_eq!(
+            scatter_dab(&mut rng, na::vector![1.0, 2.0], &options),
+            (na::vector![1.0, 2.0], options.stroke_width)
+        );
+    }
+}
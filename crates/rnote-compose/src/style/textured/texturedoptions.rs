@@ -20,6 +20,18 @@ pub struct TexturedOptions {
     /// Amount of dots of the texture per 10x10 area.
     #[serde(rename = "density", with = "crate::serialize::f64_dp3")]
     pub density: f64,
+    /// Distance between the dabs along the path, as fraction of the stroke width.
+    ///
+    /// Lower spacing results in smoother and heavier strokes, higher spacing in visible dots.
+    /// When set to zero, the texture is scattered continuously along the path without dabs.
+    #[serde(rename = "spacing", with = "crate::serialize::f64_dp3")]
+    pub spacing: f64,
+    /// Randomly offsets the dabs from the path, as fraction of the stroke width.
+    #[serde(rename = "scatter", with = "crate::serialize::f64_dp3")]
+    pub scatter: f64,
+    /// Randomly varies the size of the dabs, as fraction of the stroke width.
+    #[serde(rename = "jitter", with = "crate::serialize::f64_dp3")]
+    pub jitter: f64,
     /// Texture dots distribution type.
     #[serde(rename = "distribution")]
     pub distribution: TexturedDotsDistribution,
@@ -34,6 +46,9 @@ impl Default for TexturedOptions {
             seed: None,
             stroke_width: 6.0,
             density: 5.0,
+            spacing: 0.0,
+            scatter: 0.0,
+            jitter: 0.0,
             stroke_color: Some(Color::BLACK),
             distribution: TexturedDotsDistribution::default(),
             pressure_curve: PressureCurve::default(),
@@ -50,6 +65,28 @@ impl TexturedOptions {
     pub const DENSITY_MIN: f64 = 0.1;
     /// Maximum dots density.
     pub const DENSITY_MAX: f64 = 100.0;
+    /// Minimum dab spacing.
+    pub const SPACING_MIN: f64 = 0.0;
+    /// Maximum dab spacing.
+    pub const SPACING_MAX: f64 = 10.0;
+    /// Minimum dab scatter.
+    pub const SCATTER_MIN: f64 = 0.0;
+    /// Maximum dab scatter.
+    pub const SCATTER_MAX: f64 = 5.0;
+    /// Minimum dab jitter.
+    pub const JITTER_MIN: f64 = 0.0;
+    /// Maximum dab jitter.
+    pub const JITTER_MAX: f64 = 0.9;
+
+    /// How far the texture extends from the path, as fraction of the stroke width.
+    pub(super) fn extent_factor(&self) -> f64 {
+        if self.spacing > 0.0 {
+            (1.0 + self.jitter.clamp(Self::JITTER_MIN, Self::JITTER_MAX)) * 0.5
+                + self.scatter.clamp(Self::SCATTER_MIN, Self::SCATTER_MAX)
+        } else {
+            0.5
+        }
+    }
 
     /// Advances the seed.
     pub fn advance_seed(&mut self) {
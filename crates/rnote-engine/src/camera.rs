@@ -21,6 +21,60 @@ pub enum NudgeDirection {
     NorthWest,
 }
 
+/// The range that the camera zoom is constrained to.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "zoom_limits")]
+pub struct ZoomLimits {
+    #[serde(rename = "min")]
+    pub min: f64,
+    #[serde(rename = "max")]
+    pub max: f64,
+}
+
+impl Default for ZoomLimits {
+    fn default() -> Self {
+        Self {
+            min: Camera::ZOOM_MIN,
+            max: Camera::ZOOM_MAX,
+        }
+    }
+}
+
+impl ZoomLimits {
+    /// The lowest configurable limit, so that the zoom never degenerates to zero.
+    pub const LOWER_BOUND: f64 = 0.01;
+    /// The highest configurable limit.
+    pub const UPPER_BOUND: f64 = 100.0;
+
+    /// New zoom limits, constrained to [Self::LOWER_BOUND] and [Self::UPPER_BOUND].
+    ///
+    /// Falls back to the default limits for non-finite values.
+    pub fn new(min: f64, max: f64) -> Self {
+        if !min.is_finite() || !max.is_finite() {
+            return Self::default();
+        }
+        let min = min.clamp(Self::LOWER_BOUND, Self::UPPER_BOUND);
+        let max = max.clamp(Self::LOWER_BOUND, Self::UPPER_BOUND);
+        Self {
+            min: min.min(max),
+            max: max.max(min),
+        }
+    }
+
+    /// Clamp the zoom to the limits. A NaN zoom becomes the default zoom clamped to the limits.
+    pub fn clamp(&self, zoom: f64) -> f64 {
+        if zoom.is_nan() {
+            return Camera::ZOOM_DEFAULT.clamp(self.min, self.max);
+        }
+        zoom.clamp(self.min, self.max)
+    }
+
+    /// Whether the zoom lies within the limits.
+    pub fn contains(&self, zoom: f64) -> bool {
+        (self.min..=self.max).contains(&zoom)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "camera")]
 pub struct Camera {
@@ -33,6 +87,11 @@ pub struct Camera {
     /// The camera zoom, origin at (0.0, 0.0).
     #[serde(rename = "zoom")]
     zoom: f64,
+    /// The rotation of the view in radians, around the center of the surface.
+    ///
+    /// Is purely a view property, the strokes are not rotated in the document.
+    #[serde(rename = "view_rotation", with = "rnote_compose::serialize::f64_dp3")]
+    view_rotation: f64,
     /// The temporary zoom. Is used to overlay the "permanent" zoom.
     #[serde(skip)]
     temporary_zoom: f64,
@@ -42,6 +101,13 @@ pub struct Camera {
     /// This value could become a non-integer value in the future, so it is stored as float.
     #[serde(skip)]
     scale_factor: f64,
+    /// The limits the zoom is constrained to. Is part of the engine config.
+    #[serde(skip)]
+    zoom_limits: ZoomLimits,
+    /// Whether pen input gets transformed relative to the view center, see [Camera::surface_to_doc].
+    /// Is part of the engine config.
+    #[serde(skip)]
+    rebase_input: bool,
 
     #[serde(skip)]
     zoom_task_handle: Option<crate::tasks::OneOffTaskHandle>,
@@ -53,8 +119,11 @@ impl Default for Camera {
             offset: na::vector![-Self::OVERSHOOT_HORIZONTAL, -Self::OVERSHOOT_VERTICAL],
             size: na::vector![800.0, 600.0],
             zoom: 1.0,
+            view_rotation: 0.0,
             temporary_zoom: 1.0,
             scale_factor: 1.0,
+            zoom_limits: ZoomLimits::default(),
+            rebase_input: false,
             zoom_task_handle: None,
         }
     }
@@ -66,6 +135,9 @@ impl CloneConfig for Camera {
             offset: self.offset,
             size: self.size,
             zoom: self.zoom,
+            view_rotation: self.view_rotation,
+            zoom_limits: self.zoom_limits,
+            rebase_input: self.rebase_input,
             ..Default::default()
         }
     }
@@ -83,7 +155,7 @@ impl Camera {
     pub const OVERSHOOT_VERTICAL: f64 = 96.0;
 
     pub fn with_zoom(mut self, zoom: f64) -> Self {
-        self.zoom = zoom.clamp(Self::ZOOM_MIN, Self::ZOOM_MAX);
+        self.zoom = self.zoom_limits.clamp(zoom);
         self
     }
 
@@ -96,11 +168,23 @@ impl Camera {
         self
     }
 
+    pub fn with_view_rotation(mut self, view_rotation: f64) -> Self {
+        self.view_rotation = view_rotation.rem_euclid(std::f64::consts::TAU);
+        self
+    }
+
     /// The current viewport offset in surface coordinate space.
     pub fn offset(&self) -> na::Vector2<f64> {
         self.offset
     }
 
+    /// The change of the offset that pans the view by the given delta in surface coordinates.
+    ///
+    /// The offset is applied before the view rotation, so the delta gets rotated back.
+    pub fn offset_delta_for_surface_delta(&self, delta: na::Vector2<f64>) -> na::Vector2<f64> {
+        na::Rotation2::new(-self.view_rotation) * delta
+    }
+
     pub fn set_offset(&mut self, offset: na::Vector2<f64>, doc: &Document) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
         let (lower, upper) = self.offset_lower_upper(doc);
@@ -166,11 +250,50 @@ impl Camera {
     /// Set the permanent zoom.
     pub fn zoom_to(&mut self, zoom: f64) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
-        self.zoom = zoom.clamp(Self::ZOOM_MIN, Self::ZOOM_MAX);
+        self.zoom = self.zoom_limits.clamp(zoom);
         widget_flags.zoomed = true;
         widget_flags
     }
 
+    /// The limits the zoom is constrained to.
+    pub fn zoom_limits(&self) -> ZoomLimits {
+        self.zoom_limits
+    }
+
+    /// Set the limits the zoom is constrained to. The current zoom gets clamped to them.
+    pub fn set_zoom_limits(&mut self, zoom_limits: ZoomLimits) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.zoom_limits = ZoomLimits::new(zoom_limits.min, zoom_limits.max);
+        if !self.zoom_limits.contains(self.zoom) {
+            widget_flags |= self.zoom_temporarily_to(1.0) | self.zoom_to(self.zoom);
+        }
+        widget_flags
+    }
+
+    /// Whether pen input gets transformed relative to the view center.
+    pub fn rebase_input(&self) -> bool {
+        self.rebase_input
+    }
+
+    pub fn set_rebase_input(&mut self, rebase_input: bool) {
+        self.rebase_input = rebase_input;
+    }
+
+    /// The zoom at which the bounds fit into the viewport, constrained to the zoom limits.
+    pub fn fit_zoom(&self, bounds: Aabb) -> f64 {
+        let extents = bounds.extents();
+        self.zoom_limits
+            .clamp((self.size[0] / extents[0]).min(self.size[1] / extents[1]))
+    }
+
+    /// Zoom so that the bounds fit into the viewport, and center the viewport on them.
+    pub fn fit_view(&mut self, bounds: Aabb) -> WidgetFlags {
+        let zoom = self.fit_zoom(bounds);
+        self.zoom_temporarily_to(1.0)
+            | self.zoom_to(zoom)
+            | self.set_viewport_center(bounds.center().coords)
+    }
+
     /// The temporary zoom, to be overlaid on the surface when zooming with a timeout.
     pub fn temporary_zoom(&self) -> f64 {
         self.temporary_zoom
@@ -179,8 +302,14 @@ impl Camera {
     /// Set the temporary zoom.
     pub fn zoom_temporarily_to(&mut self, temporary_zoom: f64) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
-        self.temporary_zoom =
-            temporary_zoom.clamp(Camera::ZOOM_MIN / self.zoom, Camera::ZOOM_MAX / self.zoom);
+        self.temporary_zoom = if temporary_zoom.is_nan() {
+            1.0
+        } else {
+            temporary_zoom.clamp(
+                self.zoom_limits.min / self.zoom,
+                self.zoom_limits.max / self.zoom,
+            )
+        };
         widget_flags.zoomed_temporarily = true;
         widget_flags
     }
@@ -226,6 +355,22 @@ impl Camera {
         self.zoom * self.temporary_zoom
     }
 
+    /// The rotation of the view in radians, around the center of the surface.
+    pub fn view_rotation(&self) -> f64 {
+        self.view_rotation
+    }
+
+    /// Set the rotation of the view in radians, around the center of the surface.
+    ///
+    /// Background and content rendering then need to be updated.
+    pub fn set_view_rotation(&mut self, view_rotation: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.view_rotation = view_rotation.rem_euclid(std::f64::consts::TAU);
+        widget_flags.redraw = true;
+        widget_flags.view_modified = true;
+        widget_flags
+    }
+
     /// The scaling factor for generating bitmap images with the current permanent zoom.
     ///
     /// Takes the scale factor in account
@@ -248,13 +393,10 @@ impl Camera {
     }
 
     /// The viewport in document coordinate space.
+    ///
+    /// When the view is rotated, this is the bounding box of the visible area.
     pub fn viewport(&self) -> Aabb {
-        let total_zoom = self.total_zoom();
-
-        Aabb::new_positive(
-            (self.offset / total_zoom).into(),
-            ((self.offset + self.size) / total_zoom).into(),
-        )
+        self.transform_inv_bounds(Aabb::new_positive(na::point![0.0, 0.0], self.size.into()))
     }
 
     /// The current viewport center in document coordinate space.
@@ -274,13 +416,60 @@ impl Camera {
     }
 
     /// Transform Aabb from document coords to surface coords.
+    ///
+    /// When the view is rotated, this is the bounding box of the transformed bounds.
     pub fn transform_bounds(&self, bounds: Aabb) -> Aabb {
-        bounds.scale(self.total_zoom()).translate(-self.offset)
+        if self.view_rotation == 0.0 {
+            return bounds.scale(self.total_zoom()).translate(-self.offset);
+        }
+        let transform = self.transform();
+        Aabb::from_points(
+            &bounds
+                .vertices()
+                .map(|vertex| transform.transform_point(&vertex)),
+        )
     }
 
     /// Transform Aabb from surface coords to document coords.
+    ///
+    /// When the view is rotated, this is the bounding box of the transformed bounds.
     pub fn transform_inv_bounds(&self, bounds: Aabb) -> Aabb {
-        bounds.translate(self.offset).scale(1.0 / self.total_zoom())
+        if self.view_rotation == 0.0 {
+            return bounds.translate(self.offset).scale(1.0 / self.total_zoom());
+        }
+        let transform_inv = self.transform().inverse();
+        Aabb::from_points(
+            &bounds
+                .vertices()
+                .map(|vertex| transform_inv.transform_point(&vertex)),
+        )
+    }
+
+    /// Transform a position from surface coords to document coords.
+    ///
+    /// When rebasing the input is enabled, the position is transformed relative to a local origin near the view
+    /// center, which has integer document coordinates. The offset is rebased onto it before dividing by the zoom,
+    /// so that the math stays near the origin and only the final addition of the local origin rounds at the
+    /// magnitude of the document coordinates. This avoids the precision loss of the inverted transform at high zoom
+    /// far away from the document origin.
+    pub fn surface_to_doc(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        if !self.rebase_input {
+            return (self.transform().inverse() * na::Point2::from(pos)).coords;
+        }
+        let total_zoom = self.total_zoom();
+        let center = self.size * 0.5;
+        let local_origin = ((center + self.offset) / total_zoom).map(f64::round);
+        // fused, so that the offset relative to the local origin is not rounded at the magnitude of the offset
+        let local_offset = local_origin.zip_map(&self.offset, |origin, offset| {
+            origin.mul_add(-total_zoom, offset)
+        }) + center;
+        local_origin
+            + (na::Rotation2::new(-self.view_rotation) * (pos - center) + local_offset) / total_zoom
+    }
+
+    /// Transform a position from document coords to surface coords.
+    pub fn doc_to_surface(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        (self.transform() * na::Point2::from(pos)).coords
     }
 
     /// The transform from document coords to surface coords.
@@ -288,10 +477,14 @@ impl Camera {
     /// To get the inverse, call `.inverse()`.
     pub fn transform(&self) -> na::Affine2<f64> {
         let total_zoom = self.total_zoom();
+        let center = self.size * 0.5;
 
         na::try_convert(
             // LHS is applied onto RHS, so the order is scaling by zoom -> Translation by offset
-            na::Translation2::from(-self.offset).to_homogeneous()
+            // -> Rotation around the surface center
+            na::Translation2::from(center).to_homogeneous()
+                * na::Rotation2::new(self.view_rotation).to_homogeneous()
+                * na::Translation2::from(-center - self.offset).to_homogeneous()
                 * na::Scale2::from(na::Vector2::from_element(total_zoom)).to_homogeneous(),
         )
         .unwrap()
@@ -305,11 +498,17 @@ impl Camera {
     #[cfg(feature = "ui")]
     pub fn transform_for_gtk_snapshot(&self) -> gtk4::gsk::Transform {
         let total_zoom = self.total_zoom();
+        let center = self.size * 0.5;
 
         gtk4::gsk::Transform::new()
             .translate(&gtk4::graphene::Point::new(
-                -self.offset[0] as f32,
-                -self.offset[1] as f32,
+                center[0] as f32,
+                center[1] as f32,
+            ))
+            .rotate(self.view_rotation.to_degrees() as f32)
+            .translate(&gtk4::graphene::Point::new(
+                (-center[0] - self.offset[0]) as f32,
+                (-center[1] - self.offset[1]) as f32,
             ))
             .scale(total_zoom as f32, total_zoom as f32)
     }
@@ -371,8 +570,85 @@ impl Camera {
 
 #[cfg(test)]
 mod tests {
+    use crate::camera::ZoomLimits;
     use crate::Camera;
     use approx::assert_relative_eq;
+    use p2d::bounding_volume::Aabb;
+
+    #[test]
+    fn rebased_input_is_accurate_far_from_origin() {
+        let zoom = 4.0;
+        let size = na::vector![800.0, 600.0];
+        // the view center is located at these document coordinates
+        let view_center_doc = na::vector![1.0e9, -3.0e9];
+        let offset = view_center_doc * zoom - size * 0.5;
+        let delta = na::vector![0.125, -0.0625];
+
+        let mut camera = Camera::default()
+            .with_size(size)
+            .with_zoom(zoom)
+            .with_offset(offset);
+        camera.set_rebase_input(true);
+        assert_relative_eq!(
+            camera.surface_to_doc(size * 0.5 + delta * zoom),
+            view_center_doc + delta,
+            epsilon = 1e-6
+        );
+
+        // a quarter turn of the view
+        let camera = camera.with_view_rotation(std::f64::consts::FRAC_PI_2);
+        assert_relative_eq!(
+            camera.surface_to_doc(size * 0.5 + delta * zoom),
+            view_center_doc + na::vector![delta[1], -delta[0]],
+            epsilon = 1e-6
+        );
+        // rebasing does not change the mapping
+        assert_relative_eq!(
+            camera.doc_to_surface(camera.surface_to_doc(na::vector![10.0, 20.0])),
+            na::vector![10.0, 20.0],
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn rebased_input_error_at_huge_offsets() {
+        // a zoom that is not a power of two, so that dividing by it rounds
+        let zoom = 3.0;
+        let size = na::vector![800.0, 600.0];
+        let offset = na::vector![3.0e12 + 1.0, -9.0e12 + 2.0];
+        // the exact document coordinates are `numerator / (8 * zoom)`, split into the integer part and the remainder
+        let exact = |pos: na::Vector2<f64>, i: usize| {
+            let numerator = ((offset[i] + pos[i]) * 8.0) as i128;
+            (
+                numerator.div_euclid(24),
+                numerator.rem_euclid(24) as f64 / 24.0,
+            )
+        };
+        let error = |doc: f64, (int, rem): (i128, f64)| ((doc - int as f64) - rem).abs();
+
+        let mut camera = Camera::default()
+            .with_size(size)
+            .with_zoom(zoom)
+            .with_offset(offset);
+        for pos in [
+            na::vector![0.0, 0.0],
+            na::vector![400.125, 300.375],
+            na::vector![799.875, 0.625],
+            na::vector![17.5, 599.25],
+        ] {
+            camera.set_rebase_input(false);
+            let plain = camera.surface_to_doc(pos);
+            camera.set_rebase_input(true);
+            let rebased = camera.surface_to_doc(pos);
+            for i in 0..2 {
+                let exact_doc = exact(pos, i);
+                let half_ulp = (exact_doc.0 as f64).abs() * f64::EPSILON * 0.5;
+                // the rebased position only rounds once at the magnitude of the document coordinates
+                assert!(error(rebased[i], exact_doc) <= half_ulp + 1e-9);
+                assert!(error(rebased[i], exact_doc) <= error(plain[i], exact_doc) + 1e-9);
+            }
+        }
+    }
 
     #[test]
     fn transform_vec() {
@@ -408,4 +684,80 @@ mod tests {
         assert_relative_eq!(viewport.mins, mins);
         assert_relative_eq!(viewport.maxs, maxs);
     }
+
+    #[test]
+    fn view_rotation_round_trip() {
+        let camera = Camera::default()
+            .with_zoom(1.5)
+            .with_offset(na::vector![40.0, -20.0])
+            .with_size(na::vector![800.0, 600.0])
+            .with_view_rotation(std::f64::consts::FRAC_PI_6);
+
+        let surface_pos = na::vector![120.0, 350.0];
+        let doc_pos = camera.surface_to_doc(surface_pos);
+        assert_relative_eq!(camera.doc_to_surface(doc_pos), surface_pos, epsilon = 1e-9);
+
+        // The surface center stays fixed when rotating
+        let unrotated = camera.clone().with_view_rotation(0.0);
+        assert_relative_eq!(
+            camera.surface_to_doc(camera.size() * 0.5),
+            unrotated.surface_to_doc(unrotated.size() * 0.5),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(camera.viewport_center(), unrotated.viewport_center());
+        // other surface points don't
+        assert!((doc_pos - unrotated.surface_to_doc(surface_pos)).magnitude() > 1.0);
+    }
+
+    #[test]
+    fn panning_rotated_view_follows_surface_delta() {
+        let camera = Camera::default()
+            .with_zoom(1.5)
+            .with_offset(na::vector![40.0, -20.0])
+            .with_size(na::vector![800.0, 600.0])
+            .with_view_rotation(std::f64::consts::FRAC_PI_3);
+        let doc_pos = na::vector![100.0, 50.0];
+        let delta = na::vector![30.0, -12.0];
+
+        // dragging the content by the delta on the surface moves the offset the opposite way
+        let panned = camera
+            .clone()
+            .with_offset(camera.offset() - camera.offset_delta_for_surface_delta(delta));
+        assert_relative_eq!(
+            panned.doc_to_surface(doc_pos),
+            camera.doc_to_surface(doc_pos) + delta,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn fit_view_stays_within_zoom_limits() {
+        let mut camera = Camera::default().with_size(na::vector![800.0, 600.0]);
+        let _ = camera.set_zoom_limits(ZoomLimits::new(0.5, 4.0));
+
+        // tiny and degenerate content would zoom in infinitely
+        for bounds in [
+            Aabb::new(na::point![10.0, 10.0], na::point![10.1, 10.1]),
+            Aabb::new(na::point![10.0, 10.0], na::point![10.0, 10.0]),
+        ] {
+            let _ = camera.fit_view(bounds);
+            assert_relative_eq!(camera.total_zoom(), 4.0);
+            assert_relative_eq!(camera.viewport_center(), bounds.center().coords);
+        }
+
+        // huge content would zoom out infinitely
+        let _ = camera.fit_view(Aabb::new(na::point![0.0, 0.0], na::point![1e9, 1e9]));
+        assert_relative_eq!(camera.total_zoom(), 0.5);
+
+        // content that fits within the limits
+        let _ = camera.fit_view(Aabb::new(na::point![0.0, 0.0], na::point![400.0, 600.0]));
+        assert_relative_eq!(camera.total_zoom(), 1.0);
+
+        // narrowing the limits clamps the current zoom
+        let _ = camera.set_zoom_limits(ZoomLimits::new(2.0, 3.0));
+        assert_relative_eq!(camera.total_zoom(), 2.0);
+        // invalid limits fall back to sane values
+        assert_eq!(ZoomLimits::new(f64::NAN, 1.0), ZoomLimits::default());
+        assert_eq!(ZoomLimits::new(0.0, 1e6), ZoomLimits::new(0.01, 100.0));
+    }
 }
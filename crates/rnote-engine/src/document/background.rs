@@ -59,6 +59,7 @@ const QUARTER_SQRT_THREE: f64 = SQRT_THREE / 4_f64;
 
 fn gen_hline_pattern(
     bounds: Aabb,
+    origin: na::Vector2<f64>,
     spacing: f64,
     color: Color,
     line_width: f64,
@@ -70,8 +71,8 @@ fn gen_hline_pattern(
     let pattern = element::Definitions::new().add(
         element::Pattern::new()
             .set("id", pattern_id.as_str())
-            .set("x", 0_f64)
-            .set("y", 0_f64)
+            .set("x", origin[0])
+            .set("y", origin[1])
             .set("width", bounds.extents()[0])
             .set("height", spacing)
             .set("patternUnits", "userSpaceOnUse")
@@ -100,6 +101,7 @@ fn gen_hline_pattern(
 
 fn gen_grid_pattern(
     bounds: Aabb,
+    origin: na::Vector2<f64>,
     row_spacing: f64,
     column_spacing: f64,
     color: Color,
@@ -112,8 +114,8 @@ fn gen_grid_pattern(
     let pattern = element::Definitions::new().add(
         element::Pattern::new()
             .set("id", pattern_id.as_str())
-            .set("x", 0_f64)
-            .set("y", 0_f64)
+            .set("x", origin[0])
+            .set("y", origin[1])
             .set("width", column_spacing)
             .set("height", row_spacing)
             .set("patternUnits", "userSpaceOnUse")
@@ -151,6 +153,7 @@ fn gen_grid_pattern(
 
 fn gen_dots_pattern(
     bounds: Aabb,
+    origin: na::Vector2<f64>,
     row_spacing: f64,
     column_spacing: f64,
     color: Color,
@@ -161,8 +164,8 @@ fn gen_dots_pattern(
     let pattern = element::Definitions::new().add(
         element::Pattern::new()
             .set("id", pattern_id.as_str())
-            .set("x", 0_f64)
-            .set("y", 0_f64)
+            .set("x", origin[0])
+            .set("y", origin[1])
             .set("width", column_spacing)
             .set("height", row_spacing)
             .set("patternUnits", "userSpaceOnUse")
@@ -196,6 +199,7 @@ fn calc_width_iso_pattern(spacing: f64) -> f64 {
 
 fn gen_iso_grid_pattern(
     bounds: Aabb,
+    origin: na::Vector2<f64>,
     spacing: f64,
     color: Color,
     line_width: f64,
@@ -211,8 +215,8 @@ fn gen_iso_grid_pattern(
     let pattern = element::Definitions::new().add(
         element::Pattern::new()
             .set("id", pattern_id.as_str())
-            .set("x", 0_f64)
-            .set("y", 0_f64)
+            .set("x", origin[0])
+            .set("y", origin[1])
             .set("width", pattern_width)
             .set("height", spacing)
             .set("patternUnits", "userSpaceOnUse")
@@ -268,6 +272,7 @@ fn gen_iso_grid_pattern(
 
 fn gen_iso_dots_pattern(
     bounds: Aabb,
+    origin: na::Vector2<f64>,
     spacing: f64,
     color: Color,
     hexagon_height: f64,
@@ -301,8 +306,8 @@ fn gen_iso_dots_pattern(
     let pattern = element::Definitions::new().add(
         element::Pattern::new()
             .set("id", pattern_id.as_str())
-            .set("x", 0_f64)
-            .set("y", 0_f64)
+            .set("x", origin[0])
+            .set("y", origin[1])
             .set("width", pattern_width)
             .set("height", spacing)
             .set("patternUnits", "userSpaceOnUse")
@@ -345,6 +350,12 @@ pub struct Background {
     pub pattern_size: na::Vector2<f64>,
     #[serde(rename = "pattern_color")]
     pub pattern_color: Color,
+    /// The origin the pattern is aligned to, for example to start the lines below a top margin.
+    #[serde(
+        rename = "pattern_origin",
+        with = "rnote_compose::serialize::na_vector2_f64_dp3"
+    )]
+    pub pattern_origin: na::Vector2<f64>,
 }
 
 impl Default for Background {
@@ -354,6 +365,7 @@ impl Default for Background {
             pattern: PatternStyle::default(),
             pattern_size: Self::PATTERN_SIZE_DEFAULT,
             pattern_color: Self::PATTERN_COLOR_DEFAULT,
+            pattern_origin: na::Vector2::zeros(),
         }
     }
 }
@@ -446,6 +458,7 @@ impl Background {
                 PatternStyle::Lines => {
                     svg_group = svg_group.add(gen_hline_pattern(
                         bounds,
+                        self.pattern_origin,
                         self.pattern_size[1],
                         pattern_color,
                         Self::LINE_WIDTH,
@@ -454,6 +467,7 @@ impl Background {
                 PatternStyle::Grid => {
                     svg_group = svg_group.add(gen_grid_pattern(
                         bounds,
+                        self.pattern_origin,
                         self.pattern_size[1],
                         self.pattern_size[0],
                         pattern_color,
@@ -463,6 +477,7 @@ impl Background {
                 PatternStyle::Dots => {
                     svg_group = svg_group.add(gen_dots_pattern(
                         bounds,
+                        self.pattern_origin,
                         self.pattern_size[1],
                         self.pattern_size[0],
                         pattern_color,
@@ -472,6 +487,7 @@ impl Background {
                 PatternStyle::IsometricGrid => {
                     svg_group = svg_group.add(gen_iso_grid_pattern(
                         bounds,
+                        self.pattern_origin,
                         self.pattern_size[1],
                         pattern_color,
                         Self::LINE_WIDTH,
@@ -480,6 +496,7 @@ impl Background {
                 PatternStyle::IsometricDots => {
                     svg_group = svg_group.add(gen_iso_dots_pattern(
                         bounds,
+                        self.pattern_origin,
                         self.pattern_size[1],
                         pattern_color,
                         Self::HEXAGON_HEIGHT,
@@ -494,10 +511,25 @@ impl Background {
         Ok(render::Svg { svg_data, bounds })
     }
 
-    pub(crate) fn gen_tile_image(&self, image_scale: f64) -> Result<render::Image, anyhow::Error> {
-        let tile_bounds = Aabb::new(na::point![0.0, 0.0], self.tile_size().into());
+    /// The offset of the tiles from the document origin, so that tiles start at the pattern origin.
+    pub(crate) fn tile_offset(&self) -> na::Vector2<f64> {
+        let tile_size = self.tile_size();
+        na::vector![
+            self.pattern_origin[0].rem_euclid(tile_size[0]),
+            self.pattern_origin[1].rem_euclid(tile_size[1])
+        ]
+    }
+
+    /// Generate the image of a single tile that starts at the tile offset.
+    pub(crate) fn gen_tile_image(
+        &self,
+        image_scale: f64,
+        aa_quality: render::AaQuality,
+    ) -> Result<render::Image, anyhow::Error> {
+        let tile_offset = self.tile_offset();
+        let tile_bounds = Aabb::new(tile_offset.into(), (tile_offset + self.tile_size()).into());
         self.gen_svg(tile_bounds, true, false)?
-            .gen_image(image_scale)
+            .gen_image_aa(image_scale, aa_quality)
     }
 
     pub(crate) fn draw_to_cairo(
@@ -512,3 +544,38 @@ impl Background {
         background_svg.draw_to_cairo(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Background, PatternStyle};
+    use p2d::bounding_volume::Aabb;
+
+    #[test]
+    fn pattern_origin_shifts_lines() {
+        let bounds = Aabb::new(na::point![0.0, 0.0], na::point![64.0, 64.0]);
+        // The rows that contain a pattern line, detected by the red channel that differs from the white background
+        let line_rows = |background: &Background| {
+            let image = background
+                .gen_svg(bounds, true, false)
+                .unwrap()
+                .gen_image(1.0)
+                .unwrap();
+            let stride = image.pixel_width as usize * 4;
+            (0..image.pixel_height as usize)
+                .filter(|y| image.data[y * stride + 5 * 4] < 250)
+                .collect::<Vec<usize>>()
+        };
+        let mut background = Background {
+            pattern: PatternStyle::Lines,
+            ..Default::default()
+        };
+        assert_eq!(line_rows(&background), vec![0, 32]);
+
+        background.pattern_origin = na::vector![0.0, 10.0];
+        assert_eq!(line_rows(&background), vec![10, 42]);
+
+        // The tiles start at the origin, wrapped into the tile size
+        background.pattern_origin = na::vector![0.0, 330.0];
+        assert_eq!(background.tile_offset(), na::vector![0.0, 330.0 % 128.0]);
+    }
+}
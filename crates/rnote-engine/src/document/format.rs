@@ -194,6 +194,8 @@ impl Format {
     pub const DPI_MIN: f64 = 1.0;
     pub const DPI_MAX: f64 = 5000.0;
     pub const DPI_DEFAULT: f64 = 96.0;
+    /// The amount of typographic points in an inch.
+    pub const PT_PER_INCH: f64 = 72.0;
 
     pub const BORDER_COLOR_DEFAULT: piet::Color = color::GNOME_BRIGHTS[2];
 
@@ -223,6 +225,32 @@ impl Format {
         self.dpi = dpi.clamp(Self::DPI_MIN, Self::DPI_MAX);
     }
 
+    /// The amount of document coordinate units that make up one physical millimeter.
+    pub fn pixels_per_mm(&self) -> f64 {
+        self.dpi / MeasureUnit::AMOUNT_MM_IN_INCH
+    }
+
+    /// Set the mapping from document coordinates to physical units. Gets clamped to the allowed DPI range.
+    pub fn set_pixels_per_mm(&mut self, pixels_per_mm: f64) {
+        self.set_dpi(pixels_per_mm * MeasureUnit::AMOUNT_MM_IN_INCH);
+    }
+
+    /// Convert a length in document coordinates to a physical length in the given unit.
+    pub fn px_to_unit(&self, value: f64, unit: MeasureUnit) -> f64 {
+        MeasureUnit::convert_measurement(value, MeasureUnit::Px, self.dpi, unit, self.dpi)
+    }
+
+    /// Convert a physical length in the given unit to a length in document coordinates.
+    pub fn unit_to_px(&self, value: f64, unit: MeasureUnit) -> f64 {
+        MeasureUnit::convert_measurement(value, unit, self.dpi, MeasureUnit::Px, self.dpi)
+    }
+
+    /// The scale factor from document coordinates to typographic points (1/72 inch),
+    /// used by targets that have physical dimensions like Pdf.
+    pub fn px_to_pt_scale(&self) -> f64 {
+        Self::PT_PER_INCH / self.dpi
+    }
+
     pub fn orientation(&self) -> Orientation {
         self.orientation
     }
@@ -239,3 +267,23 @@ impl Format {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, MeasureUnit};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn px_to_mm() {
+        let mut format = Format::default();
+        assert_relative_eq!(format.px_to_unit(96.0, MeasureUnit::Mm), 25.4);
+        assert_relative_eq!(
+            format.unit_to_px(10.0, MeasureUnit::Cm),
+            377.952_755_905_511_8
+        );
+
+        format.set_pixels_per_mm(10.0);
+        assert_relative_eq!(format.dpi(), 254.0);
+        assert_relative_eq!(format.px_to_unit(100.0, MeasureUnit::Mm), 10.0);
+    }
+}
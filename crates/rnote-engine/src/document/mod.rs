@@ -11,7 +11,7 @@ use crate::{Camera, CloneConfig, StrokeStore, WidgetFlags};
 use core::fmt::Display;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::ext::{AabbExt, Vector2Ext};
-use rnote_compose::{Color, SplitOrder};
+use rnote_compose::{Color, PenEvent, SplitOrder};
 use serde::{Deserialize, Serialize};
 
 #[derive(
@@ -88,6 +88,44 @@ impl Layout {
     }
 }
 
+/// How the pages of the fixed-size layout are adjusted when the content changes.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "page_layout")]
+pub enum PageLayout {
+    /// Keep all pages, even when they become empty.
+    #[serde(rename = "keep_pages")]
+    KeepPages,
+    /// Remove empty pages at the end of the document automatically.
+    #[serde(rename = "trim_trailing_pages")]
+    TrimTrailingPages,
+}
+
+impl Default for PageLayout {
+    fn default() -> Self {
+        Self::KeepPages
+    }
+}
+
+impl TryFrom<u32> for PageLayout {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!("PageLayout try_from::<u32>() for value {} failed", value)
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "document")]
 pub struct Document {
@@ -107,6 +145,14 @@ pub struct Document {
     pub layout: Layout,
     #[serde(rename = "snap_positions")]
     pub snap_positions: bool,
+    /// Clamp the pen input to the document bounds, so that nothing can be drawn outside of the pages.
+    ///
+    /// Only applies to the fixed-size layout, other layouts expand instead.
+    #[serde(rename = "clamp_input")]
+    pub clamp_input: bool,
+    /// Whether empty trailing pages are kept or trimmed in the fixed-size layout.
+    #[serde(rename = "page_layout")]
+    pub page_layout: PageLayout,
 }
 
 impl Default for Document {
@@ -120,6 +166,8 @@ impl Default for Document {
             background: Background::default(),
             layout: Layout::default(),
             snap_positions: false,
+            clamp_input: false,
+            page_layout: PageLayout::default(),
         }
     }
 }
@@ -139,6 +187,30 @@ impl Document {
         b: 0.0,
         a: 0.35,
     };
+    /// The maximum width and height of the document.
+    pub const DIMENSION_MAX: f64 = 1_000_000.0;
+
+    /// The dimensions of the document.
+    pub fn dimensions(&self) -> na::Vector2<f64> {
+        na::vector![self.width, self.height]
+    }
+
+    /// Set the dimensions of the document.
+    ///
+    /// Errors when the width or height is not positive or larger than [Self::DIMENSION_MAX].
+    pub fn set_dimensions(&mut self, width: f64, height: f64) -> anyhow::Result<()> {
+        for (name, value) in [("width", width), ("height", height)] {
+            if !value.is_finite() || value <= 0.0 || value > Self::DIMENSION_MAX {
+                return Err(anyhow::anyhow!(
+                    "Document {name} {value} is invalid, must be positive and not larger than {}.",
+                    Self::DIMENSION_MAX
+                ));
+            }
+        }
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
 
     pub(crate) fn bounds(&self) -> Aabb {
         Aabb::new(
@@ -208,7 +280,10 @@ impl Document {
         let mut widget_flags = WidgetFlags::default();
         match self.layout {
             Layout::FixedSize => {
-                // do not resize in fixed size mode, if wanted use resize_to_fit_content() for it.
+                // do not grow in fixed size mode, if wanted use resize_to_fit_content() for it.
+                if self.page_layout == PageLayout::TrimTrailingPages {
+                    widget_flags.resize |= self.trim_trailing_pages_fixed_size(store);
+                }
             }
             Layout::ContinuousVertical => {
                 widget_flags.resize |= self.resize_doc_continuous_vertical_layout(store);
@@ -273,6 +348,21 @@ impl Document {
         true
     }
 
+    /// Removes the empty pages at the end of the document in the fixed-size layout, keeping at least one page.
+    ///
+    /// Returns true if a resize happened.
+    #[must_use = "Determines if the resize flag should be set"]
+    fn trim_trailing_pages_fixed_size(&mut self, store: &StrokeStore) -> bool {
+        let format_height = self.format.height();
+        let content_height =
+            ((store.calc_height().max(1.0)) / format_height).ceil() * format_height;
+        if content_height >= self.height {
+            return false;
+        }
+        self.height = content_height;
+        true
+    }
+
     /// Returns true if a resize happened.
     #[must_use = "Determines if the resize flag should be set"]
     fn resize_doc_fixed_size_layout(&mut self, store: &StrokeStore) -> bool {
@@ -402,6 +492,30 @@ impl Document {
         )
     }
 
+    /// Clamp the position to the document bounds when `clamp_input` is enabled and the layout is fixed-size.
+    ///
+    /// If not, the original coordinates are returned.
+    pub(crate) fn clamp_position(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        if !self.clamp_input || self.layout != Layout::FixedSize {
+            return pos;
+        }
+        let bounds = self.bounds();
+        pos.sup(&bounds.mins.coords).inf(&bounds.maxs.coords)
+    }
+
+    /// Clamp the positions of the elements of the pen event to the document bounds when `clamp_input` is enabled.
+    pub(crate) fn clamp_pen_event(&self, mut event: PenEvent) -> PenEvent {
+        match &mut event {
+            PenEvent::Down { element, .. }
+            | PenEvent::Up { element, .. }
+            | PenEvent::Proximity { element, .. } => {
+                element.pos = self.clamp_position(element.pos);
+            }
+            PenEvent::KeyPressed { .. } | PenEvent::Text { .. } | PenEvent::Cancel => {}
+        }
+        event
+    }
+
     /// Snap the position to the document and pattern grid when `snap_positions` is enabled.
     ///
     /// If not, the original coordinates are returned.
@@ -409,6 +523,7 @@ impl Document {
         const DOCUMENT_SNAP_DIST: f64 = 10.;
         let doc_format_size = self.format.size();
         let pattern_size = self.background.pattern_size;
+        let pattern_origin = self.background.pattern_origin;
 
         if !self.snap_positions {
             return pos;
@@ -419,7 +534,7 @@ impl Document {
             grid_size.component_mul(&grid_pos.round())
         };
 
-        let pos_snapped_pattern = snap_to_grid(pos, pattern_size);
+        let pos_snapped_pattern = snap_to_grid(pos - pattern_origin, pattern_size) + pattern_origin;
         let pos_snapped_document = snap_to_grid(pos, doc_format_size);
 
         let mut pos_snapped = pos_snapped_pattern;
@@ -467,3 +582,31 @@ fn set_dimensions_checked(
     }
     check
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Document, Layout};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn clamp_input_moves_to_nearest_edge() {
+        let mut document = Document {
+            width: 100.0,
+            height: 200.0,
+            clamp_input: true,
+            ..Default::default()
+        };
+        let outside = na::vector![150.0, -20.0];
+        let inside = na::vector![50.0, 50.0];
+
+        // Infinite layouts don't clamp
+        assert_relative_eq!(document.clamp_position(outside), outside);
+
+        document.layout = Layout::FixedSize;
+        assert_relative_eq!(document.clamp_position(outside), na::vector![100.0, 0.0]);
+        assert_relative_eq!(document.clamp_position(inside), inside);
+
+        document.clamp_input = false;
+        assert_relative_eq!(document.clamp_position(outside), outside);
+    }
+}
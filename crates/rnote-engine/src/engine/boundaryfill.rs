@@ -0,0 +1,191 @@
+// Imports
+use crate::store::StrokeKey;
+use crate::strokes::{ShapeStroke, Stroke};
+use crate::{Engine, WidgetFlags};
+use geo::ConvexHull;
+use rnote_compose::shapes::{Polygon, Shape};
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::{Color, Style};
+use std::time::Instant;
+
+impl Engine {
+    /// The maximum distance between the ends of strokes that are still joined when filling a boundary.
+    pub const BOUNDARY_FILL_GAP_TOLERANCE: f64 = 20.0;
+    /// The tolerance when flattening the curves of the boundary strokes.
+    const BOUNDARY_FILL_FLATTEN_TOLERANCE: f64 = 0.25;
+
+    /// Fill the area enclosed by the strokes, which only need to roughly form a boundary.
+    ///
+    /// The strokes get chained together end to end, bridging gaps up to [Engine::BOUNDARY_FILL_GAP_TOLERANCE].
+    /// When they can't be chained into a closed loop, their convex hull is filled instead.
+    /// The filled shape is inserted behind the strokes, as a single history entry.
+    ///
+    /// Returns the key of the inserted stroke, None when the strokes don't enclose an area.
+    pub fn fill_selection_boundary(
+        &mut self,
+        keys: &[StrokeKey],
+        color: Color,
+    ) -> (Option<StrokeKey>, WidgetFlags) {
+        let polylines = keys
+            .iter()
+            .filter_map(|&key| {
+                self.store
+                    .get_stroke_ref(key)?
+                    .flattened_outline(Self::BOUNDARY_FILL_FLATTEN_TOLERANCE)
+            })
+            .collect::<Vec<Vec<na::Vector2<f64>>>>();
+        let Some(boundary) = boundary_polygon(polylines, Self::BOUNDARY_FILL_GAP_TOLERANCE) else {
+            return (None, WidgetFlags::default());
+        };
+        // Inserted on the lowest layer of the strokes, behind all strokes on it
+        let Some(layer) = keys.iter().filter_map(|&key| self.store.layer(key)).min() else {
+            return (None, WidgetFlags::default());
+        };
+        let z_index = keys
+            .iter()
+            .filter(|&&key| self.store.layer(key) == Some(layer))
+            .filter_map(|&key| self.store.z_index(key))
+            .min()
+            .unwrap_or(0)
+            .saturating_sub(1);
+
+        let stroke = Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Polygon(Polygon {
+                start: boundary[0],
+                path: boundary[1..].to_vec(),
+                corner_radius: 0.0,
+            }),
+            Style::Smooth(SmoothOptions {
+                stroke_color: None,
+                fill_color: Some(color),
+                ..Default::default()
+            }),
+        ));
+        let key = self.store.insert_stroke(stroke, Some(layer));
+        self.store.set_z_index(key, z_index);
+        self.store.regenerate_rendering_for_stroke(
+            key,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+
+        let widget_flags = self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport();
+        (Some(key), widget_flags)
+    }
+}
+
+/// Chain the polylines end to end into a closed polygon, bridging gaps up to the tolerance.
+///
+/// Falls back to the convex hull of all points when they can't be chained. None when there are less than three
+/// points.
+fn boundary_polygon(
+    mut polylines: Vec<Vec<na::Vector2<f64>>>,
+    gap_tolerance: f64,
+) -> Option<Vec<na::Vector2<f64>>> {
+    let hull = |polylines: &[Vec<na::Vector2<f64>>]| {
+        let points = polylines
+            .iter()
+            .flatten()
+            .map(|p| geo::Point::new(p[0], p[1]))
+            .collect::<geo::MultiPoint<f64>>();
+        let mut hull = points
+            .convex_hull()
+            .exterior()
+            .coords()
+            .map(|c| na::vector![c.x, c.y])
+            .collect::<Vec<na::Vector2<f64>>>();
+        // geo rings are closed by repeating the first coordinate
+        hull.pop();
+        (hull.len() >= 3).then_some(hull)
+    };
+    if polylines.is_empty() {
+        return None;
+    }
+
+    let all = polylines.clone();
+    let mut chain = polylines.swap_remove(0);
+    while !polylines.is_empty() {
+        let end = *chain.last()?;
+        // The nearest polyline start or end, and whether the polyline needs to be reversed
+        let (i, reverse, dist) = polylines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, polyline)| {
+                [
+                    (i, false, (polyline[0] - end).magnitude()),
+                    (i, true, (polyline[polyline.len() - 1] - end).magnitude()),
+                ]
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))?;
+        if dist > gap_tolerance {
+            return hull(&all);
+        }
+        let mut next = polylines.swap_remove(i);
+        if reverse {
+            next.reverse();
+        }
+        chain.extend(next);
+    }
+
+    if chain.len() < 3 || (chain[0] - chain[chain.len() - 1]).magnitude() > gap_tolerance {
+        return hull(&all);
+    }
+    Some(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::tests::polyline_stroke;
+    use crate::strokes::Stroke;
+    use crate::Engine;
+    use approx::assert_relative_eq;
+    use geo::{Area, Contains};
+    use rnote_compose::shapes::Shapeable;
+    use rnote_compose::Color;
+
+    #[test]
+    fn fill_boundary_bridges_gaps() {
+        let mut engine = Engine::default();
+        // a square outline drawn with three strokes, leaving small gaps at the corners
+        let keys = [
+            [na::vector![0.0, 0.0], na::vector![100.0, 0.0]].as_slice(),
+            &[na::vector![100.0, 100.0], na::vector![100.0, 5.0]],
+            &[
+                na::vector![95.0, 100.0],
+                na::vector![0.0, 100.0],
+                na::vector![0.0, 8.0],
+            ],
+        ]
+        .map(|points| engine.store.insert_stroke(polyline_stroke(points), None));
+
+        let (fill_key, _) = engine.fill_selection_boundary(&keys, Color::BLACK);
+        let fill_key = fill_key.unwrap();
+        let Some(Stroke::ShapeStroke(fill)) = engine.store.get_stroke_ref(fill_key) else {
+            panic!("the fill is not a shape stroke");
+        };
+        let polygon =
+            crate::utils::bezpath_to_geo_polygon(&fill.shape.outline_path(), 0.1).unwrap();
+        assert_relative_eq!(polygon.unsigned_area(), 10000.0, epsilon = 1e-6);
+        assert!(polygon.contains(&geo::point!(x: 50.0, y: 50.0)));
+        assert!(engine.store.z_index(fill_key) < engine.store.z_index(keys[0]));
+        assert_eq!(engine.store.stroke_keys_as_rendered()[0], fill_key);
+
+        // strokes that are too far apart are filled with their convex hull
+        let apart = [
+            [na::vector![0.0, 200.0], na::vector![100.0, 200.0]].as_slice(),
+            &[na::vector![50.0, 300.0], na::vector![60.0, 300.0]],
+        ]
+        .map(|points| engine.store.insert_stroke(polyline_stroke(points), None));
+        let (hull_key, _) = engine.fill_selection_boundary(&apart, Color::BLACK);
+        let hull = engine.store.get_stroke_ref(hull_key.unwrap()).unwrap();
+        assert_relative_eq!(
+            crate::utils::bezpath_to_geo_polygon(&hull.outline_path(), 0.1)
+                .unwrap()
+                .unsigned_area(),
+            0.5 * (100.0 + 10.0) * 100.0,
+            epsilon = 1e-6
+        );
+    }
+}
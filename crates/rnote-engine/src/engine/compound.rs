@@ -0,0 +1,213 @@
+// Imports
+use crate::store::StrokeKey;
+use crate::strokes::{ShapeStroke, Stroke};
+use crate::{Engine, WidgetFlags};
+use rnote_compose::shapes::{CompoundPath, Shape};
+use std::collections::HashSet;
+use std::time::Instant;
+
+impl Engine {
+    /// Merge the shape strokes into a single compound path stroke, for example to export a logo as one path or to
+    /// move many pieces as one rigid object.
+    ///
+    /// The compound stroke takes the style and layer of the bottommost stroke and is selected when it was. Recorded as
+    /// a single history entry. Returns the key of the new stroke, None when less than two shape strokes are given.
+    ///
+    /// Only shape strokes are merged. Brush strokes, text and images are not made up of shapes, so they are left as
+    /// they are and stay selected.
+    pub fn merge_selection_to_compound(
+        &mut self,
+        keys: &[StrokeKey],
+    ) -> (Option<StrokeKey>, WidgetFlags) {
+        let keys = keys.iter().copied().collect::<HashSet<StrokeKey>>();
+        let merged = self
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter(|key| keys.contains(key))
+            .filter(|&key| matches!(self.store.get_stroke_ref(key), Some(Stroke::ShapeStroke(_))))
+            .collect::<Vec<StrokeKey>>();
+        if merged.len() < 2 {
+            return (None, WidgetFlags::default());
+        }
+
+        let bottommost = merged[0];
+        let Some(Stroke::ShapeStroke(bottommost_stroke)) = self.store.get_stroke_ref(bottommost)
+        else {
+            return (None, WidgetFlags::default());
+        };
+        let style = bottommost_stroke.style.clone();
+        let layer = self.store.layer(bottommost);
+        let selected = self.store.selected(bottommost).unwrap_or(false);
+        let compound =
+            CompoundPath::new(merged.iter().filter_map(
+                |&key| match self.store.get_stroke_ref(key) {
+                    Some(Stroke::ShapeStroke(shapestroke)) => Some(shapestroke.shape.clone()),
+                    _ => None,
+                },
+            ));
+
+        self.store.set_trashed_keys(&merged, true);
+        let key = self.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(Shape::CompoundPath(compound), style)),
+            layer,
+        );
+        self.store.set_selected(key, selected);
+        self.store.regenerate_rendering_for_stroke(
+            key,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+
+        let widget_flags = self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport();
+        (Some(key), widget_flags)
+    }
+
+    /// Explode the compound path strokes back into separate shape strokes, one for each subpath.
+    ///
+    /// The new strokes keep the style, layer and selection of their compound stroke. Other strokes are left as they
+    /// are. Recorded as a single history entry. Returns the keys of the new strokes.
+    pub fn explode_compound_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let mut exploded = vec![];
+        let mut new_keys = vec![];
+
+        for &key in keys {
+            let Some(Stroke::ShapeStroke(ShapeStroke {
+                shape: Shape::CompoundPath(compound),
+                style,
+                ..
+            })) = self.store.get_stroke_ref(key)
+            else {
+                continue;
+            };
+            let new_strokes = compound
+                .subpaths
+                .iter()
+                .map(|subpath| {
+                    Stroke::ShapeStroke(ShapeStroke::new(subpath.clone(), style.clone()))
+                })
+                .collect::<Vec<Stroke>>();
+            let layer = self.store.layer(key);
+            let selected = self.store.selected(key).unwrap_or(false);
+
+            for stroke in new_strokes {
+                let new_key = self.store.insert_stroke(stroke, layer);
+                self.store.set_selected(new_key, selected);
+                new_keys.push(new_key);
+            }
+            exploded.push(key);
+        }
+        if exploded.is_empty() {
+            return (new_keys, WidgetFlags::default());
+        }
+
+        self.store.set_trashed_keys(&exploded, true);
+        self.store.regenerate_rendering_for_strokes(
+            &new_keys,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+
+        let widget_flags = self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport();
+        (new_keys, widget_flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strokes::{BrushStroke, ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::penpath::Element;
+    use rnote_compose::shapes::{Line, Rectangle, Shape};
+    use rnote_compose::Style;
+
+    #[test]
+    fn merge_then_explode_keeps_subpaths() {
+        let mut engine = Engine::default();
+        let mut keys = [
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![0.0, 0.0],
+                na::point![50.0, 50.0],
+            ))),
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![10.0, 10.0],
+                na::point![40.0, 40.0],
+            ))),
+            Shape::Line(Line::new(na::vector![60.0, 0.0], na::vector![90.0, 30.0])),
+        ]
+        .map(|shape| {
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(shape, Style::default())),
+                None,
+            )
+        })
+        .to_vec();
+        let bounds = engine.store.bounds_for_strokes(&keys).unwrap();
+
+        let (compound_key, _) = engine.merge_selection_to_compound(&keys);
+        let compound_key = compound_key.unwrap();
+        let Some(Stroke::ShapeStroke(ShapeStroke {
+            shape: Shape::CompoundPath(compound),
+            ..
+        })) = engine.store.get_stroke_ref(compound_key)
+        else {
+            panic!("the merged stroke is not a compound path");
+        };
+        assert_eq!(compound.subpaths.len(), 3);
+        for &key in &keys {
+            assert_eq!(engine.store.trashed(key), Some(true));
+        }
+
+        let (exploded, _) = engine.explode_compound_strokes(&[compound_key]);
+        assert_eq!(exploded.len(), 3);
+        assert_eq!(engine.store.trashed(compound_key), Some(true));
+        assert_eq!(engine.store.bounds_for_strokes(&exploded), Some(bounds));
+    }
+
+    #[test]
+    fn merge_leaves_brush_strokes_untouched() {
+        let mut engine = Engine::default();
+        let rect = |mins: na::Point2<f64>| {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    mins,
+                    mins + na::vector![20.0, 20.0],
+                ))),
+                Style::default(),
+            ))
+        };
+        let shape = engine.store.insert_stroke(rect(na::point![0.0, 0.0]), None);
+        let brushstroke = engine.store.insert_stroke(
+            Stroke::BrushStroke(BrushStroke::new(
+                Element::new(na::vector![0.0, 100.0], 0.5),
+                Style::default(),
+            )),
+            None,
+        );
+        engine.store.set_selected_keys(&[shape, brushstroke], true);
+
+        // a single shape stroke is not merged together with the brush stroke
+        let (compound_key, _) = engine.merge_selection_to_compound(&[shape, brushstroke]);
+        assert_eq!(compound_key, None);
+        assert_eq!(engine.store.trashed(shape), Some(false));
+
+        let other_shape = engine
+            .store
+            .insert_stroke(rect(na::point![50.0, 0.0]), None);
+        let (compound_key, _) =
+            engine.merge_selection_to_compound(&[shape, brushstroke, other_shape]);
+        assert!(compound_key.is_some());
+        assert_eq!(engine.store.trashed(brushstroke), Some(false));
+        assert_eq!(engine.store.selected(brushstroke), Some(true));
+    }
+}
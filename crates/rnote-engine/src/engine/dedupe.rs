@@ -0,0 +1,212 @@
+// Imports
+use crate::store::StrokeKey;
+use crate::{Engine, WidgetFlags};
+use p2d::bounding_volume::BoundingVolume;
+use rnote_compose::shapes::Shapeable;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+impl Engine {
+    /// The tolerance when flattening the curves of the compared strokes.
+    const DEDUPE_FLATTEN_TOLERANCE: f64 = 0.25;
+
+    /// Remove strokes that duplicate another stroke, for example from importing content twice or from accidentally
+    /// drawing over a stroke.
+    ///
+    /// Brush and shape strokes are duplicates when they are on the same layer, have the same style and their paths are
+    /// nowhere further apart than the tolerance. The stroke that is drawn below is kept, locked strokes are never
+    /// removed. Recorded as a single history entry.
+    ///
+    /// Returns the number of removed strokes.
+    pub fn dedupe_strokes(&mut self, tolerance: f64) -> (usize, WidgetFlags) {
+        let rendered = self.store.stroke_keys_as_rendered();
+        let draw_positions = rendered
+            .iter()
+            .enumerate()
+            .map(|(i, &key)| (key, i))
+            .collect::<HashMap<StrokeKey, usize>>();
+        let mut duplicates = HashSet::new();
+        for (i, &key) in rendered.iter().enumerate() {
+            if duplicates.contains(&key) {
+                continue;
+            }
+            let Some(stroke) = self.store.get_stroke_ref(key) else {
+                continue;
+            };
+            let bounds = stroke.bounds();
+            let layer = self.store.layer(key);
+            let Some(points) = stroke.flattened_outline(Self::DEDUPE_FLATTEN_TOLERANCE) else {
+                continue;
+            };
+            // only strokes with nearly the same bounds can be duplicates
+            for other_key in self
+                .store
+                .keys_unordered_intersecting_bounds(bounds.loosened(tolerance))
+            {
+                // only compare with the strokes that are drawn above, which excludes trashed and hidden strokes
+                if draw_positions
+                    .get(&other_key)
+                    .map_or(true, |&other_i| other_i <= i)
+                    || duplicates.contains(&other_key)
+                    || self.store.layer(other_key) != layer
+                    || self.store.locked(other_key) != Some(false)
+                {
+                    continue;
+                }
+                let Some(other) = self.store.get_stroke_ref(other_key) else {
+                    continue;
+                };
+                let other_bounds = other.bounds();
+                if (other_bounds.mins - bounds.mins).abs().max() > tolerance
+                    || (other_bounds.maxs - bounds.maxs).abs().max() > tolerance
+                    || !stroke.has_same_style(other)
+                {
+                    continue;
+                }
+                let Some(other_points) = other.flattened_outline(Self::DEDUPE_FLATTEN_TOLERANCE)
+                else {
+                    continue;
+                };
+                if max_distance_to_polyline(&points, &other_points) <= tolerance
+                    && max_distance_to_polyline(&other_points, &points) <= tolerance
+                {
+                    duplicates.insert(other_key);
+                }
+            }
+        }
+        if duplicates.is_empty() {
+            return (0, WidgetFlags::default());
+        }
+
+        let duplicates = duplicates.into_iter().collect::<Vec<StrokeKey>>();
+        self.store.set_trashed_keys(&duplicates, true);
+        let widget_flags = self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport();
+        (duplicates.len(), widget_flags)
+    }
+}
+
+/// The largest distance of the points to the polyline.
+fn max_distance_to_polyline(points: &[na::Vector2<f64>], polyline: &[na::Vector2<f64>]) -> f64 {
+    let dist_to_segment = |p: na::Vector2<f64>, a: na::Vector2<f64>, b: na::Vector2<f64>| {
+        let ab = b - a;
+        let t = if ab.norm_squared() > 0.0 {
+            ((p - a).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        (a + ab * t - p).magnitude()
+    };
+    points
+        .iter()
+        .map(|&p| {
+            if polyline.len() == 1 {
+                return (polyline[0] - p).magnitude();
+            }
+            polyline
+                .windows(2)
+                .map(|w| dist_to_segment(p, w[0], w[1]))
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::chrono_comp::StrokeLayer;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Polyline, Rectangle, Shape};
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::Style;
+    use std::time::Instant;
+
+    #[test]
+    fn dedupe_removes_identical_strokes() {
+        let mut engine = Engine::default();
+        let rect = |style: Style| {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![50.0, 50.0],
+                ))),
+                style,
+            ))
+        };
+        let original = engine.store.insert_stroke(rect(Style::default()), None);
+        let duplicate = engine.store.insert_stroke(rect(Style::default()), None);
+        // distinct strokes overlapping the rectangles
+        let shifted = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![10.0, 10.0],
+                    na::point![60.0, 60.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        let restyled = engine.store.insert_stroke(
+            rect(Style::Smooth(SmoothOptions {
+                stroke_width: 6.0,
+                ..Default::default()
+            })),
+            None,
+        );
+        let line = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Polyline(Polyline {
+                    start: na::vector![0.0, 0.0],
+                    path: vec![na::vector![50.0, 50.0]],
+                }),
+                Style::default(),
+            )),
+            None,
+        );
+        let _ = engine.record(Instant::now());
+
+        let (removed, _) = engine.dedupe_strokes(0.5);
+        assert_eq!(removed, 1);
+        assert_eq!(engine.store.trashed(duplicate), Some(true));
+        for key in [original, shifted, restyled, line] {
+            assert_eq!(engine.store.trashed(key), Some(false));
+        }
+        assert_eq!(engine.dedupe_strokes(0.5).0, 0);
+
+        let _ = engine.undo(Instant::now());
+        assert_eq!(engine.store.trashed(duplicate), Some(false));
+    }
+
+    #[test]
+    fn dedupe_keeps_strokes_on_other_layers_and_locked_strokes() {
+        let mut engine = Engine::default();
+        let rect = || {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![50.0, 50.0],
+                ))),
+                Style::default(),
+            ))
+        };
+        let original = engine
+            .store
+            .insert_stroke(rect(), Some(StrokeLayer::UserLayer(0)));
+        let other_layer = engine
+            .store
+            .insert_stroke(rect(), Some(StrokeLayer::UserLayer(1)));
+        let locked = engine
+            .store
+            .insert_stroke(rect(), Some(StrokeLayer::UserLayer(0)));
+        engine.store.set_locked(locked, true);
+        let _ = engine.record(Instant::now());
+
+        assert_eq!(engine.dedupe_strokes(0.5).0, 0);
+        for key in [original, other_layer, locked] {
+            assert_eq!(engine.store.trashed(key), Some(false));
+        }
+    }
+}
@@ -2,13 +2,16 @@
 use super::{Engine, EngineConfig, StrokeContent};
 use crate::fileformats::rnoteformat::RnoteFile;
 use crate::fileformats::{xoppformat, FileFormatSaver};
+use crate::render::AaQuality;
 use crate::CloneConfig;
 use anyhow::Context;
 use futures::channel::oneshot;
+use p2d::bounding_volume::Aabb;
 use rayon::prelude::*;
 use rnote_compose::transform::Transformable;
-use rnote_compose::SplitOrder;
+use rnote_compose::{Color, SplitOrder};
 use serde::{Deserialize, Serialize};
+use slotmap::Key;
 use std::sync::Arc;
 use tracing::error;
 
@@ -86,6 +89,12 @@ pub struct DocExportPrefs {
     /// The page order when documents with layouts that expand in horizontal and vertical directions are cut into pages.
     #[serde(rename = "page_order")]
     pub page_order: SplitOrder,
+    /// Whether the pinned strokes should be exported, placed relative to the top-left corner of every page.
+    #[serde(rename = "with_pinned_strokes")]
+    pub with_pinned_strokes: bool,
+    /// Whether consecutive strokes with the same style should be combined in a group when exporting as Svg.
+    #[serde(rename = "group_by_style")]
+    pub group_by_style: bool,
 }
 
 impl Default for DocExportPrefs {
@@ -96,12 +105,19 @@ impl Default for DocExportPrefs {
             optimize_printing: false,
             export_format: DocExportFormat::default(),
             page_order: SplitOrder::default(),
+            with_pinned_strokes: false,
+            group_by_style: false,
         }
     }
 }
 
 impl DocExportPrefs {
     const MARGIN: f64 = 0.0;
+
+    /// The export options that correspond to these preferences.
+    pub fn export_options(&self) -> ExportOptions {
+        ExportOptions::from_background_flag(self.with_background, Self::MARGIN)
+    }
 }
 
 /// Document pages export format.
@@ -183,10 +199,18 @@ pub struct DocPagesExportPrefs {
     /// Quality when exporting as Jpeg.
     #[serde(rename = "jpg_quality")]
     pub jpeg_quality: u8,
+    /// Whether consecutive strokes with the same style should be combined in a group when exporting as Svg.
+    #[serde(rename = "group_by_style")]
+    pub group_by_style: bool,
 }
 
 impl DocPagesExportPrefs {
     const MARGIN: f64 = 0.0;
+
+    /// The export options that correspond to these preferences.
+    pub fn export_options(&self) -> ExportOptions {
+        ExportOptions::from_background_flag(self.with_background, Self::MARGIN)
+    }
 }
 
 impl Default for DocPagesExportPrefs {
@@ -199,10 +223,24 @@ impl Default for DocPagesExportPrefs {
             page_order: SplitOrder::default(),
             bitmap_scalefactor: 1.8,
             jpeg_quality: 85,
+            group_by_style: false,
         }
     }
 }
 
+/// A file created when exporting the document pages.
+#[derive(Debug, Clone)]
+pub struct ExportedFile {
+    /// The index of the exported page.
+    pub page_index: usize,
+    /// The suggested file name, including the file extension.
+    pub file_name: String,
+    /// The size of the exported page. In pixels for bitmap formats, in document coordinates for Svg.
+    pub size: na::Vector2<u32>,
+    /// The file content.
+    pub bytes: Vec<u8>,
+}
+
 /// Selection export format.
 #[derive(
     Debug,
@@ -297,8 +335,130 @@ impl Default for SelectionExportPrefs {
     }
 }
 
+impl SelectionExportPrefs {
+    /// The export options that correspond to these preferences.
+    pub fn export_options(&self) -> ExportOptions {
+        ExportOptions::from_background_flag(self.with_background, self.margin)
+    }
+}
+
+/// The background of exported content.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "export_background")]
+pub enum ExportBackground {
+    /// No background, the area without strokes stays fully transparent.
+    #[serde(rename = "transparent")]
+    Transparent,
+    /// The background of the document, including its pattern when requested.
+    #[serde(rename = "sheet_color")]
+    SheetColor,
+    /// Filled with a custom color.
+    #[serde(rename = "custom")]
+    Custom(Color),
+}
+
+impl Default for ExportBackground {
+    fn default() -> Self {
+        Self::SheetColor
+    }
+}
+
+/// Options for the area and background of exported content.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "export_options")]
+pub struct ExportOptions {
+    /// The padding around the bounds of the content.
+    #[serde(rename = "padding", with = "rnote_compose::serialize::f64_dp3")]
+    pub padding: f64,
+    /// The background.
+    #[serde(rename = "background")]
+    pub background: ExportBackground,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            padding: 0.0,
+            background: ExportBackground::default(),
+        }
+    }
+}
+
+impl ExportOptions {
+    /// The options for the sheet color background when `with_background` is true, else for a transparent background.
+    pub fn from_background_flag(with_background: bool, padding: f64) -> Self {
+        Self {
+            padding,
+            background: if with_background {
+                ExportBackground::SheetColor
+            } else {
+                ExportBackground::Transparent
+            },
+        }
+    }
+}
+
+/// Remapping of the colors of exported content, for example to print notes that are written in dark mode.
+///
+/// The stored strokes are not modified.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "export_color_map")]
+pub enum ExportColorMap {
+    /// The colors are exported as they are.
+    #[serde(rename = "none")]
+    None,
+    /// The luminance of all colors is inverted, so dark content on a light background is exported as light content
+    /// on a dark background and vice versa.
+    #[serde(rename = "invert_luminance")]
+    InvertLuminance,
+    /// Colors that match the first color of an entry are replaced by its second color, other colors are kept.
+    #[serde(rename = "palette")]
+    Palette(Vec<(Color, Color)>),
+}
+
+impl Default for ExportColorMap {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl ExportColorMap {
+    /// The mapped color.
+    pub fn map_color(&self, color: Color) -> Color {
+        match self {
+            Self::None => color,
+            Self::InvertLuminance => color.to_inverted_brightness_color(),
+            Self::Palette(entries) => entries
+                .iter()
+                .find(|(from, _)| from.approx_eq_f32(color))
+                .map(|(_, to)| *to)
+                .unwrap_or(color),
+        }
+    }
+
+    /// Map the colors of the strokes and the background of the content.
+    ///
+    /// The strokes are copied when they are modified, so the strokes that are shared with the store stay unchanged.
+    pub fn apply_to_content(&self, mut content: StrokeContent) -> StrokeContent {
+        if *self == Self::None {
+            return content;
+        }
+        for stroke in content.strokes.iter_mut() {
+            let mut mapped = stroke.as_ref().clone();
+            if mapped.map_colors(|color| self.map_color(color)) {
+                *stroke = Arc::new(mapped);
+            }
+        }
+        if let Some(background) = content.background.as_mut() {
+            background.color = self.map_color(background.color);
+            background.pattern_color = self.map_color(background.pattern_color);
+        }
+        content
+    }
+}
+
 /// Export preferences.
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default, rename = "export_prefs")]
 pub struct ExportPrefs {
     /// Document export preferences.
@@ -310,14 +470,48 @@ pub struct ExportPrefs {
     /// Selection export preferences.
     #[serde(rename = "selection_export_prefs")]
     pub selection_export_prefs: SelectionExportPrefs,
+    /// The remapping of the colors that is applied to all exports.
+    #[serde(rename = "color_map")]
+    pub color_map: ExportColorMap,
 }
 
 impl CloneConfig for ExportPrefs {
     fn clone_config(&self) -> Self {
-        *self
+        self.clone()
     }
 }
 
+/// A stroke in the exported stroke order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "stroke_order_entry")]
+pub struct StrokeOrderEntry {
+    /// The id of the stroke, unique within the document.
+    ///
+    /// It is the ffi value of the slotmap key of the stroke, so it changes when the document is saved and loaded again
+    /// and only matches the strokes of the same export.
+    #[serde(rename = "id")]
+    pub id: u64,
+    /// The chronological order of the stroke. Increases with every created or modified stroke, but is not a time.
+    #[serde(rename = "order")]
+    pub order: u32,
+    /// The time the stroke was created, in milliseconds since the unix epoch.
+    /// None for strokes of files that did not record it.
+    #[serde(rename = "created_at")]
+    pub created_at: Option<i64>,
+    /// The number of points of the stroke geometry.
+    #[serde(rename = "point_count")]
+    pub point_count: usize,
+}
+
+/// The order of the strokes, for animating how the document was written.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename = "stroke_order")]
+pub struct StrokeOrder {
+    /// The strokes in the order they were written.
+    #[serde(rename = "strokes")]
+    pub strokes: Vec<StrokeOrderEntry>,
+}
+
 impl Engine {
     /// The used image scale-factor for any strokes that are converted to bitmap images on export.
     pub const STROKE_EXPORT_IMAGE_SCALE: f64 = 1.8;
@@ -353,17 +547,24 @@ impl Engine {
             penholder: self.penholder.clone_config(),
             import_prefs: self.import_prefs.clone_config(),
             export_prefs: self.export_prefs.clone_config(),
+            overlay: self.overlay.clone_config(),
             pen_sounds: self.pen_sounds(),
             optimize_epd: self.optimize_epd(),
+            antialiasing: self.antialiasing(),
+            min_screen_width_px: self.min_screen_width_px(),
+            zoom_limits: self.zoom_limits(),
+            rebase_input: self.rebase_input(),
+            reject_initial_jitter: self.reject_initial_jitter(),
         }
     }
 
     pub fn extract_document_content(&self) -> StrokeContent {
+        let (strokes, clip_groups) = self
+            .store
+            .get_strokes_arc_w_clip_groups(self.store.stroke_keys_as_rendered());
         StrokeContent::default()
-            .with_strokes(
-                self.store
-                    .get_strokes_arc(&self.store.stroke_keys_as_rendered()),
-            )
+            .with_strokes(strokes)
+            .with_clip_groups(clip_groups)
             .with_bounds(Some(
                 self.bounds_w_content_extended()
                     .unwrap_or(self.document.bounds()),
@@ -371,24 +572,64 @@ impl Engine {
             .with_background(Some(self.document.background))
     }
 
+    /// Extract the document content, optionally including the pinned strokes on every page with content.
+    pub fn extract_document_content_w_pinned(
+        &self,
+        page_order: SplitOrder,
+        with_pinned_strokes: bool,
+    ) -> StrokeContent {
+        let mut content = self.extract_document_content();
+        if with_pinned_strokes {
+            for page_bounds in self.pages_bounds_w_content(page_order) {
+                content
+                    .strokes
+                    .extend(self.pinned_strokes_for_page(page_bounds));
+            }
+        }
+        content
+    }
+
     pub fn extract_pages_content(&self, page_order: SplitOrder) -> Vec<StrokeContent> {
+        self.extract_pages_content_w_pinned(page_order, false)
+    }
+
+    /// Extract the pages content, optionally including the pinned strokes on every page.
+    pub fn extract_pages_content_w_pinned(
+        &self,
+        page_order: SplitOrder,
+        with_pinned_strokes: bool,
+    ) -> Vec<StrokeContent> {
         self.pages_bounds_w_content(page_order)
             .into_iter()
             .map(|bounds| {
+                let (mut strokes, clip_groups) = self.store.get_strokes_arc_w_clip_groups(
+                    self.store
+                        .stroke_keys_as_rendered_intersecting_bounds(bounds),
+                );
+                if with_pinned_strokes {
+                    strokes.extend(self.pinned_strokes_for_page(bounds));
+                }
                 StrokeContent::default()
-                    .with_strokes(
-                        self.store.get_strokes_arc(
-                            &self
-                                .store
-                                .stroke_keys_as_rendered_intersecting_bounds(bounds),
-                        ),
-                    )
+                    .with_strokes(strokes)
+                    .with_clip_groups(clip_groups)
                     .with_bounds(Some(bounds))
                     .with_background(Some(self.document.background))
             })
             .collect()
     }
 
+    /// Extract the pages content like [Engine::extract_pages_content_w_pinned], with the export color map applied.
+    fn extract_pages_content_w_pinned_color_mapped(
+        &self,
+        page_order: SplitOrder,
+        with_pinned_strokes: bool,
+    ) -> Vec<StrokeContent> {
+        self.extract_pages_content_w_pinned(page_order, with_pinned_strokes)
+            .into_iter()
+            .map(|content| self.export_prefs.color_map.apply_to_content(content))
+            .collect()
+    }
+
     pub fn extract_selection_content(&self) -> Option<StrokeContent> {
         let selection_keys = self.store.selection_keys_as_rendered();
         if selection_keys.is_empty() {
@@ -401,11 +642,52 @@ impl Engine {
         )
     }
 
+    /// Extract the content of the given region. Strokes straddling the region are included,
+    /// the region then acts as a clip when exporting.
+    pub fn extract_region_content(&self, region: Aabb) -> Option<StrokeContent> {
+        let keys = self
+            .store
+            .stroke_keys_as_rendered_intersecting_bounds(region);
+        if keys.is_empty() {
+            return None;
+        }
+        let (strokes, clip_groups) = self.store.get_strokes_arc_w_clip_groups(keys);
+        Some(
+            StrokeContent::default()
+                .with_strokes(strokes)
+                .with_clip_groups(clip_groups)
+                .with_bounds(Some(region))
+                .with_background(Some(self.document.background)),
+        )
+    }
+
     /// Export the current engine config as Json string.
     pub fn export_engine_config_as_json(&self) -> anyhow::Result<String> {
         Ok(serde_json::to_string(&self.extract_engine_config())?)
     }
 
+    /// Export the order of the strokes as Json string, to be stored as sidecar next to an exported document.
+    ///
+    /// Players can animate the writing from it, for example for teaching handwriting.
+    pub fn export_stroke_order(&self) -> anyhow::Result<String> {
+        let mut strokes = self
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter_map(|key| {
+                Some(StrokeOrderEntry {
+                    id: key.data().as_ffi(),
+                    order: self.store.chrono_t(key)?,
+                    created_at: self.store.created_at(key),
+                    point_count: self.store.get_stroke_ref(key)?.point_count(),
+                })
+            })
+            .collect::<Vec<StrokeOrderEntry>>();
+        // The draw order is primarily by layer, the writing order is by the chronological order only
+        strokes.sort_by_key(|entry| entry.order);
+        Ok(serde_json::to_string(&StrokeOrder { strokes })?)
+    }
+
     /// Export the entire engine state as Json string.
     ///
     /// Only intended to be used for debugging.
@@ -422,9 +704,32 @@ impl Engine {
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
 
+        self.export_doc_w_options(
+            title,
+            doc_export_prefs.export_options(),
+            doc_export_prefs_override,
+        )
+    }
+
+    /// Export the document with the padding and background of the given options.
+    ///
+    /// The options replace the background of the document export preferences. Xopp exports ignore them.
+    pub fn export_doc_w_options(
+        &self,
+        title: String,
+        options: ExportOptions,
+        doc_export_prefs_override: Option<DocExportPrefs>,
+    ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
+        let doc_export_prefs =
+            doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
+
         match doc_export_prefs.export_format {
-            DocExportFormat::Svg => self.export_doc_as_svg_bytes(doc_export_prefs_override),
-            DocExportFormat::Pdf => self.export_doc_as_pdf_bytes(title, doc_export_prefs_override),
+            DocExportFormat::Svg => {
+                self.export_doc_as_svg_bytes(options, doc_export_prefs_override)
+            }
+            DocExportFormat::Pdf => {
+                self.export_doc_as_pdf_bytes(title, options, doc_export_prefs_override)
+            }
             DocExportFormat::Xopp => {
                 self.export_doc_as_xopp_bytes(title, doc_export_prefs_override)
             }
@@ -434,23 +739,34 @@ impl Engine {
     /// Export the doc with the strokes as Svg.
     fn export_doc_as_svg_bytes(
         &self,
+        options: ExportOptions,
         doc_export_prefs_override: Option<DocExportPrefs>,
     ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
-        let doc_content = self.extract_document_content();
+        let doc_content =
+            self.export_prefs
+                .color_map
+                .apply_to_content(self.extract_document_content_w_pinned(
+                    doc_export_prefs.page_order,
+                    doc_export_prefs.with_pinned_strokes,
+                ));
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<u8>> {
-                let doc_svg = doc_content
-                    .gen_svg(
-                        doc_export_prefs.with_background,
-                        doc_export_prefs.with_pattern,
-                        doc_export_prefs.optimize_printing,
-                        DocExportPrefs::MARGIN,
-                    )?
-                    .ok_or(anyhow::anyhow!("Generating doc svg failed, returned None."))?;
+                let gen_svg = if doc_export_prefs.group_by_style {
+                    StrokeContent::gen_svg_grouped_by_style_w_options
+                } else {
+                    StrokeContent::gen_svg_w_options
+                };
+                let doc_svg = gen_svg(
+                    &doc_content,
+                    options,
+                    doc_export_prefs.with_pattern,
+                    doc_export_prefs.optimize_printing,
+                )?
+                .ok_or(anyhow::anyhow!("Generating doc svg failed, returned None."))?;
                 Ok(rnote_compose::utils::add_xml_header(
                     rnote_compose::utils::wrap_svg_root(
                         doc_svg.svg_data.as_str(),
@@ -475,19 +791,29 @@ impl Engine {
     fn export_doc_as_pdf_bytes(
         &self,
         title: String,
+        options: ExportOptions,
         doc_export_prefs_override: Option<DocExportPrefs>,
     ) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
-        let pages_content = self.extract_pages_content(doc_export_prefs.page_order);
-        let format_size = self.document.format.size();
+        let pages_content = self.extract_pages_content_w_pinned_color_mapped(
+            doc_export_prefs.page_order,
+            doc_export_prefs.with_pinned_strokes,
+        );
+        let page_size =
+            self.document.format.size() + na::Vector2::from_element(2.0 * options.padding);
+        // Pdf dimensions are in points, scale so that the document keeps its physical size
+        let pt_scale = self.document.format.px_to_pt_scale();
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<u8>> {
-                let target_surface =
-                    cairo::PdfSurface::for_stream(format_size[0], format_size[1], Vec::<u8>::new())
-                        .context("Creating Pdf target surface failed.")?;
+                let target_surface = cairo::PdfSurface::for_stream(
+                    page_size[0] * pt_scale,
+                    page_size[1] * pt_scale,
+                    Vec::<u8>::new(),
+                )
+                .context("Creating Pdf target surface failed.")?;
 
                 target_surface
                     .set_metadata(cairo::PdfMetadata::Title, title.as_str())
@@ -505,17 +831,19 @@ impl Engine {
                         .context("Creating new cairo context for pdf target surface failed.")?;
 
                     for (i, page_content) in pages_content.into_iter().enumerate() {
-                        let Some(page_bounds) = page_content.bounds() else {
+                        let Some(page_bounds) =
+                            page_content.bounds().map(|b| b.loosened(options.padding))
+                        else {
                             continue;
                         };
                         cairo_cx.save()?;
+                        cairo_cx.scale(pt_scale, pt_scale);
                         cairo_cx.translate(-page_bounds.mins[0], -page_bounds.mins[1]);
-                        page_content.draw_to_cairo(
+                        page_content.draw_to_cairo_w_options(
                             &cairo_cx,
-                            doc_export_prefs.with_background,
+                            options,
                             doc_export_prefs.with_pattern,
                             doc_export_prefs.optimize_printing,
-                            DocExportPrefs::MARGIN,
                             Engine::STROKE_EXPORT_IMAGE_SCALE,
                         )?;
                         cairo_cx.show_page().map_err(|e| {
@@ -554,7 +882,10 @@ impl Engine {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
-        let pages_content = self.extract_pages_content(doc_export_prefs.page_order);
+        let pages_content = self.extract_pages_content_w_pinned_color_mapped(
+            doc_export_prefs.page_order,
+            doc_export_prefs.with_pinned_strokes,
+        );
         let document = self.document.clone();
 
         rayon::spawn(move || {
@@ -685,25 +1016,25 @@ impl Engine {
         let doc_pages_export_prefs =
             doc_pages_export_prefs_override.unwrap_or(self.export_prefs.doc_pages_export_prefs);
 
-        match doc_pages_export_prefs.export_format {
-            DocPagesExportFormat::Svg => {
-                self.export_doc_pages_as_svgs_bytes(doc_pages_export_prefs_override)
-            }
-            DocPagesExportFormat::Png | DocPagesExportFormat::Jpeg => {
-                self.export_doc_pages_as_bitmap_bytes(doc_pages_export_prefs_override)
-            }
-        }
+        self.export_doc_pages_w_options(
+            doc_pages_export_prefs.export_options(),
+            doc_pages_export_prefs_override,
+        )
     }
 
-    /// Export the document as Svg.
-    fn export_doc_pages_as_svgs_bytes(
+    /// Export the document pages with the padding and background of the given options.
+    ///
+    /// The options replace the background of the document pages export preferences.
+    pub fn export_doc_pages_w_options(
         &self,
+        options: ExportOptions,
         doc_pages_export_prefs_override: Option<DocPagesExportPrefs>,
     ) -> oneshot::Receiver<Result<Vec<Vec<u8>>, anyhow::Error>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<Vec<u8>>>>();
         let doc_pages_export_prefs =
             doc_pages_export_prefs_override.unwrap_or(self.export_prefs.doc_pages_export_prefs);
-        let pages_content = self.extract_pages_content(doc_pages_export_prefs.page_order);
+        let pages_content = self
+            .extract_pages_content_w_pinned_color_mapped(doc_pages_export_prefs.page_order, false);
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<Vec<u8>>> {
@@ -711,33 +1042,14 @@ impl Engine {
                     .into_par_iter()
                     .enumerate()
                     .map(|(i, page_content)| {
-                        let page_svg = page_content
-                            .gen_svg(
-                                doc_pages_export_prefs.with_background,
-                                doc_pages_export_prefs.with_pattern,
-                                doc_pages_export_prefs.optimize_printing,
-                                DocPagesExportPrefs::MARGIN,
-                            )?
-                            .ok_or(anyhow::anyhow!(
-                                "Generating Svg for page {i} failed, returned None."
-                            ))?;
-                        Ok(rnote_compose::utils::add_xml_header(
-                            rnote_compose::utils::wrap_svg_root(
-                                page_svg.svg_data.as_str(),
-                                Some(page_svg.bounds),
-                                Some(page_svg.bounds),
-                                false,
-                            )
-                            .as_str(),
-                        )
-                        .into_bytes())
+                        Ok(export_doc_page(&page_content, i, options, &doc_pages_export_prefs)?.0)
                     })
                     .collect()
             };
 
             if oneshot_sender.send(result()).is_err() {
                 error!(
-                    "Sending result to receiver failed while exporting document pages as Svg bytes. Receiver already dropped."
+                    "Sending result to receiver failed while exporting document pages bytes. Receiver already dropped."
                 );
             }
         });
@@ -745,49 +1057,68 @@ impl Engine {
         oneshot_receiver
     }
 
-    /// Export the document pages as bitmap.
+    /// Export all document pages, together with a description of every created file.
     ///
-    /// Returns an error if the format pref is not set to a bitmap variant.
-    fn export_doc_pages_as_bitmap_bytes(
+    /// `file_stem_name` is the stem name of the suggested file names.
+    /// It is extended by the page number and the file extension of the export format.
+    /// The pages are rendered in parallel, the returned files are ordered by their page index.
+    pub fn export_all_pages(
         &self,
+        file_stem_name: String,
         doc_pages_export_prefs_override: Option<DocPagesExportPrefs>,
-    ) -> oneshot::Receiver<Result<Vec<Vec<u8>>, anyhow::Error>> {
-        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<Vec<u8>>>>();
+    ) -> oneshot::Receiver<Result<Vec<ExportedFile>, anyhow::Error>> {
         let doc_pages_export_prefs =
             doc_pages_export_prefs_override.unwrap_or(self.export_prefs.doc_pages_export_prefs);
-        let pages_contents = self.extract_pages_content(doc_pages_export_prefs.page_order);
+
+        self.export_all_pages_w_options(
+            file_stem_name,
+            doc_pages_export_prefs.export_options(),
+            doc_pages_export_prefs_override,
+        )
+    }
+
+    /// Export all document pages like [Engine::export_all_pages()], with the padding and background of the given
+    /// options.
+    pub fn export_all_pages_w_options(
+        &self,
+        file_stem_name: String,
+        options: ExportOptions,
+        doc_pages_export_prefs_override: Option<DocPagesExportPrefs>,
+    ) -> oneshot::Receiver<Result<Vec<ExportedFile>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Vec<ExportedFile>>>();
+        let doc_pages_export_prefs =
+            doc_pages_export_prefs_override.unwrap_or(self.export_prefs.doc_pages_export_prefs);
+        let pages_content = self
+            .extract_pages_content_w_pinned_color_mapped(doc_pages_export_prefs.page_order, false);
+        let file_ext = doc_pages_export_prefs.export_format.file_ext();
 
         rayon::spawn(move || {
-            let result = || -> Result<Vec<Vec<u8>>, anyhow::Error> {
-                let image_format = match doc_pages_export_prefs.export_format {
-                    DocPagesExportFormat::Svg => return Err(anyhow::anyhow!("Extracting bitmap image format from doc pages export prefs failed, not set to a bitmap format.")),
-                    DocPagesExportFormat::Png => image::ImageFormat::Png,
-                    DocPagesExportFormat::Jpeg => image::ImageFormat::Jpeg,
-                };
-                pages_contents
+            let result = || -> anyhow::Result<Vec<ExportedFile>> {
+                pages_content
                     .into_par_iter()
                     .enumerate()
                     .map(|(i, page_content)| {
-                        page_content
-                            .gen_svg(
-                                doc_pages_export_prefs.with_background,
-                                doc_pages_export_prefs.with_pattern,
-                                doc_pages_export_prefs.optimize_printing,
-                                DocPagesExportPrefs::MARGIN,
-                            )?
-                            .ok_or(anyhow::anyhow!(
-                                "Generating Svg for page {i} failed, returned None."
-                            ))?
-                            .gen_image(doc_pages_export_prefs.bitmap_scalefactor)?
-                            .into_encoded_bytes(
-                                image_format,
-                                Some(doc_pages_export_prefs.jpeg_quality),
-                            )
+                        let (bytes, size) =
+                            export_doc_page(&page_content, i, options, &doc_pages_export_prefs)?;
+                        Ok(ExportedFile {
+                            page_index: i,
+                            file_name: crate::utils::doc_pages_files_names(
+                                file_stem_name.clone(),
+                                i + 1,
+                            ) + "."
+                                + &file_ext,
+                            size,
+                            bytes,
+                        })
                     })
                     .collect()
             };
+
             if oneshot_sender.send(result()).is_err() {
-                error!("Sending result to receiver failed while exporting document pages as bitmap bytes. Receiver already dropped.");
+                error!(
+                    "Sending result to receiver failed while exporting all document pages. Receiver already dropped."
+                );
             }
         });
 
@@ -802,37 +1133,124 @@ impl Engine {
         let selection_export_prefs =
             selection_export_prefs_override.unwrap_or(self.export_prefs.selection_export_prefs);
 
+        self.export_selection_w_options(
+            selection_export_prefs.export_options(),
+            selection_export_prefs_override,
+        )
+    }
+
+    /// Exports the current selection with the padding and background of the given options.
+    ///
+    /// The options replace the margin and background of the selection export preferences.
+    pub fn export_selection_w_options(
+        &self,
+        options: ExportOptions,
+        selection_export_prefs_override: Option<SelectionExportPrefs>,
+    ) -> oneshot::Receiver<Result<Option<Vec<u8>>, anyhow::Error>> {
+        let selection_export_prefs =
+            selection_export_prefs_override.unwrap_or(self.export_prefs.selection_export_prefs);
+
         match selection_export_prefs.export_format {
             SelectionExportFormat::Svg => {
-                self.export_selection_as_svg_bytes(selection_export_prefs_override)
+                self.export_selection_as_svg_bytes(options, selection_export_prefs_override)
             }
             SelectionExportFormat::Png | SelectionExportFormat::Jpeg => {
-                self.export_selection_as_bitmap_bytes(selection_export_prefs_override)
+                self.export_selection_as_bitmap_bytes(options, selection_export_prefs_override)
             }
         }
     }
 
+    /// Export a cropped region of the document.
+    ///
+    /// Only strokes intersecting the region are exported and they are clipped to it.
+    /// The selection export preferences are used for the format and background, but no margin is applied.
+    pub fn export_region(
+        &self,
+        region: Aabb,
+        selection_export_prefs_override: Option<SelectionExportPrefs>,
+    ) -> oneshot::Receiver<Result<Option<Vec<u8>>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Option<Vec<u8>>>>();
+        let selection_export_prefs =
+            selection_export_prefs_override.unwrap_or(self.export_prefs.selection_export_prefs);
+        let content = self
+            .extract_region_content(region)
+            .map(|content| self.export_prefs.color_map.apply_to_content(content));
+
+        rayon::spawn(move || {
+            let result = || -> Result<Option<Vec<u8>>, anyhow::Error> {
+                let Some(content) = content else {
+                    return Ok(None);
+                };
+                let Some(svg) = content.gen_svg_w_options(
+                    ExportOptions {
+                        padding: 0.0,
+                        ..selection_export_prefs.export_options()
+                    },
+                    selection_export_prefs.with_pattern,
+                    selection_export_prefs.optimize_printing,
+                )?
+                else {
+                    return Ok(None);
+                };
+                let image_format = match selection_export_prefs.export_format {
+                    SelectionExportFormat::Svg => {
+                        return Ok(Some(
+                            rnote_compose::utils::add_xml_header(
+                                rnote_compose::utils::wrap_svg_root(
+                                    svg.svg_data.as_str(),
+                                    Some(svg.bounds),
+                                    Some(svg.bounds),
+                                    false,
+                                )
+                                .as_str(),
+                            )
+                            .into_bytes(),
+                        ))
+                    }
+                    SelectionExportFormat::Png => image::ImageFormat::Png,
+                    SelectionExportFormat::Jpeg => image::ImageFormat::Jpeg,
+                };
+
+                Ok(Some(
+                    svg.gen_image_aa(selection_export_prefs.bitmap_scalefactor, AaQuality::High)?
+                        .into_encoded_bytes(
+                            image_format,
+                            Some(selection_export_prefs.jpeg_quality),
+                        )?,
+                ))
+            };
+            if oneshot_sender.send(result()).is_err() {
+                error!("Sending result to receiver failed while exporting region. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Exports the selection as Svg.
     fn export_selection_as_svg_bytes(
         &self,
+        options: ExportOptions,
         selection_export_prefs_override: Option<SelectionExportPrefs>,
     ) -> oneshot::Receiver<Result<Option<Vec<u8>>, anyhow::Error>> {
         let (oneshot_sender, oneshot_receiver) =
             oneshot::channel::<anyhow::Result<Option<Vec<u8>>>>();
         let selection_export_prefs =
             selection_export_prefs_override.unwrap_or(self.export_prefs.selection_export_prefs);
-        let content = self.extract_selection_content();
+        let content = self
+            .extract_selection_content()
+            .map(|content| self.export_prefs.color_map.apply_to_content(content));
 
         rayon::spawn(move || {
             let result = || -> Result<Option<Vec<u8>>, anyhow::Error> {
                 let Some(content) = content else {
                     return Ok(None);
                 };
-                let Some(svg) = content.gen_svg(
-                    selection_export_prefs.with_background,
+                let Some(svg) = content.gen_svg_w_options(
+                    options,
                     selection_export_prefs.with_pattern,
                     selection_export_prefs.optimize_printing,
-                    selection_export_prefs.margin,
                 )?
                 else {
                     return Ok(None);
@@ -864,24 +1282,26 @@ impl Engine {
     /// Returns an error if the format pref is not set to a bitmap format
     fn export_selection_as_bitmap_bytes(
         &self,
+        options: ExportOptions,
         selection_export_prefs_override: Option<SelectionExportPrefs>,
     ) -> oneshot::Receiver<Result<Option<Vec<u8>>, anyhow::Error>> {
         let (oneshot_sender, oneshot_receiver) =
             oneshot::channel::<anyhow::Result<Option<Vec<u8>>>>();
         let selection_export_prefs =
             selection_export_prefs_override.unwrap_or(self.export_prefs.selection_export_prefs);
-        let content = self.extract_selection_content();
+        let content = self
+            .extract_selection_content()
+            .map(|content| self.export_prefs.color_map.apply_to_content(content));
 
         rayon::spawn(move || {
             let result = || -> Result<Option<Vec<u8>>, anyhow::Error> {
                 let Some(content) = content else {
                     return Ok(None);
                 };
-                let Some(svg) = content.gen_svg(
-                    selection_export_prefs.with_background,
+                let Some(svg) = content.gen_svg_w_options(
+                    options,
                     selection_export_prefs.with_pattern,
                     selection_export_prefs.optimize_printing,
-                    selection_export_prefs.margin,
                 )?
                 else {
                     return Ok(None);
@@ -893,7 +1313,7 @@ impl Engine {
                 };
 
                 Ok(Some(
-                    svg.gen_image(selection_export_prefs.bitmap_scalefactor)?
+                    svg.gen_image_aa(selection_export_prefs.bitmap_scalefactor, AaQuality::High)?
                         .into_encoded_bytes(
                             image_format,
                             Some(selection_export_prefs.jpeg_quality),
@@ -908,3 +1328,402 @@ impl Engine {
         oneshot_receiver
     }
 }
+
+/// Export a single document page in the format of the export prefs.
+///
+/// Returns the bytes and the size of the exported page.
+fn export_doc_page(
+    page_content: &StrokeContent,
+    page_index: usize,
+    options: ExportOptions,
+    doc_pages_export_prefs: &DocPagesExportPrefs,
+) -> anyhow::Result<(Vec<u8>, na::Vector2<u32>)> {
+    let gen_svg = if doc_pages_export_prefs.group_by_style
+        && doc_pages_export_prefs.export_format == DocPagesExportFormat::Svg
+    {
+        StrokeContent::gen_svg_grouped_by_style_w_options
+    } else {
+        StrokeContent::gen_svg_w_options
+    };
+    let page_svg = gen_svg(
+        page_content,
+        options,
+        doc_pages_export_prefs.with_pattern,
+        doc_pages_export_prefs.optimize_printing,
+    )?
+    .ok_or(anyhow::anyhow!(
+        "Generating Svg for page {page_index} failed, returned None."
+    ))?;
+
+    let image_format = match doc_pages_export_prefs.export_format {
+        DocPagesExportFormat::Svg => {
+            let extents = page_svg.bounds.extents();
+            let size = na::vector![extents[0].ceil() as u32, extents[1].ceil() as u32];
+            let bytes = rnote_compose::utils::add_xml_header(
+                rnote_compose::utils::wrap_svg_root(
+                    page_svg.svg_data.as_str(),
+                    Some(page_svg.bounds),
+                    Some(page_svg.bounds),
+                    false,
+                )
+                .as_str(),
+            )
+            .into_bytes();
+            return Ok((bytes, size));
+        }
+        DocPagesExportFormat::Png => image::ImageFormat::Png,
+        DocPagesExportFormat::Jpeg => image::ImageFormat::Jpeg,
+    };
+    let image =
+        page_svg.gen_image_aa(doc_pages_export_prefs.bitmap_scalefactor, AaQuality::High)?;
+    let size = na::vector![image.pixel_width, image.pixel_height];
+    let bytes =
+        image.into_encoded_bytes(image_format, Some(doc_pages_export_prefs.jpeg_quality))?;
+    Ok((bytes, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DocPagesExportFormat, DocPagesExportPrefs, ExportBackground, ExportColorMap, ExportOptions,
+        SelectionExportFormat, SelectionExportPrefs, StrokeOrder,
+    };
+    use crate::document::Layout;
+    use crate::store::chrono_comp::StrokeLayer;
+    use crate::strokes::{BrushStroke, ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::penpath::{Element, Segment};
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::{Color, PenPath, Style};
+    use slotmap::Key;
+
+    #[test]
+    fn export_stroke_order_matches_draw_order() {
+        let mut engine = Engine::default();
+        let rect = || {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![50.0, 50.0],
+                ))),
+                Style::default(),
+            ))
+        };
+        let brushstroke = Stroke::BrushStroke(BrushStroke::from_penpath(
+            PenPath::new_w_segments(
+                Element::new(na::vector![0.0, 0.0], 0.5),
+                [10.0, 20.0].map(|x| Segment::LineTo {
+                    end: Element::new(na::vector![x, 0.0], 0.5),
+                }),
+            ),
+            Style::default(),
+        ));
+        let first = engine.store.insert_stroke(brushstroke, None);
+        // highlighter strokes are drawn below the ones on user layers, but are ordered as they are written
+        let highlighted = engine
+            .store
+            .insert_stroke(rect(), Some(StrokeLayer::Highlighter));
+        let last = engine.store.insert_stroke(rect(), None);
+
+        let order =
+            serde_json::from_str::<StrokeOrder>(&engine.export_stroke_order().unwrap()).unwrap();
+        assert_eq!(
+            order
+                .strokes
+                .iter()
+                .map(|entry| entry.id)
+                .collect::<Vec<u64>>(),
+            [first, highlighted, last].map(|key| key.data().as_ffi())
+        );
+        assert_eq!(
+            order
+                .strokes
+                .iter()
+                .map(|entry| entry.point_count)
+                .collect::<Vec<usize>>(),
+            vec![3, 4, 4]
+        );
+        assert!(order
+            .strokes
+            .windows(2)
+            .all(|entries| entries[0].order < entries[1].order));
+        // the creation times allow replaying the timing of the writing
+        let created_at = order
+            .strokes
+            .iter()
+            .map(|entry| entry.created_at.unwrap())
+            .collect::<Vec<i64>>();
+        assert!(created_at.windows(2).all(|times| times[0] <= times[1]));
+    }
+
+    #[test]
+    fn export_all_pages_of_two_page_document() {
+        let mut engine = Engine::default();
+        let page_size = engine.document.format.size();
+        engine.document.layout = Layout::FixedSize;
+        let _ = engine
+            .set_doc_dimensions(page_size[0], page_size[1] * 2.0)
+            .unwrap();
+        for page in 0..2 {
+            let mins = na::point![50.0, page_size[1] * page as f64 + 50.0];
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        mins,
+                        mins + na::vector![100.0, 100.0],
+                    ))),
+                    Style::default(),
+                )),
+                None,
+            );
+        }
+        let prefs = DocPagesExportPrefs {
+            export_format: DocPagesExportFormat::Svg,
+            ..Default::default()
+        };
+
+        let files = futures::executor::block_on(
+            engine.export_all_pages(String::from("notes"), Some(prefs)),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(files.len(), 2);
+        for (i, file) in files.iter().enumerate() {
+            assert_eq!(file.page_index, i);
+            assert_eq!(file.file_name, format!("notes - Page {:02}.svg", i + 1));
+            assert_eq!(
+                file.size,
+                na::vector![page_size[0].ceil() as u32, page_size[1].ceil() as u32]
+            );
+            assert!(!file.bytes.is_empty());
+        }
+    }
+
+    #[test]
+    fn doc_pages_export_applies_options() {
+        let mut engine = Engine::default();
+        let page_size = engine.document.format.size();
+        engine.document.layout = Layout::FixedSize;
+        let _ = engine
+            .set_doc_dimensions(page_size[0], page_size[1])
+            .unwrap();
+        engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![50.0, 50.0],
+                    na::point![150.0, 150.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        let prefs = DocPagesExportPrefs {
+            export_format: DocPagesExportFormat::Png,
+            bitmap_scalefactor: 1.0,
+            ..Default::default()
+        };
+        let options = ExportOptions {
+            padding: 10.0,
+            background: ExportBackground::Custom(Color::new(1.0, 0.0, 0.0, 1.0)),
+        };
+
+        let pages =
+            futures::executor::block_on(engine.export_doc_pages_w_options(options, Some(prefs)))
+                .unwrap()
+                .unwrap();
+        assert_eq!(pages.len(), 1);
+        let image = image::load_from_memory(&pages[0]).unwrap().to_rgba8();
+        assert_eq!(image.width(), (page_size[0] + 20.0).round() as u32);
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+        // the preferences map to a transparent background when the background is disabled
+        let transparent = DocPagesExportPrefs {
+            with_background: false,
+            ..prefs
+        };
+        let pages = futures::executor::block_on(engine.export_doc_pages(Some(transparent)))
+            .unwrap()
+            .unwrap();
+        let image = image::load_from_memory(&pages[0]).unwrap().to_rgba8();
+        assert_eq!(image.width(), page_size[0].round() as u32);
+        assert_eq!(image.get_pixel(0, 0).0[3], 0);
+    }
+
+    #[test]
+    fn export_region_clips_strokes_at_the_boundary() {
+        let mut engine = Engine::default();
+        let filled_rect = |mins: na::Point2<f64>| {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    mins,
+                    mins + na::vector![100.0, 100.0],
+                ))),
+                Style::Smooth(SmoothOptions {
+                    stroke_color: None,
+                    fill_color: Some(Color::BLACK),
+                    ..Default::default()
+                }),
+            ))
+        };
+        // crossing the boundary of the region
+        engine
+            .store
+            .insert_stroke(filled_rect(na::point![50.0, 50.0]), None);
+        // outside of the region
+        engine
+            .store
+            .insert_stroke(filled_rect(na::point![300.0, 300.0]), None);
+        let region = Aabb::new(na::point![0.0, 0.0], na::point![100.0, 100.0]);
+        let prefs = SelectionExportPrefs {
+            export_format: SelectionExportFormat::Png,
+            with_background: false,
+            bitmap_scalefactor: 1.0,
+            ..Default::default()
+        };
+
+        let bytes = futures::executor::block_on(engine.export_region(region, Some(prefs)))
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        // the stroke is cut off at the boundary of the region
+        assert_eq!((image.width(), image.height()), (100, 100));
+        assert_eq!(image.get_pixel(75, 75).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(98, 98).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(25, 25).0[3], 0);
+        assert_eq!(image.get_pixel(75, 25).0[3], 0);
+
+        // nothing is exported for a region without strokes
+        assert!(futures::executor::block_on(engine.export_region(
+            Aabb::new(na::point![150.0, 150.0], na::point![250.0, 250.0]),
+            Some(prefs)
+        ))
+        .unwrap()
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn invert_luminance_color_map_inverts_export() {
+        let near_black = Color::new(0.05, 0.05, 0.05, 1.0);
+        for (foreground, background) in [(near_black, Color::WHITE), (Color::WHITE, near_black)] {
+            let mut engine = Engine::default();
+            engine.document.background.color = background;
+            let key = engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        na::point![0.0, 0.0],
+                        na::point![100.0, 100.0],
+                    ))),
+                    Style::Smooth(SmoothOptions {
+                        stroke_color: Some(foreground),
+                        fill_color: Some(foreground),
+                        ..Default::default()
+                    }),
+                )),
+                None,
+            );
+            engine.store.set_selected_keys(&[key], true);
+            engine.export_prefs.color_map = ExportColorMap::InvertLuminance;
+            let prefs = SelectionExportPrefs {
+                export_format: SelectionExportFormat::Png,
+                bitmap_scalefactor: 1.0,
+                ..Default::default()
+            };
+
+            let bytes = futures::executor::block_on(engine.export_selection(Some(prefs)))
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+            let content_pixel = image.get_pixel(image.width() / 2, image.height() / 2);
+            let margin_pixel = image.get_pixel(2, 2);
+            let is_dark = |pixel: &image::Rgba<u8>| pixel.0[..3].iter().all(|&c| c < 64);
+            let is_light = |pixel: &image::Rgba<u8>| pixel.0[..3].iter().all(|&c| c > 192);
+            if foreground == near_black {
+                assert!(is_light(content_pixel) && is_dark(margin_pixel));
+            } else {
+                assert!(is_dark(content_pixel) && is_light(margin_pixel));
+            }
+            // the stored stroke keeps its color
+            let Some(Stroke::ShapeStroke(shape_stroke)) = engine.store.get_stroke_ref(key) else {
+                panic!("the stroke is not a shape stroke");
+            };
+            assert_eq!(shape_stroke.style.stroke_color(), Some(foreground));
+        }
+    }
+
+    #[test]
+    fn document_export_applies_clip_below() {
+        let mut engine = Engine::default();
+        let filled_rect = |bounds: Aabb| {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(bounds)),
+                Style::Smooth(SmoothOptions {
+                    stroke_color: None,
+                    fill_color: Some(Color::BLACK),
+                    ..Default::default()
+                }),
+            ))
+        };
+        engine.store.insert_stroke(
+            filled_rect(Aabb::new(na::point![0.0, 0.0], na::point![10.0, 10.0])),
+            Some(StrokeLayer::UserLayer(0)),
+        );
+        engine.store.insert_stroke(
+            filled_rect(Aabb::new(na::point![0.0, 0.0], na::point![40.0, 40.0])),
+            Some(StrokeLayer::UserLayer(1)),
+        );
+        let _ = engine.set_layer_clip_below(1, true);
+
+        // only the alpha is drawn, which is independent of the byte order
+        let mut surface = cairo::ImageSurface::create(cairo::Format::A8, 40, 40).unwrap();
+        {
+            let cairo_cx = cairo::Context::new(&surface).unwrap();
+            engine
+                .extract_document_content()
+                .draw_to_cairo(&cairo_cx, false, false, false, 0.0, 1.0)
+                .unwrap();
+        }
+        surface.flush();
+        let stride = surface.stride() as usize;
+        let data = surface.data().unwrap();
+        let alpha = |x: usize, y: usize| data[y * stride + x];
+
+        // inside the layer below the clipped layer is visible
+        assert_eq!(alpha(5, 5), 255);
+        // outside the layer below the mask is empty, so nothing is drawn
+        assert_eq!(alpha(30, 30), 0);
+        assert_eq!(alpha(5, 30), 0);
+    }
+
+    #[test]
+    fn document_content_includes_pinned_strokes_on_pages() {
+        let mut engine = Engine::default();
+        let rect = |bounds: Aabb| {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(bounds)),
+                Style::default(),
+            ))
+        };
+        engine.store.insert_stroke(
+            rect(Aabb::new(
+                na::point![100.0, 100.0],
+                na::point![200.0, 200.0],
+            )),
+            None,
+        );
+        let _ = engine.add_pinned_stroke(rect(Aabb::new(
+            na::point![10.0, 10.0],
+            na::point![20.0, 20.0],
+        )));
+        let n_pages = engine.pages_bounds_w_content(Default::default()).len();
+
+        let without = engine.extract_document_content_w_pinned(Default::default(), false);
+        assert_eq!(without.strokes.len(), 1);
+        let with = engine.extract_document_content_w_pinned(Default::default(), true);
+        assert_eq!(with.strokes.len(), 1 + n_pages);
+    }
+}
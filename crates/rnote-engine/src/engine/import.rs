@@ -161,11 +161,17 @@ impl Engine {
         self.penholder = engine_config.penholder;
         self.import_prefs = engine_config.import_prefs;
         self.export_prefs = engine_config.export_prefs;
+        self.overlay = engine_config.overlay;
 
         // Set the pen sounds to update the audioplayer
         self.set_pen_sounds(engine_config.pen_sounds, data_dir);
 
         self.set_optimize_epd(engine_config.optimize_epd);
+        widget_flags |= self.set_antialiasing(engine_config.antialiasing);
+        widget_flags |= self.set_min_screen_width_px(engine_config.min_screen_width_px);
+        widget_flags |= self.set_zoom_limits(engine_config.zoom_limits);
+        self.set_rebase_input(engine_config.rebase_input);
+        self.set_reject_initial_jitter(engine_config.reject_initial_jitter);
 
         widget_flags |= self
             .penholder
@@ -195,11 +201,17 @@ impl Engine {
         self.penholder = engine_config.penholder;
         self.import_prefs = engine_config.import_prefs;
         self.export_prefs = engine_config.export_prefs;
+        self.overlay = engine_config.overlay;
 
         // Set the pen sounds to update the audioplayer
         self.set_pen_sounds(engine_config.pen_sounds, data_dir);
 
         self.set_optimize_epd(engine_config.optimize_epd);
+        widget_flags |= self.set_antialiasing(engine_config.antialiasing);
+        widget_flags |= self.set_min_screen_width_px(engine_config.min_screen_width_px);
+        widget_flags |= self.set_zoom_limits(engine_config.zoom_limits);
+        self.set_rebase_input(engine_config.rebase_input);
+        self.set_reject_initial_jitter(engine_config.reject_initial_jitter);
 
         widget_flags |= self
             .penholder
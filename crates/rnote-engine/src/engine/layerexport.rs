@@ -0,0 +1,168 @@
+// Imports
+use crate::store::chrono_comp::StrokeLayer;
+use crate::store::{LayerProps, StrokeKey};
+use crate::strokes::Stroke;
+use crate::{Engine, WidgetFlags};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// A stroke of an exported layer, together with its ordering and visibility within the layer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "layer_export_stroke")]
+pub struct LayerExportStroke {
+    #[serde(rename = "stroke")]
+    pub stroke: Stroke,
+    #[serde(rename = "z_index", default)]
+    pub z_index: i32,
+    #[serde(rename = "hidden", default)]
+    pub hidden: bool,
+}
+
+/// A single user layer exported from a document, so that it can be imported into other documents.
+///
+/// Contains the strokes of the layer in draw order and the layer properties, but no keys of the store it
+/// originated from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "layer_export")]
+pub struct LayerExport {
+    #[serde(rename = "props")]
+    pub props: LayerProps,
+    #[serde(rename = "strokes")]
+    pub strokes: Vec<LayerExportStroke>,
+}
+
+impl LayerExport {
+    pub const MIME_TYPE: &'static str = "application/rnote-layer";
+}
+
+impl Engine {
+    /// Export the user layer with its strokes and properties.
+    ///
+    /// Strokes on other layers are not included.
+    pub fn export_layer(&self, user_layer: u32) -> anyhow::Result<Vec<u8>> {
+        let strokes = self
+            .store
+            .keys_sorted_chrono()
+            .into_iter()
+            .filter(|&key| {
+                self.store.layer(key) == Some(StrokeLayer::UserLayer(user_layer))
+                    && self.store.trashed(key) == Some(false)
+            })
+            .filter_map(|key| {
+                Some(LayerExportStroke {
+                    stroke: self.store.get_stroke_ref(key)?.clone(),
+                    z_index: self.store.z_index(key)?,
+                    hidden: self.store.hidden(key)?,
+                })
+            })
+            .collect();
+        let layer_export = LayerExport {
+            props: self.store.layer_props(user_layer),
+            strokes,
+        };
+        Ok(serde_json::to_vec(&layer_export)?)
+    }
+
+    /// Import a layer that was exported with [Engine::export_layer] as a new user layer above all existing layers.
+    ///
+    /// The strokes are inserted with new keys. Returns the index of the new user layer.
+    pub fn import_layer(&mut self, bytes: &[u8]) -> anyhow::Result<(u32, WidgetFlags)> {
+        let mut widget_flags = WidgetFlags::default();
+        let layer_export = serde_json::from_slice::<LayerExport>(bytes)?;
+        let user_layer = self.store.next_user_layer();
+
+        let inserted = layer_export
+            .strokes
+            .into_iter()
+            .map(|entry| {
+                let key = self
+                    .store
+                    .insert_stroke(entry.stroke, Some(StrokeLayer::UserLayer(user_layer)));
+                self.store.set_z_index(key, entry.z_index);
+                self.store.set_hidden(key, entry.hidden);
+                key
+            })
+            .collect::<Vec<StrokeKey>>();
+        self.store.set_layer_props(user_layer, layer_export.props);
+
+        self.store.update_geometry_for_strokes(&inserted);
+        self.store.regenerate_rendering_in_viewport_threaded(
+            self.tasks_tx.clone(),
+            false,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+        widget_flags |= self.doc_resize_autoexpand();
+        widget_flags |= self.store.record(Instant::now());
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        Ok((user_layer, widget_flags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::chrono_comp::StrokeLayer;
+    use crate::store::LayerProps;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Line, Shape, Shapeable};
+    use rnote_compose::Style;
+
+    #[test]
+    fn layer_export_roundtrip() {
+        let line = |y: f64| {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Line(Line {
+                    start: na::vector![0.0, y],
+                    end: na::vector![100.0, y],
+                }),
+                Style::default(),
+            ))
+        };
+        let mut source = Engine::default();
+        let _ = source.store.insert_stroke(line(0.0), None);
+        let first = source
+            .store
+            .insert_stroke(line(10.0), Some(StrokeLayer::UserLayer(2)));
+        let second = source
+            .store
+            .insert_stroke(line(20.0), Some(StrokeLayer::UserLayer(2)));
+        source.store.set_hidden(second, true);
+        source.store.set_layer_props(
+            2,
+            LayerProps {
+                name: String::from("Sketch"),
+                clip_below: true,
+            },
+        );
+        let bounds = |engine: &Engine, keys: &[_]| {
+            keys.iter()
+                .map(|&key| engine.store.get_stroke_ref(key).unwrap().bounds())
+                .collect::<Vec<Aabb>>()
+        };
+        let exported_bounds = bounds(&source, &[first, second]);
+
+        let bytes = source.export_layer(2).unwrap();
+        let mut target = Engine::default();
+        let _ = target.store.insert_stroke(line(50.0), None);
+        let (user_layer, _) = target.import_layer(&bytes).unwrap();
+
+        assert_eq!(user_layer, 1);
+        assert_eq!(
+            target.store.layer_props(user_layer),
+            source.store.layer_props(2)
+        );
+        let imported = target
+            .store
+            .keys_sorted_chrono()
+            .into_iter()
+            .filter(|&key| target.store.layer(key) == Some(StrokeLayer::UserLayer(user_layer)))
+            .collect::<Vec<_>>();
+        assert_eq!(bounds(&target, &imported), exported_bounds);
+        assert_eq!(target.store.hidden(imported[1]), Some(true));
+        // the layer of the other document is left untouched
+        assert_eq!(target.store.stroke_keys_unordered().len(), 3);
+    }
+}
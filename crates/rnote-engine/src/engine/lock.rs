@@ -0,0 +1,145 @@
+// Imports
+use crate::store::StrokeKey;
+use crate::{Engine, WidgetFlags};
+use p2d::bounding_volume::Aabb;
+use std::collections::HashSet;
+use std::time::Instant;
+
+/// Which strokes are affected when locking a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionLockMode {
+    /// The strokes that are fully contained in the region.
+    Inside,
+    /// The strokes that are not fully contained in the region.
+    Outside,
+}
+
+/// Systems related to locking strokes.
+///
+/// Locked strokes are rendered like all other strokes, but they can't be selected or erased. This protects content
+/// like templates from accidental changes while annotating it.
+impl Engine {
+    /// Whether the stroke is locked. None when the stroke is not present.
+    pub fn stroke_locked(&self, key: StrokeKey) -> Option<bool> {
+        self.store.locked(key)
+    }
+
+    /// Lock or unlock the strokes.
+    pub fn set_strokes_locked(&mut self, keys: &[StrokeKey], locked: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let mut changed = false;
+        for &key in keys {
+            changed |= self.store.set_locked(key, locked);
+        }
+        if !changed {
+            return widget_flags;
+        }
+
+        widget_flags |= self.current_pen_update_state();
+        widget_flags |= self.record(Instant::now());
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    /// Lock or unlock all strokes inside or outside the region in bulk.
+    pub fn set_region_locked(
+        &mut self,
+        bounds: Aabb,
+        locked: bool,
+        mode: RegionLockMode,
+    ) -> WidgetFlags {
+        let inside = self.store.stroke_keys_as_rendered_in_bounds(bounds);
+        let keys = match mode {
+            RegionLockMode::Inside => inside,
+            RegionLockMode::Outside => {
+                let inside = inside.into_iter().collect::<HashSet<StrokeKey>>();
+                self.store
+                    .stroke_keys_as_rendered()
+                    .into_iter()
+                    .filter(|key| !inside.contains(key))
+                    .collect()
+            }
+        };
+        self.set_strokes_locked(&keys, locked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegionLockMode;
+    use crate::pens::PenStyle;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::penevent::PenEvent;
+    use rnote_compose::penpath::Element;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
+    use std::collections::HashSet;
+    use std::time::Instant;
+
+    #[test]
+    fn locked_region_survives_erasing() {
+        let mut engine = Engine::default();
+        let mut rect = |mins: na::Point2<f64>| {
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        mins,
+                        mins + na::vector![40.0, 40.0],
+                    ))),
+                    Style::default(),
+                )),
+                None,
+            )
+        };
+        let inside = [rect(na::point![20.0, 20.0]), rect(na::point![80.0, 20.0])];
+        let outside = [rect(na::point![220.0, 20.0]), rect(na::point![280.0, 20.0])];
+        let _ = engine.record(Instant::now());
+
+        let region = Aabb::new(na::point![0.0, 0.0], na::point![150.0, 100.0]);
+        let _ = engine.set_region_locked(region, true, RegionLockMode::Inside);
+        assert!(inside
+            .iter()
+            .all(|&key| engine.stroke_locked(key) == Some(true)));
+        assert!(outside
+            .iter()
+            .all(|&key| engine.stroke_locked(key) == Some(false)));
+
+        // swipe the eraser across all strokes
+        let _ = engine.change_pen_style(PenStyle::Eraser);
+        engine.pens_config.eraser_config.width = 20.0;
+        let now = Instant::now();
+        for x in (0..=34).map(|i| i as f64 * 10.0) {
+            let _ = engine.handle_pen_event(
+                PenEvent::Down {
+                    element: Element::new(na::vector![x, 40.0], 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                None,
+                now,
+            );
+        }
+        let _ = engine.handle_pen_event(
+            PenEvent::Up {
+                element: Element::new(na::vector![340.0, 40.0], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            None,
+            now,
+        );
+        assert!(inside
+            .iter()
+            .all(|&key| engine.store.trashed(key) == Some(false)));
+        assert!(outside
+            .iter()
+            .all(|&key| engine.store.trashed(key) == Some(true)));
+
+        // locked strokes can't be selected either
+        let _ = engine.select_all_strokes();
+        assert!(inside
+            .iter()
+            .all(|&key| engine.store.selected(key) == Some(false)));
+    }
+}
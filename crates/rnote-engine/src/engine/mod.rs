@@ -1,9 +1,23 @@
 // Modules
+pub mod boundaryfill;
+pub mod compound;
+pub mod dedupe;
 pub mod export;
 pub mod import;
+pub mod layerexport;
+pub mod lock;
+pub mod outline;
+pub mod overlay;
+pub mod pinned;
 pub mod rendering;
+pub mod repeat;
+pub mod similarity;
 pub mod snapshot;
+pub mod stats;
+pub mod stencil;
 pub mod strokecontent;
+pub mod thumbnail;
+pub mod visibility;
 pub mod visual_debug;
 
 // Re-exports
@@ -11,17 +25,31 @@ pub use export::ExportPrefs;
 use futures::channel::mpsc::UnboundedReceiver;
 use futures::StreamExt;
 pub use import::ImportPrefs;
+pub use layerexport::LayerExport;
+pub use lock::RegionLockMode;
+pub use outline::OutlineBoundary;
+pub use overlay::Overlay;
+pub use repeat::RepeatableAction;
+pub use similarity::ShapeDescriptor;
 pub use snapshot::EngineSnapshot;
+pub use stats::DocumentStats;
+pub use stencil::Stencil;
 pub use strokecontent::StrokeContent;
+pub use visibility::StrokeFilter;
 
 // Imports
+use crate::camera::ZoomLimits;
 use crate::document::Layout;
-use crate::pens::{Pen, PenStyle};
+use crate::inputclock::InputClock;
+use crate::jitterfilter::JitterFilter;
+use crate::pens::{CursorHint, Pen, PenStyle};
 use crate::pens::{PenMode, PensConfig};
+use crate::render::AaQuality;
 use crate::store::render_comp::{self, RenderCompState};
-use crate::store::StrokeKey;
+use crate::store::{Alignment, DistributeAxis, PickMode, StoreEvent, StrokeKey};
 use crate::strokes::content::GeneratedContentImages;
 use crate::strokes::textstroke::{TextAttribute, TextStyle};
+use crate::strokes::Stroke;
 use crate::{render, AudioPlayer, CloneConfig, SelectionCollision, WidgetFlags};
 use crate::{Camera, Document, PenHolder, StrokeStore};
 use futures::channel::{mpsc, oneshot};
@@ -29,12 +57,16 @@ use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::eventresult::EventPropagation;
 use rnote_compose::ext::AabbExt;
 use rnote_compose::penevent::{PenEvent, ShortcutKey};
+use rnote_compose::shapes::{convex_hull, Line, Shapeable};
 use rnote_compose::{Color, SplitOrder};
 use serde::{Deserialize, Serialize};
+use similarity::ShapeDescriptorCache;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::error;
+use thumbnail::ThumbnailCache;
+use tracing::{error, warn};
 
 /// An immutable view into the engine, excluding the penholder.
 #[derive(Debug)]
@@ -119,10 +151,22 @@ pub struct EngineConfig {
     import_prefs: ImportPrefs,
     #[serde(rename = "export_prefs")]
     export_prefs: ExportPrefs,
+    #[serde(rename = "overlay")]
+    overlay: Overlay,
     #[serde(rename = "pen_sounds")]
     pen_sounds: bool,
     #[serde(rename = "optimize_epd")]
     optimize_epd: bool,
+    #[serde(rename = "antialiasing")]
+    antialiasing: AaQuality,
+    #[serde(rename = "min_screen_width_px")]
+    min_screen_width_px: f64,
+    #[serde(rename = "zoom_limits")]
+    zoom_limits: ZoomLimits,
+    #[serde(rename = "rebase_input")]
+    rebase_input: bool,
+    #[serde(rename = "reject_initial_jitter")]
+    reject_initial_jitter: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -167,15 +211,29 @@ pub struct Engine {
     pub import_prefs: ImportPrefs,
     #[serde(rename = "export_prefs")]
     pub export_prefs: ExportPrefs,
+    #[serde(rename = "overlay")]
+    pub overlay: Overlay,
     #[serde(rename = "pen_sounds")]
     pen_sounds: bool,
     #[serde(rename = "optimize_epd")]
     optimize_epd: bool,
+    #[serde(rename = "antialiasing")]
+    antialiasing: AaQuality,
+    #[serde(rename = "min_screen_width_px")]
+    min_screen_width_px: f64,
+    #[serde(rename = "reject_initial_jitter")]
+    reject_initial_jitter: bool,
+    /// Strokes pinned to the viewport, in surface coordinates.
+    #[serde(rename = "pinned_strokes")]
+    pinned_strokes: Vec<Arc<Stroke>>,
 
     #[serde(skip)]
     audioplayer: Option<AudioPlayer>,
     #[serde(skip)]
     visual_debug: bool,
+    /// Draw a graph of the raw and remapped pen pressure while drawing with the brush.
+    #[serde(skip)]
+    debug_overlay: bool,
     // the task sender. Must not be modified, only cloned.
     #[serde(skip)]
     tasks_tx: EngineTaskSender,
@@ -193,6 +251,20 @@ pub struct Engine {
     #[cfg(feature = "ui")]
     #[serde(skip)]
     origin_indicator_rendernode: Option<gtk4::gsk::RenderNode>,
+    #[serde(skip)]
+    thumbnail_cache: Option<ThumbnailCache>,
+    #[serde(skip)]
+    shape_descriptor_cache: ShapeDescriptorCache,
+    // The time of the last nudge and the store revision it produced, to coalesce consecutive nudges.
+    #[serde(skip)]
+    last_nudge: Option<(Instant, u64)>,
+    #[serde(skip)]
+    input_clock: InputClock,
+    #[serde(skip)]
+    jitter_filter: JitterFilter,
+    // The jitter filters of the pointers that are currently in use, keyed by pointer id.
+    #[serde(skip)]
+    pointer_jitter_filters: HashMap<u32, JitterFilter>,
 }
 
 impl Default for Engine {
@@ -207,11 +279,17 @@ impl Default for Engine {
             penholder: PenHolder::default(),
             import_prefs: ImportPrefs::default(),
             export_prefs: ExportPrefs::default(),
+            overlay: Overlay::default(),
             pen_sounds: false,
             optimize_epd: false,
+            antialiasing: AaQuality::default(),
+            min_screen_width_px: 0.0,
+            reject_initial_jitter: false,
+            pinned_strokes: Vec::new(),
 
             audioplayer: None,
             visual_debug: false,
+            debug_overlay: false,
             tasks_tx: EngineTaskSender(tasks_tx),
             tasks_rx: Some(EngineTaskReceiver(tasks_rx)),
             background_tile_image: None,
@@ -220,6 +298,12 @@ impl Default for Engine {
             origin_indicator_image: None,
             #[cfg(feature = "ui")]
             origin_indicator_rendernode: None,
+            thumbnail_cache: None,
+            shape_descriptor_cache: ShapeDescriptorCache::default(),
+            last_nudge: None,
+            input_clock: InputClock::default(),
+            jitter_filter: JitterFilter::default(),
+            pointer_jitter_filters: HashMap::default(),
         }
     }
 }
@@ -296,6 +380,62 @@ impl Engine {
         self.optimize_epd = optimize_epd
     }
 
+    /// The anti-aliasing quality of the interactive rendering.
+    pub fn antialiasing(&self) -> AaQuality {
+        self.antialiasing
+    }
+
+    /// Set the anti-aliasing quality of the interactive rendering.
+    ///
+    /// Bitmap exports and rendered clipboard images always use [AaQuality::High].
+    pub fn set_antialiasing(&mut self, antialiasing: AaQuality) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.antialiasing == antialiasing && self.store.aa_quality() == antialiasing {
+            return widget_flags;
+        }
+        self.antialiasing = antialiasing;
+        self.store.set_aa_quality(antialiasing);
+        self.store
+            .set_rendering_dirty_for_strokes(&self.store.stroke_keys_unordered());
+        widget_flags |= self.background_rendering_regenerate()
+            | self.update_content_rendering_current_viewport();
+        widget_flags
+    }
+
+    /// The minimum width of rendered strokes in screen pixels. Zero when disabled.
+    pub fn min_screen_width_px(&self) -> f64 {
+        self.min_screen_width_px
+    }
+
+    /// Set the minimum width of rendered strokes in screen pixels. Zero disables it.
+    ///
+    /// Only affects the rendering on screen, the stroke widths are not altered.
+    pub fn set_min_screen_width_px(&mut self, min_screen_width_px: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.store.set_min_screen_width_px(min_screen_width_px);
+        let min_screen_width_px = self.store.min_screen_width_px();
+        if self.min_screen_width_px == min_screen_width_px {
+            return widget_flags;
+        }
+        self.min_screen_width_px = min_screen_width_px;
+        self.store
+            .set_rendering_dirty_for_strokes(&self.store.stroke_keys_unordered());
+        widget_flags |= self.update_content_rendering_current_viewport();
+        widget_flags
+    }
+
+    /// The number of appended segment images of the currently drawn stroke, after which they are merged into a single image.
+    /// Zero when disabled.
+    pub fn current_stroke_cache_threshold(&self) -> usize {
+        self.store.current_stroke_cache_threshold()
+    }
+
+    /// Set the number of appended segment images of the currently drawn stroke, after which they are merged into a single image.
+    /// Zero disables the cache.
+    pub fn set_current_stroke_cache_threshold(&mut self, threshold: usize) {
+        self.store.set_current_stroke_cache_threshold(threshold);
+    }
+
     pub fn visual_debug(&self) -> bool {
         self.visual_debug
     }
@@ -307,6 +447,31 @@ impl Engine {
         widget_flags
     }
 
+    pub fn debug_overlay(&self) -> bool {
+        self.debug_overlay
+    }
+
+    /// Enable or disable the pen pressure debug overlay, which plots the raw and the remapped pressure of the most
+    /// recent input while drawing with the brush.
+    pub fn set_debug_overlay(&mut self, debug_overlay: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.debug_overlay = debug_overlay;
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Subscribe to notifications about added, removed, modified and (de)selected strokes.
+    ///
+    /// Replaces any previous subscription. The events are sent on the thread that owns the engine.
+    pub fn subscribe_store_events(&mut self) -> UnboundedReceiver<StoreEvent> {
+        self.store.subscribe_events()
+    }
+
+    /// Unsubscribe from the store notifications.
+    pub fn unsubscribe_store_events(&mut self) {
+        self.store.unsubscribe_events()
+    }
+
     /// Takes a snapshot of the current state.
     pub fn take_snapshot(&self) -> EngineSnapshot {
         let mut store_history_entry = self.store.create_history_entry();
@@ -328,13 +493,21 @@ impl Engine {
             stroke_components: Arc::clone(&store_history_entry.stroke_components),
             chrono_components: Arc::clone(&store_history_entry.chrono_components),
             chrono_counter: store_history_entry.chrono_counter,
+            layer_props: store_history_entry.layer_props.as_ref().clone(),
+            pinned_strokes: self.pinned_strokes.clone(),
         }
     }
 
     /// Imports an engine snapshot. A save file should always be loaded with this method.
     pub fn load_snapshot(&mut self, snapshot: EngineSnapshot) -> WidgetFlags {
         self.document = snapshot.document.clone_config();
+        // The zoom limits and input rebasing are part of the engine config, so they are kept
+        let zoom_limits = self.camera.zoom_limits();
+        let rebase_input = self.camera.rebase_input();
         self.camera = snapshot.camera.clone_config();
+        let _ = self.camera.set_zoom_limits(zoom_limits);
+        self.camera.set_rebase_input(rebase_input);
+        self.pinned_strokes = snapshot.pinned_strokes.clone();
         let mut widget_flags = self.store.import_from_snapshot(&snapshot)
             | self.doc_resize_autoexpand()
             | self.current_pen_update_state()
@@ -355,6 +528,27 @@ impl Engine {
         self.store.update_latest_history_entry(now)
     }
 
+    /// Begin a history transaction. All changes until it is committed become a single undo step.
+    ///
+    /// Useful for continuous edits like dragging a slider that changes the selection live.
+    pub fn begin_history_transaction(&mut self) {
+        self.store.begin_transaction();
+    }
+
+    /// Commit the history transaction that was begun with [Engine::begin_history_transaction].
+    pub fn commit_history_transaction(&mut self) -> WidgetFlags {
+        self.store.commit_transaction();
+        let mut widget_flags = WidgetFlags::default();
+        widget_flags.hide_undo = Some(!self.can_undo());
+        widget_flags.hide_redo = Some(!self.can_redo());
+        widget_flags
+    }
+
+    /// Whether a history transaction is open.
+    pub fn in_history_transaction(&self) -> bool {
+        self.store.in_transaction()
+    }
+
     /// Undo the latest changes.
     pub fn undo(&mut self, now: Instant) -> WidgetFlags {
         self.store.undo(now)
@@ -470,15 +664,103 @@ impl Engine {
     }
 
     /// Handle a pen event.
+    ///
+    /// The time of the event is made monotonic, so that pens never see input that goes back in time.
     pub fn handle_pen_event(
         &mut self,
         event: PenEvent,
         pen_mode: Option<PenMode>,
         now: Instant,
     ) -> (EventPropagation, WidgetFlags) {
-        self.penholder.handle_pen_event(
+        self.handle_pen_event_w_pointer(event, None, pen_mode, now)
+    }
+
+    /// Handle a received pen event of the pointer with the given id, see [PenHolder::handle_pen_event_for_pointer].
+    ///
+    /// Every pointer has its own jitter filter, so that the input of multiple pens does not get mixed up.
+    pub fn handle_pen_event_for_pointer(
+        &mut self,
+        event: PenEvent,
+        pointer_id: u32,
+        pen_mode: Option<PenMode>,
+        now: Instant,
+    ) -> (EventPropagation, WidgetFlags) {
+        self.handle_pen_event_w_pointer(event, Some(pointer_id), pen_mode, now)
+    }
+
+    fn handle_pen_event_w_pointer(
+        &mut self,
+        event: PenEvent,
+        pointer_id: Option<u32>,
+        pen_mode: Option<PenMode>,
+        now: Instant,
+    ) -> (EventPropagation, WidgetFlags) {
+        let now = self.input_clock.timestamp(now);
+        // a pointer that lifted or left the proximity starts a new stroke with a fresh filter
+        let released = matches!(
             event,
-            pen_mode,
+            PenEvent::Up { .. } | PenEvent::Proximity { .. } | PenEvent::Cancel
+        );
+        let reject_jitter = self.reject_initial_jitter
+            && self.penholder.current_pen_style_w_override() == PenStyle::Brush;
+        let total_zoom = self.camera.total_zoom();
+        let jitter_filter = match pointer_id {
+            Some(pointer_id) => self.pointer_jitter_filters.entry(pointer_id).or_default(),
+            None => &mut self.jitter_filter,
+        };
+        let events = jitter_filter.filter_event(event, now, reject_jitter, total_zoom);
+        if released {
+            if let Some(pointer_id) = pointer_id {
+                self.pointer_jitter_filters.remove(&pointer_id);
+            }
+        }
+        if events.is_empty() {
+            return (EventPropagation::Stop, WidgetFlags::default());
+        }
+
+        let mut propagate = EventPropagation::Proceed;
+        let mut widget_flags = WidgetFlags::default();
+        for (event, now) in events {
+            let mut engine_view = EngineViewMut {
+                tasks_tx: self.engine_tasks_tx(),
+                pens_config: &mut self.pens_config,
+                document: &mut self.document,
+                store: &mut self.store,
+                camera: &mut self.camera,
+                audioplayer: &mut self.audioplayer,
+            };
+            let (p, wf) = match pointer_id {
+                Some(pointer_id) => self.penholder.handle_pen_event_for_pointer(
+                    event,
+                    pointer_id,
+                    pen_mode,
+                    now,
+                    &mut engine_view,
+                ),
+                None => self
+                    .penholder
+                    .handle_pen_event(event, pen_mode, now, &mut engine_view),
+            };
+            propagate |= p;
+            widget_flags |= wf;
+        }
+        (propagate, widget_flags)
+    }
+
+    /// Prepare a batch of input with their event times before handling it.
+    ///
+    /// When reordering the input is enabled in the penholder, the batch gets sorted by time and input that is older
+    /// than the previously handled input is dropped.
+    pub fn reorder_input_batch<T>(&self, batch: &mut Vec<(T, Instant)>) {
+        if self.penholder.reorder_input() {
+            self.input_clock.reorder_batch(batch);
+        }
+    }
+
+    /// Handle a new display frame. Commits pen input that was buffered while throttled, and finishes the current pen
+    /// action when the pen up event got lost.
+    pub fn handle_frame_tick(&mut self, now: Instant) -> WidgetFlags {
+        self.penholder.handle_frame_tick(
             now,
             &mut EngineViewMut {
                 tasks_tx: self.engine_tasks_tx(),
@@ -491,6 +773,11 @@ impl Engine {
         )
     }
 
+    /// Whether [Self::handle_frame_tick] needs to be called on new display frames.
+    pub fn needs_frame_tick(&self) -> bool {
+        self.penholder.needs_frame_tick()
+    }
+
     /// Handle a pressed shortcut key.
     pub fn handle_pressed_shortcut_key(
         &mut self,
@@ -526,6 +813,19 @@ impl Engine {
         )
     }
 
+    /// The cursor hint for the current pen, to be applied by the UI when the pointer hovers over the canvas.
+    pub fn cursor_hint(&self) -> CursorHint {
+        self.penholder.cursor_hint(&self.view())
+    }
+
+    /// The region that was last captured with the crop tool, when the tools pen is the current pen.
+    pub fn crop_region(&self) -> Option<Aabb> {
+        match self.penholder.current_pen_ref() {
+            Pen::Tools(tools) => tools.crop_region(),
+            _ => None,
+        }
+    }
+
     /// Change the pen style (temporary) override.
     pub fn change_pen_style_override(
         &mut self,
@@ -544,6 +844,18 @@ impl Engine {
         )
     }
 
+    /// Set the phase of the dashed selector outline, in surface coordinates.
+    ///
+    /// Meant to be incremented by the UI's frame clock, so that the engine does not need to keep track of time.
+    pub fn set_selector_dash_phase(&mut self, dash_phase: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if let Pen::Selector(selector) = self.penholder.current_pen_mut() {
+            selector.set_dash_phase(dash_phase);
+            widget_flags.redraw = true;
+        }
+        widget_flags
+    }
+
     /// Change the pen mode. Relevant for stylus input.
     pub fn change_pen_mode(&mut self, pen_mode: PenMode) -> WidgetFlags {
         self.penholder.change_pen_mode(
@@ -559,6 +871,18 @@ impl Engine {
         )
     }
 
+    /// The stroke color of the current pen. Is `None` if the current pen has no color.
+    pub fn current_pen_color(&self) -> Option<Color> {
+        self.pens_config
+            .stroke_color(self.penholder.current_pen_style_w_override())
+    }
+
+    /// Set the stroke color of the current pen. Does nothing if the current pen has no color.
+    pub fn set_current_pen_color(&mut self, color: Color) {
+        self.pens_config
+            .set_stroke_color(self.penholder.current_pen_style_w_override(), color);
+    }
+
     /// Reinstall the pen in the current style.
     pub fn reinstall_pen_current_style(&mut self) -> WidgetFlags {
         self.penholder
@@ -657,6 +981,57 @@ impl Engine {
             | self.update_rendering_current_viewport()
     }
 
+    /// The limits the camera zoom is constrained to.
+    pub fn zoom_limits(&self) -> ZoomLimits {
+        self.camera.zoom_limits()
+    }
+
+    /// Set the limits the camera zoom is constrained to.
+    ///
+    /// Background and content rendering then need to be updated when the zoom changed.
+    pub fn set_zoom_limits(&mut self, zoom_limits: ZoomLimits) -> WidgetFlags {
+        self.camera.set_zoom_limits(zoom_limits)
+    }
+
+    /// Whether pen input gets transformed relative to the view center, improving the precision at high zoom on very
+    /// large documents.
+    pub fn rebase_input(&self) -> bool {
+        self.camera.rebase_input()
+    }
+
+    pub fn set_rebase_input(&mut self, rebase_input: bool) {
+        self.camera.set_rebase_input(rebase_input);
+    }
+
+    /// Whether the jitter at the start of brush strokes gets rejected, see [JitterFilter].
+    ///
+    /// Applies to the input handled with [Self::handle_pen_event] and [Self::handle_pen_event_for_pointer].
+    pub fn reject_initial_jitter(&self) -> bool {
+        self.reject_initial_jitter
+    }
+
+    pub fn set_reject_initial_jitter(&mut self, reject_initial_jitter: bool) {
+        self.reject_initial_jitter = reject_initial_jitter;
+    }
+
+    /// Zoom and move the view so that the content fits into it, or the document when there is no content.
+    ///
+    /// The zoom stays within the zoom limits.
+    pub fn fit_view(&mut self) -> WidgetFlags {
+        let bounds = self
+            .store
+            .bounds_for_strokes(&self.store.stroke_keys_as_rendered())
+            .unwrap_or(self.document.bounds());
+        let mut widget_flags = self.camera.fit_view(bounds);
+
+        let all_strokes = self.store.stroke_keys_unordered();
+        self.store.set_rendering_dirty_for_strokes(&all_strokes);
+        widget_flags |= self.doc_resize_autoexpand()
+            | self.background_rendering_regenerate()
+            | self.update_rendering_current_viewport();
+        widget_flags
+    }
+
     pub fn return_to_origin(&mut self, parent_width: Option<f64>) -> WidgetFlags {
         let zoom = self.camera.zoom();
         let new_offset = if let Some(parent_width) = parent_width {
@@ -681,6 +1056,29 @@ impl Engine {
         self.camera_set_offset_expand(new_offset)
     }
 
+    /// Set the dimensions of the document.
+    ///
+    /// Setting dimensions smaller than the content is allowed, but logs a warning.
+    /// In autoexpanding layouts the document might get resized again to fit the content.
+    ///
+    /// Background rendering then needs to be updated.
+    pub fn set_doc_dimensions(&mut self, width: f64, height: f64) -> anyhow::Result<WidgetFlags> {
+        self.document.set_dimensions(width, height)?;
+        if let Some(content_bounds) = self
+            .store
+            .bounds_for_strokes(&self.store.stroke_keys_as_rendered())
+        {
+            if !self.document.bounds().contains(&content_bounds) {
+                warn!(
+                    "Document dimensions {width}x{height} were set smaller than the bounds of the content."
+                );
+            }
+        }
+        let mut widget_flags = self.update_rendering_current_viewport();
+        widget_flags.resize = true;
+        Ok(widget_flags)
+    }
+
     /// Resize the doc when in autoexpanding layouts. called e.g. when finishing a new stroke.
     ///
     /// Background rendering then needs to be updated.
@@ -726,6 +1124,73 @@ impl Engine {
         widget_flags
     }
 
+    /// Remove empty pages between the content by moving the content of all following pages up.
+    ///
+    /// Only applies to the fixed-size and continuous-vertical layouts, where the pages are stacked vertically.
+    /// Strokes are moved by whole page heights, so their position relative to their page is kept.
+    pub fn reflow_pages(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let page_height = self.document.format.height();
+        if !self.document.layout.is_fixed_width() || page_height <= 0.0 {
+            return widget_flags;
+        }
+        let doc_y = self.document.y;
+        let page_index = |y: f64| ((y - doc_y) / page_height).floor().max(0.0) as usize;
+
+        // Hidden strokes are moved as well
+        let strokes = self
+            .store
+            .stroke_keys_unordered()
+            .into_iter()
+            .filter_map(|key| Some((key, self.store.get_stroke_ref(key)?.bounds())))
+            .collect::<Vec<(StrokeKey, Aabb)>>();
+        let n_pages = strokes
+            .iter()
+            .map(|(_, bounds)| page_index(bounds.maxs[1]) + 1)
+            .max()
+            .unwrap_or(0);
+        let mut occupied = vec![false; n_pages];
+        for (_, bounds) in strokes.iter() {
+            for page in page_index(bounds.mins[1])..=page_index(bounds.maxs[1]) {
+                occupied[page] = true;
+            }
+        }
+        // The number of empty pages above each page
+        let empty_above = occupied
+            .iter()
+            .scan(0, |n_empty, &occupied| {
+                let above = *n_empty;
+                if !occupied {
+                    *n_empty += 1;
+                }
+                Some(above)
+            })
+            .collect::<Vec<usize>>();
+
+        let mut moved = vec![];
+        for (key, bounds) in strokes {
+            let n_empty = empty_above[page_index(bounds.mins[1])];
+            if n_empty == 0 {
+                continue;
+            }
+            let offset = na::vector![0.0, -(n_empty as f64) * page_height];
+            self.store.translate_strokes(&[key], offset);
+            self.store.translate_strokes_images(&[key], offset);
+            moved.push(key);
+        }
+        if moved.is_empty() {
+            return widget_flags;
+        }
+
+        self.store.update_geometry_for_strokes(&moved);
+        widget_flags |= self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now());
+        widget_flags.resize = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
     /// Update the viewport offset of the camera, clamped to mins and maxs values depending on the document layout.
     ///
     /// Background and content rendering then need to be updated.
@@ -749,6 +1214,13 @@ impl Engine {
         self.camera.set_size(size)
     }
 
+    /// Update the view rotation of the camera, in radians around the center of the viewport.
+    ///
+    /// Background and content rendering then need to be updated.
+    pub fn camera_set_view_rotation(&mut self, view_rotation: f64) -> WidgetFlags {
+        self.camera.set_view_rotation(view_rotation)
+    }
+
     /// Update the viewport size of the camera.
     ///
     /// Background and content rendering then need to be updated.
@@ -810,6 +1282,23 @@ impl Engine {
         }
     }
 
+    /// Set the document background color.
+    ///
+    /// The brush stroke color follows the background when enabled in the brush config.
+    pub fn set_doc_background_color(&mut self, color: Color) -> WidgetFlags {
+        let prev_color = std::mem::replace(&mut self.document.background.color, color);
+        let mut widget_flags = self.background_rendering_regenerate();
+        if self
+            .pens_config
+            .brush_config
+            .follow_background(prev_color, color)
+        {
+            widget_flags.refresh_ui = true;
+        }
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
     pub fn select_all_strokes(&mut self) -> WidgetFlags {
         let widget_flags = self.change_pen_style(PenStyle::Selector);
         self.store
@@ -832,6 +1321,19 @@ impl Engine {
             | self.update_rendering_current_viewport()
     }
 
+    /// Whether the point lies over the current selection or its handles, when the selector is the current pen.
+    pub fn hit_selection(&self, point: na::Point2<f64>) -> bool {
+        match self.penholder.current_pen_ref() {
+            Pen::Selector(selector) => selector.hit_selection(point, &self.view()),
+            _ => false,
+        }
+    }
+
+    /// The stroke at the position. When multiple strokes overlap, the pick mode decides which one is returned.
+    pub fn hit_test(&self, pos: na::Vector2<f64>, mode: PickMode) -> Option<StrokeKey> {
+        self.store.hit_test(pos, mode)
+    }
+
     pub fn select_with_bounds(
         &mut self,
         bounds: Aabb,
@@ -858,6 +1360,107 @@ impl Engine {
             | self.update_rendering_current_viewport()
     }
 
+    /// Create `count` copies of the strokes, like the array modifier known from CAD.
+    ///
+    /// Every copy is translated by the offset and rotated by the rotation step (in radians) relative to the previous
+    /// one. Recorded as a single history entry. Returns the keys of the copies, which will be selected.
+    pub fn array_duplicate(
+        &mut self,
+        keys: &[StrokeKey],
+        count: usize,
+        offset: na::Vector2<f64>,
+        rotation_step: f64,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let new_keys = self
+            .store
+            .array_duplicate_strokes(keys, count, offset, rotation_step);
+        if new_keys.is_empty() {
+            return (new_keys, WidgetFlags::default());
+        }
+        self.store.update_geometry_for_strokes(&new_keys);
+        let widget_flags = self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport();
+        (new_keys, widget_flags)
+    }
+
+    /// Transform the strokes with an arbitrary affine transformation, as a single undoable step.
+    ///
+    /// Moving, rotating, scaling and mirroring are all special cases of it, and transformations can be composed by
+    /// multiplying the matrices. The stroke widths are scaled along when `scale_width` is true.
+    pub fn transform_selection(
+        &mut self,
+        keys: &[StrokeKey],
+        matrix: na::Affine2<f64>,
+        scale_width: bool,
+    ) -> WidgetFlags {
+        if keys.is_empty() {
+            return WidgetFlags::default();
+        }
+        self.store.transform_strokes(keys, matrix, scale_width);
+        self.store.update_geometry_for_strokes(keys);
+        self.store.regenerate_rendering_for_strokes_threaded(
+            self.engine_tasks_tx(),
+            keys,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+        let mut widget_flags = self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport();
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    /// Create mirrored copies of the strokes across the axis line, for example for symmetric designs.
+    ///
+    /// Unlike flipping, the original strokes are kept. Recorded as a single history entry. Returns the keys of the
+    /// copies, which will be selected.
+    pub fn mirror_copy_selection(
+        &mut self,
+        keys: &[StrokeKey],
+        axis_line: Line,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let new_keys = self.store.mirror_duplicate_strokes(keys, axis_line);
+        if new_keys.is_empty() {
+            return (new_keys, WidgetFlags::default());
+        }
+        self.store.update_geometry_for_strokes(&new_keys);
+        self.store.regenerate_rendering_for_strokes_threaded(
+            self.engine_tasks_tx(),
+            &new_keys,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+        let widget_flags = self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport();
+        (new_keys, widget_flags)
+    }
+
+    /// The convex hull of all points of the outlines of the strokes, with curves flattened.
+    ///
+    /// See [rnote_compose::shapes::convex_hull] for the order of the vertices. Empty when none of the keys exist.
+    pub fn selection_hull(&self, keys: &[StrokeKey]) -> Vec<na::Point2<f64>> {
+        const FLATTEN_TOLERANCE: f64 = 0.25;
+        let mut points = Vec::new();
+        for stroke in keys
+            .iter()
+            .filter_map(|&key| self.store.get_stroke_ref(key))
+        {
+            kurbo::flatten(stroke.outline_path(), FLATTEN_TOLERANCE, |el| match el {
+                kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) => {
+                    points.push(na::point![p.x, p.y])
+                }
+                _ => {}
+            });
+        }
+        convex_hull(&points)
+    }
+
     pub fn trash_selection(&mut self) -> WidgetFlags {
         let selection_keys = self.store.selection_keys_as_rendered();
         self.store.set_trashed_keys(&selection_keys, true);
@@ -867,6 +1470,60 @@ impl Engine {
             | self.update_rendering_current_viewport()
     }
 
+    /// Delete all strokes contained in or intersecting the given region, as a single undoable step.
+    pub fn delete_region(&mut self, bounds: Aabb, collision: SelectionCollision) -> WidgetFlags {
+        if self
+            .store
+            .trash_strokes_in_region(bounds, collision)
+            .is_empty()
+        {
+            return WidgetFlags::default();
+        }
+        self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport()
+    }
+
+    /// Align the selected strokes to each other.
+    pub fn align_selection(&mut self, alignment: Alignment) -> WidgetFlags {
+        let moved = self
+            .store
+            .align_strokes(&self.store.selection_keys_as_rendered(), alignment);
+        self.arrange_selection_finish(&moved, RepeatableAction::Align(alignment))
+    }
+
+    /// Distribute the selected strokes along the axis with even spacing.
+    pub fn distribute_selection(&mut self, axis: DistributeAxis) -> WidgetFlags {
+        let moved = self
+            .store
+            .distribute_strokes(&self.store.selection_keys_as_rendered(), axis);
+        self.arrange_selection_finish(&moved, RepeatableAction::Distribute(axis))
+    }
+
+    fn arrange_selection_finish(
+        &mut self,
+        moved: &[StrokeKey],
+        action: RepeatableAction,
+    ) -> WidgetFlags {
+        if moved.is_empty() {
+            return WidgetFlags::default();
+        }
+        self.store.update_geometry_for_strokes(moved);
+        self.store.regenerate_rendering_for_strokes_threaded(
+            self.engine_tasks_tx(),
+            moved,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+        let mut widget_flags = self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.store.record_w_action(Instant::now(), action)
+            | self.update_rendering_current_viewport();
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
     pub fn nothing_selected(&self) -> bool {
         self.store.selection_keys_unordered().is_empty()
     }
@@ -874,21 +1531,75 @@ impl Engine {
     pub fn change_selection_stroke_colors(&mut self, stroke_color: Color) -> WidgetFlags {
         self.store
             .change_stroke_colors(&self.store.selection_keys_as_rendered(), stroke_color)
-            | self.record(Instant::now())
+            | self.store.record_w_action(
+                Instant::now(),
+                RepeatableAction::ChangeStrokeColor(stroke_color),
+            )
             | self.update_content_rendering_current_viewport()
     }
 
     pub fn change_selection_fill_colors(&mut self, fill_color: Color) -> WidgetFlags {
         self.store
             .change_fill_colors(&self.store.selection_keys_as_rendered(), fill_color)
-            | self.record(Instant::now())
+            | self.store.record_w_action(
+                Instant::now(),
+                RepeatableAction::ChangeFillColor(fill_color),
+            )
             | self.update_content_rendering_current_viewport()
     }
 
+    /// Change the z-index of the selected strokes, which orders them within their layer.
+    pub fn change_selection_z_index(&mut self, z_index: i32) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        for key in self.store.selection_keys_as_rendered() {
+            if self.store.z_index(key) != Some(z_index) {
+                self.store.set_z_index(key, z_index);
+                widget_flags.store_modified = true;
+            }
+        }
+        widget_flags.redraw = true;
+        widget_flags
+            | self
+                .store
+                .record_w_action(Instant::now(), RepeatableAction::ChangeZIndex(z_index))
+    }
+
+    /// Set whether the user layer is clipped to the content of the layer below it.
+    pub fn set_layer_clip_below(&mut self, user_layer: u32, clip_below: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let mut layer_props = self.store.layer_props(user_layer);
+        if layer_props.clip_below != clip_below {
+            layer_props.clip_below = clip_below;
+            self.store.set_layer_props(user_layer, layer_props);
+            widget_flags.store_modified = true;
+            widget_flags.redraw = true;
+            widget_flags |= self.store.record(Instant::now());
+        }
+        widget_flags
+    }
+
+    /// Move the selected strokes onto a new user layer above all existing layers, keeping their draw order.
+    ///
+    /// Returns the index of the new user layer, or `None` when nothing is selected and no layer was created.
+    pub fn move_selection_to_new_layer(&mut self, name: String) -> (Option<u32>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if selection_keys.is_empty() {
+            return (None, widget_flags);
+        }
+        let new_layer = self.store.move_strokes_to_new_layer(&selection_keys, name);
+        widget_flags.store_modified = true;
+        widget_flags.redraw = true;
+        widget_flags |= self.store.record(Instant::now());
+        (Some(new_layer), widget_flags)
+    }
+
     pub fn invert_selection_colors(&mut self) -> WidgetFlags {
         self.store
             .invert_color_brightness(&self.store.selection_keys_as_rendered())
-            | self.record(Instant::now())
+            | self
+                .store
+                .record_w_action(Instant::now(), RepeatableAction::InvertColors)
             | self.update_content_rendering_current_viewport()
     }
 
@@ -1000,3 +1711,403 @@ impl Engine {
         widget_flags
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Engine;
+    use crate::document::{Layout, PageLayout};
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::WidgetFlags;
+    use approx::assert_relative_eq;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::builders::PenPathBuilderType;
+    use rnote_compose::penevent::PenEvent;
+    use rnote_compose::penpath::Element;
+    use rnote_compose::shapes::{Line, Polyline, Rectangle, Shape, Shapeable};
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::{Color, Style};
+    use std::collections::HashSet;
+    use std::time::{Duration, Instant};
+
+    pub(crate) fn polyline_stroke(points: &[na::Vector2<f64>]) -> Stroke {
+        Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Polyline(Polyline {
+                start: points[0],
+                path: points[1..].to_vec(),
+            }),
+            Style::default(),
+        ))
+    }
+
+    #[test]
+    fn move_empty_selection_to_new_layer() {
+        let mut engine = Engine::default();
+        let key = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Line(Line {
+                    start: na::vector![0.0, 0.0],
+                    end: na::vector![10.0, 10.0],
+                }),
+                Style::default(),
+            )),
+            None,
+        );
+
+        let (new_layer, widget_flags) = engine.move_selection_to_new_layer(String::from("Empty"));
+        assert_eq!(new_layer, None);
+        assert_eq!(widget_flags, WidgetFlags::default());
+        assert!(!engine.store.can_undo());
+
+        engine.store.set_selected_keys(&[key], true);
+        let (new_layer, widget_flags) =
+            engine.move_selection_to_new_layer(String::from("Selected"));
+        assert_eq!(new_layer, Some(1));
+        assert!(widget_flags.store_modified);
+    }
+
+    #[test]
+    fn transform_selection_applies_affine_matrix() {
+        let mut engine = Engine::default();
+        let key = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Polyline(Polyline {
+                    start: na::vector![10.0, 0.0],
+                    path: vec![na::vector![20.0, 0.0], na::vector![20.0, 5.0]],
+                }),
+                Style::Smooth(SmoothOptions {
+                    stroke_width: 2.0,
+                    ..Default::default()
+                }),
+            )),
+            None,
+        );
+        let _ = engine.record(Instant::now());
+
+        // scale x by 2 and y by 3, then rotate by 90 degrees and move
+        let matrix = na::Affine2::from_matrix_unchecked(
+            na::Translation2::new(100.0, 50.0).to_homogeneous()
+                * na::Rotation2::new(std::f64::consts::FRAC_PI_2).to_homogeneous()
+                * na::Matrix3::new_nonuniform_scaling(&na::vector![2.0, 3.0]),
+        );
+        let _ = engine.transform_selection(&[key], matrix, true);
+        let Some(Stroke::ShapeStroke(shape_stroke)) = engine.store.get_stroke_ref(key) else {
+            panic!("the stroke is not a shape stroke");
+        };
+        let Shape::Polyline(polyline) = &shape_stroke.shape else {
+            panic!("the shape is not a polyline");
+        };
+        for (transformed, expected) in std::iter::once(&polyline.start)
+            .chain(polyline.path.iter())
+            .zip([
+                na::vector![100.0, 70.0],
+                na::vector![100.0, 90.0],
+                na::vector![85.0, 90.0],
+            ])
+        {
+            assert_relative_eq!(*transformed, expected, epsilon = 1e-9);
+        }
+        assert_relative_eq!(
+            shape_stroke.style.stroke_width(),
+            2.0 * 6.0_f64.sqrt(),
+            epsilon = 1e-9
+        );
+        let bounds = engine.store.get_stroke_ref(key).unwrap().bounds();
+        assert!(bounds.mins[0] < 85.0 && bounds.maxs[1] > 90.0);
+
+        // undoable as a single step
+        let _ = engine.undo(Instant::now());
+        let Some(Stroke::ShapeStroke(shape_stroke)) = engine.store.get_stroke_ref(key) else {
+            panic!("the stroke is not a shape stroke");
+        };
+        let Shape::Polyline(polyline) = &shape_stroke.shape else {
+            panic!("the shape is not a polyline");
+        };
+        assert_relative_eq!(polyline.start, na::vector![10.0, 0.0]);
+    }
+
+    #[test]
+    fn history_transaction_coalesces_changes() {
+        let mut engine = Engine::default();
+        let key = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![10.0, 10.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        engine.store.set_selected_keys(&[key], true);
+        let _ = engine.record(Instant::now());
+        let stroke_color = |engine: &Engine| match engine.store.get_stroke_ref(key) {
+            Some(Stroke::ShapeStroke(shape_stroke)) => shape_stroke.style.stroke_color(),
+            _ => None,
+        };
+        let initial_color = stroke_color(&engine);
+
+        engine.begin_history_transaction();
+        for i in 1..=10 {
+            let _ =
+                engine.change_selection_stroke_colors(Color::new(i as f64 * 0.1, 0.0, 0.0, 1.0));
+        }
+        let _ = engine.commit_history_transaction();
+        assert!(!engine.in_history_transaction());
+        assert_eq!(stroke_color(&engine), Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+
+        // a single undo step reverts all recolors
+        let _ = engine.undo(Instant::now());
+        assert_eq!(stroke_color(&engine), initial_color);
+        let _ = engine.undo(Instant::now());
+        assert!(engine.store.get_stroke_ref(key).is_none());
+        let _ = engine.redo(Instant::now());
+        let _ = engine.redo(Instant::now());
+        assert_eq!(stroke_color(&engine), Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+        assert!(!engine.can_redo());
+
+        // changes after the transaction are recorded separately again
+        let _ = engine.change_selection_stroke_colors(Color::BLACK);
+        let _ = engine.undo(Instant::now());
+        assert_eq!(stroke_color(&engine), Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn array_duplicate_places_copies_at_offsets() {
+        let mut engine = Engine::default();
+        let key = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![10.0, 10.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        let _ = engine.record(Instant::now());
+
+        let (copies, _) = engine.array_duplicate(&[key], 3, na::vector![20.0, 5.0], 0.0);
+        assert_eq!(copies.len(), 3);
+        for (i, &copy) in copies.iter().enumerate() {
+            let bounds = engine.store.get_stroke_ref(copy).unwrap().bounds();
+            let expected = na::vector![20.0, 5.0] * (i + 1) as f64;
+            assert_relative_eq!(bounds.mins.coords, expected, epsilon = 1e-9);
+            assert_relative_eq!(
+                bounds.maxs.coords,
+                expected + na::vector![10.0, 10.0],
+                epsilon = 1e-9
+            );
+            assert_eq!(engine.store.selected(copy), Some(true));
+        }
+        assert_eq!(engine.store.selected(key), Some(false));
+
+        // the whole array is undone at once
+        let _ = engine.undo(Instant::now());
+        assert!(copies
+            .iter()
+            .all(|&copy| engine.store.trashed(copy) != Some(false)));
+        assert_eq!(engine.store.trashed(key), Some(false));
+    }
+
+    #[test]
+    fn mirror_copy_reflects_across_axis() {
+        let mut engine = Engine::default();
+        let points = [
+            na::vector![10.0, 0.0],
+            na::vector![30.0, 10.0],
+            na::vector![40.0, 50.0],
+        ];
+        let key = engine.store.insert_stroke(polyline_stroke(&points), None);
+        let _ = engine.record(Instant::now());
+
+        // the oblique axis through (0, 20) with a slope of 1/2
+        let axis_line = Line {
+            start: na::vector![0.0, 20.0],
+            end: na::vector![40.0, 40.0],
+        };
+        let (copies, _) = engine.mirror_copy_selection(&[key], axis_line);
+        assert_eq!(copies.len(), 1);
+        let Some(Stroke::ShapeStroke(copy)) = engine.store.get_stroke_ref(copies[0]) else {
+            panic!("the mirrored copy is not a shape stroke");
+        };
+        let Shape::Polyline(polyline) = &copy.shape else {
+            panic!("the mirrored copy is not a polyline");
+        };
+        let mirrored = std::iter::once(polyline.start)
+            .chain(polyline.path.iter().copied())
+            .collect::<Vec<na::Vector2<f64>>>();
+        let direction = (axis_line.end - axis_line.start).normalize();
+        for (original, mirrored) in points.iter().zip(mirrored.iter()) {
+            // the axis is the perpendicular bisector of every original point and its reflection
+            let midpoint = (original + mirrored) * 0.5 - axis_line.start;
+            assert_relative_eq!(midpoint.perp(&direction), 0.0, epsilon = 1e-9);
+            assert_relative_eq!((mirrored - original).dot(&direction), 0.0, epsilon = 1e-9);
+        }
+        assert_relative_eq!(mirrored[0], na::vector![-10.0, 40.0], epsilon = 1e-9);
+        assert_eq!(copy.style.stroke_width(), Style::default().stroke_width());
+
+        // the original is kept unchanged
+        assert_eq!(engine.store.trashed(key), Some(false));
+        assert_eq!(engine.store.selected(key), Some(false));
+        assert_eq!(engine.store.selected(copies[0]), Some(true));
+        let Some(Stroke::ShapeStroke(original)) = engine.store.get_stroke_ref(key) else {
+            panic!("the original is not a shape stroke");
+        };
+        let Shape::Polyline(polyline) = &original.shape else {
+            panic!("the original is not a polyline");
+        };
+        assert_eq!(polyline.start, points[0]);
+    }
+
+    #[test]
+    fn selection_hull_encloses_stroke_points() {
+        let mut engine = Engine::default();
+        let polyline = |start: na::Vector2<f64>, path: Vec<na::Vector2<f64>>| {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Polyline(Polyline { start, path }),
+                Style::default(),
+            ))
+        };
+        let a = engine.store.insert_stroke(
+            polyline(
+                na::vector![0.0, 0.0],
+                vec![na::vector![10.0, 5.0], na::vector![20.0, 0.0]],
+            ),
+            None,
+        );
+        let b = engine.store.insert_stroke(
+            polyline(
+                na::vector![5.0, 5.0],
+                vec![na::vector![10.0, 20.0], na::vector![15.0, 5.0]],
+            ),
+            None,
+        );
+        // not part of the selection
+        engine.store.insert_stroke(
+            polyline(na::vector![100.0, 100.0], vec![na::vector![120.0, 80.0]]),
+            None,
+        );
+
+        assert_eq!(
+            engine.selection_hull(&[a, b]),
+            vec![
+                na::point![0.0, 0.0],
+                na::point![20.0, 0.0],
+                na::point![10.0, 20.0],
+            ]
+        );
+        assert!(engine.selection_hull(&[]).is_empty());
+    }
+
+    #[test]
+    fn reordered_input_commits_monotonic_geometry() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.penholder.set_reorder_input(true);
+        let start = Instant::now();
+        let handle_batch = |engine: &mut Engine, millis: &[u64]| {
+            let mut batch = millis
+                .iter()
+                .map(|&millis| {
+                    (
+                        Element::new(na::vector![100.0 + millis as f64, 100.0], 0.5),
+                        start + Duration::from_millis(millis),
+                    )
+                })
+                .collect::<Vec<(Element, Instant)>>();
+            engine.reorder_input_batch(&mut batch);
+            for (element, time) in batch {
+                let _ = engine.handle_pen_event(
+                    PenEvent::Down {
+                        element,
+                        modifier_keys: HashSet::new(),
+                    },
+                    None,
+                    time,
+                );
+            }
+        };
+
+        handle_batch(&mut engine, &[0, 10]);
+        // shuffled, with a point that is older than the last committed one
+        handle_batch(&mut engine, &[40, 20, 5, 50, 30]);
+
+        let keys = engine.store.stroke_keys_unordered();
+        assert_eq!(keys.len(), 1);
+        let Some(Stroke::BrushStroke(brushstroke)) = engine.store.get_stroke_ref(keys[0]) else {
+            panic!("no brush stroke was drawn");
+        };
+        let xs = std::iter::once(brushstroke.path.start.pos.x)
+            .chain(brushstroke.path.segments.iter().map(|seg| seg.end().pos.x))
+            .collect::<Vec<f64>>();
+        assert!(xs.windows(2).all(|w| w[0] <= w[1]));
+        assert!(!xs.contains(&105.0));
+        assert_relative_eq!(*xs.last().unwrap(), 150.0);
+    }
+
+    #[test]
+    fn reflow_pages_shifts_later_strokes_up() {
+        let mut engine = Engine::default();
+        engine.document.layout = Layout::FixedSize;
+        engine.document.page_layout = PageLayout::TrimTrailingPages;
+        let page_height = engine.document.format.height();
+        engine.document.height = page_height * 3.0;
+        let mut insert_rect = |page: f64| {
+            let mins = na::point![20.0, page * page_height + 40.0];
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        mins,
+                        mins + na::vector![100.0, 50.0],
+                    ))),
+                    Style::default(),
+                )),
+                None,
+            )
+        };
+        let first = insert_rect(0.0);
+        let middle = insert_rect(1.0);
+        let last = insert_rect(2.0);
+        let last_bounds = engine.store.get_stroke_ref(last).unwrap().bounds();
+
+        // remove the content of the middle page
+        engine.store.set_trashed_keys(&[middle], true);
+        let _ = engine.record(Instant::now());
+        let _ = engine.reflow_pages();
+
+        assert_relative_eq!(
+            engine
+                .store
+                .get_stroke_ref(first)
+                .unwrap()
+                .bounds()
+                .mins
+                .coords,
+            na::vector![20.0, 40.0],
+            epsilon = 1e-9
+        );
+        let reflowed = engine.store.get_stroke_ref(last).unwrap().bounds();
+        assert_relative_eq!(
+            reflowed.mins.coords,
+            last_bounds.mins.coords - na::vector![0.0, page_height],
+            epsilon = 1e-9
+        );
+        // the now empty trailing page is trimmed
+        assert_relative_eq!(engine.document.height, page_height * 2.0);
+
+        // and it can be undone
+        let _ = engine.undo(Instant::now());
+        assert_relative_eq!(
+            engine
+                .store
+                .get_stroke_ref(last)
+                .unwrap()
+                .bounds()
+                .mins
+                .coords,
+            last_bounds.mins.coords,
+            epsilon = 1e-9
+        );
+    }
+}
@@ -0,0 +1,134 @@
+// Imports
+use crate::store::StrokeKey;
+use crate::strokes::{ShapeStroke, Stroke};
+use crate::{Engine, WidgetFlags};
+use geo::{ConcaveHull, ConvexHull};
+use p2d::bounding_volume::BoundingVolume;
+use rnote_compose::shapes::Shapeable;
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::{Color, Style};
+use std::time::Instant;
+
+/// The boundary that is traced when outlining strokes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutlineBoundary {
+    /// The convex hull around all strokes.
+    #[default]
+    ConvexHull,
+    /// A concave boundary that follows the gaps between the strokes more closely.
+    Concave,
+}
+
+impl Engine {
+    /// The padding between the outlined strokes and the outline.
+    pub const OUTLINE_PADDING: f64 = 8.0;
+    /// The concavity of the concave boundary. Lower values follow the strokes more closely.
+    const OUTLINE_CONCAVITY: f64 = 2.0;
+
+    /// Trace the outline around the strokes as a new stroke, for example to circle a group or to create a border.
+    ///
+    /// The outline keeps a padding of [Engine::OUTLINE_PADDING] to the stroke bounds. Recorded as a single history
+    /// entry. Returns the key of the new stroke, None when no strokes are given.
+    pub fn outline_selection(
+        &mut self,
+        keys: &[StrokeKey],
+        color: Color,
+        width: f64,
+        boundary: OutlineBoundary,
+    ) -> (Option<StrokeKey>, WidgetFlags) {
+        let points = keys
+            .iter()
+            .filter_map(|&key| self.store.get_stroke_ref(key))
+            .flat_map(|stroke| {
+                stroke
+                    .bounds()
+                    .loosened(Self::OUTLINE_PADDING + width * 0.5)
+                    .vertices()
+            })
+            .map(|vertex| geo::Point::new(vertex[0], vertex[1]))
+            .collect::<geo::MultiPoint<f64>>();
+        if points.0.is_empty() {
+            return (None, WidgetFlags::default());
+        }
+        let hull = match boundary {
+            OutlineBoundary::ConvexHull => points.convex_hull(),
+            OutlineBoundary::Concave => points.concave_hull(Self::OUTLINE_CONCAVITY),
+        };
+        let Some(shape) = crate::utils::geo_polygon_to_shape(&hull) else {
+            return (None, WidgetFlags::default());
+        };
+
+        let stroke = Stroke::ShapeStroke(ShapeStroke::new(
+            shape,
+            Style::Smooth(SmoothOptions {
+                stroke_width: width,
+                stroke_color: Some(color),
+                fill_color: None,
+                ..Default::default()
+            }),
+        ));
+        let key = self.store.insert_stroke(stroke, None);
+        self.store.regenerate_rendering_for_stroke(
+            key,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+
+        let widget_flags = self.doc_resize_autoexpand()
+            | self.record(Instant::now())
+            | self.update_rendering_current_viewport();
+        (Some(key), widget_flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutlineBoundary;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use geo::{Area, Contains};
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape, Shapeable};
+    use rnote_compose::{Color, Style};
+
+    #[test]
+    fn outline_encloses_strokes() {
+        let mut engine = Engine::default();
+        // three squares arranged in an L
+        let keys = [
+            na::point![0.0, 0.0],
+            na::point![0.0, 100.0],
+            na::point![100.0, 100.0],
+        ]
+        .map(|mins| {
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        mins,
+                        mins + na::vector![50.0, 50.0],
+                    ))),
+                    Style::default(),
+                )),
+                None,
+            )
+        });
+
+        let areas = [OutlineBoundary::ConvexHull, OutlineBoundary::Concave].map(|boundary| {
+            let (outline_key, _) = engine.outline_selection(&keys, Color::BLACK, 2.0, boundary);
+            let outline = engine.store.get_stroke_ref(outline_key.unwrap()).unwrap();
+            let polygon =
+                crate::utils::bezpath_to_geo_polygon(&outline.outline_path(), 0.1).unwrap();
+            for &key in keys.iter() {
+                let bounds = engine.store.get_stroke_ref(key).unwrap().bounds();
+                for vertex in bounds.vertices() {
+                    assert!(
+                        polygon.contains(&geo::point!(x: vertex[0], y: vertex[1])),
+                        "{boundary:?} outline does not enclose stroke bounds"
+                    );
+                }
+            }
+            polygon.unsigned_area()
+        });
+        assert!(areas[1] <= areas[0] + 1e-6);
+    }
+}
@@ -0,0 +1,77 @@
+// Imports
+use crate::{CloneConfig, Engine, WidgetFlags};
+use rnote_compose::Color;
+use serde::{Deserialize, Serialize};
+
+/// The editing overlay.
+///
+/// Drawn on top of everything else while editing, but never part of the document or any export.
+/// Unlike the [Background](crate::document::Background) it is stored with the engine configuration, not the document.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "overlay")]
+pub struct Overlay {
+    /// Whether the grid is shown.
+    #[serde(rename = "show_grid")]
+    pub show_grid: bool,
+    /// The grid spacing in document coordinates.
+    #[serde(rename = "grid_spacing", with = "rnote_compose::serialize::f64_dp3")]
+    pub grid_spacing: f64,
+    /// The grid line color.
+    #[serde(rename = "grid_color")]
+    pub grid_color: Color,
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self {
+            show_grid: false,
+            grid_spacing: Self::GRID_SPACING_DEFAULT,
+            grid_color: Self::GRID_COLOR_DEFAULT,
+        }
+    }
+}
+
+impl CloneConfig for Overlay {
+    fn clone_config(&self) -> Self {
+        *self
+    }
+}
+
+impl Overlay {
+    /// The default grid spacing.
+    pub const GRID_SPACING_DEFAULT: f64 = 32.0;
+    /// The minimum grid spacing.
+    pub const GRID_SPACING_MIN: f64 = 1.0;
+    /// The maximum grid spacing.
+    pub const GRID_SPACING_MAX: f64 = 512.0;
+    /// The default grid line color.
+    pub const GRID_COLOR_DEFAULT: Color = Color {
+        r: 0.27,
+        g: 0.52,
+        b: 0.93,
+        a: 0.35,
+    };
+    /// The grid line width in surface coordinates.
+    pub(crate) const GRID_LINE_WIDTH: f64 = 1.0;
+    /// The grid is not drawn when the spacing on the surface gets smaller than this.
+    pub(crate) const GRID_SPACING_SURFACE_MIN: f64 = 4.0;
+}
+
+impl Engine {
+    /// Show or hide the overlay grid.
+    pub fn set_overlay_show_grid(&mut self, show_grid: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.overlay.show_grid = show_grid;
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Set the overlay grid spacing. Gets clamped to the allowed range.
+    pub fn set_overlay_grid_spacing(&mut self, grid_spacing: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.overlay.grid_spacing =
+            grid_spacing.clamp(Overlay::GRID_SPACING_MIN, Overlay::GRID_SPACING_MAX);
+        widget_flags.redraw = true;
+        widget_flags
+    }
+}
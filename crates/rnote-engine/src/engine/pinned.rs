@@ -0,0 +1,152 @@
+// Imports
+use crate::strokes::Stroke;
+use crate::{Camera, Engine, WidgetFlags};
+use p2d::bounding_volume::Aabb;
+use rnote_compose::shapes::Shapeable;
+use std::sync::Arc;
+
+/// Systems related to pinned strokes.
+///
+/// Pinned strokes stay in place on the screen while the canvas is moved or zoomed, useful for annotations like page
+/// numbers or watermarks. They are positioned in surface coordinates: the origin is the top-left corner of the
+/// visible canvas area and one unit is one logical surface pixel, regardless of the camera offset and zoom.
+///
+/// Pinned strokes are not part of the store, so they are excluded from the content bounds, selections and the
+/// history. When exporting the document, they can optionally be included. They are then placed relative to the
+/// top-left corner of every page, with one unit being one document coordinate unit.
+impl Engine {
+    /// The pinned strokes, in surface coordinates.
+    pub fn pinned_strokes(&self) -> &[Arc<Stroke>] {
+        &self.pinned_strokes
+    }
+
+    /// Add a stroke that is pinned to the viewport. It must be positioned in surface coordinates.
+    pub fn add_pinned_stroke(&mut self, stroke: Stroke) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.pinned_strokes.push(Arc::new(stroke));
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    /// Remove all pinned strokes.
+    pub fn clear_pinned_strokes(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if !self.pinned_strokes.is_empty() {
+            self.pinned_strokes.clear();
+            widget_flags.redraw = true;
+            widget_flags.store_modified = true;
+        }
+        widget_flags
+    }
+
+    /// The bounds where the pinned strokes currently appear on the document, in document coordinates.
+    pub fn pinned_strokes_doc_bounds(&self, camera: &Camera) -> Vec<Aabb> {
+        self.pinned_strokes
+            .iter()
+            .map(|stroke| camera.transform_inv_bounds(stroke.bounds()))
+            .collect()
+    }
+
+    /// Draw the pinned strokes. Expects the snapshot to be untransformed, in surface coordinates.
+    #[cfg(feature = "ui")]
+    pub(crate) fn draw_pinned_strokes_to_gtk_snapshot(
+        &self,
+        snapshot: &gtk4::Snapshot,
+        surface_bounds: Aabb,
+    ) -> anyhow::Result<()> {
+        use crate::ext::GrapheneRectExt;
+        use crate::Drawable;
+        use gtk4::{graphene, prelude::*};
+        use p2d::bounding_volume::BoundingVolume;
+
+        if !self
+            .pinned_strokes
+            .iter()
+            .any(|stroke| stroke.bounds().intersects(&surface_bounds))
+        {
+            return Ok(());
+        }
+
+        let cairo_cx = snapshot.append_cairo(&graphene::Rect::from_p2d_aabb(surface_bounds));
+        let mut piet_cx = piet_cairo::CairoRenderContext::new(&cairo_cx);
+        for stroke in self
+            .pinned_strokes
+            .iter()
+            .filter(|stroke| stroke.bounds().intersects(&surface_bounds))
+        {
+            stroke.draw(&mut piet_cx, self.camera.scale_factor())?;
+        }
+        piet_cx.finish().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+        Ok(())
+    }
+
+    /// Clones of the pinned strokes placed relative to the given page origin, in document coordinates.
+    pub(crate) fn pinned_strokes_for_page(&self, page_bounds: Aabb) -> Vec<Arc<Stroke>> {
+        use rnote_compose::transform::Transformable;
+
+        self.pinned_strokes
+            .iter()
+            .map(|stroke| {
+                let mut stroke = (**stroke).clone();
+                stroke.translate(page_bounds.mins.coords);
+                Arc::new(stroke)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::{Camera, Engine};
+    use approx::assert_relative_eq;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape, Shapeable};
+    use rnote_compose::Style;
+
+    #[test]
+    fn pinned_stroke_stays_on_screen() {
+        let mut engine = Engine::default();
+        let _ = engine.add_pinned_stroke(Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![10.0, 20.0],
+                na::point![50.0, 40.0],
+            ))),
+            Style::default(),
+        )));
+
+        let cameras = [
+            Camera::default(),
+            Camera::default().with_offset(na::vector![300.0, -120.0]),
+            Camera::default()
+                .with_zoom(2.5)
+                .with_offset(na::vector![-40.0, 800.0]),
+        ];
+        let surface_bounds = cameras
+            .iter()
+            .map(|camera| camera.transform_bounds(engine.pinned_strokes_doc_bounds(camera)[0]))
+            .collect::<Vec<Aabb>>();
+
+        for bounds in surface_bounds.iter().skip(1) {
+            assert_relative_eq!(
+                bounds.mins.coords,
+                surface_bounds[0].mins.coords,
+                epsilon = 1e-9
+            );
+            assert_relative_eq!(
+                bounds.maxs.coords,
+                surface_bounds[0].maxs.coords,
+                epsilon = 1e-9
+            );
+        }
+        // but the position on the document differs
+        let doc_bounds = engine.pinned_strokes_doc_bounds(&cameras[1])[0];
+        assert_relative_eq!(
+            doc_bounds.mins.coords,
+            engine.pinned_strokes()[0].bounds().mins.coords + na::vector![300.0, -120.0],
+            epsilon = 1e-9
+        );
+    }
+}
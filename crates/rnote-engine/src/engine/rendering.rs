@@ -1,6 +1,8 @@
 // Imports
+use super::StrokeContent;
 use crate::render::Image;
 use crate::{Engine, WidgetFlags};
+use futures::channel::oneshot;
 use p2d::bounding_volume::Aabb;
 use piet::RenderContext;
 use rnote_compose::color;
@@ -33,10 +35,17 @@ impl Engine {
                     }
                 };
 
-                for split_bounds in viewport.split_extended_origin_aligned(
-                    self.document.background.tile_size(),
-                    SplitOrder::default(),
-                ) {
+                // The tiles are aligned to the pattern origin
+                let tile_offset = self.document.background.tile_offset();
+                for split_bounds in viewport
+                    .translate(-tile_offset)
+                    .split_extended_origin_aligned(
+                        self.document.background.tile_size(),
+                        SplitOrder::default(),
+                    )
+                    .into_iter()
+                    .map(|bounds| bounds.translate(tile_offset))
+                {
                     rendernodes.push(
                         gsk::TextureNode::new(
                             &new_texture,
@@ -122,13 +131,58 @@ impl Engine {
         widget_flags
     }
 
+    /// Rasterize the background and the content inside the bounds on the calling thread.
+    pub fn render_region(&self, bounds: Aabb, image_scale: f64) -> anyhow::Result<Image> {
+        render_region_content(self.region_render_content(bounds), bounds, image_scale)
+    }
+
+    /// Rasterize the background and the content inside the bounds on the rayon thread pool, keeping the calling
+    /// thread responsive.
+    ///
+    /// The strokes are shared with the store, so changes to the engine after the call are not reflected in the
+    /// rendered image. The caller composites the image once it is received. Strokes that are in progress are still
+    /// drawn synchronously by the pens.
+    pub fn render_region_async(
+        &self,
+        bounds: Aabb,
+        image_scale: f64,
+    ) -> oneshot::Receiver<anyhow::Result<Image>> {
+        let (sender, receiver) = oneshot::channel::<anyhow::Result<Image>>();
+        let content = self.region_render_content(bounds);
+        rayon::spawn(move || {
+            if sender
+                .send(render_region_content(content, bounds, image_scale))
+                .is_err()
+            {
+                error!("Sending rendered region in `render_region_async()` failed, receiver already dropped.");
+            }
+        });
+        receiver
+    }
+
+    fn region_render_content(&self, bounds: Aabb) -> StrokeContent {
+        let (strokes, clip_groups) = self.store.get_strokes_arc_w_clip_groups(
+            self.store
+                .stroke_keys_as_rendered_intersecting_bounds(bounds),
+        );
+        StrokeContent::default()
+            .with_strokes(strokes)
+            .with_clip_groups(clip_groups)
+            .with_bounds(Some(bounds))
+            .with_background(Some(self.document.background))
+    }
+
     /// Regenerate the background tile image, origin indicator and updates the background rendering.
     pub fn background_rendering_regenerate(&mut self) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
         let image_scale = self.camera.image_scale();
         let scale_factor = self.camera.scale_factor();
 
-        match self.document.background.gen_tile_image(image_scale) {
+        match self
+            .document
+            .background
+            .gen_tile_image(image_scale, self.antialiasing)
+        {
             Ok(image) => {
                 self.background_tile_image = Some(image);
             }
@@ -179,6 +233,7 @@ impl Engine {
         self.store
             .draw_strokes_to_gtk_snapshot(snapshot, doc_bounds, viewport);
         snapshot.restore();
+        self.draw_pinned_strokes_to_gtk_snapshot(snapshot, surface_bounds)?;
         /*
                let cairo_cx = snapshot.append_cairo(&graphene::Rect::from_p2d_aabb(surface_bounds));
                let mut piet_cx = piet_cairo::CairoRenderContext::new(&cairo_cx);
@@ -210,6 +265,24 @@ impl Engine {
 
             visual_debug::draw_statistics_to_gtk_snapshot(snapshot, self, surface_bounds)?;
         }
+        if self.debug_overlay {
+            if let Some(samples) = match self.penholder.current_pen_ref() {
+                crate::pens::Pen::Brush(brush) => brush.pressure_samples(),
+                _ => None,
+            } {
+                visual_debug::draw_pressure_graph_to_gtk_snapshot(
+                    snapshot,
+                    samples,
+                    surface_bounds,
+                )?;
+            }
+        }
+
+        // The overlay is always drawn last
+        snapshot.save();
+        snapshot.transform(Some(&camera_transform));
+        self.draw_overlay_to_gtk_snapshot(snapshot)?;
+        snapshot.restore();
 
         Ok(())
     }
@@ -326,6 +399,51 @@ impl Engine {
         Ok(())
     }
 
+    /// Draw the editing overlay. It is only visible on the canvas and never exported.
+    #[cfg(feature = "ui")]
+    fn draw_overlay_to_gtk_snapshot(&self, snapshot: &gtk4::Snapshot) -> anyhow::Result<()> {
+        use crate::engine::Overlay;
+        use crate::ext::{GdkRGBAExt, GrapheneRectExt};
+        use gtk4::{gdk, graphene, prelude::*};
+
+        if !self.overlay.show_grid {
+            return Ok(());
+        }
+        let total_zoom = self.camera.total_zoom();
+        let spacing = self.overlay.grid_spacing;
+        if spacing * total_zoom < Overlay::GRID_SPACING_SURFACE_MIN {
+            return Ok(());
+        }
+        let line_width = Overlay::GRID_LINE_WIDTH / total_zoom;
+        let viewport = self.camera.viewport();
+        let color = gdk::RGBA::from_compose_color(self.overlay.grid_color);
+
+        let mut x = (viewport.mins[0] / spacing).floor() * spacing;
+        while x <= viewport.maxs[0] {
+            snapshot.append_color(
+                &color,
+                &graphene::Rect::from_p2d_aabb(Aabb::new(
+                    na::point![x - line_width * 0.5, viewport.mins[1]],
+                    na::point![x + line_width * 0.5, viewport.maxs[1]],
+                )),
+            );
+            x += spacing;
+        }
+        let mut y = (viewport.mins[1] / spacing).floor() * spacing;
+        while y <= viewport.maxs[1] {
+            snapshot.append_color(
+                &color,
+                &graphene::Rect::from_p2d_aabb(Aabb::new(
+                    na::point![viewport.mins[0], y - line_width * 0.5],
+                    na::point![viewport.maxs[0], y + line_width * 0.5],
+                )),
+            );
+            y += spacing;
+        }
+
+        Ok(())
+    }
+
     /// Draw the document origin indicator cross.
     #[cfg(feature = "ui")]
     fn draw_origin_indicator_to_gtk_snapshot(
@@ -387,3 +505,68 @@ fn gen_origin_indicator_image(scale_factor: f64) -> anyhow::Result<Image> {
         scale_factor,
     )
 }
+
+fn render_region_content(
+    content: StrokeContent,
+    bounds: Aabb,
+    image_scale: f64,
+) -> anyhow::Result<Image> {
+    Image::gen_with_cairo(
+        |cairo_cx| content.draw_to_cairo(cairo_cx, true, true, false, 0.0, image_scale),
+        bounds,
+        image_scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::Image;
+    use crate::strokes::{Content, ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
+
+    #[test]
+    fn async_render_matches_sync_render() {
+        let mut engine = Engine::default();
+        let rect = |mins: na::Point2<f64>, maxs: na::Point2<f64>| {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(mins, maxs))),
+                Style::default(),
+            ))
+        };
+        let key = engine
+            .store
+            .insert_stroke(rect(na::point![20.0, 10.0], na::point![80.0, 70.0]), None);
+        let bounds = Aabb::new(na::point![0.0, 0.0], na::point![120.0, 80.0]);
+        let image_scale = 1.5;
+
+        let receiver = engine.render_region_async(bounds, image_scale);
+        // changes after the call are not reflected in the rendered image
+        engine
+            .store
+            .insert_stroke(rect(na::point![90.0, 10.0], na::point![110.0, 70.0]), None);
+        let async_image = futures::executor::block_on(receiver).unwrap().unwrap();
+
+        // drawn synchronously, the background and the stroke directly for reference
+        let stroke = engine.store.get_stroke_ref(key).unwrap();
+        let sync_image = Image::gen_with_cairo(
+            |cairo_cx| {
+                engine
+                    .document
+                    .background
+                    .draw_to_cairo(cairo_cx, bounds, true, false)?;
+                stroke.draw_to_cairo(cairo_cx, image_scale)
+            },
+            bounds,
+            image_scale,
+        )
+        .unwrap();
+        assert_eq!(
+            (sync_image.pixel_width, sync_image.pixel_height),
+            (async_image.pixel_width, async_image.pixel_height)
+        );
+        assert_eq!(sync_image.data, async_image.data);
+    }
+}
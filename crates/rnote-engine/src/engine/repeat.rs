@@ -0,0 +1,311 @@
+// Imports
+use super::Engine;
+use crate::store::{Alignment, DistributeAxis, StrokeKey};
+use crate::WidgetFlags;
+use rnote_compose::Color;
+use std::time::{Duration, Instant};
+
+/// A command that was applied to the selection and can be repeated on the current selection.
+///
+/// It is stored alongside the history entry that recorded it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepeatableAction {
+    /// Translate by the offset.
+    Translate {
+        /// The offset.
+        offset: na::Vector2<f64>,
+    },
+    /// Rotate around the center of the selection bounds.
+    Rotate {
+        /// The angle in radians.
+        angle: f64,
+    },
+    /// Resize with a pivot relative to the selection bounds.
+    Resize {
+        /// The scale factors.
+        scale: na::Vector2<f64>,
+        /// The position of the pivot relative to the selection bounds,
+        /// where [0.0, 0.0] is the top-left corner and [1.0, 1.0] the bottom-right corner.
+        pivot: na::Vector2<f64>,
+    },
+    /// Change the stroke colors.
+    ChangeStrokeColor(Color),
+    /// Change the fill colors.
+    ChangeFillColor(Color),
+    /// Invert the color brightness.
+    InvertColors,
+    /// Change the z-index.
+    ChangeZIndex(i32),
+    /// Align the strokes to each other.
+    Align(Alignment),
+    /// Distribute the strokes evenly.
+    Distribute(DistributeAxis),
+}
+
+impl RepeatableAction {
+    /// The minimum amount of selected strokes needed so that the action can be applied.
+    pub fn min_selection_len(&self) -> usize {
+        match self {
+            Self::Align(_) => 2,
+            Self::Distribute(_) => 3,
+            _ => 1,
+        }
+    }
+}
+
+impl Engine {
+    /// The time window within which consecutive nudges are coalesced into a single history entry.
+    pub const NUDGE_COALESCE_WINDOW: Duration = Duration::from_millis(500);
+
+    /// The action that was applied to the selection and that produced the current state, if any.
+    pub fn last_action(&self) -> Option<RepeatableAction> {
+        self.store.last_action()
+    }
+
+    /// Repeat the last action on the current selection.
+    ///
+    /// Errors when there is no action to repeat or the current selection is not compatible with it.
+    pub fn repeat_last_action(&mut self) -> anyhow::Result<WidgetFlags> {
+        let Some(action) = self.last_action() else {
+            return Err(anyhow::anyhow!("There is no action that can be repeated."));
+        };
+        let selection_len = self.store.selection_keys_unordered().len();
+        if selection_len < action.min_selection_len() {
+            return Err(anyhow::anyhow!(
+                "Repeating action {action:?} needs at least {} selected strokes, but {selection_len} are selected.",
+                action.min_selection_len()
+            ));
+        }
+
+        let widget_flags = match action {
+            RepeatableAction::Translate { offset } => self.translate_selection(offset),
+            RepeatableAction::Rotate { angle } => self.rotate_selection(angle),
+            RepeatableAction::Resize { scale, pivot } => self.resize_selection(scale, pivot),
+            RepeatableAction::ChangeStrokeColor(color) => {
+                self.change_selection_stroke_colors(color)
+            }
+            RepeatableAction::ChangeFillColor(color) => self.change_selection_fill_colors(color),
+            RepeatableAction::InvertColors => self.invert_selection_colors(),
+            RepeatableAction::ChangeZIndex(z_index) => self.change_selection_z_index(z_index),
+            RepeatableAction::Align(alignment) => self.align_selection(alignment),
+            RepeatableAction::Distribute(axis) => self.distribute_selection(axis),
+        };
+        Ok(widget_flags)
+    }
+
+    /// Translate the selection by the offset.
+    pub fn translate_selection(&mut self, offset: na::Vector2<f64>) -> WidgetFlags {
+        let selection = self.store.selection_keys_as_rendered();
+        if selection.is_empty() {
+            return WidgetFlags::default();
+        }
+        self.store.translate_strokes(&selection, offset);
+        self.store.translate_strokes_images(&selection, offset);
+        self.transform_selection_finish(&selection, RepeatableAction::Translate { offset })
+    }
+
+    /// Nudge the strokes by a small offset, for example when moving the selection with the arrow keys.
+    ///
+    /// Consecutive nudges within [Engine::NUDGE_COALESCE_WINDOW] are coalesced into a single history entry,
+    /// as long as nothing else was recorded in between.
+    pub fn nudge_selection(
+        &mut self,
+        keys: &[StrokeKey],
+        delta: na::Vector2<f64>,
+        now: Instant,
+    ) -> WidgetFlags {
+        if keys.is_empty() {
+            return WidgetFlags::default();
+        }
+        self.store.translate_strokes(keys, delta);
+        self.store.translate_strokes_images(keys, delta);
+        self.store.update_geometry_for_strokes(keys);
+        self.store.regenerate_rendering_for_strokes_threaded(
+            self.engine_tasks_tx(),
+            keys,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+
+        let coalesce_offset = match (self.last_nudge, self.store.last_action()) {
+            (Some((last_time, revision)), Some(RepeatableAction::Translate { offset }))
+                if revision == self.store.revision()
+                    && now.saturating_duration_since(last_time) <= Self::NUDGE_COALESCE_WINDOW =>
+            {
+                Some(offset)
+            }
+            _ => None,
+        };
+        let history_flags = match coalesce_offset {
+            Some(offset) => self.store.update_latest_history_entry_w_action(
+                now,
+                RepeatableAction::Translate {
+                    offset: offset + delta,
+                },
+            ),
+            None => self
+                .store
+                .record_w_action(now, RepeatableAction::Translate { offset: delta }),
+        };
+        self.last_nudge = Some((now, self.store.revision()));
+
+        let mut widget_flags = self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | history_flags
+            | self.update_rendering_current_viewport();
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    /// Rotate the selection by the angle in radians around the center of its bounds.
+    pub fn rotate_selection(&mut self, angle: f64) -> WidgetFlags {
+        let selection = self.store.selection_keys_as_rendered();
+        let Some(bounds) = self.store.bounds_for_strokes(&selection) else {
+            return WidgetFlags::default();
+        };
+        self.store
+            .rotate_strokes(&selection, angle, bounds.center());
+        self.store
+            .rotate_strokes_images(&selection, angle, bounds.center());
+        self.transform_selection_finish(&selection, RepeatableAction::Rotate { angle })
+    }
+
+    /// Resize the selection by the scale factors.
+    ///
+    /// The pivot is relative to the selection bounds, where [0.0, 0.0] is the top-left corner
+    /// and [1.0, 1.0] the bottom-right corner.
+    pub fn resize_selection(
+        &mut self,
+        scale: na::Vector2<f64>,
+        pivot: na::Vector2<f64>,
+    ) -> WidgetFlags {
+        let selection = self.store.selection_keys_as_rendered();
+        let Some(bounds) = self.store.bounds_for_strokes(&selection) else {
+            return WidgetFlags::default();
+        };
+        let pivot_pos = bounds.mins.coords + bounds.extents().component_mul(&pivot);
+        self.store
+            .scale_strokes_with_pivot(&selection, scale, pivot_pos);
+        self.store
+            .scale_strokes_images_with_pivot(&selection, scale, pivot_pos);
+        self.transform_selection_finish(&selection, RepeatableAction::Resize { scale, pivot })
+    }
+
+    fn transform_selection_finish(
+        &mut self,
+        selection: &[StrokeKey],
+        action: RepeatableAction,
+    ) -> WidgetFlags {
+        self.store.update_geometry_for_strokes(selection);
+        self.store.regenerate_rendering_for_strokes_threaded(
+            self.engine_tasks_tx(),
+            selection,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+        let mut widget_flags = self.current_pen_update_state()
+            | self.doc_resize_autoexpand()
+            | self.store.record_w_action(Instant::now(), action)
+            | self.update_rendering_current_viewport();
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RepeatableAction;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use approx::assert_relative_eq;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn repeat_translation() {
+        let mut engine = Engine::default();
+        assert!(engine.repeat_last_action().is_err());
+
+        let key = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![10.0, 10.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        let start_bounds = engine.store.bounds_for_strokes(&[key]).unwrap();
+        engine.store.set_selected(key, true);
+
+        let offset = na::vector![20.0, -5.0];
+        let _ = engine.translate_selection(offset);
+        assert_eq!(
+            engine.last_action(),
+            Some(RepeatableAction::Translate { offset })
+        );
+
+        let _ = engine.repeat_last_action().unwrap();
+        let bounds = engine.store.bounds_for_strokes(&[key]).unwrap();
+        assert_relative_eq!(bounds.mins.coords, start_bounds.mins.coords + offset * 2.0);
+        assert_relative_eq!(bounds.maxs.coords, start_bounds.maxs.coords + offset * 2.0);
+
+        // Nothing is selected anymore
+        engine.store.set_selected(key, false);
+        assert!(engine.repeat_last_action().is_err());
+    }
+
+    #[test]
+    fn nudges_coalesce_into_single_undo_step() {
+        let mut engine = Engine::default();
+        let key = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![10.0, 10.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        let _ = engine.record(Instant::now());
+        let start_bounds = engine.store.bounds_for_strokes(&[key]).unwrap();
+
+        let now = Instant::now();
+        let deltas = [
+            na::vector![1.0, 0.0],
+            na::vector![1.0, 0.0],
+            na::vector![0.0, 10.0],
+        ];
+        for (i, delta) in deltas.iter().enumerate() {
+            let _ =
+                engine.nudge_selection(&[key], *delta, now + Duration::from_millis(i as u64 * 100));
+        }
+        let total = na::vector![2.0, 10.0];
+        let bounds = engine.store.bounds_for_strokes(&[key]).unwrap();
+        assert_relative_eq!(bounds.mins.coords, start_bounds.mins.coords + total);
+        assert_eq!(
+            engine.last_action(),
+            Some(RepeatableAction::Translate { offset: total })
+        );
+
+        // A single undo step reverts all nudges
+        let _ = engine.undo(Instant::now());
+        let bounds = engine.store.bounds_for_strokes(&[key]).unwrap();
+        assert_relative_eq!(bounds.mins.coords, start_bounds.mins.coords);
+        let _ = engine.redo(Instant::now());
+
+        // Nudges after the window start a new history entry
+        let _ = engine.nudge_selection(
+            &[key],
+            na::vector![1.0, 0.0],
+            now + Engine::NUDGE_COALESCE_WINDOW * 2,
+        );
+        let _ = engine.undo(Instant::now());
+        let bounds = engine.store.bounds_for_strokes(&[key]).unwrap();
+        assert_relative_eq!(bounds.mins.coords, start_bounds.mins.coords + total);
+    }
+}
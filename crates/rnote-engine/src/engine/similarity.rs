@@ -0,0 +1,195 @@
+// Imports
+use crate::store::StrokeKey;
+use crate::strokes::Stroke;
+use crate::Engine;
+use rnote_compose::shapes::Shapeable;
+use std::collections::HashMap;
+
+/// A cheap descriptor of the shape of a stroke, independent of its position, size and rotation.
+///
+/// It is the turning function of the stroke path: the outline gets resampled at equal distances and the accumulated
+/// turning angle is recorded at every sample.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeDescriptor {
+    turning: [f64; Self::SAMPLES],
+}
+
+impl ShapeDescriptor {
+    /// The number of samples along the path.
+    pub const SAMPLES: usize = 32;
+    /// The tolerance when flattening the curves of the path.
+    const FLATTEN_TOLERANCE: f64 = 0.25;
+
+    /// The descriptor of the path of brush and shape strokes. None for other strokes and strokes without extent.
+    pub fn from_stroke(stroke: &Stroke) -> Option<Self> {
+        let path = match stroke {
+            Stroke::BrushStroke(brushstroke) => brushstroke.outline_path(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.outline_path(),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => return None,
+        };
+        let mut points = Vec::new();
+        let mut subpath_start = None;
+        kurbo::flatten(path, Self::FLATTEN_TOLERANCE, |el| match el {
+            kurbo::PathEl::MoveTo(p) => {
+                subpath_start = Some(p);
+                points.push(na::vector![p.x, p.y]);
+            }
+            kurbo::PathEl::LineTo(p) => points.push(na::vector![p.x, p.y]),
+            kurbo::PathEl::ClosePath => {
+                if let Some(p) = subpath_start {
+                    points.push(na::vector![p.x, p.y]);
+                }
+            }
+            _ => {}
+        });
+        Self::from_points(&points)
+    }
+
+    /// The descriptor of a polyline through the points. None when it has no extent.
+    pub fn from_points(points: &[na::Vector2<f64>]) -> Option<Self> {
+        let seg_lens = points
+            .windows(2)
+            .map(|w| (w[1] - w[0]).magnitude())
+            .collect::<Vec<f64>>();
+        let total_len = seg_lens.iter().sum::<f64>();
+        if total_len <= f64::EPSILON {
+            return None;
+        }
+
+        // Resample at equal distances along the path
+        let step = total_len / Self::SAMPLES as f64;
+        let mut resampled = Vec::with_capacity(Self::SAMPLES + 1);
+        let (mut seg_i, mut seg_start_len) = (0, 0.0);
+        for i in 0..=Self::SAMPLES {
+            let len = i as f64 * step;
+            while seg_i < seg_lens.len() - 1 && seg_start_len + seg_lens[seg_i] < len {
+                seg_start_len += seg_lens[seg_i];
+                seg_i += 1;
+            }
+            let t = if seg_lens[seg_i] > 0.0 {
+                ((len - seg_start_len) / seg_lens[seg_i]).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            resampled.push(points[seg_i].lerp(&points[seg_i + 1], t));
+        }
+
+        let dirs = resampled
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .collect::<Vec<na::Vector2<f64>>>();
+        let mut turning = [0.0; Self::SAMPLES];
+        for i in 1..Self::SAMPLES {
+            let (prev, dir) = (dirs[i - 1], dirs[i]);
+            turning[i] = turning[i - 1] + prev.perp(&dir).atan2(prev.dot(&dir));
+        }
+        Some(Self { turning })
+    }
+
+    /// The distance to the other descriptor. Zero for identical shapes.
+    ///
+    /// A distance of 1.0 corresponds to a mean difference of the turning angle of half a turn. Mirrored shapes are
+    /// considered identical.
+    pub fn distance(&self, other: &Self) -> f64 {
+        let mean_diff = |sign: f64| {
+            self.turning
+                .iter()
+                .zip(other.turning.iter())
+                .map(|(a, b)| (a - sign * b).abs())
+                .sum::<f64>()
+                / Self::SAMPLES as f64
+        };
+        mean_diff(1.0).min(mean_diff(-1.0)) / std::f64::consts::PI
+    }
+}
+
+/// The cached descriptors of the strokes, together with the revision of the store they were computed from.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ShapeDescriptorCache {
+    revision: u64,
+    descriptors: HashMap<StrokeKey, Option<ShapeDescriptor>>,
+}
+
+impl Engine {
+    /// Find the visible strokes with a shape similar to the stroke, which have a [ShapeDescriptor] distance not larger
+    /// than the threshold. Sorted by their similarity, the stroke itself is excluded.
+    ///
+    /// The descriptors are cached until the recorded state of the store changes, so repeated queries are cheap.
+    pub fn find_similar(&mut self, key: StrokeKey, threshold: f64) -> Vec<StrokeKey> {
+        let revision = self.store.revision();
+        if self.shape_descriptor_cache.revision != revision {
+            self.shape_descriptor_cache = ShapeDescriptorCache {
+                revision,
+                descriptors: HashMap::default(),
+            };
+        }
+        let store = &self.store;
+        let descriptors = &mut self.shape_descriptor_cache.descriptors;
+        let mut descriptor_for = |key: StrokeKey| {
+            descriptors
+                .entry(key)
+                .or_insert_with(|| {
+                    store
+                        .get_stroke_ref(key)
+                        .and_then(ShapeDescriptor::from_stroke)
+                })
+                .clone()
+        };
+
+        let Some(descriptor) = descriptor_for(key) else {
+            return vec![];
+        };
+        let mut similar = store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter(|&other| other != key)
+            .filter_map(|other| {
+                let distance = descriptor.distance(&descriptor_for(other)?);
+                (distance <= threshold).then_some((other, distance))
+            })
+            .collect::<Vec<(StrokeKey, f64)>>();
+        similar.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        similar.into_iter().map(|(key, _)| key).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShapeDescriptor;
+    use crate::engine::tests::polyline_stroke;
+    use crate::Engine;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn find_similar_matches_identical_shapes() {
+        let zigzag = [
+            na::vector![0.0, 0.0],
+            na::vector![10.0, 10.0],
+            na::vector![20.0, 0.0],
+            na::vector![30.0, 10.0],
+        ];
+        // the same zigzag moved, scaled and rotated by a quarter turn
+        let transformed = zigzag.map(|p| na::vector![200.0 - p.y * 3.0, 100.0 + p.x * 3.0]);
+        let line = [na::vector![0.0, 50.0], na::vector![100.0, 60.0]];
+
+        let mut engine = Engine::default();
+        let key = engine.store.insert_stroke(polyline_stroke(&zigzag), None);
+        let transformed_key = engine
+            .store
+            .insert_stroke(polyline_stroke(&transformed), None);
+        let _line_key = engine.store.insert_stroke(polyline_stroke(&line), None);
+
+        assert_relative_eq!(
+            ShapeDescriptor::from_points(&zigzag)
+                .unwrap()
+                .distance(&ShapeDescriptor::from_points(&transformed).unwrap()),
+            0.0,
+            epsilon = 1e-9
+        );
+        assert_eq!(engine.find_similar(key, 0.05), vec![transformed_key]);
+        assert_eq!(engine.find_similar(key, 10.0).len(), 2);
+
+        engine.store.set_hidden(transformed_key, true);
+        assert!(engine.find_similar(key, 0.05).is_empty());
+    }
+}
@@ -2,13 +2,14 @@
 use crate::document::background;
 use crate::engine::import::XoppImportPrefs;
 use crate::fileformats::{rnoteformat, xoppformat, FileFormatLoader};
-use crate::store::{ChronoComponent, StrokeKey};
+use crate::store::{ChronoComponent, LayerProps, StrokeKey};
 use crate::strokes::Stroke;
 use crate::{Camera, Document, Engine};
 use anyhow::Context;
 use futures::channel::oneshot;
 use serde::{Deserialize, Serialize};
 use slotmap::{HopSlotMap, SecondaryMap};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tracing::error;
 
@@ -26,6 +27,11 @@ pub struct EngineSnapshot {
     pub chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
     #[serde(rename = "chrono_counter")]
     pub chrono_counter: u32,
+    #[serde(rename = "layer_props")]
+    pub layer_props: BTreeMap<u32, LayerProps>,
+    /// Strokes pinned to the viewport, in surface coordinates.
+    #[serde(rename = "pinned_strokes")]
+    pub pinned_strokes: Vec<Arc<Stroke>>,
 }
 
 impl Default for EngineSnapshot {
@@ -36,6 +42,8 @@ impl Default for EngineSnapshot {
             stroke_components: Arc::new(HopSlotMap::with_key()),
             chrono_components: Arc::new(SecondaryMap::new()),
             chrono_counter: 0,
+            layer_props: BTreeMap::new(),
+            pinned_strokes: Vec::new(),
         }
     }
 }
@@ -197,3 +205,28 @@ impl EngineSnapshot {
         snapshot_receiver.await?
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::EngineSnapshot;
+    use crate::document::Layout;
+    use crate::Engine;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn dimensions_persist_through_save_load() {
+        let mut engine = Engine::default();
+        engine.document.layout = Layout::FixedSize;
+        assert!(engine.set_doc_dimensions(-10.0, 500.0).is_err());
+        assert!(engine.set_doc_dimensions(f64::NAN, 500.0).is_err());
+        let _ = engine.set_doc_dimensions(1200.0, 3400.0).unwrap();
+
+        // Serialized the same way as when saving as a .rnote file
+        let serialized = ijson::to_value(engine.take_snapshot()).unwrap();
+        let snapshot = ijson::from_value::<EngineSnapshot>(&serialized).unwrap();
+
+        let mut loaded = Engine::default();
+        let _ = loaded.load_snapshot(snapshot);
+        assert_relative_eq!(loaded.document.dimensions(), na::vector![1200.0, 3400.0]);
+    }
+}
@@ -0,0 +1,134 @@
+// Imports
+use super::Engine;
+use crate::strokes::Stroke;
+use p2d::bounding_volume::Aabb;
+
+/// Statistics about the strokes of the document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentStats {
+    /// The number of strokes, excluding trashed ones.
+    pub n_strokes: usize,
+    /// The total number of points that define the stroke geometries.
+    pub n_points: usize,
+    /// The number of brush strokes.
+    pub n_brushstrokes: usize,
+    /// The number of shape strokes.
+    pub n_shapestrokes: usize,
+    /// The number of text strokes.
+    pub n_textstrokes: usize,
+    /// The number of vector images.
+    pub n_vectorimages: usize,
+    /// The number of bitmap images.
+    pub n_bitmapimages: usize,
+    /// The bounds of the content, None when there are no strokes.
+    pub content_bounds: Option<Aabb>,
+    /// An approximation of the memory occupied by the strokes in bytes, without the rendered images.
+    pub approx_bytes: usize,
+}
+
+impl Engine {
+    /// Collect statistics about the current strokes of the document.
+    ///
+    /// Only reads the strokes, so no caches are invalidated.
+    pub fn stats(&self) -> DocumentStats {
+        let keys = self.store.stroke_keys_as_rendered();
+        let mut stats = DocumentStats {
+            content_bounds: self.store.bounds_for_strokes(&keys),
+            ..Default::default()
+        };
+
+        for stroke in keys
+            .iter()
+            .filter_map(|&key| self.store.get_stroke_ref(key))
+        {
+            stats.n_strokes += 1;
+            stats.n_points += stroke.point_count();
+            stats.approx_bytes += stroke.approx_bytes();
+            match stroke {
+                Stroke::BrushStroke(_) => stats.n_brushstrokes += 1,
+                Stroke::ShapeStroke(_) => stats.n_shapestrokes += 1,
+                Stroke::TextStroke(_) => stats.n_textstrokes += 1,
+                Stroke::VectorImage(_) => stats.n_vectorimages += 1,
+                Stroke::BitmapImage(_) => stats.n_bitmapimages += 1,
+            }
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strokes::{BrushStroke, ShapeStroke, Stroke, TextStroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::penpath::{Element, Segment};
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::{PenPath, Style};
+
+    #[test]
+    fn stats_count_strokes_and_points() {
+        let mut engine = Engine::default();
+        assert_eq!(engine.stats().n_strokes, 0);
+        assert!(engine.stats().content_bounds.is_none());
+
+        let brushstroke = Stroke::BrushStroke(BrushStroke::from_penpath(
+            PenPath::new_w_segments(
+                Element::new(na::vector![0.0, 0.0], 0.5),
+                [
+                    Segment::LineTo {
+                        end: Element::new(na::vector![10.0, 0.0], 0.5),
+                    },
+                    Segment::LineTo {
+                        end: Element::new(na::vector![20.0, 0.0], 0.5),
+                    },
+                ],
+            ),
+            Style::default(),
+        ));
+        let brushstroke_bytes = brushstroke.approx_bytes();
+        engine.store.insert_stroke(brushstroke, None);
+        engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 50.0],
+                    na::point![100.0, 100.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        engine.store.insert_stroke(
+            Stroke::TextStroke(TextStroke::new(
+                String::from("text"),
+                na::vector![0.0, 200.0],
+                Default::default(),
+            )),
+            None,
+        );
+        let trashed = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![1000.0, 1000.0],
+                    na::point![2000.0, 2000.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        engine.store.set_trashed(trashed, true);
+
+        let stats = engine.stats();
+        assert_eq!(stats.n_strokes, 3);
+        assert_eq!(stats.n_points, 3 + 4);
+        assert_eq!(stats.n_brushstrokes, 1);
+        assert_eq!(stats.n_shapestrokes, 1);
+        assert_eq!(stats.n_textstrokes, 1);
+        assert_eq!(stats.n_vectorimages + stats.n_bitmapimages, 0);
+        assert!(stats.approx_bytes > brushstroke_bytes);
+        // The trashed stroke is far outside the content bounds
+        let content_bounds = stats.content_bounds.unwrap();
+        assert!(content_bounds.mins[0] <= 0.0);
+        assert!(content_bounds.maxs[0] < 1000.0);
+    }
+}
@@ -0,0 +1,210 @@
+// Imports
+use crate::store::StrokeKey;
+use crate::strokes::Stroke;
+use crate::{Engine, WidgetFlags};
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::shapes::Shapeable;
+use rnote_compose::transform::Transformable;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// A reusable stencil, created from existing strokes.
+///
+/// The strokes are stored relative to the center of their combined bounds, so the stencil does not depend on where
+/// it was created and does not reference any keys of the store it originated from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "stencil")]
+pub struct Stencil {
+    #[serde(rename = "strokes")]
+    strokes: Vec<Stroke>,
+}
+
+impl Stencil {
+    pub const MIME_TYPE: &'static str = "application/rnote-stencil";
+
+    /// Create a stencil from the given strokes, which are positioned in document coordinates.
+    ///
+    /// Returns `None` when no strokes are given.
+    pub fn from_strokes(mut strokes: Vec<Stroke>) -> Option<Self> {
+        let bounds = strokes
+            .iter()
+            .map(|stroke| stroke.bounds())
+            .reduce(|acc, bounds| acc.merged(&bounds))?;
+        let offset = -bounds.center().coords;
+        for stroke in strokes.iter_mut() {
+            stroke.translate(offset);
+        }
+        Some(Self { strokes })
+    }
+
+    /// The strokes of the stencil, relative to its center.
+    pub fn strokes(&self) -> &[Stroke] {
+        &self.strokes
+    }
+
+    /// The bounds of the stencil, centered around the origin.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.strokes
+            .iter()
+            .map(|stroke| stroke.bounds())
+            .reduce(|acc, bounds| acc.merged(&bounds))
+    }
+
+    /// Clones of the strokes, scaled and rotated (in radians) around the center of the stencil and then centered at
+    /// the given position.
+    pub fn transformed_strokes(
+        &self,
+        at: na::Vector2<f64>,
+        scale: f64,
+        rotation: f64,
+    ) -> Vec<Stroke> {
+        self.strokes
+            .iter()
+            .map(|stroke| {
+                let mut stroke = stroke.clone();
+                stroke.scale(na::Vector2::repeat(scale));
+                stroke.rotate(rotation, na::Point2::origin());
+                stroke.translate(at);
+                stroke
+            })
+            .collect()
+    }
+}
+
+impl Engine {
+    /// Create a stencil from the strokes of the given keys. Keys of strokes that are not present are ignored.
+    ///
+    /// Returns `None` when none of the strokes are present.
+    pub fn create_stencil(&self, keys: &[StrokeKey]) -> Option<Stencil> {
+        Stencil::from_strokes(
+            keys.iter()
+                .filter_map(|&key| self.store.get_stroke_ref(key).cloned())
+                .collect(),
+        )
+    }
+
+    /// Place a stencil centered at the given position, scaled by the given factor and rotated by the given angle (in
+    /// radians).
+    ///
+    /// The placed strokes are inserted as new strokes and are selected.
+    pub fn place_stencil(
+        &mut self,
+        stencil: &Stencil,
+        at: na::Vector2<f64>,
+        scale: f64,
+        rotation: f64,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let strokes = stencil.transformed_strokes(at, scale, rotation);
+        if strokes.is_empty() {
+            return widget_flags;
+        }
+
+        let all_strokes = self.store.stroke_keys_as_rendered();
+        self.store.set_selected_keys(&all_strokes, false);
+        widget_flags |= self.change_pen_style(crate::pens::PenStyle::Selector);
+
+        let inserted = strokes
+            .into_iter()
+            .map(|stroke| self.store.insert_stroke(stroke, None))
+            .collect::<Vec<StrokeKey>>();
+        self.store.set_selected_keys(&inserted, true);
+
+        self.store.update_geometry_for_strokes(&inserted);
+        self.store.regenerate_rendering_in_viewport_threaded(
+            self.tasks_tx.clone(),
+            false,
+            self.camera.viewport(),
+            self.camera.image_scale(),
+        );
+
+        widget_flags |= self.doc_resize_autoexpand();
+        widget_flags |= self.current_pen_update_state();
+        widget_flags |= self.store.record(Instant::now());
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stencil;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use approx::assert_relative_eq;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Line, Rectangle, Shape, Shapeable};
+    use rnote_compose::Style;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn rectangle(mins: na::Point2<f64>, maxs: na::Point2<f64>) -> Stroke {
+        Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(mins, maxs))),
+            Style::default(),
+        ))
+    }
+
+    #[test]
+    fn stencil_roundtrip() {
+        let mut engine = Engine::default();
+        let keys = vec![
+            engine.store.insert_stroke(
+                rectangle(na::point![100.0, 100.0], na::point![140.0, 120.0]),
+                None,
+            ),
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Line(Line::new(
+                        na::vector![140.0, 120.0],
+                        na::vector![180.0, 160.0],
+                    )),
+                    Style::default(),
+                )),
+                None,
+            ),
+        ];
+        let stencil = engine.create_stencil(&keys).unwrap();
+        let bounds = stencil.bounds().unwrap();
+        assert_relative_eq!(bounds.center().coords, na::Vector2::zeros(), epsilon = 1e-9);
+
+        let json = serde_json::to_string(&stencil).unwrap();
+        let loaded: Stencil = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.strokes().len(), 2);
+        let loaded_bounds = loaded.bounds().unwrap();
+        assert_relative_eq!(
+            loaded_bounds.mins.coords,
+            bounds.mins.coords,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            loaded_bounds.maxs.coords,
+            bounds.maxs.coords,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn stencil_placed_at_transform() {
+        let mut engine = Engine::default();
+        let key = engine.store.insert_stroke(
+            rectangle(na::point![0.0, 0.0], na::point![40.0, 20.0]),
+            None,
+        );
+        let stencil = engine.create_stencil(&[key]).unwrap();
+        let extents = stencil.bounds().unwrap().extents();
+
+        let at = na::vector![300.0, 200.0];
+        let _ = engine.place_stencil(&stencil, at, 2.0, FRAC_PI_2);
+        let placed = engine.store.selection_keys_as_rendered();
+        assert_eq!(placed.len(), 1);
+        let bounds = engine.store.get_stroke_ref(placed[0]).unwrap().bounds();
+        assert_relative_eq!(bounds.center().coords, at, epsilon = 1e-6);
+        // rotated by a quarter turn, so width and height are swapped
+        assert_relative_eq!(
+            bounds.extents(),
+            na::vector![extents[1] * 2.0, extents[0] * 2.0],
+            epsilon = 1e-6
+        );
+    }
+}
@@ -1,11 +1,13 @@
 // Imports
 use crate::document::Background;
+use crate::engine::export::{ExportBackground, ExportOptions};
 use crate::render::Svg;
 use crate::strokes::Stroke;
 use crate::Drawable;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::shapes::Shapeable;
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 use std::sync::Arc;
 use tracing::warn;
 
@@ -21,6 +23,18 @@ pub struct StrokeContent {
     pub bounds: Option<Aabb>,
     #[serde(rename = "background")]
     pub background: Option<Background>,
+    /// The strokes from clipping layers, as index ranges into the strokes. Ordered and not overlapping.
+    #[serde(skip)]
+    pub clip_groups: Vec<ContentClipGroup>,
+}
+
+/// Strokes of the content that are only drawn where the strokes right before them have content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentClipGroup {
+    /// The strokes that mask the clipped strokes.
+    pub base: Range<usize>,
+    /// The clipped strokes, starting right after the base.
+    pub clipped: Range<usize>,
 }
 
 impl StrokeContent {
@@ -42,6 +56,11 @@ impl StrokeContent {
         self
     }
 
+    pub fn with_clip_groups(mut self, clip_groups: Vec<ContentClipGroup>) -> Self {
+        self.clip_groups = clip_groups;
+        self
+    }
+
     pub fn bounds(&self) -> Option<Aabb> {
         if self.bounds.is_some() {
             return self.bounds;
@@ -73,17 +92,35 @@ impl StrokeContent {
         optimize_printing: bool,
         margin: f64,
     ) -> anyhow::Result<Option<Svg>> {
-        let Some(bounds_loosened) = self.bounds().map(|b| b.loosened(margin)) else {
+        self.gen_svg_w_options(
+            ExportOptions::from_background_flag(draw_background, margin),
+            draw_pattern,
+            optimize_printing,
+        )
+    }
+
+    /// Generate a Svg from the content, padded and filled with the background of the export options.
+    ///
+    /// The pattern is only drawn for the [ExportBackground::SheetColor] background.
+    /// Moves the bounds to mins: [0.0, 0.0], maxs: extents.
+    ///
+    /// Returns Ok(None) if there is no content stored.
+    pub fn gen_svg_w_options(
+        &self,
+        options: ExportOptions,
+        draw_pattern: bool,
+        optimize_printing: bool,
+    ) -> anyhow::Result<Option<Svg>> {
+        let Some(bounds_loosened) = self.bounds().map(|b| b.loosened(options.padding)) else {
             return Ok(None);
         };
         let mut svg = Svg::gen_with_cairo(
             |cairo_cx| {
-                self.draw_to_cairo(
+                self.draw_to_cairo_w_options(
                     cairo_cx,
-                    draw_background,
+                    options,
                     draw_pattern,
                     optimize_printing,
-                    margin,
                     1.0,
                 )
             },
@@ -96,6 +133,76 @@ impl StrokeContent {
         Ok(Some(svg))
     }
 
+    /// Generate a Svg from the content, with consecutive strokes of the same style combined in a group.
+    ///
+    /// The draw order is preserved. Moves the bounds to mins: [0.0, 0.0], maxs: extents.
+    ///
+    /// Returns Ok(None) if there is no content stored.
+    pub fn gen_svg_grouped_by_style(
+        &self,
+        draw_background: bool,
+        draw_pattern: bool,
+        optimize_printing: bool,
+        margin: f64,
+    ) -> anyhow::Result<Option<Svg>> {
+        self.gen_svg_grouped_by_style_w_options(
+            ExportOptions::from_background_flag(draw_background, margin),
+            draw_pattern,
+            optimize_printing,
+        )
+    }
+
+    /// Generate a Svg from the content like [StrokeContent::gen_svg_grouped_by_style()], padded and filled with the
+    /// background of the export options.
+    ///
+    /// Returns Ok(None) if there is no content stored.
+    pub fn gen_svg_grouped_by_style_w_options(
+        &self,
+        options: ExportOptions,
+        draw_pattern: bool,
+        optimize_printing: bool,
+    ) -> anyhow::Result<Option<Svg>> {
+        let Some(bounds_loosened) = self.bounds().map(|b| b.loosened(options.padding)) else {
+            return Ok(None);
+        };
+        let bounds_simplified = Aabb::new(na::point![0.0, 0.0], bounds_loosened.extents().into());
+        let id_prefix = rnote_compose::utils::svg_random_id_prefix();
+        let mut content = String::new();
+        if options.background != ExportBackground::Transparent {
+            content += &gen_simplified_svg_content(
+                |cairo_cx| {
+                    self.draw_export_background_to_cairo(
+                        cairo_cx,
+                        options,
+                        draw_pattern,
+                        optimize_printing,
+                    )
+                },
+                bounds_loosened,
+            )?;
+        }
+        for (i, range) in self.style_chunks().into_iter().enumerate() {
+            let strokes_content = gen_simplified_svg_content(
+                |cairo_cx| self.draw_strokes_to_cairo(cairo_cx, range, optimize_printing, 1.0),
+                bounds_loosened,
+            )?;
+            let group = svg::node::element::Group::new()
+                .set("id", format!("{id_prefix}style-group-{i}"))
+                .add(svg::node::Blob::new(strokes_content));
+            content += &rnote_compose::utils::svg_node_to_string(&group)?;
+        }
+
+        Ok(Some(Svg {
+            svg_data: rnote_compose::utils::wrap_svg_root(
+                &content,
+                Some(bounds_simplified),
+                Some(bounds_simplified),
+                false,
+            ),
+            bounds: bounds_simplified,
+        }))
+    }
+
     pub fn draw_to_cairo(
         &self,
         cairo_cx: &cairo::Context,
@@ -105,7 +212,76 @@ impl StrokeContent {
         margin: f64,
         image_scale: f64,
     ) -> anyhow::Result<()> {
-        let Some(bounds) = self.bounds() else {
+        self.draw_to_cairo_w_options(
+            cairo_cx,
+            ExportOptions::from_background_flag(draw_background, margin),
+            draw_pattern,
+            optimize_printing,
+            image_scale,
+        )
+    }
+
+    /// Draw the content, padded and filled with the background of the export options.
+    ///
+    /// The pattern is only drawn for the [ExportBackground::SheetColor] background.
+    pub fn draw_to_cairo_w_options(
+        &self,
+        cairo_cx: &cairo::Context,
+        options: ExportOptions,
+        draw_pattern: bool,
+        optimize_printing: bool,
+        image_scale: f64,
+    ) -> anyhow::Result<()> {
+        self.draw_export_background_to_cairo(cairo_cx, options, draw_pattern, optimize_printing)?;
+        self.draw_strokes_to_cairo(
+            cairo_cx,
+            0..self.strokes.len(),
+            optimize_printing,
+            image_scale,
+        )
+    }
+
+    fn draw_export_background_to_cairo(
+        &self,
+        cairo_cx: &cairo::Context,
+        options: ExportOptions,
+        draw_pattern: bool,
+        optimize_printing: bool,
+    ) -> anyhow::Result<()> {
+        match options.background {
+            ExportBackground::Transparent => {}
+            ExportBackground::SheetColor => self.draw_background_to_cairo(
+                cairo_cx,
+                draw_pattern,
+                optimize_printing,
+                options.padding,
+            )?,
+            ExportBackground::Custom(color) => {
+                let Some(bounds_loosened) = self.bounds().map(|b| b.loosened(options.padding))
+                else {
+                    return Ok(());
+                };
+                cairo_cx.set_source_rgba(color.r, color.g, color.b, color.a);
+                cairo_cx.rectangle(
+                    bounds_loosened.mins[0],
+                    bounds_loosened.mins[1],
+                    bounds_loosened.extents()[0],
+                    bounds_loosened.extents()[1],
+                );
+                cairo_cx.fill()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_background_to_cairo(
+        &self,
+        cairo_cx: &cairo::Context,
+        draw_pattern: bool,
+        optimize_printing: bool,
+        margin: f64,
+    ) -> anyhow::Result<()> {
+        let (Some(bounds), Some(background)) = (self.bounds(), &self.background) else {
             return Ok(());
         };
         let bounds_loosened = bounds.loosened(margin);
@@ -118,19 +294,44 @@ impl StrokeContent {
             bounds_loosened.extents()[1],
         );
         cairo_cx.clip();
+        background.draw_to_cairo(cairo_cx, bounds_loosened, draw_pattern, optimize_printing)?;
+        cairo_cx.restore()?;
 
-        if draw_background {
-            if let Some(background) = &self.background {
-                background.draw_to_cairo(
-                    cairo_cx,
-                    bounds_loosened,
-                    draw_pattern,
-                    optimize_printing,
-                )?;
+        Ok(())
+    }
+
+    /// The index ranges of consecutive strokes with the same style.
+    ///
+    /// Clip groups are never split, so they are kept in a single chunk.
+    fn style_chunks(&self) -> Vec<Range<usize>> {
+        let mut chunks: Vec<Range<usize>> = Vec::new();
+        for i in 0..self.strokes.len() {
+            let continues_chunk = i > 0
+                && (self.strokes[i - 1].has_same_style(&self.strokes[i])
+                    || self
+                        .clip_groups
+                        .iter()
+                        .any(|group| group.base.start < i && i < group.clipped.end));
+            match chunks.last_mut() {
+                Some(chunk) if continues_chunk => chunk.end = i + 1,
+                _ => chunks.push(i..i + 1),
             }
         }
+        chunks
+    }
+
+    /// Draw the strokes in the given index range, with the clip groups that are entirely in the range applied.
+    fn draw_strokes_to_cairo(
+        &self,
+        cairo_cx: &cairo::Context,
+        range: Range<usize>,
+        optimize_printing: bool,
+        image_scale: f64,
+    ) -> anyhow::Result<()> {
+        let Some(bounds) = self.bounds() else {
+            return Ok(());
+        };
 
-        cairo_cx.restore()?;
         cairo_cx.save()?;
         cairo_cx.rectangle(
             bounds.mins[0],
@@ -150,28 +351,164 @@ impl StrokeContent {
             })
             .collect::<Vec<Aabb>>();
 
-        for stroke in self.strokes.iter() {
-            let stroke_bounds = stroke.bounds();
+        let draw_strokes = |range: Range<usize>| -> anyhow::Result<()> {
+            for stroke in self.strokes[range].iter() {
+                let stroke_bounds = stroke.bounds();
 
-            if optimize_printing
-                && image_bounds
-                    .iter()
-                    .all(|bounds| !bounds.contains(&stroke_bounds))
-            {
-                // Using the stroke's bounds instead of hitboxes works for inclusion.
-                // If this is changed to intersection, all hitboxes must be checked individually.
+                if optimize_printing
+                    && image_bounds
+                        .iter()
+                        .all(|bounds| !bounds.contains(&stroke_bounds))
+                {
+                    // Using the stroke's bounds instead of hitboxes works for inclusion.
+                    // If this is changed to intersection, all hitboxes must be checked individually.
 
-                let mut darkest_color_stroke = stroke.as_ref().clone();
-                darkest_color_stroke.set_to_darkest_color();
+                    let mut darkest_color_stroke = stroke.as_ref().clone();
+                    darkest_color_stroke.set_to_darkest_color();
 
-                darkest_color_stroke.draw_to_cairo(cairo_cx, image_scale)?;
-            } else {
-                stroke.draw_to_cairo(cairo_cx, image_scale)?;
+                    darkest_color_stroke.draw_to_cairo(cairo_cx, image_scale)?;
+                } else {
+                    stroke.draw_to_cairo(cairo_cx, image_scale)?;
+                }
             }
+            Ok(())
+        };
+
+        let mut drawn_end = range.start;
+        for group in self
+            .clip_groups
+            .iter()
+            .filter(|group| range.start <= group.base.start && group.clipped.end <= range.end)
+        {
+            draw_strokes(drawn_end..group.clipped.start)?;
+
+            cairo_cx.push_group();
+            draw_strokes(group.base.clone())?;
+            let mask = cairo_cx.pop_group()?;
+
+            cairo_cx.push_group();
+            draw_strokes(group.clipped.clone())?;
+            cairo_cx.pop_group_to_source()?;
+            cairo_cx.mask(&mask)?;
+
+            drawn_end = group.clipped.end;
         }
+        draw_strokes(drawn_end..range.end)?;
 
         cairo_cx.restore()?;
 
         Ok(())
     }
 }
+
+/// Generate a simplified Svg with cairo and return the content of its root element.
+///
+/// Every part is simplified on its own, so the ids of their definitions don't collide when they are combined.
+fn gen_simplified_svg_content<F>(draw_func: F, bounds: Aabb) -> anyhow::Result<String>
+where
+    F: FnOnce(&cairo::Context) -> anyhow::Result<()>,
+{
+    let mut svg = Svg::gen_with_cairo(draw_func, bounds)?;
+    svg.simplify()?;
+    let doc = roxmltree::Document::parse(&svg.svg_data)?;
+    let mut children = doc.root_element().children().filter(|n| n.is_element());
+    let Some(first) = children.next() else {
+        return Ok(String::new());
+    };
+    let end = children.last().unwrap_or(first).range().end;
+    Ok(svg.svg_data[first.range().start..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StrokeContent;
+    use crate::document::Background;
+    use crate::engine::export::{ExportBackground, ExportOptions};
+    use crate::render::Svg;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::{Color, Style};
+    use std::sync::Arc;
+
+    /// The number of elements on the top level, skipping anonymous wrapping groups.
+    fn top_level_elements(svg: &Svg) -> usize {
+        let doc = roxmltree::Document::parse(&svg.svg_data).unwrap();
+        let mut node = doc.root_element();
+        loop {
+            let children = node
+                .children()
+                .filter(|n| n.is_element())
+                .collect::<Vec<_>>();
+            match children.as_slice() {
+                [child] if child.has_tag_name("g") && child.attribute("id").is_none() => {
+                    node = *child;
+                }
+                _ => return children.len(),
+            }
+        }
+    }
+
+    #[test]
+    fn grouping_by_style_reduces_top_level_elements() {
+        let strokes = (0..4)
+            .map(|i| {
+                let mins = na::point![i as f64 * 50.0, 0.0];
+                Arc::new(Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        mins,
+                        mins + na::vector![40.0, 40.0],
+                    ))),
+                    Style::default(),
+                )))
+            })
+            .collect();
+        let content = StrokeContent::default().with_strokes(strokes);
+
+        let ungrouped = content.gen_svg(false, false, false, 0.0).unwrap().unwrap();
+        let grouped = content
+            .gen_svg_grouped_by_style(false, false, false, 0.0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(top_level_elements(&grouped), 1);
+        assert!(top_level_elements(&grouped) < top_level_elements(&ungrouped));
+    }
+
+    #[test]
+    fn export_background_fills_padding() {
+        let content = StrokeContent::default()
+            .with_strokes(vec![Arc::new(Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![20.0, 20.0],
+                    na::point![60.0, 60.0],
+                ))),
+                Style::default(),
+            )))])
+            .with_background(Some(Background::default()));
+        // The rgba values of the top-left corner pixel
+        let corner = |background: ExportBackground| {
+            let image = content
+                .gen_svg_w_options(
+                    ExportOptions {
+                        padding: 10.0,
+                        background,
+                    },
+                    false,
+                    false,
+                )
+                .unwrap()
+                .unwrap()
+                .gen_image(1.0)
+                .unwrap();
+            assert_eq!(image.pixel_width, image.pixel_height);
+            [image.data[0], image.data[1], image.data[2], image.data[3]]
+        };
+
+        assert_eq!(corner(ExportBackground::Transparent)[3], 0);
+        assert_eq!(corner(ExportBackground::SheetColor)[3], 255);
+        assert_eq!(
+            corner(ExportBackground::Custom(Color::new(1.0, 0.0, 0.0, 1.0))),
+            [255, 0, 0, 255]
+        );
+    }
+}
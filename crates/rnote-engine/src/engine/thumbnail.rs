@@ -0,0 +1,109 @@
+// Imports
+use crate::render::{self, AaQuality};
+use crate::Engine;
+use rnote_compose::Color;
+
+/// A cached thumbnail, together with the state it was generated from.
+#[derive(Debug, Clone)]
+pub(crate) struct ThumbnailCache {
+    revision: u64,
+    max_dimension: u32,
+    background_color: Color,
+    image: image::RgbaImage,
+}
+
+impl Engine {
+    /// The margin that the image generation adds around the content, in document coordinates.
+    const THUMBNAIL_MARGIN: f64 = 1.0;
+
+    /// Generate a thumbnail of the document content, where the larger side is at most `max_dimension` pixels.
+    ///
+    /// The thumbnail is rendered with the fast anti-aliasing path and without the pattern and overlays.
+    /// It is cached until the recorded state of the store changes, so repeated calls are cheap.
+    /// Returns None when the document has no content.
+    pub fn thumbnail(&mut self, max_dimension: u32) -> anyhow::Result<Option<image::RgbaImage>> {
+        let revision = self.store.revision();
+        let background_color = self.document.background.color;
+
+        if let Some(cache) = &self.thumbnail_cache {
+            if cache.revision == revision
+                && cache.max_dimension == max_dimension
+                && cache.background_color == background_color
+            {
+                return Ok(Some(cache.image.clone()));
+            }
+        }
+        self.thumbnail_cache.take();
+
+        let keys = self.store.stroke_keys_as_rendered();
+        let Some(content_bounds) = self.store.bounds_for_strokes(&keys) else {
+            return Ok(None);
+        };
+        let Some(content) = self.extract_region_content(content_bounds) else {
+            return Ok(None);
+        };
+        // The image generation loosens the bounds by the margin on each side
+        let image_scale = thumbnail_image_scale(
+            content_bounds.extents() + na::Vector2::repeat(2.0 * Self::THUMBNAIL_MARGIN),
+            max_dimension,
+        );
+
+        let image = render::Image::gen_with_cairo_aa(
+            |cairo_cx| content.draw_to_cairo(cairo_cx, true, false, false, 0.0, image_scale),
+            content_bounds,
+            image_scale,
+            AaQuality::Fast,
+        )?
+        .into_imgbuf()?;
+
+        self.thumbnail_cache = Some(ThumbnailCache {
+            revision,
+            max_dimension,
+            background_color,
+            image: image.clone(),
+        });
+        Ok(Some(image))
+    }
+}
+
+/// The image scale so that the larger side of the extents is scaled to the max dimension.
+fn thumbnail_image_scale(extents: na::Vector2<f64>, max_dimension: u32) -> f64 {
+    let max_extent = extents.max();
+    if max_extent <= 0.0 {
+        return 1.0;
+    }
+    // Round down, so that the rounding when generating the image never exceeds the max dimension
+    ((max_dimension as f64 / max_extent) * 1e6).floor() / 1e6
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
+    use std::time::Instant;
+
+    #[test]
+    fn thumbnail_respects_cap_and_aspect_ratio() {
+        let mut engine = Engine::default();
+        engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![400.0, 200.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        let _ = engine.store.record(Instant::now());
+
+        let thumbnail = engine.thumbnail(64).unwrap().unwrap();
+        assert!(thumbnail.width() <= 64 && thumbnail.height() <= 64);
+        assert!(thumbnail.width() >= 63);
+        let aspect_ratio = thumbnail.width() as f64 / thumbnail.height() as f64;
+        assert!((aspect_ratio - 2.0).abs() < 0.1);
+    }
+}
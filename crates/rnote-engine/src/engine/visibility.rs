@@ -0,0 +1,200 @@
+// Imports
+use crate::pens::PenStyle;
+use crate::store::chrono_comp::StrokeLayer;
+use crate::store::StrokeKey;
+use crate::strokes::Stroke;
+use crate::{Engine, WidgetFlags};
+use rnote_compose::Color;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// A filter that selects strokes for changing their visibility.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "stroke_filter")]
+pub enum StrokeFilter {
+    /// Strokes that have the tag.
+    #[serde(rename = "tag")]
+    Tag(String),
+    /// Strokes on the layer.
+    #[serde(rename = "layer")]
+    Layer(StrokeLayer),
+    /// Strokes drawn with the stroke color. For texts, the text color is compared.
+    #[serde(rename = "color")]
+    Color(Color),
+    /// Strokes that are created by the pen style.
+    #[serde(rename = "pen_style")]
+    PenStyle(PenStyle),
+}
+
+impl StrokeFilter {
+    /// Whether the stroke on the given layer and with the given tags matches the filter.
+    pub fn matches(&self, stroke: &Stroke, layer: StrokeLayer, tags: &[String]) -> bool {
+        match self {
+            Self::Tag(tag) => tags.contains(tag),
+            Self::Layer(filter_layer) => *filter_layer == layer,
+            Self::Color(color) => {
+                let stroke_color = match stroke {
+                    Stroke::BrushStroke(brushstroke) => brushstroke.style.stroke_color(),
+                    Stroke::ShapeStroke(shapestroke) => shapestroke.style.stroke_color(),
+                    Stroke::TextStroke(textstroke) => Some(textstroke.text_style.color),
+                    Stroke::VectorImage(_) | Stroke::BitmapImage(_) => None,
+                };
+                stroke_color == Some(*color)
+            }
+            Self::PenStyle(pen_style) => {
+                let stroke_pen_style = match stroke {
+                    Stroke::BrushStroke(_) => Some(PenStyle::Brush),
+                    Stroke::ShapeStroke(_) => Some(PenStyle::Shaper),
+                    Stroke::TextStroke(_) => Some(PenStyle::Typewriter),
+                    Stroke::VectorImage(_) | Stroke::BitmapImage(_) => None,
+                };
+                stroke_pen_style == Some(*pen_style)
+            }
+        }
+    }
+}
+
+/// Systems related to the visibility of single strokes.
+///
+/// Hidden strokes are skipped when rendering and are excluded from selecting, erasing and other hit-testing, but
+/// they are kept in the store and are saved together with their hidden state. This is independent of the layers.
+impl Engine {
+    /// Whether the stroke is visible. None when the stroke is not present.
+    pub fn stroke_visible(&self, key: StrokeKey) -> Option<bool> {
+        self.store.hidden(key).map(|hidden| !hidden)
+    }
+
+    /// Show or hide the stroke.
+    pub fn set_stroke_visible(&mut self, key: StrokeKey, visible: bool) -> WidgetFlags {
+        self.set_strokes_visible(&[key], visible)
+    }
+
+    /// Show or hide all strokes matching the filter.
+    pub fn set_strokes_visible_by_filter(
+        &mut self,
+        filter: StrokeFilter,
+        visible: bool,
+    ) -> WidgetFlags {
+        let keys = self
+            .store
+            .stroke_keys_unordered()
+            .into_iter()
+            .filter(|&key| {
+                let (Some(stroke), Some(layer), Some(tags)) = (
+                    self.store.get_stroke_ref(key),
+                    self.store.layer(key),
+                    self.store.tags(key),
+                ) else {
+                    return false;
+                };
+                filter.matches(stroke, layer, tags)
+            })
+            .collect::<Vec<StrokeKey>>();
+        self.set_strokes_visible(&keys, visible)
+    }
+
+    /// The tags of the stroke. None when the stroke is not present.
+    pub fn stroke_tags(&self, key: StrokeKey) -> Option<&[String]> {
+        self.store.tags(key)
+    }
+
+    /// Replace the tags of the stroke, which can then be used to filter strokes.
+    pub fn set_stroke_tags(&mut self, key: StrokeKey, tags: Vec<String>) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if !self.store.set_tags(key, tags) {
+            return widget_flags;
+        }
+        widget_flags |= self.record(Instant::now());
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    /// Show all hidden strokes.
+    pub fn show_all_strokes(&mut self) -> WidgetFlags {
+        let keys = self.store.stroke_keys_unordered();
+        self.set_strokes_visible(&keys, true)
+    }
+
+    fn set_strokes_visible(&mut self, keys: &[StrokeKey], visible: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let mut changed = false;
+        for &key in keys {
+            changed |= self.store.set_hidden(key, !visible);
+        }
+        if !changed {
+            return widget_flags;
+        }
+
+        // The visibility is not a separate step in the history
+        widget_flags |= self.store.update_latest_history_entry(Instant::now());
+        widget_flags |= self.current_pen_update_state();
+        widget_flags |= self.doc_resize_autoexpand();
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StrokeFilter;
+    use crate::engine::EngineSnapshot;
+    use crate::pens::PenStyle;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
+
+    #[test]
+    fn hidden_strokes_are_not_rendered_or_hit_but_persist() {
+        let mut engine = Engine::default();
+        let bounds = Aabb::new(na::point![10.0, 10.0], na::point![50.0, 50.0]);
+        let key = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(bounds)),
+                Style::default(),
+            )),
+            None,
+        );
+        let viewport = Aabb::new(na::point![0.0, 0.0], na::point![100.0, 100.0]);
+        let _ =
+            engine.set_strokes_visible_by_filter(StrokeFilter::PenStyle(PenStyle::Shaper), false);
+
+        assert_eq!(engine.stroke_visible(key), Some(false));
+        assert!(engine
+            .store
+            .stroke_keys_as_rendered_intersecting_bounds(viewport)
+            .is_empty());
+        assert!(engine
+            .store
+            .strokes_hitboxes_contained_in_aabb(viewport, viewport)
+            .is_empty());
+
+        // saving and loading keeps the hidden stroke
+        let snapshot: EngineSnapshot =
+            ijson::from_value(&ijson::to_value(engine.take_snapshot()).unwrap()).unwrap();
+        let mut loaded = Engine::default();
+        let _ = loaded.load_snapshot(snapshot);
+        let keys = loaded.store.stroke_keys_unordered();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(loaded.stroke_visible(keys[0]), Some(false));
+
+        let _ = loaded.show_all_strokes();
+        let _ = loaded.set_stroke_tags(keys[0], vec![String::from("sketch")]);
+        let _ =
+            loaded.set_strokes_visible_by_filter(StrokeFilter::Tag(String::from("draft")), false);
+        assert_eq!(loaded.stroke_visible(keys[0]), Some(true));
+        let _ =
+            loaded.set_strokes_visible_by_filter(StrokeFilter::Tag(String::from("sketch")), false);
+        assert_eq!(loaded.stroke_visible(keys[0]), Some(false));
+
+        let _ = loaded.show_all_strokes();
+        assert_eq!(
+            loaded
+                .store
+                .strokes_hitboxes_contained_in_aabb(viewport, viewport),
+            keys
+        );
+    }
+}
@@ -49,6 +49,18 @@ pub const COLOR_DOC_BOUNDS: Color = Color {
     b: 0.8,
     a: 1.0,
 };
+pub const COLOR_PRESSURE_RAW: Color = Color {
+    r: 1.0,
+    g: 0.6,
+    b: 0.0,
+    a: 1.0,
+};
+pub const COLOR_PRESSURE_REMAPPED: Color = Color {
+    r: 0.0,
+    g: 0.8,
+    b: 1.0,
+    a: 1.0,
+};
 
 #[cfg(feature = "ui")]
 pub(crate) fn draw_bounds_to_gtk_snapshot(
@@ -196,6 +208,74 @@ pub(crate) fn draw_statistics_to_gtk_snapshot(
     Ok(())
 }
 
+/// Draw a graph of the raw and remapped pressure samples, oldest first, in the bottom left corner.
+///
+/// Expects that the snapshot is untransformed in surface coordinate space.
+#[cfg(feature = "ui")]
+pub(crate) fn draw_pressure_graph_to_gtk_snapshot(
+    snapshot: &gtk4::Snapshot,
+    samples: &std::collections::VecDeque<(f64, f64)>,
+    surface_bounds: p2d::bounding_volume::Aabb,
+) -> anyhow::Result<()> {
+    use crate::ext::GrapheneRectExt;
+    use gtk4::{graphene, prelude::*};
+    use p2d::bounding_volume::{Aabb, BoundingVolume};
+    use piet::RenderContext;
+    use rnote_compose::ext::AabbExt;
+
+    const GRAPH_SIZE: na::Vector2<f64> = na::vector![240.0, 120.0];
+    const LINE_WIDTH: f64 = 1.5;
+
+    if samples.len() < 2 {
+        return Ok(());
+    }
+    let graph_bounds = Aabb::new(
+        na::point![
+            surface_bounds.mins[0] + 20.0,
+            surface_bounds.maxs[1] - 20.0 - GRAPH_SIZE[1]
+        ],
+        na::point![
+            surface_bounds.mins[0] + 20.0 + GRAPH_SIZE[0],
+            surface_bounds.maxs[1] - 20.0
+        ],
+    );
+    let plot_bounds = graph_bounds.tightened(10.0);
+    let cairo_cx = snapshot.append_cairo(&graphene::Rect::from_p2d_aabb(graph_bounds));
+    let mut piet_cx = piet_cairo::CairoRenderContext::new(&cairo_cx);
+
+    piet_cx.fill(
+        graph_bounds.to_kurbo_rect(),
+        &piet::Color::rgba(0.1, 0.1, 0.1, 0.8),
+    );
+    let step = plot_bounds.extents()[0] / (samples.len() - 1) as f64;
+    let point = |i: usize, pressure: f64| {
+        kurbo::Point::new(
+            plot_bounds.mins[0] + i as f64 * step,
+            plot_bounds.maxs[1] - pressure.clamp(0.0, 1.0) * plot_bounds.extents()[1],
+        )
+    };
+    let mut raw_path = kurbo::BezPath::new();
+    let mut remapped_path = kurbo::BezPath::new();
+    for (i, &(raw, remapped)) in samples.iter().enumerate() {
+        if i == 0 {
+            raw_path.move_to(point(i, raw));
+            remapped_path.move_to(point(i, remapped));
+        } else {
+            raw_path.line_to(point(i, raw));
+            remapped_path.line_to(point(i, remapped));
+        }
+    }
+    piet_cx.stroke(raw_path, &piet::Color::from(COLOR_PRESSURE_RAW), LINE_WIDTH);
+    piet_cx.stroke(
+        remapped_path,
+        &piet::Color::from(COLOR_PRESSURE_REMAPPED),
+        LINE_WIDTH,
+    );
+    piet_cx.finish().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    Ok(())
+}
+
 /// Draw stroke bounds, positions, etc. for visual debugging purposes.
 #[cfg(feature = "ui")]
 pub(crate) fn draw_stroke_debug_to_gtk_snapshot(
@@ -0,0 +1,163 @@
+// Imports
+use anyhow::Context;
+use tracing::warn;
+
+/// The schema version of the engine snapshot that is written when saving.
+///
+/// Needs to be incremented when the serialized representation changes in a way that older files can't be
+/// deserialized anymore without losing data, together with adding a migration step to [MIGRATIONS].
+/// Renames that can be expressed with a serde `alias` don't need a migration step.
+pub(crate) const SCHEMA_VERSION: u32 = 0;
+
+/// A step that upgrades the engine snapshot by one schema version.
+type MigrationStep = fn(&mut ijson::IObject) -> anyhow::Result<()>;
+
+/// The migration steps, the step at index `i` upgrades from schema version `i` to `i + 1`.
+const MIGRATIONS: [MigrationStep; SCHEMA_VERSION as usize] = [];
+
+/// Upgrades the engine snapshot from the given schema version to the current [SCHEMA_VERSION],
+/// before it gets deserialized.
+pub(crate) fn migrate(
+    schema_version: u32,
+    engine_snapshot: ijson::IValue,
+) -> anyhow::Result<ijson::IValue> {
+    migrate_w_steps(schema_version, engine_snapshot, &MIGRATIONS)
+}
+
+/// Upgrades the engine snapshot from the given schema version by applying the remaining steps.
+///
+/// The supported schema version is the number of steps.
+fn migrate_w_steps(
+    schema_version: u32,
+    mut engine_snapshot: ijson::IValue,
+    steps: &[MigrationStep],
+) -> anyhow::Result<ijson::IValue> {
+    let supported_version = steps.len();
+    if schema_version as usize > supported_version {
+        warn!(
+            "Engine snapshot has schema version {schema_version}, which is newer than the supported version {supported_version}. Loading it without migrating."
+        );
+        return Ok(engine_snapshot);
+    }
+    let object = engine_snapshot
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("engine snapshot is not a JSON object."))?;
+
+    for (from, step) in steps.iter().enumerate().skip(schema_version as usize) {
+        step(object).with_context(|| {
+            format!(
+                "migrating engine snapshot from schema version {from} to {} failed.",
+                from + 1
+            )
+        })?;
+    }
+
+    Ok(engine_snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{migrate, migrate_w_steps, MigrationStep, SCHEMA_VERSION};
+    use crate::document::Layout;
+    use crate::engine::EngineSnapshot;
+    use crate::fileformats::rnoteformat::RnoteFile;
+    use crate::fileformats::FileFormatLoader;
+
+    fn load_snapshot(bytes: &[u8]) -> EngineSnapshot {
+        let rnote_file = RnoteFile::load_from_bytes(bytes).unwrap();
+        ijson::from_value::<EngineSnapshot>(&rnote_file.engine_snapshot).unwrap()
+    }
+
+    #[test]
+    fn migrate_keeps_current_snapshot() {
+        let engine_snapshot = ijson::to_value(EngineSnapshot::default()).unwrap();
+        assert_eq!(
+            migrate(SCHEMA_VERSION, engine_snapshot.clone()).unwrap(),
+            engine_snapshot
+        );
+        // snapshots from newer versions are loaded as they are
+        assert_eq!(
+            migrate(SCHEMA_VERSION + 1, engine_snapshot.clone()).unwrap(),
+            engine_snapshot
+        );
+    }
+
+    /// A synthetic migration step that records its application and renames `v{N}_field` to `v{N+1}_field`.
+    fn synthetic_step<const N: u32>(object: &mut ijson::IObject) -> anyhow::Result<()> {
+        let value = object
+            .remove(format!("v{N}_field").as_str())
+            .ok_or_else(|| anyhow::anyhow!("field of version {N} is missing."))?;
+        object.insert(format!("v{}_field", N + 1), value);
+        object
+            .get_mut("applied")
+            .and_then(|applied| applied.as_array_mut())
+            .unwrap()
+            .push(N);
+        Ok(())
+    }
+
+    const SYNTHETIC_STEPS: [MigrationStep; 3] = [
+        synthetic_step::<0>,
+        synthetic_step::<1>,
+        synthetic_step::<2>,
+    ];
+
+    fn synthetic_snapshot(schema_version: u32) -> ijson::IValue {
+        let mut object = ijson::IObject::new();
+        object.insert(format!("v{schema_version}_field"), "data");
+        object.insert("applied", ijson::IArray::new());
+        object.into()
+    }
+
+    #[test]
+    fn migration_applies_remaining_steps_in_order() {
+        for schema_version in 0..=3 {
+            let migrated = migrate_w_steps(
+                schema_version,
+                synthetic_snapshot(schema_version),
+                &SYNTHETIC_STEPS,
+            )
+            .unwrap();
+            let object = migrated.as_object().unwrap();
+            assert_eq!(
+                object
+                    .get("v3_field")
+                    .and_then(|v| v.as_string())
+                    .map(|s| s.as_str()),
+                Some("data")
+            );
+            let applied: Vec<u32> = ijson::from_value(object.get("applied").unwrap()).unwrap();
+            assert_eq!(applied, (schema_version..3).collect::<Vec<u32>>());
+        }
+    }
+
+    #[test]
+    fn failing_migration_step_reports_the_version() {
+        // the snapshot claims version 1, but has the field of version 0
+        let err = migrate_w_steps(1, synthetic_snapshot(0), &SYNTHETIC_STEPS).unwrap_err();
+        assert!(format!("{err:#}").contains("from schema version 1 to 2"));
+        // snapshots that are not objects are rejected
+        assert!(migrate_w_steps(0, ijson::IValue::NULL, &SYNTHETIC_STEPS).is_err());
+    }
+
+    #[test]
+    fn old_files_load_through_migration() {
+        let snapshot = load_snapshot(include_bytes!(
+            "../../../../../misc/file-tests/v0-5-5-test.rnote"
+        ));
+        assert_eq!(snapshot.document.layout, Layout::ContinuousVertical);
+        assert!(!snapshot.stroke_components.is_empty());
+
+        let snapshot = load_snapshot(include_bytes!(
+            "../../../../../misc/file-tests/v0-6-0-test.rnote"
+        ));
+        assert_eq!(snapshot.document.layout, Layout::ContinuousVertical);
+        assert!(!snapshot.stroke_components.is_empty());
+
+        let snapshot = load_snapshot(include_bytes!(
+            "../../../../../misc/file-tests/v0-9-0-test.rnote"
+        ));
+        assert_eq!(snapshot.document.layout, Layout::Infinite);
+        assert!(!snapshot.stroke_components.is_empty());
+    }
+}
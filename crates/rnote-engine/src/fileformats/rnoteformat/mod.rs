@@ -4,12 +4,16 @@
 //! where X: semver major, Y: semver minor version.
 //!
 //! Then [TryFrom] can be implemented to allow conversions and chaining from older to newer versions.
+//!
+//! Independently from the file format version, the engine snapshot is tagged with a schema version
+//! and upgraded in [migrate::migrate()] before it gets deserialized.
 
 // Modules
 pub(crate) mod maj0min5patch8;
 pub(crate) mod maj0min5patch9;
 pub(crate) mod maj0min6;
 pub(crate) mod maj0min9;
+pub(crate) mod migrate;
 
 // Imports
 use self::maj0min5patch8::RnoteFileMaj0Min5Patch8;
@@ -65,6 +69,9 @@ fn decompress_from_gzip(compressed: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
 struct RnotefileWrapper {
     #[serde(rename = "version")]
     version: semver::Version,
+    /// The schema version of the engine snapshot. Files saved before it was introduced have version `0`.
+    #[serde(rename = "schema_version", default)]
+    schema_version: u32,
     #[serde(rename = "data")]
     data: ijson::IValue,
 }
@@ -86,7 +93,7 @@ impl FileFormatLoader for RnoteFile {
         .context("deserializing RnotefileWrapper from bytes failed.")?;
 
         // Conversions for older file format versions happen here
-        if semver::VersionReq::parse(">=0.9.0")
+        let mut rnote_file = if semver::VersionReq::parse(">=0.9.0")
             .unwrap()
             .matches(&wrapper.version)
         {
@@ -124,7 +131,14 @@ impl FileFormatLoader for RnoteFile {
                 "failed to load rnote file from bytes, unsupported version: {}.",
                 wrapper.version
             ))
-        }
+        }?;
+
+        // Upgrades of the engine snapshot schema happen here
+        rnote_file.engine_snapshot =
+            migrate::migrate(wrapper.schema_version, rnote_file.engine_snapshot)
+                .context("migrating the engine snapshot schema failed.")?;
+
+        Ok(rnote_file)
     }
 }
 
@@ -132,6 +146,7 @@ impl FileFormatSaver for RnoteFile {
     fn save_as_bytes(&self, _file_name: &str) -> anyhow::Result<Vec<u8>> {
         let wrapper = RnotefileWrapper {
             version: semver::Version::parse(Self::SEMVER).unwrap(),
+            schema_version: migrate::SCHEMA_VERSION,
             data: ijson::to_value(self).context("converting RnoteFile to JSON value failed.")?,
         };
         let compressed = compress_to_gzip(
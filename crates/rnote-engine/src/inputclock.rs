@@ -0,0 +1,84 @@
+// Imports
+use std::time::{Duration, Instant};
+
+/// Assigns timestamps to pen input, which never decrease.
+///
+/// The platform usually delivers input together with a backlog of earlier events, whose times are reconstructed
+/// from the platform event times. These can lie before input that was already handled,
+/// which would confuse features that rely on the time between input elements, like the pen path modeling or dwelling.
+#[derive(Debug, Clone, Copy)]
+pub struct InputClock {
+    session_start: Instant,
+    last: Option<Instant>,
+}
+
+impl Default for InputClock {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+
+impl InputClock {
+    /// A new input clock, with the given start of the input session.
+    pub fn new(session_start: Instant) -> Self {
+        Self {
+            session_start,
+            last: None,
+        }
+    }
+
+    /// The start of the input session.
+    pub fn session_start(&self) -> Instant {
+        self.session_start
+    }
+
+    /// The timestamp for input at the given time.
+    ///
+    /// `time` is the time reconstructed from the platform event time, or the arrival time of the input when the
+    /// platform doesn't provide it. The timestamp is never before the previous one.
+    pub fn timestamp(&mut self, time: Instant) -> Instant {
+        let timestamp = self.last.map_or(time, |last| time.max(last));
+        self.last = Some(timestamp);
+        timestamp
+    }
+
+    /// Sort the batch of input by time and drop the input that is older than the previous timestamp.
+    pub fn reorder_batch<T>(&self, batch: &mut Vec<(T, Instant)>) {
+        batch.sort_by_key(|(_, time)| *time);
+        if let Some(last) = self.last {
+            batch.retain(|(_, time)| *time >= last);
+        }
+    }
+
+    /// The time since the start of the input session.
+    ///
+    /// Timestamps should be serialized in this relative representation, as instants are not reproducible
+    /// across sessions.
+    pub fn session_time(&self, timestamp: Instant) -> Duration {
+        timestamp.saturating_duration_since(self.session_start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputClock;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn timestamps_are_monotonic() {
+        let start = Instant::now();
+        let mut clock = InputClock::new(start);
+        let at = |millis: u64| start + Duration::from_millis(millis);
+
+        // a backlog of events delivered late reaches back before the last handled input
+        let timestamps = [10, 20, 15, 12, 30, 30, 25].map(|millis| clock.timestamp(at(millis)));
+        assert!(timestamps.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(timestamps[3], at(20));
+        assert_eq!(timestamps[4], at(30));
+
+        assert_eq!(clock.session_time(timestamps[4]), Duration::from_millis(30));
+        // times before the session start are clamped to it
+        let clock = InputClock::new(at(100));
+        assert_eq!(clock.session_time(start), Duration::ZERO);
+    }
+}
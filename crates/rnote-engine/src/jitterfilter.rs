@@ -0,0 +1,225 @@
+// Imports
+use rnote_compose::penevent::PenEvent;
+use std::time::Instant;
+
+/// Rejects the jitter at the start of strokes.
+///
+/// When the nib first touches, it often moves rapidly back and forth within a tiny distance, which would draw a blob.
+/// The down events at the start of a stroke are held back, together with their timestamps, until the pen moved further
+/// than [Self::JITTER_RADIUS] from the first position. Then the elements before the last reversal of the motion are
+/// discarded, so that the stroke starts where the consistent motion begins. Taps that never leave the radius are passed
+/// unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct JitterFilter {
+    state: JitterFilterState,
+}
+
+#[derive(Debug, Clone, Default)]
+enum JitterFilterState {
+    #[default]
+    Idle,
+    /// Holding back the down events at the start of the stroke.
+    Settling { held_back: Vec<(PenEvent, Instant)> },
+    /// The consistent motion has begun, events pass unchanged until the stroke ends.
+    Moving,
+}
+
+impl JitterFilter {
+    /// The distance from the first position within which the motion is considered jitter, in surface coordinates.
+    pub const JITTER_RADIUS: f64 = 3.0;
+
+    /// Filter the event received at the given time, returning the events that should be handled in its place
+    /// together with the times they were received.
+    ///
+    /// Returns no events while the start of a stroke is held back. Events pass unchanged when not enabled, after the
+    /// held back events. The total zoom of the camera scales the jitter radius to document coordinates.
+    pub fn filter_event(
+        &mut self,
+        event: PenEvent,
+        now: Instant,
+        enabled: bool,
+        total_zoom: f64,
+    ) -> Vec<(PenEvent, Instant)> {
+        if !enabled {
+            let mut events = self.take_held_back();
+            events.push((event, now));
+            return events;
+        }
+        match (&mut self.state, event) {
+            (JitterFilterState::Idle, event @ PenEvent::Down { .. }) => {
+                self.state = JitterFilterState::Settling {
+                    held_back: vec![(event, now)],
+                };
+                vec![]
+            }
+            (JitterFilterState::Settling { held_back }, event @ PenEvent::Down { element, .. }) => {
+                let first_pos = match held_back.first() {
+                    Some((PenEvent::Down { element, .. }, _)) => element.pos,
+                    _ => element.pos,
+                };
+                held_back.push((event, now));
+                if (element.pos - first_pos).magnitude() <= Self::JITTER_RADIUS / total_zoom {
+                    return vec![];
+                }
+                let events = trim_jitter(std::mem::take(held_back));
+                self.state = JitterFilterState::Moving;
+                events
+            }
+            (_, PenEvent::Cancel) => {
+                // The held back events never reached the pen, so there is nothing to cancel for them
+                self.state = JitterFilterState::Idle;
+                vec![(PenEvent::Cancel, now)]
+            }
+            (_, event @ PenEvent::Up { .. }) => {
+                let mut events = self.take_held_back();
+                events.push((event, now));
+                events
+            }
+            (JitterFilterState::Settling { .. }, event) => {
+                let mut events = self.take_held_back();
+                events.push((event, now));
+                self.state = JitterFilterState::Moving;
+                events
+            }
+            (JitterFilterState::Idle | JitterFilterState::Moving, event) => vec![(event, now)],
+        }
+    }
+
+    /// Take the held back events and reset the filter.
+    fn take_held_back(&mut self) -> Vec<(PenEvent, Instant)> {
+        match std::mem::take(&mut self.state) {
+            JitterFilterState::Settling { held_back } => held_back,
+            JitterFilterState::Idle | JitterFilterState::Moving => vec![],
+        }
+    }
+}
+
+/// Discard the down events before the last reversal of the motion, relative to the overall direction from the first
+/// to the last position.
+fn trim_jitter(mut events: Vec<(PenEvent, Instant)>) -> Vec<(PenEvent, Instant)> {
+    let positions = events
+        .iter()
+        .filter_map(|(event, _)| match event {
+            PenEvent::Down { element, .. } => Some(element.pos),
+            _ => None,
+        })
+        .collect::<Vec<na::Vector2<f64>>>();
+    if positions.len() != events.len() || positions.len() < 2 {
+        return events;
+    }
+    let direction = positions[positions.len() - 1] - positions[0];
+    let mut start = positions.len() - 1;
+    while start > 0 && (positions[start] - positions[start - 1]).dot(&direction) > 0.0 {
+        start -= 1;
+    }
+    events.drain(..start);
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JitterFilter;
+    use rnote_compose::penevent::PenEvent;
+    use rnote_compose::penpath::Element;
+    use std::time::{Duration, Instant};
+
+    fn down(x: f64, y: f64) -> PenEvent {
+        PenEvent::Down {
+            element: Element::new(na::vector![x, y], 0.5),
+            modifier_keys: Default::default(),
+        }
+    }
+
+    /// Feeds the positions as down events followed by an up event, returning the positions of the filtered down
+    /// events.
+    fn filtered_positions(positions: &[(f64, f64)], enabled: bool) -> Vec<na::Vector2<f64>> {
+        let mut filter = JitterFilter::default();
+        let now = Instant::now();
+        let mut events = positions
+            .iter()
+            .flat_map(|&(x, y)| filter.filter_event(down(x, y), now, enabled, 1.0))
+            .collect::<Vec<(PenEvent, Instant)>>();
+        let up = PenEvent::Up {
+            element: Element::new(na::vector![20.0, 0.0], 0.5),
+            modifier_keys: Default::default(),
+        };
+        events.extend(filter.filter_event(up, now, enabled, 1.0));
+        assert!(matches!(events.last(), Some((PenEvent::Up { .. }, _))));
+        events
+            .into_iter()
+            .filter_map(|(event, _)| match event {
+                PenEvent::Down { element, .. } => Some(element.pos),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn jittery_start_gets_trimmed() {
+        let jitter = [(0.0, 0.0), (1.0, 0.0), (-0.5, 0.0), (1.0, 0.2), (0.0, 0.0)];
+        let clean = [(0.5, 0.0), (1.5, 0.0), (3.0, 0.0), (4.5, 0.0), (6.0, 0.0)];
+        let input = jitter
+            .iter()
+            .chain(clean.iter())
+            .copied()
+            .collect::<Vec<_>>();
+
+        let filtered = filtered_positions(&input, true);
+        // starts at the last reversal, where the consistent motion begins
+        assert_eq!(filtered.len(), clean.len() + 1);
+        assert_eq!(filtered[0], na::vector![0.0, 0.0]);
+        assert!(filtered.windows(2).all(|w| w[1][0] > w[0][0]));
+
+        // not filtered when disabled or without jitter
+        assert_eq!(filtered_positions(&input, false).len(), input.len());
+        assert_eq!(filtered_positions(&clean, true).len(), clean.len());
+        // taps are kept
+        assert_eq!(
+            filtered_positions(&[(0.0, 0.0), (0.5, 0.0), (0.0, 0.0)], true).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn held_back_events_keep_their_time() {
+        let mut filter = JitterFilter::default();
+        let start = Instant::now();
+        let positions = [0.0, 1.0, 2.0, 4.0];
+        let events = positions
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &x)| {
+                filter.filter_event(
+                    down(x, 0.0),
+                    start + Duration::from_millis(i as u64 * 10),
+                    true,
+                    1.0,
+                )
+            })
+            .collect::<Vec<(PenEvent, Instant)>>();
+
+        assert_eq!(events.len(), positions.len());
+        for (i, (_, time)) in events.iter().enumerate() {
+            assert_eq!(*time, start + Duration::from_millis(i as u64 * 10));
+        }
+    }
+
+    #[test]
+    fn jitter_radius_scales_with_zoom() {
+        // moves two document units, which is beyond the radius on the screen when zoomed in
+        let mut filter = JitterFilter::default();
+        let now = Instant::now();
+        assert!(filter
+            .filter_event(down(0.0, 0.0), now, true, 4.0)
+            .is_empty());
+        assert_eq!(filter.filter_event(down(2.0, 0.0), now, true, 4.0).len(), 2);
+
+        let mut filter = JitterFilter::default();
+        assert!(filter
+            .filter_event(down(0.0, 0.0), now, true, 1.0)
+            .is_empty());
+        assert!(filter
+            .filter_event(down(2.0, 0.0), now, true, 1.0)
+            .is_empty());
+    }
+}
@@ -17,6 +17,8 @@ pub mod drawable;
 pub mod engine;
 pub mod ext;
 pub mod fileformats;
+pub mod inputclock;
+pub mod jitterfilter;
 pub mod pens;
 pub mod render;
 pub mod selectioncollision;
@@ -24,6 +26,7 @@ pub mod snap;
 pub mod store;
 pub mod strokes;
 pub mod tasks;
+pub mod touchgesture;
 pub mod utils;
 pub mod widgetflags;
 
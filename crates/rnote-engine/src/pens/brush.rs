@@ -1,10 +1,16 @@
 // Imports
+use super::dwell::DwellDetector;
 use super::pensconfig::brushconfig::BrushStyle;
+use super::pensconfig::BrushConfig;
+use super::smoothing::InputSmoother;
+use super::straighten::InputStraightener;
 use super::PenBehaviour;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
+use crate::snap::{snap_point, SnapContext, SnapIndicator};
 use crate::store::StrokeKey;
 use crate::strokes::BrushStroke;
+use crate::strokes::ShapeStroke;
 use crate::strokes::Stroke;
 use crate::{DrawableOnDoc, WidgetFlags};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
@@ -16,7 +22,9 @@ use rnote_compose::builders::{
 use rnote_compose::eventresult::{EventPropagation, EventResult};
 use rnote_compose::penevent::{PenEvent, PenProgress};
 use rnote_compose::penpath::{Element, Segment};
-use rnote_compose::Constraints;
+use rnote_compose::shapes::recognize_shape;
+use rnote_compose::{Constraints, Shape};
+use std::collections::VecDeque;
 use std::time::Instant;
 
 #[derive(Debug)]
@@ -25,18 +33,38 @@ enum BrushState {
     Drawing {
         path_builder: Box<dyn Buildable<Emit = Segment>>,
         current_stroke_key: StrokeKey,
+        started: Instant,
     },
 }
 
 #[derive(Debug)]
 pub struct Brush {
     state: BrushState,
+    smoother: InputSmoother,
+    straightener: InputStraightener,
+    dwell: DwellDetector,
+    /// Indicates the snap of the start of the current stroke.
+    snap_indicator: SnapIndicator,
+    /// The raw and the remapped pressure of the most recent input elements of the current stroke.
+    pressure_samples: VecDeque<(f64, f64)>,
+    /// The pressure at the end of the last committed stroke, which determines its end width.
+    last_end_pressure: Option<f64>,
+    /// While handling a batch of events, the number of new segments whose rendering is deferred to the end of the
+    /// batch.
+    deferred_segments: Option<usize>,
 }
 
 impl Default for Brush {
     fn default() -> Self {
         Self {
             state: BrushState::Idle,
+            smoother: InputSmoother::default(),
+            straightener: InputStraightener::default(),
+            dwell: DwellDetector::default(),
+            snap_indicator: SnapIndicator::default(),
+            pressure_samples: VecDeque::with_capacity(Self::PRESSURE_SAMPLES_MAX),
+            last_end_pressure: None,
+            deferred_segments: None,
         }
     }
 }
@@ -65,21 +93,221 @@ impl PenBehaviour for Brush {
         engine_view: &mut EngineViewMut,
     ) -> (EventResult<PenProgress>, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
+        let raw_pressure = match &event {
+            PenEvent::Down { element, .. } | PenEvent::Up { element, .. } => Some(element.pressure),
+            _ => None,
+        };
+        let event = apply_pressure_influence(event, &engine_view.pens_config.brush_config);
+        let event = self
+            .smoother
+            .smooth_event(event, engine_view.pens_config.smoothing(PenStyle::Brush));
+        let event = self.straightener.straighten_event(
+            event,
+            now,
+            engine_view.pens_config.brush_config.adaptive_straighten,
+        );
+        if let (Some(raw_pressure), PenEvent::Down { element, .. } | PenEvent::Up { element, .. }) =
+            (raw_pressure, &event)
+        {
+            if matches!(self.state, BrushState::Idle) {
+                self.pressure_samples.clear();
+            }
+            self.record_pressure_sample(raw_pressure, element.pressure);
+        }
+
+        // Holding the pen still at the end of the stroke finishes it as if the pen was released,
+        // and snaps it into a shape
+        let hold_to_snap = engine_view
+            .pens_config
+            .brush_config
+            .hold_to_snap
+            .then_some(BrushConfig::HOLD_TO_SNAP_DURATION);
+        let Some(event) = self.dwell.handle_event(
+            event,
+            now,
+            hold_to_snap,
+            DwellDetector::RADIUS_PX / engine_view.camera.total_zoom(),
+        ) else {
+            // The stroke was already finished, only the release is left
+            return (
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: PenProgress::Idle,
+                },
+                widget_flags,
+            );
+        };
+        let snap = self.dwell.committed();
+
+        let (event_result, wf) = self.handle_stroke_event(event, now, snap, engine_view);
+        widget_flags |= wf;
+
+        (event_result, widget_flags)
+    }
+
+    fn handle_frame_tick(
+        &mut self,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (Option<PenProgress>, WidgetFlags) {
+        let hold_to_snap = engine_view
+            .pens_config
+            .brush_config
+            .hold_to_snap
+            .then_some(BrushConfig::HOLD_TO_SNAP_DURATION);
+        let Some(event) = self.dwell.handle_frame_tick(now, hold_to_snap) else {
+            return (None, WidgetFlags::default());
+        };
+        let (event_result, widget_flags) = self.handle_stroke_event(event, now, true, engine_view);
+
+        (Some(event_result.progress), widget_flags)
+    }
+
+    fn handle_event_batch(
+        &mut self,
+        events: Vec<(PenEvent, Instant)>,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventResult<PenProgress>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let mut event_result = EventResult {
+            handled: false,
+            propagate: EventPropagation::Proceed,
+            progress: PenProgress::Idle,
+        };
+        self.deferred_segments = Some(0);
+        for (event, now) in events {
+            let (er, wf) = self.handle_event(event, now, engine_view);
+            widget_flags |= wf;
+            event_result = EventResult {
+                handled: event_result.handled || er.handled,
+                propagate: event_result.propagate | er.propagate,
+                progress: er.progress,
+            };
+        }
+        // The new segments of the batch are rendered at once
+        let n_segments = self.deferred_segments.take().unwrap_or(0);
+        if let (
+            true,
+            BrushState::Drawing {
+                current_stroke_key, ..
+            },
+        ) = (n_segments > 0, &self.state)
+        {
+            engine_view.store.append_rendering_last_segments(
+                engine_view.tasks_tx.clone(),
+                *current_stroke_key,
+                n_segments,
+                engine_view.camera.viewport(),
+                engine_view.camera.image_scale(),
+            );
+        }
+        (event_result, widget_flags)
+    }
+}
+
+impl DrawableOnDoc for Brush {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        let style = engine_view
+            .pens_config
+            .brush_config
+            .style_for_current_options();
+
+        match &self.state {
+            BrushState::Idle => None,
+            BrushState::Drawing { path_builder, .. } => {
+                let bounds = path_builder.bounds(&style, engine_view.camera.zoom());
+                match self.snap_indicator.bounds_on_doc(engine_view) {
+                    Some(indicator_bounds) => Some(
+                        bounds.map_or(indicator_bounds, |bounds| bounds.merged(&indicator_bounds)),
+                    ),
+                    None => bounds,
+                }
+            }
+        }
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+        match &self.state {
+            BrushState::Idle => {}
+            BrushState::Drawing { path_builder, .. } => {
+                match engine_view.pens_config.brush_config.style {
+                    BrushStyle::Marker => {
+                        // Don't draw the marker, as the pen would render on top of other strokes, while the stroke itself would render underneath them.
+                    }
+                    BrushStyle::Solid | BrushStyle::Textured => {
+                        let style = engine_view
+                            .pens_config
+                            .brush_config
+                            .style_for_current_options();
+                        path_builder.draw_styled(cx, &style, engine_view.camera.total_zoom());
+                    }
+                }
+                self.snap_indicator.draw_on_doc(cx, engine_view)?;
+            }
+        }
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+impl Brush {
+    const INPUT_OVERSHOOT: f64 = 30.0;
+
+    /// Handle the event after the dwell detection. Snaps the stroke into a shape when it is finished and `snap` is
+    /// true.
+    fn handle_stroke_event(
+        &mut self,
+        event: PenEvent,
+        now: Instant,
+        snap: bool,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventResult<PenProgress>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
 
         let event_result = match (&mut self.state, event) {
-            (BrushState::Idle, PenEvent::Down { element, .. }) => {
+            (BrushState::Idle, PenEvent::Down { mut element, .. }) => {
                 if !element.filter_by_bounds(
                     engine_view
                         .document
                         .bounds()
                         .loosened(Self::INPUT_OVERSHOOT),
                 ) {
+                    self.snap_indicator.clear();
+                    if engine_view.pens_config.brush_config.auto_connect {
+                        let snap_result = snap_point(
+                            element.pos,
+                            &SnapContext::new(engine_view.document, engine_view.store)
+                                .without_grid()
+                                .with_endpoints(
+                                    BrushConfig::AUTO_CONNECT_DIST_PX
+                                        / engine_view.camera.total_zoom(),
+                                ),
+                        );
+                        element.pos = snap_result.pos;
+                        self.snap_indicator.update(snap_result);
+                    }
+
                     if engine_view.pens_config.brush_config.style == BrushStyle::Marker {
                         play_marker_sound(engine_view);
                     } else {
                         trigger_brush_sound(engine_view);
                     }
 
+                    if engine_view.pens_config.brush_config.continue_previous_width {
+                        // Start with the width the previous stroke ended with
+                        if let Some(last_end_pressure) = self.last_end_pressure {
+                            element.pressure = last_end_pressure;
+                        }
+                    }
+
                     engine_view.pens_config.brush_config.new_style_seeds();
 
                     let brushstroke = Stroke::BrushStroke(BrushStroke::new(
@@ -112,6 +340,7 @@ impl PenBehaviour for Brush {
                             now,
                         ),
                         current_stroke_key,
+                        started: now,
                     };
 
                     EventResult {
@@ -134,10 +363,29 @@ impl PenBehaviour for Brush {
             },
             (
                 BrushState::Drawing {
-                    current_stroke_key, ..
+                    current_stroke_key,
+                    started,
+                    ..
                 },
                 PenEvent::Cancel,
             ) => {
+                if discard_micro_stroke(*current_stroke_key, *started, now, engine_view) {
+                    self.state = BrushState::Idle;
+                    widget_flags.redraw = true;
+
+                    return (
+                        EventResult {
+                            handled: true,
+                            propagate: EventPropagation::Stop,
+                            progress: PenProgress::Finished,
+                        },
+                        widget_flags,
+                    );
+                }
+                apply_curve_fit(*current_stroke_key, engine_view);
+                self.last_end_pressure =
+                    end_pressure(*current_stroke_key, engine_view).or(self.last_end_pressure);
+
                 // Finish up the last stroke
                 engine_view
                     .store
@@ -167,6 +415,7 @@ impl PenBehaviour for Brush {
                 BrushState::Drawing {
                     path_builder,
                     current_stroke_key,
+                    started,
                 },
                 pen_event,
             ) => {
@@ -198,13 +447,17 @@ impl PenBehaviour for Brush {
                                 widget_flags.store_modified = true;
                             }
 
-                            engine_view.store.append_rendering_last_segments(
-                                engine_view.tasks_tx.clone(),
-                                *current_stroke_key,
-                                n_segments,
-                                engine_view.camera.viewport(),
-                                engine_view.camera.image_scale(),
-                            );
+                            if let Some(deferred_segments) = self.deferred_segments.as_mut() {
+                                *deferred_segments += n_segments;
+                            } else {
+                                engine_view.store.append_rendering_last_segments(
+                                    engine_view.tasks_tx.clone(),
+                                    *current_stroke_key,
+                                    n_segments,
+                                    engine_view.camera.viewport(),
+                                    engine_view.camera.image_scale(),
+                                );
+                            }
                         }
 
                         PenProgress::InProgress
@@ -229,6 +482,26 @@ impl PenBehaviour for Brush {
                             );
                         }
 
+                        if discard_micro_stroke(*current_stroke_key, *started, now, engine_view) {
+                            self.state = BrushState::Idle;
+                            widget_flags.redraw = true;
+
+                            return (
+                                EventResult {
+                                    handled,
+                                    propagate,
+                                    progress: PenProgress::Finished,
+                                },
+                                widget_flags,
+                            );
+                        }
+                        apply_curve_fit(*current_stroke_key, engine_view);
+                        self.last_end_pressure = end_pressure(*current_stroke_key, engine_view)
+                            .or(self.last_end_pressure);
+                        if snap {
+                            snap_to_shape(*current_stroke_key, engine_view);
+                        }
+
                         // Finish up the last stroke
                         engine_view
                             .store
@@ -262,57 +535,28 @@ impl PenBehaviour for Brush {
 
         (event_result, widget_flags)
     }
-}
-
-impl DrawableOnDoc for Brush {
-    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
-        let style = engine_view
-            .pens_config
-            .brush_config
-            .style_for_current_options();
+    /// The maximum number of recorded pressure samples.
+    pub(crate) const PRESSURE_SAMPLES_MAX: usize = 128;
 
-        match &self.state {
+    /// The raw and the remapped pressure of the most recent input elements, oldest first.
+    ///
+    /// Is None when no stroke is currently drawn.
+    #[allow(unused)]
+    pub(crate) fn pressure_samples(&self) -> Option<&VecDeque<(f64, f64)>> {
+        match self.state {
             BrushState::Idle => None,
-            BrushState::Drawing { path_builder, .. } => {
-                path_builder.bounds(&style, engine_view.camera.zoom())
-            }
+            BrushState::Drawing { .. } => Some(&self.pressure_samples),
         }
     }
 
-    fn draw_on_doc(
-        &self,
-        cx: &mut piet_cairo::CairoRenderContext,
-        engine_view: &EngineView,
-    ) -> anyhow::Result<()> {
-        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
-
-        match &self.state {
-            BrushState::Idle => {}
-            BrushState::Drawing { path_builder, .. } => {
-                match engine_view.pens_config.brush_config.style {
-                    BrushStyle::Marker => {
-                        // Don't draw the marker, as the pen would render on top of other strokes, while the stroke itself would render underneath them.
-                    }
-                    BrushStyle::Solid | BrushStyle::Textured => {
-                        let style = engine_view
-                            .pens_config
-                            .brush_config
-                            .style_for_current_options();
-                        path_builder.draw_styled(cx, &style, engine_view.camera.total_zoom());
-                    }
-                }
-            }
+    fn record_pressure_sample(&mut self, raw: f64, remapped: f64) {
+        if self.pressure_samples.len() >= Self::PRESSURE_SAMPLES_MAX {
+            self.pressure_samples.pop_front();
         }
-
-        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
-        Ok(())
+        self.pressure_samples.push_back((raw, remapped));
     }
 }
 
-impl Brush {
-    const INPUT_OVERSHOOT: f64 = 30.0;
-}
-
 fn play_marker_sound(engine_view: &mut EngineViewMut) {
     if let Some(audioplayer) = engine_view.audioplayer {
         audioplayer.play_random_marker_sound();
@@ -325,6 +569,111 @@ fn trigger_brush_sound(engine_view: &mut EngineViewMut) {
     }
 }
 
+/// Refit the stroke with the configured curve fit, if there is one.
+fn apply_curve_fit(key: StrokeKey, engine_view: &mut EngineViewMut) {
+    let Some(curve_fit) = engine_view.pens_config.brush_config.curve_fit else {
+        return;
+    };
+    let tolerance =
+        engine_view.pens_config.brush_config.fit_tolerance() / engine_view.camera.total_zoom();
+
+    if let Some(Stroke::BrushStroke(brushstroke)) = engine_view.store.get_stroke_mut(key) {
+        brushstroke.apply_curve_fit(curve_fit, tolerance);
+    }
+}
+
+/// Removes the stroke if it is too small to be kept, according to the minimum extent and duration of the brush config.
+///
+/// Returns true when the stroke was removed.
+fn discard_micro_stroke(
+    key: StrokeKey,
+    started: Instant,
+    now: Instant,
+    engine_view: &mut EngineViewMut,
+) -> bool {
+    let Some(Stroke::BrushStroke(brushstroke)) = engine_view.store.get_stroke_ref(key) else {
+        return false;
+    };
+    let start = brushstroke.path.start.pos;
+    let extent = brushstroke
+        .path
+        .clone()
+        .into_elements()
+        .into_iter()
+        .map(|element| (element.pos - start).magnitude())
+        .fold(0.0, f64::max);
+
+    if !engine_view.pens_config.brush_config.is_micro_stroke(
+        extent * engine_view.camera.total_zoom(),
+        now.saturating_duration_since(started),
+    ) {
+        return false;
+    }
+    engine_view.store.remove_stroke(key);
+    true
+}
+
+/// The pressure of the last element of the brush stroke.
+fn end_pressure(key: StrokeKey, engine_view: &EngineViewMut) -> Option<f64> {
+    let Some(Stroke::BrushStroke(brushstroke)) = engine_view.store.get_stroke_ref(key) else {
+        return None;
+    };
+    Some(
+        brushstroke
+            .path
+            .segments
+            .last()
+            .map_or(brushstroke.path.start, |seg| *seg.end())
+            .pressure,
+    )
+}
+
+/// Replaces the brush stroke with the clean shape it resembles, if one is recognized.
+fn snap_to_shape(key: StrokeKey, engine_view: &mut EngineViewMut) {
+    let Some(Stroke::BrushStroke(brushstroke)) = engine_view.store.get_stroke_ref(key) else {
+        return;
+    };
+    let points = brushstroke
+        .path
+        .clone()
+        .into_elements()
+        .into_iter()
+        .map(|element| element.pos)
+        .collect::<Vec<na::Vector2<f64>>>();
+    let Some(mut shape) = recognize_shape(&points, BrushConfig::HOLD_TO_SNAP_TOLERANCE) else {
+        return;
+    };
+    if let Shape::Line(line) = &mut shape {
+        // Snap the angle of the line
+        line.end = snap_point(
+            line.end,
+            &SnapContext::new(engine_view.document, engine_view.store)
+                .with_angle_origin(line.start),
+        )
+        .pos;
+    }
+    // Shapes can't be drawn textured
+    let shapestroke =
+        Stroke::ShapeStroke(ShapeStroke::new(shape, brushstroke.style.to_shape_style()));
+
+    if let Some(stroke) = engine_view.store.get_stroke_mut(key) {
+        *stroke = shapestroke;
+    }
+}
+
+/// Maps the pressure of the event element to the effective pressure of the brush config.
+fn apply_pressure_influence(mut event: PenEvent, brush_config: &BrushConfig) -> PenEvent {
+    match &mut event {
+        PenEvent::Down { element, .. }
+        | PenEvent::Up { element, .. }
+        | PenEvent::Proximity { element, .. } => {
+            element.pressure = brush_config.effective_pressure(element.pressure);
+        }
+        PenEvent::KeyPressed { .. } | PenEvent::Text { .. } | PenEvent::Cancel => {}
+    }
+    event
+}
+
 fn new_builder(
     builder_type: PenPathBuilderType,
     element: Element,
@@ -336,3 +685,379 @@ fn new_builder(
         PenPathBuilderType::Modeled => Box::new(PenPathModeledBuilder::start(element, now)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use approx::assert_relative_eq;
+    use rnote_compose::{PenPath, Style};
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    #[test]
+    fn auto_connect_starts_at_nearby_endpoint() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.pens_config.brush_config.auto_connect = true;
+        let endpoint = na::vector![200.0, 100.0];
+        engine.store.insert_stroke(
+            Stroke::BrushStroke(BrushStroke::from_penpath(
+                PenPath::new_w_segments(
+                    Element::new(na::vector![100.0, 100.0], 0.5),
+                    [Segment::LineTo {
+                        end: Element::new(endpoint, 0.5),
+                    }],
+                ),
+                engine.pens_config.brush_config.style_for_current_options(),
+            )),
+            None,
+        );
+        let mut brush = Brush::default();
+        let now = Instant::now();
+
+        let _ = brush.handle_event(
+            PenEvent::Down {
+                element: Element::new(na::vector![204.0, 103.0], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            now,
+            &mut engine.view_mut(),
+        );
+        // the snap to the endpoint is indicated while drawing
+        assert!(brush.snap_indicator.bounds_on_doc(&engine.view()).is_some());
+        let _ = brush.handle_event(
+            PenEvent::Up {
+                element: Element::new(na::vector![250.0, 150.0], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            now,
+            &mut engine.view_mut(),
+        );
+
+        let keys = engine.store.stroke_keys_as_rendered();
+        assert_eq!(keys.len(), 2);
+        let Some(Stroke::BrushStroke(new_stroke)) = engine.store.get_stroke_ref(keys[1]) else {
+            panic!("the new stroke is not a brush stroke");
+        };
+        assert_eq!(new_stroke.path.start.pos, endpoint);
+
+        // strokes started further away are not connected
+        let _ = brush.handle_event(
+            PenEvent::Down {
+                element: Element::new(na::vector![230.0, 100.0], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            now,
+            &mut engine.view_mut(),
+        );
+        let keys = engine.store.stroke_keys_as_rendered();
+        let Some(Stroke::BrushStroke(far_stroke)) = engine.store.get_stroke_ref(keys[2]) else {
+            panic!("the new stroke is not a brush stroke");
+        };
+        assert_eq!(far_stroke.path.start.pos, na::vector![230.0, 100.0]);
+        assert!(brush.snap_indicator.bounds_on_doc(&engine.view()).is_none());
+    }
+
+    /// Set up hold to snap and draw a circle with the brush, without releasing the pen.
+    fn draw_circle_for_hold_to_snap(start: Instant, brush_style: BrushStyle) -> (Engine, Brush) {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.style = brush_style;
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.pens_config.brush_config.hold_to_snap = true;
+        let mut brush = Brush::default();
+
+        for i in 0..=40 {
+            let angle = i as f64 / 40.0 * std::f64::consts::TAU;
+            let _ = brush.handle_event(
+                PenEvent::Down {
+                    element: Element::new(
+                        na::vector![200.0, 200.0] + na::vector![angle.cos(), angle.sin()] * 50.0,
+                        0.5,
+                    ),
+                    modifier_keys: HashSet::new(),
+                },
+                start + Duration::from_millis(i * 10),
+                &mut engine.view_mut(),
+            );
+        }
+        let keys = engine.store.stroke_keys_as_rendered();
+        assert_eq!(keys.len(), 1);
+        assert!(matches!(
+            engine.store.get_stroke_ref(keys[0]),
+            Some(Stroke::BrushStroke(_))
+        ));
+
+        (engine, brush)
+    }
+
+    fn assert_snapped_into_ellipse(engine: &Engine) {
+        let keys = engine.store.stroke_keys_as_rendered();
+        assert_eq!(keys.len(), 1);
+        let Some(Stroke::ShapeStroke(shapestroke)) = engine.store.get_stroke_ref(keys[0]) else {
+            panic!("the stroke was not snapped into a shape");
+        };
+        assert!(matches!(shapestroke.shape, Shape::Ellipse(_)));
+    }
+
+    #[test]
+    fn hold_to_snap_converts_stroke_into_shape() {
+        let start = Instant::now();
+        let (mut engine, mut brush) = draw_circle_for_hold_to_snap(start, BrushStyle::Solid);
+        let down = |pos: na::Vector2<f64>| PenEvent::Down {
+            element: Element::new(pos, 0.5),
+            modifier_keys: HashSet::new(),
+        };
+
+        // then hold still at the end
+        let (result, _) = brush.handle_event(
+            down(na::vector![250.5, 200.0]),
+            start + Duration::from_millis(1000),
+            &mut engine.view_mut(),
+        );
+        assert_eq!(result.progress, PenProgress::Finished);
+        assert_snapped_into_ellipse(&engine);
+
+        // moving further until the pen is released does not start a new stroke
+        let _ = brush.handle_event(
+            down(na::vector![300.0, 300.0]),
+            start + Duration::from_millis(1100),
+            &mut engine.view_mut(),
+        );
+        let _ = brush.handle_event(
+            PenEvent::Up {
+                element: Element::new(na::vector![300.0, 300.0], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            start + Duration::from_millis(1200),
+            &mut engine.view_mut(),
+        );
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 1);
+    }
+
+    #[test]
+    fn hold_to_snap_snaps_line_angle() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.pens_config.brush_config.hold_to_snap = true;
+        let mut brush = Brush::default();
+        let start = Instant::now();
+
+        // a line slightly off the horizontal
+        for i in 0..=20 {
+            let _ = brush.handle_event(
+                PenEvent::Down {
+                    element: Element::new(
+                        na::vector![100.0 + i as f64 * 10.0, 100.0 + i as f64 * 0.25],
+                        0.5,
+                    ),
+                    modifier_keys: HashSet::new(),
+                },
+                start + Duration::from_millis(i * 10),
+                &mut engine.view_mut(),
+            );
+        }
+        let (progress, _) =
+            brush.handle_frame_tick(start + Duration::from_millis(1500), &mut engine.view_mut());
+        assert_eq!(progress, Some(PenProgress::Finished));
+
+        let keys = engine.store.stroke_keys_as_rendered();
+        let Some(Stroke::ShapeStroke(shapestroke)) = engine.store.get_stroke_ref(keys[0]) else {
+            panic!("the stroke was not snapped into a shape");
+        };
+        let Shape::Line(line) = &shapestroke.shape else {
+            panic!("the stroke was not snapped into a line");
+        };
+        assert_relative_eq!(line.start, na::vector![100.0, 100.0]);
+        assert_relative_eq!(line.end, na::vector![300.0, 100.0], epsilon = 1e-9);
+    }
+
+    #[test]
+    fn hold_to_snap_converts_stroke_without_further_motion() {
+        let start = Instant::now();
+        let (mut engine, mut brush) = draw_circle_for_hold_to_snap(start, BrushStyle::Solid);
+
+        // the pen is held perfectly still, so only display frames arrive
+        let (progress, _) =
+            brush.handle_frame_tick(start + Duration::from_millis(500), &mut engine.view_mut());
+        assert_eq!(progress, None);
+
+        let (progress, _) =
+            brush.handle_frame_tick(start + Duration::from_millis(1000), &mut engine.view_mut());
+        assert_eq!(progress, Some(PenProgress::Finished));
+        assert_snapped_into_ellipse(&engine);
+    }
+
+    #[test]
+    fn hold_to_snap_converts_textured_stroke_into_smooth_shape() {
+        let start = Instant::now();
+        let (mut engine, mut brush) = draw_circle_for_hold_to_snap(start, BrushStyle::Textured);
+
+        let (progress, _) =
+            brush.handle_frame_tick(start + Duration::from_millis(1000), &mut engine.view_mut());
+        assert_eq!(progress, Some(PenProgress::Finished));
+        assert_snapped_into_ellipse(&engine);
+        let keys = engine.store.stroke_keys_as_rendered();
+        let Some(Stroke::ShapeStroke(shapestroke)) = engine.store.get_stroke_ref(keys[0]) else {
+            panic!("the stroke was not snapped into a shape");
+        };
+        let Style::Smooth(options) = &shapestroke.style else {
+            panic!("the shape is not drawn smooth");
+        };
+        assert_relative_eq!(
+            options.stroke_width,
+            engine
+                .pens_config
+                .brush_config
+                .textured_options
+                .stroke_width
+        );
+    }
+
+    #[test]
+    fn micro_strokes_are_discarded_unless_dot_mode() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.pens_config.brush_config.min_stroke_extent = 3.0;
+        engine.pens_config.brush_config.min_stroke_duration = Duration::from_millis(80);
+        let mut brush = Brush::default();
+        let start = Instant::now();
+        let tap = |engine: &mut Engine, brush: &mut Brush| {
+            for (i, pos) in [
+                na::vector![100.0, 100.0],
+                na::vector![101.0, 99.5],
+                na::vector![100.5, 101.0],
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let _ = brush.handle_event(
+                    PenEvent::Down {
+                        element: Element::new(pos, 0.5),
+                        modifier_keys: HashSet::new(),
+                    },
+                    start + Duration::from_millis(i as u64 * 10),
+                    &mut engine.view_mut(),
+                );
+            }
+            brush.handle_event(
+                PenEvent::Up {
+                    element: Element::new(na::vector![100.0, 100.5], 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                start + Duration::from_millis(40),
+                &mut engine.view_mut(),
+            )
+        };
+
+        // a jittery tap below the thresholds
+        let (result, _) = tap(&mut engine, &mut brush);
+        assert_eq!(result.progress, PenProgress::Finished);
+        assert!(engine.store.stroke_keys_as_rendered().is_empty());
+
+        // a deliberate dot
+        engine.pens_config.brush_config.dot_mode = true;
+        let (result, _) = tap(&mut engine, &mut brush);
+        assert_eq!(result.progress, PenProgress::Finished);
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 1);
+    }
+
+    #[test]
+    fn pressure_samples_are_bounded() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.pens_config.brush_config.set_pressure_influence(0.0);
+        let mut brush = Brush::default();
+        let start = Instant::now();
+        assert!(brush.pressure_samples().is_none());
+
+        for i in 0..(Brush::PRESSURE_SAMPLES_MAX + 20) {
+            let _ = brush.handle_event(
+                PenEvent::Down {
+                    element: Element::new(na::vector![100.0 + i as f64, 100.0], 0.2),
+                    modifier_keys: HashSet::new(),
+                },
+                start + Duration::from_millis(i as u64),
+                &mut engine.view_mut(),
+            );
+        }
+        let samples = brush.pressure_samples().unwrap();
+        assert_eq!(samples.len(), Brush::PRESSURE_SAMPLES_MAX);
+        // without pressure influence the remapped pressure is always the full pressure
+        assert!(samples
+            .iter()
+            .all(|&(raw, remapped)| raw == 0.2 && remapped == 1.0));
+    }
+
+    #[test]
+    fn continue_previous_width_seeds_start_width() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.pens_config.brush_config.continue_previous_width = true;
+        let mut brush = Brush::default();
+        let now = Instant::now();
+        let mut draw = |engine: &mut Engine, elements: &[Element]| {
+            for &element in &elements[..elements.len() - 1] {
+                let _ = brush.handle_event(
+                    PenEvent::Down {
+                        element,
+                        modifier_keys: HashSet::new(),
+                    },
+                    now,
+                    &mut engine.view_mut(),
+                );
+            }
+            let _ = brush.handle_event(
+                PenEvent::Up {
+                    element: elements[elements.len() - 1],
+                    modifier_keys: HashSet::new(),
+                },
+                now,
+                &mut engine.view_mut(),
+            );
+        };
+        let width_at = |engine: &Engine, key: StrokeKey, first: bool| {
+            let Some(Stroke::BrushStroke(brushstroke)) = engine.store.get_stroke_ref(key) else {
+                panic!("the stroke is not a brush stroke");
+            };
+            let Style::Smooth(options) = &brushstroke.style else {
+                panic!("the brush stroke style is not smooth");
+            };
+            let element = if first {
+                brushstroke.path.start
+            } else {
+                *brushstroke.path.segments.last().unwrap().end()
+            };
+            options
+                .pressure_curve
+                .apply(options.stroke_width, element.pressure)
+        };
+
+        draw(
+            &mut engine,
+            &[
+                Element::new(na::vector![100.0, 100.0], 0.3),
+                Element::new(na::vector![150.0, 100.0], 0.6),
+                Element::new(na::vector![200.0, 100.0], 0.9),
+            ],
+        );
+        draw(
+            &mut engine,
+            &[
+                Element::new(na::vector![200.0, 150.0], 0.2),
+                Element::new(na::vector![250.0, 150.0], 0.4),
+            ],
+        );
+        let keys = engine.store.stroke_keys_as_rendered();
+        assert_eq!(keys.len(), 2);
+        let prev_end_width = width_at(&engine, keys[0], false);
+        assert_relative_eq!(width_at(&engine, keys[1], true), prev_end_width);
+        assert!(prev_end_width > width_at(&engine, keys[0], true));
+    }
+}
@@ -0,0 +1,47 @@
+// Imports
+use super::{Pen, PenBehaviour, PenStyle};
+use crate::engine::EngineView;
+
+/// A hint for the pointer cursor that fits the current pen and its state, independent of the UI toolkit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CursorHint {
+    /// A crosshair for precise drawing.
+    Crosshair,
+    /// A circle with the given diameter in surface coordinates, outlining the area that is affected.
+    Circle(f64),
+    /// The default pointer.
+    Pointer,
+    /// A text cursor.
+    Text,
+    /// Moving something around.
+    Move,
+    /// Resizing from the corner in the direction.
+    Resize(ResizeDirection),
+}
+
+/// The direction of a resize cursor hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeDirection {
+    NorthWest,
+    NorthEast,
+    SouthWest,
+    SouthEast,
+}
+
+impl Pen {
+    /// The cursor hint for the pen in its current state.
+    pub fn cursor_hint(&self, engine_view: &EngineView) -> CursorHint {
+        match self {
+            Pen::Eraser(_) => CursorHint::Circle(
+                2.0 * engine_view.pens_config.eraser_config.largest_radius()
+                    * engine_view.camera.total_zoom(),
+            ),
+            Pen::Selector(selector) => selector.cursor_hint(engine_view),
+            pen => match pen.style() {
+                PenStyle::Brush | PenStyle::Shaper => CursorHint::Crosshair,
+                PenStyle::Typewriter => CursorHint::Text,
+                PenStyle::Eraser | PenStyle::Selector | PenStyle::Tools => CursorHint::Pointer,
+            },
+        }
+    }
+}
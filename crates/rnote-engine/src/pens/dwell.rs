@@ -0,0 +1,149 @@
+// Imports
+use rnote_compose::penevent::{ModifierKey, PenEvent};
+use rnote_compose::penpath::Element;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Tracks whether the pen dwells at the same position.
+#[derive(Debug, Clone)]
+struct Dwell {
+    anchor_pos: na::Vector2<f64>,
+    anchor_time: Instant,
+    /// Dwelling is only detected after the pen has moved once, so that nothing is committed right after pressing.
+    armed: bool,
+    /// Dwelling was detected and its action was committed, but the pen is not yet released.
+    committed: bool,
+    /// The last pen element and modifier keys, used for the up event when dwelling is detected without new motion.
+    last_element: Element,
+    last_modifier_keys: HashSet<ModifierKey>,
+}
+
+impl Dwell {
+    fn new(element: Element, modifier_keys: HashSet<ModifierKey>, now: Instant) -> Self {
+        Self {
+            anchor_pos: element.pos,
+            anchor_time: now,
+            armed: false,
+            committed: false,
+            last_element: element,
+            last_modifier_keys: modifier_keys,
+        }
+    }
+
+    /// Update with a new pen position.
+    fn update(&mut self, pos: na::Vector2<f64>, now: Instant, radius: f64) {
+        if (pos - self.anchor_pos).magnitude() > radius {
+            self.anchor_pos = pos;
+            self.anchor_time = now;
+            self.armed = true;
+        }
+    }
+
+    /// Whether the pen has dwelled for the given duration.
+    fn dwelled(&self, now: Instant, duration: Duration) -> bool {
+        self.armed && now.saturating_duration_since(self.anchor_time) >= duration
+    }
+}
+
+/// Detects whether the pen dwells at the same position while it is pressed down, so that pens can commit their action
+/// as if the pen was released.
+///
+/// Dwelling is checked on every pen event and on every display frame, so that it is also detected when the pen is
+/// held perfectly still and no motion events arrive.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DwellDetector {
+    dwell: Option<Dwell>,
+}
+
+impl DwellDetector {
+    /// The radius in surface coordinates the pen needs to stay within to be considered dwelling.
+    pub(crate) const RADIUS_PX: f64 = 4.0;
+
+    /// Whether dwelling was detected and the pen is not yet released.
+    pub(crate) fn committed(&self) -> bool {
+        self.dwell.as_ref().is_some_and(|dwell| dwell.committed)
+    }
+
+    /// Pass a pen event through the detection. Disabled when the duration is `None`.
+    ///
+    /// Returns the event that should be handled by the pen, which is an up event when dwelling was detected.
+    /// Returns `None` when the event should be ignored, because dwelling was already detected and the pen is not yet
+    /// released.
+    pub(crate) fn handle_event(
+        &mut self,
+        event: PenEvent,
+        now: Instant,
+        duration: Option<Duration>,
+        radius: f64,
+    ) -> Option<PenEvent> {
+        match event {
+            PenEvent::Down {
+                element,
+                modifier_keys,
+            } => {
+                let Some(duration) = duration else {
+                    self.dwell = None;
+                    return Some(PenEvent::Down {
+                        element,
+                        modifier_keys,
+                    });
+                };
+                let Some(dwell) = &mut self.dwell else {
+                    self.dwell = Some(Dwell::new(element, modifier_keys.clone(), now));
+                    return Some(PenEvent::Down {
+                        element,
+                        modifier_keys,
+                    });
+                };
+                if dwell.committed {
+                    return None;
+                }
+                dwell.update(element.pos, now, radius);
+                dwell.last_element = element;
+                dwell.last_modifier_keys.clone_from(&modifier_keys);
+                if dwell.dwelled(now, duration) {
+                    dwell.committed = true;
+                    Some(PenEvent::Up {
+                        element,
+                        modifier_keys,
+                    })
+                } else {
+                    Some(PenEvent::Down {
+                        element,
+                        modifier_keys,
+                    })
+                }
+            }
+            PenEvent::Up { .. } => {
+                // When already committed, only the release is left
+                (!self.dwell.take().is_some_and(|dwell| dwell.committed)).then_some(event)
+            }
+            PenEvent::Cancel => {
+                self.dwell = None;
+                Some(event)
+            }
+            event => Some(event),
+        }
+    }
+
+    /// Check for dwelling on a new display frame. Disabled when the duration is `None`.
+    ///
+    /// Returns the up event that should be handled by the pen when dwelling was detected.
+    pub(crate) fn handle_frame_tick(
+        &mut self,
+        now: Instant,
+        duration: Option<Duration>,
+    ) -> Option<PenEvent> {
+        let (Some(dwell), Some(duration)) = (&mut self.dwell, duration) else {
+            return None;
+        };
+        if dwell.committed || !dwell.dwelled(now, duration) {
+            return None;
+        }
+        dwell.committed = true;
+        Some(PenEvent::Up {
+            element: dwell.last_element,
+            modifier_keys: dwell.last_modifier_keys.clone(),
+        })
+    }
+}
@@ -4,6 +4,7 @@ use super::PenBehaviour;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
 use crate::{DrawableOnDoc, WidgetFlags};
+use geo::{BooleanOps, BoundingRect};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
 use rnote_compose::color;
@@ -23,12 +24,15 @@ pub enum EraserState {
 #[derive(Clone, Debug)]
 pub struct Eraser {
     pub(crate) state: EraserState,
+    /// The areas swept over while the pen is down in carve erasing style, which are carved when it is lifted.
+    carve_sweep: Vec<geo::Polygon<f64>>,
 }
 
 impl Default for Eraser {
     fn default() -> Self {
         Self {
             state: EraserState::Up,
+            carve_sweep: vec![],
         }
     }
 }
@@ -60,6 +64,8 @@ impl PenBehaviour for Eraser {
 
         let event_result = match (&mut self.state, event) {
             (EraserState::Up | EraserState::Proximity { .. }, PenEvent::Down { element, .. }) => {
+                self.carve_sweep.clear();
+                self.sweep_carve(None, element, engine_view);
                 widget_flags |= erase(element, engine_view);
                 self.state = EraserState::Down(element);
                 EventResult {
@@ -70,6 +76,7 @@ impl PenBehaviour for Eraser {
             }
             (EraserState::Up | EraserState::Down { .. }, PenEvent::Proximity { element, .. }) => {
                 self.state = EraserState::Proximity(element);
+                self.carve_sweep.clear();
                 EventResult {
                     handled: false,
                     propagate: EventPropagation::Proceed,
@@ -85,6 +92,7 @@ impl PenBehaviour for Eraser {
                 progress: PenProgress::Idle,
             },
             (EraserState::Down(current_element), PenEvent::Down { element, .. }) => {
+                self.sweep_carve(Some(*current_element), element, engine_view);
                 widget_flags |= erase(element, engine_view);
                 *current_element = element;
                 EventResult {
@@ -93,9 +101,12 @@ impl PenBehaviour for Eraser {
                     progress: PenProgress::InProgress,
                 }
             }
-            (EraserState::Down { .. }, PenEvent::Up { element, .. }) => {
-                widget_flags |=
-                    erase(element, engine_view) | engine_view.store.record(Instant::now());
+            (EraserState::Down(current_element), PenEvent::Up { element, .. }) => {
+                self.sweep_carve(Some(*current_element), element, engine_view);
+                widget_flags |= erase(element, engine_view)
+                    | erase_tap(element, engine_view)
+                    | erase_carve(&std::mem::take(&mut self.carve_sweep), engine_view);
+                widget_flags |= engine_view.store.record(Instant::now());
                 self.state = EraserState::Up;
                 EventResult {
                     handled: true,
@@ -126,6 +137,7 @@ impl PenBehaviour for Eraser {
             }
             (EraserState::Proximity { .. } | EraserState::Down { .. }, PenEvent::Cancel) => {
                 self.state = EraserState::Up;
+                self.carve_sweep.clear();
                 widget_flags |= engine_view.store.record(Instant::now());
                 EventResult {
                     handled: true,
@@ -159,16 +171,49 @@ impl PenBehaviour for Eraser {
     }
 }
 
+impl Eraser {
+    /// Collect the area swept over from the previous to the current element when in carve erasing style.
+    fn sweep_carve(
+        &mut self,
+        prev_element: Option<Element>,
+        element: Element,
+        engine_view: &EngineViewMut,
+    ) {
+        if matches!(
+            engine_view.pens_config.eraser_config.style,
+            EraserStyle::Carve
+        ) {
+            self.carve_sweep.push(
+                engine_view
+                    .pens_config
+                    .eraser_config
+                    .eraser_swept_polygon(prev_element, element),
+            );
+        }
+    }
+}
+
 impl DrawableOnDoc for Eraser {
     fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
         match &self.state {
             EraserState::Up => None,
-            EraserState::Proximity(current_element) | EraserState::Down(current_element) => Some(
-                engine_view
+            EraserState::Proximity(current_element) | EraserState::Down(current_element) => {
+                let bounds = engine_view
                     .pens_config
                     .eraser_config
-                    .eraser_bounds(*current_element),
-            ),
+                    .eraser_bounds(*current_element);
+                Some(
+                    self.carve_sweep
+                        .iter()
+                        .filter_map(|polygon| polygon.bounding_rect())
+                        .fold(bounds, |bounds, rect| {
+                            bounds.merged(&Aabb::new(
+                                na::point![rect.min().x, rect.min().y],
+                                na::point![rect.max().x, rect.max().y],
+                            ))
+                        }),
+                )
+            }
         }
     }
 
@@ -199,15 +244,33 @@ impl DrawableOnDoc for Eraser {
                 cx.stroke(outline_rect, &OUTLINE_COLOR, outline_width);
             }
             EraserState::Down(current_element) => {
+                // The area that is carved when the pen is lifted
+                let mut sweep_path = kurbo::BezPath::new();
+                for polygon in &self.carve_sweep {
+                    let mut coords = polygon.exterior().coords();
+                    let Some(first) = coords.next() else {
+                        continue;
+                    };
+                    sweep_path.move_to((first.x, first.y));
+                    for coord in coords {
+                        sweep_path.line_to((coord.x, coord.y));
+                    }
+                    sweep_path.close_path();
+                }
                 let bounds = engine_view
                     .pens_config
                     .eraser_config
                     .eraser_bounds(*current_element);
 
-                let fill_rect = bounds.to_kurbo_rect();
                 let outline_rect = bounds.tightened(outline_width * 0.5).to_kurbo_rect();
 
-                cx.fill(fill_rect, &FILL_COLOR);
+                if self.carve_sweep.is_empty() {
+                    cx.fill(bounds.to_kurbo_rect(), &FILL_COLOR);
+                } else {
+                    // the convex hulls are all oriented the same and include the current bounds,
+                    // so the non-zero fill draws their union
+                    cx.fill(sweep_path, &FILL_COLOR);
+                }
                 cx.stroke(outline_rect, &OUTLINE_COLOR, outline_width);
             }
         }
@@ -220,18 +283,40 @@ impl DrawableOnDoc for Eraser {
 fn erase(element: Element, engine_view: &mut EngineViewMut) -> WidgetFlags {
     // the widget_flags.store_modified flag is set in the `.trash_..()` methods
     let mut widget_flags = WidgetFlags::default();
+    let scope = engine_view.pens_config.eraser_config.scope;
+    let active_layer = engine_view.pens_config.eraser_config.active_layer;
 
     match &engine_view.pens_config.eraser_config.style {
         EraserStyle::TrashCollidingStrokes => {
             widget_flags |= engine_view.store.trash_colliding_strokes(
                 engine_view.pens_config.eraser_config.eraser_bounds(element),
-                engine_view.camera.viewport(),
+                scope,
+                active_layer,
             );
         }
         EraserStyle::SplitCollidingStrokes => {
             let (modified_strokes, wf) = engine_view.store.split_colliding_strokes(
                 engine_view.pens_config.eraser_config.eraser_bounds(element),
+                scope,
+                active_layer,
+            );
+            widget_flags |= wf;
+
+            engine_view.store.regenerate_rendering_for_strokes(
+                &modified_strokes,
                 engine_view.camera.viewport(),
+                engine_view.camera.image_scale(),
+            );
+        }
+        EraserStyle::Soft => {
+            let (modified_strokes, wf) = engine_view.store.fade_colliding_strokes(
+                engine_view.pens_config.eraser_config.eraser_bounds(element),
+                engine_view
+                    .pens_config
+                    .eraser_config
+                    .soft_opacity_reduction(element),
+                scope,
+                active_layer,
             );
             widget_flags |= wf;
 
@@ -241,7 +326,144 @@ fn erase(element: Element, engine_view: &mut EngineViewMut) -> WidgetFlags {
                 engine_view.camera.image_scale(),
             );
         }
+        EraserStyle::Tap => {
+            // Tap erasing happens only when the pen is lifted, see `erase_tap()`
+        }
+        EraserStyle::Carve => {
+            // Carving happens only when the pen is lifted, see `erase_carve()`
+        }
+    }
+
+    if engine_view.pens_config.eraser_config.erase_background {
+        let (modified_strokes, wf) = engine_view
+            .store
+            .erase_background_images(engine_view.pens_config.eraser_config.eraser_bounds(element));
+        widget_flags |= wf;
+
+        for key in modified_strokes {
+            engine_view.store.regenerate_rendering_for_stroke_threaded(
+                engine_view.tasks_tx.clone(),
+                key,
+                engine_view.camera.viewport(),
+                engine_view.camera.image_scale(),
+            );
+        }
+    }
+
+    widget_flags
+}
+
+/// Trashes the topmost stroke under the element when in tap erasing style.
+fn erase_tap(element: Element, engine_view: &mut EngineViewMut) -> WidgetFlags {
+    match &engine_view.pens_config.eraser_config.style {
+        EraserStyle::Tap => engine_view.store.trash_topmost_colliding_stroke(
+            engine_view.pens_config.eraser_config.eraser_bounds(element),
+            engine_view.pens_config.eraser_config.scope,
+            engine_view.pens_config.eraser_config.active_layer,
+        ),
+        EraserStyle::TrashCollidingStrokes
+        | EraserStyle::SplitCollidingStrokes
+        | EraserStyle::Soft
+        | EraserStyle::Carve => WidgetFlags::default(),
     }
+}
 
+/// Subtracts the entire swept area from the colliding filled shapes when in carve erasing style.
+///
+/// Carving once when the pen is lifted avoids splitting up the shapes into many parts on every motion.
+fn erase_carve(sweep: &[geo::Polygon<f64>], engine_view: &mut EngineViewMut) -> WidgetFlags {
+    if !matches!(
+        engine_view.pens_config.eraser_config.style,
+        EraserStyle::Carve
+    ) || sweep.is_empty()
+    {
+        return WidgetFlags::default();
+    }
+    let area = sweep
+        .iter()
+        .fold(geo::MultiPolygon::new(vec![]), |area, polygon| {
+            area.union(&geo::MultiPolygon::new(vec![polygon.clone()]))
+        });
+    let (modified_strokes, widget_flags) = engine_view.store.carve_colliding_strokes(
+        &area,
+        engine_view.pens_config.eraser_config.scope,
+        engine_view.pens_config.eraser_config.active_layer,
+    );
+
+    engine_view.store.regenerate_rendering_for_strokes(
+        &modified_strokes,
+        engine_view.camera.viewport(),
+        engine_view.camera.image_scale(),
+    );
     widget_flags
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::{Color, Style};
+    use std::collections::HashSet;
+
+    #[test]
+    fn carve_happens_when_lifted() {
+        let mut engine = Engine::default();
+        let key = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![100.0, 100.0],
+                ))),
+                Style::Smooth(SmoothOptions {
+                    fill_color: Some(Color::BLACK),
+                    ..Default::default()
+                }),
+            )),
+            None,
+        );
+        let _ = engine.change_pen_style(PenStyle::Eraser);
+        engine.pens_config.eraser_config.style = EraserStyle::Carve;
+        engine.pens_config.eraser_config.width = 10.0;
+        let now = Instant::now();
+
+        // swipe across the rectangle, cutting it in two
+        for x in (0..=12).map(|i| i as f64 * 10.0) {
+            let _ = engine.handle_pen_event(
+                PenEvent::Down {
+                    element: Element::new(na::vector![x - 10.0, 50.0], 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                None,
+                now,
+            );
+        }
+        assert!(matches!(
+            engine.store.get_stroke_ref(key),
+            Some(Stroke::ShapeStroke(ShapeStroke {
+                shape: Shape::Rectangle(_),
+                ..
+            }))
+        ));
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 1);
+
+        let _ = engine.handle_pen_event(
+            PenEvent::Up {
+                element: Element::new(na::vector![110.0, 50.0], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            None,
+            now,
+        );
+        assert!(matches!(
+            engine.store.get_stroke_ref(key),
+            Some(Stroke::ShapeStroke(ShapeStroke {
+                shape: Shape::Polygon(_),
+                ..
+            }))
+        ));
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 2);
+    }
+}
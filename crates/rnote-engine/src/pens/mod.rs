@@ -1,6 +1,9 @@
 // Modules
 pub mod brush;
+pub mod cursorhint;
+pub(crate) mod dwell;
 pub mod eraser;
+pub mod modedescriptor;
 pub mod penbehaviour;
 pub mod penholder;
 pub mod penmode;
@@ -8,12 +11,16 @@ pub mod pensconfig;
 pub mod selector;
 pub mod shaper;
 pub mod shortcuts;
+pub mod smoothing;
+pub(crate) mod straighten;
 pub mod tools;
 pub mod typewriter;
 
 // Re-exports
 pub use brush::Brush;
+pub use cursorhint::{CursorHint, ResizeDirection};
 pub use eraser::Eraser;
+pub use modedescriptor::ModeDescriptor;
 pub use penbehaviour::PenBehaviour;
 pub use penholder::PenHolder;
 pub use penmode::PenMode;
@@ -113,6 +120,36 @@ impl PenBehaviour for Pen {
         }
     }
 
+    fn handle_event_batch(
+        &mut self,
+        events: Vec<(PenEvent, Instant)>,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventResult<PenProgress>, WidgetFlags) {
+        match self {
+            Pen::Brush(brush) => brush.handle_event_batch(events, engine_view),
+            Pen::Shaper(shaper) => shaper.handle_event_batch(events, engine_view),
+            Pen::Typewriter(typewriter) => typewriter.handle_event_batch(events, engine_view),
+            Pen::Eraser(eraser) => eraser.handle_event_batch(events, engine_view),
+            Pen::Selector(selector) => selector.handle_event_batch(events, engine_view),
+            Pen::Tools(tools) => tools.handle_event_batch(events, engine_view),
+        }
+    }
+
+    fn handle_frame_tick(
+        &mut self,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (Option<PenProgress>, WidgetFlags) {
+        match self {
+            Pen::Brush(brush) => brush.handle_frame_tick(now, engine_view),
+            Pen::Shaper(shaper) => shaper.handle_frame_tick(now, engine_view),
+            Pen::Typewriter(typewriter) => typewriter.handle_frame_tick(now, engine_view),
+            Pen::Eraser(eraser) => eraser.handle_frame_tick(now, engine_view),
+            Pen::Selector(selector) => selector.handle_frame_tick(now, engine_view),
+            Pen::Tools(tools) => tools.handle_frame_tick(now, engine_view),
+        }
+    }
+
     fn fetch_clipboard_content(
         &self,
         engine_view: &EngineView,
@@ -183,7 +220,9 @@ impl DrawableOnDoc for Pen {
     Hash,
     num_derive::FromPrimitive,
     num_derive::ToPrimitive,
+    glib::Enum,
 )]
+#[enum_type(name = "RnPenStyle")]
 #[serde(rename = "pen_style")]
 pub enum PenStyle {
     #[serde(rename = "brush")]
@@ -247,6 +286,18 @@ impl Display for PenStyle {
 }
 
 impl PenStyle {
+    /// All pen styles, in the order they are presented to the user.
+    pub fn all() -> &'static [PenStyle] {
+        &[
+            Self::Brush,
+            Self::Shaper,
+            Self::Typewriter,
+            Self::Eraser,
+            Self::Selector,
+            Self::Tools,
+        ]
+    }
+
     pub fn icon_name(self) -> String {
         match self {
             Self::Brush => String::from("pen-brush-symbolic"),
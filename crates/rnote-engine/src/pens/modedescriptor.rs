@@ -0,0 +1,142 @@
+// Imports
+use super::pensconfig::brushconfig::BrushStyle;
+use super::pensconfig::eraserconfig::EraserStyle;
+use super::pensconfig::selectorconfig::SelectorStyle;
+use super::pensconfig::shaperconfig::ShaperStyle;
+use super::pensconfig::toolsconfig::ToolStyle;
+use super::{PenHolder, PenStyle};
+
+/// Describes a sub-mode of a pen, so that mode pickers can be built generically.
+///
+/// The display name is left to the UI, which translates it from the id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModeDescriptor {
+    /// The stable id of the mode, the same as its serialized name.
+    pub id: &'static str,
+}
+
+impl ModeDescriptor {
+    const fn new(id: &'static str) -> Self {
+        Self { id }
+    }
+}
+
+/// Collects the descriptors of all variants of a pen sub-mode enum, in the order of their discriminants.
+fn collect_modes<T: TryFrom<u32>>(descriptor: impl Fn(T) -> ModeDescriptor) -> Vec<ModeDescriptor> {
+    (0..)
+        .map_while(|i| T::try_from(i).ok())
+        .map(descriptor)
+        .collect()
+}
+
+impl PenStyle {
+    /// The sub-modes of the pen. Empty for pens without modes.
+    pub fn available_modes(self) -> Vec<ModeDescriptor> {
+        match self {
+            PenStyle::Brush => collect_modes(|style: BrushStyle| match style {
+                BrushStyle::Marker => ModeDescriptor::new("marker"),
+                BrushStyle::Solid => ModeDescriptor::new("solid"),
+                BrushStyle::Textured => ModeDescriptor::new("textured"),
+            }),
+            PenStyle::Shaper => collect_modes(|style: ShaperStyle| match style {
+                ShaperStyle::Smooth => ModeDescriptor::new("smooth"),
+                ShaperStyle::Rough => ModeDescriptor::new("rough"),
+            }),
+            PenStyle::Typewriter => vec![],
+            PenStyle::Eraser => collect_modes(|style: EraserStyle| match style {
+                EraserStyle::TrashCollidingStrokes => {
+                    ModeDescriptor::new("trash_colliding_strokes")
+                }
+                EraserStyle::SplitCollidingStrokes => {
+                    ModeDescriptor::new("split_colliding_strokes")
+                }
+                EraserStyle::Soft => ModeDescriptor::new("soft"),
+                EraserStyle::Tap => ModeDescriptor::new("tap"),
+                EraserStyle::Carve => ModeDescriptor::new("carve"),
+            }),
+            PenStyle::Selector => collect_modes(|style: SelectorStyle| match style {
+                SelectorStyle::Polygon => ModeDescriptor::new("polygon"),
+                SelectorStyle::Rectangle => ModeDescriptor::new("rectangle"),
+                SelectorStyle::Single => ModeDescriptor::new("single"),
+                SelectorStyle::IntersectingPath => ModeDescriptor::new("intersectingpath"),
+                SelectorStyle::BrushSelect => ModeDescriptor::new("brush_select"),
+            }),
+            PenStyle::Tools => collect_modes(|style: ToolStyle| match style {
+                ToolStyle::VerticalSpace => ModeDescriptor::new("verticalspace"),
+                ToolStyle::OffsetCamera => ModeDescriptor::new("offsetcamera"),
+                ToolStyle::Zoom => ModeDescriptor::new("zoom"),
+                ToolStyle::Smudge => ModeDescriptor::new("smudge"),
+                ToolStyle::Crop => ModeDescriptor::new("crop"),
+            }),
+        }
+    }
+}
+
+impl PenHolder {
+    /// The sub-modes of the current pen, for building adaptive toolbars.
+    pub fn available_modes(&self) -> Vec<ModeDescriptor> {
+        self.current_pen_style_w_override().available_modes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ModeDescriptor;
+    use crate::pens::pensconfig::brushconfig::BrushStyle;
+    use crate::pens::pensconfig::eraserconfig::EraserStyle;
+    use crate::pens::pensconfig::selectorconfig::SelectorStyle;
+    use crate::pens::pensconfig::shaperconfig::ShaperStyle;
+    use crate::pens::pensconfig::toolsconfig::ToolStyle;
+    use crate::pens::{PenHolder, PenStyle};
+    use crate::Engine;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    /// Asserts that the modes of the pen are all variants of the mode enum, with their serialized names as ids.
+    fn assert_modes_round_trip<T: TryFrom<u32> + Serialize + DeserializeOwned>(
+        pen_style: PenStyle,
+    ) {
+        let modes = pen_style.available_modes();
+        assert!(!modes.is_empty());
+        assert!(T::try_from(modes.len() as u32).is_err());
+        for (i, mode) in modes.iter().enumerate() {
+            let Ok(style) = T::try_from(i as u32) else {
+                panic!("mode {} of {pen_style:?} is not a variant", mode.id);
+            };
+            assert_eq!(serde_json::to_value(&style).unwrap(), mode.id);
+            let deserialized: T = serde_json::from_value(mode.id.into()).unwrap();
+            assert_eq!(serde_json::to_value(&deserialized).unwrap(), mode.id);
+        }
+    }
+
+    #[test]
+    fn eraser_reports_all_modes() {
+        let mut engine = Engine::default();
+        let _ = engine.change_pen_style(PenStyle::Eraser);
+        let modes = engine.penholder.available_modes();
+        assert_eq!(
+            modes.iter().map(|mode| mode.id).collect::<Vec<&str>>(),
+            vec![
+                "trash_colliding_strokes",
+                "split_colliding_strokes",
+                "soft",
+                "tap",
+                "carve"
+            ]
+        );
+        assert!(PenHolder::default().available_modes().len() > 1);
+        assert_eq!(
+            PenStyle::Typewriter.available_modes(),
+            Vec::<ModeDescriptor>::new()
+        );
+    }
+
+    #[test]
+    fn mode_ids_round_trip_for_every_pen() {
+        assert_modes_round_trip::<BrushStyle>(PenStyle::Brush);
+        assert_modes_round_trip::<ShaperStyle>(PenStyle::Shaper);
+        assert_modes_round_trip::<EraserStyle>(PenStyle::Eraser);
+        assert_modes_round_trip::<SelectorStyle>(PenStyle::Selector);
+        assert_modes_round_trip::<ToolStyle>(PenStyle::Tools);
+    }
+}
@@ -3,6 +3,7 @@ use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
 use crate::{DrawableOnDoc, WidgetFlags};
 use futures::channel::oneshot;
+use rnote_compose::eventresult::EventPropagation;
 use rnote_compose::penevent::{PenEvent, PenProgress};
 use rnote_compose::EventResult;
 use std::time::Instant;
@@ -32,6 +33,45 @@ pub trait PenBehaviour: DrawableOnDoc {
         engine_view: &mut EngineViewMut,
     ) -> (EventResult<PenProgress>, WidgetFlags);
 
+    /// Handle a batch of pen events with their event times as a single update, returning the result of the last one.
+    ///
+    /// The default implementation handles the events one after another. Pens override it to do expensive work like
+    /// rendering only once for the whole batch.
+    fn handle_event_batch(
+        &mut self,
+        events: Vec<(PenEvent, Instant)>,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventResult<PenProgress>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let mut event_result = EventResult {
+            handled: false,
+            propagate: EventPropagation::Proceed,
+            progress: PenProgress::Idle,
+        };
+        for (event, now) in events {
+            let (er, wf) = self.handle_event(event, now, engine_view);
+            widget_flags |= wf;
+            event_result = EventResult {
+                handled: event_result.handled || er.handled,
+                propagate: event_result.propagate | er.propagate,
+                progress: er.progress,
+            };
+        }
+        (event_result, widget_flags)
+    }
+
+    /// Handle a new display frame while the pen is pressed down, for pen actions that depend on time passing
+    /// without new input.
+    ///
+    /// Returns the new progress if the pen changed it.
+    fn handle_frame_tick(
+        &mut self,
+        _now: Instant,
+        _engine_view: &mut EngineViewMut,
+    ) -> (Option<PenProgress>, WidgetFlags) {
+        (None, WidgetFlags::default())
+    }
+
     /// Fetch clipboard content from the pen.
     ///
     /// The fetched content can be available in multiple formats,
@@ -2,8 +2,8 @@
 use super::penmode::PenModeState;
 use super::shortcuts::ShortcutMode;
 use super::{
-    Brush, Eraser, Pen, PenBehaviour, PenMode, PenStyle, Selector, Shaper, Shortcuts, Tools,
-    Typewriter,
+    Brush, CursorHint, Eraser, Pen, PenBehaviour, PenMode, PenStyle, Selector, Shaper, Shortcuts,
+    Tools, Typewriter,
 };
 use crate::camera::NudgeDirection;
 use crate::engine::{EngineView, EngineViewMut};
@@ -11,11 +11,13 @@ use crate::pens::shortcuts::ShortcutAction;
 use crate::widgetflags::WidgetFlags;
 use crate::{CloneConfig, DrawableOnDoc};
 use futures::channel::oneshot;
-use p2d::bounding_volume::Aabb;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
 use rnote_compose::eventresult::EventPropagation;
 use rnote_compose::penevent::{KeyboardKey, ModifierKey, PenEvent, PenProgress, ShortcutKey};
+use rnote_compose::penpath::Element;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -33,6 +35,33 @@ pub struct PenHolder {
     shortcuts: Shortcuts,
     #[serde(rename = "pen_mode_state")]
     pen_mode_state: PenModeState,
+    /// The maximum number of motion updates that are handled per second.
+    ///
+    /// Motion in between is buffered and committed together with the next update or on the next frame.
+    /// Not throttled when None.
+    #[serde(rename = "max_updates_per_second")]
+    max_updates_per_second: Option<u32>,
+    /// Sort input batches by their event times and drop input older than the previously handled input.
+    ///
+    /// Works around platforms that deliver input slightly out of order.
+    #[serde(rename = "reorder_input")]
+    reorder_input: bool,
+    /// Finish the current pen action when no motion arrived for this duration while the pen is pressed down.
+    ///
+    /// Works around styluses that sometimes drop the pen up event, leaving strokes that are never finished.
+    /// Disabled when None.
+    #[serde(rename = "lift_timeout")]
+    lift_timeout: Option<Duration>,
+    /// The minimum motion of the pen while pressed down, in surface pixels, after which a redraw is requested.
+    ///
+    /// Motion below it is still handled by the pen, only the redraw is postponed until enough motion accumulated.
+    /// Avoids redraws for sub-pixel jitter of the input. Disabled when zero.
+    #[serde(rename = "min_redraw_motion")]
+    min_redraw_motion: f64,
+    /// Route the input of multiple pointers, like two styluses, to their own pen gestures, so that several people
+    /// can draw at the same time.
+    #[serde(rename = "multi_pen_input")]
+    multi_pen_input: bool,
 
     /// The policy for the retrieval of input event backlogs.
     #[serde(skip)]
@@ -45,6 +74,31 @@ pub struct PenHolder {
     toggle_pen_style: Option<PenStyle>,
     #[serde(skip)]
     prev_shortcut_key: Option<ShortcutKey>,
+    /// Buffered motion events with their event times, while throttled.
+    #[serde(skip)]
+    motion_buffer: Vec<(PenEvent, Instant)>,
+    #[serde(skip)]
+    last_motion_update: Option<Instant>,
+    /// The last element while the pen is pressed down, with its event time.
+    #[serde(skip)]
+    last_down: Option<(Element, Instant)>,
+    /// The position of the last motion that requested a redraw while the pen is pressed down.
+    #[serde(skip)]
+    last_redraw_pos: Option<na::Vector2<f64>>,
+    /// The pointer that the gesture of the current pen belongs to, with multi pen input.
+    #[serde(skip)]
+    current_pointer: Option<u32>,
+    /// The in-progress gestures of the other pointers, each with its own pen instance, with multi pen input.
+    #[serde(skip)]
+    pointer_gestures: HashMap<u32, PointerGesture>,
+}
+
+/// The in-progress gesture of another pointer than the current one.
+#[derive(Debug)]
+struct PointerGesture {
+    pen: Pen,
+    /// The last element of the pointer, with its event time, for the lift timeout.
+    last_down: Option<(Element, Instant)>,
 }
 
 impl Default for PenHolder {
@@ -52,12 +106,23 @@ impl Default for PenHolder {
         Self {
             shortcuts: Shortcuts::default(),
             pen_mode_state: PenModeState::default(),
+            max_updates_per_second: None,
+            reorder_input: false,
+            lift_timeout: None,
+            min_redraw_motion: 0.0,
+            multi_pen_input: false,
             backlog_policy: BacklogPolicy::NoLimit,
 
             current_pen: Pen::default(),
             progress: PenProgress::Idle,
             toggle_pen_style: None,
             prev_shortcut_key: None,
+            motion_buffer: Vec::new(),
+            last_motion_update: None,
+            last_down: None,
+            last_redraw_pos: None,
+            current_pointer: None,
+            pointer_gestures: HashMap::new(),
         }
     }
 }
@@ -67,6 +132,11 @@ impl CloneConfig for PenHolder {
         Self {
             shortcuts: self.shortcuts.clone(),
             pen_mode_state: self.pen_mode_state.clone_config(),
+            max_updates_per_second: self.max_updates_per_second,
+            reorder_input: self.reorder_input,
+            lift_timeout: self.lift_timeout,
+            min_redraw_motion: self.min_redraw_motion,
+            multi_pen_input: self.multi_pen_input,
             ..Default::default()
         }
     }
@@ -125,6 +195,50 @@ impl PenHolder {
         self.backlog_policy
     }
 
+    pub fn max_updates_per_second(&self) -> Option<u32> {
+        self.max_updates_per_second
+    }
+
+    /// Set the maximum number of motion updates that are handled per second. Zero or None disables the throttling.
+    pub fn set_max_updates_per_second(&mut self, max_updates_per_second: Option<u32>) {
+        self.max_updates_per_second = max_updates_per_second.filter(|&max| max > 0);
+    }
+
+    pub fn reorder_input(&self) -> bool {
+        self.reorder_input
+    }
+
+    pub fn set_reorder_input(&mut self, reorder_input: bool) {
+        self.reorder_input = reorder_input;
+    }
+
+    pub fn lift_timeout(&self) -> Option<Duration> {
+        self.lift_timeout
+    }
+
+    /// Set the timeout after which the current pen action is finished when no motion arrives. Zero or None disables
+    /// it.
+    pub fn set_lift_timeout(&mut self, lift_timeout: Option<Duration>) {
+        self.lift_timeout = lift_timeout.filter(|timeout| !timeout.is_zero());
+    }
+
+    pub fn min_redraw_motion(&self) -> f64 {
+        self.min_redraw_motion
+    }
+
+    /// Set the minimum motion in surface pixels after which a redraw is requested. Zero disables it.
+    pub fn set_min_redraw_motion(&mut self, min_redraw_motion: f64) {
+        self.min_redraw_motion = min_redraw_motion.max(0.0);
+    }
+
+    pub fn multi_pen_input(&self) -> bool {
+        self.multi_pen_input
+    }
+
+    pub fn set_multi_pen_input(&mut self, multi_pen_input: bool) {
+        self.multi_pen_input = multi_pen_input;
+    }
+
     /// Get the style without the temporary override.
     pub fn current_pen_style(&self) -> PenStyle {
         self.pen_mode_state.style()
@@ -140,7 +254,7 @@ impl PenHolder {
         self.progress
     }
 
-    pub fn current_pen_ref(&mut self) -> &Pen {
+    pub fn current_pen_ref(&self) -> &Pen {
         &self.current_pen
     }
 
@@ -148,6 +262,23 @@ impl PenHolder {
         &mut self.current_pen
     }
 
+    /// The pen style, without the temporary override.
+    pub fn style(&self) -> PenStyle {
+        self.current_pen_style()
+    }
+
+    /// The cursor hint for the current pen in its current state.
+    pub fn cursor_hint(&self, engine_view: &EngineView) -> CursorHint {
+        self.current_pen.cursor_hint(engine_view)
+    }
+
+    /// Set the pen style.
+    ///
+    /// A gesture that is currently in progress is cancelled and finished up before the new pen is installed.
+    pub fn set_style(&mut self, style: PenStyle, engine_view: &mut EngineViewMut) -> WidgetFlags {
+        self.change_style(style, engine_view)
+    }
+
     /// Change the pen style.
     pub fn change_style(
         &mut self,
@@ -208,10 +339,19 @@ impl PenHolder {
 
     /// Reinstall the pen for the current style.
     pub fn reinstall_pen_current_style(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags {
-        // first cancel the current pen
-        let (_, mut widget_flags) =
-            self.current_pen
+        // the buffered motion still belongs to the current pen
+        let mut widget_flags = self.commit_buffered_motion(engine_view);
+        // then cancel the current pen and the gestures of the other pointers
+        let (_, wf) = self
+            .current_pen
+            .handle_event(PenEvent::Cancel, Instant::now(), engine_view);
+        widget_flags |= wf;
+        for (_, mut gesture) in self.pointer_gestures.drain() {
+            let (_, wf) = gesture
+                .pen
                 .handle_event(PenEvent::Cancel, Instant::now(), engine_view);
+            widget_flags |= wf | gesture.pen.deinit();
+        }
 
         // then reinstall a new pen instance
         let mut new_pen = new_pen(self.current_pen_style_w_override());
@@ -236,12 +376,238 @@ impl PenHolder {
         engine_view: &mut EngineViewMut,
     ) -> (EventPropagation, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
+        let changes_pen_mode =
+            pen_mode.is_some_and(|pen_mode| pen_mode != self.pen_mode_state.pen_mode());
+        self.last_down = match &event {
+            PenEvent::Down { element, .. } => Some((*element, now)),
+            PenEvent::Up { .. } | PenEvent::Proximity { .. } | PenEvent::Cancel => None,
+            PenEvent::KeyPressed { .. } | PenEvent::Text { .. } => self.last_down,
+        };
+
+        if !changes_pen_mode && matches!(event, PenEvent::Down { .. }) && self.motion_throttled(now)
+        {
+            self.motion_buffer.push((event, now));
+            return (EventPropagation::Stop, widget_flags);
+        }
+        // Buffered motion is always handled before the next event, so that no input is dropped or reordered
+        widget_flags |= self.commit_buffered_motion(engine_view);
 
         if let Some(pen_mode) = pen_mode {
             widget_flags |= self.change_pen_mode(pen_mode, engine_view);
         }
 
+        let (propagate, wf) = self.handle_pen_event_w_current_pen(event, now, engine_view);
+        widget_flags |= wf;
+
+        (propagate, widget_flags)
+    }
+
+    /// Handle a pen event of the pointer with the given id, for example a device id.
+    ///
+    /// With multi pen input, pointers that go down while the gesture of another pointer is in progress get their own
+    /// gesture with a separate instance of the current pen, until their gesture is finished. Their input is not
+    /// throttled and does not change the pen mode. Without multi pen input, the pointer id is ignored.
+    pub fn handle_pen_event_for_pointer(
+        &mut self,
+        event: PenEvent,
+        pointer_id: u32,
+        pen_mode: Option<PenMode>,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventPropagation, WidgetFlags) {
+        if !self.multi_pen_input {
+            return self.handle_pen_event(event, pen_mode, now, engine_view);
+        }
+        if let Some(gesture) = self.pointer_gestures.remove(&pointer_id) {
+            return self.handle_pointer_gesture_event(
+                gesture.pen,
+                event,
+                pointer_id,
+                now,
+                engine_view,
+            );
+        }
+        if self.progress == PenProgress::InProgress
+            && self
+                .current_pointer
+                .is_some_and(|current_pointer| current_pointer != pointer_id)
+        {
+            if !matches!(event, PenEvent::Down { .. }) {
+                // Hovering of other pointers is ignored while the current gesture is in progress
+                return (EventPropagation::Proceed, WidgetFlags::default());
+            }
+            let mut pen = new_pen(self.current_pen_style_w_override());
+            let widget_flags = pen.init(&engine_view.as_im());
+            let (propagate, wf) =
+                self.handle_pointer_gesture_event(pen, event, pointer_id, now, engine_view);
+            return (propagate, widget_flags | wf);
+        }
+
+        self.current_pointer = Some(pointer_id);
+        self.handle_pen_event(event, pen_mode, now, engine_view)
+    }
+
+    /// Handle the event with the pen of the gesture of another pointer than the current one. The gesture is kept
+    /// until it is finished.
+    fn handle_pointer_gesture_event(
+        &mut self,
+        mut pen: Pen,
+        event: PenEvent,
+        pointer_id: u32,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventPropagation, WidgetFlags) {
+        let last_down = match &event {
+            PenEvent::Down { element, .. } => Some((*element, now)),
+            _ => None,
+        };
+        let event = clamp_pen_event(pen.style(), event, engine_view);
+        let (event_result, mut widget_flags) = pen.handle_event(event, now, engine_view);
+        if event_result.progress == PenProgress::InProgress {
+            self.pointer_gestures
+                .insert(pointer_id, PointerGesture { pen, last_down });
+        } else {
+            widget_flags |= pen.deinit();
+        }
+        widget_flags.redraw = true;
+
+        (event_result.propagate, widget_flags)
+    }
+
+    /// Handle a new display frame, committing the motion that was buffered while throttled.
+    ///
+    /// Lets the current pen and the pens of the other pointers handle the frame, and finishes their actions when the
+    /// lift timeout has passed since their last motion.
+    pub fn handle_frame_tick(
+        &mut self,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if !self.motion_buffer.is_empty() {
+            widget_flags |= self.commit_buffered_motion(engine_view);
+            self.last_motion_update = Some(now);
+        }
+
+        if self.progress == PenProgress::InProgress {
+            let (progress, wf) = self.current_pen.handle_frame_tick(now, engine_view);
+            widget_flags |= wf;
+            if let Some(progress) = progress {
+                widget_flags |= self.handle_pen_progress(progress, engine_view);
+                widget_flags.redraw = true;
+            }
+        }
+
+        let lift_timeout = self.lift_timeout;
+        self.pointer_gestures.retain(|_, gesture| {
+            let (mut progress, wf) = gesture.pen.handle_frame_tick(now, engine_view);
+            widget_flags |= wf;
+            if let (Some(lift_timeout), Some((element, last_down_time))) =
+                (lift_timeout, gesture.last_down)
+            {
+                if progress != Some(PenProgress::Finished)
+                    && now.saturating_duration_since(last_down_time) >= lift_timeout
+                {
+                    let (event_result, wf) = gesture.pen.handle_event(
+                        PenEvent::Up {
+                            element,
+                            modifier_keys: HashSet::new(),
+                        },
+                        now,
+                        engine_view,
+                    );
+                    widget_flags |= wf;
+                    progress = Some(event_result.progress);
+                }
+            }
+            let Some(progress) = progress else {
+                return true;
+            };
+            widget_flags.redraw = true;
+            if progress == PenProgress::InProgress {
+                return true;
+            }
+            widget_flags |= gesture.pen.deinit();
+            false
+        });
+
+        if let (Some(lift_timeout), Some((element, last_down_time))) =
+            (self.lift_timeout, self.last_down)
+        {
+            if now.saturating_duration_since(last_down_time) >= lift_timeout {
+                self.last_down = None;
+                // The pen up event most likely got lost
+                if self.progress == PenProgress::InProgress {
+                    let (_, wf) = self.handle_pen_event_w_current_pen(
+                        PenEvent::Up {
+                            element,
+                            modifier_keys: HashSet::new(),
+                        },
+                        now,
+                        engine_view,
+                    );
+                    widget_flags |= wf;
+                }
+            }
+        }
+
+        widget_flags
+    }
+
+    fn motion_throttled(&self, now: Instant) -> bool {
+        let (Some(max_updates_per_second), Some(last_motion_update)) =
+            (self.max_updates_per_second, self.last_motion_update)
+        else {
+            return false;
+        };
+        now.saturating_duration_since(last_motion_update)
+            < Duration::from_secs_f64(1.0 / max_updates_per_second as f64)
+    }
+
+    /// Commit the buffered motion to the current pen as a single update.
+    fn commit_buffered_motion(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags {
+        let events = std::mem::take(&mut self.motion_buffer);
+        let Some((last_event, last_event_time)) = events.last().cloned() else {
+            return WidgetFlags::default();
+        };
+        self.last_motion_update = Some(last_event_time);
+
+        let style = self.current_pen.style();
+        let events = events
+            .into_iter()
+            .map(|(event, time)| (clamp_pen_event(style, event, engine_view), time))
+            .collect();
+
+        // Buffered motion only consists of down events, which are not handled globally
+        let (event_result, mut widget_flags) =
+            self.current_pen.handle_event_batch(events, engine_view);
+        widget_flags |= self.handle_pen_progress(event_result.progress, engine_view);
+        if !self.redraw_suppressed(&last_event, engine_view) {
+            widget_flags.redraw = true;
+        }
+        widget_flags
+    }
+
+    /// Whether display frames need to be handled, because there is buffered motion, a pen is pressed down or
+    /// other pointers are in a gesture.
+    pub fn needs_frame_tick(&self) -> bool {
+        !self.motion_buffer.is_empty()
+            || self.last_down.is_some()
+            || !self.pointer_gestures.is_empty()
+    }
+
+    fn handle_pen_event_w_current_pen(
+        &mut self,
+        event: PenEvent,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventPropagation, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        // Only motion while the pen is pressed down is throttled, the first one is always handled immediately
+        self.last_motion_update = matches!(event, PenEvent::Down { .. }).then_some(now);
+
         // Handle the event with the current pen
+        let event = clamp_pen_event(self.current_pen.style(), event, engine_view);
         let (mut event_result, wf) = self
             .current_pen
             .handle_event(event.clone(), now, engine_view);
@@ -253,12 +619,37 @@ impl PenHolder {
             widget_flags |= wf;
         }
 
-        // Always redraw after handling a pen event
-        widget_flags.redraw = true;
+        // Always redraw after handling a pen event, except for motion that is too small to be visible.
+        // Redraws requested by the pen itself are kept.
+        if !self.redraw_suppressed(&event, engine_view) {
+            widget_flags.redraw = true;
+        }
 
         (event_result.propagate, widget_flags)
     }
 
+    /// Whether the redraw for the handled event can be postponed, because the pen moved less than the minimum redraw
+    /// motion since the last redraw while pressed down.
+    fn redraw_suppressed(&mut self, event: &PenEvent, engine_view: &EngineViewMut) -> bool {
+        let PenEvent::Down { element, .. } = event else {
+            self.last_redraw_pos = None;
+            return false;
+        };
+        if self.progress != PenProgress::InProgress {
+            self.last_redraw_pos = None;
+            return false;
+        }
+        if let Some(last_redraw_pos) = self.last_redraw_pos {
+            if (element.pos - last_redraw_pos).magnitude() * engine_view.camera.total_zoom()
+                < self.min_redraw_motion
+            {
+                return true;
+            }
+        }
+        self.last_redraw_pos = Some(element.pos);
+        false
+    }
+
     /// Handle a pressed shortcut key.
     pub fn handle_pressed_shortcut_key(
         &mut self,
@@ -509,7 +900,10 @@ impl PenHolder {
 
 impl DrawableOnDoc for PenHolder {
     fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
-        self.current_pen.bounds_on_doc(engine_view)
+        std::iter::once(&self.current_pen)
+            .chain(self.pointer_gestures.values().map(|gesture| &gesture.pen))
+            .filter_map(|pen| pen.bounds_on_doc(engine_view))
+            .reduce(|acc, bounds| acc.merged(&bounds))
     }
     fn draw_on_doc(
         &self,
@@ -519,12 +913,28 @@ impl DrawableOnDoc for PenHolder {
         cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
 
         self.current_pen.draw_on_doc(cx, engine_view)?;
+        for gesture in self.pointer_gestures.values() {
+            gesture.pen.draw_on_doc(cx, engine_view)?;
+        }
 
         cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
         Ok(())
     }
 }
 
+/// Clamp the event to the document bounds for the pens that draw onto the document, see
+/// [Document::clamp_pen_event](crate::Document::clamp_pen_event).
+///
+/// The other pens keep the unclamped input, for example the tools pen to pan beyond the document edges.
+fn clamp_pen_event(style: PenStyle, event: PenEvent, engine_view: &EngineViewMut) -> PenEvent {
+    match style {
+        PenStyle::Brush | PenStyle::Shaper | PenStyle::Typewriter => {
+            engine_view.document.clamp_pen_event(event)
+        }
+        PenStyle::Eraser | PenStyle::Selector | PenStyle::Tools => event,
+    }
+}
+
 fn new_pen(pen_style: PenStyle) -> Pen {
     match pen_style {
         PenStyle::Brush => Pen::Brush(Brush::default()),
@@ -535,3 +945,426 @@ fn new_pen(pen_style: PenStyle) -> Pen {
         PenStyle::Tools => Pen::Tools(Tools::default()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::document::Layout;
+    use crate::engine::EngineViewMut;
+    use crate::pens::pensconfig::toolsconfig::ToolStyle;
+    use crate::pens::{CursorHint, Pen, PenStyle, ResizeDirection};
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::builders::PenPathBuilderType;
+    use rnote_compose::penevent::{PenEvent, PenProgress};
+    use rnote_compose::penpath::Element;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
+    use std::collections::HashSet;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn set_style_round_trip() {
+        let mut engine = Engine::default();
+
+        for &style in PenStyle::all() {
+            let tasks_tx = engine.engine_tasks_tx();
+            let _ = engine.penholder.set_style(
+                style,
+                &mut EngineViewMut {
+                    tasks_tx,
+                    pens_config: &mut engine.pens_config,
+                    document: &mut engine.document,
+                    store: &mut engine.store,
+                    camera: &mut engine.camera,
+                    audioplayer: &mut None,
+                },
+            );
+            assert_eq!(engine.penholder.style(), style);
+            assert_eq!(engine.penholder.current_pen_style_w_override(), style);
+        }
+    }
+
+    #[test]
+    fn cursor_hint_matches_pen_style() {
+        let mut engine = Engine::default();
+        engine.pens_config.eraser_config.width = 20.0;
+        let _ = engine.camera.zoom_to(2.0);
+
+        for &style in PenStyle::all() {
+            let _ = engine.change_pen_style(style);
+            let expected = match style {
+                PenStyle::Brush | PenStyle::Shaper => CursorHint::Crosshair,
+                PenStyle::Typewriter => CursorHint::Text,
+                PenStyle::Eraser => CursorHint::Circle(40.0),
+                PenStyle::Selector | PenStyle::Tools => CursorHint::Pointer,
+            };
+            assert_eq!(engine.cursor_hint(), expected);
+        }
+
+        // hovering over a selection and its resize handles
+        let key = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![100.0, 100.0],
+                    na::point![200.0, 200.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        let _ = engine.select_all_strokes();
+        let selection_bounds = engine.store.bounds_for_strokes(&[key]).unwrap();
+        let handle_offset =
+            engine.pens_config.selector_config.handle_size_px() * 0.5 / engine.camera.total_zoom();
+        let mut hover = |pos: na::Vector2<f64>| {
+            let _ = engine.handle_pen_event(
+                PenEvent::Proximity {
+                    element: Element::new(pos, 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                None,
+                Instant::now(),
+            );
+            engine.cursor_hint()
+        };
+        assert_eq!(hover(selection_bounds.center().coords), CursorHint::Move);
+        assert_eq!(
+            hover(selection_bounds.mins.coords - na::Vector2::repeat(handle_offset)),
+            CursorHint::Resize(ResizeDirection::NorthWest)
+        );
+        assert_eq!(
+            hover(selection_bounds.maxs.coords + na::Vector2::repeat(handle_offset)),
+            CursorHint::Resize(ResizeDirection::SouthEast)
+        );
+    }
+
+    #[test]
+    fn throttled_motion_is_committed_completely() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.penholder.set_max_updates_per_second(Some(10));
+        let start = Instant::now();
+        let positions = (0..8)
+            .map(|i| na::vector![10.0 + 10.0 * i as f64, 20.0])
+            .collect::<Vec<na::Vector2<f64>>>();
+        let down = |pos: na::Vector2<f64>| PenEvent::Down {
+            element: Element::new(pos, 0.5),
+            modifier_keys: HashSet::new(),
+        };
+
+        assert!(!engine.needs_frame_tick());
+        for (i, &pos) in positions[..4].iter().enumerate() {
+            let _ =
+                engine.handle_pen_event(down(pos), None, start + Duration::from_millis(i as u64));
+        }
+        assert!(engine.needs_frame_tick());
+        // the display frame commits the buffered motion
+        let _ = engine.handle_frame_tick(start + Duration::from_millis(4));
+        for (i, &pos) in positions[4..7].iter().enumerate() {
+            let _ = engine.handle_pen_event(
+                down(pos),
+                None,
+                start + Duration::from_millis(5 + i as u64),
+            );
+        }
+        let _ = engine.handle_pen_event(
+            PenEvent::Up {
+                element: Element::new(positions[7], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            None,
+            start + Duration::from_millis(8),
+        );
+        // no more frames are needed without input
+        assert!(!engine.needs_frame_tick());
+
+        let keys = engine.store.stroke_keys_as_rendered();
+        assert_eq!(keys.len(), 1);
+        let Some(Stroke::BrushStroke(brushstroke)) = engine.store.get_stroke_ref(keys[0]) else {
+            panic!("the committed stroke is not a brush stroke");
+        };
+        let committed = std::iter::once(brushstroke.path.start.pos)
+            .chain(brushstroke.path.segments.iter().map(|seg| seg.end().pos))
+            .collect::<Vec<na::Vector2<f64>>>();
+        assert_eq!(committed, positions);
+    }
+
+    #[test]
+    fn lift_timeout_finishes_stroke_without_up_event() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine
+            .penholder
+            .set_lift_timeout(Some(Duration::from_millis(200)));
+        let start = Instant::now();
+
+        for i in 0..4 {
+            let _ = engine.handle_pen_event(
+                PenEvent::Down {
+                    element: Element::new(na::vector![10.0 + 10.0 * i as f64, 20.0], 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                None,
+                start + Duration::from_millis(i * 10),
+            );
+        }
+        // the up event never arrives
+        let _ = engine.handle_frame_tick(start + Duration::from_millis(100));
+        assert_eq!(
+            engine.penholder.current_pen_progress(),
+            PenProgress::InProgress
+        );
+
+        let _ = engine.handle_frame_tick(start + Duration::from_millis(300));
+        assert_eq!(
+            engine.penholder.current_pen_progress(),
+            PenProgress::Finished
+        );
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 1);
+    }
+
+    #[test]
+    fn lift_timeout_finishes_gestures_of_other_pointers() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.penholder.set_multi_pen_input(true);
+        engine
+            .penholder
+            .set_lift_timeout(Some(Duration::from_millis(200)));
+        let start = Instant::now();
+
+        for i in 0..4 {
+            for pointer_id in [1, 2] {
+                let _ = engine.handle_pen_event_for_pointer(
+                    PenEvent::Down {
+                        element: Element::new(
+                            na::vector![10.0 + 10.0 * i as f64, 50.0 * pointer_id as f64],
+                            0.5,
+                        ),
+                        modifier_keys: HashSet::new(),
+                    },
+                    pointer_id,
+                    None,
+                    start + Duration::from_millis(i * 10),
+                );
+            }
+        }
+        assert!(engine.needs_frame_tick());
+
+        // neither up event ever arrives
+        let _ = engine.handle_frame_tick(start + Duration::from_millis(300));
+        assert_eq!(
+            engine.penholder.current_pen_progress(),
+            PenProgress::Finished
+        );
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 2);
+        assert!(!engine.needs_frame_tick());
+    }
+
+    #[test]
+    fn jitter_below_min_redraw_motion_does_not_redraw() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.penholder.set_min_redraw_motion(2.0);
+        let now = Instant::now();
+        let mut down = |pos: na::Vector2<f64>| {
+            engine
+                .handle_pen_event(
+                    PenEvent::Down {
+                        element: Element::new(pos / engine.camera.total_zoom(), 0.5),
+                        modifier_keys: HashSet::new(),
+                    },
+                    None,
+                    now,
+                )
+                .1
+                .redraw
+        };
+
+        assert!(down(na::vector![20.0, 20.0]));
+        assert!(down(na::vector![30.0, 20.0]));
+        for jitter in [
+            na::vector![30.5, 20.0],
+            na::vector![29.5, 20.5],
+            na::vector![31.0, 19.5],
+        ] {
+            assert!(!down(jitter));
+        }
+        // the motion accumulated since the last redraw counts
+        assert!(down(na::vector![32.5, 20.0]));
+        assert!(down(na::vector![50.0, 20.0]));
+        let _ = engine.handle_pen_event(
+            PenEvent::Up {
+                element: Element::new(na::vector![60.0, 20.0] / engine.camera.total_zoom(), 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            None,
+            now,
+        );
+
+        // the input of the suppressed redraws is still part of the stroke
+        let keys = engine.store.stroke_keys_as_rendered();
+        assert_eq!(keys.len(), 1);
+        let Some(Stroke::BrushStroke(brushstroke)) = engine.store.get_stroke_ref(keys[0]) else {
+            panic!("the committed stroke is not a brush stroke");
+        };
+        assert_eq!(brushstroke.path.segments.len(), 7);
+    }
+
+    #[test]
+    fn pen_requested_redraw_survives_min_redraw_motion() {
+        let mut engine = Engine::default();
+        let _ = engine.change_pen_style(PenStyle::Tools);
+        engine.pens_config.tools_config.style = ToolStyle::Smudge;
+        engine.penholder.set_min_redraw_motion(2.0);
+        let now = Instant::now();
+        let mut down = |pos: na::Vector2<f64>| {
+            engine
+                .handle_pen_event(
+                    PenEvent::Down {
+                        element: Element::new(pos / engine.camera.total_zoom(), 0.5),
+                        modifier_keys: HashSet::new(),
+                    },
+                    None,
+                    now,
+                )
+                .1
+                .redraw
+        };
+
+        assert!(down(na::vector![20.0, 20.0]));
+        assert!(down(na::vector![30.0, 20.0]));
+        // the smudge tool requests a redraw for every motion
+        assert!(down(na::vector![30.5, 20.0]));
+    }
+
+    #[test]
+    fn clamp_input_only_applies_to_drawing_pens() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.document.layout = Layout::FixedSize;
+        engine.document.clamp_input = true;
+        let bounds = engine.document.bounds();
+        let outside = bounds.mins.coords - na::vector![50.0, 50.0];
+        let now = Instant::now();
+
+        for pos in [bounds.center().coords, outside] {
+            let _ = engine.handle_pen_event(
+                PenEvent::Down {
+                    element: Element::new(pos, 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                None,
+                now,
+            );
+        }
+        let _ = engine.handle_pen_event(
+            PenEvent::Up {
+                element: Element::new(outside, 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            None,
+            now,
+        );
+        let keys = engine.store.stroke_keys_as_rendered();
+        let Some(Stroke::BrushStroke(brushstroke)) = engine.store.get_stroke_ref(keys[0]) else {
+            panic!("the drawn stroke is not a brush stroke");
+        };
+        assert!(brushstroke
+            .path
+            .segments
+            .iter()
+            .all(|seg| seg.end().pos == bounds.mins.coords));
+
+        // panning is not restricted to the document
+        let _ = engine.change_pen_style(PenStyle::Tools);
+        engine.pens_config.tools_config.style = ToolStyle::OffsetCamera;
+        let _ = engine.handle_pen_event(
+            PenEvent::Down {
+                element: Element::new(outside, 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            None,
+            now,
+        );
+        let Pen::Tools(tools) = engine.penholder.current_pen_ref() else {
+            panic!("the current pen is not the tools pen");
+        };
+        assert_eq!(tools.offsetcamera_tool.start, outside);
+    }
+
+    #[test]
+    fn multi_pen_input_draws_separate_strokes() {
+        let mut engine = Engine::default();
+        engine.pens_config.brush_config.builder_type = PenPathBuilderType::Simple;
+        engine.pens_config.brush_config.set_smoothing(0.0);
+        engine.penholder.set_multi_pen_input(true);
+        let now = Instant::now();
+        let element = |pointer_id: u32, i: usize| {
+            Element::new(
+                na::vector![20.0 + 10.0 * i as f64, 50.0 * pointer_id as f64],
+                0.5,
+            )
+        };
+
+        // two interleaved input streams
+        for i in 0..5 {
+            for pointer_id in [1, 2] {
+                let _ = engine.handle_pen_event_for_pointer(
+                    PenEvent::Down {
+                        element: element(pointer_id, i),
+                        modifier_keys: HashSet::new(),
+                    },
+                    pointer_id,
+                    None,
+                    now,
+                );
+            }
+        }
+        for pointer_id in [2, 1] {
+            let _ = engine.handle_pen_event_for_pointer(
+                PenEvent::Up {
+                    element: element(pointer_id, 5),
+                    modifier_keys: HashSet::new(),
+                },
+                pointer_id,
+                None,
+                now,
+            );
+        }
+
+        let keys = engine.store.stroke_keys_as_rendered();
+        assert_eq!(keys.len(), 2);
+        let mut strokes = keys
+            .iter()
+            .map(|&key| {
+                let Some(Stroke::BrushStroke(brushstroke)) = engine.store.get_stroke_ref(key)
+                else {
+                    panic!("the stroke is not a brush stroke");
+                };
+                std::iter::once(brushstroke.path.start.pos)
+                    .chain(brushstroke.path.segments.iter().map(|seg| seg.end().pos))
+                    .collect::<Vec<na::Vector2<f64>>>()
+            })
+            .collect::<Vec<Vec<na::Vector2<f64>>>>();
+        strokes.sort_by(|a, b| a[0][1].total_cmp(&b[0][1]));
+        for (stroke, pointer_id) in strokes.iter().zip([1, 2]) {
+            assert_eq!(
+                stroke,
+                &(0..6)
+                    .map(|i| element(pointer_id, i).pos)
+                    .collect::<Vec<na::Vector2<f64>>>()
+            );
+        }
+        assert_eq!(
+            engine.penholder.current_pen_progress(),
+            PenProgress::Finished
+        );
+    }
+}
@@ -1,12 +1,16 @@
 // Imports
+use crate::pens::smoothing::InputSmoother;
 use crate::store::chrono_comp::StrokeLayer;
 use rand::{Rng, SeedableRng};
 use rnote_compose::builders::PenPathBuilderType;
+use rnote_compose::color::contrasting_ink;
+use rnote_compose::penpath::CurveFit;
 use rnote_compose::style::smooth::SmoothOptions;
 use rnote_compose::style::textured::TexturedOptions;
 use rnote_compose::style::PressureCurve;
-use rnote_compose::Style;
+use rnote_compose::{Color, Style};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(
     Debug,
@@ -97,7 +101,7 @@ impl std::ops::DerefMut for SolidOptions {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "brush_config")]
 pub struct BrushConfig {
     #[serde(rename = "builder_type")]
@@ -110,11 +114,203 @@ pub struct BrushConfig {
     pub solid_options: SolidOptions,
     #[serde(rename = "textured_options")]
     pub textured_options: TexturedOptions,
+    /// How much the pen pressure influences the stroke width, in range [0.0, 1.0].
+    ///
+    /// At 0.0 the stroke has the constant base width, at 1.0 the width is fully driven by the pressure.
+    #[serde(
+        rename = "pressure_influence",
+        with = "rnote_compose::serialize::f64_dp3"
+    )]
+    pressure_influence: f64,
+    /// The input smoothing, in range [0.0, 0.95].
+    #[serde(rename = "smoothing", with = "rnote_compose::serialize::f64_dp3")]
+    smoothing: f64,
+    /// Start new strokes at the endpoint of an existing stroke when they are started close to it.
+    #[serde(rename = "auto_connect")]
+    pub auto_connect: bool,
+    /// Refit finished strokes with this algorithm. None keeps the path produced by the builder.
+    #[serde(rename = "curve_fit")]
+    pub curve_fit: Option<CurveFit>,
+    /// The maximum distance in screen pixels of the input elements to the refitted stroke. Smaller tolerances follow
+    /// the input more closely with more segments.
+    #[serde(rename = "fit_tolerance", with = "rnote_compose::serialize::f64_dp3")]
+    fit_tolerance: f64,
+    /// Start new strokes with the width the previously drawn stroke ended with, for a consistent line weight across
+    /// connected strokes.
+    #[serde(rename = "continue_previous_width")]
+    pub continue_previous_width: bool,
+    /// Switch the stroke color to the ink that contrasts with the document background when the background color
+    /// changes. Explicitly chosen colors other than the default ink of the previous background are kept.
+    #[serde(rename = "ink_follows_background")]
+    pub ink_follows_background: bool,
+    /// Map the pen pressure to the opacity of the stroke segments. Applies to the marker and solid styles.
+    #[serde(rename = "pressure_opacity")]
+    pub pressure_opacity: bool,
+    /// Mix the color of the stroke segments by the pen pressure, from color A at light pressure to color B at heavy
+    /// pressure. Applies to the marker and solid styles and replaces their stroke color.
+    #[serde(rename = "pressure_color")]
+    pub pressure_color: bool,
+    /// The color at light pressure when mixing the color by pressure.
+    #[serde(rename = "pressure_color_a")]
+    pub pressure_color_a: Color,
+    /// The color at heavy pressure when mixing the color by pressure.
+    #[serde(rename = "pressure_color_b")]
+    pub pressure_color_b: Color,
+    /// Feather the outline of strokes with soft edges instead of hard ones. Applies to the marker and solid styles.
+    #[serde(rename = "smooth_edges")]
+    pub smooth_edges: bool,
+    /// Ease the width transitions between the sampled points of strokes, avoiding facets where the width changes
+    /// abruptly. Applies to the marker and solid styles.
+    #[serde(rename = "width_smoothing")]
+    pub width_smoothing: bool,
+    /// The width that the ends of strokes are never tapered below at low pressures, so that strokes don't fade out
+    /// into nothing. Applies to the marker and solid styles.
+    #[serde(rename = "min_taper_width", with = "rnote_compose::serialize::f64_dp3")]
+    pub min_taper_width: f64,
+    /// Snap the stroke into the clean shape it resembles when holding the pen still at its end.
+    #[serde(rename = "hold_to_snap")]
+    pub hold_to_snap: bool,
+    /// Straighten the stroke progressively while it is drawn slowly, fast strokes stay freehand.
+    #[serde(rename = "adaptive_straighten")]
+    pub adaptive_straighten: bool,
+    /// Strokes that move less than this distance in screen pixels away from their start are discarded when finished.
+    #[serde(
+        rename = "min_stroke_extent",
+        with = "rnote_compose::serialize::f64_dp3"
+    )]
+    pub min_stroke_extent: f64,
+    /// Strokes that last shorter than this duration are discarded when finished.
+    #[serde(rename = "min_stroke_duration")]
+    pub min_stroke_duration: Duration,
+    /// Keep strokes below the minimum extent or duration, so that taps draw dots.
+    #[serde(rename = "dot_mode")]
+    pub dot_mode: bool,
+}
+
+impl Default for BrushConfig {
+    fn default() -> Self {
+        Self {
+            builder_type: PenPathBuilderType::default(),
+            style: BrushStyle::default(),
+            marker_options: MarkerOptions::default(),
+            solid_options: SolidOptions::default(),
+            textured_options: TexturedOptions::default(),
+            pressure_influence: Self::PRESSURE_INFLUENCE_DEFAULT,
+            smoothing: Self::SMOOTHING_DEFAULT,
+            auto_connect: false,
+            curve_fit: None,
+            fit_tolerance: Self::FIT_TOLERANCE_DEFAULT,
+            continue_previous_width: false,
+            ink_follows_background: false,
+            pressure_opacity: false,
+            pressure_color: false,
+            pressure_color_a: Self::PRESSURE_COLOR_A_DEFAULT,
+            pressure_color_b: Self::PRESSURE_COLOR_B_DEFAULT,
+            smooth_edges: false,
+            width_smoothing: false,
+            min_taper_width: Self::MIN_TAPER_WIDTH_DEFAULT,
+            hold_to_snap: false,
+            adaptive_straighten: false,
+            min_stroke_extent: 0.0,
+            min_stroke_duration: Duration::ZERO,
+            dot_mode: false,
+        }
+    }
 }
 
 impl BrushConfig {
     pub const STROKE_WIDTH_MIN: f64 = 0.1;
     pub const STROKE_WIDTH_MAX: f64 = 500.0;
+    /// The default pressure influence, where the width is fully driven by the pressure.
+    pub const PRESSURE_INFLUENCE_DEFAULT: f64 = 1.0;
+    /// The default input smoothing. Brushes are smoothed heavily.
+    pub const SMOOTHING_DEFAULT: f64 = 0.5;
+    /// The default minimum width of the tapered stroke ends, barely visible but never vanishing.
+    pub const MIN_TAPER_WIDTH_DEFAULT: f64 = 0.1;
+    /// The distance in screen pixels within which new strokes are connected to existing endpoints.
+    pub const AUTO_CONNECT_DIST_PX: f64 = 10.0;
+    /// The default maximum distance in screen pixels of the input elements to refitted strokes.
+    pub const FIT_TOLERANCE_DEFAULT: f64 = 0.5;
+    pub const FIT_TOLERANCE_MIN: f64 = 0.05;
+    pub const FIT_TOLERANCE_MAX: f64 = 20.0;
+    /// How long the pen needs to be held still at the end of a stroke to snap it into a shape.
+    pub const HOLD_TO_SNAP_DURATION: Duration = Duration::from_millis(500);
+    /// The maximum mean deviation of the stroke from the snapped shape, relative to the size of the stroke.
+    pub const HOLD_TO_SNAP_TOLERANCE: f64 = 0.08;
+    /// The default color at light pressure when mixing the color by pressure.
+    pub const PRESSURE_COLOR_A_DEFAULT: Color = Color {
+        r: 0.6,
+        g: 0.76,
+        b: 0.95,
+        a: 1.0,
+    };
+    /// The default color at heavy pressure when mixing the color by pressure.
+    pub const PRESSURE_COLOR_B_DEFAULT: Color = Color {
+        r: 0.11,
+        g: 0.15,
+        b: 0.45,
+        a: 1.0,
+    };
+
+    /// The base width of the current style, which is the width of the stroke drawn at full pressure.
+    pub fn base_width(&self) -> f64 {
+        match &self.style {
+            BrushStyle::Marker => self.marker_options.stroke_width,
+            BrushStyle::Solid => self.solid_options.stroke_width,
+            BrushStyle::Textured => self.textured_options.stroke_width,
+        }
+    }
+
+    /// Set the base width of the current style. Gets clamped to the allowed range.
+    pub fn set_base_width(&mut self, base_width: f64) {
+        let base_width = base_width.clamp(Self::STROKE_WIDTH_MIN, Self::STROKE_WIDTH_MAX);
+        match &self.style {
+            BrushStyle::Marker => self.marker_options.stroke_width = base_width,
+            BrushStyle::Solid => self.solid_options.stroke_width = base_width,
+            BrushStyle::Textured => self.textured_options.stroke_width = base_width,
+        }
+    }
+
+    pub fn pressure_influence(&self) -> f64 {
+        self.pressure_influence
+    }
+
+    /// Set the pressure influence. Gets clamped to the range [0.0, 1.0].
+    pub fn set_pressure_influence(&mut self, pressure_influence: f64) {
+        self.pressure_influence = pressure_influence.clamp(0.0, 1.0);
+    }
+
+    pub fn smoothing(&self) -> f64 {
+        self.smoothing
+    }
+
+    /// Set the input smoothing. Gets clamped to the range [0.0, 0.95].
+    pub fn set_smoothing(&mut self, smoothing: f64) {
+        self.smoothing = smoothing.clamp(0.0, InputSmoother::SMOOTHING_MAX);
+    }
+
+    pub fn fit_tolerance(&self) -> f64 {
+        self.fit_tolerance
+    }
+
+    /// Set the curve fit tolerance in screen pixels. Gets clamped to the allowed range.
+    pub fn set_fit_tolerance(&mut self, fit_tolerance: f64) {
+        self.fit_tolerance = fit_tolerance.clamp(Self::FIT_TOLERANCE_MIN, Self::FIT_TOLERANCE_MAX);
+    }
+
+    /// Whether a finished stroke is too small to be kept, given how far it moved from its start in screen pixels and
+    /// how long it lasted.
+    pub(crate) fn is_micro_stroke(&self, extent_px: f64, duration: Duration) -> bool {
+        !self.dot_mode
+            && (extent_px < self.min_stroke_extent || duration < self.min_stroke_duration)
+    }
+
+    /// The pressure the stroke width gets computed from, blending a constant full pressure
+    /// with the actual pen pressure according to the pressure influence.
+    pub(crate) fn effective_pressure(&self, pressure: f64) -> f64 {
+        let influence = self.pressure_influence.clamp(0.0, 1.0);
+        (1.0 - influence) + influence * pressure.clamp(0.0, 1.0)
+    }
 
     pub(crate) fn layer_for_current_options(&self) -> StrokeLayer {
         match &self.style {
@@ -129,15 +325,63 @@ impl BrushConfig {
         self.textured_options.seed = seed;
     }
 
+    /// Switch the stroke colors that are the default ink of the previous background to the ink of the new background,
+    /// when the ink follows the background.
+    ///
+    /// Returns true when a stroke color has changed.
+    pub(crate) fn follow_background(&mut self, prev_background: Color, background: Color) -> bool {
+        if !self.ink_follows_background {
+            return false;
+        }
+        let (prev_ink, ink) = (
+            contrasting_ink(prev_background),
+            contrasting_ink(background),
+        );
+        if prev_ink == ink {
+            return false;
+        }
+        let mut changed = false;
+        for stroke_color in [
+            &mut self.marker_options.stroke_color,
+            &mut self.solid_options.stroke_color,
+            &mut self.textured_options.stroke_color,
+        ] {
+            if stroke_color.is_some_and(|color| color.approx_eq(prev_ink)) {
+                *stroke_color = Some(ink);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn apply_pressure_color(&self, options: &mut SmoothOptions) {
+        if self.pressure_color {
+            options.stroke_color = Some(self.pressure_color_a);
+            options.pressure_color = Some(self.pressure_color_b);
+        } else {
+            options.pressure_color = None;
+        }
+    }
+
     pub(crate) fn style_for_current_options(&self) -> Style {
         match &self.style {
             BrushStyle::Marker => {
-                let MarkerOptions(options) = self.marker_options.clone();
+                let MarkerOptions(mut options) = self.marker_options.clone();
+                options.pressure_opacity = self.pressure_opacity;
+                self.apply_pressure_color(&mut options);
+                options.smooth_edges = self.smooth_edges;
+                options.width_smoothing = self.width_smoothing;
+                options.min_width = self.min_taper_width;
 
                 Style::Smooth(options)
             }
             BrushStyle::Solid => {
-                let SolidOptions(options) = self.solid_options.clone();
+                let SolidOptions(mut options) = self.solid_options.clone();
+                options.pressure_opacity = self.pressure_opacity;
+                self.apply_pressure_color(&mut options);
+                options.smooth_edges = self.smooth_edges;
+                options.width_smoothing = self.width_smoothing;
+                options.min_width = self.min_taper_width;
 
                 Style::Smooth(options)
             }
@@ -149,3 +393,296 @@ impl BrushConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BrushConfig, BrushStyle};
+    use crate::render;
+    use approx::assert_relative_eq;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::penpath::{Element, Segment};
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::style::{Composer, PressureCurve};
+    use rnote_compose::{Color, PenPath, Style};
+
+    #[test]
+    fn effective_pressure_influence_range() {
+        let mut brush_config = BrushConfig::default();
+
+        brush_config.set_pressure_influence(0.0);
+        assert_relative_eq!(brush_config.effective_pressure(0.1), 1.0);
+        assert_relative_eq!(brush_config.effective_pressure(0.9), 1.0);
+
+        brush_config.set_pressure_influence(0.5);
+        assert_relative_eq!(brush_config.effective_pressure(0.0), 0.5);
+        assert_relative_eq!(brush_config.effective_pressure(0.6), 0.8);
+
+        brush_config.set_pressure_influence(1.0);
+        assert_relative_eq!(brush_config.effective_pressure(0.0), 0.0);
+        assert_relative_eq!(brush_config.effective_pressure(0.3), 0.3);
+
+        brush_config.set_pressure_influence(2.0);
+        assert_relative_eq!(brush_config.pressure_influence(), 1.0);
+    }
+
+    #[test]
+    fn low_pressure_end_respects_min_taper_width() {
+        let mut brush_config = BrushConfig::default();
+        brush_config.style = BrushStyle::Solid;
+        brush_config.solid_options.stroke_width = 10.0;
+        brush_config.solid_options.pressure_curve = PressureCurve::Linear;
+        brush_config.min_taper_width = 2.0;
+        let Style::Smooth(options) = brush_config.style_for_current_options() else {
+            panic!("solid brush style is not smooth");
+        };
+        assert_relative_eq!(options.width_for_pressure(0.0), 2.0);
+        assert_relative_eq!(options.width_for_pressure(1.0), 10.0);
+
+        // Tapers from full pressure to no pressure at the end
+        let path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 10.0], 1.0),
+            [Segment::LineTo {
+                end: Element::new(na::vector![100.0, 10.0], 0.0),
+            }],
+        );
+        let alpha_near_end = |options: &SmoothOptions| {
+            let image = render::Image::gen_with_piet(
+                |piet_cx| {
+                    path.draw_composed(piet_cx, options);
+                    Ok(())
+                },
+                Aabb::new(na::point![0.0, 0.0], na::point![100.0, 20.0]),
+                1.0,
+            )
+            .unwrap();
+            // The image bounds are loosened by one pixel
+            let (x, y) = (98 + 1, 11);
+            image.data[(y * image.pixel_width as usize + x) * 4 + 3]
+        };
+
+        let floored = alpha_near_end(&options);
+        let vanishing = alpha_near_end(&SmoothOptions {
+            min_width: 0.0,
+            ..options.clone()
+        });
+        assert!(floored > 200);
+        assert!(vanishing < 100);
+    }
+
+    #[test]
+    fn pressure_opacity_renders_low_pressure_transparent() {
+        let mut brush_config = BrushConfig::default();
+        brush_config.style = BrushStyle::Solid;
+        brush_config.solid_options.stroke_width = 10.0;
+        // Keep the width constant, so that only the opacity depends on the pressure
+        brush_config.solid_options.pressure_curve = PressureCurve::Const;
+        brush_config.pressure_opacity = true;
+        let Style::Smooth(options) = brush_config.style_for_current_options() else {
+            panic!("solid brush style is not smooth");
+        };
+
+        let path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 10.0], 0.1),
+            [
+                Segment::LineTo {
+                    end: Element::new(na::vector![40.0, 10.0], 0.1),
+                },
+                Segment::LineTo {
+                    end: Element::new(na::vector![60.0, 10.0], 1.0),
+                },
+                Segment::LineTo {
+                    end: Element::new(na::vector![100.0, 10.0], 1.0),
+                },
+            ],
+        );
+        let alpha_at = |options: &SmoothOptions, x: usize| {
+            let image = render::Image::gen_with_piet(
+                |piet_cx| {
+                    path.draw_composed(piet_cx, options);
+                    Ok(())
+                },
+                Aabb::new(na::point![0.0, 0.0], na::point![100.0, 20.0]),
+                1.0,
+            )
+            .unwrap();
+            // The image bounds are loosened by one pixel
+            let (x, y) = (x + 1, 11);
+            image.data[(y * image.pixel_width as usize + x) * 4 + 3]
+        };
+
+        let low = alpha_at(&options, 20);
+        let high = alpha_at(&options, 80);
+        assert!(low < high);
+        assert_eq!(high, 255);
+
+        // Without the pressure opacity the stroke is opaque everywhere
+        let opaque_options = SmoothOptions {
+            pressure_opacity: false,
+            ..options.clone()
+        };
+        assert_eq!(alpha_at(&opaque_options, 20), 255);
+    }
+
+    #[test]
+    fn pressure_color_mixes_colors_by_pressure() {
+        let mut brush_config = BrushConfig::default();
+        brush_config.style = BrushStyle::Solid;
+        brush_config.solid_options.stroke_width = 10.0;
+        brush_config.solid_options.pressure_curve = PressureCurve::Const;
+        brush_config.pressure_color = true;
+        brush_config.pressure_color_a = Color::RED;
+        brush_config.pressure_color_b = Color::BLUE;
+        let Style::Smooth(options) = brush_config.style_for_current_options() else {
+            panic!("solid brush style is not smooth");
+        };
+
+        let path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 10.0], 0.0),
+            [
+                Segment::LineTo {
+                    end: Element::new(na::vector![30.0, 10.0], 0.0),
+                },
+                Segment::LineTo {
+                    end: Element::new(na::vector![40.0, 10.0], 0.5),
+                },
+                Segment::LineTo {
+                    end: Element::new(na::vector![60.0, 10.0], 0.5),
+                },
+                Segment::LineTo {
+                    end: Element::new(na::vector![70.0, 10.0], 1.0),
+                },
+                Segment::LineTo {
+                    end: Element::new(na::vector![100.0, 10.0], 1.0),
+                },
+            ],
+        );
+        let image = render::Image::gen_with_piet(
+            |piet_cx| {
+                path.draw_composed(piet_cx, &options);
+                Ok(())
+            },
+            Aabb::new(na::point![0.0, 0.0], na::point![100.0, 20.0]),
+            1.0,
+        )
+        .unwrap();
+        // The image bounds are loosened by one pixel
+        let rgba_at = |x: usize| {
+            let i = (11 * image.pixel_width as usize + x + 1) * 4;
+            [
+                image.data[i],
+                image.data[i + 1],
+                image.data[i + 2],
+                image.data[i + 3],
+            ]
+        };
+
+        assert_eq!(rgba_at(15), [255, 0, 0, 255]);
+        assert_eq!(rgba_at(85), [0, 0, 255, 255]);
+        let [r, g, b, a] = rgba_at(50);
+        assert!((r as i32 - 128).abs() <= 1 && g == 0 && (b as i32 - 128).abs() <= 1);
+        assert_eq!(a, 255);
+
+        // Without mixing the color by pressure the stroke color is drawn everywhere
+        brush_config.pressure_color = false;
+        let Style::Smooth(options) = brush_config.style_for_current_options() else {
+            panic!("solid brush style is not smooth");
+        };
+        assert_eq!(options.pressure_color, None);
+    }
+
+    #[test]
+    fn smooth_edges_render_alpha_gradient() {
+        let mut brush_config = BrushConfig::default();
+        brush_config.style = BrushStyle::Solid;
+        brush_config.solid_options.stroke_width = 10.0;
+        brush_config.solid_options.pressure_curve = PressureCurve::Const;
+        brush_config.smooth_edges = true;
+        let Style::Smooth(soft_options) = brush_config.style_for_current_options() else {
+            panic!("solid brush style is not smooth");
+        };
+        let hard_options = SmoothOptions {
+            smooth_edges: false,
+            ..soft_options.clone()
+        };
+
+        let path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 10.0], 1.0),
+            [Segment::LineTo {
+                end: Element::new(na::vector![100.0, 10.0], 1.0),
+            }],
+        );
+        // The alpha values of the column through the middle of the stroke, from its center outwards
+        let alpha_column = |options: &SmoothOptions| {
+            let image = render::Image::gen_with_piet(
+                |piet_cx| {
+                    path.draw_composed(piet_cx, options);
+                    Ok(())
+                },
+                Aabb::new(na::point![0.0, 0.0], na::point![100.0, 20.0]),
+                1.0,
+            )
+            .unwrap();
+            // The image bounds are loosened by one pixel
+            (11..=17)
+                .map(|y| image.data[(y * image.pixel_width as usize + 51) * 4 + 3])
+                .collect::<Vec<u8>>()
+        };
+        let partial = |alphas: &[u8]| alphas.iter().filter(|a| **a > 0 && **a < 255).count();
+
+        let soft = alpha_column(&soft_options);
+        assert_eq!(soft[0], 255);
+        assert_eq!(*soft.last().unwrap(), 0);
+        assert!(soft.windows(2).all(|w| w[0] >= w[1]));
+        assert!(partial(&soft) >= 3);
+
+        // Hard edges jump from opaque to transparent at the outline
+        let hard = alpha_column(&hard_options);
+        assert_eq!(hard[0], 255);
+        assert_eq!(*hard.last().unwrap(), 0);
+        assert!(partial(&hard) <= 1);
+    }
+
+    #[test]
+    fn smooth_edges_dont_stack_at_segment_joins() {
+        let mut brush_config = BrushConfig::default();
+        brush_config.style = BrushStyle::Solid;
+        brush_config.solid_options.stroke_width = 10.0;
+        brush_config.solid_options.pressure_curve = PressureCurve::Const;
+        brush_config.smooth_edges = true;
+        let Style::Smooth(options) = brush_config.style_for_current_options() else {
+            panic!("solid brush style is not smooth");
+        };
+
+        let path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 10.0], 1.0),
+            (1..=10).map(|i| Segment::LineTo {
+                end: Element::new(na::vector![i as f64 * 10.0, 10.0], 1.0),
+            }),
+        );
+        let image = render::Image::gen_with_piet(
+            |piet_cx| {
+                path.draw_composed(piet_cx, &options);
+                Ok(())
+            },
+            Aabb::new(na::point![0.0, 0.0], na::point![100.0, 20.0]),
+            1.0,
+        )
+        .unwrap();
+        // The image bounds are loosened by one pixel
+        let alpha_column = |x: usize| {
+            (11..=17)
+                .map(|y| image.data[(y * image.pixel_width as usize + x + 1) * 4 + 3])
+                .collect::<Vec<u8>>()
+        };
+
+        // The falloff at the join of two segments is the same as in the middle of a segment
+        let at_join = alpha_column(50);
+        let in_segment = alpha_column(55);
+        for (join, segment) in at_join.iter().zip(in_segment.iter()) {
+            assert!(
+                join.abs_diff(*segment) <= 2,
+                "{at_join:?} != {in_segment:?}"
+            );
+        }
+    }
+}
@@ -1,4 +1,5 @@
 // Imports
+use geo::ConvexHull;
 use p2d::bounding_volume::Aabb;
 use rnote_compose::penpath::Element;
 use serde::{Deserialize, Serialize};
@@ -10,8 +11,20 @@ use serde::{Deserialize, Serialize};
 pub enum EraserStyle {
     #[serde(rename = "trash_colliding_strokes")]
     TrashCollidingStrokes,
+    /// Splits colliding freehand brush strokes, while shapes are trashed as a whole.
     #[serde(rename = "split_colliding_strokes")]
     SplitCollidingStrokes,
+    /// Reduces the opacity of colliding strokes proportional to the pen pressure,
+    /// until they are fully transparent and get trashed.
+    #[serde(rename = "soft")]
+    Soft,
+    /// Trashes the topmost stroke under the pen when it is lifted, without needing to swipe.
+    #[serde(rename = "tap")]
+    Tap,
+    /// Subtracts the swept area of the eraser from colliding filled shapes when it is lifted,
+    /// keeping the remaining parts.
+    #[serde(rename = "carve")]
+    Carve,
 }
 
 impl Default for EraserStyle {
@@ -30,6 +43,47 @@ impl TryFrom<u32> for EraserStyle {
     }
 }
 
+/// Which strokes the eraser is allowed to erase.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "eraser_scope")]
+pub enum EraserScope {
+    /// All strokes.
+    #[serde(rename = "all")]
+    All,
+    /// Only strokes on the active user layer.
+    #[serde(rename = "active_layer")]
+    ActiveLayer,
+    /// Only strokes that are currently selected.
+    #[serde(rename = "selection")]
+    Selection,
+}
+
+impl Default for EraserScope {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
+impl TryFrom<u32> for EraserScope {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!("EraserScope try_from::<u32>() for value {} failed", value)
+        })
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "eraser_config")]
 pub struct EraserConfig {
@@ -37,6 +91,23 @@ pub struct EraserConfig {
     pub width: f64,
     #[serde(rename = "style")]
     pub style: EraserStyle,
+    #[serde(rename = "scope")]
+    pub scope: EraserScope,
+    /// The user layer that is erased when the scope is [EraserScope::ActiveLayer].
+    #[serde(rename = "active_layer")]
+    pub active_layer: u32,
+    /// Whether the eraser also carves transparency into the background images on the image layer.
+    #[serde(rename = "erase_background")]
+    pub erase_background: bool,
+    /// Whether the pen pressure maps the eraser radius between the min and max radius, instead of using the width.
+    #[serde(rename = "pressure_radius")]
+    pub pressure_radius: bool,
+    /// The eraser radius at no pressure, when the pressure radius is enabled.
+    #[serde(rename = "min_radius")]
+    pub min_radius: f64,
+    /// The eraser radius at full pressure, when the pressure radius is enabled.
+    #[serde(rename = "max_radius")]
+    pub max_radius: f64,
 }
 
 impl Default for EraserConfig {
@@ -44,6 +115,12 @@ impl Default for EraserConfig {
         Self {
             width: Self::WIDTH_DEFAULT,
             style: EraserStyle::default(),
+            scope: EraserScope::default(),
+            active_layer: 0,
+            erase_background: false,
+            pressure_radius: false,
+            min_radius: Self::MIN_RADIUS_DEFAULT,
+            max_radius: Self::MAX_RADIUS_DEFAULT,
         }
     }
 }
@@ -52,8 +129,132 @@ impl EraserConfig {
     pub const WIDTH_MIN: f64 = 1.0;
     pub const WIDTH_MAX: f64 = 500.0;
     pub const WIDTH_DEFAULT: f64 = 12.0;
+    pub const MIN_RADIUS_DEFAULT: f64 = 3.0;
+    pub const MAX_RADIUS_DEFAULT: f64 = 24.0;
+    /// The opacity that the soft eraser removes from colliding strokes per event, at full pressure.
+    pub const SOFT_OPACITY_REDUCTION: f64 = 0.05;
 
     pub(crate) fn eraser_bounds(&self, element: Element) -> Aabb {
-        Aabb::from_half_extents(element.pos.into(), na::Vector2::repeat(self.width * 0.5))
+        Aabb::from_half_extents(
+            element.pos.into(),
+            na::Vector2::repeat(self.radius(element)),
+        )
+    }
+
+    /// The effective eraser radius for the given element.
+    ///
+    /// Input without pressure support reports the default pressure, so it gets a fixed radius.
+    pub(crate) fn radius(&self, element: Element) -> f64 {
+        if !self.pressure_radius {
+            return self.width * 0.5;
+        }
+        let (min, max) = (
+            self.min_radius.min(self.max_radius),
+            self.max_radius.max(self.min_radius),
+        );
+        min + (max - min) * element.pressure.clamp(0.0, 1.0)
+    }
+
+    /// The largest radius the eraser can have, which is the max radius when the pressure radius is enabled.
+    pub(crate) fn largest_radius(&self) -> f64 {
+        if self.pressure_radius {
+            self.max_radius.max(self.min_radius)
+        } else {
+            self.width * 0.5
+        }
+    }
+
+    /// The area that the eraser swept over when moved from the previous to the current element.
+    pub(crate) fn eraser_swept_polygon(
+        &self,
+        prev_element: Option<Element>,
+        element: Element,
+    ) -> geo::Polygon<f64> {
+        let corners = prev_element
+            .into_iter()
+            .chain(std::iter::once(element))
+            .flat_map(|element| {
+                let bounds = self.eraser_bounds(element);
+                [
+                    geo::coord! { x: bounds.mins[0], y: bounds.mins[1] },
+                    geo::coord! { x: bounds.maxs[0], y: bounds.mins[1] },
+                    geo::coord! { x: bounds.maxs[0], y: bounds.maxs[1] },
+                    geo::coord! { x: bounds.mins[0], y: bounds.maxs[1] },
+                ]
+            })
+            .map(geo::Point::from)
+            .collect::<geo::MultiPoint<f64>>();
+
+        corners.convex_hull()
+    }
+
+    /// The opacity that the soft eraser removes for the given element.
+    pub(crate) fn soft_opacity_reduction(&self, element: Element) -> f64 {
+        Self::SOFT_OPACITY_REDUCTION * element.pressure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EraserConfig, EraserScope};
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::StrokeStore;
+    use rnote_compose::penpath::Element;
+    use rnote_compose::shapes::{Line, Shape};
+    use rnote_compose::Style;
+
+    #[test]
+    fn pressure_radius_erases_wider_swath() {
+        let config = EraserConfig {
+            pressure_radius: true,
+            min_radius: 2.0,
+            max_radius: 20.0,
+            ..Default::default()
+        };
+        // short vertical lines, spaced 4.0 apart to the right of the eraser position, starting at 8.0
+        let erased_count = |pressure: f64| {
+            let mut store = StrokeStore::default();
+            for i in 1..=8 {
+                let x = 4.0 + i as f64 * 4.0;
+                store.insert_stroke(
+                    Stroke::ShapeStroke(ShapeStroke::new(
+                        Shape::Line(Line {
+                            start: na::vector![x, -1.0],
+                            end: na::vector![x, 1.0],
+                        }),
+                        Style::default(),
+                    )),
+                    None,
+                );
+            }
+            let _ = store.trash_colliding_strokes(
+                config.eraser_bounds(Element::new(na::vector![0.0, 0.0], pressure)),
+                EraserScope::All,
+                0,
+            );
+            store.trashed_keys_unordered().len()
+        };
+
+        assert!(erased_count(1.0) > erased_count(0.1));
+        assert_eq!(erased_count(0.1), 0);
+
+        // without the pressure radius, all pressures erase the same swath
+        let fixed = EraserConfig {
+            width: 12.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            fixed.eraser_bounds(Element::new(na::vector![0.0, 0.0], 1.0)),
+            fixed.eraser_bounds(Element::new(na::vector![0.0, 0.0], 0.1))
+        );
+
+        // the largest radius bounds every pressure
+        for config in [&config, &fixed] {
+            assert!(
+                config.radius(Element::new(na::vector![0.0, 0.0], 1.0)) <= config.largest_radius()
+            );
+        }
+        assert_eq!(config.largest_radius(), 20.0);
+        assert_eq!(fixed.largest_radius(), 6.0);
     }
 }
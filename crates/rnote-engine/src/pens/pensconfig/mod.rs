@@ -15,9 +15,12 @@ pub use toolsconfig::ToolsConfig;
 pub use typewriterconfig::TypewriterConfig;
 
 // Imports
+use crate::pens::PenStyle;
 use crate::CloneConfig;
+use brushconfig::BrushStyle;
 use rnote_compose::Color;
 use serde::{Deserialize, Serialize};
+use shaperconfig::ShaperStyle;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(default, rename = "pens_config")]
@@ -43,6 +46,65 @@ impl CloneConfig for PensConfig {
 }
 
 impl PensConfig {
+    /// The stroke color of the pen with the given style, for its currently selected sub-style.
+    ///
+    /// Is `None` for pens that don't have a color.
+    pub fn stroke_color(&self, pen_style: PenStyle) -> Option<Color> {
+        match pen_style {
+            PenStyle::Brush => match self.brush_config.style {
+                BrushStyle::Marker => self.brush_config.marker_options.stroke_color,
+                BrushStyle::Solid => self.brush_config.solid_options.stroke_color,
+                BrushStyle::Textured => self.brush_config.textured_options.stroke_color,
+            },
+            PenStyle::Shaper => match self.shaper_config.style {
+                ShaperStyle::Smooth => self.shaper_config.smooth_options.stroke_color,
+                ShaperStyle::Rough => self.shaper_config.rough_options.stroke_color,
+            },
+            PenStyle::Typewriter => Some(self.typewriter_config.text_style.color),
+            PenStyle::Eraser | PenStyle::Selector | PenStyle::Tools => None,
+        }
+    }
+
+    /// Set the stroke color of the pen with the given style, for its currently selected sub-style.
+    ///
+    /// Does nothing for pens that don't have a color.
+    pub fn set_stroke_color(&mut self, pen_style: PenStyle, stroke_color: Color) {
+        match pen_style {
+            PenStyle::Brush => match self.brush_config.style {
+                BrushStyle::Marker => {
+                    self.brush_config.marker_options.stroke_color = Some(stroke_color)
+                }
+                BrushStyle::Solid => {
+                    self.brush_config.solid_options.stroke_color = Some(stroke_color)
+                }
+                BrushStyle::Textured => {
+                    self.brush_config.textured_options.stroke_color = Some(stroke_color)
+                }
+            },
+            PenStyle::Shaper => match self.shaper_config.style {
+                ShaperStyle::Smooth => {
+                    self.shaper_config.smooth_options.stroke_color = Some(stroke_color)
+                }
+                ShaperStyle::Rough => {
+                    self.shaper_config.rough_options.stroke_color = Some(stroke_color)
+                }
+            },
+            PenStyle::Typewriter => self.typewriter_config.text_style.color = stroke_color,
+            PenStyle::Eraser | PenStyle::Selector | PenStyle::Tools => {}
+        }
+    }
+
+    /// The input smoothing of the pen with the given style.
+    ///
+    /// Is zero for pens that don't smooth their input.
+    pub fn smoothing(&self, pen_style: PenStyle) -> f64 {
+        match pen_style {
+            PenStyle::Brush => self.brush_config.smoothing(),
+            PenStyle::Shaper => self.shaper_config.smoothing(),
+            PenStyle::Typewriter | PenStyle::Eraser | PenStyle::Selector | PenStyle::Tools => 0.0,
+        }
+    }
+
     pub fn set_all_stroke_colors(&mut self, stroke_color: Color) {
         self.brush_config.marker_options.stroke_color = Some(stroke_color);
         self.brush_config.solid_options.stroke_color = Some(stroke_color);
@@ -59,3 +121,42 @@ impl PensConfig {
         self.shaper_config.rough_options.fill_color = Some(fill_color);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_stroke_color_targets_current_pen() {
+        let mut pens_config = PensConfig::default();
+        let color = Color::new(0.1, 0.2, 0.3, 1.0);
+
+        pens_config.brush_config.style = BrushStyle::Solid;
+        pens_config.set_stroke_color(PenStyle::Brush, color);
+        assert_eq!(pens_config.stroke_color(PenStyle::Brush), Some(color));
+        assert_eq!(
+            pens_config.brush_config.solid_options.stroke_color,
+            Some(color)
+        );
+        assert_ne!(
+            pens_config.brush_config.marker_options.stroke_color,
+            Some(color)
+        );
+        assert_ne!(pens_config.stroke_color(PenStyle::Shaper), Some(color));
+
+        pens_config.shaper_config.style = ShaperStyle::Rough;
+        pens_config.set_stroke_color(PenStyle::Shaper, color);
+        assert_eq!(
+            pens_config.shaper_config.rough_options.stroke_color,
+            Some(color)
+        );
+        assert_ne!(
+            pens_config.shaper_config.smooth_options.stroke_color,
+            Some(color)
+        );
+
+        pens_config.set_stroke_color(PenStyle::Eraser, color);
+        assert_eq!(pens_config.stroke_color(PenStyle::Eraser), None);
+        assert_eq!(pens_config.stroke_color(PenStyle::Selector), None);
+    }
+}
@@ -24,6 +24,9 @@ pub enum SelectorStyle {
     Single,
     #[serde(rename = "intersectingpath")]
     IntersectingPath,
+    /// Selects every stroke that the path touches, like painting over them with a brush.
+    #[serde(rename = "brush_select")]
+    BrushSelect,
 }
 
 impl Default for SelectorStyle {
@@ -49,6 +52,12 @@ pub struct SelectorConfig {
     pub style: SelectorStyle,
     #[serde(rename = "resize_lock_aspectratio")]
     pub resize_lock_aspectratio: bool,
+    /// The size of the transform handles, in surface coordinates.
+    #[serde(rename = "handle_size_px")]
+    handle_size_px: f64,
+    /// The radius around the path that strokes get selected in when brush selecting, in document coordinates.
+    #[serde(rename = "brush_select_radius")]
+    brush_select_radius: f64,
 }
 
 impl Default for SelectorConfig {
@@ -56,6 +65,43 @@ impl Default for SelectorConfig {
         Self {
             style: SelectorStyle::default(),
             resize_lock_aspectratio: false,
+            handle_size_px: Self::HANDLE_SIZE_PX_DEFAULT,
+            brush_select_radius: Self::BRUSH_SELECT_RADIUS_DEFAULT,
         }
     }
 }
+
+impl SelectorConfig {
+    /// The minimum handle size.
+    pub const HANDLE_SIZE_PX_MIN: f64 = 8.0;
+    /// The maximum handle size.
+    pub const HANDLE_SIZE_PX_MAX: f64 = 64.0;
+    /// The default handle size.
+    pub const HANDLE_SIZE_PX_DEFAULT: f64 = 18.0;
+    /// The minimum brush select radius.
+    pub const BRUSH_SELECT_RADIUS_MIN: f64 = 1.0;
+    /// The maximum brush select radius.
+    pub const BRUSH_SELECT_RADIUS_MAX: f64 = 100.0;
+    /// The default brush select radius.
+    pub const BRUSH_SELECT_RADIUS_DEFAULT: f64 = 8.0;
+
+    pub fn handle_size_px(&self) -> f64 {
+        self.handle_size_px
+    }
+
+    /// Set the handle size. Gets clamped to the allowed range.
+    pub fn set_handle_size_px(&mut self, handle_size_px: f64) {
+        self.handle_size_px =
+            handle_size_px.clamp(Self::HANDLE_SIZE_PX_MIN, Self::HANDLE_SIZE_PX_MAX);
+    }
+
+    pub fn brush_select_radius(&self) -> f64 {
+        self.brush_select_radius
+    }
+
+    /// Set the brush select radius. Gets clamped to the allowed range.
+    pub fn set_brush_select_radius(&mut self, brush_select_radius: f64) {
+        self.brush_select_radius =
+            brush_select_radius.clamp(Self::BRUSH_SELECT_RADIUS_MIN, Self::BRUSH_SELECT_RADIUS_MAX);
+    }
+}
@@ -1,12 +1,14 @@
 // Imports
+use crate::pens::smoothing::InputSmoother;
 use rand::{Rng, SeedableRng};
-use rnote_compose::builders::ShapeBuilderType;
+use rnote_compose::builders::{FreehandBezierBuilder, ShapeBuilderType};
 use rnote_compose::constraints::ConstraintRatio;
 use rnote_compose::style::rough::RoughOptions;
 use rnote_compose::style::smooth::SmoothOptions;
 use rnote_compose::Constraints;
-use rnote_compose::Style;
+use rnote_compose::{Shape, Style};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(
     Copy, Clone, Debug, Serialize, Deserialize, num_derive::FromPrimitive, num_derive::ToPrimitive,
@@ -48,6 +50,22 @@ pub struct ShaperConfig {
     pub rough_options: RoughOptions,
     #[serde(rename = "constraints")]
     pub constraints: Constraints,
+    /// The input smoothing, in range [0.0, 0.95].
+    #[serde(rename = "smoothing", with = "rnote_compose::serialize::f64_dp3")]
+    smoothing: f64,
+    /// When set, the shape is committed when the pen dwells at the same position for this duration,
+    /// as if the pen was released.
+    #[serde(rename = "dwell_commit")]
+    pub dwell_commit: Option<Duration>,
+    /// The radius of the rounded corners of rectangles and polygons. Zero for sharp corners.
+    ///
+    /// The rough style always draws sharp corners.
+    #[serde(rename = "corner_radius", with = "rnote_compose::serialize::f64_dp3")]
+    pub corner_radius: f64,
+    /// The maximum distance in screen pixels of the drawn path to the curves fitted by the freehand bezier builder.
+    /// Smaller tolerances follow the path more closely with more curves.
+    #[serde(rename = "fit_tolerance", with = "rnote_compose::serialize::f64_dp3")]
+    fit_tolerance: f64,
 }
 
 impl Default for ShaperConfig {
@@ -63,6 +81,10 @@ impl Default for ShaperConfig {
             smooth_options: SmoothOptions::default(),
             rough_options: RoughOptions::default(),
             constraints,
+            smoothing: Self::SMOOTHING_DEFAULT,
+            dwell_commit: None,
+            corner_radius: 0.0,
+            fit_tolerance: FreehandBezierBuilder::FIT_TOLERANCE_DEFAULT,
         }
     }
 }
@@ -70,6 +92,28 @@ impl Default for ShaperConfig {
 impl ShaperConfig {
     pub const STROKE_WIDTH_MIN: f64 = 0.1;
     pub const STROKE_WIDTH_MAX: f64 = 500.0;
+    /// The default input smoothing. Shapes are not smoothed, so that they follow the input precisely.
+    pub const SMOOTHING_DEFAULT: f64 = 0.0;
+    pub const FIT_TOLERANCE_MIN: f64 = 0.05;
+    pub const FIT_TOLERANCE_MAX: f64 = 20.0;
+
+    pub fn smoothing(&self) -> f64 {
+        self.smoothing
+    }
+
+    /// Set the input smoothing. Gets clamped to the range [0.0, 0.95].
+    pub fn set_smoothing(&mut self, smoothing: f64) {
+        self.smoothing = smoothing.clamp(0.0, InputSmoother::SMOOTHING_MAX);
+    }
+
+    pub fn fit_tolerance(&self) -> f64 {
+        self.fit_tolerance
+    }
+
+    /// Set the curve fit tolerance in screen pixels. Gets clamped to the allowed range.
+    pub fn set_fit_tolerance(&mut self, fit_tolerance: f64) {
+        self.fit_tolerance = fit_tolerance.clamp(Self::FIT_TOLERANCE_MIN, Self::FIT_TOLERANCE_MAX);
+    }
 
     /// A new seed for new shapes
     pub(crate) fn new_style_seeds(&mut self) {
@@ -77,6 +121,15 @@ impl ShaperConfig {
         self.rough_options.seed = seed;
     }
 
+    /// Apply the corner radius to rectangles and polygons.
+    pub(crate) fn apply_corner_radius(&self, shape: &mut Shape) {
+        match shape {
+            Shape::Rectangle(rectangle) => rectangle.corner_radius = self.corner_radius.max(0.0),
+            Shape::Polygon(polygon) => polygon.corner_radius = self.corner_radius.max(0.0),
+            _ => {}
+        }
+    }
+
     pub(crate) fn gen_style_for_current_options(&self) -> Style {
         match &self.style {
             ShaperStyle::Smooth => {
@@ -22,6 +22,10 @@ pub enum ToolStyle {
     OffsetCamera,
     #[serde(rename = "zoom")]
     Zoom,
+    #[serde(rename = "smudge")]
+    Smudge,
+    #[serde(rename = "crop")]
+    Crop,
 }
 
 impl Default for ToolStyle {
@@ -49,10 +53,32 @@ pub struct VerticalSpaceToolConfig {
     pub limit_movement_vertical_borders: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "smudge_tool_config")]
+pub struct SmudgeToolConfig {
+    /// The radius around the drag path in which the colors of the strokes are blended.
+    #[serde(rename = "radius", with = "rnote_compose::serialize::f64_dp3")]
+    pub radius: f64,
+    /// How strongly the carried color is blended into the strokes along the drag path, in range [0.0, 1.0].
+    #[serde(rename = "strength", with = "rnote_compose::serialize::f64_dp3")]
+    pub strength: f64,
+}
+
+impl Default for SmudgeToolConfig {
+    fn default() -> Self {
+        Self {
+            radius: 10.0,
+            strength: 0.5,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(default, rename = "tools_config")]
 pub struct ToolsConfig {
     #[serde(rename = "style")]
     pub style: ToolStyle,
     pub verticalspace_tool_config: VerticalSpaceToolConfig,
+    #[serde(rename = "smudge_tool_config")]
+    pub smudge_tool_config: SmudgeToolConfig,
 }
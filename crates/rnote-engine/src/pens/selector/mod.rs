@@ -2,11 +2,12 @@
 mod penevents;
 
 // Imports
+use super::cursorhint::{CursorHint, ResizeDirection};
 use super::pensconfig::selectorconfig::SelectorStyle;
 use super::PenBehaviour;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut, StrokeContent};
-use crate::render::Svg;
+use crate::render::{AaQuality, Svg};
 use crate::snap::SnapCorner;
 use crate::store::StrokeKey;
 use crate::strokes::Content;
@@ -62,11 +63,72 @@ impl Default for ModifyState {
     }
 }
 
+/// How the strokes of a new selection are combined with the existing selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectCombine {
+    /// Replace the existing selection.
+    Replace,
+    /// Add the new strokes to the existing selection.
+    Add,
+    /// Remove the new strokes from the existing selection.
+    Subtract,
+    /// Keep only the strokes that are in both the existing and the new selection.
+    Intersect,
+}
+
+impl Default for SelectCombine {
+    fn default() -> Self {
+        Self::Replace
+    }
+}
+
+impl SelectCombine {
+    /// The combine mode for the held modifier keys.
+    ///
+    /// Shift adds, Alt subtracts and both together intersect.
+    pub fn from_modifier_keys(modifier_keys: &HashSet<ModifierKey>) -> Self {
+        match (
+            modifier_keys.contains(&ModifierKey::KeyboardShift),
+            modifier_keys.contains(&ModifierKey::KeyboardAlt),
+        ) {
+            (false, false) => Self::Replace,
+            (true, false) => Self::Add,
+            (false, true) => Self::Subtract,
+            (true, true) => Self::Intersect,
+        }
+    }
+
+    /// Combine the existing selection with the new selected strokes.
+    pub fn combine(&self, existing: &[StrokeKey], new: Vec<StrokeKey>) -> Vec<StrokeKey> {
+        match self {
+            Self::Replace => new,
+            Self::Add => {
+                let mut combined = existing.to_vec();
+                combined.extend(new.into_iter().filter(|key| !existing.contains(key)));
+                combined
+            }
+            Self::Subtract => existing
+                .iter()
+                .copied()
+                .filter(|key| !new.contains(key))
+                .collect(),
+            Self::Intersect => existing
+                .iter()
+                .copied()
+                .filter(|key| new.contains(key))
+                .collect(),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(super) enum SelectorState {
     Idle,
     Selecting {
         path: Vec<Element>,
+        combine: SelectCombine,
+        /// The selection that existed when selecting started, kept when combining.
+        prev_selection: Vec<StrokeKey>,
     },
     ModifySelection {
         modify_state: ModifyState,
@@ -84,12 +146,17 @@ impl Default for SelectorState {
 #[derive(Clone, Debug)]
 pub struct Selector {
     pub(super) state: SelectorState,
+    /// The phase of the dashed outline while selecting, in surface coordinates.
+    ///
+    /// Driven by the UI to animate the outline.
+    dash_phase: f64,
 }
 
 impl Default for Selector {
     fn default() -> Self {
         Self {
             state: SelectorState::default(),
+            dash_phase: 0.0,
         }
     }
 }
@@ -195,7 +262,7 @@ impl PenBehaviour for Selector {
 
                         // Add rendered Png
                         let image = stroke_content_svg
-                            .gen_image(Engine::STROKE_EXPORT_IMAGE_SCALE)?
+                            .gen_image_aa(Engine::STROKE_EXPORT_IMAGE_SCALE, AaQuality::High)?
                             .into_encoded_bytes(image::ImageFormat::Png, None)?;
                         clipboard_content.push((image, String::from("image/png")));
                     }
@@ -256,7 +323,7 @@ impl PenBehaviour for Selector {
 
                         // Add rendered Png
                         let image = stroke_content_svg
-                            .gen_image(Engine::STROKE_EXPORT_IMAGE_SCALE)?
+                            .gen_image_aa(Engine::STROKE_EXPORT_IMAGE_SCALE, AaQuality::High)?
                             .into_encoded_bytes(image::ImageFormat::Png, None)?;
                         clipboard_content.push((image, String::from("image/png")));
                     }
@@ -278,7 +345,7 @@ impl DrawableOnDoc for Selector {
 
         match &self.state {
             SelectorState::Idle => None,
-            SelectorState::Selecting { path } => {
+            SelectorState::Selecting { path, .. } => {
                 // Making sure bounds are always outside of coord + width
                 let mut path_iter = path.iter();
                 if let Some(first) = path_iter.next() {
@@ -295,14 +362,33 @@ impl DrawableOnDoc for Selector {
                         new_bounds.merge(&pos_bounds);
                     });
 
-                    Some(new_bounds.loosened(Self::SELECTING_SINGLE_CIRCLE_RADIUS / total_zoom))
+                    // The brush select outline is drawn with the radius around the path
+                    let brush_select_radius = match engine_view.pens_config.selector_config.style {
+                        SelectorStyle::BrushSelect => engine_view
+                            .pens_config
+                            .selector_config
+                            .brush_select_radius(),
+                        SelectorStyle::Polygon
+                        | SelectorStyle::Rectangle
+                        | SelectorStyle::Single
+                        | SelectorStyle::IntersectingPath => 0.0,
+                    };
+
+                    Some(
+                        new_bounds
+                            .loosened(Self::SELECTING_SINGLE_CIRCLE_RADIUS / total_zoom)
+                            .loosened(brush_select_radius),
+                    )
                 } else {
                     None
                 }
             }
             SelectorState::ModifySelection {
                 selection_bounds, ..
-            } => Some(selection_bounds.extend_by(Self::RESIZE_NODE_SIZE / total_zoom)),
+            } => Some(selection_bounds.extend_by(
+                na::Vector2::repeat(engine_view.pens_config.selector_config.handle_size_px())
+                    / total_zoom,
+            )),
         }
     }
 
@@ -316,7 +402,7 @@ impl DrawableOnDoc for Selector {
 
         match &self.state {
             SelectorState::Idle => {}
-            SelectorState::Selecting { path } => {
+            SelectorState::Selecting { path, .. } => {
                 match engine_view.pens_config.selector_config.style {
                     SelectorStyle::Polygon => {
                         let mut bez_path = kurbo::BezPath::new();
@@ -331,13 +417,7 @@ impl DrawableOnDoc for Selector {
 
                             bez_path.close_path();
 
-                            let mut stroke_style = piet::StrokeStyle::new();
-                            stroke_style.set_dash_pattern(
-                                Self::SELECTING_DASH_PATTERN
-                                    .into_iter()
-                                    .map(|x| x / total_zoom)
-                                    .collect::<Vec<f64>>(),
-                            );
+                            let stroke_style = self.selecting_stroke_style(total_zoom);
 
                             cx.fill(bez_path.clone(), &Self::SELECTION_FILL_COLOR);
                             cx.stroke_styled(
@@ -355,13 +435,7 @@ impl DrawableOnDoc for Selector {
                                 last.pos.to_kurbo_point(),
                             );
 
-                            let mut stroke_style = piet::StrokeStyle::new();
-                            stroke_style.set_dash_pattern(
-                                Self::SELECTING_DASH_PATTERN
-                                    .into_iter()
-                                    .map(|x| x / total_zoom)
-                                    .collect::<Vec<f64>>(),
-                            );
+                            let stroke_style = self.selecting_stroke_style(total_zoom);
 
                             cx.fill(select_rect, &Self::SELECTION_FILL_COLOR);
                             cx.stroke_styled(
@@ -395,13 +469,7 @@ impl DrawableOnDoc for Selector {
                                 bez_path.line_to(element.pos.to_kurbo_point());
                             }
 
-                            let mut stroke_style = piet::StrokeStyle::new();
-                            stroke_style.set_dash_pattern(
-                                Self::SELECTING_DASH_PATTERN
-                                    .into_iter()
-                                    .map(|x| x / total_zoom)
-                                    .collect::<Vec<f64>>(),
-                            );
+                            let stroke_style = self.selecting_stroke_style(total_zoom);
 
                             cx.stroke_styled(
                                 bez_path,
@@ -411,6 +479,31 @@ impl DrawableOnDoc for Selector {
                             );
                         }
                     }
+                    SelectorStyle::BrushSelect => {
+                        let mut bez_path = kurbo::BezPath::new();
+                        let mut path_iter = path.iter();
+
+                        if let Some(first) = path_iter.next() {
+                            bez_path.move_to(first.pos.to_kurbo_point());
+                            bez_path.line_to(first.pos.to_kurbo_point());
+
+                            for element in path_iter {
+                                bez_path.line_to(element.pos.to_kurbo_point());
+                            }
+
+                            cx.stroke_styled(
+                                bez_path,
+                                &Self::BRUSH_SELECT_COLOR,
+                                2.0 * engine_view
+                                    .pens_config
+                                    .selector_config
+                                    .brush_select_radius(),
+                                &piet::StrokeStyle::default()
+                                    .line_cap(piet::LineCap::Round)
+                                    .line_join(piet::LineJoin::Round),
+                            );
+                        }
+                    }
                 }
             }
             SelectorState::ModifySelection {
@@ -430,6 +523,7 @@ impl DrawableOnDoc for Selector {
                     cx,
                     *selection_bounds,
                     modify_state,
+                    engine_view.pens_config.selector_config.handle_size_px(),
                     engine_view.camera,
                 )?;
 
@@ -468,18 +562,19 @@ impl Selector {
     const SELECTING_DASH_PATTERN: [f64; 2] = [12.0, 6.0];
     /// The radius of the circle when selecting in single mode.
     const SELECTING_SINGLE_CIRCLE_RADIUS: f64 = 4.0;
-    /// Resize node size, in surface coordinates.
-    const RESIZE_NODE_SIZE: na::Vector2<f64> = na::vector![18.0, 18.0];
-    /// Rotate node diameter, in surface coordinates.
-    const ROTATE_NODE_DIAMETER: f64 = 18.0;
     /// The outline color when drawing a selection
     const SELECTION_OUTLINE_COLOR: piet::Color = color::GNOME_BRIGHTS[4].with_a8(240);
     /// The fill color when drawing a selection
     const SELECTION_FILL_COLOR: piet::Color = color::GNOME_BRIGHTS[2].with_a8(13);
+    /// The color of the brush path while brush selecting
+    const BRUSH_SELECT_COLOR: piet::Color = color::GNOME_BRIGHTS[2].with_a8(51);
 
     fn add_to_select_path(style: SelectorStyle, path: &mut Vec<Element>, element: Element) {
         match style {
-            SelectorStyle::Polygon | SelectorStyle::Single | SelectorStyle::IntersectingPath => {
+            SelectorStyle::Polygon
+            | SelectorStyle::Single
+            | SelectorStyle::IntersectingPath
+            | SelectorStyle::BrushSelect => {
                 path.push(element);
             }
             SelectorStyle::Rectangle => {
@@ -493,59 +588,190 @@ impl Selector {
         }
     }
 
-    fn resize_node_bounds(position: ResizeCorner, selection_bounds: Aabb, camera: &Camera) -> Aabb {
-        let total_zoom = camera.total_zoom();
-        match position {
-            ResizeCorner::TopLeft => Aabb::from_half_extents(
-                na::point![
-                    selection_bounds.mins[0] - Self::RESIZE_NODE_SIZE[0] * 0.5 / total_zoom,
-                    selection_bounds.mins[1] - Self::RESIZE_NODE_SIZE[0] * 0.5 / total_zoom
-                ],
-                Self::RESIZE_NODE_SIZE * 0.5 / total_zoom,
-            ),
-            ResizeCorner::TopRight => Aabb::from_half_extents(
-                na::point![
-                    selection_bounds.maxs[0] + Self::RESIZE_NODE_SIZE[0] * 0.5 / total_zoom,
-                    selection_bounds.mins[1] - Self::RESIZE_NODE_SIZE[0] * 0.5 / total_zoom
-                ],
-                Self::RESIZE_NODE_SIZE * 0.5 / total_zoom,
-            ),
-            ResizeCorner::BottomLeft => Aabb::from_half_extents(
-                na::point![
-                    selection_bounds.mins[0] - Self::RESIZE_NODE_SIZE[0] * 0.5 / total_zoom,
-                    selection_bounds.maxs[1] + Self::RESIZE_NODE_SIZE[0] * 0.5 / total_zoom
-                ],
-                Self::RESIZE_NODE_SIZE * 0.5 / total_zoom,
-            ),
-            ResizeCorner::BottomRight => Aabb::from_half_extents(
-                na::point![
-                    selection_bounds.maxs[0] + Self::RESIZE_NODE_SIZE[0] * 0.5 / total_zoom,
-                    selection_bounds.maxs[1] + Self::RESIZE_NODE_SIZE[0] * 0.5 / total_zoom
-                ],
-                Self::RESIZE_NODE_SIZE * 0.5 / total_zoom,
-            ),
-        }
+    /// The bounds of the resize node at the given corner.
+    ///
+    /// The handle size is in surface coordinates, so the node keeps a constant size on the screen regardless of the zoom.
+    fn resize_node_bounds(
+        position: ResizeCorner,
+        selection_bounds: Aabb,
+        handle_size: f64,
+        camera: &Camera,
+    ) -> Aabb {
+        let half_extent = handle_size * 0.5 / camera.total_zoom();
+        let center = match position {
+            ResizeCorner::TopLeft => na::point![
+                selection_bounds.mins[0] - half_extent,
+                selection_bounds.mins[1] - half_extent
+            ],
+            ResizeCorner::TopRight => na::point![
+                selection_bounds.maxs[0] + half_extent,
+                selection_bounds.mins[1] - half_extent
+            ],
+            ResizeCorner::BottomLeft => na::point![
+                selection_bounds.mins[0] - half_extent,
+                selection_bounds.maxs[1] + half_extent
+            ],
+            ResizeCorner::BottomRight => na::point![
+                selection_bounds.maxs[0] + half_extent,
+                selection_bounds.maxs[1] + half_extent
+            ],
+        };
+        Aabb::from_half_extents(center, na::Vector2::repeat(half_extent))
     }
 
-    fn rotate_node_sphere(selection_bounds: Aabb, camera: &Camera) -> BoundingSphere {
-        let total_zoom = camera.total_zoom();
+    /// The scale that is applied on the current selection bounds when resizing.
+    ///
+    /// `offset` is the offset of the dragged corner to its start position, pointing outwards of the start bounds.
+    /// With `lock_aspect` the ratio between the width and height of the start bounds is kept constant,
+    /// also when the new extents are clamped to `min_extents`.
+    fn resize_scale(
+        start_bounds: Aabb,
+        selection_bounds: Aabb,
+        offset: na::Vector2<f64>,
+        lock_aspect: bool,
+        min_extents: na::Vector2<f64>,
+    ) -> na::Vector2<f64> {
+        let start_extents = start_bounds.extents();
+        let new_extents = if lock_aspect {
+            let factor = (1.0 + offset.mean() / start_extents.mean())
+                .max(min_extents[0] / start_extents[0])
+                .max(min_extents[1] / start_extents[1]);
+            start_extents * factor
+        } else {
+            (start_extents + offset).maxs(&min_extents)
+        };
+        new_extents.component_div(&selection_bounds.extents())
+    }
+
+    /// The sphere of the rotate node.
+    ///
+    /// The handle size is in surface coordinates, so the node keeps a constant size on the screen regardless of the zoom.
+    fn rotate_node_sphere(
+        selection_bounds: Aabb,
+        handle_size: f64,
+        camera: &Camera,
+    ) -> BoundingSphere {
+        let radius = handle_size * 0.5 / camera.total_zoom();
         let pos = na::point![
-            selection_bounds.maxs[0] + Self::RESIZE_NODE_SIZE[0] / (2.0 * total_zoom),
+            selection_bounds.maxs[0] + radius,
             (selection_bounds.maxs[1] + selection_bounds.mins[1]) * 0.5
         ];
-        BoundingSphere::new(pos, Self::ROTATE_NODE_DIAMETER * 0.5 / total_zoom)
+        BoundingSphere::new(pos, radius)
+    }
+
+    /// Whether the point lies over the current selection, either inside its bounds or on one of its handles.
+    ///
+    /// Used to decide whether pressing at the point modifies the selection instead of starting a new one,
+    /// e.g. to show a move cursor.
+    pub fn hit_selection(&self, point: na::Point2<f64>, engine_view: &EngineView) -> bool {
+        let SelectorState::ModifySelection {
+            selection_bounds, ..
+        } = &self.state
+        else {
+            return false;
+        };
+        let handle_size = engine_view.pens_config.selector_config.handle_size_px();
+
+        selection_bounds.contains_local_point(&point)
+            || Self::rotate_node_sphere(*selection_bounds, handle_size, engine_view.camera)
+                .contains_local_point(&point)
+            || [
+                ResizeCorner::TopLeft,
+                ResizeCorner::TopRight,
+                ResizeCorner::BottomLeft,
+                ResizeCorner::BottomRight,
+            ]
+            .into_iter()
+            .any(|corner| {
+                Self::resize_node_bounds(corner, *selection_bounds, handle_size, engine_view.camera)
+                    .contains_local_point(&point)
+            })
+    }
+
+    /// The cursor hint for the selector, a move or resize cursor when hovering or dragging the selection or its resize
+    /// handles.
+    pub(super) fn cursor_hint(&self, engine_view: &EngineView) -> CursorHint {
+        let SelectorState::ModifySelection {
+            modify_state,
+            selection_bounds,
+            ..
+        } = &self.state
+        else {
+            return CursorHint::Pointer;
+        };
+        let resize_direction = |corner: ResizeCorner| match corner {
+            ResizeCorner::TopLeft => ResizeDirection::NorthWest,
+            ResizeCorner::TopRight => ResizeDirection::NorthEast,
+            ResizeCorner::BottomLeft => ResizeDirection::SouthWest,
+            ResizeCorner::BottomRight => ResizeDirection::SouthEast,
+        };
+
+        match modify_state {
+            ModifyState::Translate { .. } => CursorHint::Move,
+            ModifyState::Resize { from_corner, .. } => {
+                CursorHint::Resize(resize_direction(*from_corner))
+            }
+            ModifyState::Hover(pos) => {
+                let handle_size = engine_view.pens_config.selector_config.handle_size_px();
+                let pos = (*pos).into();
+                if let Some(corner) = [
+                    ResizeCorner::TopLeft,
+                    ResizeCorner::TopRight,
+                    ResizeCorner::BottomLeft,
+                    ResizeCorner::BottomRight,
+                ]
+                .into_iter()
+                .find(|&corner| {
+                    Self::resize_node_bounds(
+                        corner,
+                        *selection_bounds,
+                        handle_size,
+                        engine_view.camera,
+                    )
+                    .contains_local_point(&pos)
+                }) {
+                    CursorHint::Resize(resize_direction(corner))
+                } else if selection_bounds.contains_local_point(&pos) {
+                    CursorHint::Move
+                } else {
+                    CursorHint::Pointer
+                }
+            }
+            ModifyState::Up | ModifyState::Rotate { .. } => CursorHint::Pointer,
+        }
+    }
+
+    /// Set the phase of the dashed outline while selecting, in surface coordinates.
+    ///
+    /// Incrementing it over time produces moving dashes ("marching ants").
+    pub fn set_dash_phase(&mut self, dash_phase: f64) {
+        let pattern_len = Self::SELECTING_DASH_PATTERN.iter().sum::<f64>();
+        self.dash_phase = dash_phase.rem_euclid(pattern_len);
+    }
+
+    fn selecting_stroke_style(&self, total_zoom: f64) -> piet::StrokeStyle {
+        let mut stroke_style = piet::StrokeStyle::new();
+        stroke_style.set_dash_pattern(
+            Self::SELECTING_DASH_PATTERN
+                .into_iter()
+                .map(|x| x / total_zoom)
+                .collect::<Vec<f64>>(),
+        );
+        stroke_style.set_dash_offset(self.dash_phase / total_zoom);
+        stroke_style
     }
 
     fn draw_selection_overlay(
         piet_cx: &mut impl RenderContext,
         selection_bounds: Aabb,
         modify_state: &ModifyState,
+        handle_size: f64,
         camera: &Camera,
     ) -> anyhow::Result<()> {
         piet_cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
         let total_zoom = camera.total_zoom();
 
-        let rotate_node_sphere = Self::rotate_node_sphere(selection_bounds, camera);
+        let rotate_node_sphere = Self::rotate_node_sphere(selection_bounds, handle_size, camera);
         let rotate_node_state = match modify_state {
             ModifyState::Rotate { .. } => PenState::Down,
             ModifyState::Hover(pos) => {
@@ -559,7 +785,7 @@ impl Selector {
         };
 
         let resize_tl_node_bounds =
-            Self::resize_node_bounds(ResizeCorner::TopLeft, selection_bounds, camera);
+            Self::resize_node_bounds(ResizeCorner::TopLeft, selection_bounds, handle_size, camera);
         let resize_tl_node_state = match modify_state {
             ModifyState::Resize {
                 from_corner: ResizeCorner::TopLeft,
@@ -575,8 +801,12 @@ impl Selector {
             _ => PenState::Up,
         };
 
-        let resize_tr_node_bounds =
-            Self::resize_node_bounds(ResizeCorner::TopRight, selection_bounds, camera);
+        let resize_tr_node_bounds = Self::resize_node_bounds(
+            ResizeCorner::TopRight,
+            selection_bounds,
+            handle_size,
+            camera,
+        );
         let resize_tr_node_state = match modify_state {
             ModifyState::Resize {
                 from_corner: ResizeCorner::TopRight,
@@ -592,8 +822,12 @@ impl Selector {
             _ => PenState::Up,
         };
 
-        let resize_bl_node_bounds =
-            Self::resize_node_bounds(ResizeCorner::BottomLeft, selection_bounds, camera);
+        let resize_bl_node_bounds = Self::resize_node_bounds(
+            ResizeCorner::BottomLeft,
+            selection_bounds,
+            handle_size,
+            camera,
+        );
         let resize_bl_node_state = match modify_state {
             ModifyState::Resize {
                 from_corner: ResizeCorner::BottomLeft,
@@ -609,8 +843,12 @@ impl Selector {
             _ => PenState::Up,
         };
 
-        let resize_br_node_bounds =
-            Self::resize_node_bounds(ResizeCorner::BottomRight, selection_bounds, camera);
+        let resize_br_node_bounds = Self::resize_node_bounds(
+            ResizeCorner::BottomRight,
+            selection_bounds,
+            handle_size,
+            camera,
+        );
         let resize_br_node_state = match modify_state {
             ModifyState::Resize {
                 from_corner: ResizeCorner::BottomRight,
@@ -820,3 +1058,271 @@ fn cancel_selection(selection: &[StrokeKey], engine_view: &mut EngineViewMut) ->
     widget_flags.resize = true;
     widget_flags
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ModifyState, ResizeCorner, SelectCombine, Selector, SelectorState};
+    use crate::pens::pensconfig::selectorconfig::SelectorStyle;
+    use crate::pens::pensconfig::SelectorConfig;
+    use crate::pens::PenBehaviour;
+    use crate::store::StrokeKey;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::{Camera, Engine};
+    use approx::assert_relative_eq;
+    use p2d::bounding_volume::Aabb;
+    use p2d::query::PointQuery;
+    use rnote_compose::ext::AabbExt;
+    use rnote_compose::penevent::{ModifierKey, PenEvent};
+    use rnote_compose::penpath::Element;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
+    use std::collections::HashSet;
+    use std::time::Instant;
+
+    /// Selects the first two of three strokes in a row, then drags a rectangle around the last two
+    /// while holding the modifier keys, lifting the pen or cancelling at the end.
+    /// Returns the stroke keys, the resulting selection and the selector.
+    fn marquee_over_existing_selection(
+        modifier_keys: HashSet<ModifierKey>,
+        cancel: bool,
+    ) -> ([StrokeKey; 3], Vec<StrokeKey>, Selector) {
+        let mut engine = Engine::default();
+        engine.pens_config.selector_config.style = SelectorStyle::Rectangle;
+        let keys = [0.0, 20.0, 40.0].map(|x| {
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        na::point![x, 0.0],
+                        na::point![x + 10.0, 10.0],
+                    ))),
+                    Style::default(),
+                )),
+                None,
+            )
+        });
+        engine.store.set_selected_keys(&keys[..2], true);
+        let mut selector = Selector::default();
+        let _ = selector.update_state(&mut engine.view_mut());
+        let now = Instant::now();
+
+        // Without modifiers, the first press outside of the selection only resets it
+        for pos in [
+            na::vector![15.0, 60.0],
+            na::vector![15.0, 60.0],
+            na::vector![55.0, -5.0],
+        ] {
+            let _ = selector.handle_event(
+                PenEvent::Down {
+                    element: Element::new(pos, 0.5),
+                    modifier_keys: modifier_keys.clone(),
+                },
+                now,
+                &mut engine.view_mut(),
+            );
+        }
+        let event = if cancel {
+            PenEvent::Cancel
+        } else {
+            PenEvent::Up {
+                element: Element::new(na::vector![55.0, -5.0], 0.5),
+                modifier_keys,
+            }
+        };
+        let _ = selector.handle_event(event, now, &mut engine.view_mut());
+
+        let mut selection = engine.store.selection_keys_unordered();
+        selection.sort_by_key(|key| keys.iter().position(|k| k == key));
+        (keys, selection, selector)
+    }
+
+    #[test]
+    fn select_combine_with_existing_selection() {
+        let shift = ModifierKey::KeyboardShift;
+        let alt = ModifierKey::KeyboardAlt;
+
+        let (keys, selection, _) = marquee_over_existing_selection(HashSet::new(), false);
+        assert_eq!(selection, vec![keys[1], keys[2]]);
+
+        let modifier_keys = HashSet::from([shift]);
+        assert_eq!(
+            SelectCombine::from_modifier_keys(&modifier_keys),
+            SelectCombine::Add
+        );
+        let (keys, selection, _) = marquee_over_existing_selection(modifier_keys, false);
+        assert_eq!(selection, vec![keys[0], keys[1], keys[2]]);
+
+        let modifier_keys = HashSet::from([alt]);
+        assert_eq!(
+            SelectCombine::from_modifier_keys(&modifier_keys),
+            SelectCombine::Subtract
+        );
+        let (keys, selection, _) = marquee_over_existing_selection(modifier_keys, false);
+        assert_eq!(selection, vec![keys[0]]);
+
+        let modifier_keys = HashSet::from([shift, alt]);
+        assert_eq!(
+            SelectCombine::from_modifier_keys(&modifier_keys),
+            SelectCombine::Intersect
+        );
+        let (keys, selection, _) = marquee_over_existing_selection(modifier_keys, false);
+        assert_eq!(selection, vec![keys[1]]);
+    }
+
+    #[test]
+    fn cancel_combined_selecting_restores_previous_selection() {
+        let (keys, selection, selector) =
+            marquee_over_existing_selection(HashSet::from([ModifierKey::KeyboardShift]), true);
+        assert_eq!(selection, vec![keys[0], keys[1]]);
+        let SelectorState::ModifySelection { selection, .. } = &selector.state else {
+            panic!("the previous selection is not modifiable after cancelling");
+        };
+        assert_eq!(selection.len(), 2);
+
+        // when replacing, the previous selection was already reset on the first press
+        let (_, selection, selector) = marquee_over_existing_selection(HashSet::new(), true);
+        assert!(selection.is_empty());
+        assert!(matches!(selector.state, SelectorState::Idle));
+    }
+
+    #[test]
+    fn brush_select_selects_touched_strokes() {
+        let mut engine = Engine::default();
+        engine.pens_config.selector_config.style = SelectorStyle::BrushSelect;
+        engine
+            .pens_config
+            .selector_config
+            .set_brush_select_radius(5.0);
+        // a row of small squares, the last one is below the dragged path
+        let keys = [
+            na::point![0.0, 0.0],
+            na::point![30.0, 0.0],
+            na::point![60.0, 0.0],
+            na::point![90.0, 40.0],
+        ]
+        .map(|mins| {
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        mins,
+                        mins + na::vector![10.0, 10.0],
+                    ))),
+                    Style::default(),
+                )),
+                None,
+            )
+        });
+        let mut selector = Selector::default();
+        let now = Instant::now();
+
+        // drag above the squares, so that the path only touches them within the radius
+        let path = [
+            na::vector![-10.0, -4.0],
+            na::vector![40.0, -4.0],
+            na::vector![75.0, -4.0],
+        ];
+        for &pos in path.iter() {
+            let _ = selector.handle_event(
+                PenEvent::Down {
+                    element: Element::new(pos, 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                now,
+                &mut engine.view_mut(),
+            );
+        }
+        let _ = selector.handle_event(
+            PenEvent::Up {
+                element: Element::new(path[2], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            now,
+            &mut engine.view_mut(),
+        );
+
+        let mut selection = engine.store.selection_keys_unordered();
+        selection.sort_by_key(|key| keys.iter().position(|k| k == key));
+        assert_eq!(selection, vec![keys[0], keys[1], keys[2]]);
+    }
+
+    #[test]
+    fn handle_hit_is_independent_of_zoom() {
+        let handle_size = SelectorConfig::HANDLE_SIZE_PX_DEFAULT;
+        let selection_bounds = Aabb::new(na::point![100.0, 100.0], na::point![200.0, 200.0]);
+
+        for zoom in [Camera::ZOOM_MIN, 1.0, Camera::ZOOM_MAX] {
+            let camera = Camera::default().with_zoom(zoom);
+            // A point a few pixels on the screen away from the selection corner, inside the handle.
+            let pos = selection_bounds.maxs + na::Vector2::repeat(4.0 / zoom);
+            // A point further away on the screen than the handle extends.
+            let pos_outside = selection_bounds.maxs + na::Vector2::repeat(2.0 * handle_size / zoom);
+
+            let node_bounds = Selector::resize_node_bounds(
+                ResizeCorner::BottomRight,
+                selection_bounds,
+                handle_size,
+                &camera,
+            );
+            assert!(node_bounds.contains_local_point(&pos));
+            assert!(!node_bounds.contains_local_point(&pos_outside));
+        }
+    }
+
+    #[test]
+    fn hit_selection_includes_handles() {
+        let mut engine = Engine::default();
+        let selection_bounds = Aabb::new(na::point![100.0, 100.0], na::point![200.0, 200.0]);
+        let selector = Selector {
+            state: SelectorState::ModifySelection {
+                modify_state: ModifyState::default(),
+                selection: vec![],
+                selection_bounds,
+            },
+            ..Default::default()
+        };
+        assert!(!Selector::default().hit_selection(na::point![150.0, 150.0], &engine.view()));
+
+        for zoom in [0.5, 1.0, 4.0] {
+            engine.camera = Camera::default().with_zoom(zoom);
+            // Just outside the bottom right corner, inside the resize handle
+            let on_handle = selection_bounds.maxs + na::Vector2::repeat(4.0 / zoom);
+            // Further away on the screen than the handles extend
+            let outside = selection_bounds.maxs
+                + na::Vector2::repeat(2.0 * SelectorConfig::HANDLE_SIZE_PX_DEFAULT / zoom);
+
+            assert!(selector.hit_selection(na::point![150.0, 150.0], &engine.view()));
+            assert!(selector.hit_selection(on_handle, &engine.view()));
+            assert!(!selector.hit_selection(outside, &engine.view()));
+        }
+    }
+
+    #[test]
+    fn resize_with_lock_aspect_keeps_ratio() {
+        let start_bounds = Aabb::new(na::point![0.0, 0.0], na::point![200.0, 100.0]);
+        let min_extents = na::Vector2::repeat(2.0);
+        let ratio = |bounds: Aabb| bounds.extents()[0] / bounds.extents()[1];
+
+        // Resizing in multiple steps, with offsets that would otherwise distort the bounds
+        let mut selection_bounds = start_bounds;
+        for offset in [
+            na::vector![50.0, -80.0],
+            na::vector![-30.0, 120.0],
+            na::vector![-500.0, -500.0],
+        ] {
+            let scale =
+                Selector::resize_scale(start_bounds, selection_bounds, offset, true, min_extents);
+            selection_bounds = selection_bounds.scale_non_uniform(scale);
+            assert_relative_eq!(ratio(selection_bounds), ratio(start_bounds));
+        }
+        // clamped to the minimum extents while keeping the ratio
+        assert_relative_eq!(selection_bounds.extents(), na::vector![4.0, 2.0]);
+
+        let scale = Selector::resize_scale(
+            start_bounds,
+            start_bounds,
+            na::vector![50.0, -80.0],
+            false,
+            min_extents,
+        );
+        assert_relative_eq!(scale, na::vector![1.25, 0.2]);
+    }
+}
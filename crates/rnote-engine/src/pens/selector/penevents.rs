@@ -1,10 +1,10 @@
 // Imports
-use super::{ModifyState, ResizeCorner, Selector, SelectorState};
-use crate::engine::EngineViewMut;
+use super::{ModifyState, ResizeCorner, SelectCombine, Selector, SelectorState};
+use crate::engine::{EngineViewMut, RepeatableAction};
 use crate::pens::pensconfig::selectorconfig::SelectorStyle;
-use crate::snap::SnapCorner;
-use crate::store::StrokeKey;
-use crate::{DrawableOnDoc, WidgetFlags};
+use crate::snap::{snap_point, SnapContext, SnapCorner};
+use crate::store::{FillRule, StrokeKey};
+use crate::{DrawableOnDoc, SelectionCollision, WidgetFlags};
 use p2d::bounding_volume::Aabb;
 use p2d::query::PointQuery;
 use rnote_compose::eventresult::{EventPropagation, EventResult};
@@ -26,15 +26,23 @@ impl Selector {
 
         let event_result = match &mut self.state {
             SelectorState::Idle => {
-                // Deselect on start
+                let combine = SelectCombine::from_modifier_keys(&modifier_keys);
                 let selection_keys = engine_view.store.selection_keys_as_rendered();
-                if !selection_keys.is_empty() {
-                    engine_view.store.set_selected_keys(&selection_keys, false);
-                    widget_flags.store_modified = true;
-                }
+                // Deselect on start, unless the new selection is combined with the existing one
+                let prev_selection = if combine == SelectCombine::Replace {
+                    if !selection_keys.is_empty() {
+                        engine_view.store.set_selected_keys(&selection_keys, false);
+                        widget_flags.store_modified = true;
+                    }
+                    vec![]
+                } else {
+                    selection_keys
+                };
 
                 self.state = SelectorState::Selecting {
                     path: vec![element],
+                    combine,
+                    prev_selection,
                 };
 
                 EventResult {
@@ -43,7 +51,7 @@ impl Selector {
                     progress: PenProgress::InProgress,
                 }
             }
-            SelectorState::Selecting { path } => {
+            SelectorState::Selecting { path, .. } => {
                 Self::add_to_select_path(
                     engine_view.pens_config.selector_config.style,
                     path,
@@ -78,6 +86,7 @@ impl Selector {
 
                 match modify_state {
                     ModifyState::Up | ModifyState::Hover(_) => {
+                        let handle_size = engine_view.pens_config.selector_config.handle_size_px();
                         // If we click on another, not-already selected stroke while in separate style or
                         // while pressing Shift, we add it to the selection
                         let key_to_add = engine_view
@@ -102,8 +111,12 @@ impl Selector {
                             {
                                 *selection_bounds = new_bounds;
                             }
-                        } else if Self::rotate_node_sphere(*selection_bounds, engine_view.camera)
-                            .contains_local_point(&element.pos.into())
+                        } else if Self::rotate_node_sphere(
+                            *selection_bounds,
+                            handle_size,
+                            engine_view.camera,
+                        )
+                        .contains_local_point(&element.pos.into())
                         {
                             // clicking on the rotate node
                             let rotation_angle = {
@@ -120,6 +133,7 @@ impl Selector {
                         } else if Self::resize_node_bounds(
                             ResizeCorner::TopLeft,
                             *selection_bounds,
+                            handle_size,
                             engine_view.camera,
                         )
                         .contains_local_point(&element.pos.into())
@@ -133,6 +147,7 @@ impl Selector {
                         } else if Self::resize_node_bounds(
                             ResizeCorner::TopRight,
                             *selection_bounds,
+                            handle_size,
                             engine_view.camera,
                         )
                         .contains_local_point(&element.pos.into())
@@ -146,6 +161,7 @@ impl Selector {
                         } else if Self::resize_node_bounds(
                             ResizeCorner::BottomLeft,
                             *selection_bounds,
+                            handle_size,
                             engine_view.camera,
                         )
                         .contains_local_point(&element.pos.into())
@@ -159,6 +175,7 @@ impl Selector {
                         } else if Self::resize_node_bounds(
                             ResizeCorner::BottomRight,
                             *selection_bounds,
+                            handle_size,
                             engine_view.camera,
                         )
                         .contains_local_point(&element.pos.into())
@@ -180,11 +197,21 @@ impl Selector {
                                 snap_corner,
                             };
                         } else {
-                            // when clicking outside the selection bounds, reset
-                            engine_view.store.set_selected_keys(selection, false);
-                            self.state = SelectorState::Idle;
-
-                            progress = PenProgress::Finished;
+                            let combine = SelectCombine::from_modifier_keys(&modifier_keys);
+                            if combine == SelectCombine::Replace {
+                                // when clicking outside the selection bounds, reset
+                                engine_view.store.set_selected_keys(selection, false);
+                                self.state = SelectorState::Idle;
+
+                                progress = PenProgress::Finished;
+                            } else {
+                                // start a new selection that gets combined with the current one
+                                self.state = SelectorState::Selecting {
+                                    path: vec![element],
+                                    combine,
+                                    prev_selection: std::mem::take(selection),
+                                };
+                            }
                         }
                     }
                     ModifyState::Translate {
@@ -203,10 +230,11 @@ impl Selector {
                             SnapCorner::BottomRight => selection_bounds.maxs.coords,
                         };
 
-                        let offset = engine_view
-                            .document
-                            .snap_position(snap_corner_pos + (element.pos - *current_pos))
-                            - snap_corner_pos;
+                        let offset = snap_point(
+                            snap_corner_pos + (element.pos - *current_pos),
+                            &SnapContext::new(engine_view.document, engine_view.store),
+                        )
+                        .pos - snap_corner_pos;
 
                         if offset.magnitude()
                             > Self::TRANSLATE_OFFSET_THRESHOLD / engine_view.camera.total_zoom()
@@ -271,7 +299,8 @@ impl Selector {
                         start_pos,
                         last_rendered_bounds,
                     } => {
-                        let lock_aspectratio = engine_view
+                        // The modifier toggles the lock for this resize only
+                        let lock_aspect = engine_view
                             .pens_config
                             .selector_config
                             .resize_lock_aspectratio
@@ -301,11 +330,12 @@ impl Selector {
                             ResizeCorner::BottomRight => start_bounds.mins.coords,
                         };
                         let mut offset_to_start = element.pos - *start_pos;
-                        if !lock_aspectratio {
-                            offset_to_start = engine_view
-                                .document
-                                .snap_position(snap_corner_pos + offset_to_start)
-                                - snap_corner_pos;
+                        if !lock_aspect {
+                            offset_to_start = snap_point(
+                                snap_corner_pos + offset_to_start,
+                                &SnapContext::new(engine_view.document, engine_view.store),
+                            )
+                            .pos - snap_corner_pos;
                         }
                         offset_to_start = match from_corner {
                             ResizeCorner::TopLeft => -offset_to_start,
@@ -317,17 +347,15 @@ impl Selector {
                             }
                             ResizeCorner::BottomRight => offset_to_start,
                         };
-                        if lock_aspectratio {
-                            let start_extents = start_bounds.extents();
-                            let start_mean = start_extents.mean();
-                            let offset_mean = offset_to_start.mean();
-                            offset_to_start = start_extents * (offset_mean / start_mean);
-                        }
                         let min_extents = na::Vector2::<f64>::from_element(2.0f64)
                             / engine_view.camera.total_zoom();
-                        let scale = (start_bounds.extents() + offset_to_start)
-                            .maxs(&min_extents)
-                            .component_div(&selection_bounds.extents());
+                        let scale = Selector::resize_scale(
+                            *start_bounds,
+                            *selection_bounds,
+                            offset_to_start,
+                            lock_aspect,
+                            min_extents,
+                        );
 
                         // resize strokes
                         engine_view
@@ -407,18 +435,25 @@ impl Selector {
                 propagate: EventPropagation::Proceed,
                 progress: PenProgress::Idle,
             },
-            SelectorState::Selecting { path } => {
+            SelectorState::Selecting {
+                path,
+                combine,
+                prev_selection,
+            } => {
                 let mut progress = PenProgress::Finished;
 
                 let new_selection = match engine_view.pens_config.selector_config.style {
                     SelectorStyle::Polygon => {
                         if path.len() >= 3 {
-                            engine_view
-                                .store
-                                .strokes_hitboxes_contained_in_path_polygon(
-                                    path,
-                                    engine_view.camera.viewport(),
-                                )
+                            let polygon = path
+                                .iter()
+                                .map(|element| element.pos.into())
+                                .collect::<Vec<na::Point2<f64>>>();
+                            engine_view.store.strokes_in_polygon(
+                                &polygon,
+                                SelectionCollision::Contains,
+                                FillRule::EvenOdd,
+                            )
                         } else {
                             vec![]
                         }
@@ -459,8 +494,23 @@ impl Selector {
                             vec![]
                         }
                     }
+                    SelectorStyle::BrushSelect => engine_view.store.strokes_hitboxes_near_path(
+                        path,
+                        engine_view
+                            .pens_config
+                            .selector_config
+                            .brush_select_radius(),
+                        engine_view.camera.viewport(),
+                    ),
                 };
-                if !new_selection.is_empty() {
+                let new_selection = combine.combine(prev_selection, new_selection);
+                if !prev_selection.is_empty() {
+                    engine_view.store.set_selected_keys(prev_selection, false);
+                    widget_flags.store_modified = true;
+                }
+                if new_selection.is_empty() {
+                    self.state = SelectorState::Idle;
+                } else {
                     engine_view.store.set_selected_keys(&new_selection, true);
                     widget_flags.store_modified = true;
                     widget_flags.deselect_color_setters = true;
@@ -487,31 +537,60 @@ impl Selector {
                 selection,
                 selection_bounds,
             } => {
-                match modify_state {
-                    ModifyState::Translate { .. }
-                    | ModifyState::Rotate { .. }
-                    | ModifyState::Resize { .. } => {
-                        engine_view.store.update_geometry_for_strokes(selection);
-                        widget_flags |= engine_view
-                            .document
-                            .resize_autoexpand(engine_view.store, engine_view.camera);
-                        engine_view.store.regenerate_rendering_in_viewport_threaded(
-                            engine_view.tasks_tx.clone(),
-                            false,
-                            engine_view.camera.viewport(),
-                            engine_view.camera.image_scale(),
-                        );
-
-                        if let Some(new_bounds) = engine_view.store.bounds_for_strokes(selection) {
-                            *selection_bounds = new_bounds;
-                        }
-                        // We would need to update bounds held in the modify state, but since we transition into either
-                        // the up or hover state anyway that is not actually needed.
+                let action = match modify_state {
+                    ModifyState::Translate {
+                        start_pos,
+                        current_pos,
+                        ..
+                    } => Some(RepeatableAction::Translate {
+                        offset: *current_pos - *start_pos,
+                    }),
+                    ModifyState::Rotate {
+                        start_rotation_angle,
+                        current_rotation_angle,
+                        ..
+                    } => Some(RepeatableAction::Rotate {
+                        angle: *current_rotation_angle - *start_rotation_angle,
+                    }),
+                    ModifyState::Resize {
+                        from_corner,
+                        start_bounds,
+                        ..
+                    } => Some(RepeatableAction::Resize {
+                        scale: selection_bounds
+                            .extents()
+                            .component_div(&start_bounds.extents()),
+                        // The pivot is the corner opposite of the one that was dragged
+                        pivot: match from_corner {
+                            ResizeCorner::TopLeft => na::vector![1.0, 1.0],
+                            ResizeCorner::TopRight => na::vector![0.0, 1.0],
+                            ResizeCorner::BottomLeft => na::vector![1.0, 0.0],
+                            ResizeCorner::BottomRight => na::vector![0.0, 0.0],
+                        },
+                    }),
+                    ModifyState::Up | ModifyState::Hover(_) => None,
+                };
 
-                        widget_flags |= engine_view.store.record(Instant::now());
-                        widget_flags.store_modified = true;
+                if let Some(action) = action {
+                    engine_view.store.update_geometry_for_strokes(selection);
+                    widget_flags |= engine_view
+                        .document
+                        .resize_autoexpand(engine_view.store, engine_view.camera);
+                    engine_view.store.regenerate_rendering_in_viewport_threaded(
+                        engine_view.tasks_tx.clone(),
+                        false,
+                        engine_view.camera.viewport(),
+                        engine_view.camera.image_scale(),
+                    );
+
+                    if let Some(new_bounds) = engine_view.store.bounds_for_strokes(selection) {
+                        *selection_bounds = new_bounds;
                     }
-                    _ => {}
+                    // We would need to update bounds held in the modify state, but since we transition into either
+                    // the up or hover state anyway that is not actually needed.
+
+                    widget_flags |= engine_view.store.record_w_action(Instant::now(), action);
+                    widget_flags.store_modified = true;
                 }
 
                 *modify_state = if selector_bounds
@@ -720,12 +799,24 @@ impl Selector {
                 propagate: EventPropagation::Proceed,
                 progress: PenProgress::Idle,
             },
-            SelectorState::Selecting { .. } => {
-                self.state = SelectorState::Idle;
+            SelectorState::Selecting { prev_selection, .. } => {
+                // Restore the selection that is combined with, it is still selected
+                let mut progress = PenProgress::Finished;
+                let prev_selection = std::mem::take(prev_selection);
+                if let Some(prev_bounds) = engine_view.store.bounds_for_strokes(&prev_selection) {
+                    self.state = SelectorState::ModifySelection {
+                        modify_state: ModifyState::default(),
+                        selection: prev_selection,
+                        selection_bounds: prev_bounds,
+                    };
+                    progress = PenProgress::InProgress;
+                } else {
+                    self.state = SelectorState::Idle;
+                }
                 EventResult {
                     handled: true,
                     propagate: EventPropagation::Stop,
-                    progress: PenProgress::Finished,
+                    progress,
                 }
             }
             SelectorState::ModifySelection { selection, .. } => {
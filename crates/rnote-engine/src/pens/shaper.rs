@@ -1,17 +1,21 @@
 // Imports
+use super::dwell::DwellDetector;
+use super::smoothing::InputSmoother;
 use super::PenBehaviour;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
+use crate::snap::{snap_point, SnapContext, SnapIndicator};
 use crate::strokes::ShapeStroke;
 use crate::strokes::Stroke;
 use crate::{DrawableOnDoc, WidgetFlags};
-use p2d::bounding_volume::Aabb;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
 use rnote_compose::builders::buildable::{Buildable, BuilderCreator, BuilderProgress};
 use rnote_compose::builders::{ArrowBuilder, GridBuilder, PolygonBuilder, PolylineBuilder};
 use rnote_compose::builders::{
     CoordSystem2DBuilder, CoordSystem3DBuilder, CubBezBuilder, EllipseBuilder, FociEllipseBuilder,
-    LineBuilder, QuadBezBuilder, QuadrantCoordSystem2DBuilder, RectangleBuilder, ShapeBuilderType,
+    FreehandBezierBuilder, LineBuilder, QuadBezBuilder, QuadrantCoordSystem2DBuilder,
+    RectangleBuilder, ShapeBuilderType,
 };
 use rnote_compose::eventresult::{EventPropagation, EventResult};
 use rnote_compose::penevent::{KeyboardKey, ModifierKey, PenEvent, PenProgress};
@@ -24,18 +28,26 @@ enum ShaperState {
     Idle,
     BuildShape {
         builder: Box<dyn Buildable<Emit = Shape>>,
+        /// The snapped start position of the shape.
+        start: na::Vector2<f64>,
     },
 }
 
 #[derive(Debug)]
 pub struct Shaper {
     state: ShaperState,
+    smoother: InputSmoother,
+    dwell: DwellDetector,
+    snap_indicator: SnapIndicator,
 }
 
 impl Default for Shaper {
     fn default() -> Self {
         Self {
             state: ShaperState::Idle,
+            smoother: InputSmoother::default(),
+            dwell: DwellDetector::default(),
+            snap_indicator: SnapIndicator::default(),
         }
     }
 }
@@ -64,17 +76,80 @@ impl PenBehaviour for Shaper {
         engine_view: &mut EngineViewMut,
     ) -> (EventResult<PenProgress>, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
+        let event = self
+            .smoother
+            .smooth_event(event, engine_view.pens_config.smoothing(PenStyle::Shaper));
+
+        // Dwelling with the pen commits the shape as if the pen was released
+        let Some(event) = self.dwell.handle_event(
+            event,
+            now,
+            engine_view.pens_config.shaper_config.dwell_commit,
+            DwellDetector::RADIUS_PX / engine_view.camera.total_zoom(),
+        ) else {
+            return (
+                EventResult {
+                    handled: true,
+                    propagate: EventPropagation::Stop,
+                    progress: self.dwell_committed_progress(),
+                },
+                widget_flags,
+            );
+        };
+
+        let (event_result, wf) = self.handle_shape_event(event, now, engine_view);
+        widget_flags |= wf;
+
+        (event_result, widget_flags)
+    }
+
+    fn handle_frame_tick(
+        &mut self,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (Option<PenProgress>, WidgetFlags) {
+        let Some(event) = self
+            .dwell
+            .handle_frame_tick(now, engine_view.pens_config.shaper_config.dwell_commit)
+        else {
+            return (None, WidgetFlags::default());
+        };
+        let (event_result, widget_flags) = self.handle_shape_event(event, now, engine_view);
+
+        (Some(event_result.progress), widget_flags)
+    }
+}
+
+impl Shaper {
+    /// Handle the event after the dwell detection.
+    fn handle_shape_event(
+        &mut self,
+        event: PenEvent,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (EventResult<PenProgress>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
 
         let event_result = match (&mut self.state, event) {
-            (ShaperState::Idle, PenEvent::Down { element, .. }) => {
+            (ShaperState::Idle, PenEvent::Down { mut element, .. }) => {
                 engine_view.pens_config.shaper_config.new_style_seeds();
 
+                let snap_result = snap_point(
+                    element.pos,
+                    &SnapContext::new(engine_view.document, engine_view.store),
+                );
+                element.pos = snap_result.pos;
+                self.snap_indicator.update(snap_result);
+
                 self.state = ShaperState::BuildShape {
                     builder: new_builder(
                         engine_view.pens_config.shaper_config.builder_type,
                         element,
                         now,
+                        engine_view.pens_config.shaper_config.fit_tolerance()
+                            / engine_view.camera.total_zoom(),
                     ),
+                    start: element.pos,
                 };
 
                 EventResult {
@@ -97,7 +172,24 @@ impl PenBehaviour for Shaper {
                     progress: PenProgress::Finished,
                 }
             }
-            (ShaperState::BuildShape { builder }, event) => {
+            (ShaperState::BuildShape { builder, start }, mut event) => {
+                if let PenEvent::Down { element, .. } | PenEvent::Up { element, .. } = &mut event {
+                    let mut snap_context =
+                        SnapContext::new(engine_view.document, engine_view.store);
+                    // Lines are snapped to angle steps from their start when snapping is enabled
+                    if engine_view.document.snap_positions
+                        && matches!(
+                            engine_view.pens_config.shaper_config.builder_type,
+                            ShapeBuilderType::Line | ShapeBuilderType::Arrow
+                        )
+                    {
+                        snap_context = snap_context.with_angle_origin(*start);
+                    }
+                    let snap_result = snap_point(element.pos, &snap_context);
+                    element.pos = snap_result.pos;
+                    self.snap_indicator.update(snap_result);
+                }
+
                 // Use Ctrl to temporarily enable/disable constraints when the switch is off/on
                 let mut constraints = engine_view.pens_config.shaper_config.constraints.clone();
                 constraints.enabled = match event {
@@ -128,7 +220,11 @@ impl PenBehaviour for Shaper {
                             .gen_style_for_current_options();
                         let shapes_emitted = !shapes.is_empty();
 
-                        for shape in shapes {
+                        for mut shape in shapes {
+                            engine_view
+                                .pens_config
+                                .shaper_config
+                                .apply_corner_radius(&mut shape);
                             let key = engine_view.store.insert_stroke(
                                 Stroke::ShapeStroke(ShapeStroke::new(shape, style.clone())),
                                 None,
@@ -154,7 +250,11 @@ impl PenBehaviour for Shaper {
                             .gen_style_for_current_options();
 
                         let shapes_emitted = !shapes.is_empty();
-                        for shape in shapes {
+                        for mut shape in shapes {
+                            engine_view
+                                .pens_config
+                                .shaper_config
+                                .apply_corner_radius(&mut shape);
                             let key = engine_view.store.insert_stroke(
                                 Stroke::ShapeStroke(ShapeStroke::new(shape, style.clone())),
                                 None,
@@ -202,6 +302,14 @@ impl PenBehaviour for Shaper {
 
         (event_result, widget_flags)
     }
+
+    /// The progress while waiting for the pen to be released after the shape was committed by dwelling.
+    fn dwell_committed_progress(&self) -> PenProgress {
+        match self.state {
+            ShaperState::Idle => PenProgress::Idle,
+            ShaperState::BuildShape { .. } => PenProgress::InProgress,
+        }
+    }
 }
 
 impl DrawableOnDoc for Shaper {
@@ -213,8 +321,14 @@ impl DrawableOnDoc for Shaper {
 
         match &self.state {
             ShaperState::Idle => None,
-            ShaperState::BuildShape { builder } => {
-                builder.bounds(&style, engine_view.camera.total_zoom())
+            ShaperState::BuildShape { builder, .. } => {
+                let bounds = builder.bounds(&style, engine_view.camera.total_zoom());
+                match self.snap_indicator.bounds_on_doc(engine_view) {
+                    Some(indicator_bounds) => Some(
+                        bounds.map_or(indicator_bounds, |bounds| bounds.merged(&indicator_bounds)),
+                    ),
+                    None => bounds,
+                }
             }
         }
     }
@@ -232,8 +346,9 @@ impl DrawableOnDoc for Shaper {
 
         match &self.state {
             ShaperState::Idle => {}
-            ShaperState::BuildShape { builder } => {
-                builder.draw_styled(cx, &style, engine_view.camera.total_zoom())
+            ShaperState::BuildShape { builder, .. } => {
+                builder.draw_styled(cx, &style, engine_view.camera.total_zoom());
+                self.snap_indicator.draw_on_doc(cx, engine_view)?;
             }
         }
 
@@ -246,6 +361,7 @@ fn new_builder(
     builder_type: ShapeBuilderType,
     element: Element,
     now: Instant,
+    fit_tolerance: f64,
 ) -> Box<dyn Buildable<Emit = Shape>> {
     match builder_type {
         ShapeBuilderType::Arrow => Box::new(ArrowBuilder::start(element, now)),
@@ -263,5 +379,104 @@ fn new_builder(
         ShapeBuilderType::CubBez => Box::new(CubBezBuilder::start(element, now)),
         ShapeBuilderType::Polyline => Box::new(PolylineBuilder::start(element, now)),
         ShapeBuilderType::Polygon => Box::new(PolygonBuilder::start(element, now)),
+        ShapeBuilderType::FreehandBezier => {
+            Box::new(FreehandBezierBuilder::start(element, now).with_fit_tolerance(fit_tolerance))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    #[test]
+    fn dwell_commits_shape() {
+        let mut engine = Engine::default();
+        engine.pens_config.shaper_config.builder_type = ShapeBuilderType::Line;
+        engine.pens_config.shaper_config.dwell_commit = Some(Duration::from_millis(500));
+        let mut shaper = Shaper::default();
+        let start = Instant::now();
+        let down = |pos: na::Vector2<f64>| PenEvent::Down {
+            element: Element::new(pos, 0.5),
+            modifier_keys: HashSet::new(),
+        };
+
+        for (pos, millis) in [
+            (na::vector![0.0, 0.0], 0),
+            (na::vector![100.0, 0.0], 50),
+            // Holding still, with slight jitter
+            (na::vector![100.5, 0.5], 300),
+        ] {
+            let (result, _) = shaper.handle_event(
+                down(pos),
+                start + Duration::from_millis(millis),
+                &mut engine.view_mut(),
+            );
+            assert_eq!(result.progress, PenProgress::InProgress);
+        }
+        assert!(engine.store.stroke_keys_as_rendered().is_empty());
+
+        let (result, _) = shaper.handle_event(
+            down(na::vector![100.2, 0.0]),
+            start + Duration::from_millis(700),
+            &mut engine.view_mut(),
+        );
+        assert_eq!(result.progress, PenProgress::Finished);
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 1);
+
+        // Further motion until the pen is released does not start a new shape
+        let (result, _) = shaper.handle_event(
+            down(na::vector![150.0, 0.0]),
+            start + Duration::from_millis(800),
+            &mut engine.view_mut(),
+        );
+        assert_eq!(result.progress, PenProgress::Idle);
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 1);
+    }
+
+    #[test]
+    fn dwell_commits_shape_without_further_motion() {
+        let mut engine = Engine::default();
+        engine.pens_config.shaper_config.builder_type = ShapeBuilderType::Line;
+        engine.pens_config.shaper_config.dwell_commit = Some(Duration::from_millis(500));
+        let mut shaper = Shaper::default();
+        let start = Instant::now();
+
+        for (pos, millis) in [(na::vector![0.0, 0.0], 0), (na::vector![100.0, 0.0], 50)] {
+            let _ = shaper.handle_event(
+                PenEvent::Down {
+                    element: Element::new(pos, 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                start + Duration::from_millis(millis),
+                &mut engine.view_mut(),
+            );
+        }
+
+        // The pen is held perfectly still, so only display frames arrive
+        let (progress, _) =
+            shaper.handle_frame_tick(start + Duration::from_millis(300), &mut engine.view_mut());
+        assert_eq!(progress, None);
+        assert!(engine.store.stroke_keys_as_rendered().is_empty());
+
+        let (progress, _) =
+            shaper.handle_frame_tick(start + Duration::from_millis(600), &mut engine.view_mut());
+        assert_eq!(progress, Some(PenProgress::Finished));
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 1);
+
+        // The release afterwards does not start a new shape
+        let (result, _) = shaper.handle_event(
+            PenEvent::Up {
+                element: Element::new(na::vector![100.0, 0.0], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            start + Duration::from_millis(700),
+            &mut engine.view_mut(),
+        );
+        assert_eq!(result.progress, PenProgress::Idle);
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 1);
     }
 }
@@ -0,0 +1,122 @@
+// Imports
+use rnote_compose::penevent::PenEvent;
+use rnote_compose::penpath::Element;
+
+/// Smooths the input positions of pen events with an exponential moving average.
+///
+/// The smoothing factor is in range [0.0, [Self::SMOOTHING_MAX]], where 0.0 passes the input through unchanged
+/// and higher values let the output follow the input more slowly.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InputSmoother {
+    prev: Option<Element>,
+}
+
+impl InputSmoother {
+    /// The maximum smoothing factor. Higher values would let the output lag too much behind the input.
+    pub(crate) const SMOOTHING_MAX: f64 = 0.95;
+
+    /// Smooths the element of down events.
+    ///
+    /// Up events keep their raw position, so that the stroke ends where the pen was lifted.
+    /// The smoothing starts over after an up or cancel event.
+    pub(crate) fn smooth_event(&mut self, mut event: PenEvent, smoothing: f64) -> PenEvent {
+        match &mut event {
+            PenEvent::Down { element, .. } => {
+                *element = self.smooth_element(*element, smoothing);
+            }
+            PenEvent::Up { .. } | PenEvent::Cancel => {
+                self.prev = None;
+            }
+            PenEvent::Proximity { .. } | PenEvent::KeyPressed { .. } | PenEvent::Text { .. } => {}
+        }
+        event
+    }
+
+    fn smooth_element(&mut self, element: Element, smoothing: f64) -> Element {
+        let smoothing = smoothing.clamp(0.0, Self::SMOOTHING_MAX);
+        let smoothed = match self.prev {
+            Some(prev) => Element {
+                pos: prev.pos + (element.pos - prev.pos) * (1.0 - smoothing),
+                ..element
+            },
+            None => element,
+        };
+        self.prev = Some(smoothed);
+        smoothed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputSmoother;
+    use crate::pens::{PenStyle, PensConfig};
+    use approx::assert_relative_eq;
+    use rnote_compose::penevent::PenEvent;
+    use rnote_compose::penpath::Element;
+
+    fn smoothed_pos(smoothing: f64) -> na::Vector2<f64> {
+        let mut smoother = InputSmoother::default();
+        let mut last = na::Vector2::zeros();
+        for x in [0.0, 10.0, 20.0] {
+            let event = smoother.smooth_event(
+                PenEvent::Down {
+                    element: Element::new(na::vector![x, 0.0], 0.5),
+                    modifier_keys: Default::default(),
+                },
+                smoothing,
+            );
+            if let PenEvent::Down { element, .. } = event {
+                last = element.pos;
+            }
+        }
+        last
+    }
+
+    #[test]
+    fn smoothing_depends_on_pen_style() {
+        let mut pens_config = PensConfig::default();
+
+        // Shapers are not smoothed by default
+        assert_relative_eq!(
+            smoothed_pos(pens_config.smoothing(PenStyle::Shaper)),
+            na::vector![20.0, 0.0]
+        );
+        // Brushes are smoothed, so the output lags behind the input
+        assert!(smoothed_pos(pens_config.smoothing(PenStyle::Brush))[0] < 20.0);
+
+        pens_config.shaper_config.set_smoothing(0.5);
+        assert_relative_eq!(
+            smoothed_pos(pens_config.smoothing(PenStyle::Shaper)),
+            na::vector![12.5, 0.0]
+        );
+        assert_relative_eq!(pens_config.smoothing(PenStyle::Selector), 0.0);
+    }
+
+    #[test]
+    fn up_keeps_raw_position_and_resets() {
+        let mut smoother = InputSmoother::default();
+        let down = |x: f64| PenEvent::Down {
+            element: Element::new(na::vector![x, 0.0], 0.5),
+            modifier_keys: Default::default(),
+        };
+        let _ = smoother.smooth_event(down(0.0), 0.5);
+        let _ = smoother.smooth_event(down(10.0), 0.5);
+
+        let PenEvent::Up { element, .. } = smoother.smooth_event(
+            PenEvent::Up {
+                element: Element::new(na::vector![20.0, 0.0], 0.5),
+                modifier_keys: Default::default(),
+            },
+            0.5,
+        ) else {
+            panic!("smoothing changed the event type");
+        };
+        assert_relative_eq!(element.pos, na::vector![20.0, 0.0]);
+
+        // the next stroke does not follow the previous one
+        let PenEvent::Down { element, .. } = smoother.smooth_event(down(100.0), 0.5) else {
+            panic!("smoothing changed the event type");
+        };
+        assert_relative_eq!(element.pos, na::vector![100.0, 0.0]);
+    }
+}
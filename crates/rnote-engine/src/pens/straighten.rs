@@ -0,0 +1,154 @@
+// Imports
+use rnote_compose::penevent::PenEvent;
+use rnote_compose::penpath::Element;
+use std::time::Instant;
+
+/// Straightens the input positions of pen events progressively when drawing slowly and steadily.
+///
+/// The positions are pulled towards the line from an anchor through the average of the input positions, the more the
+/// slower the pen moves. Fast input stays freehand. It is applied to the input before it becomes part of the stroke, so
+/// already committed geometry is never modified, and the positions are never moved further than
+/// [Self::MAX_DEVIATION]. When the pen deviates further from the line, the line starts over at the current position.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InputStraightener {
+    prev: Option<(na::Vector2<f64>, Instant)>,
+    anchor: Option<na::Vector2<f64>>,
+    /// The sum and count of the input positions since the anchor.
+    positions_sum: na::Vector2<f64>,
+    positions_count: usize,
+    direction: Option<na::Vector2<f64>>,
+}
+
+impl InputStraightener {
+    /// Below this velocity the input gets straightened, in document coordinates per second.
+    pub(crate) const SLOW_VELOCITY: f64 = 250.0;
+    /// The maximum distance that the input positions are moved, in document coordinates.
+    pub(crate) const MAX_DEVIATION: f64 = 6.0;
+    /// The distance of the average input position from the anchor after which the line direction is determined, in
+    /// document coordinates.
+    const DIRECTION_DIST: f64 = 12.0;
+
+    /// Straightens the element of down and up events.
+    ///
+    /// The straightening starts over after an up or cancel event. Events pass unchanged when not enabled.
+    pub(crate) fn straighten_event(
+        &mut self,
+        mut event: PenEvent,
+        now: Instant,
+        enabled: bool,
+    ) -> PenEvent {
+        if !enabled {
+            *self = Self::default();
+            return event;
+        }
+        match &mut event {
+            PenEvent::Down { element, .. } => {
+                *element = self.straighten_element(*element, now);
+            }
+            PenEvent::Up { element, .. } => {
+                *element = self.straighten_element(*element, now);
+                *self = Self::default();
+            }
+            PenEvent::Cancel => {
+                *self = Self::default();
+            }
+            PenEvent::Proximity { .. } | PenEvent::KeyPressed { .. } | PenEvent::Text { .. } => {}
+        }
+        event
+    }
+
+    fn straighten_element(&mut self, element: Element, now: Instant) -> Element {
+        let pos = element.pos;
+        let velocity = match self.prev.replace((pos, now)) {
+            Some((prev_pos, prev_time)) => {
+                let dt = now.saturating_duration_since(prev_time).as_secs_f64();
+                if dt > 0.0 {
+                    (pos - prev_pos).magnitude() / dt
+                } else {
+                    f64::INFINITY
+                }
+            }
+            None => f64::INFINITY,
+        };
+        let anchor = *self.anchor.get_or_insert(pos);
+        let mut straightened = element;
+        if let Some(direction) = self.direction {
+            // The position projected onto the line
+            let projected = anchor + direction * (pos - anchor).dot(&direction);
+            let deviation = projected - pos;
+            if deviation.magnitude() > Self::MAX_DEVIATION {
+                // The pen clearly leaves the line, so start a new one from here
+                self.restart(pos);
+            } else {
+                let blend = (1.0 - velocity / Self::SLOW_VELOCITY).clamp(0.0, 1.0);
+                straightened.pos = pos + deviation * blend;
+            }
+        }
+        self.update_direction(pos);
+        straightened
+    }
+
+    fn restart(&mut self, anchor: na::Vector2<f64>) {
+        self.anchor = Some(anchor);
+        self.positions_sum = na::Vector2::zeros();
+        self.positions_count = 0;
+        self.direction = None;
+    }
+
+    /// Updates the line direction with the raw input position. Averaging keeps the direction steady with wobbly input.
+    fn update_direction(&mut self, pos: na::Vector2<f64>) {
+        let Some(anchor) = self.anchor else {
+            return;
+        };
+        self.positions_sum += pos;
+        self.positions_count += 1;
+        let offset = self.positions_sum / self.positions_count as f64 - anchor;
+        if offset.magnitude() >= Self::DIRECTION_DIST {
+            self.direction = Some(offset.normalize());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputStraightener;
+    use rnote_compose::penevent::PenEvent;
+    use rnote_compose::penpath::Element;
+    use std::time::{Duration, Instant};
+
+    /// The maximum distance of the straightened positions from the x-axis, for a wobbly line along it drawn with the
+    /// given interval between the input elements.
+    fn max_wobble(interval: Duration) -> f64 {
+        let mut straightener = InputStraightener::default();
+        let start = Instant::now();
+        (0..100)
+            .filter_map(|i| {
+                let raw = na::vector![i as f64 * 2.0, (i as f64 * 0.7).sin() * 2.0];
+                let event = straightener.straighten_event(
+                    PenEvent::Down {
+                        element: Element::new(raw, 0.5),
+                        modifier_keys: Default::default(),
+                    },
+                    start + interval * i,
+                    true,
+                );
+                let PenEvent::Down { element, .. } = event else {
+                    return None;
+                };
+                assert!((element.pos - raw).magnitude() <= InputStraightener::MAX_DEVIATION);
+                // skip the elements before the line direction is determined
+                (i >= 20).then_some(element.pos[1].abs())
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn slow_strokes_are_straighter_than_fast_ones() {
+        // 2.0 units every 80ms is 25 units per second
+        let slow = max_wobble(Duration::from_millis(80));
+        // 2.0 units every 2ms is 1000 units per second
+        let fast = max_wobble(Duration::from_millis(2));
+        assert!(slow < 0.5 * fast);
+        assert!(fast > 1.5);
+    }
+}
@@ -3,14 +3,18 @@ use super::pensconfig::toolsconfig::ToolStyle;
 use super::PenBehaviour;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
+use crate::snap::{snap_point, SnapContext};
 use crate::store::StrokeKey;
+use crate::strokes::Stroke;
 use crate::{Camera, DrawableOnDoc, WidgetFlags};
-use p2d::bounding_volume::Aabb;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use p2d::query::PointQuery;
 use piet::RenderContext;
-use rnote_compose::color;
 use rnote_compose::eventresult::{EventPropagation, EventResult};
 use rnote_compose::ext::{AabbExt, Vector2Ext};
 use rnote_compose::penevent::{PenEvent, PenProgress};
+use rnote_compose::shapes::Shapeable;
+use rnote_compose::{color, Color};
 use std::time::Instant;
 
 #[derive(Clone, Debug)]
@@ -257,6 +261,250 @@ impl DrawableOnDoc for ZoomTool {
     }
 }
 
+/// Blends the colors of the strokes along the drag path, pushing them in the drag direction.
+///
+/// The colors of the strokes below the tool are picked up and carried along, while the carried color is blended
+/// into the strokes that are passed. The blending falls off with the distance of the strokes to the drag path.
+/// Strokes have a single color, so they get recolored as a whole.
+#[derive(Clone, Debug, Default)]
+pub struct SmudgeTool {
+    pub pos: na::Vector2<f64>,
+    carried_color: Option<Color>,
+    radius: f64,
+}
+
+impl SmudgeTool {
+    const OUTLINE_WIDTH: f64 = 1.5;
+    const OUTLINE_COLOR: piet::Color = color::GNOME_DARKS[3].with_a8(240);
+    /// The distance between the smudged positions along the drag path in relation to the radius.
+    const PATH_STEP_FACTOR: f64 = 0.5;
+
+    /// Start smudging at the position, picking up the colors below the tool.
+    fn start(&mut self, pos: na::Vector2<f64>, engine_view: &mut EngineViewMut) -> WidgetFlags {
+        self.carried_color = None;
+        let (_, widget_flags) = self.smudge_at(pos, engine_view);
+        widget_flags
+    }
+
+    /// Smudge the strokes along the drag path from the previous to the new position.
+    ///
+    /// Returns the keys of the strokes that got recolored.
+    fn smudge_along(
+        &mut self,
+        pos: na::Vector2<f64>,
+        engine_view: &mut EngineViewMut,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let radius = engine_view
+            .pens_config
+            .tools_config
+            .smudge_tool_config
+            .radius
+            .max(0.0);
+        let start = self.pos;
+        let step = (radius * Self::PATH_STEP_FACTOR).max(1.0);
+        let n_steps = ((pos - start).magnitude() / step).ceil().max(1.0) as usize;
+
+        let mut keys = Vec::new();
+        for i in 1..=n_steps {
+            let (smudged, wf) = self.smudge_at(
+                start + (pos - start) * (i as f64 / n_steps as f64),
+                engine_view,
+            );
+            widget_flags |= wf;
+            for key in smudged {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        (keys, widget_flags)
+    }
+
+    /// Smudge the strokes below the tool at the position.
+    ///
+    /// Returns the keys of the strokes that got recolored.
+    fn smudge_at(
+        &mut self,
+        pos: na::Vector2<f64>,
+        engine_view: &mut EngineViewMut,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let config = &engine_view.pens_config.tools_config.smudge_tool_config;
+        let (radius, strength) = (config.radius.max(0.0), config.strength.clamp(0.0, 1.0));
+        self.pos = pos;
+        self.radius = radius;
+
+        let bounds = Aabb::from_half_extents(pos.into(), na::Vector2::repeat(radius));
+        let underlying = engine_view
+            .store
+            .stroke_keys_as_rendered_intersecting_bounds(bounds)
+            .into_iter()
+            .filter_map(|key| {
+                let stroke = engine_view.store.get_stroke_ref(key)?;
+                let distance = stroke
+                    .hitboxes()
+                    .iter()
+                    .map(|hitbox| hitbox.distance_to_local_point(&pos.into(), true))
+                    .reduce(f64::min)?;
+                if distance > radius {
+                    return None;
+                }
+                // Strokes close to the tool center are blended the most
+                let weight = if radius > 0.0 {
+                    1.0 - distance / radius
+                } else {
+                    1.0
+                };
+                Some((key, Self::stroke_color(stroke)?, weight))
+            })
+            .collect::<Vec<(StrokeKey, Color, f64)>>();
+        let total_weight = underlying.iter().map(|(_, _, weight)| weight).sum::<f64>();
+        if underlying.is_empty() || total_weight <= 0.0 {
+            return (vec![], widget_flags);
+        }
+
+        // The average of the underlying colors, weighted by their distance
+        let sampled =
+            underlying
+                .iter()
+                .fold(Color::new(0.0, 0.0, 0.0, 0.0), |acc, (_, color, weight)| {
+                    let w = weight / total_weight;
+                    Color::new(
+                        acc.r + color.r * w,
+                        acc.g + color.g * w,
+                        acc.b + color.b * w,
+                        acc.a + color.a * w,
+                    )
+                });
+
+        let Some(carried) = self.carried_color else {
+            self.carried_color = Some(sampled);
+            return (vec![], widget_flags);
+        };
+        let keys = underlying
+            .iter()
+            .map(|&(key, color, weight)| {
+                widget_flags |= engine_view
+                    .store
+                    .change_stroke_colors(&[key], Self::mix(color, carried, strength * weight));
+                key
+            })
+            .collect::<Vec<StrokeKey>>();
+        let max_weight = underlying
+            .iter()
+            .map(|(_, _, weight)| *weight)
+            .fold(0.0, f64::max);
+        self.carried_color = Some(Self::mix(carried, sampled, strength * max_weight));
+
+        (keys, widget_flags)
+    }
+
+    fn stroke_color(stroke: &Stroke) -> Option<Color> {
+        match stroke {
+            Stroke::BrushStroke(brushstroke) => brushstroke.style.stroke_color(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.style.stroke_color(),
+            Stroke::TextStroke(textstroke) => Some(textstroke.text_style.color),
+            Stroke::VectorImage(_) | Stroke::BitmapImage(_) => None,
+        }
+    }
+
+    fn mix(color: Color, other: Color, t: f64) -> Color {
+        Color::new(
+            color.r + (other.r - color.r) * t,
+            color.g + (other.g - color.g) * t,
+            color.b + (other.b - color.b) * t,
+            color.a + (other.a - color.a) * t,
+        )
+    }
+}
+
+impl DrawableOnDoc for SmudgeTool {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        Some(Aabb::from_half_extents(
+            self.pos.into(),
+            na::Vector2::repeat(
+                self.radius + Self::OUTLINE_WIDTH * 0.5 / engine_view.camera.total_zoom(),
+            ),
+        ))
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+        let circle = kurbo::Circle::new(self.pos.to_kurbo_point(), self.radius);
+        if let Some(carried_color) = self.carried_color {
+            cx.fill(circle, &Into::<piet::Color>::into(carried_color));
+        }
+        cx.stroke(
+            circle,
+            &Self::OUTLINE_COLOR,
+            Self::OUTLINE_WIDTH / engine_view.camera.total_zoom(),
+        );
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+/// Drags a rectangle that captures a region of the document, which can then be exported.
+#[derive(Clone, Debug, Default)]
+pub struct CropTool {
+    pub start: na::Vector2<f64>,
+    pub current: na::Vector2<f64>,
+}
+
+impl CropTool {
+    /// The minimum extents of a captured region.
+    const MIN_EXTENTS: f64 = 1.0;
+    const OUTLINE_WIDTH: f64 = 1.5;
+    const OUTLINE_DASH_PATTERN: [f64; 2] = [9.0, 6.0];
+    const OUTLINE_COLOR: piet::Color = color::GNOME_BLUES[3];
+    const FILL_COLOR: piet::Color = color::GNOME_BLUES[1].with_a8(23);
+
+    /// The currently dragged region.
+    fn region(&self) -> Aabb {
+        Aabb::new_positive(self.start.into(), self.current.into())
+    }
+}
+
+impl DrawableOnDoc for CropTool {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        Some(
+            self.region()
+                .loosened(Self::OUTLINE_WIDTH * 0.5 / engine_view.camera.total_zoom()),
+        )
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+        let region = self.region();
+        let rect = kurbo::Rect::from_points(
+            region.mins.coords.to_kurbo_point(),
+            region.maxs.coords.to_kurbo_point(),
+        );
+        cx.fill(rect, &Self::FILL_COLOR);
+        cx.stroke_styled(
+            rect,
+            &Self::OUTLINE_COLOR,
+            Self::OUTLINE_WIDTH / engine_view.camera.total_zoom(),
+            &piet::StrokeStyle::new().dash_pattern(&Self::OUTLINE_DASH_PATTERN),
+        );
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum ToolsState {
     Idle,
@@ -274,6 +522,10 @@ pub struct Tools {
     pub verticalspace_tool: VerticalSpaceTool,
     pub offsetcamera_tool: OffsetCameraTool,
     pub zoom_tool: ZoomTool,
+    pub smudge_tool: SmudgeTool,
+    pub crop_tool: CropTool,
+    /// The region that was last captured with the crop tool.
+    crop_region: Option<Aabb>,
     state: ToolsState,
 }
 
@@ -366,6 +618,15 @@ impl PenBehaviour for Tools {
                             .transform_point(&element.pos.into())
                             .coords;
                     }
+                    ToolStyle::Smudge => {
+                        widget_flags |= self.smudge_tool.start(element.pos, engine_view);
+                        widget_flags.redraw = true;
+                    }
+                    ToolStyle::Crop => {
+                        self.crop_tool.start = element.pos;
+                        self.crop_tool.current = element.pos;
+                        widget_flags.redraw = true;
+                    }
                 }
                 widget_flags |= engine_view
                     .document
@@ -393,9 +654,11 @@ impl PenBehaviour for Tools {
                         {
                             self.verticalspace_tool.start_pos_y - self.verticalspace_tool.pos_y
                         } else {
-                            engine_view.document.snap_position(
+                            snap_point(
                                 element.pos - na::vector![0., self.verticalspace_tool.pos_y],
-                            )[1]
+                                &SnapContext::new(engine_view.document, engine_view.store),
+                            )
+                            .pos[1]
                         };
 
                         if y_offset.abs() > VerticalSpaceTool::Y_OFFSET_THRESHOLD {
@@ -438,6 +701,7 @@ impl PenBehaviour for Tools {
                                 .transform_point(&self.offsetcamera_tool.start.into())
                                 .coords;
 
+                        let offset = engine_view.camera.offset_delta_for_surface_delta(offset);
                         widget_flags |= engine_view
                             .camera
                             .set_offset(engine_view.camera.offset() - offset, engine_view.document);
@@ -461,7 +725,7 @@ impl PenBehaviour for Tools {
                         let new_zoom =
                             total_zoom_old * (1.0 - offset[1] * Camera::DRAG_ZOOM_MAGN_ZOOM_FACTOR);
 
-                        if (Camera::ZOOM_MIN..=Camera::ZOOM_MAX).contains(&new_zoom) {
+                        if engine_view.camera.zoom_limits().contains(new_zoom) {
                             widget_flags |= engine_view
                                 .camera
                                 .zoom_w_timeout(new_zoom, engine_view.tasks_tx.clone());
@@ -483,6 +747,27 @@ impl PenBehaviour for Tools {
                         }
                         self.zoom_tool.current_surface_coord = new_surface_coord;
                     }
+                    ToolStyle::Smudge => {
+                        let (smudged, wf) = self.smudge_tool.smudge_along(element.pos, engine_view);
+                        widget_flags |= wf;
+                        if !smudged.is_empty() {
+                            engine_view.store.regenerate_rendering_for_strokes_threaded(
+                                engine_view.tasks_tx.clone(),
+                                &smudged,
+                                engine_view.camera.viewport(),
+                                engine_view.camera.image_scale(),
+                            );
+                            widget_flags.store_modified = true;
+                        }
+                        widget_flags.redraw = true;
+                    }
+                    ToolStyle::Crop => {
+                        self.crop_tool.current = element.pos;
+                        widget_flags |= engine_view
+                            .camera
+                            .nudge_w_pos(element.pos, engine_view.document);
+                        widget_flags.redraw = true;
+                    }
                 }
 
                 EventResult {
@@ -501,6 +786,17 @@ impl PenBehaviour for Tools {
                         widget_flags |= engine_view.store.record(Instant::now());
                         widget_flags.store_modified = true;
                     }
+                    ToolStyle::Smudge => {
+                        widget_flags |= engine_view.store.record(Instant::now());
+                    }
+                    ToolStyle::Crop => {
+                        let region = self.crop_tool.region();
+                        if region.extents().min() >= CropTool::MIN_EXTENTS {
+                            self.crop_region = Some(region);
+                        }
+                        widget_flags.redraw = true;
+                        widget_flags.refresh_ui = true;
+                    }
                     ToolStyle::OffsetCamera | ToolStyle::Zoom => {}
                 }
 
@@ -569,6 +865,8 @@ impl DrawableOnDoc for Tools {
                 ToolStyle::VerticalSpace => self.verticalspace_tool.bounds_on_doc(engine_view),
                 ToolStyle::OffsetCamera => self.offsetcamera_tool.bounds_on_doc(engine_view),
                 ToolStyle::Zoom => self.zoom_tool.bounds_on_doc(engine_view),
+                ToolStyle::Smudge => self.smudge_tool.bounds_on_doc(engine_view),
+                ToolStyle::Crop => self.crop_tool.bounds_on_doc(engine_view),
             },
             ToolsState::Idle => None,
         }
@@ -591,6 +889,12 @@ impl DrawableOnDoc for Tools {
             ToolStyle::Zoom => {
                 self.zoom_tool.draw_on_doc(cx, engine_view)?;
             }
+            ToolStyle::Smudge => {
+                self.smudge_tool.draw_on_doc(cx, engine_view)?;
+            }
+            ToolStyle::Crop => {
+                self.crop_tool.draw_on_doc(cx, engine_view)?;
+            }
         }
 
         cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
@@ -599,6 +903,11 @@ impl DrawableOnDoc for Tools {
 }
 
 impl Tools {
+    /// The region that was last captured with the crop tool.
+    pub fn crop_region(&self) -> Option<Aabb> {
+        self.crop_region
+    }
+
     fn reset(&mut self, engine_view: &mut EngineViewMut) {
         match engine_view.pens_config.tools_config.style {
             ToolStyle::VerticalSpace => {
@@ -612,7 +921,191 @@ impl Tools {
                 self.zoom_tool.start_surface_coord = na::Vector2::zeros();
                 self.zoom_tool.current_surface_coord = na::Vector2::zeros();
             }
+            ToolStyle::Smudge => {
+                self.smudge_tool.carried_color = None;
+            }
+            ToolStyle::Crop => {
+                self.crop_tool.start = na::Vector2::zeros();
+                self.crop_tool.current = na::Vector2::zeros();
+            }
         }
         self.state = ToolsState::Idle;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Tools;
+    use crate::pens::pensconfig::toolsconfig::ToolStyle;
+    use crate::pens::PenBehaviour;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::Engine;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::penevent::PenEvent;
+    use rnote_compose::penpath::Element;
+    use rnote_compose::shapes::{Line, Shape};
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::{Color, Style};
+    use std::collections::HashSet;
+    use std::time::Instant;
+
+    #[test]
+    fn crop_captures_dragged_region() {
+        let mut engine = Engine::default();
+        engine.pens_config.tools_config.style = ToolStyle::Crop;
+        let mut tools = Tools::default();
+        let now = Instant::now();
+        let mut drag = |tools: &mut Tools, positions: &[na::Vector2<f64>]| {
+            for &pos in positions {
+                let _ = tools.handle_event(
+                    PenEvent::Down {
+                        element: Element::new(pos, 0.5),
+                        modifier_keys: HashSet::new(),
+                    },
+                    now,
+                    &mut engine.view_mut(),
+                );
+            }
+            let _ = tools.handle_event(
+                PenEvent::Up {
+                    element: Element::new(*positions.last().unwrap(), 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                now,
+                &mut engine.view_mut(),
+            );
+        };
+
+        assert_eq!(tools.crop_region(), None);
+        drag(
+            &mut tools,
+            &[
+                na::vector![120.0, 80.0],
+                na::vector![60.0, 40.0],
+                na::vector![20.0, 30.0],
+            ],
+        );
+        assert_eq!(
+            tools.crop_region(),
+            Some(Aabb::new(na::point![20.0, 30.0], na::point![120.0, 80.0]))
+        );
+        // a tap does not replace the captured region
+        drag(&mut tools, &[na::vector![200.0, 200.0]]);
+        assert_eq!(
+            tools.crop_region(),
+            Some(Aabb::new(na::point![20.0, 30.0], na::point![120.0, 80.0]))
+        );
+    }
+
+    #[test]
+    fn smudge_blends_colors_across_boundary() {
+        let mut engine = Engine::default();
+        engine.pens_config.tools_config.style = ToolStyle::Smudge;
+        let mut insert_line = |start: f64, end: f64, color: Color| {
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Line(Line {
+                        start: na::vector![start, 50.0],
+                        end: na::vector![end, 50.0],
+                    }),
+                    Style::Smooth(SmoothOptions {
+                        stroke_width: 4.0,
+                        stroke_color: Some(color),
+                        ..Default::default()
+                    }),
+                )),
+                None,
+            )
+        };
+        let red = insert_line(0.0, 50.0, Color::RED);
+        let blue = insert_line(50.0, 100.0, Color::BLUE);
+        let color_of = |engine: &Engine, key| match engine.store.get_stroke_ref(key) {
+            Some(Stroke::ShapeStroke(shapestroke)) => shapestroke.style.stroke_color().unwrap(),
+            _ => panic!("no shape stroke"),
+        };
+
+        // drag from the red stroke across the boundary into the blue stroke
+        let mut tools = Tools::default();
+        let now = Instant::now();
+        for x in (1..10).map(|i| i as f64 * 10.0) {
+            let _ = tools.handle_event(
+                PenEvent::Down {
+                    element: Element::new(na::vector![x, 50.0], 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                now,
+                &mut engine.view_mut(),
+            );
+        }
+        let _ = tools.handle_event(
+            PenEvent::Up {
+                element: Element::new(na::vector![90.0, 50.0], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            now,
+            &mut engine.view_mut(),
+        );
+
+        // the red got pushed into the blue stroke, while the red stroke stays mostly red
+        let smudged = color_of(&engine, blue);
+        assert!(smudged.r > 0.0 && smudged.b < 1.0);
+        let red_color = color_of(&engine, red);
+        assert!(red_color.r > red_color.b);
+    }
+
+    #[test]
+    fn smudge_follows_drag_path_with_falloff() {
+        let mut engine = Engine::default();
+        engine.pens_config.tools_config.style = ToolStyle::Smudge;
+        let mut insert_line = |start: f64, end: f64, y: f64, color: Color| {
+            engine.store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Line(Line {
+                        start: na::vector![start, y],
+                        end: na::vector![end, y],
+                    }),
+                    Style::Smooth(SmoothOptions {
+                        stroke_width: 4.0,
+                        stroke_color: Some(color),
+                        ..Default::default()
+                    }),
+                )),
+                None,
+            )
+        };
+        let _red = insert_line(0.0, 20.0, 50.0, Color::RED);
+        let near = insert_line(45.0, 55.0, 52.0, Color::BLUE);
+        let far = insert_line(45.0, 55.0, 57.0, Color::BLUE);
+        let color_of = |engine: &Engine, key| match engine.store.get_stroke_ref(key) {
+            Some(Stroke::ShapeStroke(shapestroke)) => shapestroke.style.stroke_color().unwrap(),
+            _ => panic!("no shape stroke"),
+        };
+
+        // a fast drag, where no event lands near the blue strokes
+        let mut tools = Tools::default();
+        let now = Instant::now();
+        for x in [10.0, 90.0] {
+            let _ = tools.handle_event(
+                PenEvent::Down {
+                    element: Element::new(na::vector![x, 50.0], 0.5),
+                    modifier_keys: HashSet::new(),
+                },
+                now,
+                &mut engine.view_mut(),
+            );
+        }
+        let _ = tools.handle_event(
+            PenEvent::Up {
+                element: Element::new(na::vector![90.0, 50.0], 0.5),
+                modifier_keys: HashSet::new(),
+            },
+            now,
+            &mut engine.view_mut(),
+        );
+
+        // both strokes along the path are smudged, the closer one more
+        let (near, far) = (color_of(&engine, near), color_of(&engine, far));
+        assert!(far.r > 0.0);
+        assert!(near.r > far.r);
+    }
+}
@@ -2,6 +2,7 @@
 use super::{ModifyState, Typewriter, TypewriterState};
 use crate::engine::EngineViewMut;
 use crate::pens::PenBehaviour;
+use crate::snap::{snap_point, SnapContext};
 use crate::strokes::{Stroke, TextStroke};
 use crate::{DrawableOnDoc, StrokeStore, WidgetFlags};
 use rnote_compose::eventresult::{EventPropagation, EventResult};
@@ -27,8 +28,13 @@ impl Typewriter {
         let event_result = match &mut self.state {
             TypewriterState::Idle | TypewriterState::Start { .. } => {
                 let mut refresh_state = false;
-                let mut new_state =
-                    TypewriterState::Start(engine_view.document.snap_position(element.pos));
+                let mut new_state = TypewriterState::Start(
+                    snap_point(
+                        element.pos,
+                        &SnapContext::new(engine_view.document, engine_view.store),
+                    )
+                    .pos,
+                );
 
                 if let Some(&stroke_key) = engine_view
                     .store
@@ -239,10 +245,11 @@ impl Typewriter {
                             .map(|s| s.bounds())
                         {
                             let snap_corner_pos = textstroke_bounds.mins.coords;
-                            let offset = engine_view
-                                .document
-                                .snap_position(snap_corner_pos + (element.pos - *current_pos))
-                                - snap_corner_pos;
+                            let offset = snap_point(
+                                snap_corner_pos + (element.pos - *current_pos),
+                                &SnapContext::new(engine_view.document, engine_view.store),
+                            )
+                            .pos - snap_corner_pos;
 
                             if offset.magnitude()
                                 > Self::TRANSLATE_OFFSET_THRESHOLD / engine_view.camera.total_zoom()
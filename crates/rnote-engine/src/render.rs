@@ -32,6 +32,57 @@ pub const POINT_TO_PX_CONV_FACTOR: f64 = 72.0 / 96.0;
 /// There is a trade off: a larger value will consume more memory, a smaller value will mean more stuttering on zooms and when moving the view.
 pub const VIEWPORT_EXTENTS_MARGIN_FACTOR: f64 = 0.4;
 
+/// The anti-aliasing quality used when rasterizing content.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "aa_quality")]
+pub enum AaQuality {
+    /// No anti-aliasing. Crisp, aliased edges for maximum speed.
+    #[serde(rename = "none")]
+    None = 0,
+    /// Fast anti-aliasing, trading some edge quality for speed.
+    #[serde(rename = "fast")]
+    Fast,
+    /// High quality anti-aliasing.
+    #[serde(rename = "high")]
+    High,
+}
+
+impl Default for AaQuality {
+    fn default() -> Self {
+        Self::Fast
+    }
+}
+
+impl TryFrom<u32> for AaQuality {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!("AaQuality try_from::<u32>() for value {} failed", value)
+        })
+    }
+}
+
+impl From<AaQuality> for cairo::Antialias {
+    fn from(value: AaQuality) -> Self {
+        match value {
+            AaQuality::None => cairo::Antialias::None,
+            AaQuality::Fast => cairo::Antialias::Fast,
+            AaQuality::High => cairo::Antialias::Best,
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum ImageMemoryFormat {
@@ -323,10 +374,32 @@ impl Image {
     }
 
     /// Generates an image with a provided closure that draws onto a [cairo::Context].
-    pub fn gen_with_cairo<F>(
+    pub fn gen_with_cairo<F>(draw_func: F, bounds: Aabb, image_scale: f64) -> anyhow::Result<Self>
+    where
+        F: FnOnce(&cairo::Context) -> anyhow::Result<()>,
+    {
+        Self::gen_with_cairo_antialias(draw_func, bounds, image_scale, cairo::Antialias::Default)
+    }
+
+    /// Generates an image with a provided closure that draws onto a [cairo::Context],
+    /// rasterized with the given anti-aliasing quality.
+    pub fn gen_with_cairo_aa<F>(
+        draw_func: F,
+        bounds: Aabb,
+        image_scale: f64,
+        aa_quality: AaQuality,
+    ) -> anyhow::Result<Self>
+    where
+        F: FnOnce(&cairo::Context) -> anyhow::Result<()>,
+    {
+        Self::gen_with_cairo_antialias(draw_func, bounds, image_scale, aa_quality.into())
+    }
+
+    fn gen_with_cairo_antialias<F>(
         draw_func: F,
         mut bounds: Aabb,
         image_scale: f64,
+        antialias: cairo::Antialias,
     ) -> anyhow::Result<Self>
     where
         F: FnOnce(&cairo::Context) -> anyhow::Result<()>,
@@ -353,6 +426,7 @@ impl Image {
 
         {
             let cairo_cx = cairo::Context::new(&image_surface)?;
+            cairo_cx.set_antialias(antialias);
             cairo_cx.scale(image_scale, image_scale);
             cairo_cx.translate(-bounds.mins[0], -bounds.mins[1]);
             // Apply the draw function
@@ -380,6 +454,32 @@ impl Image {
 
     /// Generates an image with a provided closure that draws onto a [piet_cairo::CairoRenderContext].
     pub fn gen_with_piet<F>(draw_func: F, bounds: Aabb, image_scale: f64) -> anyhow::Result<Self>
+    where
+        F: FnOnce(&mut piet_cairo::CairoRenderContext) -> anyhow::Result<()>,
+    {
+        Self::gen_with_piet_antialias(draw_func, bounds, image_scale, cairo::Antialias::Default)
+    }
+
+    /// Generates an image with a provided closure that draws onto a [piet_cairo::CairoRenderContext],
+    /// rasterized with the given anti-aliasing quality.
+    pub fn gen_with_piet_aa<F>(
+        draw_func: F,
+        bounds: Aabb,
+        image_scale: f64,
+        aa_quality: AaQuality,
+    ) -> anyhow::Result<Self>
+    where
+        F: FnOnce(&mut piet_cairo::CairoRenderContext) -> anyhow::Result<()>,
+    {
+        Self::gen_with_piet_antialias(draw_func, bounds, image_scale, aa_quality.into())
+    }
+
+    fn gen_with_piet_antialias<F>(
+        draw_func: F,
+        bounds: Aabb,
+        image_scale: f64,
+        antialias: cairo::Antialias,
+    ) -> anyhow::Result<Self>
     where
         F: FnOnce(&mut piet_cairo::CairoRenderContext) -> anyhow::Result<()>,
     {
@@ -393,7 +493,7 @@ impl Image {
             Ok(())
         };
 
-        Self::gen_with_cairo(cairo_draw_fn, bounds, image_scale)
+        Self::gen_with_cairo_antialias(cairo_draw_fn, bounds, image_scale, antialias)
     }
 }
 
@@ -584,6 +684,25 @@ impl Svg {
     ///
     /// Using rsvg for rendering.
     pub fn gen_image(&self, image_scale: f64) -> Result<Image, anyhow::Error> {
+        self.gen_image_antialias(image_scale, cairo::Antialias::Default)
+    }
+
+    /// Generate an image from an Svg, rasterized with the given anti-aliasing quality.
+    ///
+    /// Using rsvg for rendering.
+    pub fn gen_image_aa(
+        &self,
+        image_scale: f64,
+        aa_quality: AaQuality,
+    ) -> Result<Image, anyhow::Error> {
+        self.gen_image_antialias(image_scale, aa_quality.into())
+    }
+
+    fn gen_image_antialias(
+        &self,
+        image_scale: f64,
+        antialias: cairo::Antialias,
+    ) -> Result<Image, anyhow::Error> {
         let mut bounds = self.bounds;
         bounds.ensure_positive();
         bounds.assert_valid()?;
@@ -612,6 +731,7 @@ impl Svg {
         {
             let cx =
                 cairo::Context::new(&surface).context("creating new cairo::Context failed.")?;
+            cx.set_antialias(antialias);
             cx.scale(image_scale, image_scale);
             cx.translate(-bounds.mins[0], -bounds.mins[1]);
 
@@ -669,3 +789,74 @@ fn convert_image_bgra_to_rgba(_width: u32, _height: u32, mut bytes: Vec<u8>) ->
     }
     bytes
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn gen_diagonal_line(aa_quality: AaQuality) -> Image {
+        Image::gen_with_cairo_aa(
+            |cx| {
+                cx.set_line_width(3.0);
+                cx.move_to(2.0, 2.0);
+                cx.line_to(62.0, 40.0);
+                cx.stroke()?;
+                Ok(())
+            },
+            Aabb::new(na::point![0.0, 0.0], na::point![64.0, 64.0]),
+            1.0,
+            aa_quality,
+        )
+        .unwrap()
+    }
+
+    fn n_partially_covered_pixels(image: &Image) -> usize {
+        image
+            .data
+            .chunks_exact(4)
+            .filter(|pixel| pixel[3] != 0 && pixel[3] != u8::MAX)
+            .count()
+    }
+
+    #[test]
+    fn aa_quality_changes_rasterized_edges() {
+        let none = gen_diagonal_line(AaQuality::None);
+        let fast = gen_diagonal_line(AaQuality::Fast);
+        let high = gen_diagonal_line(AaQuality::High);
+
+        assert_eq!(n_partially_covered_pixels(&none), 0);
+        assert!(n_partially_covered_pixels(&fast) > 0);
+        assert!(n_partially_covered_pixels(&high) > 0);
+        assert_ne!(none.data, high.data);
+        // the qualities only differ in the edges, the covered area stays the same
+        let covered = |image: &Image| {
+            image
+                .data
+                .chunks_exact(4)
+                .map(|pixel| pixel[3] as f64 / u8::MAX as f64)
+                .sum::<f64>()
+        };
+        assert!((covered(&none) - covered(&high)).abs() < covered(&high) * 0.1);
+    }
+
+    /// Benchmarks rasterizing with the different anti-aliasing qualities.
+    ///
+    /// Run with `cargo test -p rnote-engine --release -- --ignored bench_aa_quality`.
+    #[test]
+    #[ignore]
+    fn bench_aa_quality_rasterization() {
+        const N_IMAGES: u32 = 200;
+
+        let mut elapsed = Vec::new();
+        for aa_quality in [AaQuality::None, AaQuality::Fast, AaQuality::High] {
+            let start = Instant::now();
+            for _ in 0..N_IMAGES {
+                gen_diagonal_line(aa_quality);
+            }
+            elapsed.push(start.elapsed() / N_IMAGES);
+        }
+        // allow some noise from the measurement
+        assert!(elapsed[0] <= elapsed[2] + Duration::from_micros(50));
+    }
+}
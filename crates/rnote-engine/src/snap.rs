@@ -1,5 +1,10 @@
 // Imports
+use crate::engine::EngineView;
+use crate::{Document, DrawableOnDoc, StrokeStore};
 use p2d::bounding_volume::Aabb;
+use piet::RenderContext;
+use rnote_compose::color;
+use rnote_compose::ext::Vector2Ext;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SnapCorner {
@@ -28,3 +33,356 @@ impl SnapCorner {
         }
     }
 }
+
+/// The kind of snap that moved a point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapKind {
+    /// Snapped to the document and pattern grid.
+    Grid,
+    /// Snapped to an angle step on the line from the angle origin.
+    Angle,
+    /// Snapped to the endpoint of a stroke.
+    Endpoint,
+}
+
+/// How the snap is chosen when multiple snaps apply at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapResolution {
+    /// The snap that comes first in the order wins. Snaps that are missing from the order come last.
+    ///
+    /// Among equal priorities the snap closest to the point wins.
+    Priority([SnapKind; 3]),
+    /// The snap closest to the point wins.
+    Nearest,
+}
+
+impl Default for SnapResolution {
+    fn default() -> Self {
+        // Snaps to existing geometry are the most specific, and the grid always applies when it is enabled.
+        Self::Priority([SnapKind::Endpoint, SnapKind::Angle, SnapKind::Grid])
+    }
+}
+
+impl SnapResolution {
+    /// The priority of the kind of snap. The snap with the highest priority wins.
+    fn priority(&self, kind: SnapKind) -> usize {
+        match self {
+            Self::Priority(order) => order
+                .iter()
+                .position(|&order_kind| order_kind == kind)
+                .map_or(0, |i| order.len() - i),
+            Self::Nearest => 0,
+        }
+    }
+}
+
+/// The result of snapping a point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapResult {
+    /// The snapped point. Is the original point if no snap applied.
+    pub pos: na::Vector2<f64>,
+    /// The snap that fired, if any.
+    pub kind: Option<SnapKind>,
+}
+
+/// The snaps that are considered when snapping a point, and what they snap to.
+///
+/// Snapping to the grid is enabled through the document. The other snaps are opted into by the pens.
+#[derive(Debug, Clone, Copy)]
+pub struct SnapContext<'a> {
+    document: &'a Document,
+    store: &'a StrokeStore,
+    /// Whether the grid is snapped to, when it is enabled in the document.
+    grid: bool,
+    /// The maximum distance to stroke endpoints that are snapped to, in document coordinates.
+    endpoint_dist: Option<f64>,
+    /// The origin of the line that is snapped to angle steps.
+    angle_origin: Option<na::Vector2<f64>>,
+    /// How the snap is chosen when multiple snaps apply.
+    resolution: SnapResolution,
+}
+
+impl<'a> SnapContext<'a> {
+    /// The angle steps that are snapped to, in radians.
+    pub const ANGLE_STEP: f64 = std::f64::consts::PI / 12.0;
+    /// The maximum angle between the line and the nearest angle step where it is snapped, in radians.
+    pub const ANGLE_TOLERANCE: f64 = std::f64::consts::PI / 60.0;
+
+    /// A new context that snaps to the grid as configured in the document.
+    pub fn new(document: &'a Document, store: &'a StrokeStore) -> Self {
+        Self {
+            document,
+            store,
+            grid: true,
+            endpoint_dist: None,
+            angle_origin: None,
+            resolution: SnapResolution::default(),
+        }
+    }
+
+    /// Don't snap to the grid, regardless of the document.
+    pub fn without_grid(mut self) -> Self {
+        self.grid = false;
+        self
+    }
+
+    /// Snap to stroke endpoints within the maximum distance, in document coordinates.
+    pub fn with_endpoints(mut self, max_dist: f64) -> Self {
+        self.endpoint_dist = Some(max_dist);
+        self
+    }
+
+    /// Snap the angle of the line from the origin to the point to multiples of [Self::ANGLE_STEP].
+    pub fn with_angle_origin(mut self, origin: na::Vector2<f64>) -> Self {
+        self.angle_origin = Some(origin);
+        self
+    }
+
+    /// Choose between multiple snaps that apply at the same time with the resolution.
+    pub fn with_resolution(mut self, resolution: SnapResolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    fn snap_grid(&self, point: na::Vector2<f64>) -> Option<na::Vector2<f64>> {
+        (self.grid && self.document.snap_positions).then(|| self.document.snap_position(point))
+    }
+
+    fn snap_angle(&self, point: na::Vector2<f64>) -> Option<na::Vector2<f64>> {
+        let origin = self.angle_origin?;
+        let offset = point - origin;
+        let dist = offset.magnitude();
+        if dist == 0.0 {
+            return None;
+        }
+        let angle = offset[1].atan2(offset[0]);
+        let snapped_angle = (angle / Self::ANGLE_STEP).round() * Self::ANGLE_STEP;
+        ((angle - snapped_angle).abs() <= Self::ANGLE_TOLERANCE).then(|| {
+            // project onto the snapped line, so that the point moves the shortest distance
+            let direction = na::vector![snapped_angle.cos(), snapped_angle.sin()];
+            origin + direction * offset.dot(&direction)
+        })
+    }
+
+    fn snap_endpoint(&self, point: na::Vector2<f64>) -> Option<na::Vector2<f64>> {
+        self.store
+            .nearest_stroke_endpoint(point, self.endpoint_dist?)
+    }
+}
+
+/// Snap the point with all snaps of the context that apply.
+///
+/// When multiple snaps apply, the [SnapResolution] of the context decides which one wins.
+pub fn snap_point(point: na::Vector2<f64>, context: &SnapContext) -> SnapResult {
+    [
+        (SnapKind::Grid, context.snap_grid(point)),
+        (SnapKind::Angle, context.snap_angle(point)),
+        (SnapKind::Endpoint, context.snap_endpoint(point)),
+    ]
+    .into_iter()
+    .filter_map(|(kind, pos)| Some((kind, pos?)))
+    .max_by(|(first_kind, first_pos), (second_kind, second_pos)| {
+        context
+            .resolution
+            .priority(*first_kind)
+            .cmp(&context.resolution.priority(*second_kind))
+            .then_with(|| {
+                (second_pos - point)
+                    .norm()
+                    .total_cmp(&(first_pos - point).norm())
+            })
+    })
+    .map(|(kind, pos)| SnapResult {
+        pos,
+        kind: Some(kind),
+    })
+    .unwrap_or(SnapResult {
+        pos: point,
+        kind: None,
+    })
+}
+
+/// Indicates the snap that fired last at the snapped position, with a marker depending on its [SnapKind].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SnapIndicator {
+    result: Option<SnapResult>,
+}
+
+impl SnapIndicator {
+    /// The size of the marker in surface coordinates.
+    const SIZE: f64 = 6.0;
+    const LINE_WIDTH: f64 = 1.5;
+    const COLOR: piet::Color = color::GNOME_BLUES[3];
+
+    /// Indicate the snap result. Nothing is indicated if no snap fired.
+    pub(crate) fn update(&mut self, result: SnapResult) {
+        self.result = result.kind.is_some().then_some(result);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.result = None;
+    }
+}
+
+impl DrawableOnDoc for SnapIndicator {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        let result = self.result?;
+        let total_zoom = engine_view.camera.total_zoom();
+        Some(Aabb::from_half_extents(
+            result.pos.into(),
+            na::Vector2::repeat((Self::SIZE + Self::LINE_WIDTH) / total_zoom),
+        ))
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        let Some(SnapResult {
+            pos,
+            kind: Some(kind),
+        }) = self.result
+        else {
+            return Ok(());
+        };
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let total_zoom = engine_view.camera.total_zoom();
+        let size = Self::SIZE / total_zoom;
+        let line_width = Self::LINE_WIDTH / total_zoom;
+
+        match kind {
+            SnapKind::Grid => {
+                // a cross along the grid lines
+                let mut cross = kurbo::BezPath::new();
+                cross.move_to((pos - na::vector![size, 0.0]).to_kurbo_point());
+                cross.line_to((pos + na::vector![size, 0.0]).to_kurbo_point());
+                cross.move_to((pos - na::vector![0.0, size]).to_kurbo_point());
+                cross.line_to((pos + na::vector![0.0, size]).to_kurbo_point());
+                cx.stroke(cross, &Self::COLOR, line_width);
+            }
+            SnapKind::Angle => {
+                let mut diamond = kurbo::BezPath::new();
+                diamond.move_to((pos - na::vector![size, 0.0]).to_kurbo_point());
+                diamond.line_to((pos - na::vector![0.0, size]).to_kurbo_point());
+                diamond.line_to((pos + na::vector![size, 0.0]).to_kurbo_point());
+                diamond.line_to((pos + na::vector![0.0, size]).to_kurbo_point());
+                diamond.close_path();
+                cx.stroke(diamond, &Self::COLOR, line_width);
+            }
+            SnapKind::Endpoint => {
+                cx.stroke(
+                    kurbo::Circle::new(pos.to_kurbo_point(), size),
+                    &Self::COLOR,
+                    line_width,
+                );
+            }
+        }
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{snap_point, SnapContext, SnapIndicator, SnapKind, SnapResolution, SnapResult};
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::DrawableOnDoc;
+    use crate::Engine;
+    use approx::assert_relative_eq;
+    use rnote_compose::shapes::{Line, Shape};
+    use rnote_compose::Style;
+
+    #[test]
+    fn conflicting_snaps_resolve_by_priority() {
+        let mut engine = Engine::default();
+        let endpoint = na::vector![101.3, 47.9];
+        engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Line(Line {
+                    start: na::vector![301.7, 247.1],
+                    end: endpoint,
+                }),
+                Style::default(),
+            )),
+            None,
+        );
+        let point = na::vector![103.0, 49.0];
+
+        // without any snaps the point is unchanged
+        let result = snap_point(point, &SnapContext::new(&engine.document, &engine.store));
+        assert_eq!(result.kind, None);
+        assert_eq!(result.pos, point);
+
+        engine.document.snap_positions = true;
+        let context = SnapContext::new(&engine.document, &engine.store);
+        let result = snap_point(point, &context);
+        assert_eq!(result.kind, Some(SnapKind::Grid));
+        assert_eq!(result.pos, engine.document.snap_position(point));
+
+        // the angle from the origin is within the tolerance of the horizontal step
+        let result = snap_point(point, &context.with_angle_origin(na::vector![0.0, 48.0]));
+        assert_eq!(result.kind, Some(SnapKind::Angle));
+        assert_relative_eq!(result.pos, na::vector![103.0, 48.0], epsilon = 1e-9);
+
+        // the endpoint beats both the angle and the grid
+        let result = snap_point(
+            point,
+            &context
+                .with_angle_origin(na::vector![0.0, 48.0])
+                .with_endpoints(5.0),
+        );
+        assert_eq!(result.kind, Some(SnapKind::Endpoint));
+        assert_eq!(result.pos, endpoint);
+
+        // the angle step is closer to the point than the endpoint
+        let context_w_all = context
+            .with_angle_origin(na::vector![0.0, 48.0])
+            .with_endpoints(5.0);
+        let result = snap_point(
+            point,
+            &context_w_all.with_resolution(SnapResolution::Nearest),
+        );
+        assert_eq!(result.kind, Some(SnapKind::Angle));
+        let result = snap_point(
+            point,
+            &context_w_all.with_resolution(SnapResolution::Priority([
+                SnapKind::Grid,
+                SnapKind::Angle,
+                SnapKind::Endpoint,
+            ])),
+        );
+        assert_eq!(result.kind, Some(SnapKind::Grid));
+
+        // out of reach of the endpoint and the angle step, so the grid applies again
+        let far_point = na::vector![130.0, 100.0];
+        let result = snap_point(
+            far_point,
+            &context
+                .with_angle_origin(na::vector![0.0, 48.0])
+                .with_endpoints(5.0),
+        );
+        assert_eq!(result.kind, Some(SnapKind::Grid));
+        let result = snap_point(far_point, &context.without_grid());
+        assert_eq!(result.kind, None);
+        assert_eq!(result.pos, far_point);
+    }
+
+    #[test]
+    fn indicator_shows_only_fired_snaps() {
+        let engine = Engine::default();
+        let mut indicator = SnapIndicator::default();
+        let pos = na::vector![10.0, 20.0];
+
+        indicator.update(SnapResult {
+            pos,
+            kind: Some(SnapKind::Endpoint),
+        });
+        let bounds = indicator.bounds_on_doc(&engine.view()).unwrap();
+        assert_relative_eq!(bounds.center().coords, pos);
+
+        indicator.update(SnapResult { pos, kind: None });
+        assert!(indicator.bounds_on_doc(&engine.view()).is_none());
+    }
+}
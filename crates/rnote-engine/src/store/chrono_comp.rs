@@ -1,5 +1,7 @@
 // Imports
 use super::{StrokeKey, StrokeStore};
+use crate::engine::strokecontent::ContentClipGroup;
+use crate::strokes::Stroke;
 use p2d::bounding_volume::Aabb;
 use rayon::slice::ParallelSliceMut;
 use serde::{Deserialize, Serialize};
@@ -61,13 +63,57 @@ impl Ord for StrokeLayer {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, Ord, PartialEq, PartialOrd)]
+/// Properties of a user layer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default, rename = "layer_props")]
+pub struct LayerProps {
+    /// The name of the layer, which is empty for unnamed layers.
+    #[serde(rename = "name")]
+    pub name: String,
+    /// Clip the rendering of the layer to the content of the layer below it.
+    ///
+    /// The clipping is non-destructive, the strokes of both layers are not modified.
+    /// Consecutive clipping layers are all clipped to the same non-clipping layer below them.
+    #[serde(rename = "clip_below")]
+    pub clip_below: bool,
+}
+
+/// Strokes, in draw order, that are composited together because of clipping layers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClipGroup {
+    /// Strokes of the layer that is drawn normally and masks the clipped strokes.
+    pub(crate) base: Vec<StrokeKey>,
+    /// Strokes of the clipping layers above the base, only drawn where the base has content.
+    pub(crate) clipped: Vec<StrokeKey>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, Ord, PartialEq, PartialOrd)]
 #[serde(default, rename = "chrono_component")]
 pub struct ChronoComponent {
     #[serde(rename = "t")]
     t: u32,
     #[serde(rename = "layer")]
     pub layer: StrokeLayer,
+    /// Explicit ordering within a layer. Strokes with a higher z-index are drawn on top.
+    #[serde(rename = "z_index")]
+    pub z_index: i32,
+    /// Hidden strokes are skipped when rendering and hit-testing, but are kept in the store.
+    #[serde(rename = "hidden", skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+    /// Locked strokes can't be selected or erased, but are rendered and kept in the store.
+    #[serde(rename = "locked", skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    /// The time the stroke was created, in milliseconds since the unix epoch.
+    /// Zero for strokes of files that did not record it.
+    #[serde(rename = "created_at", skip_serializing_if = "is_zero")]
+    pub created_at: i64,
+    /// User defined tags, for example used to filter the strokes.
+    #[serde(rename = "tags", skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+fn is_zero(value: &i64) -> bool {
+    *value == 0
 }
 
 impl Default for ChronoComponent {
@@ -75,13 +121,36 @@ impl Default for ChronoComponent {
         Self {
             t: 0,
             layer: StrokeLayer::default(),
+            z_index: 0,
+            hidden: false,
+            locked: false,
+            created_at: 0,
+            tags: Vec::new(),
         }
     }
 }
 
 impl ChronoComponent {
     pub(crate) fn new(t: u32, layer: StrokeLayer) -> Self {
-        Self { t, layer }
+        Self {
+            t,
+            layer,
+            z_index: 0,
+            hidden: false,
+            locked: false,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// The draw order: primarily by layer, then by z-index, then by the time of creation or last modification.
+    ///
+    /// [Ordering::Less] means that `self` is drawn before `other`.
+    pub(crate) fn draw_order(&self, other: &Self) -> Ordering {
+        self.layer
+            .cmp(&other.layer)
+            .then(self.z_index.cmp(&other.z_index))
+            .then(self.t.cmp(&other.t))
     }
 }
 
@@ -94,7 +163,218 @@ impl StrokeStore {
         }
     }
 
-    /// Returns the keys in chronological order, as in first: gets drawn first, last: gets drawn last.
+    /// The chronological counter value of the time the stroke was created or last modified.
+    pub(crate) fn chrono_t(&self, key: StrokeKey) -> Option<u32> {
+        self.chrono_components.get(key).map(|c| c.t)
+    }
+
+    /// The time the stroke was created, in milliseconds since the unix epoch. None when it was not recorded.
+    pub(crate) fn created_at(&self, key: StrokeKey) -> Option<i64> {
+        self.chrono_components
+            .get(key)
+            .map(|c| c.created_at)
+            .filter(|&created_at| created_at != 0)
+    }
+
+    pub(crate) fn z_index(&self, key: StrokeKey) -> Option<i32> {
+        self.chrono_components.get(key).map(|c| c.z_index)
+    }
+
+    /// Set the z-index of the stroke, which orders it within its layer.
+    pub(crate) fn set_z_index(&mut self, key: StrokeKey, z_index: i32) {
+        if let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) {
+            Arc::make_mut(chrono_comp).z_index = z_index;
+        }
+    }
+
+    pub(crate) fn hidden(&self, key: StrokeKey) -> Option<bool> {
+        self.chrono_components.get(key).map(|c| c.hidden)
+    }
+
+    /// Hide or show the stroke. Hidden strokes get deselected.
+    ///
+    /// Returns true when the visibility has changed.
+    pub(crate) fn set_hidden(&mut self, key: StrokeKey, hidden: bool) -> bool {
+        let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) else {
+            return false;
+        };
+        if chrono_comp.hidden == hidden {
+            return false;
+        }
+        Arc::make_mut(chrono_comp).hidden = hidden;
+        if hidden {
+            self.set_selected(key, false);
+        }
+        true
+    }
+
+    pub(crate) fn locked(&self, key: StrokeKey) -> Option<bool> {
+        self.chrono_components.get(key).map(|c| c.locked)
+    }
+
+    /// Lock or unlock the stroke. Locked strokes get deselected.
+    ///
+    /// Returns true when the lock has changed.
+    pub(crate) fn set_locked(&mut self, key: StrokeKey, locked: bool) -> bool {
+        let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) else {
+            return false;
+        };
+        if chrono_comp.locked == locked {
+            return false;
+        }
+        Arc::make_mut(chrono_comp).locked = locked;
+        if locked {
+            self.set_selected(key, false);
+        }
+        true
+    }
+
+    pub(crate) fn tags(&self, key: StrokeKey) -> Option<&[String]> {
+        self.chrono_components.get(key).map(|c| c.tags.as_slice())
+    }
+
+    /// Replace the tags of the stroke.
+    ///
+    /// Returns true when the tags have changed.
+    pub(crate) fn set_tags(&mut self, key: StrokeKey, tags: Vec<String>) -> bool {
+        let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) else {
+            return false;
+        };
+        if chrono_comp.tags == tags {
+            return false;
+        }
+        Arc::make_mut(chrono_comp).tags = tags;
+        true
+    }
+
+    pub fn layer_props(&self, user_layer: u32) -> LayerProps {
+        self.layer_props
+            .get(&user_layer)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set_layer_props(&mut self, user_layer: u32, layer_props: LayerProps) {
+        if layer_props == LayerProps::default() {
+            Arc::make_mut(&mut self.layer_props).remove(&user_layer);
+        } else {
+            Arc::make_mut(&mut self.layer_props).insert(user_layer, layer_props);
+        }
+    }
+
+    pub(crate) fn layer(&self, key: StrokeKey) -> Option<StrokeLayer> {
+        self.chrono_components.get(key).map(|c| c.layer)
+    }
+
+    /// The index of a new user layer above all existing user layers.
+    pub(crate) fn next_user_layer(&self) -> u32 {
+        self.chrono_components
+            .values()
+            .filter_map(|chrono_comp| match chrono_comp.layer {
+                StrokeLayer::UserLayer(user_layer) => Some(user_layer),
+                _ => None,
+            })
+            .chain(self.layer_props.keys().copied())
+            .max()
+            .map(|user_layer| user_layer + 1)
+            .unwrap_or(1)
+    }
+
+    /// Move the strokes onto a new user layer above all existing user layers.
+    ///
+    /// The strokes keep their z-index and time, so their draw order among each other is preserved.
+    /// Returns the index of the new user layer.
+    pub(crate) fn move_strokes_to_new_layer(&mut self, keys: &[StrokeKey], name: String) -> u32 {
+        let new_layer = self.next_user_layer();
+
+        for &key in keys {
+            if let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) {
+                Arc::make_mut(chrono_comp).layer = StrokeLayer::UserLayer(new_layer);
+            }
+        }
+        self.set_layer_props(
+            new_layer,
+            LayerProps {
+                name,
+                ..Default::default()
+            },
+        );
+
+        new_layer
+    }
+
+    fn layer_clips_below(&self, layer: StrokeLayer) -> bool {
+        match layer {
+            StrokeLayer::UserLayer(user_layer) => self.layer_props(user_layer).clip_below,
+            _ => false,
+        }
+    }
+
+    /// Splits the given keys, which are expected to be in draw order, into groups of base and clipped strokes.
+    ///
+    /// Clipping layers that have no layer below them are clipped to nothing.
+    pub(crate) fn clip_groups(&self, keys: Vec<StrokeKey>) -> Vec<ClipGroup> {
+        let mut groups: Vec<ClipGroup> = Vec::new();
+        let mut prev_layer: Option<StrokeLayer> = None;
+
+        for key in keys {
+            let Some(layer) = self.chrono_components.get(key).map(|c| c.layer) else {
+                continue;
+            };
+            let clipped = self.layer_clips_below(layer);
+            let new_layer = prev_layer != Some(layer);
+            prev_layer = Some(layer);
+
+            match groups.last_mut() {
+                Some(group) if clipped => group.clipped.push(key),
+                Some(group) if !new_layer && group.clipped.is_empty() => group.base.push(key),
+                _ if clipped => groups.push(ClipGroup {
+                    base: vec![],
+                    clipped: vec![key],
+                }),
+                _ => groups.push(ClipGroup {
+                    base: vec![key],
+                    clipped: vec![],
+                }),
+            }
+        }
+
+        groups
+    }
+
+    /// The strokes for the given keys, which are expected to be in draw order, with the clip groups as index ranges into
+    /// the strokes.
+    pub(crate) fn get_strokes_arc_w_clip_groups(
+        &self,
+        keys: Vec<StrokeKey>,
+    ) -> (Vec<Arc<Stroke>>, Vec<ContentClipGroup>) {
+        let mut strokes = Vec::with_capacity(keys.len());
+        let mut content_clip_groups = Vec::new();
+
+        for group in self.clip_groups(keys) {
+            let base_start = strokes.len();
+            strokes.extend(self.get_strokes_arc(&group.base));
+            let clipped_start = strokes.len();
+            strokes.extend(self.get_strokes_arc(&group.clipped));
+            if clipped_start < strokes.len() {
+                content_clip_groups.push(ContentClipGroup {
+                    base: base_start..clipped_start,
+                    clipped: clipped_start..strokes.len(),
+                });
+            }
+        }
+
+        (strokes, content_clip_groups)
+    }
+
+    /// The keys of the strokes that are not trashed, in the order that they are drawn.
+    ///
+    /// See [ChronoComponent::draw_order] for how the order is determined.
+    pub fn sorted_draw_keys(&self) -> Vec<StrokeKey> {
+        self.stroke_keys_as_rendered()
+    }
+
+    /// Returns the keys in draw order, as in first: gets drawn first, last: gets drawn last.
     pub(crate) fn keys_sorted_chrono(&self) -> Vec<StrokeKey> {
         let chrono_components = &self.chrono_components;
 
@@ -104,13 +384,7 @@ impl StrokeStore {
             if let (Some(first_chrono), Some(second_chrono)) =
                 (chrono_components.get(first), chrono_components.get(second))
             {
-                let layer_order = first_chrono.layer.cmp(&second_chrono.layer);
-
-                if layer_order != std::cmp::Ordering::Equal {
-                    layer_order
-                } else {
-                    first_chrono.t.cmp(&second_chrono.t)
-                }
+                first_chrono.draw_order(second_chrono)
             } else {
                 std::cmp::Ordering::Equal
             }
@@ -128,13 +402,7 @@ impl StrokeStore {
             if let (Some(first_chrono), Some(second_chrono)) =
                 (chrono_components.get(first), chrono_components.get(second))
             {
-                let layer_order = first_chrono.layer.cmp(&second_chrono.layer);
-
-                if layer_order != std::cmp::Ordering::Equal {
-                    layer_order
-                } else {
-                    first_chrono.t.cmp(&second_chrono.t)
-                }
+                first_chrono.draw_order(second_chrono)
             } else {
                 std::cmp::Ordering::Equal
             }
@@ -152,13 +420,7 @@ impl StrokeStore {
             if let (Some(first_chrono), Some(second_chrono)) =
                 (chrono_components.get(first), chrono_components.get(second))
             {
-                let layer_order = first_chrono.layer.cmp(&second_chrono.layer);
-
-                if layer_order != std::cmp::Ordering::Equal {
-                    layer_order
-                } else {
-                    first_chrono.t.cmp(&second_chrono.t)
-                }
+                first_chrono.draw_order(second_chrono)
             } else {
                 std::cmp::Ordering::Equal
             }
@@ -167,3 +429,93 @@ impl StrokeStore {
         keys
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{LayerProps, StrokeLayer};
+    use crate::store::StrokeKey;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::StrokeStore;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
+    use std::time::Instant;
+
+    fn new_stroke() -> Stroke {
+        Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![0.0, 0.0],
+                na::point![10.0, 10.0],
+            ))),
+            Style::default(),
+        ))
+    }
+
+    #[test]
+    fn draw_order_layer_z_index_time() {
+        let mut store = StrokeStore::default();
+        let user_first = store.insert_stroke(new_stroke(), Some(StrokeLayer::UserLayer(0)));
+        let user_second = store.insert_stroke(new_stroke(), Some(StrokeLayer::UserLayer(0)));
+        let user_raised = store.insert_stroke(new_stroke(), Some(StrokeLayer::UserLayer(0)));
+        let highlighter = store.insert_stroke(new_stroke(), Some(StrokeLayer::Highlighter));
+        let user_layer_one = store.insert_stroke(new_stroke(), Some(StrokeLayer::UserLayer(1)));
+        let image = store.insert_stroke(new_stroke(), Some(StrokeLayer::Image));
+
+        // raise the first stroke, so that it gets drawn above the other strokes of the same layer
+        store.set_z_index(user_first, 1);
+        // a high z-index does not move the highlighter above user layers
+        store.set_z_index(highlighter, 100);
+
+        assert_eq!(
+            store.sorted_draw_keys(),
+            vec![
+                image,
+                highlighter,
+                user_second,
+                user_raised,
+                user_first,
+                user_layer_one
+            ]
+        );
+
+        store.set_z_index(user_raised, 1);
+        assert_eq!(
+            store.sorted_draw_keys(),
+            vec![
+                image,
+                highlighter,
+                user_second,
+                user_first,
+                user_raised,
+                user_layer_one
+            ]
+        );
+    }
+
+    #[test]
+    fn move_strokes_to_new_layer_keeps_order() {
+        let mut store = StrokeStore::default();
+        let first = store.insert_stroke(new_stroke(), Some(StrokeLayer::UserLayer(0)));
+        let second = store.insert_stroke(new_stroke(), Some(StrokeLayer::UserLayer(0)));
+        let third = store.insert_stroke(new_stroke(), Some(StrokeLayer::UserLayer(0)));
+        let _ = store.record(Instant::now());
+
+        let new_layer = store.move_strokes_to_new_layer(&[third, first], String::from("Sketch"));
+        let _ = store.record(Instant::now());
+
+        assert_eq!(new_layer, 1);
+        assert_eq!(store.layer_props(new_layer).name, "Sketch");
+        let in_new_layer = store
+            .sorted_draw_keys()
+            .into_iter()
+            .filter(|&key| store.layer(key) == Some(StrokeLayer::UserLayer(new_layer)))
+            .collect::<Vec<StrokeKey>>();
+        assert_eq!(in_new_layer, vec![first, third]);
+        assert_eq!(store.sorted_draw_keys(), vec![second, first, third]);
+
+        let _ = store.undo(Instant::now());
+        assert_eq!(store.layer(first), Some(StrokeLayer::UserLayer(0)));
+        assert_eq!(store.layer(third), Some(StrokeLayer::UserLayer(0)));
+        assert_eq!(store.layer_props(new_layer), LayerProps::default());
+    }
+}
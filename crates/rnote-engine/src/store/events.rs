@@ -0,0 +1,50 @@
+// Imports
+use super::{StrokeKey, StrokeStore};
+use futures::channel::mpsc;
+
+/// A notification about changes of the strokes in the store.
+///
+/// Events are sent on the thread that made the change, which is the thread that owns the engine.
+/// The receiver can be polled wherever it is convenient, e.g. as a stream in the main loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreEvent {
+    /// Strokes were added, or restored from the trash.
+    StrokeAdded(Vec<StrokeKey>),
+    /// Strokes were removed, or moved into the trash.
+    StrokeRemoved(Vec<StrokeKey>),
+    /// The geometry or the content of strokes was modified.
+    StrokeModified(Vec<StrokeKey>),
+    /// The selection state of strokes changed.
+    SelectionChanged(Vec<StrokeKey>),
+}
+
+/// Systems that are related to event notifications.
+impl StrokeStore {
+    /// Subscribe to the store events, replacing a previous subscription.
+    ///
+    /// When nobody subscribed, no events are created.
+    pub(crate) fn subscribe_events(&mut self) -> mpsc::UnboundedReceiver<StoreEvent> {
+        let (events_tx, events_rx) = mpsc::unbounded::<StoreEvent>();
+        self.events_tx = Some(events_tx);
+        events_rx
+    }
+
+    /// Unsubscribe from the store events.
+    pub(crate) fn unsubscribe_events(&mut self) {
+        self.events_tx.take();
+    }
+
+    /// Emit an event. The event is only created when there is a subscriber.
+    pub(crate) fn emit_event<F>(&mut self, event: F)
+    where
+        F: FnOnce() -> StoreEvent,
+    {
+        let Some(events_tx) = &self.events_tx else {
+            return;
+        };
+        if events_tx.unbounded_send(event()).is_err() {
+            // The receiver was dropped, so there is no one listening anymore
+            self.events_tx.take();
+        }
+    }
+}
@@ -1,5 +1,6 @@
 // Modules
 pub mod chrono_comp;
+pub mod events;
 pub mod keytree;
 pub mod render_comp;
 pub mod selection_comp;
@@ -7,24 +8,28 @@ pub mod stroke_comp;
 pub mod trash_comp;
 
 // Re-exports
-pub use chrono_comp::ChronoComponent;
+pub use chrono_comp::{ChronoComponent, LayerProps};
+pub use events::StoreEvent;
 use keytree::KeyTree;
 pub use render_comp::RenderComponent;
 pub use selection_comp::SelectionComponent;
+pub use stroke_comp::{Alignment, DistributeAxis, FillRule, PickMode};
 pub use trash_comp::TrashComponent;
 
 // Imports
 use self::chrono_comp::StrokeLayer;
-use crate::engine::EngineSnapshot;
+use crate::engine::{EngineSnapshot, RepeatableAction};
+use crate::render;
 use crate::strokes::Stroke;
 use crate::WidgetFlags;
+use futures::channel::mpsc;
 use rnote_compose::shapes::Shapeable;
 use serde::{Deserialize, Serialize};
 use slotmap::{HopSlotMap, SecondaryMap};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::debug;
+use tracing::{debug, warn};
 
 slotmap::new_key_type! {
     pub struct StrokeKey;
@@ -41,6 +46,14 @@ pub struct HistoryEntry {
     pub chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
     #[serde(rename = "chrono_counter")]
     pub chrono_counter: u32,
+    #[serde(rename = "layer_props")]
+    pub layer_props: Arc<BTreeMap<u32, LayerProps>>,
+    /// The action that produced this entry, if it can be repeated.
+    #[serde(skip)]
+    pub action: Option<RepeatableAction>,
+    /// The estimated size in bytes of the strokes that were introduced with this entry, compared to the previous one.
+    #[serde(skip)]
+    pub size_estimate: usize,
 }
 
 impl Default for HistoryEntry {
@@ -51,6 +64,9 @@ impl Default for HistoryEntry {
             chrono_components: Arc::new(SecondaryMap::new()),
 
             chrono_counter: 0,
+            layer_props: Arc::new(BTreeMap::new()),
+            action: None,
+            size_estimate: 0,
         }
     }
 }
@@ -84,6 +100,9 @@ pub struct StrokeStore {
     /// Value must be kept equal to the [ChronoComponent] of the newest inserted or modified stroke.
     #[serde(rename = "chrono_counter")]
     chrono_counter: u32,
+    /// The properties of the user layers. Layers without an entry have the default properties.
+    #[serde(rename = "layer_props")]
+    layer_props: Arc<BTreeMap<u32, LayerProps>>,
     #[serde(skip)]
     render_components: SecondaryMap<StrokeKey, RenderComponent>,
     #[serde(skip)]
@@ -96,6 +115,35 @@ pub struct StrokeStore {
     /// Needs to be updated with `update_with_key()` when strokes changed their geometry or position!
     #[serde(skip)]
     key_tree: KeyTree,
+    /// The sender for store events. Is only Some when there is a subscriber.
+    #[serde(skip)]
+    events_tx: Option<mpsc::UnboundedSender<StoreEvent>>,
+    /// Incremented every time the recorded state changes, through recording, undo, redo or clearing the history.
+    #[serde(skip)]
+    revision: u64,
+    /// The memory budget of the history in bytes. The oldest entries are evicted when it is exceeded.
+    #[serde(skip)]
+    max_history_bytes: usize,
+    /// The nesting depth of the open history transactions. Recorded changes coalesce while it is not zero.
+    #[serde(skip)]
+    transaction_depth: usize,
+    /// Whether the open history transaction has already recorded a history entry.
+    #[serde(skip)]
+    transaction_recorded: bool,
+    /// The minimum width of rendered strokes in screen pixels. Does not alter the stored stroke widths.
+    ///
+    /// Disabled when zero.
+    #[serde(skip)]
+    min_screen_width_px: f64,
+    /// The number of appended segment images of a stroke that is currently drawn,
+    /// after which they are merged into a single cached prefix image.
+    ///
+    /// Disabled when zero.
+    #[serde(skip)]
+    current_stroke_cache_threshold: usize,
+    /// The anti-aliasing quality the stroke images are rendered with.
+    #[serde(skip)]
+    aa_quality: render::AaQuality,
 }
 
 impl Default for StrokeStore {
@@ -114,6 +162,15 @@ impl Default for StrokeStore {
             key_tree: KeyTree::default(),
 
             chrono_counter: 0,
+            layer_props: Arc::new(BTreeMap::new()),
+            events_tx: None,
+            revision: 0,
+            max_history_bytes: Self::MAX_HISTORY_BYTES_DEFAULT,
+            transaction_depth: 0,
+            transaction_recorded: false,
+            min_screen_width_px: 0.0,
+            current_stroke_cache_threshold: Self::CURRENT_STROKE_CACHE_THRESHOLD_DEFAULT,
+            aa_quality: render::AaQuality::default(),
         }
     }
 }
@@ -121,6 +178,8 @@ impl Default for StrokeStore {
 impl StrokeStore {
     /// Max length of the history.
     pub(crate) const HISTORY_MAX_LEN: usize = 100;
+    /// The default memory budget of the history in bytes.
+    pub const MAX_HISTORY_BYTES_DEFAULT: usize = 256 * 1024 * 1024;
 
     /// Import from a engine snapshot. A loaded strokes store should always be imported with this method.
     ///
@@ -132,6 +191,7 @@ impl StrokeStore {
         self.stroke_components = Arc::clone(&snapshot.stroke_components);
         self.chrono_components = Arc::clone(&snapshot.chrono_components);
         self.chrono_counter = snapshot.chrono_counter;
+        self.layer_props = Arc::new(snapshot.layer_props.clone());
 
         self.update_geometry_for_strokes(&self.keys_unordered());
         self.rebuild_selection_components_slotmap();
@@ -159,6 +219,7 @@ impl StrokeStore {
             && Arc::ptr_eq(&self.trash_components, &history_entry.trash_components)
             && Arc::ptr_eq(&self.chrono_components, &history_entry.chrono_components)
             && self.chrono_counter == history_entry.chrono_counter
+            && Arc::ptr_eq(&self.layer_props, &history_entry.layer_props)
     }
 
     /// Create a history entry from the current state.
@@ -168,6 +229,9 @@ impl StrokeStore {
             trash_components: Arc::clone(&self.trash_components),
             chrono_components: Arc::clone(&self.chrono_components),
             chrono_counter: self.chrono_counter,
+            layer_props: Arc::clone(&self.layer_props),
+            action: None,
+            size_estimate: 0,
         }
     }
 
@@ -177,6 +241,7 @@ impl StrokeStore {
         self.trash_components = Arc::clone(&history_entry.trash_components);
         self.chrono_components = Arc::clone(&history_entry.chrono_components);
         self.chrono_counter = history_entry.chrono_counter;
+        self.layer_props = Arc::clone(&history_entry.layer_props);
 
         // Since we don't store the rtree in the history, we need to rebuild it.
         self.rebuild_rtree();
@@ -187,10 +252,16 @@ impl StrokeStore {
 
         let all_strokes = self.stroke_keys_unordered();
         self.set_rendering_dirty_for_strokes(&all_strokes);
+        self.emit_event(|| StoreEvent::StrokeModified(all_strokes));
     }
 
     /// Record the current state and save it in the history.
-    pub(crate) fn record(&mut self, _now: Instant) -> WidgetFlags {
+    ///
+    /// Inside a history transaction, only the first recording adds a history entry. The following ones update it.
+    pub(crate) fn record(&mut self, now: Instant) -> WidgetFlags {
+        if self.transaction_depth > 0 && self.transaction_recorded {
+            return self.update_latest_history_entry(now);
+        }
         let mut widget_flags = WidgetFlags::default();
 
         if self
@@ -202,7 +273,8 @@ impl StrokeStore {
             // as soon as the current state is recorded, remove the future
             self.history.truncate(self.live_index + 1);
 
-            let current = self.create_history_entry();
+            let mut current = self.create_history_entry();
+            current.size_estimate = estimate_history_entry_size(&current, self.history.back());
             self.history.push_back(current);
             self.live_index += 1;
 
@@ -211,6 +283,9 @@ impl StrokeStore {
                 self.history.pop_front();
                 self.live_index -= 1;
             }
+            self.evict_history_over_budget();
+            self.revision += 1;
+            self.transaction_recorded = self.transaction_depth > 0;
         } else {
             debug!("State has not changed, no need to record.");
         }
@@ -221,6 +296,39 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Record the current state and save it in the history, together with the action that produced it.
+    pub(crate) fn record_w_action(
+        &mut self,
+        now: Instant,
+        action: RepeatableAction,
+    ) -> WidgetFlags {
+        let revision = self.revision;
+        let widget_flags = self.record(now);
+        if self.revision != revision {
+            self.history[self.live_index].action = Some(action);
+        }
+        widget_flags
+    }
+
+    /// Update the latest history entry with the current state, together with the action that produced it.
+    pub(crate) fn update_latest_history_entry_w_action(
+        &mut self,
+        now: Instant,
+        action: RepeatableAction,
+    ) -> WidgetFlags {
+        let revision = self.revision;
+        let widget_flags = self.update_latest_history_entry(now);
+        if self.revision != revision {
+            self.history[self.live_index].action = Some(action);
+        }
+        widget_flags
+    }
+
+    /// The action that produced the current state, if it can be repeated.
+    pub(crate) fn last_action(&self) -> Option<RepeatableAction> {
+        self.history.get(self.live_index)?.action
+    }
+
     /// Update the state of the latest history entry with the current document state.
     pub(crate) fn update_latest_history_entry(&mut self, _now: Instant) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
@@ -234,8 +342,17 @@ impl StrokeStore {
             // as soon as the current state is recorded, remove the future
             self.history.truncate(self.live_index + 1);
 
-            let current = self.create_history_entry();
+            let mut current = self.create_history_entry();
+            if let Some(prev) = self
+                .live_index
+                .checked_sub(1)
+                .and_then(|i| self.history.get(i))
+            {
+                current.size_estimate = estimate_history_entry_size(&current, Some(prev));
+            }
             self.history[self.live_index] = current;
+            self.evict_history_over_budget();
+            self.revision += 1;
         } else {
             debug!("State has not changed, no need to update history with current state.");
         }
@@ -246,6 +363,34 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Begin a history transaction, which groups all changes that are recorded until it is committed into a single
+    /// history entry.
+    ///
+    /// Transactions can be nested, the changes then coalesce until the outermost one is committed.
+    pub(crate) fn begin_transaction(&mut self) {
+        if self.transaction_depth == 0 {
+            self.transaction_recorded = false;
+        }
+        self.transaction_depth += 1;
+    }
+
+    /// Commit the innermost open history transaction.
+    pub(crate) fn commit_transaction(&mut self) {
+        let Some(depth) = self.transaction_depth.checked_sub(1) else {
+            warn!("Committing history transaction failed, there is no open transaction.");
+            return;
+        };
+        self.transaction_depth = depth;
+        if depth == 0 {
+            self.transaction_recorded = false;
+        }
+    }
+
+    /// Whether a history transaction is open.
+    pub(crate) fn in_transaction(&self) -> bool {
+        self.transaction_depth > 0
+    }
+
     /// Undo the latest changes.
     ///
     /// Should only be called from inside the engine undo wrapper function.
@@ -259,6 +404,9 @@ impl StrokeStore {
         let prev = self.history[self.live_index - 1].clone();
         self.import_history_entry(prev);
         self.live_index -= 1;
+        self.revision += 1;
+        // changes in an open transaction after undoing must not overwrite the entry before it
+        self.transaction_recorded = false;
 
         widget_flags.hide_undo = Some(!self.can_undo());
         widget_flags.hide_redo = Some(!self.can_redo());
@@ -280,6 +428,8 @@ impl StrokeStore {
         let next = self.history[self.live_index + 1].clone();
         self.import_history_entry(next);
         self.live_index += 1;
+        self.revision += 1;
+        self.transaction_recorded = false;
 
         widget_flags.hide_undo = Some(!self.can_undo());
         widget_flags.hide_redo = Some(!self.can_redo());
@@ -288,6 +438,13 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// The revision of the recorded state.
+    ///
+    /// Changes that are not yet recorded are not reflected.
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision
+    }
+
     pub(crate) fn can_undo(&self) -> bool {
         self.live_index > 0
     }
@@ -296,12 +453,47 @@ impl StrokeStore {
         self.live_index < self.history.len() - 1
     }
 
+    /// The memory budget of the history in bytes.
+    pub fn max_history_bytes(&self) -> usize {
+        self.max_history_bytes
+    }
+
+    /// Set the memory budget of the history in bytes, evicting the oldest entries when it is exceeded.
+    pub fn set_max_history_bytes(&mut self, max_history_bytes: usize) {
+        self.max_history_bytes = max_history_bytes;
+        self.evict_history_over_budget();
+    }
+
+    /// The estimated memory usage of the history in bytes.
+    ///
+    /// Counts the strokes that the entries introduced compared to their previous entry,
+    /// so the oldest entry which every other entry is based on is not included.
+    pub fn history_bytes(&self) -> usize {
+        self.history
+            .iter()
+            .skip(1)
+            .map(|entry| entry.size_estimate)
+            .sum()
+    }
+
+    /// Evict the oldest history entries until the memory usage is within the budget.
+    ///
+    /// The entry of the live document is never evicted.
+    fn evict_history_over_budget(&mut self) {
+        while self.live_index > 0 && self.history_bytes() > self.max_history_bytes {
+            self.history.pop_front();
+            self.live_index -= 1;
+        }
+    }
+
     /// Clear the history.
     pub(crate) fn clear_history(&mut self, initial_state: HistoryEntry) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
         self.history = VecDeque::from(vec![initial_state]);
         self.live_index = 0;
+        self.revision += 1;
+        self.transaction_recorded = false;
 
         widget_flags.hide_undo = Some(true);
         widget_flags.hide_redo = Some(true);
@@ -335,6 +527,7 @@ impl StrokeStore {
         );
         self.render_components
             .insert(key, RenderComponent::default());
+        self.emit_event(|| StoreEvent::StrokeAdded(vec![key]));
 
         key
     }
@@ -348,19 +541,28 @@ impl StrokeStore {
         self.render_components.remove(key);
 
         self.key_tree.remove_with_key(key);
-        Arc::make_mut(&mut self.stroke_components)
+        let removed = Arc::make_mut(&mut self.stroke_components)
             .remove(key)
-            .map(|stroke| (*stroke).clone())
+            .map(|stroke| (*stroke).clone());
+        if removed.is_some() {
+            self.emit_event(|| StoreEvent::StrokeRemoved(vec![key]));
+        }
+        removed
     }
 
     /// Clears the entire store.
     pub(super) fn clear(&mut self) -> WidgetFlags {
+        if self.events_tx.is_some() {
+            let all_keys = self.keys_unordered();
+            self.emit_event(|| StoreEvent::StrokeRemoved(all_keys));
+        }
         Arc::make_mut(&mut self.stroke_components).clear();
         Arc::make_mut(&mut self.trash_components).clear();
         Arc::make_mut(&mut self.selection_components).clear();
         Arc::make_mut(&mut self.chrono_components).clear();
 
         self.chrono_counter = 0;
+        Arc::make_mut(&mut self.layer_props).clear();
         let widget_flags = self.clear_history(HistoryEntry::default());
 
         self.render_components.clear();
@@ -369,3 +571,93 @@ impl StrokeStore {
         widget_flags
     }
 }
+
+/// Estimate the size in bytes of the strokes that were introduced by the history entry compared to the previous entry,
+/// by the size of their serialized representation.
+fn estimate_history_entry_size(entry: &HistoryEntry, prev: Option<&HistoryEntry>) -> usize {
+    /// Counts the written bytes.
+    struct ByteCounter(usize);
+
+    impl std::io::Write for ByteCounter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    if prev.is_some_and(|prev| Arc::ptr_eq(&entry.stroke_components, &prev.stroke_components)) {
+        return 0;
+    }
+
+    entry
+        .stroke_components
+        .iter()
+        .filter(|(key, stroke)| {
+            prev.and_then(|prev| prev.stroke_components.get(*key))
+                .map(|prev_stroke| !Arc::ptr_eq(prev_stroke, stroke))
+                .unwrap_or(true)
+        })
+        .map(|(_, stroke)| {
+            let mut counter = ByteCounter(0);
+            if let Err(e) = serde_json::to_writer(&mut counter, stroke.as_ref()) {
+                debug!("Serializing stroke for estimating its size failed, Err: {e:?}");
+            }
+            counter.0
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::StrokeStore;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
+    use std::time::Instant;
+
+    fn insert_rect(store: &mut StrokeStore, offset: f64) {
+        store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![offset, offset],
+                    na::point![offset + 10.0, offset + 10.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+    }
+
+    #[test]
+    fn history_evicts_by_memory_budget() {
+        let mut store = StrokeStore::default();
+        insert_rect(&mut store, 0.0);
+        let _ = store.record(Instant::now());
+        let stroke_bytes = store.history_bytes();
+        assert!(stroke_bytes > 0);
+
+        // Small entries accumulate
+        store.set_max_history_bytes(stroke_bytes * 10);
+        for i in 1..5 {
+            insert_rect(&mut store, i as f64 * 20.0);
+            let _ = store.record(Instant::now());
+        }
+        assert_eq!(store.history.len(), 6);
+        assert!(store.history_bytes() >= stroke_bytes * 5);
+        assert!(store.history_bytes() <= store.max_history_bytes());
+
+        // A large entry evicts the oldest ones
+        for i in 0..8 {
+            insert_rect(&mut store, 200.0 + i as f64 * 20.0);
+        }
+        let _ = store.record(Instant::now());
+        assert!(store.history.len() < 6);
+        assert!(store.history_bytes() <= store.max_history_bytes());
+        assert!(store.can_undo());
+    }
+}
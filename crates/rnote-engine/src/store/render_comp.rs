@@ -7,6 +7,7 @@ use crate::{render, Drawable};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::ext::AabbExt;
 use rnote_compose::shapes::Shapeable;
+use std::sync::Arc;
 use tracing::error;
 
 /// The tolerance where check between scale-factors are considered "equal".
@@ -46,6 +47,47 @@ impl Default for RenderComponent {
 }
 
 impl StrokeStore {
+    /// The maximum of the minimum on-screen stroke width in pixels.
+    pub const MIN_SCREEN_WIDTH_PX_MAX: f64 = 10.0;
+    /// The default number of appended segment images of the currently drawn stroke, after which they are merged into a single image.
+    pub const CURRENT_STROKE_CACHE_THRESHOLD_DEFAULT: usize = 64;
+
+    /// The minimum width of rendered strokes in screen pixels. Zero when disabled.
+    pub fn min_screen_width_px(&self) -> f64 {
+        self.min_screen_width_px
+    }
+
+    /// Set the minimum width of rendered strokes in screen pixels. Zero disables it.
+    ///
+    /// The rendering of the strokes needs to be regenerated afterwards.
+    pub fn set_min_screen_width_px(&mut self, min_screen_width_px: f64) {
+        self.min_screen_width_px = min_screen_width_px.clamp(0.0, Self::MIN_SCREEN_WIDTH_PX_MAX);
+    }
+
+    /// The number of appended segment images of the currently drawn stroke, after which they are merged into a single image.
+    /// Zero when disabled.
+    pub fn current_stroke_cache_threshold(&self) -> usize {
+        self.current_stroke_cache_threshold
+    }
+
+    /// Set the number of appended segment images of the currently drawn stroke, after which they are merged into a single image.
+    /// Zero disables the cache.
+    pub fn set_current_stroke_cache_threshold(&mut self, threshold: usize) {
+        self.current_stroke_cache_threshold = threshold;
+    }
+
+    /// The anti-aliasing quality the stroke images are rendered with.
+    pub fn aa_quality(&self) -> render::AaQuality {
+        self.aa_quality
+    }
+
+    /// Set the anti-aliasing quality the stroke images are rendered with.
+    ///
+    /// The rendering of the strokes needs to be regenerated afterwards.
+    pub fn set_aa_quality(&mut self, aa_quality: render::AaQuality) {
+        self.aa_quality = aa_quality;
+    }
+
     /// Rebuild the slotmap with empty render components with the keys returned from the stroke components.
     pub(crate) fn rebuild_render_components_slotmap(&mut self) {
         self.render_components = slotmap::SecondaryMap::new();
@@ -110,10 +152,12 @@ impl StrokeStore {
                 return;
             }
 
+            let stroke = stroke_for_rendering(stroke, self.min_screen_width_px, image_scale);
+            let aa_quality = self.aa_quality;
             let viewport_extended =
                 viewport.extend_by(viewport.extents() * render::VIEWPORT_EXTENTS_MARGIN_FACTOR);
 
-            match stroke.gen_images(viewport_extended, image_scale) {
+            match stroke.gen_images(viewport_extended, image_scale, aa_quality) {
                 Ok(GeneratedContentImages::Partial { images, viewport }) => {
                     #[cfg(feature = "ui")]
                     match render::Image::images_to_rendernodes(&images) {
@@ -190,7 +234,8 @@ impl StrokeStore {
                 return;
             }
 
-            let stroke = stroke.clone();
+            let stroke = stroke_for_rendering(stroke, self.min_screen_width_px, image_scale);
+            let aa_quality = self.aa_quality;
             let viewport_extended =
                 viewport.extend_by(viewport.extents() * render::VIEWPORT_EXTENTS_MARGIN_FACTOR);
 
@@ -198,8 +243,8 @@ impl StrokeStore {
             render_comp.state = RenderCompState::BusyRenderingInTask;
 
             // Spawn a new thread for image rendering
-            rayon::spawn(
-                move || match stroke.gen_images(viewport_extended, image_scale) {
+            rayon::spawn(move || {
+                match stroke.gen_images(viewport_extended, image_scale, aa_quality) {
                     Ok(images) => {
                         tasks_tx.send(EngineTask::UpdateStrokeWithImages {
                             key,
@@ -212,8 +257,8 @@ impl StrokeStore {
                             "Generating images of stroke failed while regenerating stroke rendering, stroke key {key:?} , Err: {e:?}"
                         );
                     }
-                },
-            );
+                }
+            });
         }
     }
 
@@ -293,11 +338,12 @@ impl StrokeStore {
 
                 // indicates that a task has now started to render the stroke
                 render_comp.state = RenderCompState::BusyRenderingInTask;
-                let stroke = stroke.clone();
+                let stroke = stroke_for_rendering(stroke, self.min_screen_width_px, image_scale);
+                let aa_quality = self.aa_quality;
 
                 // Spawn a new thread for image rendering
-                rayon::spawn(
-                    move || match stroke.gen_images(viewport_extended, image_scale) {
+                rayon::spawn(move || {
+                    match stroke.gen_images(viewport_extended, image_scale, aa_quality) {
                         Ok(images) => {
                             tasks_tx.send(EngineTask::UpdateStrokeWithImages {
                                 key,
@@ -310,8 +356,8 @@ impl StrokeStore {
                                 "Generating stroke images failed stroke while regenerating rendering in viewport `{viewport:?}`, stroke key: {key:?}, Err: {e:?}"
                             );
                         }
-                    },
-                );
+                    }
+                });
             }
         }
     }
@@ -331,6 +377,11 @@ impl StrokeStore {
     /// Generate images and appends them to the render component for the last segments of brushstrokes.
     ///
     /// For other strokes the rendering is regenerated completely.
+    ///
+    /// While drawing, every call appends a new image. Drawing all of them becomes expensive for long strokes,
+    /// so when their number exceeds the current stroke cache threshold, the already rendered images are merged into a single cached
+    /// prefix image. Only the new segments are rasterized, the prefix is never regenerated from the stroke.
+    /// The cached prefix is discarded when the rendering is regenerated after the stroke is finished.
     pub(crate) fn append_rendering_last_segments(
         &mut self,
         tasks_tx: EngineTaskSender,
@@ -343,10 +394,21 @@ impl StrokeStore {
             self.stroke_components.get(key),
             self.render_components.get_mut(key),
         ) {
+            let stroke = stroke_for_rendering(stroke, self.min_screen_width_px, image_scale);
+            let aa_quality = self.aa_quality;
             match stroke.as_ref() {
                 Stroke::BrushStroke(brushstroke) => {
-                    match brushstroke.gen_image_for_last_segments(n_last_segments, image_scale) {
+                    match brushstroke.gen_image_for_last_segments(
+                        n_last_segments,
+                        image_scale,
+                        aa_quality,
+                    ) {
                         Ok(Some(image)) => {
+                            if self.current_stroke_cache_threshold != 0
+                                && render_comp.images.len() >= self.current_stroke_cache_threshold
+                            {
+                                merge_rendering_into_prefix(render_comp, image_scale, aa_quality);
+                            }
                             #[cfg(feature = "ui")]
                             match render::Image::images_to_rendernodes([&image]) {
                                 Ok(mut rendernodes) => {
@@ -485,16 +547,19 @@ impl StrokeStore {
         viewport: Aabb,
     ) {
         use crate::ext::{GdkRGBAExt, GrapheneRectExt};
-        use gtk4::{gdk, graphene, prelude::*};
+        use gtk4::{gdk, graphene, gsk, prelude::*};
         use rnote_compose::color;
 
         snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds));
 
-        for key in self.stroke_keys_as_rendered_intersecting_bounds(viewport) {
-            if let (Some(stroke), Some(render_comp)) = (
-                self.stroke_components.get(key),
-                self.render_components.get(key),
-            ) {
+        let draw_keys = |keys: &[StrokeKey]| {
+            for &key in keys {
+                let (Some(stroke), Some(render_comp)) = (
+                    self.stroke_components.get(key),
+                    self.render_components.get(key),
+                ) else {
+                    continue;
+                };
                 // if the stroke currently does not have a rendering and is will create one,
                 // draw a placeholder filled rect
                 if render_comp.rendernodes.is_empty()
@@ -513,6 +578,19 @@ impl StrokeStore {
                     snapshot.append_node(rendernode);
                 }
             }
+        };
+
+        for group in self.clip_groups(self.stroke_keys_as_rendered_intersecting_bounds(viewport)) {
+            draw_keys(&group.base);
+
+            if !group.clipped.is_empty() {
+                // the mask is recorded until the first pop, the masked source until the second.
+                snapshot.push_mask(gsk::MaskMode::Alpha);
+                draw_keys(&group.base);
+                snapshot.pop();
+                draw_keys(&group.clipped);
+                snapshot.pop();
+            }
         }
 
         snapshot.pop();
@@ -651,3 +729,282 @@ impl StrokeStore {
         Ok(())
     }
 }
+
+/// Merge all current images of the render component into a single image.
+///
+/// On failure the images are left untouched.
+fn merge_rendering_into_prefix(
+    render_comp: &mut RenderComponent,
+    image_scale: f64,
+    aa_quality: render::AaQuality,
+) {
+    let Some(bounds) = render_comp
+        .images
+        .iter()
+        .map(|image| image.rect.bounds())
+        .reduce(|acc, bounds| acc.merged(&bounds))
+    else {
+        return;
+    };
+    let images = &render_comp.images;
+    let prefix = match render::Image::gen_with_piet_aa(
+        |piet_cx| {
+            for image in images {
+                image.draw(piet_cx, image_scale)?;
+            }
+            Ok(())
+        },
+        bounds,
+        image_scale,
+        aa_quality,
+    ) {
+        Ok(prefix) => prefix,
+        Err(e) => {
+            error!("Merging images of the currently drawn stroke failed, Err: {e:?}");
+            return;
+        }
+    };
+    #[cfg(feature = "ui")]
+    match prefix.to_rendernode() {
+        Ok(rendernode) => {
+            render_comp.rendernodes = vec![rendernode];
+            render_comp.images = vec![prefix];
+        }
+        Err(e) => {
+            error!("Generating rendernode for the merged images of the currently drawn stroke failed, Err: {e:?}");
+        }
+    }
+    #[cfg(not(feature = "ui"))]
+    {
+        render_comp.images = vec![prefix];
+    }
+}
+
+/// The stroke as it should be rendered, with its stroke width clamped to the minimum on-screen width.
+fn stroke_for_rendering(
+    stroke: &Arc<Stroke>,
+    min_screen_width_px: f64,
+    image_scale: f64,
+) -> Arc<Stroke> {
+    if min_screen_width_px <= 0.0 {
+        return Arc::clone(stroke);
+    }
+    stroke
+        .with_min_stroke_width(min_screen_width_px / image_scale)
+        .map(Arc::new)
+        .unwrap_or_else(|| Arc::clone(stroke))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::chrono_comp::StrokeLayer;
+    use crate::store::LayerProps;
+    use crate::strokes::{BrushStroke, ShapeStroke};
+    use crate::Engine;
+    use approx::assert_relative_eq;
+    use rnote_compose::penpath::{Element, Segment};
+    use rnote_compose::shapes::{Line, Rectangle, Shape};
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::Style;
+    use std::time::{Duration, Instant};
+
+    /// A frame while drawing a brush stroke: the time spent appending the segment and the bounds of the images
+    /// that need to be drawn afterwards.
+    type Frame = (Duration, Vec<Aabb>);
+
+    /// Draw a brush stroke segment by segment like the brush does, returning the frames for each appended segment
+    /// and the bounds of the stroke.
+    fn draw_brush_stroke(cache_threshold: usize, n_segments: usize) -> (Vec<Frame>, Aabb) {
+        let mut engine = Engine::default();
+        engine
+            .store
+            .set_current_stroke_cache_threshold(cache_threshold);
+        let viewport = Aabb::new(na::point![-10.0, -10.0], na::point![510.0, 510.0]);
+        let key = engine.store.insert_stroke(
+            Stroke::BrushStroke(BrushStroke::new(
+                Element::new(na::vector![0.0, 0.0], 0.5),
+                Style::Smooth(SmoothOptions::default()),
+            )),
+            None,
+        );
+
+        let frames = (1..=n_segments)
+            .map(|i| {
+                let t = i as f64 / n_segments as f64;
+                let end = Element::new(
+                    na::vector![t * 500.0, 250.0 + 200.0 * (t * 40.0).sin()],
+                    0.5,
+                );
+                let start = Instant::now();
+                if let Some(Stroke::BrushStroke(brushstroke)) = engine.store.get_stroke_mut(key) {
+                    brushstroke.push_segment(Segment::LineTo { end });
+                }
+                engine.store.append_rendering_last_segments(
+                    engine.engine_tasks_tx(),
+                    key,
+                    1,
+                    viewport,
+                    1.0,
+                );
+                let elapsed = start.elapsed();
+                let images = engine
+                    .store
+                    .render_components
+                    .get(key)
+                    .unwrap()
+                    .images
+                    .iter()
+                    .map(|image| image.rect.bounds())
+                    .collect();
+                (elapsed, images)
+            })
+            .collect();
+        let bounds = engine.store.get_stroke_ref(key).unwrap().bounds();
+        (frames, bounds)
+    }
+
+    #[test]
+    fn current_stroke_cache_bounds_appended_images() {
+        let (uncached, _) = draw_brush_stroke(0, 100);
+        assert_eq!(uncached.last().unwrap().1.len(), 100);
+
+        let (cached, bounds) = draw_brush_stroke(16, 100);
+        assert!(cached.iter().all(|(_, images)| images.len() <= 16));
+        // the merged prefix and the newly appended segments still cover the entire stroke
+        let rendered = cached
+            .last()
+            .unwrap()
+            .1
+            .iter()
+            .copied()
+            .reduce(|acc, image_bounds| acc.merged(&image_bounds))
+            .unwrap();
+        assert!(rendered.contains(&bounds));
+    }
+
+    /// Benchmarks the growth of the per-frame cost while drawing long strokes.
+    ///
+    /// Run with `cargo test -p rnote-engine --release -- --ignored --nocapture bench_current_stroke`.
+    #[test]
+    #[ignore]
+    fn bench_current_stroke_frame_cost() {
+        const N_SEGMENTS: usize = 4000;
+        const WINDOW: usize = 500;
+
+        for (name, threshold) in [
+            ("without cache", 0),
+            (
+                "with cache",
+                StrokeStore::CURRENT_STROKE_CACHE_THRESHOLD_DEFAULT,
+            ),
+        ] {
+            println!("{name}:");
+            let (frames, _) = draw_brush_stroke(threshold, N_SEGMENTS);
+            for (i, window) in frames.chunks(WINDOW).enumerate() {
+                let mean = window.iter().map(|(d, _)| *d).sum::<Duration>() / window.len() as u32;
+                let max_images = window
+                    .iter()
+                    .map(|(_, images)| images.len())
+                    .max()
+                    .unwrap_or(0);
+                println!(
+                    "  segments {:>5}..{:>5}: {mean:>10.2?} per frame, up to {max_images} images to draw",
+                    i * WINDOW,
+                    (i + 1) * WINDOW
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn min_screen_width_clamps_at_low_zoom() {
+        let options = SmoothOptions {
+            stroke_width: 1.0,
+            ..Default::default()
+        };
+        let stroke = Arc::new(Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Line(Line {
+                start: na::vector![0.0, 0.0],
+                end: na::vector![100.0, 0.0],
+            }),
+            Style::Smooth(options),
+        )));
+        let rendered_width = |stroke: &Stroke| match stroke {
+            Stroke::ShapeStroke(shape_stroke) => shape_stroke.style.stroke_width(),
+            _ => unreachable!(),
+        };
+        // At a zoom of 0.25 a width of 1.0 covers a quarter of a screen pixel.
+        let image_scale = 0.25;
+
+        let unclamped = stroke_for_rendering(&stroke, 0.0, image_scale);
+        assert_relative_eq!(rendered_width(&unclamped) * image_scale, 0.25);
+
+        let clamped = stroke_for_rendering(&stroke, 1.0, image_scale);
+        assert_relative_eq!(rendered_width(&clamped) * image_scale, 1.0);
+        // the stored stroke is unaltered
+        assert_relative_eq!(rendered_width(&stroke), 1.0);
+
+        // strokes that are wide enough on screen are not touched
+        let high_zoom = stroke_for_rendering(&stroke, 1.0, 2.0);
+        assert!(Arc::ptr_eq(&high_zoom, &stroke));
+    }
+
+    /// Run with `cargo test -p rnote-engine --features ui -- --ignored clip_below` in a graphical session.
+    #[cfg(feature = "ui")]
+    #[test]
+    #[ignore = "rendering the gtk snapshot needs a display"]
+    fn clip_below_masks_to_layer_below() {
+        use gtk4::{gdk, graphene, gsk, prelude::*};
+
+        gtk4::init().unwrap();
+        let filled_rect = |bounds: Aabb| {
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(bounds)),
+                Style::Smooth(SmoothOptions {
+                    stroke_color: None,
+                    fill_color: Some(rnote_compose::Color::BLACK),
+                    ..Default::default()
+                }),
+            ))
+        };
+        let viewport = Aabb::new(na::point![0.0, 0.0], na::point![40.0, 40.0]);
+        let mut store = StrokeStore::default();
+        let keys = [
+            store.insert_stroke(
+                filled_rect(Aabb::new(na::point![0.0, 0.0], na::point![10.0, 10.0])),
+                Some(StrokeLayer::UserLayer(0)),
+            ),
+            store.insert_stroke(filled_rect(viewport), Some(StrokeLayer::UserLayer(1))),
+        ];
+        store.set_layer_props(
+            1,
+            LayerProps {
+                clip_below: true,
+                ..Default::default()
+            },
+        );
+        for key in keys {
+            store.regenerate_rendering_for_stroke(key, viewport, 1.0);
+        }
+
+        let snapshot = gtk4::Snapshot::new();
+        store.draw_strokes_to_gtk_snapshot(&snapshot, viewport, viewport);
+        let node = snapshot.to_node().unwrap();
+        let renderer = gsk::CairoRenderer::new();
+        renderer.realize(None::<&gdk::Surface>).unwrap();
+        let texture =
+            renderer.render_texture(&node, Some(&graphene::Rect::new(0.0, 0.0, 40.0, 40.0)));
+        renderer.unrealize();
+        let mut downloader = gdk::TextureDownloader::new(&texture);
+        downloader.set_format(gdk::MemoryFormat::R8g8b8a8Premultiplied);
+        let (data, stride) = downloader.download_bytes();
+        let alpha = |x: usize, y: usize| data[y * stride + x * 4 + 3];
+
+        // inside the layer below the clipped layer is visible
+        assert_eq!(alpha(5, 5), 255);
+        // outside the layer below the mask is empty, so nothing is drawn
+        assert_eq!(alpha(30, 30), 0);
+        assert_eq!(alpha(5, 30), 0);
+    }
+}
@@ -1,9 +1,10 @@
 // Imports
 use super::render_comp::RenderCompState;
-use super::{StrokeKey, StrokeStore};
+use super::{StoreEvent, StrokeKey, StrokeStore};
 use crate::strokes::content::GeneratedContentImages;
 use crate::strokes::Stroke;
 use p2d::bounding_volume::Aabb;
+use rnote_compose::shapes::Line;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -43,15 +44,22 @@ impl StrokeStore {
             .map(|selection_comp| selection_comp.selected)
     }
 
-    /// Set if the stroke is currently selected.
+    /// Select or deselect the stroke. Locked strokes can't be selected.
     pub(crate) fn set_selected(&mut self, key: StrokeKey, selected: bool) {
+        if selected && self.locked(key).unwrap_or(false) {
+            return;
+        }
         if let Some(selection_comp) = Arc::make_mut(&mut self.selection_components)
             .get_mut(key)
             .map(Arc::make_mut)
         {
+            let changed = selection_comp.selected != selected;
             selection_comp.selected = selected;
 
             self.update_chrono_to_last(key);
+            if changed {
+                self.emit_event(|| StoreEvent::SelectionChanged(vec![key]));
+            }
         }
     }
 
@@ -131,4 +139,87 @@ impl StrokeStore {
 
         new_selected
     }
+
+    /// Duplicate the strokes `count` times, each copy translated by the offset and rotated by the rotation step
+    /// (in radians) around its bounds center relative to the previous copy.
+    ///
+    /// The copies are selected instead of the original strokes. Their keys are returned in the order of the copies.
+    ///
+    /// The returned, duplicated strokes then need to update their geometry and rendering.
+    pub(crate) fn array_duplicate_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+        count: usize,
+        offset: na::Vector2<f64>,
+        rotation_step: f64,
+    ) -> Vec<StrokeKey> {
+        let Some(bounds) = self.bounds_for_strokes(keys) else {
+            return vec![];
+        };
+        self.set_selected_keys(keys, false);
+
+        let mut new_keys = Vec::with_capacity(keys.len() * count);
+        for i in 1..=count {
+            let copy_offset = offset * i as f64;
+            let copy_keys = keys
+                .iter()
+                .filter_map(|&key| {
+                    let stroke = (**self.stroke_components.get(key)?).clone();
+                    let layer = self.layer(key);
+                    Some(self.insert_stroke(stroke, layer))
+                })
+                .collect::<Vec<StrokeKey>>();
+            self.translate_strokes(&copy_keys, copy_offset);
+            if rotation_step != 0.0 {
+                self.rotate_strokes(
+                    &copy_keys,
+                    rotation_step * i as f64,
+                    bounds.center() + copy_offset,
+                );
+            }
+            self.set_selected_keys(&copy_keys, true);
+            new_keys.extend(copy_keys);
+        }
+
+        new_keys
+    }
+
+    /// Duplicate the strokes mirrored across the axis line, keeping the original strokes.
+    ///
+    /// The copies are selected instead of the original strokes and are inserted on the layers of their originals. Returns
+    /// no keys when the axis line has no length.
+    ///
+    /// The returned, duplicated strokes then need to update their geometry and rendering.
+    pub(crate) fn mirror_duplicate_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+        axis_line: Line,
+    ) -> Vec<StrokeKey> {
+        let direction = axis_line.end - axis_line.start;
+        if direction.magnitude() == 0.0 {
+            return vec![];
+        }
+        let direction = direction.normalize();
+        // The reflection across the line through the origin, followed by the translation that keeps the axis in place
+        let reflection = 2.0 * direction * direction.transpose() - na::Matrix2::identity();
+        let translation = axis_line.start - reflection * axis_line.start;
+        let mut matrix = na::Matrix3::identity();
+        matrix.fixed_view_mut::<2, 2>(0, 0).copy_from(&reflection);
+        matrix.fixed_view_mut::<2, 1>(0, 2).copy_from(&translation);
+        let transform = na::Affine2::from_matrix_unchecked(matrix);
+
+        let new_keys = keys
+            .iter()
+            .filter_map(|&key| {
+                let stroke = (**self.stroke_components.get(key)?).clone();
+                let layer = self.layer(key);
+                Some(self.insert_stroke(stroke, layer))
+            })
+            .collect::<Vec<StrokeKey>>();
+        self.transform_strokes(&new_keys, transform, false);
+        self.set_selected_keys(keys, false);
+        self.set_selected_keys(&new_keys, true);
+
+        new_keys
+    }
 }
@@ -1,11 +1,10 @@
 // Imports
 use super::render_comp::RenderCompState;
-use super::StrokeKey;
+use super::{StoreEvent, StrokeKey};
 use crate::engine::StrokeContent;
 use crate::strokes::{Content, Stroke};
-use crate::{StrokeStore, WidgetFlags};
+use crate::{SelectionCollision, StrokeStore, WidgetFlags};
 use geo::intersects::Intersects;
-use geo::prelude::Contains;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::penpath::Element;
 use rnote_compose::shapes::Shapeable;
@@ -15,6 +14,134 @@ use std::sync::Arc;
 #[cfg(feature = "ui")]
 use tracing::error;
 
+/// How strokes get aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// Align the left edges.
+    Left,
+    /// Align the right edges.
+    Right,
+    /// Align the top edges.
+    Top,
+    /// Align the bottom edges.
+    Bottom,
+    /// Align the horizontal centers.
+    CenterH,
+    /// Align the vertical centers.
+    CenterV,
+}
+
+/// The axis along which strokes get distributed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistributeAxis {
+    /// Distribute horizontally.
+    Horizontal,
+    /// Distribute vertically.
+    Vertical,
+}
+
+/// Which stroke is picked when multiple strokes overlap at the hit-tested position.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PickMode {
+    /// The stroke that is drawn on top of the others.
+    #[default]
+    Topmost,
+    /// The stroke with the hitbox center closest to the position, which approximates its center-line.
+    ///
+    /// Equally near strokes are picked topmost first.
+    Nearest,
+}
+
+/// The rule deciding which regions of a self-intersecting polygon are inside.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FillRule {
+    /// A point is inside when a ray from it crosses the polygon an odd number of times.
+    #[default]
+    EvenOdd,
+    /// A point is inside when the polygon winds around it a non-zero number of times.
+    NonZero,
+}
+
+impl FillRule {
+    /// The distance of the probes to both sides of a polygon edge, when checking if the edge separates inside regions.
+    const EDGE_PROBE_DIST: f64 = 1e-6;
+
+    /// Whether the point is inside the polygon.
+    fn polygon_contains_point(self, polygon: &[na::Point2<f64>], point: na::Point2<f64>) -> bool {
+        let mut crossings = 0;
+        let mut winding = 0;
+
+        for (start, end) in polygon_edges(polygon) {
+            let side = (end - start).perp(&(point - start));
+
+            if start[1] <= point[1] {
+                if end[1] > point[1] && side > 0.0 {
+                    crossings += 1;
+                    winding += 1;
+                }
+            } else if end[1] <= point[1] && side < 0.0 {
+                crossings += 1;
+                winding -= 1;
+            }
+        }
+
+        match self {
+            FillRule::EvenOdd => crossings % 2 == 1,
+            FillRule::NonZero => winding != 0,
+        }
+    }
+
+    /// Whether the Aabb is completely inside the polygon.
+    fn polygon_contains_aabb(self, polygon: &[na::Point2<f64>], aabb: Aabb) -> bool {
+        if !aabb
+            .vertices()
+            .into_iter()
+            .all(|corner| self.polygon_contains_point(polygon, corner))
+        {
+            return false;
+        }
+
+        // Edges crossing the Aabb must only separate inside regions,
+        // which happens with overlapping loops of self-intersecting polygons.
+        for (start, end) in polygon_edges(polygon) {
+            let edge_vec = end - start;
+            let edge_len = edge_vec.magnitude();
+            if edge_len <= 0.0 {
+                continue;
+            }
+            let normal = na::vector![-edge_vec[1], edge_vec[0]] / edge_len;
+            for (aabb_start, aabb_end) in aabb_edges(aabb) {
+                let Some(crossing) = segments_intersection(start, end, aabb_start, aabb_end) else {
+                    continue;
+                };
+                if !self.polygon_contains_point(polygon, crossing + normal * Self::EDGE_PROBE_DIST)
+                    || !self
+                        .polygon_contains_point(polygon, crossing - normal * Self::EDGE_PROBE_DIST)
+                {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Whether the Aabb is at least partially inside the polygon.
+    fn polygon_intersects_aabb(self, polygon: &[na::Point2<f64>], aabb: Aabb) -> bool {
+        aabb.vertices()
+            .into_iter()
+            .any(|corner| self.polygon_contains_point(polygon, corner))
+            || polygon
+                .iter()
+                .any(|vertex| aabb.contains_local_point(vertex))
+            || polygon_edges(polygon).any(|(start, end)| {
+                aabb_edges(aabb).into_iter().any(|(aabb_start, aabb_end)| {
+                    segments_intersection(start, end, aabb_start, aabb_end).is_some()
+                })
+            })
+    }
+}
+
 /// Systems that are related to the stroke components.
 impl StrokeStore {
     /// Gets a immutable reference to a stroke.
@@ -67,11 +194,16 @@ impl StrokeStore {
             .collect()
     }
 
+    /// Whether the stroke is trashed or hidden, and therefore is neither rendered nor hit-tested.
+    fn trashed_or_hidden(&self, key: StrokeKey) -> bool {
+        self.trashed(key).unwrap_or(false) || self.hidden(key).unwrap_or(false)
+    }
+
     /// Storke keys in the order that they should be rendered.
     pub(crate) fn stroke_keys_as_rendered(&self) -> Vec<StrokeKey> {
         self.keys_sorted_chrono()
             .into_iter()
-            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter(|&key| !self.trashed_or_hidden(key))
             .collect::<Vec<StrokeKey>>()
     }
 
@@ -82,7 +214,7 @@ impl StrokeStore {
     ) -> Vec<StrokeKey> {
         self.keys_sorted_chrono_intersecting_bounds(bounds)
             .into_iter()
-            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter(|&key| !self.trashed_or_hidden(key))
             .collect::<Vec<StrokeKey>>()
     }
 
@@ -90,10 +222,28 @@ impl StrokeStore {
     pub(crate) fn stroke_keys_as_rendered_in_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
         self.keys_sorted_chrono_in_bounds(bounds)
             .into_iter()
-            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter(|&key| !self.trashed_or_hidden(key))
             .collect::<Vec<StrokeKey>>()
     }
 
+    /// The stroke endpoint that is closest to the position, if there is one within the maximum distance.
+    pub(crate) fn nearest_stroke_endpoint(
+        &self,
+        pos: na::Vector2<f64>,
+        max_dist: f64,
+    ) -> Option<na::Vector2<f64>> {
+        let search_bounds = Aabb::from_half_extents(pos.into(), na::Vector2::repeat(max_dist));
+
+        self.stroke_keys_as_rendered_intersecting_bounds(search_bounds)
+            .into_iter()
+            .filter_map(|key| self.stroke_components.get(key))
+            .flat_map(|stroke| stroke.endpoints())
+            .map(|endpoint| (endpoint, (endpoint - pos).norm()))
+            .filter(|&(_, dist)| dist <= max_dist)
+            .min_by(|(_, first), (_, second)| first.total_cmp(second))
+            .map(|(endpoint, _)| endpoint)
+    }
+
     /// Clone the strokes for the given keys.
     #[allow(unused)]
     pub(crate) fn clone_strokes(&self, keys: &[StrokeKey]) -> Vec<Stroke> {
@@ -113,6 +263,7 @@ impl StrokeStore {
             stroke.update_geometry();
             self.key_tree.update_with_key(key, stroke.bounds());
             self.set_rendering_dirty(key);
+            self.emit_event(|| StoreEvent::StrokeModified(vec![key]));
         }
     }
 
@@ -207,6 +358,101 @@ impl StrokeStore {
         });
     }
 
+    /// Align the strokes to the edges or centers of their enclosing bounds.
+    ///
+    /// Returns the keys of the strokes that were moved, which then need to update their geometry and rendering.
+    pub(crate) fn align_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+        alignment: Alignment,
+    ) -> Vec<StrokeKey> {
+        if keys.len() < 2 {
+            return vec![];
+        }
+        let Some(all_bounds) = self.bounds_for_strokes(keys) else {
+            return vec![];
+        };
+
+        keys.iter()
+            .filter_map(|&key| {
+                let bounds = self.stroke_components.get(key)?.bounds();
+                let offset = match alignment {
+                    Alignment::Left => na::vector![all_bounds.mins[0] - bounds.mins[0], 0.0],
+                    Alignment::Right => na::vector![all_bounds.maxs[0] - bounds.maxs[0], 0.0],
+                    Alignment::Top => na::vector![0.0, all_bounds.mins[1] - bounds.mins[1]],
+                    Alignment::Bottom => na::vector![0.0, all_bounds.maxs[1] - bounds.maxs[1]],
+                    Alignment::CenterH => {
+                        na::vector![all_bounds.center()[0] - bounds.center()[0], 0.0]
+                    }
+                    Alignment::CenterV => {
+                        na::vector![0.0, all_bounds.center()[1] - bounds.center()[1]]
+                    }
+                };
+                if offset == na::Vector2::zeros() {
+                    return None;
+                }
+                self.translate_strokes(&[key], offset);
+                self.translate_strokes_images(&[key], offset);
+                Some(key)
+            })
+            .collect()
+    }
+
+    /// Distribute the strokes along the axis, so that the gaps between them are equal.
+    ///
+    /// The outermost strokes stay in place.
+    /// Returns the keys of the strokes that were moved, which then need to update their geometry and rendering.
+    pub(crate) fn distribute_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+        axis: DistributeAxis,
+    ) -> Vec<StrokeKey> {
+        let dim = match axis {
+            DistributeAxis::Horizontal => 0,
+            DistributeAxis::Vertical => 1,
+        };
+        let mut keys_bounds = keys
+            .iter()
+            .filter_map(|&key| Some((key, self.stroke_components.get(key)?.bounds())))
+            .collect::<Vec<(StrokeKey, Aabb)>>();
+        if keys_bounds.len() < 3 {
+            return vec![];
+        }
+        keys_bounds.sort_by(|(_, first), (_, second)| {
+            first.center()[dim].total_cmp(&second.center()[dim])
+        });
+
+        let start = keys_bounds
+            .iter()
+            .map(|(_, b)| b.mins[dim])
+            .fold(f64::MAX, f64::min);
+        let end = keys_bounds
+            .iter()
+            .map(|(_, b)| b.maxs[dim])
+            .fold(f64::MIN, f64::max);
+        let extents_sum = keys_bounds
+            .iter()
+            .map(|(_, b)| b.extents()[dim])
+            .sum::<f64>();
+        let gap = (end - start - extents_sum) / (keys_bounds.len() - 1) as f64;
+
+        let mut pos = start;
+        keys_bounds
+            .into_iter()
+            .filter_map(|(key, bounds)| {
+                let mut offset = na::Vector2::<f64>::zeros();
+                offset[dim] = pos - bounds.mins[dim];
+                pos += bounds.extents()[dim] + gap;
+                if offset == na::Vector2::zeros() {
+                    return None;
+                }
+                self.translate_strokes(&[key], offset);
+                self.translate_strokes_images(&[key], offset);
+                Some(key)
+            })
+            .collect()
+    }
+
     /// Translate the stroke rendering images.
     ///
     /// The strokes then need to update their rendering.
@@ -257,6 +503,26 @@ impl StrokeStore {
         });
     }
 
+    /// Transform the strokes with the affine transformation, see [Stroke::transform].
+    ///
+    /// The strokes then need to update their geometry and rendering.
+    pub(crate) fn transform_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+        transform: na::Affine2<f64>,
+        scale_width: bool,
+    ) {
+        keys.iter().for_each(|&key| {
+            if let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
+                .get_mut(key)
+                .map(Arc::make_mut)
+            {
+                stroke.transform(transform, scale_width);
+                self.key_tree.update_with_key(key, stroke.bounds());
+            }
+        });
+    }
+
     /// Change the stroke and text color for the given keys.
     ///
     /// The strokes then need to update their rendering.
@@ -267,6 +533,7 @@ impl StrokeStore {
             return widget_flags;
         }
 
+        let mut modified_keys = Vec::with_capacity(keys.len());
         keys.iter().for_each(|&key| {
             if let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
                 .get_mut(key)
@@ -277,14 +544,17 @@ impl StrokeStore {
                         Stroke::BrushStroke(brush_stroke) => {
                             brush_stroke.style.set_stroke_color(color);
                             self.set_rendering_dirty(key);
+                            modified_keys.push(key);
                         }
                         Stroke::ShapeStroke(shape_stroke) => {
                             shape_stroke.style.set_stroke_color(color);
                             self.set_rendering_dirty(key);
+                            modified_keys.push(key);
                         }
                         Stroke::TextStroke(text_stroke) => {
                             text_stroke.text_style.color = color;
                             self.set_rendering_dirty(key);
+                            modified_keys.push(key);
                         }
                         _ => {}
                     }
@@ -292,6 +562,10 @@ impl StrokeStore {
             }
         });
 
+        if !modified_keys.is_empty() {
+            self.emit_event(|| StoreEvent::StrokeModified(modified_keys));
+        }
+
         widget_flags.redraw = true;
         widget_flags.store_modified = true;
 
@@ -308,6 +582,7 @@ impl StrokeStore {
             return widget_flags;
         }
 
+        let mut modified_keys = Vec::with_capacity(keys.len());
         keys.iter().for_each(|&key| {
             if let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
                 .get_mut(key)
@@ -317,10 +592,15 @@ impl StrokeStore {
 
                 if stroke_modified {
                     self.set_rendering_dirty(key);
+                    modified_keys.push(key);
                 }
             }
         });
 
+        if !modified_keys.is_empty() {
+            self.emit_event(|| StoreEvent::StrokeModified(modified_keys));
+        }
+
         widget_flags.redraw = true;
         widget_flags.store_modified = true;
 
@@ -337,6 +617,7 @@ impl StrokeStore {
             return widget_flags;
         }
 
+        let mut modified_keys = Vec::with_capacity(keys.len());
         keys.iter().for_each(|&key| {
             if let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
                 .get_mut(key)
@@ -347,10 +628,12 @@ impl StrokeStore {
                         Stroke::BrushStroke(brush_stroke) => {
                             brush_stroke.style.set_fill_color(color);
                             self.set_rendering_dirty(key);
+                            modified_keys.push(key);
                         }
                         Stroke::ShapeStroke(shape_stroke) => {
                             shape_stroke.style.set_fill_color(color);
                             self.set_rendering_dirty(key);
+                            modified_keys.push(key);
                         }
                         _ => {}
                     }
@@ -358,6 +641,10 @@ impl StrokeStore {
             }
         });
 
+        if !modified_keys.is_empty() {
+            self.emit_event(|| StoreEvent::StrokeModified(modified_keys));
+        }
+
         widget_flags.redraw = true;
         widget_flags.store_modified = true;
 
@@ -465,57 +752,112 @@ impl StrokeStore {
         self.translate_strokes_images(strokes, pivot);
     }
 
-    /// Return the keys for stroke whose hitboxes are contained in the given polygon path.
-    pub(crate) fn strokes_hitboxes_contained_in_path_polygon(
-        &mut self,
-        path: &[Element],
-        viewport: Aabb,
+    /// Return the keys for strokes that are inside the given polygon.
+    ///
+    /// Depending on the collision, strokes must be completely contained in it or only intersect it.
+    /// Which regions of self-intersecting polygons are considered inside is decided by the fill rule.
+    pub fn strokes_in_polygon(
+        &self,
+        polygon: &[na::Point2<f64>],
+        collision: SelectionCollision,
+        fill_rule: FillRule,
     ) -> Vec<StrokeKey> {
-        let mut bounds = viewport;
-        for p in path {
-            bounds.take_point(p.pos.into());
+        if polygon.len() < 3 {
+            return vec![];
+        }
+        let mut bounds = Aabb::new_invalid();
+        for p in polygon {
+            bounds.take_point(*p);
         }
-
-        let path_polygon = {
-            let selector_path_points = path
-                .iter()
-                .map(|element| geo::Coord {
-                    x: element.pos[0],
-                    y: element.pos[1],
-                })
-                .collect::<Vec<geo::Coord<f64>>>();
-
-            geo::Polygon::new(selector_path_points.into(), vec![])
-        };
 
         self.keys_sorted_chrono_intersecting_bounds(bounds)
             .into_iter()
             .filter_map(|key| {
-                // skip if stroke is trashed
-                if self.trashed(key)? {
+                // skip if stroke is trashed or hidden
+                if self.trashed_or_hidden(key) {
                     return None;
                 }
 
                 let stroke = self.stroke_components.get(key)?;
                 let stroke_bounds = stroke.bounds();
 
-                if path_polygon.contains(&crate::utils::p2d_aabb_to_geo_polygon(stroke_bounds)) {
-                    return Some(key);
-                } else if path_polygon
-                    .intersects(&crate::utils::p2d_aabb_to_geo_polygon(stroke_bounds))
-                {
-                    for &hitbox_elem in stroke.hitboxes().iter() {
-                        if !path_polygon
-                            .contains(&crate::utils::p2d_aabb_to_geo_polygon(hitbox_elem))
-                        {
+                match collision {
+                    SelectionCollision::Contains => {
+                        if fill_rule.polygon_contains_aabb(polygon, stroke_bounds) {
+                            return Some(key);
+                        }
+                        if !fill_rule.polygon_intersects_aabb(polygon, stroke_bounds) {
                             return None;
                         }
+                        stroke
+                            .hitboxes()
+                            .into_iter()
+                            .all(|hitbox| fill_rule.polygon_contains_aabb(polygon, hitbox))
+                            .then_some(key)
+                    }
+                    SelectionCollision::Intersects => {
+                        if !fill_rule.polygon_intersects_aabb(polygon, stroke_bounds) {
+                            return None;
+                        }
+                        stroke
+                            .hitboxes()
+                            .into_iter()
+                            .any(|hitbox| fill_rule.polygon_intersects_aabb(polygon, hitbox))
+                            .then_some(key)
                     }
-
-                    return Some(key);
                 }
+            })
+            .collect()
+    }
 
-                None
+    /// Return the keys for strokes whose hitboxes are within the radius around the given path.
+    pub(crate) fn strokes_hitboxes_near_path(
+        &self,
+        path: &[Element],
+        radius: f64,
+        viewport: Aabb,
+    ) -> Vec<StrokeKey> {
+        let Some(first) = path.first() else {
+            return vec![];
+        };
+        let mut bounds = viewport;
+        for p in path {
+            bounds.take_point(p.pos.into());
+        }
+
+        let mut path_coords = path
+            .iter()
+            .map(|element| geo::Coord {
+                x: element.pos[0],
+                y: element.pos[1],
+            })
+            .collect::<Vec<geo::Coord<f64>>>();
+        // a single tap still selects the strokes around it
+        if path_coords.len() == 1 {
+            path_coords.push(geo::Coord {
+                x: first.pos[0],
+                y: first.pos[1],
+            });
+        }
+        let path_linestring = geo::LineString::new(path_coords);
+
+        self.keys_sorted_chrono_intersecting_bounds(bounds.loosened(radius))
+            .into_iter()
+            .filter(|&key| {
+                // skip if stroke is trashed or hidden
+                if self.trashed_or_hidden(key) {
+                    return false;
+                }
+                let Some(stroke) = self.stroke_components.get(key) else {
+                    return false;
+                };
+                path_linestring.intersects(&crate::utils::p2d_aabb_to_geo_polygon(
+                    stroke.bounds().loosened(radius),
+                )) && stroke.hitboxes().into_iter().any(|hitbox| {
+                    path_linestring.intersects(&crate::utils::p2d_aabb_to_geo_polygon(
+                        hitbox.loosened(radius),
+                    ))
+                })
             })
             .collect()
     }
@@ -546,8 +888,8 @@ impl StrokeStore {
         self.keys_sorted_chrono_intersecting_bounds(bounds)
             .into_iter()
             .filter_map(|key| {
-                // skip if stroke is trashed
-                if self.trashed(key)? {
+                // skip if stroke is trashed or hidden
+                if self.trashed_or_hidden(key) {
                     return None;
                 }
 
@@ -579,8 +921,8 @@ impl StrokeStore {
         self.keys_sorted_chrono_intersecting_bounds(viewport.merged(&aabb))
             .into_iter()
             .filter_map(|key| {
-                // skip if stroke is trashed
-                if self.trashed(key)? {
+                // skip if stroke is trashed or hidden
+                if self.trashed_or_hidden(key) {
                     return None;
                 }
 
@@ -628,6 +970,35 @@ impl StrokeStore {
             .collect()
     }
 
+    /// The stroke where the given coord is inside at least one of its hitboxes.
+    ///
+    /// When multiple strokes overlap at the coord, the pick mode decides which one is returned.
+    pub(crate) fn hit_test(&self, coord: na::Vector2<f64>, mode: PickMode) -> Option<StrokeKey> {
+        let point = na::Point2::from(coord);
+        let mut hits = self
+            .stroke_keys_as_rendered_intersecting_bounds(Aabb::new(point, point))
+            .into_iter()
+            .rev()
+            .filter_map(|key| {
+                let distance = self
+                    .stroke_components
+                    .get(key)?
+                    .hitboxes()
+                    .into_iter()
+                    .filter(|hitbox| hitbox.contains_local_point(&point))
+                    .map(|hitbox| (hitbox.center() - point).norm())
+                    .reduce(f64::min)?;
+                Some((key, distance))
+            });
+
+        match mode {
+            PickMode::Topmost => hits.next().map(|(key, _)| key),
+            PickMode::Nearest => hits
+                .min_by(|(_, first), (_, second)| first.total_cmp(second))
+                .map(|(key, _)| key),
+        }
+    }
+
     /// Return all keys below the given `y`.
     pub(crate) fn keys_below_y(&self, y: f64) -> Vec<StrokeKey> {
         self.stroke_components
@@ -750,3 +1121,197 @@ impl StrokeStore {
             .collect()
     }
 }
+
+/// The edges of the closed polygon.
+fn polygon_edges(
+    polygon: &[na::Point2<f64>],
+) -> impl Iterator<Item = (na::Point2<f64>, na::Point2<f64>)> + '_ {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(start, end)| (*start, *end))
+}
+
+/// The edges of the Aabb.
+fn aabb_edges(aabb: Aabb) -> [(na::Point2<f64>, na::Point2<f64>); 4] {
+    let [a, b, c, d] = aabb.vertices();
+    [(a, b), (b, c), (c, d), (d, a)]
+}
+
+/// The intersection point of two line segments, if they intersect.
+fn segments_intersection(
+    first_start: na::Point2<f64>,
+    first_end: na::Point2<f64>,
+    second_start: na::Point2<f64>,
+    second_end: na::Point2<f64>,
+) -> Option<na::Point2<f64>> {
+    let first_vec = first_end - first_start;
+    let second_vec = second_end - second_start;
+    let denom = first_vec.perp(&second_vec);
+    if denom.abs() <= f64::EPSILON {
+        // parallel or degenerate
+        return None;
+    }
+    let start_diff = second_start - first_start;
+    let t = start_diff.perp(&second_vec) / denom;
+    let u = start_diff.perp(&first_vec) / denom;
+
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then(|| first_start + first_vec * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alignment, DistributeAxis, FillRule, PickMode};
+    use crate::store::StoreEvent;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use crate::{SelectionCollision, StrokeStore};
+    use approx::assert_relative_eq;
+    use futures::StreamExt;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Line, Rectangle, Shape};
+    use rnote_compose::{Color, Style};
+
+    fn insert_rect(store: &mut StrokeStore, mins: [f64; 2], maxs: [f64; 2]) -> super::StrokeKey {
+        store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    mins.into(),
+                    maxs.into(),
+                ))),
+                Style::default(),
+            )),
+            None,
+        )
+    }
+
+    #[test]
+    fn color_changes_emit_stroke_modified() {
+        let mut store = StrokeStore::default();
+        let key = insert_rect(&mut store, [0.0, 0.0], [10.0, 10.0]);
+        let events_rx = store.subscribe_events();
+
+        let _ = store.change_stroke_colors(&[key], Color::RED);
+        let _ = store.change_fill_colors(&[key], Color::BLUE);
+        let _ = store.invert_color_brightness(&[key]);
+        store.unsubscribe_events();
+
+        let events = futures::executor::block_on(events_rx.collect::<Vec<StoreEvent>>());
+        assert_eq!(events, vec![StoreEvent::StrokeModified(vec![key]); 3]);
+    }
+
+    #[test]
+    fn hit_test_picks_topmost_or_nearest() {
+        let mut store = StrokeStore::default();
+        let mut style = Style::default();
+        style.set_stroke_width(20.0);
+        let mut insert_line = |y: f64| {
+            store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Line(Line::new(na::vector![0.0, y], na::vector![100.0, y])),
+                    style.clone(),
+                )),
+                None,
+            )
+        };
+        let lower = insert_line(100.0);
+        let upper = insert_line(110.0);
+
+        // both strokes are hit, but the position is closer to the center-line of the lower one
+        let pos = na::vector![50.0, 103.0];
+        assert_eq!(store.hit_test(pos, PickMode::Topmost), Some(upper));
+        assert_eq!(store.hit_test(pos, PickMode::Nearest), Some(lower));
+        // only the upper stroke is hit
+        let pos = na::vector![50.0, 115.0];
+        assert_eq!(store.hit_test(pos, PickMode::Nearest), Some(upper));
+        assert_eq!(
+            store.hit_test(na::vector![50.0, 200.0], PickMode::Topmost),
+            None
+        );
+    }
+
+    #[test]
+    fn align_strokes_edges() {
+        let mut store = StrokeStore::default();
+        let keys = vec![
+            insert_rect(&mut store, [10.0, 0.0], [30.0, 20.0]),
+            insert_rect(&mut store, [50.0, 40.0], [100.0, 60.0]),
+            insert_rect(&mut store, [-20.0, 80.0], [0.0, 90.0]),
+        ];
+
+        store.align_strokes(&keys, Alignment::Left);
+        let bounds = store.strokes_bounds(&keys);
+        assert_relative_eq!(bounds[0].mins[0], bounds[1].mins[0]);
+        assert_relative_eq!(bounds[0].mins[0], bounds[2].mins[0]);
+
+        store.align_strokes(&keys, Alignment::Bottom);
+        let bounds = store.strokes_bounds(&keys);
+        assert_relative_eq!(bounds[0].maxs[1], bounds[1].maxs[1]);
+        assert_relative_eq!(bounds[0].maxs[1], bounds[2].maxs[1]);
+
+        // a single stroke is not moved
+        assert!(store.align_strokes(&keys[..1], Alignment::Right).is_empty());
+    }
+
+    #[test]
+    fn distribute_strokes_even_gaps() {
+        let mut store = StrokeStore::default();
+        let keys = vec![
+            insert_rect(&mut store, [0.0, 0.0], [10.0, 10.0]),
+            insert_rect(&mut store, [12.0, 0.0], [32.0, 10.0]),
+            insert_rect(&mut store, [100.0, 0.0], [110.0, 10.0]),
+        ];
+
+        let bounds_before = store.strokes_bounds(&keys);
+        store.distribute_strokes(&keys, DistributeAxis::Horizontal);
+        let bounds = store.strokes_bounds(&keys);
+        assert_relative_eq!(
+            bounds[1].mins[0] - bounds[0].maxs[0],
+            bounds[2].mins[0] - bounds[1].maxs[0]
+        );
+        // the outermost strokes stay in place
+        assert_relative_eq!(bounds[0].mins[0], bounds_before[0].mins[0]);
+        assert_relative_eq!(bounds[2].maxs[0], bounds_before[2].maxs[0]);
+    }
+
+    #[test]
+    fn strokes_in_polygon_partly_and_fully_inside() {
+        let mut store = StrokeStore::default();
+        let inside = insert_rect(&mut store, [10.0, 10.0], [20.0, 20.0]);
+        let partly = insert_rect(&mut store, [90.0, 40.0], [110.0, 60.0]);
+        let outside = insert_rect(&mut store, [200.0, 200.0], [210.0, 210.0]);
+        let polygon = vec![
+            na::point![0.0, 0.0],
+            na::point![100.0, 0.0],
+            na::point![100.0, 100.0],
+            na::point![0.0, 100.0],
+        ];
+
+        let contained =
+            store.strokes_in_polygon(&polygon, SelectionCollision::Contains, FillRule::EvenOdd);
+        assert_eq!(contained, vec![inside]);
+
+        let intersecting =
+            store.strokes_in_polygon(&polygon, SelectionCollision::Intersects, FillRule::EvenOdd);
+        assert_eq!(intersecting, vec![inside, partly]);
+        assert!(!intersecting.contains(&outside));
+
+        // A polygon winding twice around the same square.
+        // Its inside region has a winding number of two, so only the non-zero rule considers it inside.
+        let double_wound = [polygon.clone(), polygon].concat();
+        assert!(store
+            .strokes_in_polygon(
+                &double_wound,
+                SelectionCollision::Contains,
+                FillRule::EvenOdd
+            )
+            .is_empty());
+        assert_eq!(
+            store.strokes_in_polygon(
+                &double_wound,
+                SelectionCollision::Contains,
+                FillRule::NonZero
+            ),
+            vec![inside]
+        );
+    }
+}
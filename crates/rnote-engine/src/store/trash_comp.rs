@@ -1,8 +1,10 @@
 // Imports
 use super::chrono_comp::StrokeLayer;
-use super::{StrokeKey, StrokeStore};
+use super::{StoreEvent, StrokeKey, StrokeStore};
+use crate::pens::pensconfig::eraserconfig::EraserScope;
 use crate::strokes::{BrushStroke, Stroke};
-use crate::WidgetFlags;
+use crate::{SelectionCollision, WidgetFlags};
+use geo::BoundingRect;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::shapes::Shapeable;
 use rnote_compose::PenPath;
@@ -24,6 +26,9 @@ impl Default for TrashComponent {
 
 /// Systems that are related to trashing.
 impl StrokeStore {
+    /// Strokes with an opacity at or below this threshold are considered fully erased when fading.
+    const FADE_ALPHA_THRESHOLD: f64 = 0.01;
+
     /// Rebuild the slotmap with empty trash components with the keys returned from the stroke components.
     pub(crate) fn rebuild_trash_components_slotmap(&mut self) {
         self.trash_components = Arc::new(slotmap::SecondaryMap::new());
@@ -48,8 +53,18 @@ impl StrokeStore {
             .get_mut(key)
             .map(Arc::make_mut)
         {
+            let changed = trash_comp.trashed != trash;
             trash_comp.trashed = trash;
             self.update_chrono_to_last(key);
+            if changed {
+                self.emit_event(|| {
+                    if trash {
+                        StoreEvent::StrokeRemoved(vec![key])
+                    } else {
+                        StoreEvent::StrokeAdded(vec![key])
+                    }
+                });
+            }
         }
     }
 
@@ -77,15 +92,51 @@ impl StrokeStore {
             .collect()
     }
 
-    /// Trash strokes that collide with the given bounds.
+    /// Whether the stroke can be erased in the given eraser scope. Locked strokes are never in scope.
+    pub(crate) fn in_eraser_scope(
+        &self,
+        key: StrokeKey,
+        scope: EraserScope,
+        active_layer: u32,
+    ) -> bool {
+        if self.locked(key).unwrap_or(false) {
+            return false;
+        }
+        match scope {
+            EraserScope::All => true,
+            EraserScope::ActiveLayer => {
+                self.layer(key) == Some(StrokeLayer::UserLayer(active_layer))
+            }
+            EraserScope::Selection => self.selected(key).unwrap_or(false),
+        }
+    }
+
+    /// The keys of the strokes in the given eraser scope intersecting the bounds, in the order they are rendered.
+    ///
+    /// The candidates are queried from the spatial index of the stroke bounds, so that erasing only needs to test the
+    /// strokes near the eraser, no matter how many strokes the document contains.
+    fn eraser_scope_keys_intersecting_bounds(
+        &self,
+        bounds: Aabb,
+        scope: EraserScope,
+        active_layer: u32,
+    ) -> Vec<StrokeKey> {
+        self.stroke_keys_as_rendered_intersecting_bounds(bounds)
+            .into_iter()
+            .filter(|&key| self.in_eraser_scope(key, scope, active_layer))
+            .collect()
+    }
+
+    /// Trash strokes in the eraser scope that collide with the given bounds.
     pub(crate) fn trash_colliding_strokes(
         &mut self,
         eraser_bounds: Aabb,
-        viewport: Aabb,
+        scope: EraserScope,
+        active_layer: u32,
     ) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
-        self.stroke_keys_as_rendered_intersecting_bounds(viewport)
+        self.eraser_scope_keys_intersecting_bounds(eraser_bounds, scope, active_layer)
             .into_iter()
             .for_each(|key| {
                 let mut trash_current_stroke = false;
@@ -120,8 +171,150 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Trash the topmost stroke in the eraser scope that collides with the given bounds.
+    pub(crate) fn trash_topmost_colliding_stroke(
+        &mut self,
+        eraser_bounds: Aabb,
+        scope: EraserScope,
+        active_layer: u32,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        let topmost = self
+            .eraser_scope_keys_intersecting_bounds(eraser_bounds, scope, active_layer)
+            .into_iter()
+            .rev()
+            .find(|&key| {
+                let Some(stroke) = self.stroke_components.get(key) else {
+                    return false;
+                };
+                match stroke.as_ref() {
+                    Stroke::BrushStroke(_) | Stroke::ShapeStroke(_) => stroke
+                        .hitboxes()
+                        .into_iter()
+                        .any(|hitbox| eraser_bounds.intersects(&hitbox)),
+                    // Ignore other strokes when trashing with the Eraser
+                    Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {
+                        false
+                    }
+                }
+            });
+
+        if let Some(key) = topmost {
+            self.set_trashed(key, true);
+            widget_flags.store_modified = true;
+            widget_flags.resize = true;
+        }
+
+        widget_flags
+    }
+
+    /// Trash all strokes in the given region in one go.
+    ///
+    /// Returns the keys of the trashed strokes.
+    pub(crate) fn trash_strokes_in_region(
+        &mut self,
+        bounds: Aabb,
+        collision: SelectionCollision,
+    ) -> Vec<StrokeKey> {
+        let keys = match collision {
+            SelectionCollision::Contains => self.stroke_keys_as_rendered_in_bounds(bounds),
+            SelectionCollision::Intersects => self
+                .stroke_keys_as_rendered_intersecting_bounds(bounds)
+                .into_iter()
+                .filter(|&key| {
+                    self.stroke_components.get(key).is_some_and(|stroke| {
+                        stroke
+                            .hitboxes()
+                            .into_iter()
+                            .any(|hitbox| bounds.intersects(&hitbox))
+                    })
+                })
+                .collect(),
+        };
+        self.set_trashed_keys(&keys, true);
+        keys
+    }
+
+    /// Reduce the opacity of strokes in the eraser scope that collide with the given bounds by the given amount.
+    /// Strokes that become fully transparent are trashed.
+    ///
+    /// Returns the keys of the strokes that were faded but not trashed. They need to update their rendering.
+    pub(crate) fn fade_colliding_strokes(
+        &mut self,
+        eraser_bounds: Aabb,
+        opacity_reduction: f64,
+        scope: EraserScope,
+        active_layer: u32,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let mut modified_keys = vec![];
+
+        for key in self.eraser_scope_keys_intersecting_bounds(eraser_bounds, scope, active_layer) {
+            let Some(stroke) = self.stroke_components.get(key) else {
+                continue;
+            };
+            let collides = match stroke.as_ref() {
+                Stroke::BrushStroke(_) | Stroke::ShapeStroke(_) => {
+                    eraser_bounds.intersects(&stroke.bounds())
+                        && stroke
+                            .hitboxes()
+                            .into_iter()
+                            .any(|hitbox| eraser_bounds.intersects(&hitbox))
+                }
+                // Ignore other strokes when fading with the Eraser
+                Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => false,
+            };
+            if !collides {
+                continue;
+            }
+
+            let Some(style) = Arc::make_mut(&mut self.stroke_components)
+                .get_mut(key)
+                .map(Arc::make_mut)
+                .and_then(|stroke| match stroke {
+                    Stroke::BrushStroke(brushstroke) => Some(&mut brushstroke.style),
+                    Stroke::ShapeStroke(shapestroke) => Some(&mut shapestroke.style),
+                    _ => None,
+                })
+            else {
+                continue;
+            };
+            let stroke_alpha = style.stroke_color().map(|mut color| {
+                color.a = (color.a - opacity_reduction).max(0.0);
+                style.set_stroke_color(color);
+                color.a
+            });
+            let fill_alpha = style.fill_color().map(|mut color| {
+                color.a = (color.a - opacity_reduction).max(0.0);
+                style.set_fill_color(color);
+                color.a
+            });
+            let remaining_alpha = stroke_alpha
+                .into_iter()
+                .chain(fill_alpha)
+                .fold(0.0_f64, f64::max);
+
+            if remaining_alpha <= Self::FADE_ALPHA_THRESHOLD {
+                self.set_trashed(key, true);
+                widget_flags.resize = true;
+            } else {
+                self.set_rendering_dirty(key);
+                modified_keys.push(key);
+            }
+            widget_flags.store_modified = true;
+        }
+
+        if !modified_keys.is_empty() {
+            self.emit_event(|| StoreEvent::StrokeModified(modified_keys.clone()));
+        }
+
+        (modified_keys, widget_flags)
+    }
+
     /// Remove colliding stroke segments with the given bounds.
     /// The stroke is then split. Strokes that don't have segments are trashed completely.
+    /// Colliding shapes are not split, but trashed completely.
     ///
     /// Returns the keys of all created or modified strokes.
     ///
@@ -129,13 +322,14 @@ impl StrokeStore {
     pub(crate) fn split_colliding_strokes(
         &mut self,
         eraser_bounds: Aabb,
-        viewport: Aabb,
+        scope: EraserScope,
+        active_layer: u32,
     ) -> (Vec<StrokeKey>, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
         let mut modified_keys = vec![];
 
         let new_strokes = self
-            .stroke_keys_as_rendered_intersecting_bounds(viewport)
+            .eraser_scope_keys_intersecting_bounds(eraser_bounds, scope, active_layer)
             .into_iter()
             .flat_map(|key| {
                 let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
@@ -249,4 +443,475 @@ impl StrokeStore {
 
         (modified_keys, widget_flags)
     }
+
+    /// Subtract the area from the filled shapes that collide with it, keeping the remaining parts.
+    /// Shapes that are covered entirely are trashed.
+    ///
+    /// Returns the keys of all created or modified strokes.
+    ///
+    /// The strokes then need to update their rendering.
+    pub(crate) fn carve_colliding_strokes(
+        &mut self,
+        area: &geo::MultiPolygon<f64>,
+        scope: EraserScope,
+        active_layer: u32,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let mut modified_keys = vec![];
+        let Some(area_rect) = area.bounding_rect() else {
+            return (modified_keys, widget_flags);
+        };
+        let area_bounds = Aabb::new(
+            na::point![area_rect.min().x, area_rect.min().y],
+            na::point![area_rect.max().x, area_rect.max().y],
+        );
+
+        for key in self.eraser_scope_keys_intersecting_bounds(area_bounds, scope, active_layer) {
+            let (Some(stroke), Some(chrono_comp)) = (
+                self.stroke_components.get(key),
+                self.chrono_components.get(key),
+            ) else {
+                continue;
+            };
+            // Only filled shapes can be carved
+            let Stroke::ShapeStroke(shapestroke) = stroke.as_ref() else {
+                continue;
+            };
+            if !area_bounds.intersects(&shapestroke.bounds()) {
+                continue;
+            }
+            let Some(parts) = shapestroke.carve(area) else {
+                continue;
+            };
+            let layer = chrono_comp.layer;
+            let mut parts = parts.into_iter();
+            widget_flags.store_modified = true;
+            widget_flags.resize = true;
+
+            // The first remaining part replaces the original stroke
+            let Some(first_part) = parts.next() else {
+                self.set_trashed(key, true);
+                continue;
+            };
+            if let Some(stroke) = self.get_stroke_mut(key) {
+                *stroke = Stroke::ShapeStroke(first_part);
+            }
+            self.update_geometry_for_stroke(key);
+            modified_keys.push(key);
+
+            for part in parts {
+                modified_keys.push(self.insert_stroke(Stroke::ShapeStroke(part), Some(layer)));
+            }
+        }
+
+        (modified_keys, widget_flags)
+    }
+
+    /// Carve transparency into the bitmap images on the image layer that collide with the eraser bounds.
+    ///
+    /// Independent of the eraser scope. Returns the keys of the modified strokes.
+    ///
+    /// The strokes then need to update their rendering.
+    pub(crate) fn erase_background_images(
+        &mut self,
+        eraser_bounds: Aabb,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let mut modified_keys = vec![];
+
+        for key in self.stroke_keys_as_rendered_intersecting_bounds(eraser_bounds) {
+            if self.layer(key) != Some(StrokeLayer::Image) || self.locked(key).unwrap_or(false) {
+                continue;
+            }
+            let Some(Stroke::BitmapImage(bitmapimage)) = self.get_stroke_mut(key) else {
+                continue;
+            };
+            if !eraser_bounds.intersects(&bitmapimage.bounds()) {
+                continue;
+            }
+            if bitmapimage.erase_transparent(eraser_bounds) {
+                widget_flags.store_modified = true;
+                modified_keys.push(key);
+            }
+        }
+
+        (modified_keys, widget_flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pens::pensconfig::eraserconfig::EraserScope;
+    use crate::store::chrono_comp::StrokeLayer;
+    use crate::strokes::{BitmapImage, BrushStroke, ShapeStroke, Stroke};
+    use crate::{SelectionCollision, StrokeStore};
+    use approx::assert_relative_eq;
+    use geo::{Area, Contains};
+    use p2d::bounding_volume::{Aabb, BoundingVolume};
+    use rnote_compose::penpath::{Element, Segment};
+    use rnote_compose::shapes::{Rectangle, Shape, Shapeable};
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::{Color, PenPath, Style};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn fade_passes_remove_stroke() {
+        let mut store = StrokeStore::default();
+        let key = store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![100.0, 100.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        let eraser_bounds = Aabb::new(na::point![-5.0, -5.0], na::point![5.0, 5.0]);
+
+        let (modified, _) = store.fade_colliding_strokes(eraser_bounds, 0.3, EraserScope::All, 0);
+        assert_eq!(modified, vec![key]);
+        assert_eq!(store.trashed(key), Some(false));
+
+        for _ in 0..3 {
+            let _ = store.fade_colliding_strokes(eraser_bounds, 0.3, EraserScope::All, 0);
+        }
+        assert_eq!(store.trashed(key), Some(true));
+    }
+
+    /// Benchmarks erasing in a small area on documents with a growing number of strokes elsewhere.
+    ///
+    /// Run with `cargo test -p rnote-engine --release -- --ignored --nocapture bench_erase`.
+    #[test]
+    #[ignore]
+    fn bench_erase_cost_with_stroke_count() {
+        const N_ERASES: u32 = 200;
+
+        let per_erase = [1_000, 10_000, 100_000].map(|n_strokes| {
+            let mut store = StrokeStore::default();
+            let columns = (n_strokes as f64).sqrt().ceil() as usize;
+            for i in 0..n_strokes {
+                let pos = na::vector![(i % columns) as f64, (i / columns) as f64] * 20.0;
+                store.insert_stroke(
+                    Stroke::ShapeStroke(ShapeStroke::new(
+                        Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                            pos.into(),
+                            (pos + na::vector![10.0, 10.0]).into(),
+                        ))),
+                        Style::default(),
+                    )),
+                    None,
+                );
+            }
+
+            let start = Instant::now();
+            for i in 0..N_ERASES {
+                // sweep in the gaps between the strokes, so every erase tests the same neighbours
+                let pos = na::point![15.0 + (i % 10) as f64 * 20.0, 15.0];
+                let _ = store.trash_colliding_strokes(
+                    Aabb::from_half_extents(pos, na::vector![2.0, 2.0]),
+                    EraserScope::All,
+                    0,
+                );
+            }
+            let per_erase = start.elapsed() / N_ERASES;
+            println!("{n_strokes:>7} strokes: {per_erase:>10.2?} per erase");
+            per_erase
+        });
+
+        // A hundred times the strokes must not make erasing anywhere near a hundred times slower,
+        // the candidates are queried by the eraser bounds.
+        assert!(per_erase[2] < (per_erase[0] * 20).max(Duration::from_micros(50)));
+    }
+
+    #[test]
+    fn region_trashes_only_strokes_inside() {
+        let mut store = StrokeStore::default();
+        let mut insert_rect = |mins: na::Point2<f64>, maxs: na::Point2<f64>| {
+            store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(mins, maxs))),
+                    Style::default(),
+                )),
+                None,
+            )
+        };
+        let inside = insert_rect(na::point![10.0, 10.0], na::point![40.0, 40.0]);
+        let crossing = insert_rect(na::point![80.0, 80.0], na::point![150.0, 150.0]);
+        let outside = insert_rect(na::point![200.0, 200.0], na::point![250.0, 250.0]);
+        let region = Aabb::new(na::point![0.0, 0.0], na::point![100.0, 100.0]);
+
+        let trashed = store.trash_strokes_in_region(region, SelectionCollision::Contains);
+        assert_eq!(trashed, vec![inside]);
+        assert_eq!(store.trashed(crossing), Some(false));
+
+        let trashed = store.trash_strokes_in_region(region, SelectionCollision::Intersects);
+        assert_eq!(trashed, vec![crossing]);
+        assert_eq!(store.trashed(inside), Some(true));
+        assert_eq!(store.trashed(outside), Some(false));
+    }
+
+    #[test]
+    fn eraser_scope_spares_out_of_scope_strokes() {
+        let mut store = StrokeStore::default();
+        let mut insert_rect = |layer: StrokeLayer| {
+            store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        na::point![0.0, 0.0],
+                        na::point![100.0, 100.0],
+                    ))),
+                    Style::default(),
+                )),
+                Some(layer),
+            )
+        };
+        let on_layer_0 = insert_rect(StrokeLayer::UserLayer(0));
+        let on_layer_1 = insert_rect(StrokeLayer::UserLayer(1));
+        let eraser_bounds = Aabb::new(na::point![-5.0, -5.0], na::point![5.0, 5.0]);
+        let reset = |store: &mut StrokeStore| {
+            store.set_trashed_keys(&[on_layer_0, on_layer_1], false);
+        };
+
+        let _ = store.trash_colliding_strokes(eraser_bounds, EraserScope::ActiveLayer, 1);
+        assert_eq!(store.trashed(on_layer_0), Some(false));
+        assert_eq!(store.trashed(on_layer_1), Some(true));
+
+        reset(&mut store);
+        store.set_selected(on_layer_0, true);
+        let _ = store.trash_colliding_strokes(eraser_bounds, EraserScope::Selection, 0);
+        assert_eq!(store.trashed(on_layer_0), Some(true));
+        assert_eq!(store.trashed(on_layer_1), Some(false));
+
+        // nothing is selected anymore, the tap eraser in selection scope has nothing to erase
+        reset(&mut store);
+        let _ = store.trash_topmost_colliding_stroke(eraser_bounds, EraserScope::Selection, 0);
+        assert_eq!(store.trashed(on_layer_0), Some(false));
+        assert_eq!(store.trashed(on_layer_1), Some(false));
+
+        let _ = store.trash_colliding_strokes(eraser_bounds, EraserScope::All, 0);
+        assert_eq!(store.trashed(on_layer_0), Some(true));
+        assert_eq!(store.trashed(on_layer_1), Some(true));
+    }
+
+    #[test]
+    fn split_trashes_shapes_and_splits_brush_strokes() {
+        let mut store = StrokeStore::default();
+        let brushstroke_key = store.insert_stroke(
+            Stroke::BrushStroke(BrushStroke::from_penpath(
+                PenPath::new_w_segments(
+                    Element::new(na::vector![0.0, 200.0], 0.5),
+                    (1..=20).map(|i| Segment::LineTo {
+                        end: Element::new(na::vector![i as f64 * 10.0, 200.0], 0.5),
+                    }),
+                ),
+                Style::default(),
+            )),
+            None,
+        );
+        let shapestroke_key = store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![100.0, 150.0],
+                    na::point![160.0, 250.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        let eraser_bounds = Aabb::new(na::point![95.0, 195.0], na::point![105.0, 205.0]);
+
+        let _ = store.split_colliding_strokes(eraser_bounds, EraserScope::All, 0);
+        assert_eq!(store.trashed(shapestroke_key), Some(true));
+        assert_eq!(store.trashed(brushstroke_key), Some(false));
+
+        let brushstrokes = store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter_map(|key| match store.get_stroke_ref(key) {
+                Some(Stroke::BrushStroke(brushstroke)) => Some(brushstroke.bounds()),
+                _ => None,
+            })
+            .collect::<Vec<Aabb>>();
+        // The brush stroke is split into the parts before and after the eraser
+        assert_eq!(brushstrokes.len(), 2);
+        assert!(brushstrokes
+            .iter()
+            .all(|bounds| !bounds.intersects(&eraser_bounds)));
+    }
+
+    #[test]
+    fn tap_removes_only_topmost_stroke() {
+        let mut store = StrokeStore::default();
+        let mut insert_rect = |maxs: na::Point2<f64>| {
+            store.insert_stroke(
+                Stroke::ShapeStroke(ShapeStroke::new(
+                    Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                        na::point![0.0, 0.0],
+                        maxs,
+                    ))),
+                    Style::default(),
+                )),
+                None,
+            )
+        };
+        let bottom = insert_rect(na::point![100.0, 100.0]);
+        let top = insert_rect(na::point![50.0, 50.0]);
+        // Both rectangles share the left edge at this position
+        let tap_bounds = Aabb::new(na::point![-2.0, 20.0], na::point![2.0, 24.0]);
+
+        let _ = store.trash_topmost_colliding_stroke(tap_bounds, EraserScope::All, 0);
+        assert_eq!(store.trashed(top), Some(true));
+        assert_eq!(store.trashed(bottom), Some(false));
+
+        let _ = store.trash_topmost_colliding_stroke(tap_bounds, EraserScope::All, 0);
+        assert_eq!(store.trashed(bottom), Some(true));
+    }
+
+    #[test]
+    fn carve_cuts_notch_out_of_filled_rectangle() {
+        let mut store = StrokeStore::default();
+        let key = store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![100.0, 100.0],
+                ))),
+                Style::Smooth(SmoothOptions {
+                    fill_color: Some(Color::BLACK),
+                    ..Default::default()
+                }),
+            )),
+            None,
+        );
+        // overlaps the top edge, removing a 10x10 notch
+        let eraser = geo::MultiPolygon::new(vec![crate::utils::p2d_aabb_to_geo_polygon(
+            Aabb::new(na::point![45.0, -5.0], na::point![55.0, 10.0]),
+        )]);
+
+        let (modified, _) = store.carve_colliding_strokes(&eraser, EraserScope::All, 0);
+        assert_eq!(modified, vec![key]);
+        assert_eq!(store.trashed(key), Some(false));
+
+        let Some(Stroke::ShapeStroke(carved)) = store.get_stroke_ref(key) else {
+            panic!("the carved stroke is not a shape stroke");
+        };
+        assert!(matches!(carved.shape, Shape::Polygon(_)));
+        let carved_polygon =
+            crate::utils::bezpath_to_geo_polygon(&carved.shape.outline_path(), 0.1).unwrap();
+        assert_relative_eq!(carved_polygon.unsigned_area(), 9900.0, epsilon = 1e-6);
+        assert!(!carved_polygon.contains(&geo::point!(x: 50.0, y: 5.0)));
+        assert!(carved_polygon.contains(&geo::point!(x: 50.0, y: 50.0)));
+        assert!(carved_polygon.contains(&geo::point!(x: 20.0, y: 5.0)));
+
+        // carving outside of the shape does not modify it
+        let outside = geo::MultiPolygon::new(vec![crate::utils::p2d_aabb_to_geo_polygon(
+            Aabb::new(na::point![150.0, 150.0], na::point![160.0, 160.0]),
+        )]);
+        let (modified, _) = store.carve_colliding_strokes(&outside, EraserScope::All, 0);
+        assert!(modified.is_empty());
+    }
+
+    #[test]
+    fn carve_cuts_hole_without_seam() {
+        let mut store = StrokeStore::default();
+        let key = store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![100.0, 100.0],
+                ))),
+                Style::Smooth(SmoothOptions {
+                    fill_color: Some(Color::BLACK),
+                    ..Default::default()
+                }),
+            )),
+            None,
+        );
+        let subpath_areas = |store: &StrokeStore| {
+            let Some(Stroke::ShapeStroke(carved)) = store.get_stroke_ref(key) else {
+                panic!("the carved stroke is not a shape stroke");
+            };
+            let Shape::CompoundPath(compound) = &carved.shape else {
+                panic!("the carved stroke with a hole is not a compound path");
+            };
+            compound
+                .subpaths
+                .iter()
+                .map(|subpath| {
+                    assert!(matches!(subpath, Shape::Polygon(_)));
+                    crate::utils::bezpath_to_geo_polygon(&subpath.outline_path(), 0.1)
+                        .unwrap()
+                        .unsigned_area()
+                })
+                .collect::<Vec<f64>>()
+        };
+
+        let hole = geo::MultiPolygon::new(vec![crate::utils::p2d_aabb_to_geo_polygon(Aabb::new(
+            na::point![40.0, 40.0],
+            na::point![60.0, 60.0],
+        ))]);
+        let (modified, _) = store.carve_colliding_strokes(&hole, EraserScope::All, 0);
+        assert_eq!(modified, vec![key]);
+        // the hole is its own subpath and not joined to the outline
+        let areas = subpath_areas(&store);
+        assert_eq!(areas.len(), 2);
+        assert_relative_eq!(areas[0], 10000.0, epsilon = 1e-6);
+        assert_relative_eq!(areas[1], 400.0, epsilon = 1e-6);
+
+        // the compound path can be carved again and keeps its hole
+        let notch = geo::MultiPolygon::new(vec![crate::utils::p2d_aabb_to_geo_polygon(Aabb::new(
+            na::point![45.0, -5.0],
+            na::point![55.0, 10.0],
+        ))]);
+        let (modified, _) = store.carve_colliding_strokes(&notch, EraserScope::All, 0);
+        assert_eq!(modified, vec![key]);
+        let areas = subpath_areas(&store);
+        assert_eq!(areas.len(), 2);
+        assert_relative_eq!(areas[0], 9900.0, epsilon = 1e-6);
+        assert_relative_eq!(areas[1], 400.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn erase_background_makes_image_transparent() {
+        let mut store = StrokeStore::default();
+        // an opaque white 10x10 pixel image, displayed with a size of 100x100
+        let image = crate::render::Image {
+            data: glib::Bytes::from_owned(vec![255; 10 * 10 * 4]),
+            rect: Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![0.0, 0.0],
+                na::point![100.0, 100.0],
+            )),
+            pixel_width: 10,
+            pixel_height: 10,
+            ..Default::default()
+        };
+        let key = store.insert_stroke(
+            Stroke::BitmapImage(BitmapImage::new(
+                image,
+                Rectangle::from_p2d_aabb(Aabb::new(na::point![0.0, 0.0], na::point![100.0, 100.0])),
+            )),
+            Some(StrokeLayer::Image),
+        );
+        let eraser_bounds = Aabb::new(na::point![30.0, 30.0], na::point![50.0, 50.0]);
+
+        let (modified, _) = store.erase_background_images(eraser_bounds);
+        assert_eq!(modified, vec![key]);
+        assert_eq!(store.trashed(key), Some(false));
+
+        let Some(Stroke::BitmapImage(erased)) = store.get_stroke_ref(key) else {
+            panic!("the erased stroke is not a bitmap image");
+        };
+        let alpha = |x: usize, y: usize| erased.image.data[(y * 10 + x) * 4 + 3];
+        // pixels 3 and 4 on both axes have their centers inside the eraser bounds
+        assert_eq!(alpha(3, 3), 0);
+        assert_eq!(alpha(4, 4), 0);
+        assert_eq!(alpha(2, 3), 255);
+        assert_eq!(alpha(5, 4), 255);
+        assert_eq!(alpha(0, 0), 255);
+
+        // erasing the same area again has no effect
+        let (modified, _) = store.erase_background_images(eraser_bounds);
+        assert!(modified.is_empty());
+    }
 }
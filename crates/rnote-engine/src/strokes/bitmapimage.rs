@@ -8,6 +8,7 @@ use crate::Drawable;
 use anyhow::Context;
 use kurbo::Shape;
 use p2d::bounding_volume::Aabb;
+use p2d::query::PointQuery;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use rnote_compose::color;
 use rnote_compose::ext::{AabbExt, Affine2Ext};
@@ -17,6 +18,7 @@ use rnote_compose::transform::Transform;
 use rnote_compose::transform::Transformable;
 use serde::{Deserialize, Serialize};
 use std::ops::Range;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "bitmapimage")]
@@ -29,6 +31,9 @@ pub struct BitmapImage {
     pub image: render::Image,
     #[serde(rename = "rectangle")]
     pub rectangle: Rectangle,
+    /// The pixels backing the data of the image after it was edited, which allows editing them in place.
+    #[serde(skip)]
+    pixels: Option<SharedPixels>,
 }
 
 impl Default for BitmapImage {
@@ -36,10 +41,28 @@ impl Default for BitmapImage {
         Self {
             image: render::Image::default(),
             rectangle: Rectangle::default(),
+            pixels: None,
         }
     }
 }
 
+/// Pixel data that is shared between the image data and the bitmap image, so that it can be edited in place when it
+/// is not referenced anywhere else.
+#[derive(Clone)]
+struct SharedPixels(Arc<Vec<u8>>);
+
+impl std::fmt::Debug for SharedPixels {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedPixels").field(&self.0.len()).finish()
+    }
+}
+
+impl AsRef<[u8]> for SharedPixels {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
 impl Content for BitmapImage {
     fn update_geometry(&mut self) {}
 }
@@ -98,6 +121,14 @@ impl Transformable for BitmapImage {
 }
 
 impl BitmapImage {
+    pub fn new(image: render::Image, rectangle: Rectangle) -> Self {
+        Self {
+            image,
+            rectangle,
+            pixels: None,
+        }
+    }
+
     pub fn from_image_bytes(
         bytes: &[u8],
         pos: na::Vector2<f64>,
@@ -122,8 +153,9 @@ impl BitmapImage {
         let rectangle = Rectangle {
             cuboid: p2d::shape::Cuboid::new(size * 0.5),
             transform,
+            corner_radius: 0.0,
         };
-        Ok(Self { image, rectangle })
+        Ok(Self::new(image, rectangle))
     }
 
     pub fn from_pdf_bytes(
@@ -229,4 +261,82 @@ impl BitmapImage {
             })
             .collect()
     }
+
+    /// Make the pixels of the image whose centers are inside the bounds (in the coordinate space of the document)
+    /// fully transparent.
+    ///
+    /// Returns true when any pixel was modified.
+    pub fn erase_transparent(&mut self, bounds: Aabb) -> bool {
+        let (width, height) = (self.image.pixel_width, self.image.pixel_height);
+        let half_extents = self.rectangle.cuboid.half_extents;
+        if width == 0 || height == 0 || half_extents.iter().any(|&e| e <= 0.0) {
+            return false;
+        }
+        let affine = self.rectangle.transform.affine;
+        let pixel_size = na::vector![
+            2.0 * half_extents[0] / f64::from(width),
+            2.0 * half_extents[1] / f64::from(height)
+        ];
+        let Some(affine_inv) = affine.try_inverse() else {
+            return false;
+        };
+
+        // The range of pixels that can possibly be inside the bounds
+        let local_bounds = Aabb::from_points(
+            &[
+                bounds.mins,
+                na::point![bounds.maxs[0], bounds.mins[1]],
+                bounds.maxs,
+                na::point![bounds.mins[0], bounds.maxs[1]],
+            ]
+            .map(|p| affine_inv * p),
+        );
+        let pixel_range = |axis: usize, len: u32| {
+            let to_pixel = |v: f64| (v + half_extents[axis]) / pixel_size[axis];
+            let start = to_pixel(local_bounds.mins[axis])
+                .floor()
+                .clamp(0.0, len as f64) as u32;
+            let end = to_pixel(local_bounds.maxs[axis])
+                .ceil()
+                .clamp(0.0, len as f64) as u32;
+            start..end
+        };
+        let (x_range, y_range) = (pixel_range(0, width), pixel_range(1, height));
+        if x_range.is_empty() || y_range.is_empty() {
+            return false;
+        }
+
+        // The indices of the pixels to erase
+        let erased = y_range
+            .flat_map(|y| x_range.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                let local = na::point![
+                    -half_extents[0] + (f64::from(x) + 0.5) * pixel_size[0],
+                    -half_extents[1] + (f64::from(y) + 0.5) * pixel_size[1]
+                ];
+                bounds.contains_local_point(&(affine * local))
+            })
+            .map(|(x, y)| ((y * width + x) * 4) as usize)
+            .filter(|&i| self.image.data.get(i + 3).is_some_and(|&alpha| alpha != 0))
+            .collect::<Vec<usize>>();
+        if erased.is_empty() {
+            return false;
+        }
+
+        let mut pixels = match self.pixels.take() {
+            Some(SharedPixels(pixels)) if pixels.as_ptr() == self.image.data.as_ptr() => pixels,
+            _ => Arc::new(self.image.data.to_vec()),
+        };
+        // Release the reference held by the image data. The pixels then only get copied when they are still shared,
+        // for example with the history.
+        self.image.data = glib::Bytes::from_static(&[]);
+        let data = Arc::make_mut(&mut pixels);
+        for i in erased {
+            // The data is premultiplied, so all channels are zero when fully transparent
+            data[i..i + 4].fill(0);
+        }
+        self.image.data = glib::Bytes::from_owned(SharedPixels(Arc::clone(&pixels)));
+        self.pixels = Some(SharedPixels(pixels));
+        true
+    }
 }
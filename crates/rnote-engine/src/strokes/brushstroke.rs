@@ -8,12 +8,13 @@ use crate::{
 };
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::ext::AabbExt;
-use rnote_compose::penpath::{Element, Segment};
+use rnote_compose::penpath::{CurveFit, Element, Segment};
 use rnote_compose::shapes::Shapeable;
 use rnote_compose::style::Composer;
 use rnote_compose::transform::Transformable;
 use rnote_compose::{PenPath, Style};
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 use tracing::error;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,9 +24,15 @@ pub struct BrushStroke {
     pub path: PenPath,
     #[serde(default, rename = "style")]
     pub style: Style,
+    /// The algorithm the path was fitted with. None when the path is the output of the path builder.
+    #[serde(default, rename = "curve_fit")]
+    pub curve_fit: Option<CurveFit>,
     // since the path can have many hitboxes, we store them here and update them when the stroke geometry changes
     #[serde(skip)]
     hitboxes: Vec<Aabb>,
+    // computing the bounds walks the entire path, so they are computed lazily and cached until the geometry changes
+    #[serde(skip)]
+    bounds_cache: OnceLock<Aabb>,
 }
 
 impl Content for BrushStroke {
@@ -33,6 +40,7 @@ impl Content for BrushStroke {
         &self,
         viewport: Aabb,
         image_scale: f64,
+        aa_quality: render::AaQuality,
     ) -> Result<GeneratedContentImages, anyhow::Error> {
         /// The threshold of the image size on either axis.
         /// When below it the stroke is rendered as a single image
@@ -63,13 +71,14 @@ impl Content for BrushStroke {
             // generate a single image when bounds are smaller than threshold
             match &self.style {
                 Style::Smooth(options) => {
-                    let image = render::Image::gen_with_piet(
+                    let image = render::Image::gen_with_piet_aa(
                         |piet_cx| {
                             self.path.draw_composed(piet_cx, options);
                             Ok(())
                         },
                         bounds,
                         image_scale,
+                        aa_quality,
                     );
 
                     match image {
@@ -85,13 +94,14 @@ impl Content for BrushStroke {
                     vec![]
                 }
                 Style::Textured(options) => {
-                    let image = render::Image::gen_with_piet(
+                    let image = render::Image::gen_with_piet_aa(
                         |piet_cx| {
                             self.path.draw_composed(piet_cx, options);
                             Ok(())
                         },
                         bounds,
                         image_scale,
+                        aa_quality,
                     );
 
                     match image {
@@ -111,13 +121,14 @@ impl Content for BrushStroke {
                     let mut prev = self.path.start;
                     for seg in self.path.segments.iter() {
                         let seg_path = PenPath::new_w_segments(prev, [*seg]);
-                        let image = render::Image::gen_with_piet(
+                        let image = render::Image::gen_with_piet_aa(
                             |piet_cx| {
                                 seg_path.draw_composed(piet_cx, options);
                                 Ok(())
                             },
                             seg_path.composed_bounds(options),
                             image_scale,
+                            aa_quality,
                         );
                         match image {
                             Ok(image) => images.push(image),
@@ -142,13 +153,14 @@ impl Content for BrushStroke {
                     let mut prev = self.path.start;
                     for seg in self.path.segments.iter() {
                         let seg_path = PenPath::new_w_segments(prev, [*seg]);
-                        let image = render::Image::gen_with_piet(
+                        let image = render::Image::gen_with_piet_aa(
                             |piet_cx| {
                                 seg_path.draw_composed(piet_cx, &options);
                                 Ok(())
                             },
                             seg_path.composed_bounds(&options),
                             image_scale,
+                            aa_quality,
                         );
                         match image {
                             Ok(image) => images.push(image),
@@ -201,6 +213,7 @@ impl Content for BrushStroke {
     }
 
     fn update_geometry(&mut self) {
+        self.invalidate_bounds();
         self.hitboxes = self.gen_hitboxes_int();
     }
 }
@@ -225,11 +238,11 @@ impl Drawable for BrushStroke {
 
 impl Shapeable for BrushStroke {
     fn bounds(&self) -> Aabb {
-        match &self.style {
+        *self.bounds_cache.get_or_init(|| match &self.style {
             Style::Smooth(options) => self.path.composed_bounds(options),
             Style::Rough(_options) => unimplemented!(),
             Style::Textured(options) => self.path.composed_bounds(options),
-        }
+        })
     }
 
     fn hitboxes(&self) -> Vec<Aabb> {
@@ -243,12 +256,15 @@ impl Shapeable for BrushStroke {
 
 impl Transformable for BrushStroke {
     fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.invalidate_bounds();
         self.path.translate(offset);
     }
     fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.invalidate_bounds();
         self.path.rotate(angle, center);
     }
     fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.invalidate_bounds();
         self.path.scale(scale);
         // Using the geometric mean behaves the best when scaling non-uniformly.
         let scale_scalar = (scale[0] * scale[1]).sqrt();
@@ -269,7 +285,9 @@ impl BrushStroke {
         let mut new_brushstroke = Self {
             path,
             style,
+            curve_fit: None,
             hitboxes: vec![],
+            bounds_cache: OnceLock::new(),
         };
         new_brushstroke.update_geometry();
 
@@ -277,19 +295,40 @@ impl BrushStroke {
     }
 
     pub fn push_segment(&mut self, segment: Segment) {
+        self.invalidate_bounds();
         self.path.segments.push(segment);
     }
 
     pub fn extend_w_segments(&mut self, segments: impl IntoIterator<Item = Segment>) {
+        self.invalidate_bounds();
         self.path.extend(segments);
     }
 
+    /// Invalidate the cached bounds, which get recomputed when they are queried next.
+    ///
+    /// Must be called when modifying the path or the style directly through their fields.
+    pub fn invalidate_bounds(&mut self) {
+        self.bounds_cache.take();
+    }
+
     /// Replace the current path with the given new one. the new path must not be empty.
     pub fn replace_path(&mut self, path: PenPath) {
         self.path = path;
         self.update_geometry();
     }
 
+    /// Refit the path to its input elements with the given algorithm.
+    ///
+    /// The tolerance is the maximum distance of the input elements to the new path, used for [CurveFit::Bezier].
+    pub fn apply_curve_fit(&mut self, curve_fit: CurveFit, tolerance: f64) {
+        if self.path.segments.is_empty() {
+            return;
+        }
+        self.path = self.path.fitted(curve_fit, tolerance);
+        self.curve_fit = Some(curve_fit);
+        self.update_geometry();
+    }
+
     // internal method generating the current hitboxes.
     fn gen_hitboxes_int(&self) -> Vec<Aabb> {
         let stroke_width = self.style.stroke_width();
@@ -305,6 +344,7 @@ impl BrushStroke {
         &self,
         n_last_segments: usize,
         image_scale: f64,
+        aa_quality: render::AaQuality,
     ) -> Result<Option<render::Image>, anyhow::Error> {
         let image = match &self.style {
             Style::Smooth(options) => {
@@ -324,13 +364,14 @@ impl BrushStroke {
                         .copied(),
                 );
 
-                let image = render::Image::gen_with_piet(
+                let image = render::Image::gen_with_piet_aa(
                     |piet_cx| {
                         range_path.draw_composed(piet_cx, options);
                         Ok(())
                     },
                     range_path.composed_bounds(options),
                     image_scale,
+                    aa_quality,
                 )?;
 
                 Some(image)
@@ -358,13 +399,14 @@ impl BrushStroke {
                         .copied(),
                 );
 
-                let image = render::Image::gen_with_piet(
+                let image = render::Image::gen_with_piet_aa(
                     |piet_cx| {
                         range_path.draw_composed(piet_cx, &options);
                         Ok(())
                     },
                     range_path.composed_bounds(&options),
                     image_scale,
+                    aa_quality,
                 )?;
 
                 Some(image)
@@ -374,3 +416,84 @@ impl BrushStroke {
         Ok(image)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BrushStroke;
+    use crate::{render, Drawable};
+    use approx::assert_relative_eq;
+    use rnote_compose::penpath::{Element, Segment};
+    use rnote_compose::shapes::Shapeable;
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::transform::Transformable;
+    use rnote_compose::{Color, PenPath, Style};
+
+    #[test]
+    fn bounds_recompute_only_after_geometry_change() {
+        let mut brushstroke =
+            BrushStroke::new(Element::new(na::vector![0.0, 0.0], 0.5), Style::default());
+        brushstroke.push_segment(Segment::LineTo {
+            end: Element::new(na::vector![100.0, 0.0], 0.5),
+        });
+        assert!(brushstroke.bounds_cache.get().is_none());
+
+        let bounds = brushstroke.bounds();
+        assert_eq!(brushstroke.bounds_cache.get(), Some(&bounds));
+
+        // changing the color does not change the geometry
+        brushstroke.style.set_stroke_color(Color::RED);
+        assert_eq!(brushstroke.bounds_cache.get(), Some(&bounds));
+        assert_eq!(brushstroke.bounds(), bounds);
+
+        brushstroke.translate(na::vector![10.0, 20.0]);
+        assert!(brushstroke.bounds_cache.get().is_none());
+        let translated = brushstroke.bounds();
+        assert_relative_eq!(
+            translated.mins.coords,
+            bounds.mins.coords + na::vector![10.0, 20.0],
+            epsilon = 1e-9
+        );
+        assert_eq!(brushstroke.bounds_cache.get(), Some(&translated));
+
+        brushstroke.push_segment(Segment::LineTo {
+            end: Element::new(na::vector![110.0, 120.0], 0.5),
+        });
+        assert!(brushstroke.bounds().maxs[1] > translated.maxs[1]);
+    }
+
+    #[test]
+    fn pressure_opacity_does_not_stack_at_joins() {
+        let brushstroke = BrushStroke::from_penpath(
+            PenPath::new_w_segments(
+                Element::new(na::vector![0.0, 0.0], 0.5),
+                [30.0, 60.0, 90.0].map(|x| Segment::LineTo {
+                    end: Element::new(na::vector![x, 0.0], 0.5),
+                }),
+            ),
+            Style::Smooth(SmoothOptions {
+                stroke_width: 10.0,
+                stroke_color: Some(Color::BLACK),
+                pressure_opacity: true,
+                ..Default::default()
+            }),
+        );
+        let image = render::Image::gen_with_piet(
+            |piet_cx| brushstroke.draw(piet_cx, 1.0),
+            brushstroke.bounds(),
+            1.0,
+        )
+        .unwrap();
+        let mins = image.rect.bounds().mins;
+        let alpha = |pos: na::Vector2<f64>| {
+            let x = (pos[0] - mins[0]).floor() as usize;
+            let y = (pos[1] - mins[1]).floor() as usize;
+            image.data[(y * image.pixel_width as usize + x) * 4 + 3]
+        };
+
+        let segment_alpha = alpha(na::vector![15.0, 0.0]);
+        assert!(segment_alpha > 0 && segment_alpha < u8::MAX);
+        for join in [30.0, 60.0] {
+            assert_eq!(alpha(na::vector![join, 0.0]), segment_alpha);
+        }
+    }
+}
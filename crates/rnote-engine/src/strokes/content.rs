@@ -41,23 +41,26 @@ where
         &self,
         viewport: Aabb,
         image_scale: f64,
+        aa_quality: render::AaQuality,
     ) -> Result<GeneratedContentImages, anyhow::Error> {
         let bounds = self.bounds();
 
         if viewport.contains(&bounds) {
             Ok(GeneratedContentImages::Full(vec![
-                render::Image::gen_with_piet(
+                render::Image::gen_with_piet_aa(
                     |piet_cx| self.draw(piet_cx, image_scale),
                     bounds,
                     image_scale,
+                    aa_quality,
                 )?,
             ]))
         } else if let Some(intersection_bounds) = viewport.intersection(&bounds) {
             Ok(GeneratedContentImages::Partial {
-                images: vec![render::Image::gen_with_piet(
+                images: vec![render::Image::gen_with_piet_aa(
                     |piet_cx| self.draw(piet_cx, image_scale),
                     intersection_bounds,
                     image_scale,
+                    aa_quality,
                 )?],
                 viewport,
             })
@@ -1,6 +1,7 @@
 // Imports
 use super::Content;
-use crate::{strokes::content, Drawable};
+use crate::{strokes::content, utils, Drawable};
+use geo::{Area, BooleanOps, Intersects};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::ext::AabbExt;
 use rnote_compose::shapes::Shape;
@@ -101,6 +102,11 @@ impl Transformable for ShapeStroke {
 }
 
 impl ShapeStroke {
+    /// The tolerance when flattening curved shapes for carving.
+    const CARVE_TOLERANCE: f64 = 0.25;
+    /// Remaining parts with a smaller area are discarded when carving.
+    const CARVE_MIN_AREA: f64 = 0.5;
+
     pub fn new(shape: Shape, style: Style) -> Self {
         let mut shapestroke = Self {
             shape,
@@ -112,6 +118,56 @@ impl ShapeStroke {
         shapestroke
     }
 
+    /// Subtract the area from the fill of the shape.
+    ///
+    /// Returns None when the stroke is not a filled, closed shape or is not touched by the area.
+    /// Otherwise the remaining parts as polygon shapes, or compound paths for parts with holes.
+    /// It is empty when the area covers the entire shape.
+    pub fn carve(&self, area: &geo::MultiPolygon<f64>) -> Option<Vec<ShapeStroke>> {
+        if !self.style.fill_color().is_some_and(|color| color.a > 0.0) {
+            return None;
+        }
+        let fill = Self::fill_region(&self.shape)?;
+        if !fill.intersects(area) {
+            return None;
+        }
+
+        Some(
+            fill.difference(area)
+                .iter()
+                .filter(|polygon| polygon.unsigned_area() > Self::CARVE_MIN_AREA)
+                .filter_map(utils::geo_polygon_to_shape)
+                .map(|shape| ShapeStroke::new(shape, self.style.clone()))
+                .collect(),
+        )
+    }
+
+    /// The filled region of closed shapes, or of compound paths made up of only closed shapes.
+    fn fill_region(shape: &Shape) -> Option<geo::MultiPolygon<f64>> {
+        match shape {
+            Shape::Rectangle(_) | Shape::Ellipse(_) | Shape::Polygon(_) => {
+                utils::bezpath_to_geo_polygon(&shape.outline_path(), Self::CARVE_TOLERANCE)
+                    .map(|polygon| geo::MultiPolygon::new(vec![polygon]))
+            }
+            Shape::CompoundPath(compound) => compound
+                .subpaths
+                .iter()
+                .all(|subpath| {
+                    matches!(
+                        subpath,
+                        Shape::Rectangle(_) | Shape::Ellipse(_) | Shape::Polygon(_)
+                    )
+                })
+                .then(|| compound.even_odd_region(Self::CARVE_TOLERANCE)),
+            Shape::Line(_)
+            | Shape::Arrow(_)
+            | Shape::QuadraticBezier(_)
+            | Shape::CubicBezier(_)
+            | Shape::BezierPath(_)
+            | Shape::Polyline(_) => None,
+        }
+    }
+
     fn gen_hitboxes_int(&self) -> Vec<Aabb> {
         let width = self.style.stroke_width();
 
@@ -12,7 +12,7 @@ use crate::{utils, Drawable};
 use p2d::bounding_volume::Aabb;
 use rnote_compose::ext::AabbExt;
 use rnote_compose::penpath::Element;
-use rnote_compose::shapes::{Rectangle, Shapeable};
+use rnote_compose::shapes::{Rectangle, Shape, Shapeable};
 use rnote_compose::style::smooth::SmoothOptions;
 use rnote_compose::transform::Transform;
 use rnote_compose::transform::Transformable;
@@ -50,13 +50,24 @@ impl Content for Stroke {
         &self,
         viewport: Aabb,
         image_scale: f64,
+        aa_quality: render::AaQuality,
     ) -> Result<GeneratedContentImages, anyhow::Error> {
         match self {
-            Stroke::BrushStroke(brushstroke) => brushstroke.gen_images(viewport, image_scale),
-            Stroke::ShapeStroke(shapestroke) => shapestroke.gen_images(viewport, image_scale),
-            Stroke::TextStroke(textstroke) => textstroke.gen_images(viewport, image_scale),
-            Stroke::VectorImage(vectorimage) => vectorimage.gen_images(viewport, image_scale),
-            Stroke::BitmapImage(bitmapimage) => bitmapimage.gen_images(viewport, image_scale),
+            Stroke::BrushStroke(brushstroke) => {
+                brushstroke.gen_images(viewport, image_scale, aa_quality)
+            }
+            Stroke::ShapeStroke(shapestroke) => {
+                shapestroke.gen_images(viewport, image_scale, aa_quality)
+            }
+            Stroke::TextStroke(textstroke) => {
+                textstroke.gen_images(viewport, image_scale, aa_quality)
+            }
+            Stroke::VectorImage(vectorimage) => {
+                vectorimage.gen_images(viewport, image_scale, aa_quality)
+            }
+            Stroke::BitmapImage(bitmapimage) => {
+                bitmapimage.gen_images(viewport, image_scale, aa_quality)
+            }
         }
     }
 
@@ -260,6 +271,35 @@ impl Stroke {
         }
     }
 
+    /// Replace all colors of the stroke with the mapped colors.
+    ///
+    /// Returns true if the stroke was modified and needs to update its rendering.
+    pub fn map_colors(&mut self, map: impl Fn(Color) -> Color) -> bool {
+        match self {
+            Stroke::BrushStroke(BrushStroke { style, .. })
+            | Stroke::ShapeStroke(ShapeStroke { style, .. }) => {
+                if let Some(color) = style.stroke_color() {
+                    style.set_stroke_color(map(color));
+                }
+                if let Some(color) = style.fill_color() {
+                    style.set_fill_color(map(color));
+                }
+                if let Style::Smooth(options) = style {
+                    options.pressure_color = options.pressure_color.map(&map);
+                }
+
+                true
+            }
+            Stroke::TextStroke(text_stroke) => {
+                text_stroke.text_style.color = map(text_stroke.text_style.color);
+
+                true
+            }
+            Stroke::VectorImage(_) => false,
+            Stroke::BitmapImage(_) => false,
+        }
+    }
+
     /// Set all colors of the stroke to their darkest variant.
     ///
     /// Returns true if the stroke was modified and needs to update its rendering.
@@ -301,6 +341,233 @@ impl Stroke {
         }
     }
 
+    /// Whether both strokes are drawn with the same stroke color, fill color and stroke width.
+    ///
+    /// Only brush and shape strokes have a style, others are never considered to be the same.
+    pub fn has_same_style(&self, other: &Self) -> bool {
+        fn style(stroke: &Stroke) -> Option<&Style> {
+            match stroke {
+                Stroke::BrushStroke(brush_stroke) => Some(&brush_stroke.style),
+                Stroke::ShapeStroke(shape_stroke) => Some(&shape_stroke.style),
+                Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => None,
+            }
+        }
+        let (Some(style), Some(other_style)) = (style(self), style(other)) else {
+            return false;
+        };
+        style.stroke_color() == other_style.stroke_color()
+            && style.fill_color() == other_style.fill_color()
+            && style.stroke_width() == other_style.stroke_width()
+    }
+
+    /// The start and end positions of open paths, which other strokes can connect to.
+    ///
+    /// Closed shapes, texts and images don't have endpoints.
+    pub fn endpoints(&self) -> Vec<na::Vector2<f64>> {
+        match self {
+            Stroke::BrushStroke(brush_stroke) => {
+                let path = &brush_stroke.path;
+                std::iter::once(path.start.pos)
+                    .chain(path.segments.last().map(|seg| seg.end().pos))
+                    .collect()
+            }
+            Stroke::ShapeStroke(shape_stroke) => match &shape_stroke.shape {
+                Shape::Line(line) => vec![line.start, line.end],
+                Shape::Arrow(arrow) => vec![arrow.start, arrow.tip],
+                Shape::Polyline(polyline) => std::iter::once(polyline.start)
+                    .chain(polyline.path.last().copied())
+                    .collect(),
+                Shape::QuadraticBezier(quadbez) => vec![quadbez.start, quadbez.end],
+                Shape::CubicBezier(cubbez) => vec![cubbez.start, cubbez.end],
+                Shape::BezierPath(bezier_path) => bezier_path
+                    .start()
+                    .into_iter()
+                    .chain(bezier_path.end())
+                    .collect(),
+                Shape::Rectangle(_)
+                | Shape::Ellipse(_)
+                | Shape::Polygon(_)
+                | Shape::CompoundPath(_) => vec![],
+            },
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => vec![],
+        }
+    }
+
+    /// The number of points that define the stroke geometry, for example the input elements of brush strokes.
+    ///
+    /// Texts and images don't have points.
+    pub fn point_count(&self) -> usize {
+        match self {
+            Stroke::BrushStroke(brush_stroke) => 1 + brush_stroke.path.segments.len(),
+            Stroke::ShapeStroke(shape_stroke) => shape_point_count(&shape_stroke.shape),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => 0,
+        }
+    }
+
+    /// The outline path of brush and shape strokes flattened to points, None for other strokes.
+    pub(crate) fn flattened_outline(&self, tolerance: f64) -> Option<Vec<na::Vector2<f64>>> {
+        let path = match self {
+            Stroke::BrushStroke(brushstroke) => brushstroke.outline_path(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.shape.outline_path(),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => return None,
+        };
+        let mut points = Vec::new();
+        kurbo::flatten(path, tolerance, |el| match el {
+            kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) => {
+                points.push(na::vector![p.x, p.y])
+            }
+            _ => {}
+        });
+        (!points.is_empty()).then_some(points)
+    }
+
+    /// Render a preview of the stroke, scaled to fit into a square image of `size` pixels and centered in it.
+    ///
+    /// The background is transparent.
+    pub fn render_preview(
+        &self,
+        size: u32,
+        aa_quality: render::AaQuality,
+    ) -> anyhow::Result<image::RgbaImage> {
+        if size == 0 {
+            return Err(anyhow::anyhow!(
+                "rendering stroke preview with size 0 failed."
+            ));
+        }
+        let bounds = self.bounds();
+        let side = bounds.extents().max().max(1.0);
+        // The image generation loosens the bounds by 1.0 on each side
+        let image_scale = size as f64 / (side + 2.0);
+
+        render::Image::gen_with_cairo_aa(
+            |cairo_cx| self.draw_to_cairo(cairo_cx, image_scale),
+            Aabb::from_half_extents(bounds.center(), na::Vector2::repeat(side * 0.5)),
+            image_scale,
+            aa_quality,
+        )?
+        .into_imgbuf()
+    }
+
+    /// An approximation of the memory the stroke occupies in bytes, including its heap allocations.
+    ///
+    /// Does not include the rendered images.
+    pub fn approx_bytes(&self) -> usize {
+        let heap_bytes = match self {
+            Stroke::BrushStroke(brush_stroke) => {
+                std::mem::size_of_val(brush_stroke.path.segments.as_slice())
+            }
+            Stroke::ShapeStroke(shape_stroke) => match &shape_stroke.shape {
+                Shape::Polyline(polyline) => std::mem::size_of_val(polyline.path.as_slice()),
+                Shape::Polygon(polygon) => std::mem::size_of_val(polygon.path.as_slice()),
+                Shape::BezierPath(bezier_path) => {
+                    std::mem::size_of_val(bezier_path.curves.as_slice())
+                }
+                Shape::CompoundPath(compound) => {
+                    std::mem::size_of_val(compound.subpaths.as_slice())
+                }
+                _ => 0,
+            },
+            Stroke::TextStroke(text_stroke) => {
+                text_stroke.text.len()
+                    + text_stroke.text_style.font_family.len()
+                    + std::mem::size_of_val(
+                        text_stroke.text_style.ranged_text_attributes.as_slice(),
+                    )
+            }
+            Stroke::VectorImage(vector_image) => vector_image.svg_data.len(),
+            Stroke::BitmapImage(bitmap_image) => bitmap_image.image.data.len(),
+        };
+        std::mem::size_of::<Self>() + heap_bytes
+    }
+
+    /// Invalidate the cached bounds of the stroke. Only brush strokes cache their bounds.
+    ///
+    /// Must be called when the geometry was modified directly through the fields, without calling
+    /// `update_geometry()`.
+    pub fn invalidate_bounds(&mut self) {
+        if let Stroke::BrushStroke(brushstroke) = self {
+            brushstroke.invalidate_bounds();
+        }
+    }
+
+    /// Transform the stroke with an arbitrary affine transformation.
+    ///
+    /// The linear part is decomposed into a rotation, a (possibly mirroring) scale and another rotation. The stroke width
+    /// gets scaled with the square root of the area scale factor when `scale_width` is true, else it is kept.
+    pub fn transform(&mut self, transform: na::Affine2<f64>, scale_width: bool) {
+        let matrix = transform.matrix();
+        let linear = matrix.fixed_view::<2, 2>(0, 0).into_owned();
+        let translation = na::vector![matrix[(0, 2)], matrix[(1, 2)]];
+        let svd = linear.svd(true, true);
+        let (Some(mut u), Some(mut v_t)) = (svd.u, svd.v_t) else {
+            return;
+        };
+        let mut singular_values = svd.singular_values;
+        // Turn reflections of the orthogonal factors into a negative scale, so that they become rotations
+        if u.determinant() < 0.0 {
+            u.set_column(0, &(-u.column(0)));
+            singular_values[0] = -singular_values[0];
+        }
+        if v_t.determinant() < 0.0 {
+            v_t.set_row(0, &(-v_t.row(0)));
+            singular_values[0] = -singular_values[0];
+        }
+        let stroke_width = match self {
+            Stroke::BrushStroke(brush_stroke) => Some(brush_stroke.style.stroke_width()),
+            Stroke::ShapeStroke(shape_stroke) => Some(shape_stroke.style.stroke_width()),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => None,
+        };
+
+        self.rotate(v_t[(1, 0)].atan2(v_t[(0, 0)]), na::Point2::origin());
+        self.scale(singular_values);
+        self.rotate(u[(1, 0)].atan2(u[(0, 0)]), na::Point2::origin());
+        self.translate(translation);
+
+        if let Some(stroke_width) = stroke_width {
+            let stroke_width = if scale_width {
+                stroke_width * linear.determinant().abs().sqrt()
+            } else {
+                stroke_width
+            };
+            match self {
+                Stroke::BrushStroke(brush_stroke) => {
+                    brush_stroke.style.set_stroke_width(stroke_width);
+                    brush_stroke.invalidate_bounds();
+                }
+                Stroke::ShapeStroke(shape_stroke) => {
+                    shape_stroke.style.set_stroke_width(stroke_width)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// A copy of the stroke with its stroke width widened to at least `min_width`, used for rendering.
+    ///
+    /// Returns `None` when the stroke has no stroke width or it is already wide enough.
+    pub fn with_min_stroke_width(&self, min_width: f64) -> Option<Self> {
+        let style = match self {
+            Stroke::BrushStroke(brush_stroke) => &brush_stroke.style,
+            Stroke::ShapeStroke(shape_stroke) => &shape_stroke.style,
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {
+                return None;
+            }
+        };
+        if style.stroke_width() >= min_width {
+            return None;
+        }
+        let mut stroke = self.clone();
+        match &mut stroke {
+            Stroke::BrushStroke(brush_stroke) => {
+                brush_stroke.style.set_stroke_width(min_width);
+                brush_stroke.invalidate_bounds();
+            }
+            Stroke::ShapeStroke(shape_stroke) => shape_stroke.style.set_stroke_width(min_width),
+            _ => {}
+        }
+        Some(stroke)
+    }
+
     pub fn from_xoppstroke(
         stroke: xoppformat::XoppStroke,
         offset: na::Vector2<f64>,
@@ -421,10 +688,11 @@ impl Stroke {
         let rectangle = Rectangle {
             cuboid: p2d::shape::Cuboid::new(bounds.half_extents()),
             transform: Transform::new_w_isometry(na::Isometry2::new(bounds.center().coords, 0.0)),
+            corner_radius: 0.0,
         };
         let image = render::Image::try_from_encoded_bytes(&bytes)?;
 
-        Ok(Stroke::BitmapImage(BitmapImage { image, rectangle }))
+        Ok(Stroke::BitmapImage(BitmapImage::new(image, rectangle)))
     }
 
     pub fn into_xopp(self, current_dpi: f64) -> Option<xoppformat::XoppStrokeType> {
@@ -459,9 +727,12 @@ impl Stroke {
                 let mut pressures: Vec<f64> = elements_vec
                     .iter()
                     .map(|element| match &stroke_style {
-                        Style::Smooth(options) => {
-                            options.pressure_curve.apply(stroke_width, element.pressure)
-                        }
+                        // respects the minimum width of the tapered ends
+                        Style::Smooth(options) => utils::convert_value_dpi(
+                            options.width_for_pressure(element.pressure),
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
                         Style::Rough(_) | Style::Textured(_) => stroke_width * element.pressure,
                     })
                     .collect();
@@ -673,3 +944,74 @@ impl Stroke {
         }
     }
 }
+
+/// The number of points that define the shape geometry.
+fn shape_point_count(shape: &Shape) -> usize {
+    match shape {
+        Shape::Line(_) | Shape::Arrow(_) => 2,
+        Shape::Rectangle(_) => 4,
+        Shape::Ellipse(_) => 1,
+        Shape::QuadraticBezier(_) => 3,
+        Shape::CubicBezier(_) => 4,
+        Shape::BezierPath(bezier_path) => 1 + bezier_path.curves.len() * 3,
+        Shape::Polyline(polyline) => 1 + polyline.path.len(),
+        Shape::Polygon(polygon) => 1 + polygon.path.len(),
+        Shape::CompoundPath(compound) => compound.subpaths.iter().map(shape_point_count).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::AaQuality;
+
+    #[test]
+    fn render_preview_is_square_and_centered() {
+        let stroke = Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![100.0, 50.0],
+                na::point![500.0, 150.0],
+            ))),
+            Style::Smooth(SmoothOptions {
+                fill_color: Some(Color::BLACK),
+                ..Default::default()
+            }),
+        ));
+
+        let preview = stroke.render_preview(64, AaQuality::Fast).unwrap();
+        assert_eq!((preview.width(), preview.height()), (64, 64));
+        // the filled rectangle covers the center, while the top and bottom rows stay transparent
+        assert_ne!(preview.get_pixel(32, 32)[3], 0);
+        assert_eq!(preview.get_pixel(32, 0)[3], 0);
+        assert_eq!(preview.get_pixel(32, 63)[3], 0);
+    }
+
+    #[test]
+    fn xopp_export_respects_min_width() {
+        let path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 0.0], 0.0),
+            [rnote_compose::penpath::Segment::LineTo {
+                end: Element::new(na::vector![10.0, 0.0], 1.0),
+            }],
+        );
+        let stroke = Stroke::BrushStroke(BrushStroke::from_penpath(
+            path,
+            Style::Smooth(SmoothOptions {
+                stroke_width: 4.0,
+                min_width: 1.0,
+                ..Default::default()
+            }),
+        ));
+
+        let Some(xoppformat::XoppStrokeType::XoppStroke(xopp_stroke)) =
+            stroke.into_xopp(xoppformat::XoppFile::DPI)
+        else {
+            panic!("brush stroke wasn't exported as xopp stroke");
+        };
+        // the tapered start is widened to the minimum width
+        assert_eq!(xopp_stroke.width.len(), 3);
+        for (width, expected) in xopp_stroke.width.into_iter().zip([4.0, 1.0, 4.0]) {
+            approx::assert_relative_eq!(width, expected);
+        }
+    }
+}
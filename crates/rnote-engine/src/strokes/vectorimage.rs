@@ -77,14 +77,16 @@ impl Content for VectorImage {
         &self,
         _viewport: Aabb,
         image_scale: f64,
+        aa_quality: render::AaQuality,
     ) -> Result<GeneratedContentImages, anyhow::Error> {
         let bounds = self.bounds();
         // always generate full stroke images for vectorimages, they are too expensive to be repeatedly rendered
         Ok(GeneratedContentImages::Full(vec![
-            render::Image::gen_with_piet(
+            render::Image::gen_with_piet_aa(
                 |piet_cx| self.draw(piet_cx, image_scale),
                 bounds,
                 image_scale,
+                aa_quality,
             )?,
         ]))
     }
@@ -176,6 +178,7 @@ impl VectorImage {
                 Rectangle {
                     cuboid: p2d::shape::Cuboid::new(intrinsic_size * 0.5),
                     transform,
+                    corner_radius: 0.0,
                 }
             }
             ImageSizeOption::ImposeSize(given_size) => {
@@ -184,6 +187,7 @@ impl VectorImage {
                 Rectangle {
                     cuboid: p2d::shape::Cuboid::new(given_size * 0.5),
                     transform,
+                    corner_radius: 0.0,
                 }
             }
             ImageSizeOption::ResizeImage(resize_struct) => {
@@ -193,6 +197,7 @@ impl VectorImage {
                 Rectangle {
                     cuboid: p2d::shape::Cuboid::new(intrinsic_size * resize_ratio * 0.5),
                     transform,
+                    corner_radius: 0.0,
                 }
             }
         };
@@ -0,0 +1,298 @@
+/// A touch input event. Positions are in surface coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TouchEvent {
+    /// A touch point started.
+    Down {
+        /// The identifier of the touch point, unique while it is active.
+        id: u64,
+        /// The position of the touch point.
+        pos: na::Vector2<f64>,
+    },
+    /// A touch point moved.
+    Motion {
+        /// The identifier of the touch point.
+        id: u64,
+        /// The new position of the touch point.
+        pos: na::Vector2<f64>,
+    },
+    /// A touch point ended.
+    Up {
+        /// The identifier of the touch point.
+        id: u64,
+    },
+    /// All touch points vanished unexpectedly.
+    Cancel,
+}
+
+/// A pan and zoom step produced by a two-finger gesture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanZoom {
+    /// The translation of the gesture center since the last step, in surface coordinates.
+    pub pan: na::Vector2<f64>,
+    /// The zoom factor relative to the last step.
+    pub zoom: f64,
+    /// The current gesture center, in surface coordinates. Zooming should happen around it.
+    pub center: na::Vector2<f64>,
+}
+
+/// What should be done with a touch event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TouchGestureResult {
+    /// The event should be routed to the pens as drawing input.
+    Pen,
+    /// A drawing gesture turned into a pan/zoom gesture.
+    ///
+    /// The partial stroke should be cancelled by sending [PenEvent::Cancel](rnote_compose::penevent::PenEvent::Cancel) to the pens.
+    CancelPen,
+    /// The pan and zoom should be applied to the camera.
+    PanZoom(PanZoom),
+    /// Nothing needs to be done.
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GestureState {
+    Idle,
+    Drawing {
+        id: u64,
+    },
+    PanZoom {
+        prev_center: na::Vector2<f64>,
+        prev_distance: f64,
+    },
+    /// The pan/zoom gesture ended, but not all touch points are released yet.
+    ///
+    /// Drawing only resumes after all touch points were released.
+    WaitRelease,
+}
+
+/// Recognizes two-finger pan/zoom gestures in touch input, and decides which events are routed to the pens.
+///
+/// A gesture that starts with one finger is drawing input. When a second finger lands, the gesture is converted
+/// into a pan/zoom gesture and the partial stroke gets cancelled.
+#[derive(Debug, Clone)]
+pub struct TouchGestureRecognizer {
+    /// The active touch points, in the order they landed.
+    touches: Vec<(u64, na::Vector2<f64>)>,
+    state: GestureState,
+}
+
+impl Default for TouchGestureRecognizer {
+    fn default() -> Self {
+        Self {
+            touches: Vec::new(),
+            state: GestureState::Idle,
+        }
+    }
+}
+
+impl TouchGestureRecognizer {
+    /// The distance between the touch points is clamped to this minimum when calculating the zoom,
+    /// avoiding huge zoom factors when the fingers are very close together.
+    const DISTANCE_MIN: f64 = 1.0;
+
+    /// Whether the recognizer currently routes events to the pens.
+    pub fn is_drawing(&self) -> bool {
+        matches!(self.state, GestureState::Drawing { .. })
+    }
+
+    /// Handle a touch event.
+    pub fn handle_event(&mut self, event: TouchEvent) -> TouchGestureResult {
+        match event {
+            TouchEvent::Down { id, pos } => {
+                self.touches.retain(|(touch_id, _)| *touch_id != id);
+                self.touches.push((id, pos));
+
+                match self.state {
+                    GestureState::Idle => {
+                        self.state = GestureState::Drawing { id };
+                        TouchGestureResult::Pen
+                    }
+                    GestureState::Drawing { .. } => {
+                        self.start_pan_zoom();
+                        TouchGestureResult::CancelPen
+                    }
+                    GestureState::PanZoom { .. } => TouchGestureResult::None,
+                    GestureState::WaitRelease => {
+                        if self.touches.len() >= 2 {
+                            self.start_pan_zoom();
+                        }
+                        TouchGestureResult::None
+                    }
+                }
+            }
+            TouchEvent::Motion { id, pos } => {
+                if let Some(touch) = self
+                    .touches
+                    .iter_mut()
+                    .find(|(touch_id, _)| *touch_id == id)
+                {
+                    touch.1 = pos;
+                }
+
+                match self.state {
+                    GestureState::Idle | GestureState::WaitRelease => TouchGestureResult::None,
+                    GestureState::Drawing { id: drawing_id } => {
+                        if drawing_id == id {
+                            TouchGestureResult::Pen
+                        } else {
+                            TouchGestureResult::None
+                        }
+                    }
+                    GestureState::PanZoom {
+                        prev_center,
+                        prev_distance,
+                    } => {
+                        let Some((center, distance)) = self.center_distance() else {
+                            return TouchGestureResult::None;
+                        };
+                        self.state = GestureState::PanZoom {
+                            prev_center: center,
+                            prev_distance: distance,
+                        };
+                        TouchGestureResult::PanZoom(PanZoom {
+                            pan: center - prev_center,
+                            zoom: distance / prev_distance,
+                            center,
+                        })
+                    }
+                }
+            }
+            TouchEvent::Up { id } => {
+                self.touches.retain(|(touch_id, _)| *touch_id != id);
+
+                match self.state {
+                    GestureState::Idle => TouchGestureResult::None,
+                    GestureState::Drawing { id: drawing_id } => {
+                        if drawing_id == id {
+                            self.state = GestureState::Idle;
+                            TouchGestureResult::Pen
+                        } else {
+                            TouchGestureResult::None
+                        }
+                    }
+                    GestureState::PanZoom { .. } => {
+                        if self.touches.len() >= 2 {
+                            // Continue with the remaining touch points
+                            self.start_pan_zoom();
+                        } else if self.touches.is_empty() {
+                            self.state = GestureState::Idle;
+                        } else {
+                            self.state = GestureState::WaitRelease;
+                        }
+                        TouchGestureResult::None
+                    }
+                    GestureState::WaitRelease => {
+                        if self.touches.is_empty() {
+                            self.state = GestureState::Idle;
+                        }
+                        TouchGestureResult::None
+                    }
+                }
+            }
+            TouchEvent::Cancel => {
+                let was_drawing = self.is_drawing();
+                self.touches.clear();
+                self.state = GestureState::Idle;
+                if was_drawing {
+                    TouchGestureResult::Pen
+                } else {
+                    TouchGestureResult::None
+                }
+            }
+        }
+    }
+
+    fn start_pan_zoom(&mut self) {
+        if let Some((center, distance)) = self.center_distance() {
+            self.state = GestureState::PanZoom {
+                prev_center: center,
+                prev_distance: distance,
+            };
+        }
+    }
+
+    /// The center and the distance of the first two touch points.
+    fn center_distance(&self) -> Option<(na::Vector2<f64>, f64)> {
+        let [(_, first), (_, second), ..] = self.touches.as_slice() else {
+            return None;
+        };
+        Some((
+            (first + second) * 0.5,
+            (second - first).magnitude().max(Self::DISTANCE_MIN),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TouchEvent, TouchGestureRecognizer, TouchGestureResult};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn one_to_two_finger_transition() {
+        let mut recognizer = TouchGestureRecognizer::default();
+
+        // one finger draws
+        assert_eq!(
+            recognizer.handle_event(TouchEvent::Down {
+                id: 0,
+                pos: na::vector![100.0, 100.0]
+            }),
+            TouchGestureResult::Pen
+        );
+        assert_eq!(
+            recognizer.handle_event(TouchEvent::Motion {
+                id: 0,
+                pos: na::vector![110.0, 100.0]
+            }),
+            TouchGestureResult::Pen
+        );
+
+        // a second finger converts it into pan/zoom, cancelling the stroke
+        assert_eq!(
+            recognizer.handle_event(TouchEvent::Down {
+                id: 1,
+                pos: na::vector![210.0, 100.0]
+            }),
+            TouchGestureResult::CancelPen
+        );
+        assert!(!recognizer.is_drawing());
+
+        let TouchGestureResult::PanZoom(pan_zoom) = recognizer.handle_event(TouchEvent::Motion {
+            id: 1,
+            pos: na::vector![310.0, 100.0],
+        }) else {
+            panic!("expected a pan/zoom result");
+        };
+        assert_relative_eq!(pan_zoom.pan, na::vector![50.0, 0.0]);
+        assert_relative_eq!(pan_zoom.zoom, 2.0);
+        assert_relative_eq!(pan_zoom.center, na::vector![210.0, 100.0]);
+
+        // lifting one finger does not resume drawing
+        assert_eq!(
+            recognizer.handle_event(TouchEvent::Up { id: 1 }),
+            TouchGestureResult::None
+        );
+        assert_eq!(
+            recognizer.handle_event(TouchEvent::Motion {
+                id: 0,
+                pos: na::vector![120.0, 100.0]
+            }),
+            TouchGestureResult::None
+        );
+        assert_eq!(
+            recognizer.handle_event(TouchEvent::Up { id: 0 }),
+            TouchGestureResult::None
+        );
+
+        // after every finger is released, drawing starts again
+        assert_eq!(
+            recognizer.handle_event(TouchEvent::Down {
+                id: 2,
+                pos: na::vector![0.0, 0.0]
+            }),
+            TouchGestureResult::Pen
+        );
+    }
+}
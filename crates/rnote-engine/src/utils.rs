@@ -71,6 +71,69 @@ pub fn p2d_aabb_to_geo_polygon(aabb: Aabb) -> geo::Polygon<f64> {
     geo::Polygon::new(line_string, vec![])
 }
 
+/// Convert the first subpath of a [kurbo::BezPath] to a [`geo::Polygon<f64>`], flattening curves with the given tolerance.
+///
+/// The subpath is treated as closed. Returns None when it has less than three points.
+pub fn bezpath_to_geo_polygon(path: &kurbo::BezPath, tolerance: f64) -> Option<geo::Polygon<f64>> {
+    let mut coords: Vec<geo::Coord<f64>> = Vec::new();
+    let mut subpaths = 0;
+
+    kurbo::flatten(path.elements().iter().copied(), tolerance, |el| match el {
+        kurbo::PathEl::MoveTo(p) => {
+            subpaths += 1;
+            if subpaths == 1 {
+                coords.push(geo::Coord { x: p.x, y: p.y });
+            }
+        }
+        kurbo::PathEl::LineTo(p) if subpaths == 1 => coords.push(geo::Coord { x: p.x, y: p.y }),
+        _ => {}
+    });
+
+    if coords.len() < 3 {
+        return None;
+    }
+    Some(geo::Polygon::new(geo::LineString::new(coords), vec![]))
+}
+
+/// Convert a [`geo::Polygon<f64>`] to a shape.
+///
+/// A polygon with holes becomes a compound path with a subpath for the exterior and for every hole, so the holes are
+/// cut out of the fill without joining them to the exterior. Returns None when the exterior has less than three points.
+pub fn geo_polygon_to_shape(polygon: &geo::Polygon<f64>) -> Option<rnote_compose::Shape> {
+    fn ring_polygon(ring: &geo::LineString<f64>) -> Option<rnote_compose::shapes::Polygon> {
+        let mut points = ring
+            .coords()
+            .map(|coord| na::vector![coord.x, coord.y])
+            .collect::<Vec<na::Vector2<f64>>>();
+        // geo rings are closed by repeating the first coordinate
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() < 3 {
+            return None;
+        }
+        let mut points = points.into_iter();
+        let start = points.next()?;
+        Some(rnote_compose::shapes::Polygon {
+            start,
+            path: points.collect(),
+            corner_radius: 0.0,
+        })
+    }
+
+    let exterior = ring_polygon(polygon.exterior())?;
+    if polygon.interiors().is_empty() {
+        return Some(rnote_compose::Shape::Polygon(exterior));
+    }
+    Some(rnote_compose::Shape::CompoundPath(
+        rnote_compose::shapes::CompoundPath::new(
+            std::iter::once(exterior)
+                .chain(polygon.interiors().iter().filter_map(ring_polygon))
+                .map(rnote_compose::Shape::Polygon),
+        ),
+    ))
+}
+
 pub fn positive_range<I>(first: I, second: I) -> Range<I>
 where
     I: PartialOrd,
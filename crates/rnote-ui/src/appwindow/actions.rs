@@ -76,6 +76,9 @@ impl RnAppWindow {
         let action_snap_positions =
             gio::SimpleAction::new_stateful("snap-positions", None, &false.to_variant());
         self.add_action(&action_snap_positions);
+        let action_clamp_input =
+            gio::SimpleAction::new_stateful("clamp-input", None, &false.to_variant());
+        self.add_action(&action_clamp_input);
         let action_show_format_borders =
             gio::SimpleAction::new_stateful("show-format-borders", None, &true.to_variant());
         self.add_action(&action_show_format_borders);
@@ -146,6 +149,8 @@ impl RnAppWindow {
         self.add_action(&action_export_doc_pages);
         let action_export_selection = gio::SimpleAction::new("export-selection", None);
         self.add_action(&action_export_selection);
+        let action_export_crop_region = gio::SimpleAction::new("export-crop-region", None);
+        self.add_action(&action_export_crop_region);
         let action_clipboard_copy = gio::SimpleAction::new("clipboard-copy", None);
         self.add_action(&action_clipboard_copy);
         let action_clipboard_cut = gio::SimpleAction::new("clipboard-cut", None);
@@ -377,6 +382,20 @@ impl RnAppWindow {
             }
         ));
 
+        // Clamp input
+        action_clamp_input.connect_change_state(clone!(
+            #[weak(rename_to=appwindow)]
+            self,
+            move |action, state_request| {
+                let clamp_input = state_request.unwrap().get::<bool>().unwrap();
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+                canvas.engine_mut().document.clamp_input = clamp_input;
+                action.set_state(&clamp_input.to_variant());
+            }
+        ));
+
         // Show format borders
         action_show_format_borders.connect_change_state(clone!(
             #[weak(rename_to=appwindow)]
@@ -1014,6 +1033,32 @@ impl RnAppWindow {
             }
         ));
 
+        // Export cropped region
+        action_export_crop_region.connect_activate(clone!(
+            #[weak(rename_to=appwindow)]
+            self,
+            move |_, _| {
+                glib::spawn_future_local(clone!(
+                    #[weak]
+                    appwindow,
+                    async move {
+                        let Some(canvas) = appwindow.active_tab_canvas() else {
+                            return;
+                        };
+
+                        let Some(region) = canvas.engine_ref().crop_region() else {
+                            appwindow.overlays().dispatch_toast_error(&gettext(
+                                "Exporting cropped region failed, no region cropped",
+                            ));
+                            return;
+                        };
+                        dialogs::export::dialog_export_crop_region(&appwindow, &canvas, region)
+                            .await;
+                    }
+                ));
+            }
+        ));
+
         // Clipboard copy
         action_clipboard_copy.connect_activate(clone!(#[weak(rename_to=appwindow)] self, move |_, _| {
             glib::spawn_future_local(clone!(#[weak] appwindow, async move {
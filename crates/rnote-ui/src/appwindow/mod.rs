@@ -650,6 +650,7 @@ impl RnAppWindow {
         let doc_format = canvas.engine_ref().document.format;
         let total_zoom = canvas.engine_ref().camera.total_zoom();
         let snap_positions = canvas.engine_ref().document.snap_positions;
+        let clamp_input = canvas.engine_ref().document.clamp_input;
         let can_undo = canvas.engine_ref().can_undo();
         let can_redo = canvas.engine_ref().can_redo();
 
@@ -682,6 +683,11 @@ impl RnAppWindow {
             "snap-positions",
             &snap_positions.to_variant(),
         );
+        adw::prelude::ActionGroupExt::change_action_state(
+            self,
+            "clamp-input",
+            &clamp_input.to_variant(),
+        );
         adw::prelude::ActionGroupExt::change_action_state(
             self,
             "show-format-borders",
@@ -4,6 +4,7 @@ use anyhow::Context;
 use futures::channel::oneshot;
 use futures::AsyncWriteExt;
 use gtk4::{gio, prelude::*};
+use p2d::bounding_volume::Aabb;
 use rnote_compose::ext::Vector2Ext;
 use rnote_engine::engine::export::{DocExportPrefs, DocPagesExportPrefs, SelectionExportPrefs};
 use rnote_engine::engine::{EngineSnapshot, StrokeContent};
@@ -342,6 +343,26 @@ impl RnCanvas {
         Ok(())
     }
 
+    /// Exports the cropped region of the document with the selection export preferences.
+    pub(crate) async fn export_region(
+        &self,
+        file: &gio::File,
+        region: Aabb,
+        export_prefs_override: Option<SelectionExportPrefs>,
+    ) -> anyhow::Result<()> {
+        let export_bytes = self
+            .engine_ref()
+            .export_region(region, export_prefs_override);
+
+        if let Some(export_bytes) = export_bytes.await?? {
+            crate::utils::create_replace_file_future(export_bytes, file).await?;
+        }
+
+        self.set_last_export_dir(file.parent());
+
+        Ok(())
+    }
+
     /// exports and writes the engine state as json into the file.
     /// Only for debugging!
     pub(crate) async fn export_engine_state(&self, file: &gio::File) -> anyhow::Result<()> {
@@ -9,6 +9,7 @@ use rnote_engine::pens::penholder::BacklogPolicy;
 use rnote_engine::pens::PenMode;
 use rnote_engine::WidgetFlags;
 use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::time::{Duration, Instant};
 use tracing::trace;
 
@@ -23,7 +24,7 @@ pub(crate) fn handle_pointer_controller_event(
     let touch_drawing = canvas.touch_drawing();
     let gdk_event_type = event.event_type();
     let gdk_modifiers = event.modifier_state();
-    let _gdk_device = event.device().unwrap();
+    let pointer_id = retrieve_pointer_id(event);
     let backlog_policy = canvas.engine_ref().penholder.backlog_policy();
     let is_stylus = event_is_stylus(event);
 
@@ -157,9 +158,11 @@ pub(crate) fn handle_pointer_controller_event(
     };
 
     if handle_pen_event {
-        let Some(elements) = retrieve_pointer_elements(canvas, now, event, backlog_policy) else {
+        let Some(mut elements) = retrieve_pointer_elements(canvas, now, event, backlog_policy)
+        else {
             return (glib::Propagation::Proceed, pen_state);
         };
+        canvas.engine_ref().reorder_input_batch(&mut elements);
         let modifier_keys = retrieve_modifier_keys(event.modifier_state());
         let pen_mode = retrieve_pen_mode(event);
 
@@ -182,11 +185,12 @@ pub(crate) fn handle_pointer_controller_event(
                 PenState::Up => {
                     canvas.enable_drawing_cursor(false);
 
-                    let (ep, wf) = canvas.engine_mut().handle_pen_event(
+                    let (ep, wf) = canvas.engine_mut().handle_pen_event_for_pointer(
                         PenEvent::Up {
                             element,
                             modifier_keys: modifier_keys.clone(),
                         },
+                        pointer_id,
                         pen_mode,
                         event_time,
                     );
@@ -196,11 +200,12 @@ pub(crate) fn handle_pointer_controller_event(
                 PenState::Proximity => {
                     canvas.enable_drawing_cursor(false);
 
-                    let (ep, wf) = canvas.engine_mut().handle_pen_event(
+                    let (ep, wf) = canvas.engine_mut().handle_pen_event_for_pointer(
                         PenEvent::Proximity {
                             element,
                             modifier_keys: modifier_keys.clone(),
                         },
+                        pointer_id,
                         pen_mode,
                         event_time,
                     );
@@ -211,11 +216,12 @@ pub(crate) fn handle_pointer_controller_event(
                     canvas.grab_focus();
                     canvas.enable_drawing_cursor(true);
 
-                    let (ep, wf) = canvas.engine_mut().handle_pen_event(
+                    let (ep, wf) = canvas.engine_mut().handle_pen_event_for_pointer(
                         PenEvent::Down {
                             element,
                             modifier_keys: modifier_keys.clone(),
                         },
+                        pointer_id,
                         pen_mode,
                         event_time,
                     );
@@ -226,6 +232,7 @@ pub(crate) fn handle_pointer_controller_event(
         }
     }
 
+    canvas.update_frame_tick_callback();
     canvas.emit_handle_widget_flags(widget_flags);
     (propagation, pen_state)
 }
@@ -349,9 +356,10 @@ fn retrieve_pointer_elements(
                 &graphene::Point::from_na_vec(pos - na::vector![surface_trans_x, surface_trans_y]),
             )
             .map(|p| {
-                (canvas.engine_ref().camera.transform().inverse()
-                    * na::point![p.x() as f64, p.y() as f64])
-                .coords
+                canvas
+                    .engine_ref()
+                    .camera
+                    .surface_to_doc(na::vector![p.x() as f64, p.y() as f64])
             })
             .unwrap()
     };
@@ -370,7 +378,12 @@ fn retrieve_pointer_elements(
                 continue;
             }
 
-            let entry_delta = Duration::from_millis(event_time.saturating_sub(entry.time()) as u64);
+            // Fall back to the arrival time when the platform doesn't provide event times
+            let entry_delta = if event_time == 0 || entry.time() == 0 {
+                Duration::ZERO
+            } else {
+                Duration::from_millis(event_time.saturating_sub(entry.time()) as u64)
+            };
             let Some(entry_time) = now.checked_sub(entry_delta) else {
                 continue;
             };
@@ -446,6 +459,16 @@ pub(crate) fn retrieve_modifier_keys(modifier: gdk::ModifierType) -> HashSet<Mod
     keys
 }
 
+/// Retrieve an id of the pointer that emitted the event, to tell apart the input of multiple pens.
+///
+/// Styluses on the same tablet share the device, so the serial of the device tool is included.
+fn retrieve_pointer_id(event: &gdk::Event) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    event.device().hash(&mut hasher);
+    event.device_tool().map(|t| t.serial()).hash(&mut hasher);
+    hasher.finish() as u32
+}
+
 fn retrieve_pen_mode(event: &gdk::Event) -> Option<PenMode> {
     let device_tool = event.device_tool()?;
     match device_tool.tool_type() {
@@ -30,7 +30,7 @@ use rnote_engine::Camera;
 use rnote_engine::{Engine, WidgetFlags};
 use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, warn};
 
 #[derive(Debug, Default)]
@@ -75,6 +75,8 @@ mod imp {
 
         pub(crate) engine: RefCell<Engine>,
         pub(crate) engine_task_handler_handle: RefCell<Option<glib::JoinHandle<()>>>,
+        /// Installed only while the engine needs to handle display frames.
+        pub(crate) frame_tick_callback: RefCell<Option<gtk4::TickCallbackId>>,
 
         pub(crate) output_file: RefCell<Option<gio::File>>,
         pub(crate) output_file_watcher_task: RefCell<Option<glib::JoinHandle<()>>>,
@@ -168,6 +170,7 @@ mod imp {
 
                 engine: RefCell::new(engine),
                 engine_task_handler_handle: RefCell::new(None),
+                frame_tick_callback: RefCell::new(None),
 
                 output_file: RefCell::new(None),
                 output_file_watcher_task: RefCell::new(None),
@@ -815,6 +818,31 @@ impl RnCanvas {
         self.imp().engine.borrow_mut()
     }
 
+    /// Install the frame tick callback when the engine needs to handle display frames, for example to commit pen
+    /// input that was buffered while throttled. The callback removes itself once the engine doesn't need it anymore,
+    /// so that the frame clock doesn't keep ticking without input.
+    pub(crate) fn update_frame_tick_callback(&self) {
+        if self.imp().frame_tick_callback.borrow().is_some()
+            || !self.engine_ref().needs_frame_tick()
+        {
+            return;
+        }
+        let id = self.add_tick_callback(|canvas, _frame_clock| {
+            let widget_flags = canvas.engine_mut().handle_frame_tick(Instant::now());
+            if widget_flags != WidgetFlags::default() {
+                canvas.emit_handle_widget_flags(widget_flags);
+            }
+            if canvas.engine_ref().needs_frame_tick() {
+                glib::ControlFlow::Continue
+            } else {
+                // Returning break removes the callback
+                canvas.imp().frame_tick_callback.take();
+                glib::ControlFlow::Break
+            }
+        });
+        self.imp().frame_tick_callback.replace(Some(id));
+    }
+
     /// Abort the engine task handler.
     ///
     /// Because the installed engine task handler holds a reference to the canvas,
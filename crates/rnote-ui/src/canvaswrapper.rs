@@ -350,7 +350,7 @@ mod imp {
                         let old_zoom = canvas.engine_ref().camera.total_zoom();
                         let new_zoom = old_zoom * (1.0 - dy * RnCanvas::ZOOM_SCROLL_STEP);
 
-                        if (Camera::ZOOM_MIN..=Camera::ZOOM_MAX).contains(&new_zoom) {
+                        if canvas.engine_ref().camera.zoom_limits().contains(new_zoom) {
                             let camera_offset = canvas.engine_ref().camera.offset();
                             let camera_size = canvas.engine_ref().camera.size();
                             let screen_offset = canvaswrapper
@@ -406,7 +406,11 @@ mod imp {
                     obj,
                     move |_, x, y| {
                         let canvas = canvaswrapper.canvas();
-                        let new_offset = touch_drag_start.get() - na::vector![x, y];
+                        let new_offset = touch_drag_start.get()
+                            - canvas
+                                .engine_ref()
+                                .camera
+                                .offset_delta_for_surface_delta(na::vector![x, y]);
                         let widget_flags = canvas.engine_mut().camera_set_offset_expand(new_offset);
                         canvas.emit_handle_widget_flags(widget_flags);
                     }
@@ -449,7 +453,11 @@ mod imp {
                         obj,
                         move |_, x, y| {
                             let canvas = canvaswrapper.canvas();
-                            let new_offset = mouse_drag_start.get() - na::vector![x, y];
+                            let new_offset = mouse_drag_start.get()
+                                - canvas
+                                    .engine_ref()
+                                    .camera
+                                    .offset_delta_for_surface_delta(na::vector![x, y]);
                             let widget_flags =
                                 canvas.engine_mut().camera_set_offset_expand(new_offset);
                             canvas.emit_handle_widget_flags(widget_flags);
@@ -525,8 +533,11 @@ mod imp {
                     move |gesture, scale| {
                         let canvas = canvaswrapper.canvas();
 
-                        if (Camera::ZOOM_MIN..=Camera::ZOOM_MAX)
-                            .contains(&(zoom_begin.get() * scale))
+                        if canvas
+                            .engine_ref()
+                            .camera
+                            .zoom_limits()
+                            .contains(zoom_begin.get() * scale)
                         {
                             new_zoom.set(zoom_begin.get() * scale);
                             prev_scale.set(scale);
@@ -617,7 +628,11 @@ mod imp {
                     obj,
                     move |_, offset_x, offset_y| {
                         let canvas = canvaswrapper.canvas();
-                        let new_offset = offset_start.get() - na::vector![offset_x, offset_y];
+                        let new_offset = offset_start.get()
+                            - canvas
+                                .engine_ref()
+                                .camera
+                                .offset_delta_for_surface_delta(na::vector![offset_x, offset_y]);
                         let widget_flags = canvas.engine_mut().camera_set_offset_expand(new_offset);
                         canvas.emit_handle_widget_flags(widget_flags);
                     }
@@ -686,7 +701,7 @@ mod imp {
                                     - (new_offset[1] - prev_offset.get()[1])
                                         * Camera::DRAG_ZOOM_MAGN_ZOOM_FACTOR);
 
-                            if (Camera::ZOOM_MIN..=Camera::ZOOM_MAX).contains(&new_zoom) {
+                            if canvas.engine_ref().camera.zoom_limits().contains(new_zoom) {
                                 let viewport_center = canvas.engine_ref().camera.viewport_center();
 
                                 let mut widget_flags = canvas.engine_mut().zoom_w_timeout(new_zoom);
@@ -8,6 +8,7 @@ use adw::prelude::*;
 use gettextrs::gettext;
 use gtk4::{gio, glib, glib::clone, Builder, Button, FileDialog, FileFilter, Label};
 use num_traits::ToPrimitive;
+use p2d::bounding_volume::Aabb;
 use rnote_compose::SplitOrder;
 use rnote_engine::document::Layout;
 use rnote_engine::engine::export::{
@@ -1087,6 +1088,40 @@ pub(crate) async fn dialog_export_selection_w_prefs(appwindow: &RnAppWindow, can
     dialog.present(appwindow.root().as_ref());
 }
 
+/// Exports the region that was captured with the crop tool, with the selection export preferences.
+pub(crate) async fn dialog_export_crop_region(
+    appwindow: &RnAppWindow,
+    canvas: &RnCanvas,
+    region: Aabb,
+) {
+    let selection_export_prefs = canvas.engine_ref().export_prefs.selection_export_prefs;
+    let filedialog = create_filedialog_export_selection(appwindow, canvas, &selection_export_prefs);
+
+    match filedialog.save_future(Some(appwindow)).await {
+        Ok(selected_file) => {
+            appwindow.overlays().progressbar_start_pulsing();
+
+            if let Err(e) = canvas.export_region(&selected_file, region, None).await {
+                error!("Exporting cropped region failed, Err: {e:?}");
+                appwindow
+                    .overlays()
+                    .dispatch_toast_error(&gettext("Exporting cropped region failed"));
+                appwindow.overlays().progressbar_abort();
+                return;
+            }
+
+            appwindow.overlays().dispatch_toast_text(
+                &gettext("Exported cropped region successfully"),
+                crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT,
+            );
+            appwindow.overlays().progressbar_finish();
+        }
+        Err(e) => {
+            debug!("Did not export cropped region (Error or dialog dismissed by user), Err: {e:?}");
+        }
+    }
+}
+
 /// Returns (if possible) a "reasonable" folder for export operations
 /// concerning the specified `appwindow` and `canvas`. The main goal
 /// of this function is to provide a "good" initial folder for the
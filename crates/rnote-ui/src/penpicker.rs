@@ -1,5 +1,5 @@
 // Imports
-use crate::RnAppWindow;
+use crate::{utils, RnAppWindow};
 use gtk4::{
     glib, glib::clone, prelude::*, subclass::prelude::*, Button, CompositeTemplate, TemplateChild,
     ToggleButton, Widget,
@@ -191,5 +191,26 @@ impl RnPenPicker {
                 }
             }
         ));
+
+        // List the modes of the pens in the tooltips
+        for (toggle, pen_style) in [
+            (imp.brush_toggle.get(), PenStyle::Brush),
+            (imp.shaper_toggle.get(), PenStyle::Shaper),
+            (imp.typewriter_toggle.get(), PenStyle::Typewriter),
+            (imp.eraser_toggle.get(), PenStyle::Eraser),
+            (imp.selector_toggle.get(), PenStyle::Selector),
+            (imp.tools_toggle.get(), PenStyle::Tools),
+        ] {
+            let modes = pen_style
+                .available_modes()
+                .into_iter()
+                .map(utils::pen_mode_display_name)
+                .collect::<Vec<String>>();
+            if modes.is_empty() {
+                continue;
+            }
+            let tooltip = toggle.tooltip_text().unwrap_or_default();
+            toggle.set_tooltip_text(Some(&format!("{tooltip}\n{}", modes.join(", "))));
+        }
     }
 }
@@ -7,6 +7,7 @@ use gtk4::{
 };
 use num_traits::cast::ToPrimitive;
 use rnote_compose::builders::PenPathBuilderType;
+use rnote_compose::style::smooth::TipShape;
 use rnote_compose::style::textured::{TexturedDotsDistribution, TexturedOptions};
 use rnote_compose::style::PressureCurve;
 use rnote_engine::pens::pensconfig::brushconfig::{BrushStyle, SolidOptions};
@@ -49,8 +50,16 @@ mod imp {
         #[template_child]
         pub(crate) solidstyle_pressure_curves_row: TemplateChild<adw::ComboRow>,
         #[template_child]
+        pub(crate) solidstyle_tip_shape_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
         pub(crate) texturedstyle_density_row: TemplateChild<adw::SpinRow>,
         #[template_child]
+        pub(crate) texturedstyle_spacing_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub(crate) texturedstyle_scatter_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub(crate) texturedstyle_jitter_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
         pub(crate) texturedstyle_distribution_row: TemplateChild<adw::ComboRow>,
         #[template_child]
         pub(crate) stroke_width_picker: TemplateChild<RnStrokeWidthPicker>,
@@ -169,6 +178,19 @@ impl RnBrushPage {
             .set_selected(position);
     }
 
+    pub(crate) fn solidstyle_tip_shape(&self) -> TipShape {
+        TipShape::try_from(self.imp().solidstyle_tip_shape_row.get().selected()).unwrap()
+    }
+
+    pub(crate) fn set_solidstyle_tip_shape(&self, tip_shape: TipShape) {
+        let position = tip_shape.to_u32().unwrap();
+
+        self.imp()
+            .solidstyle_tip_shape_row
+            .get()
+            .set_selected(position);
+    }
+
     pub(crate) fn texturedstyle_dots_distribution(&self) -> TexturedDotsDistribution {
         TexturedDotsDistribution::try_from(
             self.imp().texturedstyle_distribution_row.get().selected(),
@@ -372,6 +394,28 @@ impl RnBrushPage {
                 }
             ));
 
+        // Tip shape
+        imp.solidstyle_tip_shape_row
+            .get()
+            .connect_selected_notify(clone!(
+                #[weak(rename_to=brushpage)]
+                self,
+                #[weak]
+                appwindow,
+                move |_solidstyle_tip_shape_row| {
+                    let Some(canvas) = appwindow.active_tab_canvas() else {
+                        return;
+                    };
+
+                    canvas
+                        .engine_mut()
+                        .pens_config
+                        .brush_config
+                        .solid_options
+                        .tip_shape = brushpage.solidstyle_tip_shape();
+                }
+            ));
+
         // Textured style
         // Density
         imp.texturedstyle_density_row
@@ -399,6 +443,84 @@ impl RnBrushPage {
             }
         ));
 
+        // Spacing
+        imp.texturedstyle_spacing_row
+            .get()
+            .set_range(TexturedOptions::SPACING_MIN, TexturedOptions::SPACING_MAX);
+        // set value after the range!
+        imp.texturedstyle_spacing_row
+            .get()
+            .set_value(TexturedOptions::default().spacing);
+
+        imp.texturedstyle_spacing_row.get().connect_changed(clone!(
+            #[weak]
+            appwindow,
+            move |row| {
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+
+                canvas
+                    .engine_mut()
+                    .pens_config
+                    .brush_config
+                    .textured_options
+                    .spacing = row.value();
+            }
+        ));
+
+        // Scatter
+        imp.texturedstyle_scatter_row
+            .get()
+            .set_range(TexturedOptions::SCATTER_MIN, TexturedOptions::SCATTER_MAX);
+        // set value after the range!
+        imp.texturedstyle_scatter_row
+            .get()
+            .set_value(TexturedOptions::default().scatter);
+
+        imp.texturedstyle_scatter_row.get().connect_changed(clone!(
+            #[weak]
+            appwindow,
+            move |row| {
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+
+                canvas
+                    .engine_mut()
+                    .pens_config
+                    .brush_config
+                    .textured_options
+                    .scatter = row.value();
+            }
+        ));
+
+        // Jitter
+        imp.texturedstyle_jitter_row
+            .get()
+            .set_range(TexturedOptions::JITTER_MIN, TexturedOptions::JITTER_MAX);
+        // set value after the range!
+        imp.texturedstyle_jitter_row
+            .get()
+            .set_value(TexturedOptions::default().jitter);
+
+        imp.texturedstyle_jitter_row.get().connect_changed(clone!(
+            #[weak]
+            appwindow,
+            move |row| {
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+
+                canvas
+                    .engine_mut()
+                    .pens_config
+                    .brush_config
+                    .textured_options
+                    .jitter = row.value();
+            }
+        ));
+
         // dots distribution
         imp.texturedstyle_distribution_row
             .get()
@@ -432,8 +554,15 @@ impl RnBrushPage {
             .clone();
 
         self.set_solidstyle_pressure_curve(brush_config.solid_options.pressure_curve);
+        self.set_solidstyle_tip_shape(brush_config.solid_options.tip_shape);
         imp.texturedstyle_density_row
             .set_value(brush_config.textured_options.density);
+        imp.texturedstyle_spacing_row
+            .set_value(brush_config.textured_options.spacing);
+        imp.texturedstyle_scatter_row
+            .set_value(brush_config.textured_options.scatter);
+        imp.texturedstyle_jitter_row
+            .set_value(brush_config.textured_options.jitter);
         self.set_texturedstyle_distribution_variant(brush_config.textured_options.distribution);
 
         self.set_brush_style(brush_config.style);
@@ -17,6 +17,12 @@ mod imp {
         #[template_child]
         pub(crate) eraserstyle_split_colliding_strokes_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub(crate) eraserstyle_soft_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) eraserstyle_tap_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) eraserstyle_carve_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub(crate) stroke_width_picker: TemplateChild<RnStrokeWidthPicker>,
     }
 
@@ -81,6 +87,12 @@ impl RnEraserPage {
             .is_active()
         {
             Some(EraserStyle::SplitCollidingStrokes)
+        } else if self.imp().eraserstyle_soft_toggle.is_active() {
+            Some(EraserStyle::Soft)
+        } else if self.imp().eraserstyle_tap_toggle.is_active() {
+            Some(EraserStyle::Tap)
+        } else if self.imp().eraserstyle_carve_toggle.is_active() {
+            Some(EraserStyle::Carve)
         } else {
             None
         }
@@ -97,6 +109,9 @@ impl RnEraserPage {
                 .imp()
                 .eraserstyle_split_colliding_strokes_toggle
                 .set_active(true),
+            EraserStyle::Soft => self.imp().eraserstyle_soft_toggle.set_active(true),
+            EraserStyle::Tap => self.imp().eraserstyle_tap_toggle.set_active(true),
+            EraserStyle::Carve => self.imp().eraserstyle_carve_toggle.set_active(true),
         }
     }
 
@@ -139,6 +154,48 @@ impl RnEraserPage {
                 }
             ));
 
+        imp.eraserstyle_soft_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |eraserstyle_soft_toggle| {
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+
+                if eraserstyle_soft_toggle.is_active() {
+                    canvas.engine_mut().pens_config.eraser_config.style = EraserStyle::Soft;
+                }
+            }
+        ));
+
+        imp.eraserstyle_tap_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |eraserstyle_tap_toggle| {
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+
+                if eraserstyle_tap_toggle.is_active() {
+                    canvas.engine_mut().pens_config.eraser_config.style = EraserStyle::Tap;
+                }
+            }
+        ));
+
+        imp.eraserstyle_carve_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |eraserstyle_carve_toggle| {
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+
+                if eraserstyle_carve_toggle.is_active() {
+                    canvas.engine_mut().pens_config.eraser_config.style = EraserStyle::Carve;
+                }
+            }
+        ));
+
         // width
         imp.stroke_width_picker.spinbutton().set_digits(0);
         imp.stroke_width_picker
@@ -18,6 +18,8 @@ mod imp {
         #[template_child]
         pub(crate) selectorstyle_intersectingpath_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub(crate) selectorstyle_brushselect_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub(crate) resize_lock_aspectratio_togglebutton: TemplateChild<ToggleButton>,
     }
 
@@ -78,6 +80,8 @@ impl RnSelectorPage {
             Some(SelectorStyle::Single)
         } else if self.imp().selectorstyle_intersectingpath_toggle.is_active() {
             Some(SelectorStyle::IntersectingPath)
+        } else if self.imp().selectorstyle_brushselect_toggle.is_active() {
+            Some(SelectorStyle::BrushSelect)
         } else {
             None
         }
@@ -93,6 +97,9 @@ impl RnSelectorPage {
                 .imp()
                 .selectorstyle_intersectingpath_toggle
                 .set_active(true),
+            SelectorStyle::BrushSelect => {
+                self.imp().selectorstyle_brushselect_toggle.set_active(true)
+            }
         }
     }
 
@@ -158,6 +165,21 @@ impl RnSelectorPage {
                 }
             ));
 
+        imp.selectorstyle_brushselect_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |selectorstyle_brushselect_toggle| {
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+
+                if selectorstyle_brushselect_toggle.is_active() {
+                    canvas.engine_mut().pens_config.selector_config.style =
+                        SelectorStyle::BrushSelect;
+                }
+            }
+        ));
+
         imp.resize_lock_aspectratio_togglebutton
             .connect_toggled(clone!(
                 #[weak]
@@ -592,6 +592,7 @@ fn shape_builder_type_icons_get_groups() -> Vec<GroupedIconPickerGroupData> {
                 "shapebuilder-cubbez-symbolic",
                 "shapebuilder-polyline-symbolic",
                 "shapebuilder-polygon-symbolic",
+                "shapebuilder-freehandbezier-symbolic",
             ]),
         },
     ]
@@ -614,5 +615,6 @@ fn shape_builder_type_icons_to_display_name(icon_name: &str) -> String {
         ShapeBuilderType::CubBez => gettext("Cubic bezier curve"),
         ShapeBuilderType::Polyline => gettext("Polyline"),
         ShapeBuilderType::Polygon => gettext("Polygon"),
+        ShapeBuilderType::FreehandBezier => gettext("Freehand bezier curves"),
     }
 }
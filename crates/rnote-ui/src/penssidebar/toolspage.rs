@@ -19,6 +19,10 @@ mod imp {
         #[template_child]
         pub(crate) toolstyle_zoom_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub(crate) toolstyle_smudge_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) toolstyle_crop_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub(crate) verticalspace_menubutton: TemplateChild<MenuButton>,
         #[template_child]
         pub(crate) verticalspace_popover: TemplateChild<Popover>,
@@ -88,6 +92,10 @@ impl RnToolsPage {
             Some(ToolStyle::OffsetCamera)
         } else if imp.toolstyle_zoom_toggle.is_active() {
             Some(ToolStyle::Zoom)
+        } else if imp.toolstyle_smudge_toggle.is_active() {
+            Some(ToolStyle::Smudge)
+        } else if imp.toolstyle_crop_toggle.is_active() {
+            Some(ToolStyle::Crop)
         } else {
             None
         }
@@ -106,6 +114,8 @@ impl RnToolsPage {
             ToolStyle::VerticalSpace => imp.toolstyle_verticalspace_toggle.set_active(true),
             ToolStyle::OffsetCamera => imp.toolstyle_offsetcamera_toggle.set_active(true),
             ToolStyle::Zoom => imp.toolstyle_zoom_toggle.set_active(true),
+            ToolStyle::Smudge => imp.toolstyle_smudge_toggle.set_active(true),
+            ToolStyle::Crop => imp.toolstyle_crop_toggle.set_active(true),
         }
     }
 
@@ -156,6 +166,34 @@ impl RnToolsPage {
             }
         ));
 
+        imp.toolstyle_smudge_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |toggle| {
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+
+                if toggle.is_active() {
+                    canvas.engine_mut().pens_config.tools_config.style = ToolStyle::Smudge;
+                }
+            }
+        ));
+
+        imp.toolstyle_crop_toggle.connect_toggled(clone!(
+            #[weak]
+            appwindow,
+            move |toggle| {
+                let Some(canvas) = appwindow.active_tab_canvas() else {
+                    return;
+                };
+
+                if toggle.is_active() {
+                    canvas.engine_mut().pens_config.tools_config.style = ToolStyle::Crop;
+                }
+            }
+        ));
+
         imp.verticalspace_menubutton.connect_active_notify(clone!(
             #[weak(rename_to=toolspage)]
             self,
@@ -705,9 +705,9 @@ impl RnSettingsPanel {
                     .color
                     .approx_eq_f32(background_color)
                 {
-                    canvas.engine_mut().document.background.color = background_color;
-                    let mut widget_flags = canvas.engine_mut().background_rendering_regenerate();
-                    widget_flags.store_modified = true;
+                    let widget_flags = canvas
+                        .engine_mut()
+                        .set_doc_background_color(background_color);
                     appwindow.handle_widget_flags(widget_flags, &canvas);
                 }
             }
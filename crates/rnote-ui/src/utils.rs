@@ -6,6 +6,7 @@ use gtk4::{gdk, gio, prelude::*};
 use palette::convert::IntoColor;
 use path_absolutize::Absolutize;
 use rnote_compose::Color;
+use rnote_engine::pens::ModeDescriptor;
 use std::cell::Ref;
 use std::path::{Path, PathBuf};
 use std::slice::Iter;
@@ -242,6 +243,35 @@ pub(crate) fn color_to_hsv_label_string(color: Color) -> String {
     }
 }
 
+/// The translated display name of a pen sub-mode, for mode pickers built from its descriptor.
+///
+/// Falls back to the id for unknown modes.
+pub(crate) fn pen_mode_display_name(mode: ModeDescriptor) -> String {
+    match mode.id {
+        "marker" => pgettext("a pen mode", "Marker"),
+        "solid" => pgettext("a pen mode", "Solid"),
+        "textured" => pgettext("a pen mode", "Textured"),
+        "smooth" => pgettext("a pen mode", "Smooth"),
+        "rough" => pgettext("a pen mode", "Rough"),
+        "trash_colliding_strokes" => pgettext("a pen mode", "Whole Stroke"),
+        "split_colliding_strokes" => pgettext("a pen mode", "Split"),
+        "soft" => pgettext("a pen mode", "Soft"),
+        "tap" => pgettext("a pen mode", "Tap"),
+        "carve" => pgettext("a pen mode", "Carve"),
+        "polygon" => pgettext("a pen mode", "Lasso"),
+        "rectangle" => pgettext("a pen mode", "Rectangle"),
+        "single" => pgettext("a pen mode", "Single"),
+        "intersectingpath" => pgettext("a pen mode", "Intersecting Path"),
+        "brush_select" => pgettext("a pen mode", "Brush Select"),
+        "verticalspace" => pgettext("a pen mode", "Vertical Space"),
+        "offsetcamera" => pgettext("a pen mode", "Offset Camera"),
+        "zoom" => pgettext("a pen mode", "Zoom"),
+        "smudge" => pgettext("a pen mode", "Smudge"),
+        "crop" => pgettext("a pen mode", "Crop"),
+        id => id.to_string(),
+    }
+}
+
 pub(crate) fn path_walk_up_until_exists(path: impl AsRef<Path>) -> anyhow::Result<PathBuf> {
     let mut path = path.as_ref().absolutize()?.to_path_buf();
     while !path.exists() {